@@ -50,6 +50,19 @@ impl WorkflowStep for DataProcessingStep {
             format!("processed_data_{}", chrono::Utc::now().timestamp_millis()),
         );
 
+        // For demo purposes, increment a counter
+        if self.name == "increment_counter" {
+            let current = context
+                .metadata
+                .get("loop_iteration")
+                .and_then(|s| s.parse::<i32>().ok())
+                .unwrap_or(0);
+            context
+                .metadata
+                .insert("loop_iteration".to_string(), (current + 1).to_string());
+            println!("    Counter incremented to: {}", current + 1);
+        }
+
         println!("  ✅ Completed step: {}", self.name);
         Ok(WorkflowDecision::Continue)
     }
@@ -12,8 +12,9 @@ use std::io::Write;
 use std::path::{Path, PathBuf};
 use the_agency::{
     organization::{
-        coordinator::AgentCoordinator, CollaborativeWorkspace, Organization, OrganizationAgent,
-        OrganizationRole, TaskPriority, WorkspaceTask,
+        coordinator::{AgentCoordinator, ExecutionMode},
+        CollaborativeWorkspace, Organization, OrganizationAgent, OrganizationRole, TaskPriority,
+        WorkspaceTask,
     },
     AgentConfig,
 };
@@ -256,36 +257,59 @@ async fn main() -> Result<()> {
     // Execute BOM tasks across workspaces
     info!("🔄 Generating BOM documents...");
 
-    let mech_bom_results = coordinator
-        .coordinate_workspace_project(&mech_ws_id, vec![bom_tasks[0].clone()])
+    let (_, mech_bom_report) = coordinator
+        .coordinate_workspace_project_with_report(
+            &mech_ws_id,
+            vec![bom_tasks[0].clone()],
+            ExecutionMode::default(),
+        )
         .await?;
 
-    let actuation_bom_results = coordinator
-        .coordinate_workspace_project(&actuation_ws_id, vec![bom_tasks[1].clone()])
+    let (_, actuation_bom_report) = coordinator
+        .coordinate_workspace_project_with_report(
+            &actuation_ws_id,
+            vec![bom_tasks[1].clone()],
+            ExecutionMode::default(),
+        )
         .await?;
 
-    let perception_bom_results = coordinator
-        .coordinate_workspace_project(&perception_ws_id, vec![bom_tasks[2].clone()])
+    let (_, perception_bom_report) = coordinator
+        .coordinate_workspace_project_with_report(
+            &perception_ws_id,
+            vec![bom_tasks[2].clone()],
+            ExecutionMode::default(),
+        )
         .await?;
 
-    let software_bom_results = coordinator
-        .coordinate_workspace_project(&software_ws_id, vec![bom_tasks[3].clone()])
+    let (_, software_bom_report) = coordinator
+        .coordinate_workspace_project_with_report(
+            &software_ws_id,
+            vec![bom_tasks[3].clone()],
+            ExecutionMode::default(),
+        )
         .await?;
 
-    let power_bom_results = coordinator
-        .coordinate_workspace_project(&power_ws_id, vec![bom_tasks[4].clone()])
+    let (_, power_bom_report) = coordinator
+        .coordinate_workspace_project_with_report(
+            &power_ws_id,
+            vec![bom_tasks[4].clone()],
+            ExecutionMode::default(),
+        )
         .await?;
 
     info!("");
     info!("✅ BOM Generation Complete!");
-    info!("   - Mechanical BOM: {} items", mech_bom_results.len());
-    info!("   - Actuation BOM: {} items", actuation_bom_results.len());
+    info!("   - Mechanical BOM: {} items", mech_bom_report.completed);
+    info!(
+        "   - Actuation BOM: {} items",
+        actuation_bom_report.completed
+    );
     info!(
         "   - Perception BOM: {} items",
-        perception_bom_results.len()
+        perception_bom_report.completed
     );
-    info!("   - Software BOM: {} items", software_bom_results.len());
-    info!("   - Power BOM: {} items", power_bom_results.len());
+    info!("   - Software BOM: {} items", software_bom_report.completed);
+    info!("   - Power BOM: {} items", power_bom_report.completed);
     info!("");
 
     // ===== PHASE 2: DESIGN SPECIFICATIONS =====
@@ -299,20 +323,31 @@ async fn main() -> Result<()> {
 
     info!("🔄 Generating design specifications...");
 
-    let cad_design_results = coordinator
-        .coordinate_workspace_project(&mech_ws_id, vec![design_tasks[0].clone()])
+    let (_, cad_design_report) = coordinator
+        .coordinate_workspace_project_with_report(
+            &mech_ws_id,
+            vec![design_tasks[0].clone()],
+            ExecutionMode::default(),
+        )
         .await?;
 
-    let actuation_design_results = coordinator
-        .coordinate_workspace_project(&actuation_ws_id, vec![design_tasks[1].clone()])
+    let (_, actuation_design_report) = coordinator
+        .coordinate_workspace_project_with_report(
+            &actuation_ws_id,
+            vec![design_tasks[1].clone()],
+            ExecutionMode::default(),
+        )
         .await?;
 
     info!("");
     info!("✅ Design Specifications Complete!");
-    info!("   - CAD Models: {} deliverables", cad_design_results.len());
+    info!(
+        "   - CAD Models: {} deliverables",
+        cad_design_report.completed
+    );
     info!(
         "   - Control Systems: {} deliverables",
-        actuation_design_results.len()
+        actuation_design_report.completed
     );
     info!("");
 
@@ -332,8 +367,12 @@ async fn main() -> Result<()> {
         vec![mech_lead_id.clone(), power_eng_id.clone()],
     ).with_priority(TaskPriority::High);
 
-    let _supply_results = coordinator
-        .coordinate_workspace_project(&mech_ws_id, vec![supply_chain_task])
+    let (_, _supply_report) = coordinator
+        .coordinate_workspace_project_with_report(
+            &mech_ws_id,
+            vec![supply_chain_task],
+            ExecutionMode::default(),
+        )
         .await?;
 
     info!("✅ Supply Chain Strategy Complete!");
@@ -357,8 +396,12 @@ async fn main() -> Result<()> {
     )
     .with_priority(TaskPriority::Critical);
 
-    let _integration_results = coordinator
-        .coordinate_workspace_project(&integration_ws_id, vec![integration_task])
+    let (_, _integration_report) = coordinator
+        .coordinate_workspace_project_with_report(
+            &integration_ws_id,
+            vec![integration_task],
+            ExecutionMode::default(),
+        )
         .await?;
 
     info!("✅ Integration Plan Complete!");
@@ -375,28 +418,43 @@ async fn main() -> Result<()> {
 
     info!("🔄 Generating supplier-ready manufacturing work orders...");
 
-    let cnc_work_orders = coordinator
-        .coordinate_workspace_project(&mech_ws_id, vec![manufacturing_tasks[0].clone()])
+    let (_, cnc_work_orders) = coordinator
+        .coordinate_workspace_project_with_report(
+            &mech_ws_id,
+            vec![manufacturing_tasks[0].clone()],
+            ExecutionMode::default(),
+        )
         .await?;
 
-    let pcb_fabrication_orders = coordinator
-        .coordinate_workspace_project(&actuation_ws_id, vec![manufacturing_tasks[1].clone()])
+    let (_, pcb_fabrication_orders) = coordinator
+        .coordinate_workspace_project_with_report(
+            &actuation_ws_id,
+            vec![manufacturing_tasks[1].clone()],
+            ExecutionMode::default(),
+        )
         .await?;
 
-    let battery_assembly_orders = coordinator
-        .coordinate_workspace_project(&power_ws_id, vec![manufacturing_tasks[2].clone()])
+    let (_, battery_assembly_orders) = coordinator
+        .coordinate_workspace_project_with_report(
+            &power_ws_id,
+            vec![manufacturing_tasks[2].clone()],
+            ExecutionMode::default(),
+        )
         .await?;
 
     info!("");
     info!("✅ Manufacturing Work Orders Complete!");
-    info!("   - CNC Work Orders: {} packages", cnc_work_orders.len());
+    info!(
+        "   - CNC Work Orders: {} packages",
+        cnc_work_orders.completed
+    );
     info!(
         "   - PCB Fabrication Orders: {} packages",
-        pcb_fabrication_orders.len()
+        pcb_fabrication_orders.completed
     );
     info!(
         "   - Battery Assembly Orders: {} packages",
-        battery_assembly_orders.len()
+        battery_assembly_orders.completed
     );
     info!("");
 
@@ -411,29 +469,44 @@ async fn main() -> Result<()> {
 
     info!("🔄 Generating detailed assembly procedures...");
 
-    let mech_assembly = coordinator
-        .coordinate_workspace_project(&mech_ws_id, vec![assembly_tasks[0].clone()])
+    let (_, mech_assembly) = coordinator
+        .coordinate_workspace_project_with_report(
+            &mech_ws_id,
+            vec![assembly_tasks[0].clone()],
+            ExecutionMode::default(),
+        )
         .await?;
 
-    let actuation_assembly = coordinator
-        .coordinate_workspace_project(&actuation_ws_id, vec![assembly_tasks[1].clone()])
+    let (_, actuation_assembly) = coordinator
+        .coordinate_workspace_project_with_report(
+            &actuation_ws_id,
+            vec![assembly_tasks[1].clone()],
+            ExecutionMode::default(),
+        )
         .await?;
 
-    let sensor_assembly = coordinator
-        .coordinate_workspace_project(&perception_ws_id, vec![assembly_tasks[2].clone()])
+    let (_, sensor_assembly) = coordinator
+        .coordinate_workspace_project_with_report(
+            &perception_ws_id,
+            vec![assembly_tasks[2].clone()],
+            ExecutionMode::default(),
+        )
         .await?;
 
     info!("");
     info!("✅ Assembly Procedures Complete!");
     info!(
         "   - Mechanical Assembly: {} procedures",
-        mech_assembly.len()
+        mech_assembly.completed
     );
     info!(
         "   - Actuation Assembly: {} procedures",
-        actuation_assembly.len()
+        actuation_assembly.completed
+    );
+    info!(
+        "   - Sensor Assembly: {} procedures",
+        sensor_assembly.completed
     );
-    info!("   - Sensor Assembly: {} procedures", sensor_assembly.len());
     info!("");
 
     // ===== PHASE 7: VALIDATION & TESTING =====
@@ -447,31 +520,43 @@ async fn main() -> Result<()> {
 
     info!("🔄 Generating validation test protocols...");
 
-    let system_validation = coordinator
-        .coordinate_workspace_project(&integration_ws_id, vec![validation_tasks[0].clone()])
+    let (_, system_validation) = coordinator
+        .coordinate_workspace_project_with_report(
+            &integration_ws_id,
+            vec![validation_tasks[0].clone()],
+            ExecutionMode::default(),
+        )
         .await?;
 
-    let locomotion_testing = coordinator
-        .coordinate_workspace_project(&integration_ws_id, vec![validation_tasks[1].clone()])
+    let (_, locomotion_testing) = coordinator
+        .coordinate_workspace_project_with_report(
+            &integration_ws_id,
+            vec![validation_tasks[1].clone()],
+            ExecutionMode::default(),
+        )
         .await?;
 
-    let manipulation_testing = coordinator
-        .coordinate_workspace_project(&integration_ws_id, vec![validation_tasks[2].clone()])
+    let (_, manipulation_testing) = coordinator
+        .coordinate_workspace_project_with_report(
+            &integration_ws_id,
+            vec![validation_tasks[2].clone()],
+            ExecutionMode::default(),
+        )
         .await?;
 
     info!("");
     info!("✅ Validation Testing Complete!");
     info!(
         "   - System Validation: {} test suites",
-        system_validation.len()
+        system_validation.completed
     );
     info!(
         "   - Locomotion Testing: {} protocols",
-        locomotion_testing.len()
+        locomotion_testing.completed
     );
     info!(
         "   - Manipulation Testing: {} protocols",
-        manipulation_testing.len()
+        manipulation_testing.completed
     );
     info!("");
 
@@ -486,34 +571,50 @@ async fn main() -> Result<()> {
 
     info!("🔄 Generating software packages and control systems...");
 
-    let ros2_packages = coordinator
-        .coordinate_workspace_project(&software_ws_id, vec![software_dev_tasks[0].clone()])
+    let (_, ros2_packages) = coordinator
+        .coordinate_workspace_project_with_report(
+            &software_ws_id,
+            vec![software_dev_tasks[0].clone()],
+            ExecutionMode::default(),
+        )
         .await?;
 
-    let control_algorithms = coordinator
-        .coordinate_workspace_project(&actuation_ws_id, vec![software_dev_tasks[1].clone()])
+    let (_, control_algorithms) = coordinator
+        .coordinate_workspace_project_with_report(
+            &actuation_ws_id,
+            vec![software_dev_tasks[1].clone()],
+            ExecutionMode::default(),
+        )
         .await?;
 
-    let perception_pipeline = coordinator
-        .coordinate_workspace_project(&perception_ws_id, vec![software_dev_tasks[2].clone()])
+    let (_, perception_pipeline) = coordinator
+        .coordinate_workspace_project_with_report(
+            &perception_ws_id,
+            vec![software_dev_tasks[2].clone()],
+            ExecutionMode::default(),
+        )
         .await?;
 
-    let ai_ml_models = coordinator
-        .coordinate_workspace_project(&software_ws_id, vec![software_dev_tasks[3].clone()])
+    let (_, ai_ml_models) = coordinator
+        .coordinate_workspace_project_with_report(
+            &software_ws_id,
+            vec![software_dev_tasks[3].clone()],
+            ExecutionMode::default(),
+        )
         .await?;
 
     info!("");
     info!("✅ Software Development Complete!");
-    info!("   - ROS2 Packages: {} modules", ros2_packages.len());
+    info!("   - ROS2 Packages: {} modules", ros2_packages.completed);
     info!(
         "   - Control Algorithms: {} implementations",
-        control_algorithms.len()
+        control_algorithms.completed
     );
     info!(
         "   - Perception Pipeline: {} components",
-        perception_pipeline.len()
+        perception_pipeline.completed
     );
-    info!("   - AI/ML Models: {} models", ai_ml_models.len());
+    info!("   - AI/ML Models: {} models", ai_ml_models.completed);
     info!("");
 
     // ===== PHASE 9: DOCUMENTATION & HANDOFF =====
@@ -527,28 +628,40 @@ async fn main() -> Result<()> {
 
     info!("🔄 Generating project documentation...");
 
-    let technical_docs = coordinator
-        .coordinate_workspace_project(&integration_ws_id, vec![documentation_tasks[0].clone()])
+    let (_, technical_docs) = coordinator
+        .coordinate_workspace_project_with_report(
+            &integration_ws_id,
+            vec![documentation_tasks[0].clone()],
+            ExecutionMode::default(),
+        )
         .await?;
 
-    let user_manuals = coordinator
-        .coordinate_workspace_project(&integration_ws_id, vec![documentation_tasks[1].clone()])
+    let (_, user_manuals) = coordinator
+        .coordinate_workspace_project_with_report(
+            &integration_ws_id,
+            vec![documentation_tasks[1].clone()],
+            ExecutionMode::default(),
+        )
         .await?;
 
-    let maintenance_guides = coordinator
-        .coordinate_workspace_project(&mech_ws_id, vec![documentation_tasks[2].clone()])
+    let (_, maintenance_guides) = coordinator
+        .coordinate_workspace_project_with_report(
+            &mech_ws_id,
+            vec![documentation_tasks[2].clone()],
+            ExecutionMode::default(),
+        )
         .await?;
 
     info!("");
     info!("✅ Documentation Complete!");
     info!(
         "   - Technical Documentation: {} volumes",
-        technical_docs.len()
+        technical_docs.completed
     );
-    info!("   - User Manuals: {} guides", user_manuals.len());
+    info!("   - User Manuals: {} guides", user_manuals.completed);
     info!(
         "   - Maintenance Guides: {} documents",
-        maintenance_guides.len()
+        maintenance_guides.completed
     );
     info!("");
 
@@ -563,39 +676,55 @@ async fn main() -> Result<()> {
 
     info!("🔄 Generating hardware platform analysis...");
 
-    let compute_analysis = coordinator
-        .coordinate_workspace_project(&mech_ws_id, vec![hw_platform_tasks[0].clone()])
+    let (_, compute_analysis) = coordinator
+        .coordinate_workspace_project_with_report(
+            &mech_ws_id,
+            vec![hw_platform_tasks[0].clone()],
+            ExecutionMode::default(),
+        )
         .await?;
 
-    let mcu_sourcing = coordinator
-        .coordinate_workspace_project(&actuation_ws_id, vec![hw_platform_tasks[1].clone()])
+    let (_, mcu_sourcing) = coordinator
+        .coordinate_workspace_project_with_report(
+            &actuation_ws_id,
+            vec![hw_platform_tasks[1].clone()],
+            ExecutionMode::default(),
+        )
         .await?;
 
-    let second_source = coordinator
-        .coordinate_workspace_project(&mech_ws_id, vec![hw_platform_tasks[2].clone()])
+    let (_, second_source) = coordinator
+        .coordinate_workspace_project_with_report(
+            &mech_ws_id,
+            vec![hw_platform_tasks[2].clone()],
+            ExecutionMode::default(),
+        )
         .await?;
 
-    let config_mgmt = coordinator
-        .coordinate_workspace_project(&integration_ws_id, vec![hw_platform_tasks[3].clone()])
+    let (_, config_mgmt) = coordinator
+        .coordinate_workspace_project_with_report(
+            &integration_ws_id,
+            vec![hw_platform_tasks[3].clone()],
+            ExecutionMode::default(),
+        )
         .await?;
 
     info!("");
     info!("✅ Hardware Platform Analysis Complete!");
     info!(
         "   - Compute Platform Analysis: {} reports",
-        compute_analysis.len()
+        compute_analysis.completed
     );
     info!(
         "   - Microcontroller Sourcing: {} matrices",
-        mcu_sourcing.len()
+        mcu_sourcing.completed
     );
     info!(
         "   - Second-Source Qualification: {} plans",
-        second_source.len()
+        second_source.completed
     );
     info!(
         "   - Configuration Management: {} systems",
-        config_mgmt.len()
+        config_mgmt.completed
     );
     info!("");
 
@@ -613,39 +742,55 @@ async fn main() -> Result<()> {
 
     info!("🔄 Generating architecture buildout plans...");
 
-    let premium_buildout = coordinator
-        .coordinate_workspace_project(&software_ws_id, vec![arch_buildout_tasks[0].clone()])
+    let (_, premium_buildout) = coordinator
+        .coordinate_workspace_project_with_report(
+            &software_ws_id,
+            vec![arch_buildout_tasks[0].clone()],
+            ExecutionMode::default(),
+        )
         .await?;
 
-    let standard_buildout = coordinator
-        .coordinate_workspace_project(&software_ws_id, vec![arch_buildout_tasks[1].clone()])
+    let (_, standard_buildout) = coordinator
+        .coordinate_workspace_project_with_report(
+            &software_ws_id,
+            vec![arch_buildout_tasks[1].clone()],
+            ExecutionMode::default(),
+        )
         .await?;
 
-    let budget_buildout = coordinator
-        .coordinate_workspace_project(&software_ws_id, vec![arch_buildout_tasks[2].clone()])
+    let (_, budget_buildout) = coordinator
+        .coordinate_workspace_project_with_report(
+            &software_ws_id,
+            vec![arch_buildout_tasks[2].clone()],
+            ExecutionMode::default(),
+        )
         .await?;
 
-    let comparison_matrix = coordinator
-        .coordinate_workspace_project(&integration_ws_id, vec![arch_buildout_tasks[3].clone()])
+    let (_, comparison_matrix) = coordinator
+        .coordinate_workspace_project_with_report(
+            &integration_ws_id,
+            vec![arch_buildout_tasks[3].clone()],
+            ExecutionMode::default(),
+        )
         .await?;
 
     info!("");
     info!("✅ Architecture Buildout Plans Complete!");
     info!(
         "   - Premium Configuration: {} guides",
-        premium_buildout.len()
+        premium_buildout.completed
     );
     info!(
         "   - Standard Configuration: {} guides",
-        standard_buildout.len()
+        standard_buildout.completed
     );
     info!(
         "   - Budget Configuration: {} guides",
-        budget_buildout.len()
+        budget_buildout.completed
     );
     info!(
         "   - Comparison Matrix: {} documents",
-        comparison_matrix.len()
+        comparison_matrix.completed
     );
     info!("");
 
@@ -31,6 +31,7 @@ async fn main() -> anyhow::Result<()> {
         stream: false,
         task_models: HashMap::new(),
         cache: the_agency::cache::LlmCacheConfig::default(),
+        ..Default::default()
     };
 
     // Create provider manager with automatic fallback
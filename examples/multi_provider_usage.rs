@@ -73,10 +73,18 @@ async fn main() -> Result<()> {
         max_tokens: 2048,
         temperature: 0.8,
         timeout: 60,
+        generation_timeout: None,
+        stream_idle_timeout: None,
+        context_window: None,
+        context_overflow: Default::default(),
+        log_bodies: false,
+        log_body_max_len: 2048,
+        seed: None,
+        http: Default::default(),
         options: serde_json::Value::Null,
     };
 
-    let provider = OpenAIProvider::create(custom_config);
+    let provider = OpenAIProvider::create(custom_config)?;
     println!("   Created custom provider: {}", provider.name());
     println!("   Provider type: {}", provider.provider_type());
     println!();
@@ -34,8 +34,12 @@ async fn main() -> anyhow::Result<()> {
     let suspend_config = WorkflowSuspendConfig {
         auto_checkpoint: false, // Disable for this demo
         checkpoint_interval: 10,
+        checkpoint_jitter: 0,
+        min_checkpoint_interval: chrono::Duration::zero(),
         max_snapshots: 20,
         snapshot_retention: chrono::Duration::hours(1),
+        webhook: None,
+        max_total_duration: None,
     };
 
     // Demo 1: Basic sleep() functionality
@@ -533,6 +533,12 @@ impl DocumentRAGSystem {
             max_search_results: 10,
             similarity_threshold: 0.7,
             persistent: true,
+            dedup: None,
+            vector_index: None,
+            normalize_embeddings: false,
+            ranking: None,
+            shared_writes: false,
+            max_entries: None,
         };
 
         let mut memory_store = SqliteMemoryStore::new(memory_config);
@@ -548,6 +554,7 @@ impl DocumentRAGSystem {
             stream: false,
             task_models: HashMap::new(),
             cache: the_agency::cache::LlmCacheConfig::default(),
+            ..Default::default()
         };
 
         let llm_client = OllamaClient::new(llm_config);
@@ -28,8 +28,10 @@ use std::sync::Arc;
 use the_agency::{
     llm::connection_pool::OllamaConnectionPool,
     organization::{
-        coordinator::AgentCoordinator, CollaborativeWorkspace, Organization, OrganizationAgent,
-        OrganizationRole, TaskPriority, WorkspaceTask,
+        artifacts::{ArtifactFile, ArtifactWriter},
+        coordinator::{AgentCoordinator, ExecutionMode},
+        CollaborativeWorkspace, Organization, OrganizationAgent, OrganizationRole, TaskPriority,
+        WorkspaceTask,
     },
     AgentConfig,
 };
@@ -633,13 +635,17 @@ async fn execute_projects(coordinator: &AgentCoordinator, org: &Organization) ->
     .with_priority(TaskPriority::High);
 
     let robo1_tasks = vec![task1, task2, task3];
-    let robo1_results = coordinator
-        .coordinate_workspace_project(&robo1_ws_id, robo1_tasks)
+    let (_, robo1_report) = coordinator
+        .coordinate_workspace_project_with_report(
+            &robo1_ws_id,
+            robo1_tasks,
+            ExecutionMode::default(),
+        )
         .await?;
 
     println!(
         "   ✅ Completed {} Robo-1 development tasks\n",
-        robo1_results.len()
+        robo1_report.completed
     );
 
     // Project 2: Robo-2 Construction Assistant
@@ -667,13 +673,17 @@ async fn execute_projects(coordinator: &AgentCoordinator, org: &Organization) ->
     .with_priority(TaskPriority::High);
 
     let robo2_tasks = vec![task4, task5, task6];
-    let robo2_results = coordinator
-        .coordinate_workspace_project(&robo2_ws_id, robo2_tasks)
+    let (_, robo2_report) = coordinator
+        .coordinate_workspace_project_with_report(
+            &robo2_ws_id,
+            robo2_tasks,
+            ExecutionMode::default(),
+        )
         .await?;
 
     println!(
         "   ✅ Completed {} Robo-2 development tasks\n",
-        robo2_results.len()
+        robo2_report.completed
     );
 
     // Project 3: Robo-3 Rescue Operations
@@ -708,13 +718,17 @@ async fn execute_projects(coordinator: &AgentCoordinator, org: &Organization) ->
     .with_priority(TaskPriority::High);
 
     let robo3_tasks = vec![task7, task8, task9, task10];
-    let robo3_results = coordinator
-        .coordinate_workspace_project(&robo3_ws_id, robo3_tasks)
+    let (_, robo3_report) = coordinator
+        .coordinate_workspace_project_with_report(
+            &robo3_ws_id,
+            robo3_tasks,
+            ExecutionMode::default(),
+        )
         .await?;
 
     println!(
         "   ✅ Completed {} Robo-3 development tasks\n",
-        robo3_results.len()
+        robo3_report.completed
     );
 
     // Additional concurrent projects demonstrating multi-project coordination
@@ -746,13 +760,17 @@ async fn execute_projects(coordinator: &AgentCoordinator, org: &Organization) ->
     .with_priority(TaskPriority::High);
 
     let ai_research_tasks = vec![task11, task12];
-    let ai_research_results = coordinator
-        .coordinate_workspace_project(&ai_research_ws_id, ai_research_tasks)
+    let (_, ai_research_report) = coordinator
+        .coordinate_workspace_project_with_report(
+            &ai_research_ws_id,
+            ai_research_tasks,
+            ExecutionMode::default(),
+        )
         .await?;
 
     println!(
         "   ✅ Completed {} AI research tasks\n",
-        ai_research_results.len()
+        ai_research_report.completed
     );
 
     // Project 5: Software Platform Development (High Priority)
@@ -780,13 +798,17 @@ async fn execute_projects(coordinator: &AgentCoordinator, org: &Organization) ->
     .with_priority(TaskPriority::High);
 
     let platform_tasks = vec![task13, task14];
-    let platform_results = coordinator
-        .coordinate_workspace_project(&platform_ws_id, platform_tasks)
+    let (_, platform_report) = coordinator
+        .coordinate_workspace_project_with_report(
+            &platform_ws_id,
+            platform_tasks,
+            ExecutionMode::default(),
+        )
         .await?;
 
     println!(
         "   ✅ Completed {} platform development tasks\n",
-        platform_results.len()
+        platform_report.completed
     );
 
     // Project 6: Hardware Integration (Medium Priority)
@@ -814,13 +836,13 @@ async fn execute_projects(coordinator: &AgentCoordinator, org: &Organization) ->
     .with_priority(TaskPriority::Medium);
 
     let hw_tasks = vec![task15, task16];
-    let hw_results = coordinator
-        .coordinate_workspace_project(&hw_ws_id, hw_tasks)
+    let (_, hw_report) = coordinator
+        .coordinate_workspace_project_with_report(&hw_ws_id, hw_tasks, ExecutionMode::default())
         .await?;
 
     println!(
         "   ✅ Completed {} hardware integration tasks\n",
-        hw_results.len()
+        hw_report.completed
     );
 
     // Project 7: Executive Strategy (Medium Priority)
@@ -841,13 +863,13 @@ async fn execute_projects(coordinator: &AgentCoordinator, org: &Organization) ->
     .with_priority(TaskPriority::Medium);
 
     let exec_tasks = vec![task17];
-    let exec_results = coordinator
-        .coordinate_workspace_project(&exec_ws_id, exec_tasks)
+    let (_, exec_report) = coordinator
+        .coordinate_workspace_project_with_report(&exec_ws_id, exec_tasks, ExecutionMode::default())
         .await?;
 
     println!(
         "   ✅ Completed {} executive strategy tasks\n",
-        exec_results.len()
+        exec_report.completed
     );
 
     // Project 8: Product Strategy (Medium Priority)
@@ -868,13 +890,13 @@ async fn execute_projects(coordinator: &AgentCoordinator, org: &Organization) ->
     .with_priority(TaskPriority::Medium);
 
     let prod_tasks = vec![task18];
-    let prod_results = coordinator
-        .coordinate_workspace_project(&prod_ws_id, prod_tasks)
+    let (_, prod_report) = coordinator
+        .coordinate_workspace_project_with_report(&prod_ws_id, prod_tasks, ExecutionMode::default())
         .await?;
 
     println!(
         "   ✅ Completed {} product strategy tasks\n",
-        prod_results.len()
+        prod_report.completed
     );
 
     // Project 9: Customer Success Initiative (Low Priority)
@@ -902,13 +924,13 @@ async fn execute_projects(coordinator: &AgentCoordinator, org: &Organization) ->
     .with_priority(TaskPriority::Low);
 
     let cust_tasks = vec![task19, task20];
-    let cust_results = coordinator
-        .coordinate_workspace_project(&cust_ws_id, cust_tasks)
+    let (_, cust_report) = coordinator
+        .coordinate_workspace_project_with_report(&cust_ws_id, cust_tasks, ExecutionMode::default())
         .await?;
 
     println!(
         "   ✅ Completed {} customer success tasks\n",
-        cust_results.len()
+        cust_report.completed
     );
 
     // ===== ADVANCED DEVELOPMENT PHASES =====
@@ -940,13 +962,13 @@ async fn execute_projects(coordinator: &AgentCoordinator, org: &Organization) ->
         .unwrap();
 
     let mfg_tasks = vec![mfg_task1, mfg_task2];
-    let mfg_wo_results = coordinator
-        .coordinate_workspace_project(&mfg_ws_id, mfg_tasks)
+    let (_, mfg_wo_report) = coordinator
+        .coordinate_workspace_project_with_report(&mfg_ws_id, mfg_tasks, ExecutionMode::default())
         .await?;
 
     println!(
         "   ✅ Completed {} manufacturing work order packages\n",
-        mfg_wo_results.len()
+        mfg_wo_report.completed
     );
 
     // Phase 11: Assembly Procedures
@@ -971,13 +993,13 @@ async fn execute_projects(coordinator: &AgentCoordinator, org: &Organization) ->
     .with_priority(TaskPriority::High);
 
     let asm_tasks = vec![asm_task1, asm_task2];
-    let asm_results = coordinator
-        .coordinate_workspace_project(&mfg_ws_id, asm_tasks)
+    let (_, asm_report) = coordinator
+        .coordinate_workspace_project_with_report(&mfg_ws_id, asm_tasks, ExecutionMode::default())
         .await?;
 
     println!(
         "   ✅ Completed {} assembly procedure documents\n",
-        asm_results.len()
+        asm_report.completed
     );
 
     // Phase 12: Validation Testing
@@ -1004,13 +1026,17 @@ async fn execute_projects(coordinator: &AgentCoordinator, org: &Organization) ->
     .with_priority(TaskPriority::Critical);
 
     let val_tasks = vec![val_task1, val_task2];
-    let val_results = coordinator
-        .coordinate_workspace_project(&platform_ws_id, val_tasks)
+    let (_, val_report) = coordinator
+        .coordinate_workspace_project_with_report(
+            &platform_ws_id,
+            val_tasks,
+            ExecutionMode::default(),
+        )
         .await?;
 
     println!(
         "   ✅ Completed {} validation test protocols\n",
-        val_results.len()
+        val_report.completed
     );
 
     // Phase 13: Software Development
@@ -1055,13 +1081,17 @@ async fn execute_projects(coordinator: &AgentCoordinator, org: &Organization) ->
     .with_priority(TaskPriority::High);
 
     let sw_tasks = vec![sw_task1, sw_task2, sw_task3, sw_task4];
-    let sw_results = coordinator
-        .coordinate_workspace_project(&platform_ws_id, sw_tasks)
+    let (_, sw_report) = coordinator
+        .coordinate_workspace_project_with_report(
+            &platform_ws_id,
+            sw_tasks,
+            ExecutionMode::default(),
+        )
         .await?;
 
     println!(
         "   ✅ Completed {} software development modules\n",
-        sw_results.len()
+        sw_report.completed
     );
 
     // Phase 14: Documentation & Handoff
@@ -1096,13 +1126,13 @@ async fn execute_projects(coordinator: &AgentCoordinator, org: &Organization) ->
     .with_priority(TaskPriority::High);
 
     let doc_tasks = vec![doc_task1, doc_task2, doc_task3];
-    let doc_results = coordinator
-        .coordinate_workspace_project(&prod_ws_id, doc_tasks)
+    let (_, doc_report) = coordinator
+        .coordinate_workspace_project_with_report(&prod_ws_id, doc_tasks, ExecutionMode::default())
         .await?;
 
     println!(
         "   ✅ Completed {} documentation packages\n",
-        doc_results.len()
+        doc_report.completed
     );
 
     // Phase 15: Regulatory & Compliance
@@ -1143,13 +1173,13 @@ async fn execute_projects(coordinator: &AgentCoordinator, org: &Organization) ->
     .with_priority(TaskPriority::High);
 
     let reg_tasks = vec![reg_task1, reg_task2, reg_task3];
-    let reg_results = coordinator
-        .coordinate_workspace_project(&prod_ws_id, reg_tasks)
+    let (_, reg_report) = coordinator
+        .coordinate_workspace_project_with_report(&prod_ws_id, reg_tasks, ExecutionMode::default())
         .await?;
 
     println!(
         "   ✅ Completed {} regulatory compliance packages\n",
-        reg_results.len()
+        reg_report.completed
     );
 
     // Phase 16: Environmental & Durability Testing
@@ -1191,13 +1221,13 @@ async fn execute_projects(coordinator: &AgentCoordinator, org: &Organization) ->
     .with_priority(TaskPriority::High);
 
     let env_tasks = vec![env_task1, env_task2, env_task3];
-    let env_results = coordinator
-        .coordinate_workspace_project(&hw_ws_id, env_tasks)
+    let (_, env_report) = coordinator
+        .coordinate_workspace_project_with_report(&hw_ws_id, env_tasks, ExecutionMode::default())
         .await?;
 
     println!(
         "   ✅ Completed {} environmental test plans\n",
-        env_results.len()
+        env_report.completed
     );
 
     // Phase 17: Production Scaling
@@ -1242,13 +1272,13 @@ async fn execute_projects(coordinator: &AgentCoordinator, org: &Organization) ->
     .with_priority(TaskPriority::High);
 
     let scale_tasks = vec![scale_task1, scale_task2, scale_task3];
-    let scale_results = coordinator
-        .coordinate_workspace_project(&hw_ws_id, scale_tasks)
+    let (_, scale_report) = coordinator
+        .coordinate_workspace_project_with_report(&hw_ws_id, scale_tasks, ExecutionMode::default())
         .await?;
 
     println!(
         "   ✅ Completed {} production scaling plans\n",
-        scale_results.len()
+        scale_report.completed
     );
 
     // Phase 18: Field Deployment & Operations
@@ -1295,13 +1325,17 @@ async fn execute_projects(coordinator: &AgentCoordinator, org: &Organization) ->
     .with_priority(TaskPriority::High);
 
     let deploy_tasks = vec![deploy_task1, deploy_task2, deploy_task3];
-    let deploy_results = coordinator
-        .coordinate_workspace_project(&cust_ws_id, deploy_tasks)
+    let (_, deploy_report) = coordinator
+        .coordinate_workspace_project_with_report(
+            &cust_ws_id,
+            deploy_tasks,
+            ExecutionMode::default(),
+        )
         .await?;
 
     println!(
         "   ✅ Completed {} deployment and operations packages\n",
-        deploy_results.len()
+        deploy_report.completed
     );
 
     // Phase 19: Cybersecurity
@@ -1349,13 +1383,17 @@ async fn execute_projects(coordinator: &AgentCoordinator, org: &Organization) ->
     .with_priority(TaskPriority::High);
 
     let cyber_tasks = vec![cyber_task1, cyber_task2, cyber_task3];
-    let cyber_results = coordinator
-        .coordinate_workspace_project(&platform_ws_id, cyber_tasks)
+    let (_, cyber_report) = coordinator
+        .coordinate_workspace_project_with_report(
+            &platform_ws_id,
+            cyber_tasks,
+            ExecutionMode::default(),
+        )
         .await?;
 
     println!(
         "   ✅ Completed {} cybersecurity packages\n",
-        cyber_results.len()
+        cyber_report.completed
     );
 
     // Phase 20: Compute Platform Analysis & Multi-Vendor Sourcing
@@ -1445,13 +1483,13 @@ async fn execute_projects(coordinator: &AgentCoordinator, org: &Organization) ->
     .with_priority(TaskPriority::High);
 
     let hw_tasks = vec![hw_task1, hw_task2, hw_task3, hw_task4];
-    let hw_platform_results = coordinator
-        .coordinate_workspace_project(&hw_ws_id, hw_tasks)
+    let (_, hw_platform_report) = coordinator
+        .coordinate_workspace_project_with_report(&hw_ws_id, hw_tasks, ExecutionMode::default())
         .await?;
 
     println!(
         "   ✅ Completed {} hardware platform analysis packages\n",
-        hw_platform_results.len()
+        hw_platform_report.completed
     );
 
     // Phase 21: Alternative Architecture Buildout Plans
@@ -1571,13 +1609,17 @@ async fn execute_projects(coordinator: &AgentCoordinator, org: &Organization) ->
     .with_priority(TaskPriority::Critical);
 
     let arch_tasks = vec![arch_task1, arch_task2, arch_task3, arch_task4, arch_task5];
-    let arch_buildout_results = coordinator
-        .coordinate_workspace_project(&platform_ws_id, arch_tasks)
+    let (_, arch_buildout_report) = coordinator
+        .coordinate_workspace_project_with_report(
+            &platform_ws_id,
+            arch_tasks,
+            ExecutionMode::default(),
+        )
         .await?;
 
     println!(
         "   ✅ Completed {} architecture buildout plans\n",
-        arch_buildout_results.len()
+        arch_buildout_report.completed
     );
 
     // Summary
@@ -1586,109 +1628,109 @@ async fn execute_projects(coordinator: &AgentCoordinator, org: &Organization) ->
     println!("\n📈 Project Summary:\n");
     println!(
         "   🏠 Robo-1 Development: {} tasks (Critical)",
-        robo1_results.len()
+        robo1_report.total
     );
     println!(
         "   🏭 Robo-2 Development: {} tasks (Critical)",
-        robo2_results.len()
+        robo2_report.total
     );
     println!(
         "   🚒 Robo-3 Development: {} tasks (Critical)",
-        robo3_results.len()
+        robo3_report.total
     );
     println!(
         "   🧠 AI Research: {} tasks (High)",
-        ai_research_results.len()
+        ai_research_report.total
     );
     println!(
         "   💻 Platform Development: {} tasks (High)",
-        platform_results.len()
+        platform_report.total
     );
     println!(
         "   ⚙️  Hardware Integration: {} tasks (Medium)",
-        hw_results.len()
+        hw_report.total
     );
     println!(
         "   📈 Executive Strategy: {} tasks (Medium)",
-        exec_results.len()
+        exec_report.total
     );
     println!(
         "   📦 Product Strategy: {} tasks (Medium)",
-        prod_results.len()
+        prod_report.total
     );
-    println!("   🤝 Customer Success: {} tasks (Low)", cust_results.len());
+    println!("   🤝 Customer Success: {} tasks (Low)", cust_report.total);
     println!("\n🛠️  Advanced Development Phases:\n");
     println!(
         "   🏭 Manufacturing Work Orders: {} packages (Critical)",
-        mfg_wo_results.len()
+        mfg_wo_report.total
     );
     println!(
         "   🔩 Assembly Procedures: {} documents (High)",
-        asm_results.len()
+        asm_report.total
     );
     println!(
         "   🧪 Validation Testing: {} protocols (Critical)",
-        val_results.len()
+        val_report.total
     );
     println!(
         "   💻 Software Development: {} modules (Critical)",
-        sw_results.len()
+        sw_report.total
     );
     println!(
         "   📚 Documentation & Handoff: {} packages (Critical)",
-        doc_results.len()
+        doc_report.total
     );
     println!("\n🌎 Production & Commercialization Phases:\n");
     println!(
         "   ⚖️ Regulatory & Compliance: {} packages (Critical)",
-        reg_results.len()
+        reg_report.total
     );
     println!(
         "   🌡️ Environmental & Durability Testing: {} plans (Critical)",
-        env_results.len()
+        env_report.total
     );
     println!(
         "   🏭 Production Scaling: {} plans (Critical)",
-        scale_results.len()
+        scale_report.total
     );
     println!(
         "   🚀 Field Deployment & Operations: {} packages (High)",
-        deploy_results.len()
+        deploy_report.total
     );
     println!(
         "   🔒 Cybersecurity & Data Protection: {} packages (Critical)",
-        cyber_results.len()
+        cyber_report.total
     );
     println!(
         "   💻 Compute Platform Analysis: {} packages (Critical)",
-        hw_platform_results.len()
+        hw_platform_report.total
     );
     println!(
         "   🏗️ Alternative Architecture Buildouts: {} plans (Critical)",
-        arch_buildout_results.len()
-    );
-
-    let total_tasks = robo1_results.len()
-        + robo2_results.len()
-        + robo3_results.len()
-        + ai_research_results.len()
-        + platform_results.len()
-        + hw_results.len()
-        + exec_results.len()
-        + prod_results.len()
-        + cust_results.len()
-        + mfg_wo_results.len()
-        + asm_results.len()
-        + val_results.len()
-        + sw_results.len()
-        + doc_results.len()
-        + reg_results.len()
-        + env_results.len()
-        + scale_results.len()
-        + deploy_results.len()
-        + cyber_results.len()
-        + hw_platform_results.len()
-        + arch_buildout_results.len();
+        arch_buildout_report.total
+    );
+
+    let total_tasks = robo1_report.total
+        + robo2_report.total
+        + robo3_report.total
+        + ai_research_report.total
+        + platform_report.total
+        + hw_report.total
+        + exec_report.total
+        + prod_report.total
+        + cust_report.total
+        + mfg_wo_report.total
+        + asm_report.total
+        + val_report.total
+        + sw_report.total
+        + doc_report.total
+        + reg_report.total
+        + env_report.total
+        + scale_report.total
+        + deploy_report.total
+        + cyber_report.total
+        + hw_platform_report.total
+        + arch_buildout_report.total;
 
     println!("\n   📈 Total Tasks Executed: {}", total_tasks);
     println!(
@@ -2401,8 +2443,10 @@ primary_suppliers = ["AcmeTech Motors", "SensorCorp", "JetsonSupply"]
     Ok(artifacts)
 }
 
-/// Write artifacts to disk
+/// Write artifacts to disk, streaming them through a bounded writer
 async fn write_artifacts(output_dir: &Path, artifacts: &[Artifact]) -> Result<()> {
+    let writer = ArtifactWriter::spawn(output_dir.join("artifacts"), 4);
+
     for artifact in artifacts {
         let subdir = match artifact.artifact_type {
             ArtifactType::DesignDocument
@@ -2413,19 +2457,21 @@ async fn write_artifacts(output_dir: &Path, artifacts: &[Artifact]) -> Result<()
             ArtifactType::ArchitectureDiagram => "diagrams",
         };
 
-        let file_path = output_dir
-            .join("artifacts")
-            .join(subdir)
-            .join(format!("{}.{}", artifact.name, artifact.file_extension));
-
-        fs::write(&file_path, &artifact.content)?;
-        println!(
-            "  📄 {}/{}/{}.{}",
-            output_dir.join("artifacts").display(),
-            subdir,
-            artifact.name,
-            artifact.file_extension
-        );
+        writer
+            .write(ArtifactFile::new(
+                subdir,
+                format!("{}.{}", artifact.name, artifact.file_extension),
+                artifact.content.clone(),
+            ))
+            .await?;
+    }
+
+    let report = writer.finish().await?;
+    for path in &report.written {
+        println!("  📄 {}", path.display());
+    }
+    for (name, error) in &report.failed {
+        println!("  ⚠️  Failed to write {}: {}", name, error);
     }
 
     Ok(())
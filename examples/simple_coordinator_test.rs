@@ -1,5 +1,5 @@
 use anyhow::Result;
-use the_agency::organization::coordinator::AgentCoordinator;
+use the_agency::organization::coordinator::{AgentCoordinator, ExecutionMode};
 use the_agency::{
     AgentConfig, CollaborativeWorkspace, Organization, OrganizationAgent, OrganizationRole,
     TaskPriority, WorkspaceTask,
@@ -75,13 +75,13 @@ async fn main() -> Result<()> {
     .with_priority(TaskPriority::High);
 
     // Execute task
-    let results = coordinator
-        .coordinate_workspace_project(&ws_id, vec![task])
+    let (_, report) = coordinator
+        .coordinate_workspace_project_with_report(&ws_id, vec![task], ExecutionMode::default())
         .await?;
 
     println!("\n✅ SUCCESS! Results:");
-    for result in &results {
-        println!("  Response: {}", result.output);
+    for outcome in &report.outcomes {
+        println!("  Response: {}", outcome.result.output);
     }
 
     Ok(())
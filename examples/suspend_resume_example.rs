@@ -33,8 +33,12 @@ async fn main() -> anyhow::Result<()> {
     let suspend_config = WorkflowSuspendConfig {
         auto_checkpoint: true,
         checkpoint_interval: 2,
+        checkpoint_jitter: 0,
+        min_checkpoint_interval: chrono::Duration::zero(),
         max_snapshots: 5,
         snapshot_retention: chrono::Duration::days(1),
+        webhook: None,
+        max_total_duration: None,
     };
 
     // Create workflow engine with suspend/resume capability
@@ -161,8 +165,12 @@ async fn main() -> anyhow::Result<()> {
         .with_suspend_config(WorkflowSuspendConfig {
             auto_checkpoint: true,
             checkpoint_interval: 1, // Checkpoint after every step
+            checkpoint_jitter: 0,
+            min_checkpoint_interval: chrono::Duration::zero(),
             max_snapshots: 3,
             snapshot_retention: chrono::Duration::hours(1),
+            webhook: None,
+            max_total_duration: None,
         })
         .with_snapshot_storage(Box::new(FileSnapshotStorage::new(&storage_dir)))
         .add_step(Box::new(EnhancedMemoryRetrievalStep))
@@ -1,6 +1,6 @@
 //! Test loading configuration from config.toml
 
-use the_agency::AgentConfig;
+use the_agency::{AgentConfig, AgentError};
 
 fn main() -> anyhow::Result<()> {
     println!("🔧 Testing configuration loading from config.toml...\n");
@@ -23,7 +23,7 @@ fn main() -> anyhow::Result<()> {
     );
 
     // Validate configuration
-    config.validate()?;
+    config.validate().map_err(AgentError::from)?;
     println!("✅ Configuration is valid!\n");
 
     // Show task models
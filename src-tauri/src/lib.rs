@@ -1,7 +1,13 @@
+pub mod store;
+
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::fs;
-use std::path::Path;
+use store::WorkflowStore;
+use tauri::State;
+use the_agency::ids::new_id;
+
+/// Workflow persistence backend shared by all commands
+type Store = Box<dyn WorkflowStore>;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct NodeType {
@@ -57,21 +63,6 @@ pub struct Connection {
     pub to_input: String,
 }
 
-fn load_workflows() -> Vec<Workflow> {
-    let path = "workflows.json";
-    if Path::new(path).exists() {
-        let data = fs::read_to_string(path).unwrap_or_else(|_| "[]".to_string());
-        serde_json::from_str(&data).unwrap_or_else(|_| vec![])
-    } else {
-        vec![]
-    }
-}
-
-fn save_workflows(workflows: &[Workflow]) {
-    let data = serde_json::to_string_pretty(workflows).unwrap();
-    fs::write("workflows.json", data).unwrap();
-}
-
 #[tauri::command]
 fn get_node_types() -> Result<Vec<NodeType>, String> {
     let config_schema: serde_json::Value = serde_json::json!({
@@ -172,19 +163,17 @@ fn get_node_types() -> Result<Vec<NodeType>, String> {
 }
 
 #[tauri::command]
-fn get_workflows() -> Result<Vec<Workflow>, String> {
-    Ok(load_workflows())
+fn get_workflows(store: State<'_, Store>) -> Result<Vec<Workflow>, String> {
+    store.load_all()
 }
 
 #[tauri::command]
-fn create_workflow(name: String, description: String) -> Result<Workflow, String> {
-    let id = format!(
-        "workflow_{}",
-        std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis()
-    );
+fn create_workflow(
+    store: State<'_, Store>,
+    name: String,
+    description: String,
+) -> Result<Workflow, String> {
+    let id = format!("workflow_{}", new_id().simple());
     let workflow = Workflow {
         id: id.clone(),
         name,
@@ -192,36 +181,34 @@ fn create_workflow(name: String, description: String) -> Result<Workflow, String
         nodes: vec![],
         connections: vec![],
     };
-    let mut workflows = load_workflows();
-    workflows.push(workflow.clone());
-    save_workflows(&workflows);
+    store.create(&workflow)?;
     Ok(workflow)
 }
 
 #[tauri::command]
-fn get_workflow(id: String) -> Result<Workflow, String> {
-    let workflows = load_workflows();
-    workflows
-        .into_iter()
-        .find(|w| w.id == id)
+fn get_workflow(store: State<'_, Store>, id: String) -> Result<Workflow, String> {
+    store
+        .get(&id)?
         .ok_or_else(|| "Workflow not found".to_string())
 }
 
 #[tauri::command]
 fn update_workflow(
+    store: State<'_, Store>,
     id: String,
     name: String,
     description: String,
     nodes: Vec<Node>,
     connections: Vec<Connection>,
 ) -> Result<(), String> {
-    let mut workflows = load_workflows();
-    if let Some(workflow) = workflows.iter_mut().find(|w| w.id == id) {
-        workflow.name = name;
-        workflow.description = description;
-        workflow.nodes = nodes;
-        workflow.connections = connections;
-        save_workflows(&workflows);
+    let workflow = Workflow {
+        id,
+        name,
+        description,
+        nodes,
+        connections,
+    };
+    if store.update(&workflow)? {
         Ok(())
     } else {
         Err("Workflow not found".to_string())
@@ -229,19 +216,15 @@ fn update_workflow(
 }
 
 #[tauri::command]
-fn delete_workflow(id: String) -> Result<(), String> {
-    let mut workflows = load_workflows();
-    workflows.retain(|w| w.id != id);
-    save_workflows(&workflows);
+fn delete_workflow(store: State<'_, Store>, id: String) -> Result<(), String> {
+    store.delete(&id)?;
     Ok(())
 }
 
 #[tauri::command]
-fn execute_workflow(id: String) -> Result<String, String> {
-    let workflows = load_workflows();
-    let workflow = workflows
-        .into_iter()
-        .find(|w| w.id == id)
+fn execute_workflow(store: State<'_, Store>, id: String) -> Result<String, String> {
+    let workflow = store
+        .get(&id)?
         .ok_or_else(|| "Workflow not found".to_string())?;
 
     // Basic execution: simulate running the workflow
@@ -288,7 +271,10 @@ fn execute_workflow(id: String) -> Result<String, String> {
 pub fn run() {
     println!("Starting Workflow Builder Tauri application...");
 
+    let workflow_store = store::store_from_env().expect("failed to initialize workflow store");
+
     tauri::Builder::default()
+        .manage(workflow_store)
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
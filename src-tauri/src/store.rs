@@ -0,0 +1,324 @@
+//! Persistence backends for visual workflows
+//!
+//! The builder talks to a [`WorkflowStore`] rather than the filesystem directly so
+//! the storage backend can be chosen at startup. Two backends are provided:
+//! - [`JsonFileWorkflowStore`]: a single JSON file, written atomically via temp-file rename
+//! - [`SqliteWorkflowStore`]: SQLite via `the_agency::ui_workflow_storage`
+
+use crate::Workflow;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use the_agency::ui_workflow_storage::{StoredWorkflow, UIWorkflowStorage};
+
+/// Persistence backend for workflows
+pub trait WorkflowStore: Send + Sync {
+    /// Load every stored workflow
+    fn load_all(&self) -> Result<Vec<Workflow>, String>;
+
+    /// Get a workflow by id
+    fn get(&self, id: &str) -> Result<Option<Workflow>, String>;
+
+    /// Persist a new workflow
+    fn create(&self, workflow: &Workflow) -> Result<(), String>;
+
+    /// Replace an existing workflow, returning `false` if it does not exist
+    fn update(&self, workflow: &Workflow) -> Result<bool, String>;
+
+    /// Delete a workflow, returning `false` if it did not exist
+    fn delete(&self, id: &str) -> Result<bool, String>;
+}
+
+/// Workflow store backed by a single JSON file
+pub struct JsonFileWorkflowStore {
+    path: PathBuf,
+    /// Serializes read-modify-write cycles within this process
+    lock: Mutex<()>,
+}
+
+impl JsonFileWorkflowStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn read(&self) -> Result<Vec<Workflow>, String> {
+        if !self.path.exists() {
+            return Ok(vec![]);
+        }
+
+        let data = fs::read_to_string(&self.path)
+            .map_err(|e| format!("Failed to read {}: {}", self.path.display(), e))?;
+        serde_json::from_str(&data)
+            .map_err(|e| format!("Failed to parse {}: {}", self.path.display(), e))
+    }
+
+    /// Write to a sibling temp file and rename it over the target, so a crash
+    /// mid-write never leaves a truncated file behind
+    fn write(&self, workflows: &[Workflow]) -> Result<(), String> {
+        let data = serde_json::to_string_pretty(workflows).map_err(|e| e.to_string())?;
+
+        let tmp_path = temp_path_for(&self.path);
+        let mut file = fs::File::create(&tmp_path)
+            .map_err(|e| format!("Failed to create {}: {}", tmp_path.display(), e))?;
+        file.write_all(data.as_bytes())
+            .and_then(|_| file.sync_all())
+            .map_err(|e| format!("Failed to write {}: {}", tmp_path.display(), e))?;
+        drop(file);
+
+        fs::rename(&tmp_path, &self.path)
+            .map_err(|e| format!("Failed to replace {}: {}", self.path.display(), e))
+    }
+
+    fn modify<T>(&self, f: impl FnOnce(&mut Vec<Workflow>) -> (bool, T)) -> Result<T, String> {
+        let _guard = self.lock.lock().map_err(|e| e.to_string())?;
+        let mut workflows = self.read()?;
+        let (changed, value) = f(&mut workflows);
+        if changed {
+            self.write(&workflows)?;
+        }
+        Ok(value)
+    }
+}
+
+fn temp_path_for(path: &Path) -> PathBuf {
+    let mut name = path
+        .file_name()
+        .map(|n| n.to_os_string())
+        .unwrap_or_else(|| "workflows.json".into());
+    name.push(format!(".{}.tmp", std::process::id()));
+    path.with_file_name(name)
+}
+
+impl WorkflowStore for JsonFileWorkflowStore {
+    fn load_all(&self) -> Result<Vec<Workflow>, String> {
+        let _guard = self.lock.lock().map_err(|e| e.to_string())?;
+        self.read()
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Workflow>, String> {
+        Ok(self.load_all()?.into_iter().find(|w| w.id == id))
+    }
+
+    fn create(&self, workflow: &Workflow) -> Result<(), String> {
+        self.modify(|workflows| {
+            workflows.push(workflow.clone());
+            (true, ())
+        })
+    }
+
+    fn update(&self, workflow: &Workflow) -> Result<bool, String> {
+        self.modify(
+            |workflows| match workflows.iter_mut().find(|w| w.id == workflow.id) {
+                Some(existing) => {
+                    *existing = workflow.clone();
+                    (true, true)
+                }
+                None => (false, false),
+            },
+        )
+    }
+
+    fn delete(&self, id: &str) -> Result<bool, String> {
+        self.modify(|workflows| {
+            let before = workflows.len();
+            workflows.retain(|w| w.id != id);
+            let removed = workflows.len() != before;
+            (removed, removed)
+        })
+    }
+}
+
+/// Workflow store backed by SQLite
+pub struct SqliteWorkflowStore {
+    storage: UIWorkflowStorage,
+}
+
+impl SqliteWorkflowStore {
+    /// Connect to (and initialize) the database at `database_url`
+    pub fn new(database_url: &str) -> Result<Self, String> {
+        let storage = tauri::async_runtime::block_on(UIWorkflowStorage::new(database_url))
+            .map_err(|e| format!("Failed to open workflow database: {}", e))?;
+        Ok(Self { storage })
+    }
+
+    fn to_stored(workflow: &Workflow, created_at: String) -> Result<StoredWorkflow, String> {
+        Ok(StoredWorkflow {
+            id: workflow.id.clone(),
+            name: workflow.name.clone(),
+            description: workflow.description.clone(),
+            nodes_json: serde_json::to_string(&workflow.nodes).map_err(|e| e.to_string())?,
+            connections_json: serde_json::to_string(&workflow.connections)
+                .map_err(|e| e.to_string())?,
+            created_at,
+            updated_at: now_millis(),
+        })
+    }
+
+    fn from_stored(stored: StoredWorkflow) -> Result<Workflow, String> {
+        Ok(Workflow {
+            id: stored.id,
+            name: stored.name,
+            description: stored.description,
+            nodes: serde_json::from_str(&stored.nodes_json).map_err(|e| e.to_string())?,
+            connections: serde_json::from_str(&stored.connections_json)
+                .map_err(|e| e.to_string())?,
+        })
+    }
+}
+
+fn now_millis() -> String {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default()
+        .to_string()
+}
+
+impl WorkflowStore for SqliteWorkflowStore {
+    fn load_all(&self) -> Result<Vec<Workflow>, String> {
+        tauri::async_runtime::block_on(self.storage.list())
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(Self::from_stored)
+            .collect()
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Workflow>, String> {
+        tauri::async_runtime::block_on(self.storage.get(id))
+            .map_err(|e| e.to_string())?
+            .map(Self::from_stored)
+            .transpose()
+    }
+
+    fn create(&self, workflow: &Workflow) -> Result<(), String> {
+        let stored = Self::to_stored(workflow, now_millis())?;
+        tauri::async_runtime::block_on(self.storage.create(&stored)).map_err(|e| e.to_string())
+    }
+
+    fn update(&self, workflow: &Workflow) -> Result<bool, String> {
+        tauri::async_runtime::block_on(async {
+            let Some(existing) = self
+                .storage
+                .get(&workflow.id)
+                .await
+                .map_err(|e| e.to_string())?
+            else {
+                return Ok(false);
+            };
+            let stored = Self::to_stored(workflow, existing.created_at)?;
+            // A single UPDATE statement is atomic in SQLite
+            self.storage
+                .update(&stored)
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok(true)
+        })
+    }
+
+    fn delete(&self, id: &str) -> Result<bool, String> {
+        tauri::async_runtime::block_on(self.storage.delete(id)).map_err(|e| e.to_string())
+    }
+}
+
+/// Select a backend from the `WORKFLOW_BUILDER_STORE` environment variable.
+///
+/// A value starting with `sqlite:` selects the SQLite backend at that URL; any
+/// other value is used as the JSON file path. Defaults to `workflows.json`.
+pub fn store_from_env() -> Result<Box<dyn WorkflowStore>, String> {
+    let spec = std::env::var("WORKFLOW_BUILDER_STORE").unwrap_or_else(|_| "workflows.json".into());
+
+    if spec.starts_with("sqlite:") {
+        Ok(Box::new(SqliteWorkflowStore::new(&spec)?))
+    } else {
+        Ok(Box::new(JsonFileWorkflowStore::new(spec)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Connection, Node, Position};
+    use std::collections::HashMap;
+
+    fn temp_file(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "workflow-builder-{}-{}-{}",
+            name,
+            std::process::id(),
+            now_millis()
+        ))
+    }
+
+    fn sample_workflow(id: &str) -> Workflow {
+        Workflow {
+            id: id.to_string(),
+            name: "Sample".to_string(),
+            description: "A sample workflow".to_string(),
+            nodes: vec![Node {
+                id: "n1".to_string(),
+                node_type: "start".to_string(),
+                position: Position { x: 1.0, y: 2.0 },
+                config: HashMap::new(),
+                label: "Start".to_string(),
+            }],
+            connections: vec![],
+        }
+    }
+
+    fn round_trip(store: &dyn WorkflowStore) {
+        assert!(store.load_all().unwrap().is_empty());
+
+        store.create(&sample_workflow("wf1")).unwrap();
+        store.create(&sample_workflow("wf2")).unwrap();
+        assert_eq!(store.load_all().unwrap().len(), 2);
+
+        let mut updated = sample_workflow("wf1");
+        updated.name = "Renamed".to_string();
+        updated.connections.push(Connection {
+            id: "c1".to_string(),
+            from_node: "n1".to_string(),
+            from_output: "output".to_string(),
+            to_node: "n2".to_string(),
+            to_input: "input".to_string(),
+        });
+        assert!(store.update(&updated).unwrap());
+        assert!(!store.update(&sample_workflow("missing")).unwrap());
+
+        let fetched = store.get("wf1").unwrap().unwrap();
+        assert_eq!(fetched.name, "Renamed");
+        assert_eq!(fetched.nodes.len(), 1);
+        assert_eq!(fetched.connections.len(), 1);
+
+        assert!(store.delete("wf2").unwrap());
+        assert!(!store.delete("wf2").unwrap());
+        assert!(store.get("wf2").unwrap().is_none());
+        assert_eq!(store.load_all().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_json_file_store_round_trip() {
+        let path = temp_file("store.json");
+        let store = JsonFileWorkflowStore::new(&path);
+
+        round_trip(&store);
+
+        // No temp files are left behind after atomic writes
+        assert!(!temp_path_for(&path).exists());
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_sqlite_store_round_trip() {
+        let path = temp_file("store.db");
+        let store =
+            SqliteWorkflowStore::new(&format!("sqlite:{}?mode=rwc", path.display())).unwrap();
+
+        round_trip(&store);
+
+        let _ = fs::remove_file(&path);
+    }
+}
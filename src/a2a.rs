@@ -10,7 +10,10 @@
 //! - Service discovery and registration
 
 use crate::error::{AgentError, Result};
+use crate::http_client::HttpClientConfig;
+use crate::ids::new_id;
 use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
 use reqwest;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -18,7 +21,10 @@ use std::fmt;
 use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 use tokio::sync::{broadcast, Mutex, RwLock};
-use uuid::Uuid;
+
+pub mod dead_letter;
+
+pub use dead_letter::{DeadLetter, DeadLetterStore, FileDeadLetterStore, InMemoryDeadLetterStore};
 
 /// Unique identifier for an agent
 #[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
@@ -33,7 +39,7 @@ impl AgentId {
         Self {
             namespace: namespace.to_string(),
             name: name.to_string(),
-            instance: Uuid::new_v4().to_string(),
+            instance: new_id().to_string(),
         }
     }
 }
@@ -185,6 +191,9 @@ pub struct A2AConfig {
     pub discovery: ServiceDiscoveryConfig,
     pub security: SecurityConfig,
     pub routing: RoutingConfig,
+    /// Proxy, trusted CA and connection settings for A2A HTTP traffic
+    #[serde(default)]
+    pub http: HttpClientConfig,
 }
 
 /// Protocol-specific configuration
@@ -336,11 +345,18 @@ pub struct HttpA2AClient {
 }
 
 impl HttpA2AClient {
+    /// Client whose pooled connections are reused across messages. Unless
+    /// `http.pool_max_idle_per_host` is set, up to the HTTP protocol's
+    /// `connection_pool_size` idle connections are kept per peer.
     pub fn new(config: A2AConfig) -> Result<Self> {
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
-            .build()
-            .map_err(|e| AgentError::Config(format!("Failed to create HTTP client: {}", e)))?;
+        let mut http = config.http.clone();
+        if http.pool_max_idle_per_host.is_none() {
+            http.pool_max_idle_per_host = config
+                .protocols
+                .get(&ProtocolType::Http)
+                .map(|protocol| protocol.connection_pool_size as usize);
+        }
+        let client = http.build_client(std::time::Duration::from_secs(30))?;
 
         let (sender, receiver) = broadcast::channel(1000);
 
@@ -438,16 +454,20 @@ impl A2AClient for HttpA2AClient {
             let mut stats = self.stats.lock().await;
             stats.messages_failed += 1;
 
-            Err(AgentError::Network(format!(
-                "HTTP request failed with status: {}",
-                response.status()
-            )))
+            // Only overload and server errors may go away by sending again
+            let status = response.status();
+            let message = format!("HTTP request failed with status: {}", status);
+            if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                Err(AgentError::Network(message))
+            } else {
+                Err(AgentError::A2A(message))
+            }
         }
     }
 
     async fn request(&self, to: AgentId, payload: MessagePayload) -> Result<A2AResponse> {
         let message = A2AMessage {
-            id: Uuid::new_v4().to_string(),
+            id: new_id().to_string(),
             from: self.config.agent_id.clone(),
             to,
             message_type: MessageType::Request,
@@ -465,7 +485,7 @@ impl A2AClient for HttpA2AClient {
 
     async fn notify(&self, to: AgentId, payload: MessagePayload) -> Result<()> {
         let message = A2AMessage {
-            id: Uuid::new_v4().to_string(),
+            id: new_id().to_string(),
             from: self.config.agent_id.clone(),
             to,
             message_type: MessageType::Notification,
@@ -491,7 +511,7 @@ impl A2AClient for HttpA2AClient {
 
         for agent_id in to_agents {
             let message = A2AMessage {
-                id: Uuid::new_v4().to_string(),
+                id: new_id().to_string(),
                 from: self.config.agent_id.clone(),
                 to: agent_id,
                 message_type: MessageType::Event,
@@ -649,15 +669,74 @@ impl Default for A2AConfig {
                     half_open_max_calls: 3,
                 },
             },
+            http: HttpClientConfig::default(),
         }
     }
 }
 
+/// How one recipient of an [`A2AManager::broadcast`] responded
+#[derive(Debug, Clone)]
+pub enum BroadcastOutcome {
+    /// The agent processed the message
+    Success(A2AResponse),
+    /// The message could not be delivered, or the agent reported an error
+    Failed(String),
+    /// No response arrived within the broadcast timeout
+    TimedOut,
+}
+
+/// Per-agent outcomes of a broadcast, in the order the agents were given
+#[derive(Debug, Clone, Default)]
+pub struct BroadcastResult {
+    pub outcomes: Vec<(AgentId, BroadcastOutcome)>,
+}
+
+impl BroadcastResult {
+    /// Agents that processed the message
+    pub fn succeeded(&self) -> Vec<&AgentId> {
+        self.matching(|outcome| matches!(outcome, BroadcastOutcome::Success(_)))
+    }
+
+    /// Agents the message failed for
+    pub fn failed(&self) -> Vec<&AgentId> {
+        self.matching(|outcome| matches!(outcome, BroadcastOutcome::Failed(_)))
+    }
+
+    /// Agents that did not respond in time
+    pub fn timed_out(&self) -> Vec<&AgentId> {
+        self.matching(|outcome| matches!(outcome, BroadcastOutcome::TimedOut))
+    }
+
+    pub fn all_succeeded(&self) -> bool {
+        self.succeeded().len() == self.outcomes.len()
+    }
+
+    pub fn outcome(&self, agent_id: &AgentId) -> Option<&BroadcastOutcome> {
+        self.outcomes
+            .iter()
+            .find(|(id, _)| id == agent_id)
+            .map(|(_, outcome)| outcome)
+    }
+
+    fn matching(&self, predicate: impl Fn(&BroadcastOutcome) -> bool) -> Vec<&AgentId> {
+        self.outcomes
+            .iter()
+            .filter(|(_, outcome)| predicate(outcome))
+            .map(|(id, _)| id)
+            .collect()
+    }
+}
+
 /// A2A Manager that coordinates all agent-to-agent communication
 pub struct A2AManager {
     client: Arc<dyn A2AClient>,
     handlers: Arc<RwLock<HashMap<String, Arc<dyn MessageHandler>>>>,
     agent_id: AgentId,
+    broadcast_concurrency: usize,
+    broadcast_timeout: Duration,
+    retry_attempts: u32,
+    retry_delay: Duration,
+    dead_letters: Arc<dyn DeadLetterStore>,
 }
 
 impl A2AManager {
@@ -666,9 +745,46 @@ impl A2AManager {
             client,
             handlers: Arc::new(RwLock::new(HashMap::new())),
             agent_id,
+            broadcast_concurrency: 8,
+            broadcast_timeout: Duration::from_secs(30),
+            retry_attempts: 1,
+            retry_delay: Duration::from_millis(500),
+            dead_letters: Arc::new(InMemoryDeadLetterStore::default()),
         }
     }
 
+    /// Send to at most `concurrency` agents at once when broadcasting
+    pub fn with_broadcast_concurrency(mut self, concurrency: usize) -> Self {
+        self.broadcast_concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Count a broadcast recipient as timed out after `timeout`
+    pub fn with_broadcast_timeout(mut self, timeout: Duration) -> Self {
+        self.broadcast_timeout = timeout;
+        self
+    }
+
+    /// Try sending a request up to `attempts` times before dead-lettering
+    /// it; only retryable errors are tried again. Requests get one attempt
+    /// by default.
+    pub fn with_retry_attempts(mut self, attempts: u32) -> Self {
+        self.retry_attempts = attempts.max(1);
+        self
+    }
+
+    /// Wait `delay` after the first failed attempt, growing linearly after
+    pub fn with_retry_delay(mut self, delay: Duration) -> Self {
+        self.retry_delay = delay;
+        self
+    }
+
+    /// Keep permanently failed messages in `store` instead of in memory
+    pub fn with_dead_letter_store(mut self, store: Arc<dyn DeadLetterStore>) -> Self {
+        self.dead_letters = store;
+        self
+    }
+
     pub async fn add_handler(&self, service_name: String, handler: Arc<dyn MessageHandler>) {
         let mut handlers = self.handlers.write().await;
         handlers.insert(service_name, handler);
@@ -692,7 +808,7 @@ impl A2AManager {
         metadata.insert("service".to_string(), service.to_string());
 
         let message = A2AMessage {
-            id: Uuid::new_v4().to_string(),
+            id: new_id().to_string(),
             from: self.agent_id.clone(),
             to,
             message_type: MessageType::Request,
@@ -705,17 +821,140 @@ impl A2AManager {
             metadata,
         };
 
-        self.client.send_message(message).await
+        self.deliver(message, 0).await
+    }
+
+    /// Messages that permanently failed to send, oldest first
+    pub async fn dead_letters(&self) -> Result<Vec<DeadLetter>> {
+        self.dead_letters.list().await
+    }
+
+    /// Send the dead-lettered message `id` again. It leaves the dead-letter
+    /// queue on success and goes back with an updated reason if every attempt
+    /// fails again.
+    pub async fn retry_dead_letter(&self, id: &str) -> Result<A2AResponse> {
+        let letter = self
+            .dead_letters
+            .take(id)
+            .await?
+            .ok_or_else(|| AgentError::NotFound(format!("Dead letter {} not found", id)))?;
+        self.deliver(letter.message, letter.attempts).await
+    }
+
+    /// Send `message`, retrying retryable failures and dead-lettering it once
+    /// the attempts run out or a failure is permanent
+    async fn deliver(&self, message: A2AMessage, prior_attempts: u32) -> Result<A2AResponse> {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let error = match self.client.send_message(message.clone()).await {
+                Ok(response) => return Ok(response),
+                Err(e) => e,
+            };
+
+            if !error.is_retryable() || attempt >= self.retry_attempts {
+                tracing::warn!(
+                    "Dead-lettering message {} to {} after {} attempts: {}",
+                    message.id,
+                    message.to,
+                    attempt,
+                    error
+                );
+                self.dead_letter(message, &error, prior_attempts + attempt)
+                    .await;
+                return Err(error);
+            }
+
+            tracing::debug!(
+                "Send of message {} failed (attempt {}), retrying: {}",
+                message.id,
+                attempt,
+                error
+            );
+            tokio::time::sleep(self.retry_delay * attempt).await;
+        }
+    }
+
+    /// Move `message` to the dead-letter queue after it failed with `error`
+    async fn dead_letter(&self, message: A2AMessage, error: &AgentError, attempts: u32) {
+        let letter = DeadLetter {
+            message,
+            reason: error.to_string(),
+            attempts,
+            failed_at: SystemTime::now(),
+        };
+        if let Err(e) = self.dead_letters.push(letter).await {
+            tracing::error!("Failed to store dead letter: {}", e);
+        }
     }
 
     pub async fn discover_service(&self, service_name: &str) -> Result<Vec<AgentRegistration>> {
         self.client.discover_agents(service_name).await
     }
+
+    /// Send `payload` to every agent in `agent_ids` concurrently and report
+    /// how each one responded. A failing or slow agent never fails the
+    /// broadcast as a whole; messages that could not be sent are
+    /// dead-lettered.
+    pub async fn broadcast(
+        &self,
+        agent_ids: Vec<AgentId>,
+        payload: MessagePayload,
+    ) -> BroadcastResult {
+        let deliveries = agent_ids.into_iter().map(|to| {
+            let message = A2AMessage {
+                id: new_id().to_string(),
+                from: self.agent_id.clone(),
+                to: to.clone(),
+                message_type: MessageType::Event,
+                payload: payload.clone(),
+                priority: MessagePriority::Normal,
+                timestamp: SystemTime::now(),
+                expires_at: Some(SystemTime::now() + self.broadcast_timeout),
+                correlation_id: None,
+                reply_to: None,
+                metadata: HashMap::new(),
+            };
+
+            async move {
+                let outcome = match tokio::time::timeout(
+                    self.broadcast_timeout,
+                    self.client.send_message(message.clone()),
+                )
+                .await
+                {
+                    Ok(Ok(response)) => match response.status {
+                        ResponseStatus::Success => BroadcastOutcome::Success(response),
+                        ResponseStatus::Timeout => BroadcastOutcome::TimedOut,
+                        _ => BroadcastOutcome::Failed(response.error.unwrap_or_else(|| {
+                            format!("Agent responded with status {:?}", response.status)
+                        })),
+                    },
+                    Ok(Err(e)) => {
+                        self.dead_letter(message, &e, 1).await;
+                        BroadcastOutcome::Failed(e.to_string())
+                    }
+                    Err(_) => BroadcastOutcome::TimedOut,
+                };
+                if !matches!(outcome, BroadcastOutcome::Success(_)) {
+                    tracing::warn!("Broadcast to {} did not succeed: {:?}", to, outcome);
+                }
+                (to, outcome)
+            }
+        });
+
+        let outcomes = stream::iter(deliveries)
+            .buffered(self.broadcast_concurrency)
+            .collect()
+            .await;
+        BroadcastResult { outcomes }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use uuid::Uuid;
 
     #[test]
     fn test_agent_id_creation() {
@@ -756,4 +995,294 @@ mod tests {
         let client = HttpA2AClient::new(config);
         assert!(client.is_ok());
     }
+
+    /// Length of the first request in `data` once its headers and
+    /// content-length body have fully arrived
+    fn complete_request_len(data: &[u8]) -> Option<usize> {
+        let end = data.windows(4).position(|w| w == b"\r\n\r\n")?;
+        let headers = String::from_utf8_lossy(&data[..end]).to_lowercase();
+        let body_len: usize = headers
+            .lines()
+            .find_map(|line| line.strip_prefix("content-length:"))
+            .and_then(|len| len.trim().parse().ok())
+            .unwrap_or(0);
+        (data.len() >= end + 4 + body_len).then_some(end + 4 + body_len)
+    }
+
+    /// Peer answering every A2A message with success over keep-alive
+    /// HTTP/1.1, counting the connections it accepts
+    async fn counting_peer() -> (String, Arc<std::sync::atomic::AtomicUsize>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let connections = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let accepted = connections.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                accepted.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut pending = Vec::new();
+                    let mut buf = [0u8; 4096];
+                    loop {
+                        let request_len = loop {
+                            if let Some(len) = complete_request_len(&pending) {
+                                break len;
+                            }
+                            match socket.read(&mut buf).await {
+                                Ok(0) | Err(_) => return,
+                                Ok(n) => pending.extend_from_slice(&buf[..n]),
+                            }
+                        };
+                        pending.drain(..request_len);
+
+                        let body = serde_json::to_string(&A2AResponse {
+                            message_id: "reply".to_string(),
+                            status: ResponseStatus::Success,
+                            payload: None,
+                            error: None,
+                            processing_time_ms: 0,
+                        })
+                        .unwrap();
+                        let response = format!(
+                            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+                            body.len(),
+                            body
+                        );
+                        if socket.write_all(response.as_bytes()).await.is_err() {
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+        (url, connections)
+    }
+
+    #[tokio::test]
+    async fn test_sequential_sends_reuse_one_connection() {
+        let (url, connections) = counting_peer().await;
+        let config = A2AConfig {
+            http: HttpClientConfig {
+                pool_idle_timeout: Some(30),
+                tcp_keepalive: Some(60),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let client = HttpA2AClient::new(config).unwrap();
+
+        let peer = AgentId::new("test", "peer");
+        client.agent_registry.write().await.insert(
+            peer.clone(),
+            AgentRegistration {
+                agent_id: peer.clone(),
+                capabilities: AgentCapabilities {
+                    services: Vec::new(),
+                    protocols: vec!["http".to_string()],
+                    message_types: Vec::new(),
+                    metadata: HashMap::new(),
+                },
+                endpoints: HashMap::from([("http".to_string(), url)]),
+                heartbeat_interval: Duration::from_secs(30),
+                registered_at: SystemTime::now(),
+                last_seen: SystemTime::now(),
+                status: AgentStatus::Online,
+            },
+        );
+
+        for i in 0..20 {
+            let response = client
+                .request(
+                    peer.clone(),
+                    MessagePayload::Text {
+                        content: format!("ping {}", i),
+                    },
+                )
+                .await
+                .unwrap();
+            assert!(matches!(response.status, ResponseStatus::Success));
+        }
+
+        assert_eq!(connections.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert_eq!(client.get_stats().await.unwrap().messages_sent, 20);
+    }
+
+    /// Client whose peers answer by name: `down` fails, `gone` is unknown,
+    /// `slow` hangs, and everyone else succeeds
+    struct MockPeers;
+
+    #[async_trait]
+    impl A2AClient for MockPeers {
+        async fn send_message(&self, message: A2AMessage) -> Result<A2AResponse> {
+            match message.to.name.as_str() {
+                "down" => Err(AgentError::Network("connection refused".to_string())),
+                "gone" => Err(AgentError::NotFound("agent gone".to_string())),
+                "slow" => {
+                    tokio::time::sleep(Duration::from_secs(60)).await;
+                    unreachable!("broadcast should time out first")
+                }
+                _ => Ok(A2AResponse {
+                    message_id: message.id,
+                    status: ResponseStatus::Success,
+                    payload: None,
+                    error: None,
+                    processing_time_ms: 1,
+                }),
+            }
+        }
+
+        async fn request(&self, _to: AgentId, _payload: MessagePayload) -> Result<A2AResponse> {
+            Err(AgentError::A2A("mock peers only send messages".to_string()))
+        }
+
+        async fn notify(&self, _to: AgentId, _payload: MessagePayload) -> Result<()> {
+            Err(AgentError::A2A("mock peers only send messages".to_string()))
+        }
+
+        async fn broadcast(
+            &self,
+            _to_agents: Vec<AgentId>,
+            _payload: MessagePayload,
+        ) -> Result<Vec<A2AResponse>> {
+            Err(AgentError::A2A("mock peers only send messages".to_string()))
+        }
+
+        async fn subscribe(
+            &self,
+            _message_types: Vec<MessageType>,
+        ) -> Result<tokio::sync::broadcast::Receiver<A2AMessage>> {
+            Err(AgentError::A2A("mock peers only send messages".to_string()))
+        }
+
+        async fn register(&self, _capabilities: AgentCapabilities) -> Result<()> {
+            Ok(())
+        }
+
+        async fn unregister(&self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn discover_agents(&self, _capability: &str) -> Result<Vec<AgentRegistration>> {
+            Ok(Vec::new())
+        }
+
+        async fn get_agent_info(&self, _agent_id: &AgentId) -> Result<Option<AgentRegistration>> {
+            Ok(None)
+        }
+
+        async fn start(&self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn stop(&self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn get_stats(&self) -> Result<A2AStats> {
+            Ok(A2AStats::default())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_classifies_each_peer() {
+        let manager = A2AManager::new(Arc::new(MockPeers), AgentId::new("test", "sender"))
+            .with_broadcast_timeout(Duration::from_millis(200));
+        let (up, down, slow) = (
+            AgentId::new("test", "up"),
+            AgentId::new("test", "down"),
+            AgentId::new("test", "slow"),
+        );
+
+        let result = manager
+            .broadcast(
+                vec![up.clone(), down.clone(), slow.clone()],
+                MessagePayload::Text {
+                    content: "maintenance at 02:00".to_string(),
+                },
+            )
+            .await;
+
+        assert_eq!(result.outcomes.len(), 3);
+        assert_eq!(result.succeeded(), vec![&up]);
+        assert_eq!(result.failed(), vec![&down]);
+        assert_eq!(result.timed_out(), vec![&slow]);
+        assert!(!result.all_succeeded());
+        assert!(matches!(
+            result.outcome(&down),
+            Some(BroadcastOutcome::Failed(reason)) if reason.contains("connection refused")
+        ));
+
+        // Only the message that could not be sent is dead-lettered
+        let letters = manager.dead_letters().await.unwrap();
+        assert_eq!(letters.len(), 1);
+        assert_eq!(letters[0].message.to, down);
+        assert!(letters[0].reason.contains("connection refused"));
+        assert_eq!(letters[0].attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_permanently_failed_request_is_dead_lettered() {
+        let manager = A2AManager::new(Arc::new(MockPeers), AgentId::new("test", "sender"))
+            .with_retry_attempts(3)
+            .with_retry_delay(Duration::ZERO);
+        let down = AgentId::new("test", "down");
+
+        let err = manager
+            .send_request(
+                down.clone(),
+                "reports",
+                MessagePayload::Text {
+                    content: "weekly summary".to_string(),
+                },
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AgentError::Network(_)));
+
+        let letters = manager.dead_letters().await.unwrap();
+        assert_eq!(letters.len(), 1);
+        let letter = &letters[0];
+        assert_eq!(letter.message.to, down);
+        assert_eq!(letter.message.metadata["service"], "reports");
+        assert!(letter.reason.contains("connection refused"));
+        assert_eq!(letter.attempts, 3);
+
+        // A manual replay that fails again goes back with the attempts added up
+        let id = letter.id().to_string();
+        assert!(manager.retry_dead_letter(&id).await.is_err());
+        let letters = manager.dead_letters().await.unwrap();
+        assert_eq!(letters.len(), 1);
+        assert_eq!(letters[0].attempts, 6);
+
+        assert!(matches!(
+            manager.retry_dead_letter("missing").await,
+            Err(AgentError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_permanent_failure_is_not_retried() {
+        let manager = A2AManager::new(Arc::new(MockPeers), AgentId::new("test", "sender"))
+            .with_retry_attempts(3)
+            .with_retry_delay(Duration::ZERO);
+        let payload = MessagePayload::Text {
+            content: "weekly summary".to_string(),
+        };
+
+        let err = manager
+            .send_request(AgentId::new("test", "gone"), "reports", payload.clone())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AgentError::NotFound(_)));
+        assert_eq!(manager.dead_letters().await.unwrap()[0].attempts, 1);
+
+        // Requests get a single attempt unless configured otherwise
+        let manager = A2AManager::new(Arc::new(MockPeers), AgentId::new("test", "sender"));
+        manager
+            .send_request(AgentId::new("test", "down"), "reports", payload)
+            .await
+            .unwrap_err();
+        assert_eq!(manager.dead_letters().await.unwrap()[0].attempts, 1);
+    }
 }
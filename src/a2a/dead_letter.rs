@@ -0,0 +1,271 @@
+//! Dead-letter queue for A2A messages that could not be delivered
+//!
+//! When [`A2AManager::send_request`](super::A2AManager::send_request) gives up
+//! on a message, it records it as a [`DeadLetter`] together with the last
+//! error and the number of attempts made, instead of dropping it. Letters can
+//! be inspected with [`A2AManager::dead_letters`](super::A2AManager::dead_letters)
+//! and replayed with [`A2AManager::retry_dead_letter`](super::A2AManager::retry_dead_letter).
+//! The default [`InMemoryDeadLetterStore`] is lost on restart; use
+//! [`FileDeadLetterStore`] to keep letters on disk.
+
+use super::A2AMessage;
+use crate::error::{AgentError, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::time::SystemTime;
+use tokio::fs;
+use tokio::sync::Mutex;
+use tracing::warn;
+
+/// A message that permanently failed to send
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    /// The undelivered message. Its id identifies the letter.
+    pub message: A2AMessage,
+    /// Error of the last attempt
+    pub reason: String,
+    /// Send attempts made so far, including manual retries
+    pub attempts: u32,
+    /// When the message was last given up on
+    pub failed_at: SystemTime,
+}
+
+impl DeadLetter {
+    pub fn id(&self) -> &str {
+        &self.message.id
+    }
+}
+
+/// Where permanently failed messages are kept
+#[async_trait]
+pub trait DeadLetterStore: Send + Sync {
+    /// Add a letter, replacing any letter for the same message
+    async fn push(&self, letter: DeadLetter) -> Result<()>;
+
+    /// All letters, oldest first
+    async fn list(&self) -> Result<Vec<DeadLetter>>;
+
+    /// Remove and return the letter for message `id`
+    async fn take(&self, id: &str) -> Result<Option<DeadLetter>>;
+}
+
+/// Dead letters held in memory, dropping the oldest beyond a capacity
+#[derive(Debug)]
+pub struct InMemoryDeadLetterStore {
+    letters: Mutex<VecDeque<DeadLetter>>,
+    capacity: usize,
+}
+
+impl Default for InMemoryDeadLetterStore {
+    fn default() -> Self {
+        Self::new(1000)
+    }
+}
+
+impl InMemoryDeadLetterStore {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            letters: Mutex::new(VecDeque::new()),
+            capacity: capacity.max(1),
+        }
+    }
+}
+
+#[async_trait]
+impl DeadLetterStore for InMemoryDeadLetterStore {
+    async fn push(&self, letter: DeadLetter) -> Result<()> {
+        let mut letters = self.letters.lock().await;
+        letters.retain(|existing| existing.id() != letter.id());
+        if letters.len() == self.capacity {
+            if let Some(dropped) = letters.pop_front() {
+                warn!(
+                    "Dead-letter queue full, dropping message {}",
+                    dropped.message.id
+                );
+            }
+        }
+        letters.push_back(letter);
+        Ok(())
+    }
+
+    async fn list(&self) -> Result<Vec<DeadLetter>> {
+        Ok(self.letters.lock().await.iter().cloned().collect())
+    }
+
+    async fn take(&self, id: &str) -> Result<Option<DeadLetter>> {
+        let mut letters = self.letters.lock().await;
+        let position = letters.iter().position(|letter| letter.id() == id);
+        Ok(position.and_then(|position| letters.remove(position)))
+    }
+}
+
+/// Dead letters kept as one JSON file per message in a directory
+#[derive(Debug, Clone)]
+pub struct FileDeadLetterStore {
+    dir: PathBuf,
+}
+
+impl FileDeadLetterStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn letter_path(&self, id: &str) -> Result<PathBuf> {
+        if id.is_empty() || id.len() > MAX_ID_LEN {
+            return Err(AgentError::A2A(format!("Invalid dead letter id '{}'", id)));
+        }
+        Ok(self.dir.join(format!("{}.json", file_stem(id))))
+    }
+}
+
+/// Longest message id kept on disk; every byte may take three in the file name
+const MAX_ID_LEN: usize = 80;
+
+/// `id` as a file name. Message ids come from peers, so every byte other than
+/// an ASCII letter, digit or `-` is written as `_` and two hex digits; the
+/// name can't leave the directory and distinct ids keep distinct files.
+fn file_stem(id: &str) -> String {
+    let mut stem = String::with_capacity(id.len());
+    for byte in id.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' {
+            stem.push(byte as char);
+        } else {
+            stem.push_str(&format!("_{:02x}", byte));
+        }
+    }
+    stem
+}
+
+#[async_trait]
+impl DeadLetterStore for FileDeadLetterStore {
+    async fn push(&self, letter: DeadLetter) -> Result<()> {
+        let path = self.letter_path(letter.id())?;
+        fs::create_dir_all(&self.dir).await?;
+        fs::write(&path, serde_json::to_vec_pretty(&letter)?).await?;
+        Ok(())
+    }
+
+    async fn list(&self) -> Result<Vec<DeadLetter>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut letters = Vec::new();
+        let mut dir = fs::read_dir(&self.dir).await?;
+        while let Some(entry) = dir.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            match serde_json::from_slice::<DeadLetter>(&fs::read(&path).await?) {
+                Ok(letter) => letters.push(letter),
+                Err(e) => warn!("Skipping unreadable dead letter {}: {}", path.display(), e),
+            }
+        }
+        letters.sort_by_key(|letter| letter.failed_at);
+        Ok(letters)
+    }
+
+    async fn take(&self, id: &str) -> Result<Option<DeadLetter>> {
+        let path = self.letter_path(id)?;
+        let bytes = match fs::read(&path).await {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let letter = serde_json::from_slice(&bytes)?;
+        fs::remove_file(&path).await?;
+        Ok(Some(letter))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::a2a::{AgentId, MessagePayload, MessagePriority, MessageType};
+    use std::collections::HashMap;
+    use tempfile::tempdir;
+    use uuid::Uuid;
+
+    fn letter(attempts: u32) -> DeadLetter {
+        DeadLetter {
+            message: A2AMessage {
+                id: Uuid::new_v4().to_string(),
+                from: AgentId::new("test", "sender"),
+                to: AgentId::new("test", "down"),
+                message_type: MessageType::Request,
+                payload: MessagePayload::Text {
+                    content: "hello".to_string(),
+                },
+                priority: MessagePriority::Normal,
+                timestamp: SystemTime::now(),
+                expires_at: None,
+                correlation_id: None,
+                reply_to: None,
+                metadata: HashMap::new(),
+            },
+            reason: "connection refused".to_string(),
+            attempts,
+            failed_at: SystemTime::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_file_store_survives_reopening() {
+        let temp_dir = tempdir().unwrap();
+        let first = letter(3);
+        FileDeadLetterStore::new(temp_dir.path())
+            .push(first.clone())
+            .await
+            .unwrap();
+
+        let store = FileDeadLetterStore::new(temp_dir.path());
+        let letters = store.list().await.unwrap();
+        assert_eq!(letters.len(), 1);
+        assert_eq!(letters[0].id(), first.id());
+        assert_eq!(letters[0].attempts, 3);
+
+        assert!(store.take(first.id()).await.unwrap().is_some());
+        assert!(store.take(first.id()).await.unwrap().is_none());
+        assert!(store.list().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_file_store_keeps_any_message_id_inside_its_directory() {
+        let temp_dir = tempdir().unwrap();
+        let store = FileDeadLetterStore::new(temp_dir.path().join("letters"));
+        let mut escaping = letter(1);
+        escaping.message.id = "../escape".to_string();
+        let mut similar = letter(2);
+        similar.message.id = "__escape".to_string();
+        store.push(escaping).await.unwrap();
+        store.push(similar).await.unwrap();
+
+        assert!(!temp_dir.path().join("escape.json").exists());
+        assert_eq!(store.list().await.unwrap().len(), 2);
+        let taken = store.take("../escape").await.unwrap().unwrap();
+        assert_eq!(taken.attempts, 1);
+        assert_eq!(store.list().await.unwrap()[0].id(), "__escape");
+        assert!(store.take(&"x".repeat(MAX_ID_LEN + 1)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store_drops_oldest_beyond_capacity() {
+        let store = InMemoryDeadLetterStore::new(2);
+        let letters = [letter(1), letter(2), letter(3)];
+        for letter in &letters {
+            store.push(letter.clone()).await.unwrap();
+        }
+
+        let kept: Vec<u32> = store
+            .list()
+            .await
+            .unwrap()
+            .iter()
+            .map(|letter| letter.attempts)
+            .collect();
+        assert_eq!(kept, vec![2, 3]);
+    }
+}
@@ -1,16 +1,50 @@
 //! Main AI Agent implementation
 
-use crate::a2a::{A2AManager, AgentCapabilities, AgentId, HttpA2AClient};
-use crate::config::AgentConfig;
-use crate::error::Result;
+pub mod events;
+pub mod middleware;
+pub mod reasoning;
+pub mod response_limit;
+pub mod response_processor;
+
+use events::TurnEvents;
+pub use events::{AgentEvent, AgentEventHandler};
+pub use middleware::AgentMiddleware;
+use reasoning::{Piece, ThinkingSplitter};
+pub use response_limit::ResponseLimitReport;
+pub use response_processor::{ResponseProcessor, ResponseProcessorChain};
+
+use crate::a2a::{A2AManager, AgentCapabilities, AgentId, HttpA2AClient, ProtocolType};
+use crate::config::{AgentConfig, ResponseLimitStrategy};
+use crate::error::{AgentError, Result};
+use crate::llm::capabilities;
+use crate::llm::embedding_cache::{CachedEmbeddingClient, EmbeddingCache, EmbeddingCacheStats};
+use crate::llm::embedding_limit::{EmbeddingLimitStats, LimitedEmbeddingClient};
+use crate::llm::generation_timeout::TimeoutLlmClient;
+use crate::llm::manager::ProviderManager;
+use crate::llm::pricing::{PricingTable, TokenUsage};
+use crate::llm::retry_budget::RetryBudget;
+use crate::llm::single_flight::SingleFlightLlmClient;
+use crate::llm::stream::StreamEvent;
 use crate::llm::{
-    assistant_message, system_message, user_message, LlmClient, Message, OllamaClient, Role,
+    assistant_message, system_message, user_message, GenerationResponse, LlmClient, Message,
+    OllamaClient, Role,
 };
-use crate::mcp::{McpClient, ToolCall};
-use crate::memory::{MemoryStore, SqliteMemoryStore};
-use crate::tools::BuiltinTools;
-use crate::workflow::{WorkflowContext, WorkflowEngine, WorkflowResult};
-use std::collections::HashMap;
+use crate::mcp::{McpClient, ToolCall, ToolContent, ToolResult};
+use crate::memory::shared::merge_results;
+use crate::memory::{MemoryStore, SharedMemory, SqliteMemoryStore};
+use crate::prompts::{self, PromptLibrary};
+use crate::tools::memory_search::MEMORY_NAMESPACE_KEY;
+use crate::tools::{tool_name_matches, BuiltinTools, MemorySearchTool};
+use crate::unified_storage::{
+    MemoryMessage, MemoryThread, MessageRole, ResourceId, UnifiedStorage,
+};
+use crate::workflow::{
+    GuardrailStep, ToolApprovals, ToolResultSummarizer, WorkflowContext, WorkflowDecision,
+    WorkflowEngine, WorkflowResult,
+};
+use futures::stream::{self, BoxStream, StreamExt};
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::{debug, error, info, warn};
@@ -21,7 +55,13 @@ pub struct Agent {
     config: AgentConfig,
 
     /// LLM client for text generation and embeddings
-    llm: Box<dyn LlmClient>,
+    llm: Arc<dyn LlmClient>,
+
+    /// Embeddings reused across memory storage and search, when enabled
+    embedding_cache: Option<Arc<EmbeddingCache>>,
+
+    /// Keeps embedding inputs within the embedding model's limit
+    embedding_limit: Arc<LimitedEmbeddingClient>,
 
     /// Memory store for persistent knowledge
     memory: Arc<RwLock<Box<dyn MemoryStore>>>,
@@ -35,11 +75,57 @@ pub struct Agent {
     /// Built-in tools
     builtin_tools: BuiltinTools,
 
+    /// Tool for searching this agent's memory on demand
+    memory_search: Option<MemorySearchTool>,
+
+    /// Memory shared with the other agents of the current workspace
+    shared_memory: Option<SharedMemory>,
+
     /// Workflow engine
     workflow: WorkflowEngine,
 
     /// Conversation history
     conversation: Vec<Message>,
+
+    /// Pricing table used to account for generation cost
+    pricing: PricingTable,
+
+    /// Accumulated cost (USD) of all generations made by this agent
+    total_cost: f64,
+
+    /// Middleware invoked around `process`, in insertion order
+    middleware: Vec<Arc<dyn AgentMiddleware>>,
+
+    /// Applied to each final response, before `max_response_chars`
+    response_processors: ResponseProcessorChain,
+
+    /// Checks each final response before the response processors
+    guardrail: Option<Arc<GuardrailStep>>,
+
+    /// Tool name patterns this agent may use; all tools when unset
+    allowed_tools: Option<Vec<String>>,
+
+    /// Summarizes long tool results before they enter the conversation
+    tool_summarizer: Option<ToolResultSummarizer>,
+
+    /// Thread in unified storage each turn is written to, when bound
+    thread: Option<ThreadBinding>,
+
+    /// Prompt templates for the agent's own LLM calls
+    prompts: Arc<PromptLibrary>,
+
+    /// What `max_response_chars` did to the last response, when set
+    last_response_limit: Option<ResponseLimitReport>,
+
+    /// Called with each event of a turn, in registration order
+    event_handlers: Vec<AgentEventHandler>,
+}
+
+/// Conversation thread an agent persists its turns to
+struct ThreadBinding {
+    storage: Arc<dyn UnifiedStorage>,
+    thread_id: String,
+    resource_id: ResourceId,
 }
 
 impl Agent {
@@ -49,9 +135,28 @@ impl Agent {
 
         // Validate configuration
         config.validate()?;
+        capabilities::validate_config(&config)?;
+        if config.llm.verify_models {
+            capabilities::validate_ollama_models(&config).await?;
+        }
 
         // Initialize LLM client
-        let llm: Box<dyn LlmClient> = Box::new(OllamaClient::new(config.llm.clone()));
+        let mut llm = TimeoutLlmClient::wrap(
+            Arc::new(OllamaClient::try_new(config.llm.clone())?),
+            &config.llm,
+        );
+        llm = ProviderManager::wrap(llm, &config.llm);
+        llm = SingleFlightLlmClient::wrap(llm, &config.llm);
+        let embedding_limit = Arc::new(LimitedEmbeddingClient::new(llm, &config.llm));
+        llm = embedding_limit.clone();
+        let embedding_cache = EmbeddingCache::from_config(&config.llm.embedding_cache);
+        if let Some(cache) = &embedding_cache {
+            llm = Arc::new(CachedEmbeddingClient::new(
+                llm,
+                cache.clone(),
+                &config.llm.embedding_model,
+            ));
+        }
 
         // Initialize memory store
         let mut memory_store: Box<dyn MemoryStore> =
@@ -77,6 +182,18 @@ impl Agent {
         // Initialize built-in tools
         let builtin_tools = BuiltinTools::new();
 
+        // Expose memory as a tool, scoped to this agent's namespace
+        let memory_search = if config.agent.use_memory {
+            Some(
+                MemorySearchTool::new(memory.clone(), llm.clone())
+                    .with_namespace(config.agent.name.clone())
+                    .with_default_top_k(config.memory.max_search_results)
+                    .with_threshold(config.memory.similarity_threshold),
+            )
+        } else {
+            None
+        };
+
         // Initialize A2A manager if enabled
         let a2a = if config.a2a.discovery.enabled {
             let agent_id = AgentId::new(&config.agent.name, &config.agent.name);
@@ -84,7 +201,10 @@ impl Agent {
 
             match HttpA2AClient::new(a2a_config) {
                 Ok(client) => {
-                    let a2a_manager = A2AManager::new(Arc::new(client), agent_id);
+                    let mut a2a_manager = A2AManager::new(Arc::new(client), agent_id);
+                    if let Some(http) = config.a2a.protocols.get(&ProtocolType::Http) {
+                        a2a_manager = a2a_manager.with_retry_attempts(http.retry_attempts);
+                    }
                     Some(a2a_manager)
                 }
                 Err(e) => {
@@ -122,10 +242,13 @@ impl Agent {
             .clone()
             .unwrap_or_else(|| "sqlite:.agency/agent.db".to_string());
 
-        use crate::workflow::SqliteSnapshotStorage;
+        use crate::workflow::{EncryptedSnapshotStorage, SqliteSnapshotStorage};
         let mut storage = SqliteSnapshotStorage::new(database_url);
         if let Err(e) = storage.initialize().await {
             warn!("Failed to initialize workflow snapshot storage: {}", e);
+        } else if let Some(encryption) = &config.workflow.snapshot_encryption {
+            let storage = EncryptedSnapshotStorage::from_config(Arc::new(storage), encryption)?;
+            workflow = workflow.with_snapshot_storage(Box::new(storage));
         } else {
             workflow = workflow.with_snapshot_storage(Box::new(storage));
         }
@@ -136,8 +259,21 @@ impl Agent {
             let suspend_config = WorkflowSuspendConfig {
                 auto_checkpoint: config.workflow.auto_checkpoint,
                 checkpoint_interval: config.workflow.checkpoint_interval,
+                checkpoint_jitter: config.workflow.checkpoint_jitter,
+                min_checkpoint_interval: chrono::Duration::seconds(
+                    config.workflow.min_checkpoint_interval_secs as i64,
+                ),
                 max_snapshots: config.workflow.max_snapshots,
                 snapshot_retention: chrono::Duration::days(config.workflow.snapshot_retention_days),
+                webhook: config
+                    .workflow
+                    .suspension_webhook_url
+                    .clone()
+                    .map(crate::workflow::SuspensionWebhook::new),
+                max_total_duration: config
+                    .workflow
+                    .max_total_duration_secs
+                    .map(std::time::Duration::from_secs),
             };
             workflow = workflow.with_suspend_config(suspend_config);
         } else {
@@ -145,8 +281,12 @@ impl Agent {
             let suspend_config = WorkflowSuspendConfig {
                 auto_checkpoint: false,
                 checkpoint_interval: 0,
+                checkpoint_jitter: 0,
+                min_checkpoint_interval: chrono::Duration::zero(),
                 max_snapshots: 0,
                 snapshot_retention: chrono::Duration::days(0),
+                webhook: None,
+                max_total_duration: None,
             };
             workflow = workflow.with_suspend_config(suspend_config);
         }
@@ -154,114 +294,693 @@ impl Agent {
         // Initialize conversation with system message
         let conversation = vec![system_message(&config.agent.system_prompt)];
 
+        let pricing = config.llm.pricing_table();
+
+        let prompts = Arc::new(match &config.agent.prompts_dir {
+            Some(dir) => PromptLibrary::from_dir(dir)?,
+            None => PromptLibrary::builtin(),
+        });
+        let tool_summarizer = config.mcp.summarize_results.clone().map(|summary_config| {
+            ToolResultSummarizer::new(llm.clone(), summary_config)
+                .with_prompt_library(prompts.clone())
+        });
+
         info!("AI Agent initialized successfully");
 
         Ok(Self {
             config,
             llm,
+            embedding_cache,
+            embedding_limit,
             memory,
             mcp,
             a2a,
             builtin_tools,
+            memory_search,
+            shared_memory: None,
             workflow,
             conversation,
+            pricing,
+            total_cost: 0.0,
+            middleware: Vec::new(),
+            response_processors: ResponseProcessorChain::new(),
+            guardrail: None,
+            allowed_tools: None,
+            tool_summarizer,
+            thread: None,
+            prompts,
+            last_response_limit: None,
+            event_handlers: Vec::new(),
+        })
+    }
+
+    /// Write each turn's messages to the thread `thread_id` in `storage`,
+    /// creating the thread if it doesn't exist. Messages already in the thread
+    /// are not loaded; use [`Self::resume_thread`] to continue a conversation.
+    pub async fn bind_thread(
+        &mut self,
+        storage: Arc<dyn UnifiedStorage>,
+        thread_id: &str,
+    ) -> Result<()> {
+        let resource_id = match storage.get_memory_thread(thread_id).await? {
+            Some(thread) => thread.resource_id,
+            None => {
+                let resource_id = ResourceId::new("agent", &self.config.agent.name);
+                let now = std::time::SystemTime::now();
+                storage
+                    .create_memory_thread(&MemoryThread {
+                        thread_id: thread_id.to_string(),
+                        resource_id: resource_id.clone(),
+                        title: format!("Conversation with {}", self.config.agent.name),
+                        created_at: now,
+                        updated_at: now,
+                        metadata: HashMap::new(),
+                        message_count: 0,
+                    })
+                    .await?;
+                resource_id
+            }
+        };
+
+        info!("Agent bound to conversation thread {}", thread_id);
+        self.thread = Some(ThreadBinding {
+            storage,
+            thread_id: thread_id.to_string(),
+            resource_id,
+        });
+        Ok(())
+    }
+
+    /// Replace the conversation with the messages stored in the thread
+    /// `thread_id` and write further turns to it, returning how many messages
+    /// were loaded. The system prompt still comes from this agent's config.
+    pub async fn resume_thread(
+        &mut self,
+        storage: Arc<dyn UnifiedStorage>,
+        thread_id: &str,
+    ) -> Result<usize> {
+        if storage.get_memory_thread(thread_id).await?.is_none() {
+            return Err(AgentError::NotFound(format!(
+                "Conversation thread {} not found",
+                thread_id
+            )));
+        }
+
+        let messages = storage.get_memory_messages(thread_id, None).await?;
+        self.clear_conversation();
+        self.conversation
+            .extend(messages.iter().filter_map(|message| match message.role {
+                MessageRole::User => Some(user_message(&message.content)),
+                MessageRole::Assistant => Some(assistant_message(&message.content)),
+                MessageRole::System | MessageRole::Tool => None,
+            }));
+        self.limit_conversation_history();
+        debug!(
+            "Loaded {} messages from conversation thread {}",
+            messages.len(),
+            thread_id
+        );
+
+        self.bind_thread(storage, thread_id).await?;
+        Ok(messages.len())
+    }
+
+    /// Id of the thread this agent's turns are written to
+    pub fn thread_id(&self) -> Option<&str> {
+        self.thread.as_ref().map(|thread| thread.thread_id.as_str())
+    }
+
+    /// Write a user message and the reply to it to the bound thread
+    async fn persist_turn(&self, user_input: &str, response: &str) -> Result<()> {
+        let Some(thread) = &self.thread else {
+            return Ok(());
+        };
+
+        let user_message_id = crate::ids::new_id().to_string();
+        for (message_id, role, content, parent_message_id) in [
+            (user_message_id.clone(), MessageRole::User, user_input, None),
+            (
+                crate::ids::new_id().to_string(),
+                MessageRole::Assistant,
+                response,
+                Some(user_message_id),
+            ),
+        ] {
+            thread
+                .storage
+                .add_memory_message(&MemoryMessage {
+                    message_id,
+                    thread_id: thread.thread_id.clone(),
+                    resource_id: thread.resource_id.clone(),
+                    role,
+                    content: content.to_string(),
+                    timestamp: std::time::SystemTime::now(),
+                    metadata: HashMap::new(),
+                    parent_message_id,
+                })
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Also search `shared` whenever this agent searches its memory, and with
+    /// `memory.shared_writes` set, store knowledge there instead. `None`
+    /// leaves the agent with only its own memory.
+    pub fn set_shared_memory(&mut self, shared: Option<SharedMemory>) {
+        if let Some(memory_search) = &mut self.memory_search {
+            memory_search.set_shared_memory(shared.clone());
+        }
+        self.shared_memory = shared;
+    }
+
+    /// Memory shared with the other agents of the current workspace
+    pub fn shared_memory(&self) -> Option<&SharedMemory> {
+        self.shared_memory.as_ref()
+    }
+
+    /// Add middleware to run around `process`, after any already added
+    pub fn add_middleware(&mut self, middleware: Arc<dyn AgentMiddleware>) {
+        self.middleware.push(middleware);
+    }
+
+    /// Post-process each final response with `processor`, after any already
+    /// added
+    pub fn add_response_processor(&mut self, processor: Arc<dyn ResponseProcessor>) {
+        self.response_processors.push(processor);
+    }
+
+    /// Check each final response with `guardrail` before it is processed and
+    /// returned. A response held for review is withheld: the turn suspends
+    /// and answers with the guardrail's blocked message. Streamed responses
+    /// are checked once fully generated, before the turn is recorded; their
+    /// text has already been delivered by then, so only the recorded
+    /// response is replaced.
+    pub fn set_guardrail(&mut self, guardrail: GuardrailStep) {
+        self.guardrail = Some(Arc::new(guardrail));
+    }
+
+    /// Limit the tools this agent can see and call to those matching `patterns`
+    /// (see [`tool_name_matches`])
+    pub fn restrict_tools(&mut self, patterns: Vec<String>) {
+        self.allowed_tools = Some(patterns);
+    }
+
+    /// Hold back tool calls that `approvals` requires a reviewer to approve;
+    /// the turn suspends with the proposed calls instead of running them
+    pub fn set_tool_approvals(&mut self, approvals: ToolApprovals) {
+        self.workflow = std::mem::take(&mut self.workflow).with_tool_approvals(approvals);
+    }
+
+    /// Whether this agent may call the named tool
+    pub fn is_tool_allowed(&self, tool_name: &str) -> bool {
+        self.allowed_tools.as_ref().is_none_or(|patterns| {
+            patterns
+                .iter()
+                .any(|pattern| tool_name_matches(pattern, tool_name))
         })
     }
 
-    /// Process a user message and return a response
+    /// Process a user message and return a response. With a `retry_budget`
+    /// configured, LLM retries across the whole request draw on one budget.
     pub async fn process(&mut self, user_input: &str) -> Result<String> {
+        Ok(self.process_turn(user_input).await?.response)
+    }
+
+    /// [`Agent::process`], also returning the reasoning behind the response
+    /// when `capture_reasoning` is set
+    pub async fn process_turn(&mut self, user_input: &str) -> Result<TurnOutput> {
+        let mut turn = TurnEvents::start(self.event_handlers.clone());
+        let output = self.process_with_middleware(user_input).await;
+        match &output {
+            Ok(_) => turn.complete(),
+            Err(e) => turn.fail(e),
+        }
+        output
+    }
+
+    /// [`Agent::process_turn`] without events
+    async fn process_with_middleware(&mut self, user_input: &str) -> Result<TurnOutput> {
+        let mut input = user_input.to_string();
+        for middleware in &self.middleware {
+            input = middleware.before_process(input).await.map_err(|e| {
+                warn!("Middleware '{}' rejected input: {}", middleware.name(), e);
+                e
+            })?;
+        }
+
+        let started = std::time::Instant::now();
+        let output = match self.config.agent.retry_budget {
+            Some(max_retries) => {
+                RetryBudget::new(max_retries)
+                    .scope(self.process_input(&input))
+                    .await
+            }
+            None => self.process_input(&input).await,
+        };
+        crate::metrics::record_request(output.is_ok(), started.elapsed());
+        let mut output = output?;
+
+        for middleware in self.middleware.iter().rev() {
+            output.response = middleware
+                .after_process(output.response)
+                .await
+                .map_err(|e| {
+                    warn!("Middleware '{}' rejected output: {}", middleware.name(), e);
+                    e
+                })?;
+        }
+
+        Ok(output)
+    }
+
+    /// Process a user message, streaming the response as it is generated.
+    ///
+    /// Tool calls made while the workflow runs are emitted as
+    /// [`StreamEvent::ToolCall`] before the tool executes, followed by the
+    /// response text and a final [`StreamEvent::Done`] once the turn has been
+    /// recorded. Input middleware runs as in [`Agent::process`]; output
+    /// middleware, response processors and `max_response_chars` do not, since
+    /// the text is delivered before it is complete.
+    pub async fn process_stream(
+        &mut self,
+        user_input: &str,
+    ) -> Result<BoxStream<'_, Result<StreamEvent>>> {
+        let mut turn = TurnEvents::start(self.event_handlers.clone());
+        let mut input = user_input.to_string();
+        for middleware in &self.middleware {
+            input = match middleware.before_process(input).await {
+                Ok(input) => input,
+                Err(e) => {
+                    warn!("Middleware '{}' rejected input: {}", middleware.name(), e);
+                    turn.fail(&e);
+                    return Err(e);
+                }
+            };
+        }
+
+        let state = ResponseStream {
+            turn,
+            retry_budget: self.config.agent.retry_budget.map(RetryBudget::new),
+            thinking: self
+                .config
+                .agent
+                .capture_reasoning
+                .then(ThinkingSplitter::default),
+            agent: self,
+            user_input: input,
+            response: String::new(),
+            usage: None,
+            phase: StreamPhase::Start,
+            queued: VecDeque::new(),
+            context: None,
+        };
+        Ok(stream::unfold(state, |mut state| async move {
+            let event = match state.retry_budget.clone() {
+                Some(budget) => budget.scope(state.next_event()).await?,
+                None => state.next_event().await?,
+            };
+            match &event {
+                Ok(StreamEvent::TextDelta(text)) => {
+                    state.agent.emit(AgentEvent::Token(text.clone()))
+                }
+                Ok(StreamEvent::Done { .. }) => state.turn.complete(),
+                Ok(StreamEvent::ToolCall(_) | StreamEvent::Reasoning(_)) => {}
+                Err(e) => state.turn.fail(e),
+            }
+            Some((event, state))
+        })
+        .boxed())
+    }
+
+    /// Process a user message, writing response text to `writer` as it
+    /// arrives and returning the full response.
+    ///
+    /// Tool calls are written as a `[calling tool: name]` line, so a terminal
+    /// shows what the agent is doing while it waits on a tool.
+    pub async fn process_to_writer<W: Write>(
+        &mut self,
+        prompt: &str,
+        writer: &mut W,
+    ) -> Result<String> {
+        let mut events = self.process_stream(prompt).await?;
+        let mut response = String::new();
+        while let Some(event) = events.next().await {
+            match event? {
+                StreamEvent::TextDelta(text) => {
+                    writer.write_all(text.as_bytes())?;
+                    response.push_str(&text);
+                }
+                StreamEvent::ToolCall(call) => {
+                    writeln!(writer, "[calling tool: {}]", call.name)?;
+                }
+                StreamEvent::Reasoning(_) | StreamEvent::Done { .. } => {}
+            }
+            writer.flush()?;
+        }
+        Ok(response)
+    }
+
+    /// Core processing of a user message, without middleware
+    async fn process_input(&mut self, user_input: &str) -> Result<TurnOutput> {
         info!(
             "Processing user input: {}",
             user_input.chars().take(100).collect::<String>()
         );
 
+        // Execute workflow
+        let context = self.start_turn(user_input).await;
+        let mut result = self.workflow.execute(context).await?;
+
+        // Handle pending actions
+        while let Some(action) = take_pending_action(&mut result) {
+            result = self.perform_action(result, action).await?;
+        }
+        if let Some(reason) = result.suspended.take() {
+            self.emit(AgentEvent::Suspended(reason));
+        }
+
+        // Generate the final response if the workflow left it to the LLM or
+        // didn't complete
+        let mut reasoning = None;
+        if result.generate_with_llm || !result.completed {
+            (result, reasoning) = self.generate_final_response(result).await?;
+        } else {
+            self.emit(AgentEvent::Token(result.response.clone()));
+        }
+        let response = self
+            .guard_response(&mut result.context, result.response)
+            .await?;
+
+        let response = self.response_processors.process(response);
+        let response = self.limit_response(response).await;
+        self.finish_turn(user_input, &response).await?;
+
+        debug!("Generated response with {} characters", response.len());
+        Ok(TurnOutput {
+            response,
+            reasoning,
+        })
+    }
+
+    /// Fit `response` to `max_response_chars`, if set, recording what was done
+    async fn limit_response(&mut self, response: String) -> String {
+        let Some(max_chars) = self.config.agent.max_response_chars else {
+            return response;
+        };
+        let strategy = self.config.agent.response_limit_strategy;
+        let original_chars = response.chars().count();
+
+        let (limited, strategy) = if original_chars <= max_chars {
+            (response, strategy)
+        } else {
+            match strategy {
+                ResponseLimitStrategy::Truncate => (
+                    response_limit::truncate_chars(&response, max_chars),
+                    strategy,
+                ),
+                ResponseLimitStrategy::TruncateWithEllipsis => (
+                    response_limit::truncate_with_ellipsis(&response, max_chars),
+                    strategy,
+                ),
+                ResponseLimitStrategy::Summarize => {
+                    match self.compress_response(&response, max_chars).await {
+                        Some(summary) if summary.chars().count() <= max_chars => {
+                            (summary, strategy)
+                        }
+                        summary => (
+                            response_limit::truncate_with_ellipsis(
+                                summary.as_deref().unwrap_or(&response),
+                                max_chars,
+                            ),
+                            ResponseLimitStrategy::TruncateWithEllipsis,
+                        ),
+                    }
+                }
+            }
+        };
+
+        let report = ResponseLimitReport {
+            strategy,
+            truncated: original_chars > max_chars,
+            original_chars,
+            final_chars: limited.chars().count(),
+        };
+        if report.truncated {
+            info!(
+                "Response of {} characters cut to {} ({:?})",
+                report.original_chars, report.final_chars, report.strategy
+            );
+        }
+        self.last_response_limit = Some(report);
+        limited
+    }
+
+    /// Ask the LLM to compress `response` to `max_chars` characters, or `None`
+    /// if it fails
+    async fn compress_response(&mut self, response: &str, max_chars: usize) -> Option<String> {
+        let system_prompt = match self.prompts.render(
+            prompts::RESPONSE_COMPRESS,
+            &[("max_chars", &max_chars.to_string())],
+        ) {
+            Ok(system_prompt) => system_prompt,
+            Err(e) => {
+                warn!("Failed to render response compression prompt: {}", e);
+                return None;
+            }
+        };
+
+        let messages = [system_message(&system_prompt), user_message(response)];
+        match self.llm.generate(&messages).await {
+            Ok(generation) => {
+                self.record_usage(&generation);
+                Some(generation.text.trim().to_string())
+            }
+            Err(e) => {
+                warn!("Failed to compress over-long response: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Record the user message and build the workflow context for a new turn
+    async fn start_turn(&mut self, user_input: &str) -> WorkflowContext {
         // Add user message to conversation
         let user_msg = user_message(user_input);
         self.conversation.push(user_msg.clone());
 
         // Create workflow context
-        let mut context = WorkflowContext::new(self.config.agent.max_thinking_steps);
+        let mut context =
+            WorkflowContext::new(self.config.agent.max_thinking_steps).with_message_dedup(true);
+        if let Some(max_tool_calls) = self.config.workflow.max_tool_calls {
+            context = context.with_max_tool_calls(max_tool_calls);
+        }
 
         // Add conversation history to context
         for message in &self.conversation {
             context.add_message(message.clone());
         }
 
+        if let Some(now) = self.current_datetime() {
+            let line = format!("Current date and time: {}", now);
+            match context
+                .messages
+                .iter_mut()
+                .find(|message| message.role == Role::System)
+            {
+                Some(system) => system.content = format!("{}\n\n{}", line, system.content),
+                None => context.messages.insert(0, system_message(&line)),
+            }
+        }
+
         // Add available tools to context
         context.available_tools = self.get_available_tools().await;
+        context
+    }
 
-        // Execute workflow
-        let mut result = self.workflow.execute(context).await?;
+    /// The current date and time in the configured timezone and format, when
+    /// `inject_datetime` is set
+    fn current_datetime(&self) -> Option<String> {
+        let agent = &self.config.agent;
+        if !agent.inject_datetime {
+            return None;
+        }
+        // Both settings are checked when the agent is created
+        let timezone: chrono_tz::Tz = agent
+            .timezone
+            .as_deref()
+            .and_then(|timezone| timezone.parse().ok())
+            .unwrap_or(chrono_tz::UTC);
+        let now = chrono::Utc::now().with_timezone(&timezone);
+        Some(match &agent.datetime_format {
+            Some(format) => now.format(format).to_string(),
+            None => now.to_rfc3339(),
+        })
+    }
 
-        // Handle pending actions
-        while result.has_pending_actions() {
-            if let Some(tool_calls) = result.pending_tool_calls.take() {
-                result = self.handle_tool_calls(result, tool_calls).await?;
-            }
+    /// `prompt` between the configured prefix and suffix, separated by blank
+    /// lines
+    fn wrap_prompt(&self, prompt: &str) -> String {
+        let agent = &self.config.agent;
+        [
+            agent.prompt_prefix.as_deref(),
+            Some(prompt),
+            agent.prompt_suffix.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+    }
 
-            if let Some(memory_query) = result.pending_memory_query.take() {
-                result = self.handle_memory_retrieval(result, memory_query).await?;
+    /// Record the response to a turn in the conversation, thread and memory
+    /// `response`, or what the guardrail replaces it with
+    async fn guard_response(
+        &mut self,
+        context: &mut WorkflowContext,
+        response: String,
+    ) -> Result<String> {
+        let Some(guardrail) = self.guardrail.clone() else {
+            return Ok(response);
+        };
+        Ok(match guardrail.review(context, &response).await? {
+            WorkflowDecision::Complete(replacement) => replacement,
+            WorkflowDecision::Suspend(reason) => {
+                self.emit(AgentEvent::Suspended(reason));
+                guardrail.blocked_message().to_string()
             }
-        }
-
-        // Generate final response if workflow didn't complete OR returned empty response
-        if !result.completed || result.response.is_empty() {
-            result = self.generate_final_response(result).await?;
-        }
+            _ => response,
+        })
+    }
 
+    async fn finish_turn(&mut self, user_input: &str, response: &str) -> Result<()> {
         // Add assistant response to conversation
-        let assistant_msg = assistant_message(&result.response);
+        let assistant_msg = assistant_message(response);
         self.conversation.push(assistant_msg);
 
         // Limit conversation history
         self.limit_conversation_history();
 
+        if self.thread.is_some() {
+            self.persist_turn(user_input, response).await?;
+        }
+
         // Store conversation in memory if enabled
         if self.config.agent.use_memory {
-            self.store_conversation_memory(user_input, &result.response)
-                .await?;
+            self.store_conversation_memory(user_input, response).await?;
         }
 
-        debug!(
-            "Generated response with {} characters",
-            result.response.len()
-        );
-        Ok(result.response)
+        Ok(())
     }
 
-    /// Handle tool calls during workflow execution
-    async fn handle_tool_calls(
+    /// Carry out an action the workflow asked for, continuing the workflow
+    /// once the last of its pending actions is done. Shared by
+    /// [`Agent::process`] and [`Agent::process_stream`].
+    async fn perform_action(
         &self,
         mut result: WorkflowResult,
-        tool_calls: Vec<ToolCall>,
+        action: PendingAction,
     ) -> Result<WorkflowResult> {
-        debug!("Handling {} tool calls", tool_calls.len());
-
-        for tool_call in tool_calls {
-            // Try built-in tools first
-            if let Some(tool_result) = self.builtin_tools.execute(&tool_call.name).await {
-                result
-                    .context
-                    .add_tool_result(tool_call.id.clone(), tool_result);
-                continue;
-            }
-
-            // Try MCP tools
-            let mcp = self.mcp.read().await;
-            match mcp.call_tool(tool_call.clone()).await {
-                Ok(tool_result) => {
-                    result.context.add_tool_result(tool_call.id, tool_result);
-                }
-                Err(e) => {
-                    warn!("Tool call failed: {}", e);
-                    // Continue with other tools
+        match action {
+            PendingAction::ToolCall(tool_call) => {
+                self.run_tool_call(&mut result.context, tool_call).await?;
+                if result
+                    .pending_tool_calls
+                    .as_ref()
+                    .is_some_and(|calls| !calls.is_empty())
+                {
+                    return Ok(result);
                 }
+                result.pending_tool_calls = None;
+
+                // Continue workflow with tool results
+                // Don't reset step count to avoid infinite loops
+                self.workflow.execute(result.context).await
             }
+            PendingAction::MemoryQuery(query) => self.handle_memory_retrieval(result, query).await,
         }
+    }
 
-        // Continue workflow with tool results
-        // Don't reset step count to avoid infinite loops
-        self.workflow.execute(result.context).await
+    /// Execute a tool call and add its result to the workflow context
+    async fn run_tool_call(
+        &self,
+        context: &mut WorkflowContext,
+        tool_call: ToolCall,
+    ) -> Result<()> {
+        self.emit(AgentEvent::ToolCallStarted(tool_call.clone()));
+        let tool_result = self.execute_tool_call(&tool_call).await;
+        crate::metrics::record_tool_call(
+            &tool_call.name,
+            self.get_available_tools().await.contains(&tool_call.name),
+            tool_result.as_ref().is_ok_and(|result| !result.is_error),
+        );
+        let tool_result = match tool_result {
+            Ok(tool_result) => tool_result,
+            Err(e) => {
+                warn!("Tool call failed: {}", e);
+                // Continue with other tools
+                self.emit(AgentEvent::ToolCallFinished(ToolResult {
+                    id: tool_call.id,
+                    content: vec![ToolContent::Text {
+                        text: e.to_string(),
+                    }],
+                    is_error: true,
+                    truncated: false,
+                }));
+                return Ok(());
+            }
+        };
+        self.emit(AgentEvent::ToolCallFinished(tool_result.clone()));
+        // Keep the call in the history so its result can answer it
+        context.add_tool_calls("", vec![tool_call.clone()]);
+        match &self.tool_summarizer {
+            Some(summarizer) => {
+                summarizer
+                    .add_result(context, &tool_call, tool_result)
+                    .await
+            }
+            None => {
+                context.add_tool_result(tool_call.id, tool_result);
+                Ok(())
+            }
+        }
+    }
+
+    /// Run a single tool call, failing if an MCP call fails outright
+    async fn execute_tool_call(&self, tool_call: &ToolCall) -> Result<ToolResult> {
+        if !self.is_tool_allowed(&tool_call.name) {
+            warn!(
+                "Blocked call to tool '{}' outside this agent's allowance",
+                tool_call.name
+            );
+            return Ok(ToolResult {
+                id: tool_call.id.clone(),
+                content: vec![ToolContent::Text {
+                    text: format!("Tool '{}' is not permitted for this agent", tool_call.name),
+                }],
+                is_error: true,
+                truncated: false,
+            });
+        }
+
+        if tool_call.name == MemorySearchTool::NAME {
+            if let Some(memory_search) = &self.memory_search {
+                return Ok(memory_search.execute(tool_call).await);
+            }
+        }
+
+        // Try built-in tools first
+        if let Some(tool_result) = self
+            .builtin_tools
+            .execute_with_arguments(&tool_call.name, tool_call.arguments.clone())
+            .await
+        {
+            return Ok(tool_result);
+        }
+
+        // Try MCP tools
+        let mcp = self.mcp.read().await;
+        mcp.call_tool(tool_call.clone()).await
     }
 
     /// Handle memory retrieval during workflow execution
@@ -273,18 +992,29 @@ impl Agent {
         debug!("Handling memory retrieval for query: {}", query);
 
         if self.config.agent.use_memory {
-            // Generate embedding for the query
-            let embedding_response = self.llm.embed(&query).await?;
-
-            // Search memory
-            let memory = self.memory.read().await;
-            let search_results = memory
-                .search(
-                    embedding_response.embedding,
-                    self.config.memory.max_search_results,
-                    self.config.memory.similarity_threshold,
-                )
-                .await?;
+            let search_results = match self.llm.embed(&query).await {
+                Ok(embedding_response) => {
+                    self.query_memory(
+                        embedding_response.embedding,
+                        self.config.memory.max_search_results,
+                        self.config.memory.similarity_threshold,
+                    )
+                    .await?
+                }
+                Err(e) => {
+                    // Degrade to keyword search rather than failing the whole request
+                    warn!(
+                        "Embedding failed, falling back to keyword memory search: {}",
+                        e
+                    );
+                    result
+                        .context
+                        .metadata
+                        .insert("embedding_degraded".to_string(), "true".to_string());
+                    self.query_memory_keywords(&query, self.config.memory.max_search_results)
+                        .await?
+                }
+            };
 
             result.context.memories = search_results;
             result
@@ -302,19 +1032,129 @@ impl Agent {
         self.workflow.execute(result.context).await
     }
 
-    /// Generate final response using LLM
-    async fn generate_final_response(&self, mut result: WorkflowResult) -> Result<WorkflowResult> {
+    /// Generate final response using LLM, returning the reasoning behind it
+    /// separately when `capture_reasoning` is set
+    async fn generate_final_response(
+        &mut self,
+        mut result: WorkflowResult,
+    ) -> Result<(WorkflowResult, Option<String>)> {
         debug!("Generating final LLM response");
+        let messages = self.response_messages(&result.context);
 
-        // Build context for LLM
-        let mut messages = result.context.messages.clone();
+        // Generate response
+        info!(
+            "About to call LLM.generate with {} messages",
+            messages.len()
+        );
+        let generation_result = if self.event_handlers.is_empty() && !self.config.llm.stream {
+            self.llm.generate(&messages).await
+        } else {
+            self.generate_emitting_tokens(&messages).await
+        }
+        .map_err(|e| {
+            error!("LLM generate failed: {}", e);
+            e
+        })?;
+        info!("LLM generate succeeded");
+
+        self.record_usage(&generation_result);
+        let mut reasoning = None;
+        result.response = if self.config.agent.capture_reasoning {
+            let (thinking, answer) = reasoning::split_thinking(&generation_result.text);
+            let parts: Vec<String> = generation_result
+                .reasoning
+                .into_iter()
+                .chain(thinking)
+                .collect();
+            reasoning = (!parts.is_empty()).then(|| parts.join("\n\n"));
+            answer
+        } else {
+            generation_result.text
+        };
+        result.completed = true;
+
+        Ok((result, reasoning))
+    }
+
+    /// Generate from `messages` as a stream, sending each piece of text to the
+    /// event handlers as it arrives. With `capture_reasoning` set, thinking
+    /// blocks are kept out of the emitted tokens.
+    async fn generate_emitting_tokens(&self, messages: &[Message]) -> Result<GenerationResponse> {
+        let mut thinking = self
+            .config
+            .agent
+            .capture_reasoning
+            .then(ThinkingSplitter::default);
+        let emit_answer = |pieces: Vec<Piece>| {
+            for piece in pieces {
+                if let Piece::Answer(text) = piece {
+                    self.emit(AgentEvent::Token(text));
+                }
+            }
+        };
+        let mut events = self.llm.generate_stream(messages).await?;
+        let mut response = GenerationResponse {
+            text: String::new(),
+            tokens_used: None,
+            usage: None,
+            model: self.config.llm.text_model.clone(),
+            finish_reason: None,
+            system_fingerprint: None,
+            reasoning: None,
+        };
+        while let Some(event) = events.next().await {
+            match event? {
+                StreamEvent::TextDelta(text) => {
+                    match &mut thinking {
+                        Some(splitter) => emit_answer(splitter.push(&text)),
+                        None => self.emit(AgentEvent::Token(text.clone())),
+                    }
+                    response.text.push_str(&text);
+                }
+                StreamEvent::Reasoning(text) => {
+                    response
+                        .reasoning
+                        .get_or_insert_with(String::new)
+                        .push_str(&text);
+                }
+                StreamEvent::ToolCall(_) => {}
+                StreamEvent::Done {
+                    finish_reason,
+                    usage,
+                } => {
+                    response.tokens_used = usage.as_ref().map(TokenUsage::total);
+                    response.usage = usage;
+                    response.finish_reason = finish_reason;
+                }
+            }
+        }
+        if let Some(splitter) = &mut thinking {
+            emit_answer(splitter.finish());
+        }
+        Ok(response)
+    }
 
-        // Add tool results as messages
-        if !result.context.tool_results.is_empty() {
+    /// Build the messages the final response is generated from
+    fn response_messages(&self, context: &WorkflowContext) -> Vec<Message> {
+        // Build context for LLM
+        let mut messages = context.messages.clone();
+
+        // Add tool results not already answered by tool messages
+        let unanswered: Vec<&ToolResult> = context
+            .tool_results
+            .iter()
+            .filter(|(id, _)| {
+                !messages
+                    .iter()
+                    .any(|m| m.tool_call_id.as_ref() == Some(*id))
+            })
+            .map(|(_, result)| result)
+            .collect();
+        if !unanswered.is_empty() {
             let mut tool_summary = String::new();
             tool_summary.push_str("Tool results:\n");
 
-            for tool_result in result.context.tool_results.values() {
+            for tool_result in unanswered {
                 for content in &tool_result.content {
                     if let crate::mcp::ToolContent::Text { text } = content {
                         tool_summary.push_str(&format!("- {}\n", text));
@@ -326,32 +1166,68 @@ impl Agent {
         }
 
         // Add memory context
-        if !result.context.memories.is_empty() && self.config.agent.use_memory {
+        if !context.memories.is_empty() && self.config.agent.use_memory {
             let mut memory_summary = String::new();
             memory_summary.push_str("Relevant memories:\n");
 
-            for memory in &result.context.memories {
+            for memory in &context.memories {
                 memory_summary.push_str(&format!("- {}\n", memory.entry.content));
             }
 
             messages.push(assistant_message(memory_summary));
         }
 
-        // Generate response
-        info!(
-            "About to call LLM.generate with {} messages",
-            messages.len()
-        );
-        let generation_result = self.llm.generate(&messages).await.map_err(|e| {
-            error!("LLM generate failed: {}", e);
-            e
-        })?;
-        info!("LLM generate succeeded");
-
-        result.response = generation_result.text;
-        result.completed = true;
+        // Only the prompt sent for generation is wrapped; workflow steps, the
+        // conversation, thread and memory keep the input as it came out of
+        // the middleware
+        if let Some(prompt) = messages
+            .iter_mut()
+            .rev()
+            .find(|message| message.role == Role::User)
+        {
+            prompt.content = self.wrap_prompt(&prompt.content);
+        }
 
-        Ok(result)
+        messages
+    }
+
+    /// Pass `event` to every event handler
+    fn emit(&self, event: AgentEvent) {
+        for handler in &self.event_handlers {
+            handler(event.clone());
+        }
+    }
+
+    /// Accumulate the cost of a generation from its reported token usage
+    fn record_usage(&mut self, response: &GenerationResponse) {
+        if let Some(usage) = &response.usage {
+            crate::metrics::record_tokens(&response.model, usage);
+            if let Some(cost) = self.pricing.estimate_cost(usage, &response.model) {
+                self.total_cost += cost;
+                debug!(
+                    "Generation cost ${:.6} ({} tokens), total ${:.6}",
+                    cost,
+                    usage.total(),
+                    self.total_cost
+                );
+            }
+        }
+    }
+
+    /// Load the agent's text model ahead of its first request
+    pub async fn preload_model(&self) -> Result<()> {
+        self.llm.preload_model().await
+    }
+
+    /// What `max_response_chars` did to the last processed response; `None`
+    /// when no limit is configured or nothing has been processed yet
+    pub fn last_response_limit(&self) -> Option<&ResponseLimitReport> {
+        self.last_response_limit.as_ref()
+    }
+
+    /// Total cost (USD) accumulated from actual token usage so far
+    pub fn total_cost(&self) -> f64 {
+        self.total_cost
     }
 
     /// Get list of all available tools
@@ -361,6 +1237,10 @@ impl Agent {
         // Add built-in tools
         tools.extend(self.builtin_tools.list_tools());
 
+        if self.memory_search.is_some() {
+            tools.push(MemorySearchTool::NAME.to_string());
+        }
+
         // Add MCP tools
         let mcp = self.mcp.read().await;
         let mcp_tools = mcp.list_tools();
@@ -368,6 +1248,7 @@ impl Agent {
             tools.push(tool.name.clone());
         }
 
+        tools.retain(|tool| self.is_tool_allowed(tool));
         tools
     }
 
@@ -383,17 +1264,28 @@ impl Agent {
 
         let conversation_text = format!("User: {}\nAssistant: {}", user_input, response);
 
-        // Generate embedding
-        let embedding_response = self.llm.embed(&conversation_text).await?;
+        // Skip storing if the embedding service is unavailable; a placeholder
+        // vector would match unrelated queries in similarity search
+        let embedding = match self.llm.embed(&conversation_text).await {
+            Ok(embedding_response) => embedding_response.embedding,
+            Err(e) => {
+                warn!("Failed to embed conversation, not storing it: {}", e);
+                return Ok(());
+            }
+        };
 
         // Store in memory
         let mut memory = self.memory.write().await;
         let mut metadata = HashMap::new();
         metadata.insert("type".to_string(), "conversation".to_string());
+        metadata.insert(
+            MEMORY_NAMESPACE_KEY.to_string(),
+            self.config.agent.name.clone(),
+        );
         metadata.insert("timestamp".to_string(), chrono::Utc::now().to_rfc3339());
 
         memory
-            .store(conversation_text, embedding_response.embedding, metadata)
+            .insert(conversation_text, embedding, metadata)
             .await?;
 
         debug!("Conversation stored in memory");
@@ -431,6 +1323,7 @@ impl Agent {
             total_memories: 0,
             embedding_dimension: self.config.memory.embedding_dimension,
             store_size_bytes: None,
+            evicted_entries: 0,
         });
 
         let mcp = self.mcp.read().await;
@@ -441,6 +1334,8 @@ impl Agent {
             memory_stats,
             mcp_stats,
             builtin_tools_count: self.builtin_tools.list_tools().len(),
+            embedding_cache_stats: self.embedding_cache.as_ref().map(|cache| cache.stats()),
+            embedding_limit_stats: self.embedding_limit.stats(),
         }
     }
 
@@ -567,7 +1462,34 @@ impl Agent {
         &self.config
     }
 
-    /// Store knowledge entry directly in agent's memory
+    /// The agent's embedding cache, if `llm.embedding_cache` is enabled
+    pub fn embedding_cache(&self) -> Option<&Arc<EmbeddingCache>> {
+        self.embedding_cache.as_ref()
+    }
+
+    /// Ingest `sources` into the agent's memory with
+    /// [`AdaptiveKnowledgeManager::ingest_all`], chunking as
+    /// `learning.external_sources` sets. Chunks are embedded through the
+    /// agent's client, so ingestion shares its embedding cache with memory.
+    ///
+    /// [`AdaptiveKnowledgeManager::ingest_all`]: crate::knowledge::AdaptiveKnowledgeManager::ingest_all
+    pub async fn ingest_knowledge<P>(
+        &self,
+        sources: &[P],
+        concurrency: usize,
+    ) -> Result<crate::knowledge::IngestionReport>
+    where
+        P: AsRef<std::path::Path> + Sync,
+    {
+        crate::knowledge::AdaptiveKnowledgeManager::new(self.config.learning.clone())
+            .with_llm(self.llm.clone())
+            .with_store(self.memory.clone())
+            .ingest_all(sources, concurrency)
+            .await
+    }
+
+    /// Store knowledge entry directly in agent's memory, or in the shared
+    /// memory when `memory.shared_writes` is set and the agent has one.
     /// Used by organizational learning systems to persist learnings
     pub async fn store_knowledge(
         &mut self,
@@ -588,19 +1510,67 @@ impl Agent {
             entry.embedding
         };
 
+        if self.config.memory.shared_writes {
+            if let Some(shared) = &self.shared_memory {
+                return shared
+                    .store(
+                        &self.config.agent.name,
+                        entry.content,
+                        embedding,
+                        entry.metadata,
+                    )
+                    .await;
+            }
+        }
+
+        let mut metadata = entry.metadata;
+        metadata
+            .entry(MEMORY_NAMESPACE_KEY.to_string())
+            .or_insert_with(|| self.config.agent.name.clone());
+
         let mut memory = self.memory.write().await;
-        memory.store(entry.content, embedding, entry.metadata).await
+        memory.store(entry.content, embedding, metadata).await
     }
 
-    /// Query agent's memory for similar entries
+    /// Query agent's memory, and any shared memory, for similar entries
     pub async fn query_memory(
         &self,
         query_embedding: Vec<f32>,
         limit: usize,
         threshold: f32,
     ) -> Result<Vec<crate::memory::SearchResult>> {
-        let memory = self.memory.read().await;
-        memory.search(query_embedding, limit, threshold).await
+        let own = {
+            let memory = self.memory.read().await;
+            memory
+                .search(query_embedding.clone(), limit, threshold)
+                .await?
+        };
+        match &self.shared_memory {
+            Some(shared) => {
+                let found = shared.search(query_embedding, limit, threshold).await?;
+                Ok(merge_results(own, found, limit))
+            }
+            None => Ok(own),
+        }
+    }
+
+    /// Keyword search over the agent's memory and any shared memory
+    async fn query_memory_keywords(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<crate::memory::SearchResult>> {
+        let own = {
+            let memory = self.memory.read().await;
+            memory.search_keywords(query, limit).await?
+        };
+        match &self.shared_memory {
+            Some(shared) => {
+                let found = shared.search_keywords(query, limit).await?;
+                Ok(merge_results(own, found, limit))
+            }
+            None => Ok(own),
+        }
     }
 
     /// List all memories (for organizational knowledge queries)
@@ -613,6 +1583,210 @@ impl Agent {
     }
 }
 
+/// Something a workflow result waits on before the turn can go on
+enum PendingAction {
+    ToolCall(ToolCall),
+    MemoryQuery(String),
+}
+
+/// Take the next action `result` waits on: its tool calls one at a time,
+/// then its memory query
+fn take_pending_action(result: &mut WorkflowResult) -> Option<PendingAction> {
+    if let Some(calls) = result.pending_tool_calls.as_mut() {
+        if !calls.is_empty() {
+            return Some(PendingAction::ToolCall(calls.remove(0)));
+        }
+        result.pending_tool_calls = None;
+    }
+    result
+        .pending_memory_query
+        .take()
+        .map(PendingAction::MemoryQuery)
+}
+
+/// Stage of a turn being streamed by [`Agent::process_stream`]
+enum StreamPhase {
+    /// The workflow has not run yet
+    Start,
+    /// The workflow ran and may have pending tool calls or memory queries
+    Pending(WorkflowResult),
+    /// An action to perform; tool calls are announced before they run
+    Acting(WorkflowResult, PendingAction),
+    /// Streaming the final generation
+    Generating(BoxStream<'static, Result<StreamEvent>>),
+    /// The response is complete and the turn needs recording
+    Finish,
+    /// The stream has ended
+    Done,
+}
+
+/// State threaded through the stream returned by [`Agent::process_stream`]
+struct ResponseStream<'a> {
+    agent: &'a mut Agent,
+    /// Ends the turn's events, also when the stream is dropped early
+    turn: TurnEvents,
+    user_input: String,
+    response: String,
+    /// Tokens used by the final generation, when reported
+    usage: Option<TokenUsage>,
+    phase: StreamPhase,
+    /// Shared by every step of the turn
+    retry_budget: Option<RetryBudget>,
+    /// Separates inline reasoning from the answer when `capture_reasoning`
+    /// is set
+    thinking: Option<ThinkingSplitter>,
+    /// Events split from one chunk, waiting to be returned
+    queued: VecDeque<StreamEvent>,
+    /// Context of the finished workflow, for the guardrail
+    context: Option<WorkflowContext>,
+}
+
+impl ResponseStream<'_> {
+    /// Advance the turn until it produces an event, or `None` once finished.
+    /// An error ends the stream after it is returned.
+    async fn next_event(&mut self) -> Option<Result<StreamEvent>> {
+        loop {
+            if let Some(event) = self.queued.pop_front() {
+                return Some(Ok(event));
+            }
+            match std::mem::replace(&mut self.phase, StreamPhase::Done) {
+                StreamPhase::Start => {
+                    let context = self.agent.start_turn(&self.user_input).await;
+                    match self.agent.workflow.execute(context).await {
+                        Ok(result) => self.phase = StreamPhase::Pending(result),
+                        Err(e) => return Some(Err(e)),
+                    }
+                }
+                StreamPhase::Pending(mut result) => {
+                    if let Some(action) = take_pending_action(&mut result) {
+                        let announce = match &action {
+                            PendingAction::ToolCall(tool_call) => Some(tool_call.clone()),
+                            PendingAction::MemoryQuery(_) => None,
+                        };
+                        self.phase = StreamPhase::Acting(result, action);
+                        if let Some(tool_call) = announce {
+                            return Some(Ok(StreamEvent::ToolCall(tool_call)));
+                        }
+                        continue;
+                    }
+                    if let Some(reason) = result.suspended.take() {
+                        self.agent.emit(AgentEvent::Suspended(reason));
+                    }
+                    if result.completed && !result.generate_with_llm {
+                        self.response = result.response;
+                        self.context = Some(result.context);
+                        self.phase = StreamPhase::Finish;
+                        return Some(Ok(StreamEvent::TextDelta(self.response.clone())));
+                    } else {
+                        let messages = self.agent.response_messages(&result.context);
+                        self.context = Some(result.context);
+                        match self.agent.llm.generate_stream(&messages).await {
+                            Ok(events) => self.phase = StreamPhase::Generating(events),
+                            Err(e) => return Some(Err(e)),
+                        }
+                    }
+                }
+                StreamPhase::Acting(result, action) => {
+                    match self.agent.perform_action(result, action).await {
+                        Ok(result) => self.phase = StreamPhase::Pending(result),
+                        Err(e) => return Some(Err(e)),
+                    }
+                }
+                StreamPhase::Generating(mut events) => match events.next().await {
+                    Some(Ok(StreamEvent::TextDelta(text))) => {
+                        self.phase = StreamPhase::Generating(events);
+                        match &mut self.thinking {
+                            Some(splitter) => {
+                                let pieces = splitter.push(&text);
+                                self.queue(pieces);
+                            }
+                            None => {
+                                self.response.push_str(&text);
+                                return Some(Ok(StreamEvent::TextDelta(text)));
+                            }
+                        }
+                    }
+                    // Reasoning is only passed on when it is being captured,
+                    // as without that it is dropped from responses too
+                    Some(Ok(StreamEvent::Reasoning(text))) => {
+                        self.phase = StreamPhase::Generating(events);
+                        if self.thinking.is_some() {
+                            return Some(Ok(StreamEvent::Reasoning(text)));
+                        }
+                    }
+                    Some(Ok(StreamEvent::ToolCall(tool_call))) => {
+                        self.phase = StreamPhase::Generating(events);
+                        return Some(Ok(StreamEvent::ToolCall(tool_call)));
+                    }
+                    // The turn is only done once it has been recorded
+                    Some(Ok(StreamEvent::Done { usage, .. })) => {
+                        self.usage = usage;
+                        self.end_generation();
+                    }
+                    None => self.end_generation(),
+                    Some(Err(e)) => {
+                        error!("LLM stream failed: {}", e);
+                        return Some(Err(e));
+                    }
+                },
+                StreamPhase::Finish => {
+                    if let Some(mut context) = self.context.take() {
+                        let response = std::mem::take(&mut self.response);
+                        match self.agent.guard_response(&mut context, response).await {
+                            Ok(response) => self.response = response,
+                            Err(e) => return Some(Err(e)),
+                        }
+                    }
+                    let finished = self
+                        .agent
+                        .finish_turn(&self.user_input, &self.response)
+                        .await;
+                    if let Err(e) = finished {
+                        return Some(Err(e));
+                    }
+                    debug!("Streamed response with {} characters", self.response.len());
+                    return Some(Ok(StreamEvent::Done {
+                        finish_reason: Some("stop".to_string()),
+                        usage: self.usage.take(),
+                    }));
+                }
+                StreamPhase::Done => return None,
+            }
+        }
+    }
+
+    /// Queue the answer and reasoning split from the generated text
+    fn queue(&mut self, pieces: Vec<Piece>) {
+        for piece in pieces {
+            match piece {
+                Piece::Answer(text) => {
+                    self.response.push_str(&text);
+                    self.queued.push_back(StreamEvent::TextDelta(text));
+                }
+                Piece::Thought(text) => self.queued.push_back(StreamEvent::Reasoning(text)),
+            }
+        }
+    }
+
+    /// Release any text held back by the splitter and move on to recording
+    /// the turn
+    fn end_generation(&mut self) {
+        if let Some(pieces) = self.thinking.as_mut().map(ThinkingSplitter::finish) {
+            self.queue(pieces);
+        }
+        self.phase = StreamPhase::Finish;
+    }
+}
+
+/// Result of [`Agent::process_turn`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct TurnOutput {
+    pub response: String,
+    /// Reasoning the model produced for the response, kept out of it when
+    /// `capture_reasoning` is set
+    pub reasoning: Option<String>,
+}
+
 /// Agent statistics
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct AgentStats {
@@ -620,17 +1794,27 @@ pub struct AgentStats {
     pub memory_stats: crate::memory::MemoryStats,
     pub mcp_stats: crate::mcp::McpStats,
     pub builtin_tools_count: usize,
+    /// Embedding cache hits and misses, when the cache is enabled
+    pub embedding_cache_stats: Option<EmbeddingCacheStats>,
+    /// Embedding inputs embedded as they were, chunked, or rejected
+    pub embedding_limit_stats: EmbeddingLimitStats,
 }
 
 /// Builder pattern for creating an Agent
 pub struct AgentBuilder {
     config: AgentConfig,
+    middleware: Vec<Arc<dyn AgentMiddleware>>,
+    response_processors: ResponseProcessorChain,
+    event_handlers: Vec<AgentEventHandler>,
 }
 
 impl AgentBuilder {
     pub fn new() -> Self {
         Self {
             config: AgentConfig::default(),
+            middleware: Vec::new(),
+            response_processors: ResponseProcessorChain::new(),
+            event_handlers: Vec::new(),
         }
     }
 
@@ -654,8 +1838,32 @@ impl AgentBuilder {
         self
     }
 
+    /// Add middleware; `before_process` hooks run in the order they are added
+    pub fn with_middleware(mut self, middleware: impl AgentMiddleware + 'static) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Post-process final responses; processors run in the order they are
+    /// added
+    pub fn with_response_processor(mut self, processor: impl ResponseProcessor + 'static) -> Self {
+        self.response_processors = self.response_processors.with(processor);
+        self
+    }
+
+    /// Call `handler` with each event of the agent's turns; handlers run in
+    /// the order they are added
+    pub fn on_event(mut self, handler: impl Fn(AgentEvent) + Send + Sync + 'static) -> Self {
+        self.event_handlers.push(Arc::new(handler));
+        self
+    }
+
     pub async fn build(self) -> Result<Agent> {
-        Agent::new(self.config).await
+        let mut agent = Agent::new(self.config).await?;
+        agent.middleware = self.middleware;
+        agent.response_processors = self.response_processors;
+        agent.event_handlers = self.event_handlers;
+        Ok(agent)
     }
 }
 
@@ -668,6 +1876,8 @@ impl Default for AgentBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::llm::mock::{response, text_stream, MockLlm};
+    use crate::workflow::ApprovalPolicy;
 
     async fn create_test_agent() -> Agent {
         // Use in-memory SQLite database for tests
@@ -726,4 +1936,778 @@ mod tests {
         assert!(!tools.is_empty());
         assert!(tools.contains(&"system_info".to_string()));
     }
+
+    #[tokio::test]
+    async fn test_restricted_agent_cannot_call_other_tools() {
+        use crate::organization::OrganizationRole;
+
+        let mut agent = create_test_agent().await;
+        agent.restrict_tools(OrganizationRole::CustomerSuccessManager.allowed_tools());
+
+        let tools = agent.get_available_tools().await;
+        assert!(tools.contains(&"datetime_info".to_string()));
+        assert!(!tools.contains(&"system_info".to_string()));
+
+        let blocked = agent
+            .execute_tool_call(&crate::tools::create_system_info_tool())
+            .await
+            .unwrap();
+        assert!(blocked.is_error);
+
+        let allowed = agent
+            .execute_tool_call(&crate::tools::create_datetime_tool())
+            .await
+            .unwrap();
+        assert!(!allowed.is_error);
+    }
+
+    #[tokio::test]
+    async fn test_total_cost_accumulates_usage() {
+        let mut agent = create_test_agent().await;
+        assert_eq!(agent.total_cost(), 0.0);
+
+        let response = GenerationResponse {
+            text: "ok".to_string(),
+            tokens_used: Some(1500),
+            usage: Some(crate::llm::pricing::TokenUsage::new(1000, 500)),
+            model: "gpt-4o-mini".to_string(),
+            finish_reason: None,
+            system_fingerprint: None,
+            reasoning: None,
+        };
+        agent.record_usage(&response);
+        agent.record_usage(&response);
+
+        // 2 * (1K * $0.00015 + 0.5K * $0.0006)
+        assert!((agent.total_cost() - 0.0009).abs() < 1e-9);
+    }
+
+    /// Generates normally but fails every embedding request, like an unreachable embedding service
+    fn no_embedding_llm() -> Arc<MockLlm> {
+        Arc::new(
+            MockLlm::new().with_reply("I'm not sure.").with_embed(|_| {
+                Err(crate::error::LlmError::EmbeddingFailed("EOF".to_string()).into())
+            }),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_process_degrades_to_keyword_retrieval_without_embeddings() {
+        let mut agent = create_test_agent().await;
+        agent.llm = no_embedding_llm();
+
+        let dimension = agent.config.memory.embedding_dimension;
+        agent
+            .memory
+            .write()
+            .await
+            .store(
+                "User: my favourite drink is green tea".to_string(),
+                vec![0.0; dimension],
+                HashMap::new(),
+            )
+            .await
+            .unwrap();
+
+        let response = agent
+            .process("Do you remember my favourite drink?")
+            .await
+            .unwrap();
+        assert!(response.contains("green tea"));
+
+        // The exchange is not stored without an embedding
+        let stats = agent.memory.read().await.stats().await.unwrap();
+        assert_eq!(stats.total_memories, 1);
+    }
+
+    #[tokio::test]
+    async fn test_memory_retrieval_marks_embedding_degraded() {
+        let mut agent = create_test_agent().await;
+        agent.llm = no_embedding_llm();
+
+        let result = WorkflowResult {
+            response: String::new(),
+            context: WorkflowContext::new(5),
+            completed: false,
+            steps_executed: 0,
+            pending_tool_calls: None,
+            pending_memory_query: None,
+            decision_log: Vec::new(),
+            error: None,
+            suspended: None,
+            generate_with_llm: false,
+        };
+        let result = agent
+            .handle_memory_retrieval(result, "anything".to_string())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            result.context.metadata.get("embedding_degraded"),
+            Some(&"true".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prompt_prefix_and_suffix_wrap_outgoing_prompt() {
+        let mut config = AgentConfig::default();
+        config.memory.database_url = Some("sqlite::memory:".to_string());
+        config.agent.use_memory = false;
+        config.agent.use_tools = false;
+        config.agent.prompt_prefix = Some("Answer safely.".to_string());
+        config.agent.prompt_suffix = Some("Reply in one word.".to_string());
+        let mut agent = Agent::new(config).await.unwrap();
+        let llm = Arc::new(MockLlm::new().with_reply("4"));
+        agent.llm = llm.clone();
+
+        agent.process("What is 2+2?").await.unwrap();
+
+        let sent = llm.last_request();
+        assert_eq!(sent[0].role, Role::System);
+        let prompt = sent.iter().rev().find(|m| m.role == Role::User).unwrap();
+        assert_eq!(
+            prompt.content,
+            "Answer safely.\n\nWhat is 2+2?\n\nReply in one word."
+        );
+        // The conversation keeps the prompt as the user wrote it
+        assert_eq!(agent.get_conversation()[1].content, "What is 2+2?");
+    }
+
+    /// Records the last user message each time the workflow runs it
+    struct PromptRecorder(Arc<std::sync::Mutex<Vec<String>>>);
+
+    #[async_trait::async_trait]
+    impl crate::workflow::WorkflowStep for PromptRecorder {
+        async fn execute(
+            &self,
+            context: &mut WorkflowContext,
+        ) -> Result<crate::workflow::WorkflowDecision> {
+            if let Some(message) = context.last_user_message() {
+                self.0.lock().unwrap().push(message.content.clone());
+            }
+            Ok(crate::workflow::WorkflowDecision::Continue)
+        }
+
+        fn name(&self) -> &str {
+            "prompt_recorder"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_workflow_steps_see_the_unwrapped_prompt() {
+        let mut config = AgentConfig::default();
+        config.memory.database_url = Some("sqlite::memory:".to_string());
+        config.agent.use_memory = false;
+        config.agent.prompt_prefix = Some("Answer safely.".to_string());
+        let mut agent = Agent::new(config).await.unwrap();
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        agent.workflow = WorkflowEngine::new()
+            .add_step(Box::new(PromptRecorder(seen.clone())))
+            .add_step(Box::new(crate::workflow::ResponseGenerationStep));
+        let llm = Arc::new(MockLlm::new().with_reply("4"));
+        agent.llm = llm.clone();
+
+        agent.process("What is 2+2?").await.unwrap();
+
+        assert_eq!(*seen.lock().unwrap(), vec!["What is 2+2?".to_string()]);
+        let prompt = llm.last_request();
+        let prompt = prompt.iter().rev().find(|m| m.role == Role::User).unwrap();
+        assert_eq!(prompt.content, "Answer safely.\n\nWhat is 2+2?");
+    }
+
+    #[tokio::test]
+    async fn test_current_datetime_is_injected_into_system_context() {
+        let system_context = |inject_datetime: bool, timezone: Option<&str>| {
+            let timezone = timezone.map(str::to_string);
+            async move {
+                let mut config = AgentConfig::default();
+                config.memory.database_url = Some("sqlite::memory:".to_string());
+                config.agent.use_memory = false;
+                config.agent.use_tools = false;
+                config.agent.inject_datetime = inject_datetime;
+                config.agent.timezone = timezone;
+                let mut agent = Agent::new(config).await.unwrap();
+                let llm = Arc::new(MockLlm::new().with_reply("4"));
+                agent.llm = llm.clone();
+
+                agent.process("What day is it?").await.unwrap();
+                let sent = llm.last_request();
+                assert_eq!(sent[0].role, Role::System);
+                (
+                    sent[0].content.clone(),
+                    agent.get_conversation()[0].content.clone(),
+                )
+            }
+        };
+
+        let (context, stored) = system_context(true, Some("Asia/Tokyo")).await;
+        let (first, rest) = context.split_once("\n\n").unwrap();
+        let timestamp = first.strip_prefix("Current date and time: ").unwrap();
+        let now = chrono::DateTime::parse_from_rfc3339(timestamp).unwrap();
+        assert_eq!(now.offset().local_minus_utc(), 9 * 3600);
+        assert!((chrono::Utc::now() - now.with_timezone(&chrono::Utc)).num_seconds() < 60);
+        // Only the outgoing context carries the timestamp
+        assert_eq!(rest, stored);
+        assert!(!stored.contains("Current date and time"));
+
+        let (context, _) = system_context(false, None).await;
+        assert!(!context.contains("Current date and time"));
+    }
+
+    /// Answers with reasoning in both the reasoning field and the text
+    fn reasoning_llm() -> Arc<MockLlm> {
+        Arc::new(MockLlm::new().with_generate(|_| {
+            Ok(GenerationResponse {
+                reasoning: Some("The user wants a sum.".to_string()),
+                ..response("<think>Double-check: 2 + 2 = 4.</think>\nThe answer is 4.")
+            })
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_reasoning_is_captured_separately_from_response() {
+        let agent = |capture_reasoning: bool| async move {
+            let mut config = AgentConfig::default();
+            config.memory.database_url = Some("sqlite::memory:".to_string());
+            config.agent.use_memory = false;
+            config.agent.use_tools = false;
+            config.agent.capture_reasoning = capture_reasoning;
+            let mut agent = Agent::new(config).await.unwrap();
+            agent.llm = reasoning_llm();
+            agent
+        };
+
+        let mut capturing = agent(true).await;
+        let output = capturing.process_turn("What is 2+2?").await.unwrap();
+        assert_eq!(output.response, "The answer is 4.");
+        assert_eq!(
+            output.reasoning.as_deref(),
+            Some("The user wants a sum.\n\nDouble-check: 2 + 2 = 4.")
+        );
+        assert_eq!(capturing.get_conversation()[2].content, "The answer is 4.");
+
+        let mut plain = agent(false).await;
+        let output = plain.process_turn("What is 2+2?").await.unwrap();
+        assert!(output.response.contains("<think>"));
+        assert_eq!(output.reasoning, None);
+    }
+
+    #[tokio::test]
+    async fn test_streamed_reasoning_is_kept_out_of_the_response() {
+        let mut config = AgentConfig::default();
+        config.memory.database_url = Some("sqlite::memory:".to_string());
+        config.agent.use_memory = false;
+        config.agent.use_tools = false;
+        config.agent.capture_reasoning = true;
+        let mut agent = Agent::new(config).await.unwrap();
+        agent.llm = Arc::new(MockLlm::new().with_stream(|_| {
+            let mut events = vec![Ok(StreamEvent::Reasoning("The user wants a sum.".into()))];
+            events.extend(
+                ["<thi", "nk>Double-check</think>\nThe ans", "wer is 4."]
+                    .map(|text| Ok(StreamEvent::TextDelta(text.into()))),
+            );
+            stream::iter(events).boxed()
+        }));
+
+        let mut stream = agent.process_stream("What is 2+2?").await.unwrap();
+        let (mut text, mut reasoning) = (String::new(), String::new());
+        while let Some(event) = stream.next().await {
+            match event.unwrap() {
+                StreamEvent::TextDelta(delta) => text.push_str(&delta),
+                StreamEvent::Reasoning(delta) => reasoning.push_str(&delta),
+                _ => {}
+            }
+        }
+        drop(stream);
+        assert_eq!(text, "The answer is 4.");
+        assert_eq!(reasoning, "The user wants a sum.Double-check");
+        assert_eq!(
+            agent.get_conversation().last().unwrap().content,
+            "The answer is 4."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_new_rejects_an_embedding_model_as_text_model() {
+        let mut config = AgentConfig::default();
+        config.memory.database_url = Some("sqlite::memory:".to_string());
+        config.llm.text_model = "nomic-embed-text".to_string();
+        assert!(matches!(
+            Agent::new(config).await,
+            Err(AgentError::Config(message)) if message.contains("text_model")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_guardrail_checks_the_generated_response() {
+        let mut config = AgentConfig::default();
+        config.memory.database_url = Some("sqlite::memory:".to_string());
+        config.agent.use_memory = false;
+        config.agent.use_tools = false;
+        let mut agent = Agent::new(config).await.unwrap();
+        agent.llm = Arc::new(MockLlm::new().with_reply("Log in with password: hunter2"));
+        agent.set_guardrail(
+            crate::workflow::GuardrailStep::new(crate::workflow::GuardrailAction::Block)
+                .with_blocked_pattern(r"(?i)password:\s*\S+")
+                .unwrap()
+                .with_blocked_message("Response withheld."),
+        );
+
+        let response = agent.process("How do I log in?").await.unwrap();
+        assert_eq!(response, "Response withheld.");
+        assert_eq!(
+            agent.get_conversation().last().unwrap().content,
+            "Response withheld."
+        );
+
+        agent.llm = Arc::new(MockLlm::new().with_reply("Use the login page."));
+        let response = agent.process("And then?").await.unwrap();
+        assert_eq!(response, "Use the login page.");
+
+        // A streamed response is checked before the turn is recorded
+        agent.llm = Arc::new(MockLlm::new().with_reply("The password: hunter2"));
+        let mut stream = agent.process_stream("Remind me?").await.unwrap();
+        while let Some(event) = stream.next().await {
+            event.unwrap();
+        }
+        drop(stream);
+        assert_eq!(
+            agent.get_conversation().last().unwrap().content,
+            "Response withheld."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ingested_knowledge_shares_the_embedding_cache() {
+        let mut config = AgentConfig::default();
+        config.memory.database_url = Some("sqlite::memory:".to_string());
+        config.memory.embedding_dimension = 4;
+        config.agent.use_memory = false;
+        config.agent.use_tools = false;
+        config.llm.embedding_cache.enabled = true;
+        let mut agent = Agent::new(config).await.unwrap();
+        let embedder = Arc::new(MockLlm::new().with_embed(|_| Ok(vec![1.0, 0.0, 0.0, 0.0])));
+        let cache = agent.embedding_cache().unwrap().clone();
+        agent.llm = Arc::new(CachedEmbeddingClient::new(
+            embedder.clone(),
+            cache.clone(),
+            &agent.config.llm.embedding_model,
+        ));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("torque.txt");
+        std::fs::write(&path, "Torque limits for the hip actuator.").unwrap();
+        let report = agent.ingest_knowledge(&[path], 1).await.unwrap();
+        assert_eq!(report.chunks_stored(), 1);
+
+        // Memory embeds through the same client, so the vector is reused
+        agent
+            .llm
+            .embed("Torque limits for the hip actuator.")
+            .await
+            .unwrap();
+        assert_eq!(embedder.embedded().len(), 1);
+        assert_eq!(cache.stats().hits, 1);
+    }
+
+    /// Records its hook invocations and optionally rejects input
+    struct RecordingMiddleware {
+        name: &'static str,
+        log: Arc<std::sync::Mutex<Vec<String>>>,
+        reject: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl AgentMiddleware for RecordingMiddleware {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        async fn before_process(&self, input: String) -> Result<String> {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("{}:before", self.name));
+            if self.reject {
+                return Err(crate::error::AgentError::Config(
+                    "input rejected".to_string(),
+                ));
+            }
+            Ok(input)
+        }
+
+        async fn after_process(&self, output: String) -> Result<String> {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("{}:after", self.name));
+            Ok(output)
+        }
+    }
+
+    async fn build_agent_with_middleware(
+        log: &Arc<std::sync::Mutex<Vec<String>>>,
+        reject_first: bool,
+    ) -> (Agent, Arc<MockLlm>) {
+        let mut config = AgentConfig::default();
+        config.memory.database_url = Some("sqlite::memory:".to_string());
+
+        let mut agent = AgentBuilder::new()
+            .with_config(config)
+            .with_middleware(RecordingMiddleware {
+                name: "first",
+                log: log.clone(),
+                reject: reject_first,
+            })
+            .with_middleware(RecordingMiddleware {
+                name: "second",
+                log: log.clone(),
+                reject: false,
+            })
+            .build()
+            .await
+            .unwrap();
+
+        let llm = Arc::new(MockLlm::new().with_reply("Hello!"));
+        agent.llm = llm.clone();
+        (agent, llm)
+    }
+
+    #[tokio::test]
+    async fn test_middleware_invocation_order() {
+        let log = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let (mut agent, llm) = build_agent_with_middleware(&log, false).await;
+
+        agent.process("Hello there").await.unwrap();
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![
+                "first:before",
+                "second:before",
+                "second:after",
+                "first:after"
+            ]
+        );
+        assert_eq!(llm.generations(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_rejecting_middleware_short_circuits() {
+        let log = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let (mut agent, llm) = build_agent_with_middleware(&log, true).await;
+
+        assert!(agent.process("Hello there").await.is_err());
+
+        assert_eq!(*log.lock().unwrap(), vec!["first:before"]);
+        assert_eq!(llm.generations(), 0);
+        // The rejected input never reaches the conversation
+        assert_eq!(agent.conversation.len(), 1);
+    }
+
+    /// Numbers its replies
+    fn history_llm() -> Arc<MockLlm> {
+        let replies = std::sync::atomic::AtomicUsize::new(0);
+        Arc::new(MockLlm::new().with_generate(move |_| {
+            let reply = replies.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+            Ok(response(format!("Reply {}", reply)))
+        }))
+    }
+
+    async fn create_thread_agent() -> (Agent, Arc<MockLlm>) {
+        let mut config = AgentConfig::default();
+        config.memory.database_url = Some("sqlite::memory:".to_string());
+        config.agent.use_memory = false;
+        config.agent.use_tools = false;
+        let mut agent = Agent::new(config).await.unwrap();
+        let llm = history_llm();
+        agent.llm = llm.clone();
+        (agent, llm)
+    }
+
+    #[tokio::test]
+    async fn test_resumed_thread_restores_prior_turns() {
+        use crate::unified_storage::InMemoryUnifiedStorage;
+
+        let storage: Arc<dyn UnifiedStorage> = Arc::new(InMemoryUnifiedStorage::new());
+        let (mut agent, _) = create_thread_agent().await;
+        agent
+            .bind_thread(storage.clone(), "support-42")
+            .await
+            .unwrap();
+        agent.process("My order is #1187").await.unwrap();
+        agent.process("It arrived damaged").await.unwrap();
+
+        let stored = storage
+            .get_memory_messages("support-42", None)
+            .await
+            .unwrap();
+        assert_eq!(stored.len(), 4);
+        assert_eq!(stored[3].content, "Reply 2");
+        assert_eq!(
+            stored[3].parent_message_id.as_deref(),
+            Some(stored[2].message_id.as_str())
+        );
+
+        let (mut resumed, llm) = create_thread_agent().await;
+        assert!(resumed
+            .resume_thread(storage.clone(), "no-such-thread")
+            .await
+            .is_err());
+        let loaded = resumed
+            .resume_thread(storage.clone(), "support-42")
+            .await
+            .unwrap();
+        assert_eq!(loaded, 4);
+        assert_eq!(resumed.thread_id(), Some("support-42"));
+
+        let contents: Vec<&str> = resumed
+            .get_conversation()
+            .iter()
+            .map(|m| m.content.as_str())
+            .collect();
+        assert_eq!(
+            contents[1..],
+            [
+                "My order is #1187",
+                "Reply 1",
+                "It arrived damaged",
+                "Reply 2"
+            ]
+        );
+
+        // The next turn sees the earlier ones and is appended to the thread
+        resumed.process("Can I get a refund?").await.unwrap();
+        let seen: Vec<String> = llm.requests()[0]
+            .iter()
+            .map(|m| m.content.clone())
+            .collect();
+        assert!(seen.iter().any(|c| c == "My order is #1187"));
+        assert!(seen.iter().any(|c| c == "Reply 2"));
+        assert_eq!(
+            storage
+                .get_memory_messages("support-42", None)
+                .await
+                .unwrap()
+                .len(),
+            6
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retry_budget_caps_agent_retries() {
+        let mut config = AgentConfig::default();
+        config.memory.database_url = Some("sqlite::memory:".to_string());
+        config.agent.use_memory = false;
+        config.llm.max_retries = 3;
+        config.llm.retry_delay_ms = 0;
+        config.agent.retry_budget = Some(1);
+        let mut agent = Agent::new(config).await.unwrap();
+        let overloaded =
+            r#"{"type": "error", "error": {"type": "overloaded_error", "message": "Overloaded"}}"#;
+        let error = crate::llm::providers::base::parse_provider_error(529, overloaded);
+        let llm = Arc::new(
+            MockLlm::new()
+                .with_generate(move |_| Err(crate::error::LlmError::from(error.clone()).into())),
+        );
+        agent.llm = ProviderManager::wrap(llm.clone(), &agent.config.llm);
+
+        // One attempt and the single retry the budget allows
+        assert!(agent.process("Hello there").await.is_err());
+        assert_eq!(llm.generations(), 2);
+
+        // Without a budget the call retries as configured
+        agent.config.agent.retry_budget = None;
+        assert!(agent.process("Hello there").await.is_err());
+        assert_eq!(llm.generations(), 6);
+    }
+
+    /// Streams a fixed reply in several deltas
+    fn streaming_llm() -> Arc<MockLlm> {
+        Arc::new(
+            MockLlm::new().with_stream(|_| text_stream(&["The ", "system ", "is ", "healthy."])),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_process_to_writer_streams_tokens_in_order() {
+        let mut config = AgentConfig::default();
+        config.memory.database_url = Some("sqlite::memory:".to_string());
+        config.agent.use_memory = false;
+        let mut agent = Agent::new(config).await.unwrap();
+        agent.llm = streaming_llm();
+
+        let mut writer = Vec::new();
+        let response = agent
+            .process_to_writer("How is everything?", &mut writer)
+            .await
+            .unwrap();
+        assert_eq!(response, "The system is healthy.");
+        assert_eq!(writer, b"The system is healthy.");
+        let last = agent.get_conversation().last().unwrap();
+        assert_eq!(last.content, "The system is healthy.");
+
+        // Tool calls are announced before their results are written
+        let mut writer = Vec::new();
+        let response = agent
+            .process_to_writer("Show me the system info", &mut writer)
+            .await
+            .unwrap();
+        let written = String::from_utf8(writer).unwrap();
+        assert_eq!(
+            written,
+            format!("[calling tool: system_info]\n{}", response)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_events_follow_the_turn() {
+        let mut config = AgentConfig::default();
+        config.memory.database_url = Some("sqlite::memory:".to_string());
+        config.agent.use_memory = false;
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorder = events.clone();
+        let mut agent = AgentBuilder::new()
+            .with_config(config)
+            .on_event(move |event| recorder.lock().unwrap().push(event))
+            .build()
+            .await
+            .unwrap();
+        agent.llm = streaming_llm();
+        let take_kinds = || -> Vec<&'static str> {
+            events.lock().unwrap().drain(..).map(|e| e.kind()).collect()
+        };
+
+        let response = agent.process("Show me the system info").await.unwrap();
+        {
+            let events = events.lock().unwrap();
+            assert!(matches!(
+                &events[1],
+                AgentEvent::ToolCallStarted(call) if call.name == "system_info"
+            ));
+            assert!(matches!(&events[3], AgentEvent::Token(text) if *text == response));
+        }
+        assert_eq!(
+            take_kinds(),
+            [
+                "started",
+                "tool_call_started",
+                "tool_call_finished",
+                "token",
+                "completed"
+            ]
+        );
+
+        // A call held for approval is not run
+        agent.set_tool_approvals(
+            ToolApprovals::new().with_policy("system_info", ApprovalPolicy::Always),
+        );
+        agent.process("Show me the system info").await.unwrap();
+        let kinds = take_kinds();
+        assert!(kinds.contains(&"suspended"));
+        assert!(!kinds.contains(&"tool_call_started"));
+        agent.set_tool_approvals(ToolApprovals::new());
+
+        // Generated responses are sent as they arrive
+        agent.process("How is everything?").await.unwrap();
+        assert_eq!(
+            take_kinds(),
+            ["started", "token", "token", "token", "token", "completed"]
+        );
+
+        let mut stream = agent.process_stream("How is everything?").await.unwrap();
+        while let Some(event) = stream.next().await {
+            event.unwrap();
+        }
+        drop(stream);
+        assert_eq!(
+            take_kinds(),
+            ["started", "token", "token", "token", "token", "completed"]
+        );
+
+        // A stream dropped part way still ends the turn
+        let mut stream = agent.process_stream("How is everything?").await.unwrap();
+        stream.next().await.unwrap().unwrap();
+        drop(stream);
+        assert_eq!(take_kinds(), ["started", "token", "error"]);
+    }
+
+    const VERBOSE_ANSWER: &str =
+        "The answer is forty two, and here is a long explanation of why that is so.";
+
+    /// Answers at length, or with `summary` when asked to compress
+    fn verbose_llm(summary: &str) -> Arc<MockLlm> {
+        let summary = summary.to_string();
+        Arc::new(MockLlm::new().with_generate(move |messages| {
+            if messages[0].content.starts_with("Shorten") {
+                Ok(response(summary.clone()))
+            } else {
+                Ok(response(VERBOSE_ANSWER))
+            }
+        }))
+    }
+
+    async fn limited_agent(strategy: ResponseLimitStrategy, summary: &str) -> Agent {
+        let mut config = AgentConfig::default();
+        config.memory.database_url = Some("sqlite::memory:".to_string());
+        config.agent.use_memory = false;
+        config.agent.use_tools = false;
+        config.agent.max_response_chars = Some(30);
+        config.agent.response_limit_strategy = strategy;
+        let mut agent = Agent::new(config).await.unwrap();
+        agent.llm = verbose_llm(summary);
+        agent
+    }
+
+    #[tokio::test]
+    async fn test_response_limit_truncate() {
+        let mut agent = limited_agent(ResponseLimitStrategy::Truncate, "").await;
+
+        let response = agent.process("What is the answer?").await.unwrap();
+        assert_eq!(response, &VERBOSE_ANSWER[..30]);
+        assert_eq!(
+            agent.last_response_limit(),
+            Some(&ResponseLimitReport {
+                strategy: ResponseLimitStrategy::Truncate,
+                truncated: true,
+                original_chars: VERBOSE_ANSWER.len(),
+                final_chars: 30,
+            })
+        );
+        assert_eq!(agent.get_conversation().last().unwrap().content, response);
+    }
+
+    #[tokio::test]
+    async fn test_response_limit_truncate_with_ellipsis() {
+        let mut agent = limited_agent(ResponseLimitStrategy::TruncateWithEllipsis, "").await;
+
+        let response = agent.process("What is the answer?").await.unwrap();
+        assert_eq!(response, "The answer is forty two, and…");
+        let report = agent.last_response_limit().unwrap();
+        assert_eq!(report.strategy, ResponseLimitStrategy::TruncateWithEllipsis);
+        assert!(report.truncated);
+        assert!(report.final_chars <= 30);
+    }
+
+    #[tokio::test]
+    async fn test_response_limit_summarize() {
+        let mut agent = limited_agent(ResponseLimitStrategy::Summarize, "It is 42.").await;
+
+        let response = agent.process("What is the answer?").await.unwrap();
+        assert_eq!(response, "It is 42.");
+        let report = agent.last_response_limit().unwrap();
+        assert_eq!(report.strategy, ResponseLimitStrategy::Summarize);
+        assert!(report.truncated);
+        assert_eq!(report.final_chars, 9);
+
+        // A summary that still doesn't fit is cut with an ellipsis
+        let mut agent = limited_agent(ResponseLimitStrategy::Summarize, VERBOSE_ANSWER).await;
+        let response = agent.process("What is the answer?").await.unwrap();
+        assert!(response.ends_with('…'));
+        assert!(response.chars().count() <= 30);
+        assert_eq!(
+            agent.last_response_limit().unwrap().strategy,
+            ResponseLimitStrategy::TruncateWithEllipsis
+        );
+    }
 }
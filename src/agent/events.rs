@@ -0,0 +1,99 @@
+//! Lifecycle events of agent turns
+//!
+//! Handlers registered with
+//! [`AgentBuilder::on_event`](super::AgentBuilder::on_event) are called
+//! synchronously, in registration order, as [`Agent::process`](super::Agent::process)
+//! and [`Agent::process_stream`](super::Agent::process_stream) run. A turn
+//! starts with [`AgentEvent::Started`] and ends with either
+//! [`AgentEvent::Completed`] or [`AgentEvent::Error`], also when the turn is
+//! cancelled or its stream dropped before it finished. Handlers should return
+//! quickly; anything slow belongs on a channel they send to.
+
+use crate::error::AgentError;
+use crate::mcp::{ToolCall, ToolResult};
+use crate::workflow::SuspendReason;
+use std::sync::Arc;
+
+/// Something that happened during a turn
+#[derive(Debug, Clone)]
+pub enum AgentEvent {
+    Started,
+    /// Response text as it is generated
+    Token(String),
+    ToolCallStarted(ToolCall),
+    /// The result of a tool call, an error result if the call failed
+    ToolCallFinished(ToolResult),
+    /// The workflow suspended; the turn still completes with a response
+    Suspended(SuspendReason),
+    Completed,
+    /// The turn failed with this error
+    Error(String),
+}
+
+impl AgentEvent {
+    /// Name of the event without its payload
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Started => "started",
+            Self::Token(_) => "token",
+            Self::ToolCallStarted(_) => "tool_call_started",
+            Self::ToolCallFinished(_) => "tool_call_finished",
+            Self::Suspended(_) => "suspended",
+            Self::Completed => "completed",
+            Self::Error(_) => "error",
+        }
+    }
+}
+
+/// Callback receiving an agent's events
+pub type AgentEventHandler = Arc<dyn Fn(AgentEvent) + Send + Sync>;
+
+/// The start and end of one turn's events. Dropping a turn that has not
+/// ended sends an [`AgentEvent::Error`], so handlers always see it end.
+pub(crate) struct TurnEvents {
+    handlers: Vec<AgentEventHandler>,
+    ended: bool,
+}
+
+impl TurnEvents {
+    /// Send [`AgentEvent::Started`] to `handlers`
+    pub(crate) fn start(handlers: Vec<AgentEventHandler>) -> Self {
+        let turn = Self {
+            handlers,
+            ended: false,
+        };
+        turn.emit(AgentEvent::Started);
+        turn
+    }
+
+    /// End the turn with [`AgentEvent::Completed`]
+    pub(crate) fn complete(&mut self) {
+        self.end(AgentEvent::Completed);
+    }
+
+    /// End the turn with [`AgentEvent::Error`]
+    pub(crate) fn fail(&mut self, error: &AgentError) {
+        self.end(AgentEvent::Error(error.to_string()));
+    }
+
+    fn end(&mut self, event: AgentEvent) {
+        if !self.ended {
+            self.ended = true;
+            self.emit(event);
+        }
+    }
+
+    fn emit(&self, event: AgentEvent) {
+        for handler in &self.handlers {
+            handler(event.clone());
+        }
+    }
+}
+
+impl Drop for TurnEvents {
+    fn drop(&mut self) {
+        self.end(AgentEvent::Error(
+            "Turn was dropped before it finished".to_string(),
+        ));
+    }
+}
@@ -0,0 +1,29 @@
+//! Middleware around `Agent::process`
+//!
+//! Middleware adds cross-cutting behavior (logging, metrics, validation,
+//! guardrails) without changing the agent itself. The chain is an onion:
+//! `before_process` hooks run in the order middleware was added, `after_process`
+//! hooks run in reverse. An error from any hook aborts processing.
+
+use crate::error::Result;
+use async_trait::async_trait;
+
+/// Hooks invoked around each call to `Agent::process`
+#[async_trait]
+pub trait AgentMiddleware: Send + Sync {
+    /// Name used in logs
+    fn name(&self) -> &str;
+
+    /// Inspect or rewrite the user input before processing.
+    ///
+    /// Returning an error short-circuits the request: later middleware and the
+    /// agent itself are not invoked.
+    async fn before_process(&self, input: String) -> Result<String> {
+        Ok(input)
+    }
+
+    /// Inspect or rewrite the response after processing
+    async fn after_process(&self, output: String) -> Result<String> {
+        Ok(output)
+    }
+}
@@ -0,0 +1,190 @@
+//! Separating model reasoning from the answer
+//!
+//! Reasoning models either return their reasoning in a dedicated field,
+//! reported as [`GenerationResponse::reasoning`](crate::llm::GenerationResponse),
+//! or inline in the text between `<think>` (or `<thinking>`) tags. With
+//! `capture_reasoning` set, [`Agent::process_turn`](super::Agent::process_turn)
+//! strips the inline blocks from the response and returns all of the
+//! reasoning in [`TurnOutput::reasoning`](super::TurnOutput::reasoning), and
+//! [`ResponseStream`](super::ResponseStream) streams it as separate events.
+
+const TAGS: &[(&str, &str)] = &[("<think>", "</think>"), ("<thinking>", "</thinking>")];
+
+/// The reasoning in `text`'s thinking blocks, if any, and `text` without
+/// them. An unclosed block runs to the end of the text.
+pub fn split_thinking(text: &str) -> (Option<String>, String) {
+    let mut thoughts = Vec::new();
+    let mut answer = String::new();
+    let mut rest = text;
+    while let Some((start, open, close)) = next_block(rest) {
+        answer.push_str(&rest[..start]);
+        let body = &rest[start + open.len()..];
+        let (thought, after) = match body.find(close) {
+            Some(end) => (&body[..end], &body[end + close.len()..]),
+            None => (body, ""),
+        };
+        if !thought.trim().is_empty() {
+            thoughts.push(thought.trim().to_string());
+        }
+        rest = after;
+    }
+    answer.push_str(rest);
+
+    let reasoning = (!thoughts.is_empty()).then(|| thoughts.join("\n\n"));
+    (reasoning, answer.trim().to_string())
+}
+
+/// Piece of streamed text, split by [`ThinkingSplitter`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Piece {
+    Answer(String),
+    Thought(String),
+}
+
+/// Splits streamed text into answer and reasoning as it arrives. Text that
+/// could start a tag is held back until the next chunk settles it, and the
+/// answer's leading whitespace is dropped as in [`split_thinking`].
+#[derive(Debug, Default)]
+pub struct ThinkingSplitter {
+    buffer: String,
+    /// Closing tag of the block being read, if inside one
+    close: Option<&'static str>,
+    answering: bool,
+}
+
+impl ThinkingSplitter {
+    /// Feed a chunk of text and return the pieces it completes
+    pub fn push(&mut self, text: &str) -> Vec<Piece> {
+        self.buffer.push_str(text);
+        self.drain(false)
+    }
+
+    /// Return whatever is still held back once the text has ended
+    pub fn finish(&mut self) -> Vec<Piece> {
+        self.drain(true)
+    }
+
+    fn drain(&mut self, finished: bool) -> Vec<Piece> {
+        let mut pieces = Vec::new();
+        loop {
+            match self.close {
+                None => {
+                    if let Some((start, open, close)) = next_block(&self.buffer) {
+                        let answer: String = self.buffer.drain(..start).collect();
+                        self.push_answer(answer, &mut pieces);
+                        self.buffer.drain(..open.len());
+                        self.close = Some(close);
+                        continue;
+                    }
+                    let held = if finished {
+                        0
+                    } else {
+                        TAGS.iter()
+                            .map(|(open, _)| partial_tag(&self.buffer, open))
+                            .max()
+                            .unwrap_or(0)
+                    };
+                    let answer = self.buffer.drain(..self.buffer.len() - held).collect();
+                    self.push_answer(answer, &mut pieces);
+                    return pieces;
+                }
+                Some(close) => {
+                    let (end, after) = match self.buffer.find(close) {
+                        Some(end) => (end, Some(end + close.len())),
+                        None if finished => (self.buffer.len(), None),
+                        None => (self.buffer.len() - partial_tag(&self.buffer, close), None),
+                    };
+                    let thought: String = self.buffer.drain(..end).collect();
+                    if !thought.is_empty() {
+                        pieces.push(Piece::Thought(thought));
+                    }
+                    match after {
+                        Some(after) => {
+                            self.buffer.drain(..after - end);
+                            self.close = None;
+                        }
+                        None => return pieces,
+                    }
+                }
+            }
+        }
+    }
+
+    fn push_answer(&mut self, text: String, pieces: &mut Vec<Piece>) {
+        let text = if self.answering {
+            text
+        } else {
+            text.trim_start().to_string()
+        };
+        if !text.is_empty() {
+            self.answering = true;
+            pieces.push(Piece::Answer(text));
+        }
+    }
+}
+
+/// Length of the longest end of `text` that begins `tag` without completing it
+fn partial_tag(text: &str, tag: &str) -> usize {
+    (1..tag.len())
+        .rev()
+        .find(|&len| text.ends_with(&tag[..len]))
+        .unwrap_or(0)
+}
+
+/// Position and tags of the first thinking block in `text`
+fn next_block(text: &str) -> Option<(usize, &'static str, &'static str)> {
+    TAGS.iter()
+        .filter_map(|(open, close)| text.find(open).map(|start| (start, *open, *close)))
+        .min_by_key(|(start, _, _)| *start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_thinking() {
+        let (reasoning, answer) =
+            split_thinking("<think>\n2 + 2 is 4\n</think>\n\nThe answer is 4.");
+        assert_eq!(reasoning.as_deref(), Some("2 + 2 is 4"));
+        assert_eq!(answer, "The answer is 4.");
+
+        let (reasoning, answer) = split_thinking("Plain answer");
+        assert_eq!(reasoning, None);
+        assert_eq!(answer, "Plain answer");
+
+        let (reasoning, answer) = split_thinking("Draft <thinking>still going");
+        assert_eq!(reasoning.as_deref(), Some("still going"));
+        assert_eq!(answer, "Draft");
+    }
+
+    #[test]
+    fn test_splitter_handles_tags_split_across_chunks() {
+        let mut splitter = ThinkingSplitter::default();
+        let mut pieces = Vec::new();
+        for chunk in [
+            "<thi",
+            "nk>\n2 + 2",
+            "</th",
+            "ink>\n\nThe ans",
+            "wer <",
+            "b>is</b> 4.",
+        ] {
+            pieces.extend(splitter.push(chunk));
+        }
+        pieces.extend(splitter.finish());
+
+        let text = |want_thought: bool| {
+            pieces
+                .iter()
+                .filter_map(|piece| match piece {
+                    Piece::Thought(text) if want_thought => Some(text.as_str()),
+                    Piece::Answer(text) if !want_thought => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect::<String>()
+        };
+        assert_eq!(text(true), "\n2 + 2");
+        assert_eq!(text(false), "The answer <b>is</b> 4.");
+    }
+}
@@ -0,0 +1,75 @@
+//! Capping the length of agent responses
+//!
+//! With `max_response_chars` set, [`Agent::process`](super::Agent::process)
+//! fits each response to the limit using the configured
+//! [`ResponseLimitStrategy`] and records a [`ResponseLimitReport`] describing
+//! what was done. Lengths are counted in characters, not bytes.
+
+use crate::config::ResponseLimitStrategy;
+
+/// What the response limit did to the last response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResponseLimitReport {
+    /// Strategy that produced the returned response. A summary that failed
+    /// or still didn't fit is reported as `TruncateWithEllipsis`.
+    pub strategy: ResponseLimitStrategy,
+    /// Whether the response exceeded the limit and was shortened
+    pub truncated: bool,
+    /// Length of the response before the limit was applied
+    pub original_chars: usize,
+    /// Length of the returned response
+    pub final_chars: usize,
+}
+
+/// Ellipsis ending a response cut by [`ResponseLimitStrategy::TruncateWithEllipsis`]
+pub const ELLIPSIS: char = '…';
+
+/// The first `max_chars` characters of `text`
+pub fn truncate_chars(text: &str, max_chars: usize) -> String {
+    text.chars().take(max_chars).collect()
+}
+
+/// `text` cut to fit `max_chars` characters including a trailing ellipsis,
+/// at a word boundary when one is close enough
+pub fn truncate_with_ellipsis(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    if max_chars == 0 {
+        return String::new();
+    }
+
+    let cut = truncate_chars(text, max_chars - 1);
+    let mid_word = text
+        .chars()
+        .nth(max_chars - 1)
+        .is_some_and(|next| !next.is_whitespace());
+    // Don't end on half a word unless that would throw away most of the text
+    let cut = match cut.rfind(char::is_whitespace) {
+        Some(space) if mid_word && space >= cut.len() / 2 => &cut[..space],
+        _ => cut.as_str(),
+    };
+    format!("{}{}", cut.trim_end(), ELLIPSIS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_chars_counts_characters() {
+        assert_eq!(truncate_chars("héllo wörld", 7), "héllo w");
+        assert_eq!(truncate_chars("short", 10), "short");
+    }
+
+    #[test]
+    fn test_truncate_with_ellipsis_fits_limit() {
+        let text = "The quick brown fox jumps over the lazy dog";
+        let cut = truncate_with_ellipsis(text, 20);
+        assert_eq!(cut, "The quick brown fox…");
+        assert!(cut.chars().count() <= 20);
+
+        assert_eq!(truncate_with_ellipsis(text, 100), text);
+        assert_eq!(truncate_with_ellipsis("Supercalifragilistic", 6), "Super…");
+    }
+}
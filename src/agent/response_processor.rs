@@ -0,0 +1,177 @@
+//! Deterministic post-processing of agent responses
+//!
+//! A [`ResponseProcessor`] rewrites the final response text, e.g. to strip
+//! markdown code fences or pull out a JSON block. Processors added to an
+//! agent run in the order they were added, after the workflow (including any
+//! guardrail steps) has produced the response and before `max_response_chars`
+//! is applied, so the conversation records the processed text. A
+//! [`ResponseProcessorChain`] is itself a processor, so chains compose.
+
+use std::sync::Arc;
+
+/// Transforms a response
+pub trait ResponseProcessor: Send + Sync {
+    /// Name used in logs
+    fn name(&self) -> &str;
+
+    fn process(&self, response: String) -> String;
+}
+
+/// Processors applied one after another
+#[derive(Clone, Default)]
+pub struct ResponseProcessorChain {
+    processors: Vec<Arc<dyn ResponseProcessor>>,
+}
+
+impl ResponseProcessorChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `processor` after those already in the chain
+    pub fn with(mut self, processor: impl ResponseProcessor + 'static) -> Self {
+        self.push(Arc::new(processor));
+        self
+    }
+
+    pub fn push(&mut self, processor: Arc<dyn ResponseProcessor>) {
+        self.processors.push(processor);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.processors.is_empty()
+    }
+}
+
+impl ResponseProcessor for ResponseProcessorChain {
+    fn name(&self) -> &str {
+        "chain"
+    }
+
+    fn process(&self, response: String) -> String {
+        self.processors
+            .iter()
+            .fold(response, |response, processor| processor.process(response))
+    }
+}
+
+/// Removes markdown code fence lines, keeping the code between them
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StripCodeFences;
+
+impl ResponseProcessor for StripCodeFences {
+    fn name(&self) -> &str {
+        "strip_code_fences"
+    }
+
+    fn process(&self, response: String) -> String {
+        response
+            .lines()
+            .filter(|line| !is_fence(line))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Removes leading and trailing whitespace
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TrimWhitespace;
+
+impl ResponseProcessor for TrimWhitespace {
+    fn name(&self) -> &str {
+        "trim_whitespace"
+    }
+
+    fn process(&self, response: String) -> String {
+        response.trim().to_string()
+    }
+}
+
+/// Reduces the response to the JSON it contains: the first fenced block
+/// holding valid JSON, else the span from the first `{` or `[` to the last
+/// `}` or `]` if that parses. Responses without JSON are left unchanged.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExtractJsonBlock;
+
+impl ResponseProcessor for ExtractJsonBlock {
+    fn name(&self) -> &str {
+        "extract_json_block"
+    }
+
+    fn process(&self, response: String) -> String {
+        let fenced = fenced_blocks(&response)
+            .into_iter()
+            .find(|block| is_json(block));
+        if let Some(block) = fenced {
+            return block.trim().to_string();
+        }
+
+        let start = response.find(['{', '[']);
+        let end = response.rfind(['}', ']']);
+        match (start, end) {
+            (Some(start), Some(end)) if start < end && is_json(&response[start..=end]) => {
+                response[start..=end].to_string()
+            }
+            _ => response,
+        }
+    }
+}
+
+fn is_fence(line: &str) -> bool {
+    line.trim_start().starts_with("```")
+}
+
+fn is_json(text: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(text).is_ok()
+}
+
+/// Contents of the complete fenced blocks in `text`, in order
+fn fenced_blocks(text: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut current: Option<Vec<&str>> = None;
+    for line in text.lines() {
+        match (is_fence(line), current.as_mut()) {
+            (true, Some(lines)) => {
+                blocks.push(lines.join("\n"));
+                current = None;
+            }
+            (true, None) => current = Some(Vec::new()),
+            (false, Some(lines)) => lines.push(line),
+            (false, None) => {}
+        }
+    }
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chain_strips_fences_then_trims() {
+        let chain = ResponseProcessorChain::new()
+            .with(StripCodeFences)
+            .with(TrimWhitespace);
+        let response = "\n```rust\nfn main() {\n    println!(\"hi\");\n}\n```\n\n".to_string();
+
+        assert_eq!(
+            chain.process(response),
+            "fn main() {\n    println!(\"hi\");\n}"
+        );
+    }
+
+    #[test]
+    fn test_extract_json_block() {
+        let fenced = "Here you go:\n```json\n{\"city\": \"Paris\"}\n```\nAnything else?";
+        assert_eq!(
+            ExtractJsonBlock.process(fenced.to_string()),
+            "{\"city\": \"Paris\"}"
+        );
+
+        let inline = "The answer is [1, 2, 3].";
+        assert_eq!(ExtractJsonBlock.process(inline.to_string()), "[1, 2, 3]");
+
+        let prose = "No structured data {here}.";
+        assert_eq!(ExtractJsonBlock.process(prose.to_string()), prose);
+    }
+}
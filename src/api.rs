@@ -4,7 +4,7 @@
 //! - Agent operations (process, query)
 //! - Workflow management (create, execute, suspend, resume)
 //! - A2A communication
-//! - System monitoring
+//! - System monitoring, including Prometheus metrics at `/metrics`
 
 use crate::agent::{Agent, AgentBuilder};
 use crate::config::AgentConfig;
@@ -12,14 +12,17 @@ use crate::error::{AgentError, Result};
 use crate::ui_workflow_storage::UIWorkflowStorage;
 use crate::workflow::{WorkflowContext, WorkflowEngine, WorkflowSnapshot};
 use axum::{
+    body::Body,
     extract::{Path, State},
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{Html, IntoResponse, Response},
     routing::{get, post},
     Json, Router,
 };
+use futures::stream::{self, Stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::convert::Infallible;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tower_http::cors::CorsLayer;
@@ -87,6 +90,9 @@ pub struct ProcessResponse {
     /// Whether processing completed
     #[schema(example = true)]
     pub completed: bool,
+    /// Reasoning behind the response, when the agent captures it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<String>,
 }
 
 /// Request to create a workflow
@@ -320,6 +326,7 @@ impl IntoResponse for ApiError {
             AgentError::Config(msg) => (StatusCode::BAD_REQUEST, msg),
             AgentError::Network(msg) => (StatusCode::BAD_GATEWAY, msg),
             AgentError::Workflow(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
+            conflict @ AgentError::Conflict { .. } => (StatusCode::CONFLICT, conflict.to_string()),
             _ => (StatusCode::INTERNAL_SERVER_ERROR, self.0.to_string()),
         };
 
@@ -335,11 +342,43 @@ impl IntoResponse for ApiError {
 /// Convert Result<T> to Result<T, ApiError>
 type ApiResult<T> = std::result::Result<T, ApiError>;
 
+/// Render a stream of values as the chunks of one JSON array
+///
+/// The opening bracket is sent before the first value and the closing bracket
+/// after the last, so the concatenated chunks are a well-formed array however
+/// many values the source yields, including none.
+pub fn json_array_stream<S>(values: S) -> impl Stream<Item = String> + Send
+where
+    S: Stream<Item = serde_json::Value> + Send,
+{
+    let elements = values.enumerate().map(|(index, value)| {
+        if index == 0 {
+            value.to_string()
+        } else {
+            format!(",{}", value)
+        }
+    });
+
+    stream::once(async { "[".to_string() })
+        .chain(elements)
+        .chain(stream::once(async { "]".to_string() }))
+}
+
+/// Chunked `application/json` response that streams `values` as a growing array
+pub fn json_array_response<S>(values: S) -> Response
+where
+    S: Stream<Item = serde_json::Value> + Send + 'static,
+{
+    let body = Body::from_stream(json_array_stream(values).map(Ok::<_, Infallible>));
+    ([(header::CONTENT_TYPE, "application/json")], body).into_response()
+}
+
 /// OpenAPI documentation
 #[derive(OpenApi)]
 #[openapi(
     paths(
         health_handler,
+        metrics_handler,
         process_handler,
         create_workflow_handler,
         suspend_workflow_handler,
@@ -368,6 +407,7 @@ type ApiResult<T> = std::result::Result<T, ApiError>;
     ),
     tags(
         (name = "health", description = "Health check endpoints"),
+        (name = "metrics", description = "Prometheus metrics"),
         (name = "agent", description = "Agent operations"),
         (name = "workflows", description = "Workflow management")
     ),
@@ -389,6 +429,7 @@ pub fn create_router(state: AppState) -> Router {
     Router::new()
         // Health check
         .route("/health", get(health_handler))
+        .route("/metrics", get(metrics_handler))
         // Agent endpoints
         .route("/api/v1/agent/process", post(process_handler))
         // Workflow endpoints
@@ -450,6 +491,22 @@ async fn health_handler() -> Json<HealthResponse> {
     })
 }
 
+/// Prometheus metrics endpoint
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "metrics",
+    responses(
+        (status = 200, description = "Metrics in the Prometheus text format", body = String, content_type = "text/plain")
+    )
+)]
+async fn metrics_handler() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, crate::metrics::CONTENT_TYPE)],
+        crate::metrics::render(),
+    )
+}
+
 /// Process a message through the agent
 #[utoipa::path(
     post,
@@ -469,15 +526,16 @@ async fn process_handler(
     info!("Processing message: {}", request.message);
 
     let mut agent = state.agent.write().await;
-    let response = agent
-        .process(&request.message)
+    let output = agent
+        .process_turn(&request.message)
         .await
         .map_err(ApiError::from)?;
 
     Ok(Json(ProcessResponse {
-        response,
+        response: output.response,
         steps_executed: 1, // TODO: Track actual steps
         completed: true,
+        reasoning: output.reasoning,
     }))
 }
 
@@ -1003,7 +1061,7 @@ async fn execute_node(
             // NOTE: Full implementation requires child node execution for each item
             let items = inputs.get("items")
                 .and_then(|v| v.as_array())
-                .map(|arr| arr.clone())
+                .cloned()
                 .unwrap_or_default();
 
             let max_iterations = node.config.get("max_iterations")
@@ -1081,7 +1139,7 @@ async fn create_ui_workflow(
 
     let now = chrono::Utc::now();
     let workflow = UIWorkflow {
-        id: request.workflow_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+        id: request.workflow_id.unwrap_or_else(|| crate::ids::new_id().to_string()),
         name: request.name,
         description: request.description,
         nodes: request.nodes,
@@ -1356,7 +1414,7 @@ pub async fn initialize_ui_node_types(state: &AppState) {
             id: "model_config".to_string(),
             name: "Model Configuration".to_string(),
             category: "LLM".to_string(),
-            description: "Configure LLM provider connection (Ollama, OpenAI, Anthropic, Google, Azure, Groq, Together, Replicate, HuggingFace, Cohere)".to_string(),
+            description: "Configure LLM provider connection (Ollama, OpenAI, Anthropic, Google, Azure, Groq, Together, Replicate, HuggingFace, Cohere, llama.cpp)".to_string(),
             inputs: vec![],
             outputs: vec![UINodeOutput {
                 name: "config".to_string(),
@@ -1368,7 +1426,7 @@ pub async fn initialize_ui_node_types(state: &AppState) {
                 "properties": {
                     "provider": {
                         "type": "string",
-                        "enum": ["ollama", "openai", "anthropic", "google", "azureopenai", "groq", "together", "replicate", "huggingface", "cohere"],
+                        "enum": ["ollama", "openai", "anthropic", "google", "azureopenai", "groq", "together", "replicate", "huggingface", "cohere", "llamacpp"],
                         "default": "ollama",
                         "description": "LLM provider to use"
                     },
@@ -1946,6 +2004,7 @@ pub async fn start_server(state: AppState, host: &str, port: u16) -> Result<()>
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::llm::pricing::TokenUsage;
 
     #[test]
     fn test_process_request_serialization() {
@@ -1967,4 +2026,56 @@ mod tests {
 
         assert_eq!(response.status, "ok");
     }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint_exposes_counters() {
+        let app = Router::new().route("/metrics", get(metrics_handler));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        // Simulate a request that made a tool call and hit the cache
+        crate::metrics::record_tool_call("metrics_endpoint_tool", true, true);
+        crate::metrics::record_cache_lookup(true);
+        crate::metrics::record_tokens("metrics-endpoint-model", &TokenUsage::new(40, 8));
+        crate::metrics::record_request(true, std::time::Duration::from_millis(120));
+
+        let response = reqwest::get(format!("http://{}/metrics", addr))
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+        assert!(response.headers()[reqwest::header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("text/plain"));
+        let body = response.text().await.unwrap();
+
+        assert!(body.contains(r#"agent_requests_total{outcome="success"}"#));
+        assert!(body.contains(r#"agent_request_duration_seconds_count{outcome="success"}"#));
+        assert!(body.contains(
+            r#"agent_tool_calls_total{outcome="success",tool="metrics_endpoint_tool"} 1"#
+        ));
+        assert!(
+            body.contains(r#"agent_tokens_total{kind="prompt",model="metrics-endpoint-model"} 40"#)
+        );
+        assert!(body.contains("agent_cache_hits_total"));
+    }
+
+    #[tokio::test]
+    async fn test_json_array_stream_is_valid_array() {
+        let values = stream::iter(vec![
+            serde_json::json!({"step": 1}),
+            serde_json::json!("two"),
+            serde_json::json!([3]),
+        ]);
+        let chunks: Vec<String> = json_array_stream(values).collect().await;
+        assert_eq!(chunks.len(), 5);
+
+        let parsed: serde_json::Value = serde_json::from_str(&chunks.concat()).unwrap();
+        assert_eq!(parsed, serde_json::json!([{"step": 1}, "two", [3]]));
+
+        // A source that ends before producing anything still closes the array
+        let empty: Vec<String> = json_array_stream(stream::empty()).collect().await;
+        assert_eq!(empty.concat(), "[]");
+    }
 }
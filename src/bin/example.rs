@@ -25,6 +25,12 @@ async fn main() -> anyhow::Result<()> {
             similarity_threshold: 0.6, // slightly lower to make recall easier
             persistent: true,
             store_type: "sqlite".to_string(),
+            dedup: None,
+            vector_index: None,
+            normalize_embeddings: false,
+            ranking: None,
+            shared_writes: false,
+            max_entries: None,
         },
         ..Default::default()
     };
@@ -7,8 +7,9 @@ use anyhow::Result;
 use std::sync::Arc;
 use the_agency::{
     organization::{
-        coordinator::AgentCoordinator, CollaborativeWorkspace, Organization, OrganizationAgent,
-        OrganizationRole, TaskPriority, WorkspaceTask,
+        coordinator::{AgentCoordinator, ExecutionMode},
+        CollaborativeWorkspace, Organization, OrganizationAgent, OrganizationRole, TaskPriority,
+        WorkspaceTask,
     },
     AgentConfig,
 };
@@ -225,30 +226,30 @@ async fn main() -> Result<()> {
     // Simulation workspace project
     info!("📦 Workspace: Simulation Development");
     let sim_tasks = vec![task1, task2, task4];
-    let sim_results = daemon
+    let (_, sim_report) = daemon
         .coordinator()
-        .coordinate_workspace_project(&sim_ws_id, sim_tasks)
+        .coordinate_workspace_project_with_report(&sim_ws_id, sim_tasks, ExecutionMode::default())
         .await?;
 
     info!("");
     info!(
         "✅ Simulation workspace: {} tasks completed",
-        sim_results.len()
+        sim_report.completed
     );
 
     // Production workspace project
     info!("");
     info!("📦 Workspace: Production Engineering");
     let prod_tasks = vec![task3];
-    let prod_results = daemon
+    let (_, prod_report) = daemon
         .coordinator()
-        .coordinate_workspace_project(&prod_ws_id, prod_tasks)
+        .coordinate_workspace_project_with_report(&prod_ws_id, prod_tasks, ExecutionMode::default())
         .await?;
 
     info!("");
     info!(
         "✅ Production workspace: {} tasks completed",
-        prod_results.len()
+        prod_report.completed
     );
 
     // Display final organization state
@@ -282,8 +283,14 @@ async fn main() -> Result<()> {
 
     // Display results
     info!("📈 Task Results Summary:");
-    info!("  Simulation workspace: {} successful", sim_results.len());
-    info!("  Production workspace: {} successful", prod_results.len());
+    info!(
+        "  Simulation workspace: {} successful",
+        sim_report.completed
+    );
+    info!(
+        "  Production workspace: {} successful",
+        prod_report.completed
+    );
 
     info!("");
     info!("==========================================");
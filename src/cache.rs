@@ -1,5 +1,6 @@
 //! LLM response caching for improved performance on repeated queries
 
+use crate::llm::{Message, Role};
 use anyhow::Result;
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
@@ -130,6 +131,28 @@ impl LlmCache {
         format!("{:x}", hasher.finish())
     }
 
+    /// Cache key of a conversation sent to `model` with the given settings
+    pub fn compute_messages_cache_key(
+        messages: &[Message],
+        model: &str,
+        temperature: f32,
+        max_tokens: u32,
+    ) -> String {
+        let messages_json =
+            serde_json::to_string(messages).unwrap_or_else(|_| format!("{:?}", messages));
+        let system_prompt = messages
+            .iter()
+            .find(|m| m.role == Role::System)
+            .map(|m| m.content.as_str());
+        Self::compute_cache_key(
+            &messages_json,
+            model,
+            temperature,
+            max_tokens,
+            system_prompt,
+        )
+    }
+
     /// Get a cached response if available and not expired
     pub async fn get(&self, key: &str) -> Result<Option<String>> {
         if !self.config.enabled {
@@ -163,6 +186,7 @@ impl LlmCache {
                     ttl.num_seconds()
                 );
                 self.invalidate(key).await?;
+                crate::metrics::record_cache_lookup(false);
                 return Ok(None);
             }
 
@@ -187,9 +211,11 @@ impl LlmCache {
                 hit_count + 1
             );
 
+            crate::metrics::record_cache_lookup(true);
             Ok(Some(response))
         } else {
             debug!("Cache miss for key: {}", key);
+            crate::metrics::record_cache_lookup(false);
             Ok(None)
         }
     }
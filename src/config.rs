@@ -2,6 +2,12 @@
 
 use crate::a2a::A2AConfig;
 use crate::cache::LlmCacheConfig;
+use crate::error::AgentError;
+use crate::http_client::HttpClientConfig;
+use crate::llm::body_log::default_log_body_max_len;
+use crate::llm::embedding_cache::EmbeddingCacheConfig;
+use crate::llm::embedding_limit::EmbeddingLimitConfig;
+use crate::llm::pricing::{ModelPricing, PricingTable};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -56,7 +62,27 @@ pub struct LlmConfig {
     /// Request timeout in seconds
     pub timeout: u64,
 
-    /// Enable streaming responses
+    /// Longest a generation may run in seconds, including the whole of a
+    /// streamed response; unbounded when unset
+    #[serde(default)]
+    pub generation_timeout: Option<u64>,
+
+    /// Longest gap in seconds allowed between streamed tokens; unbounded when
+    /// unset
+    #[serde(default)]
+    pub stream_idle_timeout: Option<u64>,
+
+    /// Times a failed generation or embedding is retried; errors the provider
+    /// marks as permanent are not retried
+    #[serde(default)]
+    pub max_retries: usize,
+
+    /// Delay between retries in milliseconds
+    #[serde(default = "default_retry_delay_ms")]
+    pub retry_delay_ms: u64,
+
+    /// Generate final responses over streaming requests, even without event
+    /// handlers to send tokens to
     pub stream: bool,
 
     /// Task-specific model configurations
@@ -66,6 +92,53 @@ pub struct LlmConfig {
     /// LLM response cache configuration
     #[serde(default)]
     pub cache: LlmCacheConfig,
+
+    /// In-memory cache of embeddings, shared by memory and knowledge ingestion
+    #[serde(default)]
+    pub embedding_cache: EmbeddingCacheConfig,
+
+    /// Input token limit of the embedding model and handling of longer inputs
+    #[serde(default)]
+    pub embedding_limit: EmbeddingLimitConfig,
+
+    /// Let concurrent identical generations, such as those of agents in an
+    /// organization run, share one provider call
+    #[serde(default = "default_true")]
+    pub single_flight: bool,
+
+    /// Per-model pricing overrides (USD per 1K tokens), merged over the built-in table
+    #[serde(default)]
+    pub pricing: HashMap<String, ModelPricing>,
+
+    /// How long Ollama keeps the model loaded after a request, e.g. `"30m"`,
+    /// or `"-1"` to keep it loaded indefinitely. Uses the server default when unset.
+    #[serde(default)]
+    pub keep_alive: Option<String>,
+
+    /// Check at startup that `text_model` and `embedding_model` are installed
+    /// on the Ollama server
+    #[serde(default)]
+    pub verify_models: bool,
+
+    /// Log raw request and response bodies at debug level, with API keys masked
+    #[serde(default)]
+    pub log_bodies: bool,
+
+    /// Bodies longer than this many bytes are truncated in the log
+    #[serde(default = "default_log_body_max_len")]
+    pub log_body_max_len: usize,
+
+    /// Sampling seed for reproducible generations; the model samples freely when unset
+    #[serde(default)]
+    pub seed: Option<u64>,
+
+    /// Nucleus sampling cutoff; the provider default applies when unset
+    #[serde(default)]
+    pub top_p: Option<f32>,
+
+    /// Proxy, trusted CA and connection settings for the Ollama client
+    #[serde(default)]
+    pub http: HttpClientConfig,
 }
 
 /// Task-specific model configuration
@@ -111,6 +184,135 @@ pub struct MemoryConfig {
 
     /// Enable persistent storage
     pub persistent: bool,
+
+    /// Skip or merge writes that duplicate an existing memory (disabled when unset)
+    #[serde(default)]
+    pub dedup: Option<MemoryDedupConfig>,
+
+    /// Keep embeddings in an in-memory index updated on every write instead of
+    /// scanning the database on each search (disabled when unset)
+    #[serde(default)]
+    pub vector_index: Option<VectorIndexConfig>,
+
+    /// Scale embeddings and queries to unit length before storing or
+    /// searching. The setting is recorded in the database, which then refuses
+    /// to open with the other setting.
+    #[serde(default)]
+    pub normalize_embeddings: bool,
+
+    /// Re-rank similarity search results by importance and recency of
+    /// access (raw similarity order when unset)
+    #[serde(default)]
+    pub ranking: Option<MemoryRankingConfig>,
+
+    /// While working in a workspace with shared memory, store knowledge
+    /// there for teammates instead of in this agent's own memory
+    #[serde(default)]
+    pub shared_writes: bool,
+
+    /// Most entries the store may hold. Storing beyond the cap evicts the
+    /// least recently accessed entries (unbounded when unset).
+    #[serde(default)]
+    pub max_entries: Option<usize>,
+}
+
+/// Weights combining similarity, importance and recency into a search score
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryRankingConfig {
+    /// Weight of the embedding similarity
+    #[serde(default = "default_similarity_weight")]
+    pub similarity_weight: f32,
+
+    /// Weight of the entry's importance, itself between 0.0 and 1.0
+    #[serde(default = "default_importance_weight")]
+    pub importance_weight: f32,
+
+    /// Weight of the recency score, which halves every `recency_half_life_hours`
+    /// since the entry was last accessed
+    #[serde(default = "default_recency_weight")]
+    pub recency_weight: f32,
+
+    #[serde(default = "default_recency_half_life_hours")]
+    pub recency_half_life_hours: f64,
+
+    /// Candidates fetched per requested result for re-ranking
+    #[serde(default = "default_ranking_candidate_factor")]
+    pub candidate_factor: usize,
+}
+
+impl Default for MemoryRankingConfig {
+    fn default() -> Self {
+        Self {
+            similarity_weight: default_similarity_weight(),
+            importance_weight: default_importance_weight(),
+            recency_weight: default_recency_weight(),
+            recency_half_life_hours: default_recency_half_life_hours(),
+            candidate_factor: default_ranking_candidate_factor(),
+        }
+    }
+}
+
+fn default_similarity_weight() -> f32 {
+    1.0
+}
+
+fn default_importance_weight() -> f32 {
+    0.3
+}
+
+fn default_recency_weight() -> f32 {
+    0.2
+}
+
+fn default_recency_half_life_hours() -> f64 {
+    168.0
+}
+
+fn default_ranking_candidate_factor() -> usize {
+    4
+}
+
+/// In-memory vector index settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VectorIndexConfig {
+    /// Fraction of index slots held by deleted entries at which the index is
+    /// compacted
+    #[serde(default = "default_max_tombstone_ratio")]
+    pub max_tombstone_ratio: f32,
+}
+
+impl Default for VectorIndexConfig {
+    fn default() -> Self {
+        Self {
+            max_tombstone_ratio: default_max_tombstone_ratio(),
+        }
+    }
+}
+
+fn default_max_tombstone_ratio() -> f32 {
+    0.25
+}
+
+/// Near-duplicate detection for memory writes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryDedupConfig {
+    /// Embedding similarity at or above which a new entry duplicates an existing one
+    pub similarity_threshold: f32,
+
+    /// What to do with a duplicate
+    #[serde(default)]
+    pub action: MemoryDedupAction,
+}
+
+/// How a duplicate memory write is handled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MemoryDedupAction {
+    /// Drop the new entry
+    #[default]
+    Skip,
+    /// Fold the new entry's metadata into the existing entry
+    Merge,
 }
 
 /// MCP server configuration
@@ -127,6 +329,72 @@ pub struct McpConfig {
 
     /// Enable tool call caching
     pub enable_caching: bool,
+
+    /// Maximum size in bytes of a tool result's content
+    #[serde(default = "default_max_result_bytes")]
+    pub max_result_bytes: usize,
+
+    /// Truncate oversized results instead of replacing them with an error
+    #[serde(default = "default_true")]
+    pub truncate_oversized_results: bool,
+
+    /// Summarize long tool results with the LLM before they enter the
+    /// conversation (disabled when unset)
+    #[serde(default)]
+    pub summarize_results: Option<ToolSummaryConfig>,
+}
+
+fn default_max_result_bytes() -> usize {
+    1024 * 1024
+}
+
+/// When tool results are summarized
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolSummaryConfig {
+    /// Results with more text than this many characters are summarized
+    #[serde(default = "default_summary_threshold_chars")]
+    pub threshold_chars: usize,
+
+    /// Per-tool overrides, keyed by tool name
+    #[serde(default)]
+    pub tools: HashMap<String, ToolSummaryOverride>,
+}
+
+/// Summarization settings for a single tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolSummaryOverride {
+    /// Summarize this tool's results at all
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Threshold for this tool, in place of the global one
+    #[serde(default)]
+    pub threshold_chars: Option<usize>,
+}
+
+fn default_summary_threshold_chars() -> usize {
+    4000
+}
+
+impl Default for ToolSummaryConfig {
+    fn default() -> Self {
+        Self {
+            threshold_chars: default_summary_threshold_chars(),
+            tools: HashMap::new(),
+        }
+    }
+}
+
+impl ToolSummaryConfig {
+    /// Character threshold above which results of `tool_name` are
+    /// summarized, or `None` if they never are
+    pub fn threshold_for(&self, tool_name: &str) -> Option<usize> {
+        match self.tools.get(tool_name) {
+            Some(tool) if !tool.enabled => None,
+            Some(tool) => Some(tool.threshold_chars.unwrap_or(self.threshold_chars)),
+            None => Some(self.threshold_chars),
+        }
+    }
 }
 
 /// Individual MCP server configuration
@@ -166,6 +434,15 @@ pub struct WorkflowConfig {
     /// Checkpoint interval (in steps)
     pub checkpoint_interval: usize,
 
+    /// Random offset of up to this many steps applied to each workflow's
+    /// checkpoints, spreading out concurrent snapshot writes
+    #[serde(default)]
+    pub checkpoint_jitter: usize,
+
+    /// Minimum seconds between automatic checkpoints of one workflow
+    #[serde(default)]
+    pub min_checkpoint_interval_secs: u64,
+
     /// Maximum number of snapshots to keep
     pub max_snapshots: usize,
 
@@ -174,6 +451,42 @@ pub struct WorkflowConfig {
 
     /// Enable workflow step debugging
     pub debug_steps: bool,
+
+    /// URL POSTed to when a workflow suspends waiting for human input
+    #[serde(default)]
+    pub suspension_webhook_url: Option<String>,
+
+    /// Seconds a workflow run may take before it is suspended (unbounded
+    /// when unset)
+    #[serde(default)]
+    pub max_total_duration_secs: Option<u64>,
+
+    /// Tool calls a workflow may make before it completes without calling
+    /// more (unbounded when unset)
+    #[serde(default)]
+    pub max_tool_calls: Option<usize>,
+
+    /// Encrypt snapshots at rest (disabled when unset)
+    #[serde(default)]
+    pub snapshot_encryption: Option<SnapshotEncryptionConfig>,
+}
+
+/// Keys for encrypting workflow snapshots with AES-256-GCM
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SnapshotEncryptionConfig {
+    /// Id of the key used to encrypt new snapshots
+    pub active_key_id: String,
+
+    /// Base64-encoded 256-bit keys by id. Keep retired keys here after a
+    /// rotation so older snapshots can still be read. If the active key is
+    /// missing it is read from the `SNAPSHOT_ENCRYPTION_KEY` environment variable.
+    #[serde(default)]
+    pub keys: HashMap<String, String>,
+
+    /// Read snapshots stored before encryption was enabled. Only meant for
+    /// migrating them; unencrypted snapshots are rejected otherwise.
+    #[serde(default)]
+    pub allow_unencrypted: bool,
 }
 
 /// Agent behavior configuration
@@ -211,12 +524,72 @@ pub struct AgentBehaviorConfig {
     /// Minimum quality score to extract best practice
     #[serde(default = "default_min_quality_threshold")]
     pub min_quality_for_best_practice: f32,
+
+    /// Directory of `.prompt` files overriding the built-in prompt templates
+    /// (built-in templates only when unset)
+    #[serde(default)]
+    pub prompts_dir: Option<String>,
+
+    /// Longest response, in characters, the agent returns (unlimited when unset)
+    #[serde(default)]
+    pub max_response_chars: Option<usize>,
+
+    /// How a response over `max_response_chars` is cut down
+    #[serde(default)]
+    pub response_limit_strategy: ResponseLimitStrategy,
+
+    /// Text placed before each user prompt sent to the LLM, such as a safety
+    /// preamble. Unlike the system prompt it travels inside the user message.
+    #[serde(default)]
+    pub prompt_prefix: Option<String>,
+
+    /// Text placed after each user prompt sent to the LLM, such as formatting
+    /// instructions
+    #[serde(default)]
+    pub prompt_suffix: Option<String>,
+
+    /// Most LLM retries one request may make across all of its calls; each
+    /// call retries up to `llm.max_retries` times when unset
+    #[serde(default)]
+    pub retry_budget: Option<usize>,
+
+    /// Keep the reasoning of reasoning models out of responses, returned in
+    /// `TurnOutput::reasoning` and streamed as reasoning events instead
+    #[serde(default)]
+    pub capture_reasoning: bool,
+
+    /// Start the system context of each turn with the current date and time
+    #[serde(default)]
+    pub inject_datetime: bool,
+
+    /// strftime format of the injected date and time (RFC 3339 when unset)
+    #[serde(default)]
+    pub datetime_format: Option<String>,
+
+    /// IANA timezone of the injected date and time, such as
+    /// "Europe/Berlin" (UTC when unset)
+    #[serde(default)]
+    pub timezone: Option<String>,
 }
 
 fn default_min_quality_threshold() -> f32 {
     0.8
 }
 
+/// How a response longer than `max_response_chars` is made to fit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseLimitStrategy {
+    /// Cut the response at the limit
+    #[default]
+    Truncate,
+    /// Cut the response and end it with an ellipsis, within the limit
+    TruncateWithEllipsis,
+    /// Ask the LLM to compress the response to fit, truncating with an
+    /// ellipsis if it still doesn't
+    Summarize,
+}
+
 /// Learning configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LearningConfig {
@@ -309,6 +682,10 @@ fn default_max_age_days() -> i64 {
     90
 }
 
+fn default_retry_delay_ms() -> u64 {
+    1000
+}
+
 fn default_true() -> bool {
     true
 }
@@ -464,9 +841,15 @@ impl Default for WorkflowConfig {
             enable_suspend_resume: false,
             auto_checkpoint: false,
             checkpoint_interval: 5,
+            checkpoint_jitter: 0,
+            min_checkpoint_interval_secs: 0,
             max_snapshots: 10,
             snapshot_retention_days: 7,
             debug_steps: false,
+            suspension_webhook_url: None,
+            max_total_duration_secs: None,
+            max_tool_calls: None,
+            snapshot_encryption: None,
         }
     }
 }
@@ -480,14 +863,34 @@ impl Default for LlmConfig {
             max_tokens: 4096,
             temperature: 0.7,
             timeout: 600, // 10 minutes to allow for model loading and inference with Ollama
+            generation_timeout: None,
+            stream_idle_timeout: None,
+            max_retries: 0,
+            retry_delay_ms: default_retry_delay_ms(),
             stream: false,
             task_models: HashMap::new(),
             cache: LlmCacheConfig::default(),
+            embedding_cache: EmbeddingCacheConfig::default(),
+            embedding_limit: EmbeddingLimitConfig::default(),
+            single_flight: true,
+            pricing: HashMap::new(),
+            keep_alive: None,
+            verify_models: false,
+            log_bodies: false,
+            log_body_max_len: default_log_body_max_len(),
+            seed: None,
+            top_p: None,
+            http: HttpClientConfig::default(),
         }
     }
 }
 
 impl LlmConfig {
+    /// Build the pricing table: built-in prices with configured overrides applied
+    pub fn pricing_table(&self) -> PricingTable {
+        PricingTable::default().with_overrides(self.pricing.clone())
+    }
+
     /// Get the appropriate model configuration for a given task
     pub fn get_task_model(&self, task: &str) -> TaskModelConfig {
         // Try to find a matching task model by exact name
@@ -535,6 +938,12 @@ impl Default for MemoryConfig {
             max_search_results: 10,
             similarity_threshold: 0.7,
             persistent: true,
+            dedup: None,
+            vector_index: None,
+            normalize_embeddings: false,
+            ranking: None,
+            shared_writes: false,
+            max_entries: None,
         }
     }
 }
@@ -546,6 +955,9 @@ impl Default for McpConfig {
             default_timeout: 30,
             max_concurrent_calls: 5,
             enable_caching: true,
+            max_result_bytes: default_max_result_bytes(),
+            truncate_oversized_results: true,
+            summarize_results: None,
         }
     }
 }
@@ -563,6 +975,16 @@ impl Default for AgentBehaviorConfig {
             enable_reflection: false,
             enable_option_evaluation: false,
             min_quality_for_best_practice: default_min_quality_threshold(),
+            prompts_dir: None,
+            max_response_chars: None,
+            response_limit_strategy: ResponseLimitStrategy::default(),
+            prompt_prefix: None,
+            prompt_suffix: None,
+            retry_budget: None,
+            capture_reasoning: false,
+            inject_datetime: false,
+            datetime_format: None,
+            timezone: None,
         }
     }
 }
@@ -592,48 +1014,141 @@ impl AgentConfig {
         Ok(())
     }
 
-    /// Validate the configuration
-    pub fn validate(&self) -> anyhow::Result<()> {
+    /// Validate the configuration, checking each field and the invariants
+    /// between them. Every problem found is returned, not just the first.
+    pub fn validate(&self) -> std::result::Result<(), Vec<ConfigError>> {
+        let mut errors = Vec::new();
+        let mut check = |ok: bool, field: &str, message: String| {
+            if !ok {
+                errors.push(ConfigError::new(field, message));
+            }
+        };
+
         // Validate URLs
-        if !self.llm.ollama_url.starts_with("http") {
-            return Err(anyhow::anyhow!(
-                "Invalid Ollama URL: {}",
-                self.llm.ollama_url
-            ));
-        }
+        check(
+            self.llm.ollama_url.starts_with("http"),
+            "llm.ollama_url",
+            format!("Invalid Ollama URL: {}", self.llm.ollama_url),
+        );
 
         // Validate models
-        if self.llm.text_model.is_empty() {
-            return Err(anyhow::anyhow!("Text model name cannot be empty"));
-        }
-
-        if self.llm.embedding_model.is_empty() {
-            return Err(anyhow::anyhow!("Embedding model name cannot be empty"));
-        }
+        check(
+            !self.llm.text_model.is_empty(),
+            "llm.text_model",
+            "Text model name cannot be empty".to_string(),
+        );
+        check(
+            !self.llm.embedding_model.is_empty(),
+            "llm.embedding_model",
+            "Embedding model name cannot be empty".to_string(),
+        );
+
+        check(
+            self.agent.retry_budget.is_none() || self.llm.max_retries > 0,
+            "agent.retry_budget",
+            "A retry budget has no effect unless llm.max_retries is set".to_string(),
+        );
 
         // Validate memory config
-        if self.memory.embedding_dimension == 0 {
-            return Err(anyhow::anyhow!(
-                "Embedding dimension must be greater than 0"
-            ));
-        }
+        check(
+            self.memory.embedding_dimension > 0,
+            "memory.embedding_dimension",
+            "Embedding dimension must be greater than 0".to_string(),
+        );
+        check(
+            (0.0..=1.0).contains(&self.memory.similarity_threshold),
+            "memory.similarity_threshold",
+            "Similarity threshold must be between 0.0 and 1.0".to_string(),
+        );
+        let needs_database = self.agent.use_memory && self.memory.persistent;
+        check(
+            !needs_database || self.memory.database_url.is_some(),
+            "memory.database_url",
+            "A database URL is required when memory is enabled and persistent".to_string(),
+        );
+        check(
+            self.memory.max_entries != Some(0),
+            "memory.max_entries",
+            "Max entries must be greater than 0 when set".to_string(),
+        );
 
-        if self.memory.similarity_threshold < 0.0 || self.memory.similarity_threshold > 1.0 {
-            return Err(anyhow::anyhow!(
-                "Similarity threshold must be between 0.0 and 1.0"
-            ));
+        // Validate agent config
+        check(
+            !self.agent.name.is_empty(),
+            "agent.name",
+            "Agent name cannot be empty".to_string(),
+        );
+        check(
+            self.agent.max_history_length > 0,
+            "agent.max_history_length",
+            "Max history length must be greater than 0".to_string(),
+        );
+        if let Some(timezone) = &self.agent.timezone {
+            check(
+                timezone.parse::<chrono_tz::Tz>().is_ok(),
+                "agent.timezone",
+                format!("Unknown timezone: {}", timezone),
+            );
+        }
+        if let Some(format) = &self.agent.datetime_format {
+            check(
+                !chrono::format::StrftimeItems::new(format)
+                    .any(|item| item == chrono::format::Item::Error),
+                "agent.datetime_format",
+                format!("Invalid date and time format: {}", format),
+            );
         }
 
-        // Validate agent config
-        if self.agent.name.is_empty() {
-            return Err(anyhow::anyhow!("Agent name cannot be empty"));
+        // Validate learning config
+        check(
+            self.learning.soft_limit_best_practices <= self.learning.hard_limit_best_practices,
+            "learning.soft_limit_best_practices",
+            format!(
+                "Soft limit {} exceeds hard limit {}",
+                self.learning.soft_limit_best_practices, self.learning.hard_limit_best_practices
+            ),
+        );
+        let sources = &self.learning.external_sources;
+        check(
+            sources.chunk_overlap < sources.chunk_size,
+            "learning.external_sources.chunk_overlap",
+            format!(
+                "Chunk overlap {} must be smaller than chunk size {}",
+                sources.chunk_overlap, sources.chunk_size
+            ),
+        );
+
+        // Validate HTTP client settings
+        for (field, http) in [("llm.http", &self.llm.http), ("a2a.http", &self.a2a.http)] {
+            if let Err(e) = http.validate() {
+                let message = match e {
+                    AgentError::Config(message) => message,
+                    other => other.to_string(),
+                };
+                check(false, field, message);
+            }
         }
 
-        if self.agent.max_history_length == 0 {
-            return Err(anyhow::anyhow!("Max history length must be greater than 0"));
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
         }
+    }
 
-        Ok(())
+    /// Dotted paths of the fields whose value is the default, e.g.
+    /// `llm.temperature`. Maps and unset options count as one field each.
+    pub fn describe_defaults(&self) -> Vec<String> {
+        let (Ok(current), Ok(default)) = (
+            serde_json::to_value(self),
+            serde_json::to_value(Self::default()),
+        ) else {
+            return Vec::new();
+        };
+
+        let mut defaulted = Vec::new();
+        collect_defaulted("", &current, &default, &mut defaulted);
+        defaulted
     }
 
     /// Add an MCP server configuration
@@ -647,6 +1162,59 @@ impl AgentConfig {
     }
 }
 
+/// Push the path of every leaf of `current` that equals its counterpart in
+/// `default`. Objects with no default entries are compared whole.
+fn collect_defaulted(
+    path: &str,
+    current: &serde_json::Value,
+    default: &serde_json::Value,
+    defaulted: &mut Vec<String>,
+) {
+    match (current, default) {
+        (serde_json::Value::Object(current), serde_json::Value::Object(default))
+            if !default.is_empty() =>
+        {
+            for (key, default_value) in default {
+                let field = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                if let Some(value) = current.get(key) {
+                    collect_defaulted(&field, value, default_value, defaulted);
+                }
+            }
+        }
+        _ if current == default => defaulted.push(path.to_string()),
+        _ => {}
+    }
+}
+
+/// A configuration field that failed [`AgentConfig::validate`]
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{field}: {message}")]
+pub struct ConfigError {
+    /// Dotted path of the offending field, e.g. `memory.database_url`
+    pub field: String,
+    pub message: String,
+}
+
+impl ConfigError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl From<Vec<ConfigError>> for AgentError {
+    fn from(errors: Vec<ConfigError>) -> Self {
+        let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        AgentError::Config(errors.join("; "))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -676,6 +1244,66 @@ mod tests {
         assert!(config.validate().is_err());
     }
 
+    #[test]
+    fn test_valid_config_reports_defaults() {
+        let mut config = AgentConfig::default();
+        config.llm.temperature = 0.2;
+        config.agent.name = "Planner".to_string();
+        assert_eq!(config.validate(), Ok(()));
+
+        let defaulted = config.describe_defaults();
+        assert!(defaulted.contains(&"llm.text_model".to_string()));
+        assert!(defaulted.contains(&"memory.database_url".to_string()));
+        assert!(defaulted.contains(&"llm.task_models".to_string()));
+        assert!(!defaulted.contains(&"llm.temperature".to_string()));
+        assert!(!defaulted.contains(&"agent.name".to_string()));
+        assert!(!defaulted.contains(&"llm".to_string()));
+    }
+
+    #[test]
+    fn test_conflicting_memory_settings_report_every_error() {
+        let mut config = AgentConfig::default();
+        config.agent.use_memory = true;
+        config.memory.persistent = true;
+        config.memory.database_url = None;
+        config.memory.similarity_threshold = 1.5;
+        config.llm.ollama_url = "localhost:11434".to_string();
+
+        let errors = config.validate().unwrap_err();
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            [
+                "llm.ollama_url",
+                "memory.similarity_threshold",
+                "memory.database_url"
+            ]
+        );
+
+        // Non-persistent memory needs no database
+        config.memory.persistent = false;
+        config.memory.similarity_threshold = 0.7;
+        config.llm.ollama_url = "http://127.0.0.1:11434".to_string();
+        assert!(config.validate().is_ok());
+
+        let error = AgentError::from(errors);
+        assert!(error.to_string().contains("memory.database_url"));
+    }
+
+    #[test]
+    fn test_datetime_settings_are_validated() {
+        let mut config = AgentConfig::default();
+        config.agent.timezone = Some("America/New_York".to_string());
+        config.agent.datetime_format = Some("%Y-%m-%d %H:%M %Z".to_string());
+        assert!(config.validate().is_ok());
+
+        config.agent.timezone = Some("Mars/Olympus_Mons".to_string());
+        config.agent.datetime_format = Some("%Y-%Q".to_string());
+        let errors = config.validate().unwrap_err();
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, ["agent.timezone", "agent.datetime_format"]);
+    }
+
     #[test]
     fn test_mcp_server_management() {
         let mut config = AgentConfig::default();
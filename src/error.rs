@@ -1,12 +1,14 @@
 //! Error handling for the AI agent
 
+use std::sync::Arc;
 use thiserror::Error;
 
 /// Result type alias for the AI agent
 pub type Result<T> = std::result::Result<T, AgentError>;
 
-/// Main error type for the AI agent
-#[derive(Error, Debug)]
+/// Main error type for the AI agent. Errors from other crates are held in an
+/// `Arc` so that an error can be cloned, e.g. for every caller sharing a call.
+#[derive(Error, Debug, Clone)]
 pub enum AgentError {
     #[error("LLM error: {0}")]
     Llm(#[from] LlmError),
@@ -24,19 +26,19 @@ pub enum AgentError {
     Workflow(String),
 
     #[error("IO error: {0}")]
-    Io(#[from] std::io::Error),
+    Io(#[source] Arc<std::io::Error>),
 
     #[error("Serialization error: {0}")]
-    Serialization(#[from] serde_json::Error),
+    Serialization(#[source] Arc<serde_json::Error>),
 
     #[error("HTTP error: {0}")]
-    Http(#[from] reqwest::Error),
+    Http(#[source] Arc<reqwest::Error>),
 
     #[error("Database error: {0}")]
-    Database(#[from] sqlx::Error),
+    Database(#[source] Arc<sqlx::Error>),
 
     #[error("Generic error: {0}")]
-    Generic(#[from] anyhow::Error),
+    Generic(#[source] Arc<anyhow::Error>),
 
     #[error("A2A error: {0}")]
     A2A(String),
@@ -46,10 +48,60 @@ pub enum AgentError {
 
     #[error("Not found: {0}")]
     NotFound(String),
+
+    #[error("Timed out: {0}")]
+    Timeout(String),
+
+    #[error("Loop over step '{step}' exceeded {iterations} iterations")]
+    LoopLimitExceeded { step: String, iterations: usize },
+
+    #[error("Schema validation failed for {target}: missing {missing:?}, mistyped {mistyped:?}")]
+    SchemaValidation {
+        target: String,
+        missing: Vec<String>,
+        mistyped: Vec<String>,
+    },
+
+    #[error("Version conflict on {resource}: expected version {expected}, found {actual}")]
+    Conflict {
+        resource: String,
+        expected: u64,
+        actual: u64,
+    },
+}
+
+impl From<std::io::Error> for AgentError {
+    fn from(error: std::io::Error) -> Self {
+        AgentError::Io(Arc::new(error))
+    }
+}
+
+impl From<serde_json::Error> for AgentError {
+    fn from(error: serde_json::Error) -> Self {
+        AgentError::Serialization(Arc::new(error))
+    }
+}
+
+impl From<reqwest::Error> for AgentError {
+    fn from(error: reqwest::Error) -> Self {
+        AgentError::Http(Arc::new(error))
+    }
+}
+
+impl From<sqlx::Error> for AgentError {
+    fn from(error: sqlx::Error) -> Self {
+        AgentError::Database(Arc::new(error))
+    }
+}
+
+impl From<anyhow::Error> for AgentError {
+    fn from(error: anyhow::Error) -> Self {
+        AgentError::Generic(Arc::new(error))
+    }
 }
 
 /// Errors related to language model operations
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone)]
 pub enum LlmError {
     #[error("Connection failed: {0}")]
     ConnectionFailed(String),
@@ -81,12 +133,84 @@ pub enum LlmError {
     #[error("Server error: {0}")]
     ServerError(String),
 
+    #[error("Context window exceeded: ~{estimated} prompt tokens, limit {limit}")]
+    ContextWindowExceeded { estimated: usize, limit: usize },
+
+    #[error("Embedding input too long: {tokens} tokens, limit {limit}")]
+    EmbeddingInputTooLong { tokens: usize, limit: usize },
+
+    #[error("Provider error: {0}")]
+    Provider(ProviderError),
+
     #[error("Unknown error: {0}")]
     Unknown(String),
 }
 
+/// Error reported by an LLM provider's API, parsed from its error body
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{kind:?} (HTTP {http_status}): {message}")]
+pub struct ProviderError {
+    pub kind: ProviderErrorKind,
+    pub message: String,
+    pub http_status: u16,
+    /// The provider's own error code or type, such as `insufficient_quota`
+    pub code: Option<String>,
+    /// Whether the same request may succeed if retried later
+    pub retryable: bool,
+}
+
+/// What went wrong at the provider, independent of how it reports it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProviderErrorKind {
+    /// Missing or invalid API key
+    Authentication,
+    /// The key may not use this model or endpoint
+    PermissionDenied,
+    /// The account is out of credit or over its plan's quota
+    InsufficientQuota,
+    /// Too many requests or tokens in a short time
+    RateLimited,
+    /// The provider is temporarily overloaded
+    Overloaded,
+    /// The prompt does not fit the model's context window
+    ContextLengthExceeded,
+    /// The request was refused by the provider's content policy
+    ContentFiltered,
+    /// Unknown model or endpoint
+    NotFound,
+    /// Malformed or unsupported request
+    InvalidRequest,
+    /// Internal error at the provider
+    ServerError,
+    Unknown,
+}
+
+impl ProviderErrorKind {
+    /// Whether errors of this kind are transient
+    pub fn is_retryable(self) -> bool {
+        matches!(
+            self,
+            Self::RateLimited | Self::Overloaded | Self::ServerError
+        )
+    }
+}
+
+impl From<ProviderError> for LlmError {
+    /// Rate limits and failures on the provider's side keep their own
+    /// variants; every other provider error is passed on whole
+    fn from(error: ProviderError) -> Self {
+        match error.kind {
+            ProviderErrorKind::RateLimited => LlmError::RateLimited,
+            ProviderErrorKind::Overloaded | ProviderErrorKind::ServerError => {
+                LlmError::ServerError(error.to_string())
+            }
+            _ => LlmError::Provider(error),
+        }
+    }
+}
+
 /// Errors related to memory/vector store operations
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone)]
 pub enum MemoryError {
     #[error("Store not initialized")]
     NotInitialized,
@@ -105,7 +229,7 @@ pub enum MemoryError {
 }
 
 /// Errors related to MCP operations
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone)]
 pub enum McpError {
     #[error("Server connection failed: {0}")]
     ConnectionFailed(String),
@@ -119,6 +243,13 @@ pub enum McpError {
     #[error("Invalid tool parameters: {0}")]
     InvalidParameters(String),
 
+    #[error("Invalid arguments for tool {tool}: missing {missing:?}, mistyped {mistyped:?}")]
+    InvalidArguments {
+        tool: String,
+        missing: Vec<String>,
+        mistyped: Vec<String>,
+    },
+
     #[error("Protocol error: {0}")]
     ProtocolError(String),
 
@@ -133,9 +264,17 @@ impl AgentError {
             self,
             AgentError::Llm(LlmError::Timeout)
                 | AgentError::Llm(LlmError::ConnectionFailed(_))
+                | AgentError::Llm(LlmError::RateLimited)
+                | AgentError::Llm(LlmError::ServerError(_))
+                | AgentError::Llm(LlmError::Provider(ProviderError {
+                    retryable: true,
+                    ..
+                }))
                 | AgentError::Mcp(McpError::ConnectionFailed(_))
                 | AgentError::Mcp(McpError::Timeout(_))
                 | AgentError::Http(_)
+                | AgentError::Network(_)
+                | AgentError::Timeout(_)
         )
     }
 
@@ -146,7 +285,9 @@ impl AgentError {
             AgentError::Memory(_) => "memory",
             AgentError::Mcp(_) => "mcp",
             AgentError::Config(_) => "config",
-            AgentError::Workflow(_) => "workflow",
+            AgentError::Workflow(_)
+            | AgentError::LoopLimitExceeded { .. }
+            | AgentError::SchemaValidation { .. } => "workflow",
             AgentError::Io(_) => "io",
             AgentError::Serialization(_) => "serialization",
             AgentError::Http(_) => "http",
@@ -155,6 +296,8 @@ impl AgentError {
             AgentError::A2A(_) => "a2a",
             AgentError::Network(_) => "network",
             AgentError::NotFound(_) => "not_found",
+            AgentError::Timeout(_) => "timeout",
+            AgentError::Conflict { .. } => "conflict",
         }
     }
 }
@@ -170,6 +313,32 @@ mod tests {
 
         let config_error = AgentError::Config("invalid config".to_string());
         assert!(!config_error.is_retryable());
+
+        let provider_error = |kind: ProviderErrorKind, http_status: u16| {
+            AgentError::Llm(LlmError::from(ProviderError {
+                kind,
+                message: "upstream failed".to_string(),
+                http_status,
+                code: None,
+                retryable: kind.is_retryable(),
+            }))
+        };
+        let rate_limited = provider_error(ProviderErrorKind::RateLimited, 429);
+        assert!(matches!(
+            rate_limited,
+            AgentError::Llm(LlmError::RateLimited)
+        ));
+        assert!(rate_limited.is_retryable());
+
+        let server_error = provider_error(ProviderErrorKind::Overloaded, 503);
+        assert!(
+            matches!(server_error, AgentError::Llm(LlmError::ServerError(ref message)) if message.contains("upstream failed"))
+        );
+        assert!(server_error.is_retryable());
+
+        let quota = provider_error(ProviderErrorKind::InsufficientQuota, 429);
+        assert!(matches!(quota, AgentError::Llm(LlmError::Provider(_))));
+        assert!(!quota.is_retryable());
     }
 
     #[test]
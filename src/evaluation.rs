@@ -0,0 +1,390 @@
+//! LLM-as-judge evaluation runner
+//!
+//! [`Evaluator`] runs every item of an [`EvalDataset`] through a target (usually
+//! an [`Agent`]), asks a judge LLM to grade each output against the expected
+//! answer, and records the grades as [`EvalScore`]s in [`UnifiedStorage`].
+
+use crate::agent::Agent;
+use crate::error::{AgentError, Result};
+use crate::ids::new_id;
+use crate::llm::{system_message, user_message, LlmClient};
+use crate::unified_storage::{EvalDataset, EvalItem, EvalScore, UnifiedStorage};
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::SystemTime;
+use tracing::{info, warn};
+
+/// Scorer name recorded on every score produced by the judge
+pub const JUDGE_SCORER_NAME: &str = "llm_judge";
+
+const JUDGE_SYSTEM_PROMPT: &str = "You grade answers produced by an AI assistant. \
+Compare the actual answer with the expected answer and judge whether it is correct \
+and complete. Respond with JSON only: {\"score\": <number from 0 to 1>, \"rationale\": \"<one or two sentences>\"}";
+
+/// System under evaluation
+#[async_trait]
+pub trait EvalTarget: Send + Sync {
+    /// Produce a response for a dataset input
+    async fn respond(&self, input: &str) -> Result<String>;
+}
+
+/// Items run one at a time on the shared agent, each with a fresh conversation
+#[async_trait]
+impl EvalTarget for tokio::sync::Mutex<Agent> {
+    async fn respond(&self, input: &str) -> Result<String> {
+        let mut agent = self.lock().await;
+        agent.clear_conversation();
+        agent.process(input).await
+    }
+}
+
+/// Grade returned by the judge
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Judgement {
+    pub score: f64,
+    #[serde(default)]
+    pub rationale: String,
+}
+
+impl Judgement {
+    /// Parse the judge's reply, tolerating prose or code fences around the JSON
+    pub fn parse(text: &str) -> Result<Self> {
+        let json = match (text.find('{'), text.rfind('}')) {
+            (Some(start), Some(end)) if start < end => &text[start..=end],
+            _ => {
+                return Err(AgentError::from(anyhow::anyhow!(
+                    "Judge response contains no JSON object: {}",
+                    text
+                )))
+            }
+        };
+
+        let mut judgement: Judgement = serde_json::from_str(json)?;
+        if !judgement.score.is_finite() {
+            return Err(AgentError::from(anyhow::anyhow!(
+                "Judge returned a non-numeric score"
+            )));
+        }
+        judgement.score = judgement.score.clamp(0.0, 1.0);
+        Ok(judgement)
+    }
+}
+
+/// Outcome of one evaluation run
+#[derive(Debug, Clone)]
+pub struct EvalRunSummary {
+    pub run_id: String,
+
+    /// Stored scores, in dataset order after sampling
+    pub scores: Vec<EvalScore>,
+
+    /// Items the judge could not grade; these have no stored score
+    pub failed_items: Vec<String>,
+}
+
+impl EvalRunSummary {
+    pub fn mean_score(&self) -> Option<f64> {
+        if self.scores.is_empty() {
+            return None;
+        }
+        Some(self.scores.iter().map(|s| s.score).sum::<f64>() / self.scores.len() as f64)
+    }
+}
+
+/// Runs datasets against a target and stores judge scores
+pub struct Evaluator {
+    judge: Arc<dyn LlmClient>,
+    storage: Arc<dyn UnifiedStorage>,
+    metric_name: String,
+    concurrency: usize,
+    seed: u64,
+    sample_size: Option<usize>,
+}
+
+impl Evaluator {
+    pub fn new(judge: Arc<dyn LlmClient>, storage: Arc<dyn UnifiedStorage>) -> Self {
+        Self {
+            judge,
+            storage,
+            metric_name: "correctness".to_string(),
+            concurrency: 1,
+            seed: 0,
+            sample_size: None,
+        }
+    }
+
+    /// Metric name recorded on each score
+    pub fn with_metric_name(mut self, metric_name: &str) -> Self {
+        self.metric_name = metric_name.to_string();
+        self
+    }
+
+    /// Maximum number of items evaluated at once
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Seed for item ordering and sampling; the same seed selects the same items
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Evaluate only this many items, chosen by the seed
+    pub fn with_sample_size(mut self, sample_size: usize) -> Self {
+        self.sample_size = Some(sample_size);
+        self
+    }
+
+    /// Items to evaluate: a seeded shuffle of the dataset, truncated to the sample size
+    pub fn select_items<'a>(&self, dataset: &'a EvalDataset) -> Vec<&'a EvalItem> {
+        let mut items: Vec<&EvalItem> = dataset.items.iter().collect();
+
+        // Fisher-Yates with splitmix64 so the order is stable across platforms and releases
+        let mut state = self.seed;
+        for i in (1..items.len()).rev() {
+            let j = (splitmix64(&mut state) % (i as u64 + 1)) as usize;
+            items.swap(i, j);
+        }
+
+        if let Some(sample_size) = self.sample_size {
+            items.truncate(sample_size);
+        }
+        items
+    }
+
+    /// Evaluate a dataset and persist one score per graded item
+    pub async fn run(
+        &self,
+        dataset: &EvalDataset,
+        target: Arc<dyn EvalTarget>,
+    ) -> Result<EvalRunSummary> {
+        let run_id = new_id().to_string();
+        let items = self.select_items(dataset);
+        info!(
+            "Starting evaluation run {} on dataset '{}' ({} items, concurrency {})",
+            run_id,
+            dataset.name,
+            items.len(),
+            self.concurrency
+        );
+
+        let outcomes: Vec<(&EvalItem, Result<EvalScore>)> = stream::iter(items)
+            .map(|item| {
+                let target = target.clone();
+                let run_id = &run_id;
+                async move {
+                    let score = self.evaluate_item(run_id, dataset, item, target).await;
+                    (item, score)
+                }
+            })
+            .buffered(self.concurrency)
+            .collect()
+            .await;
+
+        let mut summary = EvalRunSummary {
+            run_id: run_id.clone(),
+            scores: Vec::new(),
+            failed_items: Vec::new(),
+        };
+        for (item, outcome) in outcomes {
+            match outcome {
+                Ok(score) => {
+                    self.storage.store_eval_score(&score).await?;
+                    summary.scores.push(score);
+                }
+                Err(e) => {
+                    warn!("Could not grade eval item {}: {}", item.item_id, e);
+                    summary.failed_items.push(item.item_id.clone());
+                }
+            }
+        }
+
+        info!(
+            "Finished evaluation run {}: {} scored, {} failed, mean {:?}",
+            run_id,
+            summary.scores.len(),
+            summary.failed_items.len(),
+            summary.mean_score()
+        );
+        Ok(summary)
+    }
+
+    async fn evaluate_item(
+        &self,
+        run_id: &str,
+        dataset: &EvalDataset,
+        item: &EvalItem,
+        target: Arc<dyn EvalTarget>,
+    ) -> Result<EvalScore> {
+        let mut metadata = HashMap::from([
+            ("dataset_id".to_string(), dataset.dataset_id.clone()),
+            ("seed".to_string(), self.seed.to_string()),
+        ]);
+
+        // A target failure is a legitimate wrong answer, not an evaluation failure
+        let judgement = match target.respond(&item.input).await {
+            Ok(output) => {
+                let judgement = self.judge(item, &output).await?;
+                metadata.insert("output".to_string(), output);
+                judgement
+            }
+            Err(e) => {
+                metadata.insert("target_error".to_string(), e.to_string());
+                Judgement {
+                    score: 0.0,
+                    rationale: format!("Target failed to respond: {}", e),
+                }
+            }
+        };
+
+        Ok(EvalScore {
+            score_id: new_id().to_string(),
+            run_id: run_id.to_string(),
+            item_id: item.item_id.clone(),
+            resource_id: dataset.resource_id.clone(),
+            metric_name: self.metric_name.clone(),
+            score: judgement.score,
+            reason: judgement.rationale,
+            scorer_name: JUDGE_SCORER_NAME.to_string(),
+            metadata,
+            scored_at: SystemTime::now(),
+        })
+    }
+
+    async fn judge(&self, item: &EvalItem, output: &str) -> Result<Judgement> {
+        let prompt = format!(
+            "Question:\n{}\n\nExpected answer:\n{}\n\nActual answer:\n{}",
+            item.input, item.expected, output
+        );
+        let messages = [system_message(JUDGE_SYSTEM_PROMPT), user_message(prompt)];
+        let response = self.judge.generate(&messages).await?;
+        Judgement::parse(&response.text)
+    }
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::mock::MockLlm;
+    use crate::unified_storage::{InMemoryUnifiedStorage, ResourceId};
+
+    /// Judge that always returns the same grade
+    fn fixed_judge(grade: &str) -> Arc<MockLlm> {
+        Arc::new(MockLlm::new().with_reply(grade))
+    }
+
+    struct EchoTarget;
+
+    #[async_trait]
+    impl EvalTarget for EchoTarget {
+        async fn respond(&self, input: &str) -> Result<String> {
+            Ok(input.to_uppercase())
+        }
+    }
+
+    fn dataset(size: usize) -> EvalDataset {
+        EvalDataset {
+            dataset_id: "ds".to_string(),
+            name: "arithmetic".to_string(),
+            description: String::new(),
+            resource_id: ResourceId::new("test", "evals"),
+            created_at: SystemTime::now(),
+            version: "1".to_string(),
+            metadata: HashMap::new(),
+            items: (0..size)
+                .map(|i| EvalItem::new(&format!("what is {} + {}", i, i), &(i * 2).to_string()))
+                .collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_evaluator_persists_judge_scores() {
+        let storage = Arc::new(InMemoryUnifiedStorage::new());
+        let evaluator = Evaluator::new(
+            fixed_judge(
+                "Here is my grade:\n```json\n{\"score\": 0.75, \"rationale\": \"Mostly right\"}\n```",
+            ),
+            storage.clone(),
+        )
+        .with_concurrency(4);
+
+        let summary = evaluator
+            .run(&dataset(6), Arc::new(EchoTarget))
+            .await
+            .unwrap();
+
+        assert_eq!(summary.scores.len(), 6);
+        assert!(summary.failed_items.is_empty());
+        assert_eq!(summary.mean_score(), Some(0.75));
+
+        let stored = storage.get_eval_scores(&summary.run_id).await.unwrap();
+        assert_eq!(stored.len(), 6);
+        for score in stored {
+            assert_eq!(score.score, 0.75);
+            assert_eq!(score.reason, "Mostly right");
+            assert_eq!(score.scorer_name, JUDGE_SCORER_NAME);
+            assert!(score.metadata["output"].starts_with("WHAT IS"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unparseable_judgement_is_not_stored() {
+        let storage = Arc::new(InMemoryUnifiedStorage::new());
+        let evaluator = Evaluator::new(fixed_judge("looks fine to me"), storage.clone());
+
+        let summary = evaluator
+            .run(&dataset(2), Arc::new(EchoTarget))
+            .await
+            .unwrap();
+
+        assert_eq!(summary.failed_items.len(), 2);
+        assert!(storage
+            .get_eval_scores(&summary.run_id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_seeded_sampling_is_deterministic() {
+        let dataset = dataset(20);
+        let storage = Arc::new(InMemoryUnifiedStorage::new());
+        let evaluator = |seed| {
+            Evaluator::new(fixed_judge("{}"), storage.clone())
+                .with_seed(seed)
+                .with_sample_size(5)
+        };
+
+        let ids = |seed| -> Vec<String> {
+            evaluator(seed)
+                .select_items(&dataset)
+                .iter()
+                .map(|item| item.item_id.clone())
+                .collect()
+        };
+
+        assert_eq!(ids(7).len(), 5);
+        assert_eq!(ids(7), ids(7));
+        assert_ne!(ids(7), ids(8));
+    }
+
+    #[test]
+    fn test_judgement_parse_clamps_score() {
+        let judgement = Judgement::parse("{\"score\": 1.4, \"rationale\": \"perfect\"}").unwrap();
+        assert_eq!(judgement.score, 1.0);
+        assert!(Judgement::parse("no grade").is_err());
+    }
+}
@@ -0,0 +1,222 @@
+//! Outbound HTTP client settings
+//!
+//! Networks that only allow outbound traffic through a proxy, or that
+//! terminate TLS with an internal certificate authority, need every HTTP
+//! client the agent builds to be told about it. [`HttpClientConfig`] holds
+//! those settings and applies them to a [`reqwest::ClientBuilder`]; the LLM
+//! providers, the Ollama client and the A2A client all build their clients
+//! through it.
+
+use crate::error::{AgentError, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Proxy, trusted certificates and connection settings for outbound HTTP
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HttpClientConfig {
+    /// Proxy URL all requests are routed through, e.g. `http://proxy.corp:3128`.
+    /// Credentials may be given in the URL.
+    #[serde(default)]
+    pub proxy: Option<String>,
+
+    /// PEM file of additional CA certificates to trust, on top of the
+    /// built-in roots
+    #[serde(default)]
+    pub ca_cert_path: Option<PathBuf>,
+
+    /// Connection timeout in seconds; the client's default applies when unset
+    #[serde(default)]
+    pub connect_timeout: Option<u64>,
+
+    /// Seconds an idle pooled connection is kept open; the client's default
+    /// applies when unset
+    #[serde(default)]
+    pub pool_idle_timeout: Option<u64>,
+
+    /// Most idle connections kept open to each host
+    #[serde(default)]
+    pub pool_max_idle_per_host: Option<usize>,
+
+    /// Seconds between TCP keep-alive probes, so idle pooled connections are
+    /// not dropped by middleboxes; disabled when unset
+    #[serde(default)]
+    pub tcp_keepalive: Option<u64>,
+
+    /// Speak HTTP/2 to plain-HTTP peers without negotiating it first. Over
+    /// TLS, HTTP/2 is used whenever the peer offers it.
+    #[serde(default)]
+    pub http2_prior_knowledge: bool,
+
+    /// Seconds between HTTP/2 pings keeping multiplexed connections alive
+    #[serde(default)]
+    pub http2_keep_alive_interval: Option<u64>,
+}
+
+impl HttpClientConfig {
+    /// Apply these settings to `builder`
+    pub fn apply(&self, mut builder: reqwest::ClientBuilder) -> Result<reqwest::ClientBuilder> {
+        if let Some(proxy) = &self.proxy {
+            let proxy = reqwest::Proxy::all(proxy.as_str()).map_err(|e| {
+                AgentError::Config(format!("Invalid HTTP proxy '{}': {}", proxy, e))
+            })?;
+            builder = builder.proxy(proxy);
+        }
+
+        if let Some(path) = &self.ca_cert_path {
+            for certificate in load_certificates(path)? {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        if let Some(secs) = self.connect_timeout {
+            builder = builder.connect_timeout(Duration::from_secs(secs));
+        }
+
+        if let Some(secs) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(Duration::from_secs(secs));
+        }
+
+        if let Some(max_idle) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max_idle);
+        }
+
+        if let Some(secs) = self.tcp_keepalive {
+            builder = builder.tcp_keepalive(Duration::from_secs(secs));
+        }
+
+        if self.http2_prior_knowledge {
+            builder = builder.http2_prior_knowledge();
+        }
+
+        if let Some(secs) = self.http2_keep_alive_interval {
+            builder = builder
+                .http2_keep_alive_interval(Duration::from_secs(secs))
+                .http2_keep_alive_while_idle(true);
+        }
+
+        Ok(builder)
+    }
+
+    /// Build a client with these settings and a request timeout
+    pub fn build_client(&self, timeout: Duration) -> Result<reqwest::Client> {
+        self.apply(reqwest::Client::builder().timeout(timeout))?
+            .build()
+            .map_err(|e| AgentError::Config(format!("Failed to create HTTP client: {}", e)))
+    }
+
+    /// Check that the proxy URL parses and the CA file can be loaded
+    pub fn validate(&self) -> Result<()> {
+        self.apply(reqwest::Client::builder()).map(|_| ())
+    }
+}
+
+fn load_certificates(path: &PathBuf) -> Result<Vec<reqwest::Certificate>> {
+    let pem = std::fs::read(path).map_err(|e| {
+        AgentError::Config(format!(
+            "Failed to read CA certificate '{}': {}",
+            path.display(),
+            e
+        ))
+    })?;
+    let certificates = reqwest::Certificate::from_pem_bundle(&pem).map_err(|e| {
+        AgentError::Config(format!(
+            "Invalid CA certificate '{}': {}",
+            path.display(),
+            e
+        ))
+    })?;
+    if certificates.is_empty() {
+        return Err(AgentError::Config(format!(
+            "No certificates found in '{}'",
+            path.display()
+        )));
+    }
+    Ok(certificates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Accepts one connection, records its request line and answers 200
+    async fn mock_proxy() -> (String, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = socket.read(&mut buf).await.unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+            }
+            socket
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\ncontent-length: 7\r\nconnection: close\r\n\r\nproxied",
+                )
+                .await
+                .unwrap();
+            let request = String::from_utf8_lossy(&request).to_string();
+            request.lines().next().unwrap_or_default().to_string()
+        });
+        (url, handle)
+    }
+
+    #[tokio::test]
+    async fn test_requests_are_routed_through_proxy() {
+        let (proxy_url, proxy) = mock_proxy().await;
+        let config = HttpClientConfig {
+            proxy: Some(proxy_url),
+            connect_timeout: Some(5),
+            pool_idle_timeout: Some(30),
+            ..Default::default()
+        };
+
+        let client = config.build_client(Duration::from_secs(10)).unwrap();
+        let response = client
+            .get("http://llm.internal.example/v1/models")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.text().await.unwrap(), "proxied");
+
+        // Proxied plain HTTP requests carry the absolute target URL
+        assert_eq!(
+            proxy.await.unwrap(),
+            "GET http://llm.internal.example/v1/models HTTP/1.1"
+        );
+    }
+
+    #[test]
+    fn test_invalid_settings_are_config_errors() {
+        assert!(HttpClientConfig::default().validate().is_ok());
+
+        let bad_proxy = HttpClientConfig {
+            proxy: Some("not a url".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(bad_proxy.validate(), Err(AgentError::Config(_))));
+
+        let missing_ca = HttpClientConfig {
+            ca_cert_path: Some(PathBuf::from("/nonexistent/ca.pem")),
+            ..Default::default()
+        };
+        let err = missing_ca.validate().unwrap_err();
+        assert!(err.to_string().contains("Failed to read CA certificate"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let not_pem = dir.path().join("ca.pem");
+        std::fs::write(&not_pem, "not a certificate").unwrap();
+        let bad_ca = HttpClientConfig {
+            ca_cert_path: Some(not_pem),
+            ..Default::default()
+        };
+        assert!(matches!(bad_ca.validate(), Err(AgentError::Config(_))));
+    }
+}
@@ -0,0 +1,165 @@
+//! Identifier generation
+//!
+//! Every id the crate creates, from snapshots and tool calls to memories,
+//! evaluation runs and A2A messages, comes from [`new_id`], which asks the
+//! installed [`IdGenerator`]. Ids are random
+//! unless another generator is installed: [`set_id_generator`] replaces it for
+//! the whole process, e.g. to reproduce a run, and [`scoped_id_generator`]
+//! replaces it on the current thread only, so a test can assert stable ids
+//! without affecting tests running alongside it.
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use uuid::Uuid;
+
+/// Source of new identifiers
+pub trait IdGenerator: Send + Sync {
+    fn next_id(&self) -> Uuid;
+}
+
+/// Random (v4) ids, the default
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RandomIdGenerator;
+
+impl IdGenerator for RandomIdGenerator {
+    fn next_id(&self) -> Uuid {
+        Uuid::new_v4()
+    }
+}
+
+/// Ids counting up from a starting number: 1 is
+/// `00000000-0000-0000-0000-000000000001`, and so on
+#[derive(Debug)]
+pub struct SequentialIdGenerator {
+    next: AtomicU64,
+}
+
+impl SequentialIdGenerator {
+    /// Generator whose first id is 1
+    pub fn new() -> Self {
+        Self::starting_at(1)
+    }
+
+    pub fn starting_at(first: u64) -> Self {
+        Self {
+            next: AtomicU64::new(first),
+        }
+    }
+}
+
+impl Default for SequentialIdGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IdGenerator for SequentialIdGenerator {
+    fn next_id(&self) -> Uuid {
+        Uuid::from_u128(self.next.fetch_add(1, Ordering::Relaxed) as u128)
+    }
+}
+
+/// Generator for the whole process, random when unset
+static GENERATOR: RwLock<Option<Arc<dyn IdGenerator>>> = RwLock::new(None);
+
+thread_local! {
+    /// Generators installed by [`scoped_id_generator`], innermost last
+    static SCOPED: RefCell<Vec<Arc<dyn IdGenerator>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A new id from the current thread's scoped generator, else the process
+/// generator
+pub fn new_id() -> Uuid {
+    if let Some(generator) = SCOPED.with(|scoped| scoped.borrow().last().cloned()) {
+        return generator.next_id();
+    }
+    match GENERATOR.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        Some(generator) => generator.next_id(),
+        None => Uuid::new_v4(),
+    }
+}
+
+/// Generate ids with `generator` across the process
+pub fn set_id_generator(generator: Arc<dyn IdGenerator>) {
+    *GENERATOR.write().unwrap_or_else(|e| e.into_inner()) = Some(generator);
+}
+
+/// Go back to random ids across the process
+pub fn reset_id_generator() {
+    *GENERATOR.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Generate ids on this thread with `generator` until the guard is dropped.
+/// A single-threaded async runtime, like the one `#[tokio::test]` starts,
+/// runs its tasks on the thread that holds the guard.
+pub fn scoped_id_generator(generator: Arc<dyn IdGenerator>) -> ScopedIdGenerator {
+    SCOPED.with(|scoped| scoped.borrow_mut().push(generator));
+    ScopedIdGenerator { _private: () }
+}
+
+/// Restores the previous generator of its thread when dropped
+#[must_use = "the generator is uninstalled when the guard is dropped"]
+pub struct ScopedIdGenerator {
+    _private: (),
+}
+
+impl Drop for ScopedIdGenerator {
+    fn drop(&mut self) {
+        SCOPED.with(|scoped| scoped.borrow_mut().pop());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::user_message;
+    use crate::saga::SagaContext;
+    use crate::tools::datetime::DateTimeTool;
+    use crate::workflow::{
+        SuspendReason, ToolAnalysisStep, WorkflowContext, WorkflowDecision, WorkflowEngine,
+        WorkflowStep,
+    };
+
+    #[tokio::test]
+    async fn test_sequential_generator_gives_predictable_workflow_ids() {
+        let _ids = scoped_id_generator(Arc::new(SequentialIdGenerator::new()));
+
+        let first = SagaContext::new("order".to_string(), WorkflowContext::new(10));
+        let second = SagaContext::new("refund".to_string(), WorkflowContext::new(10));
+        assert_eq!(first.id.to_string(), "00000000-0000-0000-0000-000000000001");
+        assert_eq!(second.id, Uuid::from_u128(2));
+
+        let snapshot = WorkflowEngine::new()
+            .create_snapshot(&WorkflowContext::new(10), 0, SuspendReason::Manual)
+            .await
+            .unwrap();
+        assert_eq!(snapshot.id, Uuid::from_u128(3));
+    }
+
+    #[tokio::test]
+    async fn test_sequential_generator_gives_predictable_tool_call_ids() {
+        let _ids = scoped_id_generator(Arc::new(SequentialIdGenerator::new()));
+
+        let mut context = WorkflowContext::new(10);
+        context.available_tools.push("system_info".to_string());
+        context.add_message(user_message("Show me the system info"));
+        let decision = ToolAnalysisStep.execute(&mut context).await.unwrap();
+        let WorkflowDecision::ExecuteTools(calls) = decision else {
+            panic!("expected a tool call, got {:?}", decision);
+        };
+        assert_eq!(calls[0].id, Uuid::from_u128(1).to_string());
+
+        let result = DateTimeTool::execute(&serde_json::json!({ "operation": "now" }));
+        assert_eq!(result.id, Uuid::from_u128(2).to_string());
+    }
+
+    #[test]
+    fn test_ids_are_random_outside_the_scope() {
+        {
+            let _ids = scoped_id_generator(Arc::new(SequentialIdGenerator::starting_at(7)));
+            assert_eq!(new_id(), Uuid::from_u128(7));
+        }
+        assert_ne!(new_id(), new_id());
+    }
+}
@@ -1,15 +1,92 @@
 //! Content chunking for text processing
 
-use super::types::{IngestionConfig, KnowledgeChunk};
+use super::loader::load_path;
+use super::types::{
+    DocumentFormat, IngestionConfig, IngestionDocument, IngestionProgress,
+    IngestionProgressCallback, KnowledgeChunk,
+};
+use crate::error::Result;
+use std::path::Path;
+use tracing::debug;
 
 /// Content chunker for splitting text into manageable pieces
 pub struct ContentChunker {
     config: IngestionConfig,
+    progress: Option<IngestionProgressCallback>,
 }
 
 impl ContentChunker {
     pub fn new(config: IngestionConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            progress: None,
+        }
+    }
+
+    /// Report progress to `callback` after each document in
+    /// [`Self::chunk_documents`], [`Self::ingest_path`] and
+    /// [`AdaptiveKnowledgeManager::ingest_all`](super::AdaptiveKnowledgeManager::ingest_all)
+    pub fn with_progress(mut self, callback: IngestionProgressCallback) -> Self {
+        self.progress = Some(callback);
+        self
+    }
+
+    pub(crate) fn progress(&self) -> Option<&IngestionProgressCallback> {
+        self.progress.as_ref()
+    }
+
+    /// Chunk a batch of documents according to their format
+    pub fn chunk_documents(&self, documents: &[IngestionDocument]) -> Vec<KnowledgeChunk> {
+        let total_bytes = documents.iter().map(|doc| doc.content.len()).sum();
+        let mut progress = IngestionProgress {
+            current_source: String::new(),
+            documents_processed: 0,
+            total_documents: documents.len(),
+            chunks_produced: 0,
+            bytes_processed: 0,
+            total_bytes,
+        };
+
+        let mut chunks = Vec::new();
+        for doc in documents {
+            let doc_chunks = self.chunk_document(doc);
+            debug!("Chunked {} into {} chunks", doc.source, doc_chunks.len());
+
+            progress.current_source = doc.source.clone();
+            progress.documents_processed += 1;
+            progress.chunks_produced += doc_chunks.len();
+            progress.bytes_processed += doc.content.len();
+            if let Some(callback) = &self.progress {
+                callback(progress.clone());
+            }
+
+            chunks.extend(doc_chunks);
+        }
+
+        chunks
+    }
+
+    /// Load the file or directory at `path`, detecting each file's format,
+    /// and chunk every document found
+    pub async fn ingest_path(&self, path: impl AsRef<Path>) -> Result<Vec<KnowledgeChunk>> {
+        let documents = load_path(path).await?;
+        Ok(self.chunk_documents(&documents))
+    }
+
+    pub(crate) fn chunk_document(&self, doc: &IngestionDocument) -> Vec<KnowledgeChunk> {
+        match &doc.format {
+            DocumentFormat::Markdown => {
+                self.chunk_markdown(&doc.content, doc.source.clone(), "markdown".to_string())
+            }
+            DocumentFormat::Code { language } => {
+                self.chunk_code(&doc.content, language, doc.source.clone())
+            }
+            format => self.chunk_text(
+                &doc.content,
+                doc.source.clone(),
+                format!("{:?}", format).to_lowercase(),
+            ),
+        }
     }
 
     /// Chunk text content with overlap for context preservation
@@ -162,6 +239,7 @@ impl Default for ContentChunker {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::{Arc, Mutex};
 
     #[test]
     fn test_chunk_short_text() {
@@ -190,6 +268,93 @@ mod tests {
         assert!(chunks.len() <= 5, "Should respect max_chunks limit");
     }
 
+    #[test]
+    fn test_chunk_documents_reports_progress() {
+        let updates = Arc::new(Mutex::new(Vec::new()));
+        let recorded = updates.clone();
+        let config = IngestionConfig {
+            chunk_size: 40,
+            chunk_overlap: 5,
+            ..Default::default()
+        };
+        let chunker = ContentChunker::new(config).with_progress(Arc::new(move |progress| {
+            recorded.lock().unwrap().push(progress);
+        }));
+
+        let documents = vec![
+            IngestionDocument::new(
+                "notes.txt",
+                DocumentFormat::TXT,
+                "First sentence here. Second sentence here. Third sentence here.",
+            ),
+            IngestionDocument::new(
+                "README.md",
+                DocumentFormat::Markdown,
+                "# Title\nSome intro text",
+            ),
+            IngestionDocument::new(
+                "main.rs",
+                DocumentFormat::Code {
+                    language: "rust".to_string(),
+                },
+                "fn main() {\n    println!(\"hello\");\n}",
+            ),
+        ];
+        let total_bytes: usize = documents.iter().map(|doc| doc.content.len()).sum();
+
+        let chunks = chunker.chunk_documents(&documents);
+
+        let updates = updates.lock().unwrap();
+        assert_eq!(updates.len(), documents.len());
+        for (update, doc) in updates.iter().zip(&documents) {
+            assert_eq!(update.current_source, doc.source);
+            assert_eq!(update.total_documents, documents.len());
+            assert_eq!(update.total_bytes, total_bytes);
+        }
+        for pair in updates.windows(2) {
+            assert!(pair[1].documents_processed > pair[0].documents_processed);
+            assert!(pair[1].bytes_processed > pair[0].bytes_processed);
+            assert!(pair[1].chunks_produced >= pair[0].chunks_produced);
+        }
+
+        let last = updates.last().unwrap();
+        assert_eq!(last.bytes_processed, total_bytes);
+        assert_eq!(last.chunks_produced, chunks.len());
+        assert_eq!(last.fraction(), 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_ingest_path_detects_each_format() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("guide.md"), "# Setup\nRun the installer.").unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(
+            dir.path().join("src/lib.rs"),
+            "pub fn answer() -> u32 {\n    42\n}",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "Calibrate joint 3 weekly.").unwrap();
+        std::fs::write(dir.path().join(".hidden.md"), "# Skipped").unwrap();
+
+        let chunks = ContentChunker::default()
+            .ingest_path(dir.path())
+            .await
+            .unwrap();
+
+        let source_type = |file: &str| {
+            let chunk = chunks
+                .iter()
+                .find(|chunk| chunk.source.ends_with(file))
+                .unwrap_or_else(|| panic!("no chunk from {}", file));
+            chunk.source_type.as_str()
+        };
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(source_type("guide.md"), "markdown");
+        assert_eq!(source_type("lib.rs"), "code:rust");
+        assert_eq!(source_type("notes.txt"), "txt");
+        assert!(chunks.iter().all(|chunk| !chunk.source.contains(".hidden")));
+    }
+
     #[test]
     fn test_chunk_markdown() {
         let chunker = ContentChunker::default();
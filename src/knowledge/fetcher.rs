@@ -4,10 +4,10 @@
 //! using MCP server tools (e.g., browser automation, web scraping).
 
 use crate::error::Result;
+use crate::ids::new_id;
 use crate::mcp::{McpClient, ToolCall, ToolContent};
 use serde::{Deserialize, Serialize};
 use tracing::{debug, info, warn};
-use uuid::Uuid;
 
 /// Configuration for web content fetching
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -99,7 +99,7 @@ impl WebFetcher {
                 debug!("Using browser tool: {}", tool_name);
 
                 let tool_call = ToolCall {
-                    id: Uuid::new_v4().to_string(),
+                    id: new_id().to_string(),
                     name: tool_name.to_string(),
                     arguments: serde_json::json!({
                         "url": url,
@@ -135,7 +135,7 @@ impl WebFetcher {
                 debug!("Using HTTP tool: {}", tool_name);
 
                 let tool_call = ToolCall {
-                    id: Uuid::new_v4().to_string(),
+                    id: new_id().to_string(),
                     name: tool_name.to_string(),
                     arguments: serde_json::json!({
                         "url": url,
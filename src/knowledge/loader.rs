@@ -0,0 +1,194 @@
+//! Loading documents from disk with format auto-detection
+//!
+//! [`detect_format`] infers a file's [`DocumentFormat`] from its magic bytes
+//! and extension, so a mixed directory can be ingested without naming each
+//! file's format. Text whose format cannot be told falls back to plain text.
+
+use super::fetcher::extract_text_from_html;
+use super::types::{DocumentFormat, IngestionDocument};
+use crate::error::Result;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tracing::{debug, warn};
+
+/// Source code extensions and the language each is chunked as
+const CODE_EXTENSIONS: &[(&str, &str)] = &[
+    ("rs", "rust"),
+    ("py", "python"),
+    ("js", "javascript"),
+    ("ts", "typescript"),
+    ("go", "go"),
+    ("java", "java"),
+    ("kt", "kotlin"),
+    ("c", "c"),
+    ("h", "c"),
+    ("cpp", "cpp"),
+    ("cc", "cpp"),
+    ("hpp", "cpp"),
+    ("cs", "csharp"),
+    ("rb", "ruby"),
+    ("swift", "swift"),
+    ("sh", "shell"),
+    ("sql", "sql"),
+];
+
+/// Format of the file at `path` with contents `bytes`, or `None` for binary
+/// content that cannot be ingested as text
+pub fn detect_format(path: &Path, bytes: &[u8]) -> Option<DocumentFormat> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_lowercase);
+
+    // Magic bytes win over a misleading extension
+    if bytes.starts_with(b"%PDF-") {
+        return Some(DocumentFormat::PDF);
+    }
+    if bytes.starts_with(b"PK\x03\x04") {
+        return match extension.as_deref() {
+            Some("docx") => Some(DocumentFormat::DOCX),
+            Some("epub") => Some(DocumentFormat::EPUB),
+            _ => None,
+        };
+    }
+
+    let format = match extension.as_deref() {
+        Some("md" | "markdown") => Some(DocumentFormat::Markdown),
+        Some("html" | "htm") => Some(DocumentFormat::HTML),
+        Some("txt" | "text") => Some(DocumentFormat::TXT),
+        Some(ext) => CODE_EXTENSIONS
+            .iter()
+            .find(|(code_ext, _)| *code_ext == ext)
+            .map(|(_, language)| DocumentFormat::Code {
+                language: language.to_string(),
+            }),
+        None => None,
+    };
+    if format.is_some() {
+        return format;
+    }
+
+    let text = std::str::from_utf8(bytes).ok()?;
+    let head: String = text.trim_start().chars().take(14).collect();
+    let head = head.to_lowercase();
+    if head.starts_with("<!doctype html") || head.starts_with("<html") {
+        return Some(DocumentFormat::HTML);
+    }
+
+    warn!(
+        "Could not detect the format of {}, ingesting it as plain text",
+        path.display()
+    );
+    Some(DocumentFormat::TXT)
+}
+
+/// Read and detect the document at `path`, returning `None` for files that
+/// cannot be ingested as text
+pub async fn load_document(path: &Path) -> Result<Option<IngestionDocument>> {
+    let bytes = fs::read(path).await?;
+    let source = path.display().to_string();
+
+    let Some(format) = detect_format(path, &bytes) else {
+        warn!("Skipping binary file {}", source);
+        return Ok(None);
+    };
+
+    let content = match &format {
+        DocumentFormat::PDF => match pdf_extract::extract_text_from_mem(&bytes) {
+            Ok(text) => text,
+            Err(e) => {
+                warn!("Skipping {}: failed to extract PDF text: {}", source, e);
+                return Ok(None);
+            }
+        },
+        DocumentFormat::DOCX | DocumentFormat::EPUB => {
+            warn!(
+                "Skipping {}: {:?} text extraction is not supported",
+                source, format
+            );
+            return Ok(None);
+        }
+        DocumentFormat::HTML => extract_text_from_html(&String::from_utf8_lossy(&bytes)),
+        _ => match String::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(_) => {
+                warn!("Skipping {}: not valid UTF-8", source);
+                return Ok(None);
+            }
+        },
+    };
+
+    debug!("Loaded {} as {:?}", source, format);
+    Ok(Some(IngestionDocument::new(source, format, content)))
+}
+
+/// Load the file at `path`, or every file under it if it is a directory.
+/// Hidden files and directories are skipped; files are returned in path order.
+pub async fn load_path(path: impl AsRef<Path>) -> Result<Vec<IngestionDocument>> {
+    let mut documents = Vec::new();
+    for file in list_files(path.as_ref()).await? {
+        if let Some(document) = load_document(&file).await? {
+            documents.push(document);
+        }
+    }
+    Ok(documents)
+}
+
+/// `path` itself if it is a file, else every non-hidden file under it in
+/// path order
+pub async fn list_files(path: &Path) -> Result<Vec<PathBuf>> {
+    if !fs::metadata(path).await?.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut files = Vec::new();
+    let mut dirs = vec![path.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let mut entries = fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let hidden = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with('.'));
+            if hidden {
+                continue;
+            }
+            if entry.file_type().await?.is_dir() {
+                dirs.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_format() {
+        let detect = |name: &str, bytes: &[u8]| detect_format(Path::new(name), bytes);
+
+        assert_eq!(
+            detect("notes.md", b"# Notes"),
+            Some(DocumentFormat::Markdown)
+        );
+        assert_eq!(
+            detect("main.rs", b"fn main() {}"),
+            Some(DocumentFormat::Code {
+                language: "rust".to_string()
+            })
+        );
+        assert_eq!(detect("report.bin", b"%PDF-1.7"), Some(DocumentFormat::PDF));
+        assert_eq!(
+            detect("page", b"  <!DOCTYPE html><html></html>"),
+            Some(DocumentFormat::HTML)
+        );
+        assert_eq!(detect("LICENSE", b"MIT License"), Some(DocumentFormat::TXT));
+        assert_eq!(detect("blob", &[0xff, 0xfe, 0x00, 0x81]), None);
+    }
+}
@@ -1,18 +1,71 @@
 //! Adaptive knowledge management with pruning and retention
 
+use super::chunker::ContentChunker;
+use super::loader::{list_files, load_document};
+use super::types::{
+    IngestionConfig, IngestionFailure, IngestionProgress, IngestionReport, IngestionResult,
+    KnowledgeChunk,
+};
 use crate::config::LearningConfig;
-use crate::error::Result;
+use crate::error::{AgentError, MemoryError, Result};
+use crate::llm::LlmClient;
 use crate::memory::{MemoryEntry, MemoryStore};
 use chrono::{DateTime, Utc};
+use futures::future::join_all;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::{RwLock, Semaphore};
+use tracing::{debug, info, warn};
+
+/// Entries read from the store per page while exporting
+const EXPORT_PAGE_SIZE: usize = 256;
 
 /// Manages knowledge lifecycle with adaptive limits
 pub struct AdaptiveKnowledgeManager {
     config: LearningConfig,
+    /// Splits documents for [`Self::ingest_all`]
+    chunker: ContentChunker,
+    /// Embeds chunks for [`Self::ingest_all`]
+    llm: Option<Arc<dyn LlmClient>>,
+    /// Receives the chunks of [`Self::ingest_all`]
+    store: Option<Arc<RwLock<Box<dyn MemoryStore>>>>,
 }
 
 impl AdaptiveKnowledgeManager {
+    /// Manager chunking documents as `config.external_sources` sets
     pub fn new(config: LearningConfig) -> Self {
-        Self { config }
+        let sources = &config.external_sources;
+        let chunker = ContentChunker::new(IngestionConfig {
+            chunk_size: sources.chunk_size,
+            chunk_overlap: sources.chunk_overlap,
+            quality_threshold: sources.min_content_quality_score,
+            max_chunks: None,
+        });
+        Self {
+            config,
+            chunker,
+            llm: None,
+            store: None,
+        }
+    }
+
+    /// Chunker used for ingestion, e.g. one reporting progress
+    pub fn with_chunker(mut self, chunker: ContentChunker) -> Self {
+        self.chunker = chunker;
+        self
+    }
+
+    /// Client that embeds ingested chunks
+    pub fn with_llm(mut self, llm: Arc<dyn LlmClient>) -> Self {
+        self.llm = Some(llm);
+        self
+    }
+
+    /// Store that ingested chunks are written to
+    pub fn with_store(mut self, store: Arc<RwLock<Box<dyn MemoryStore>>>) -> Self {
+        self.store = Some(store);
+        self
     }
 
     /// Check if knowledge store needs management
@@ -238,6 +291,305 @@ impl AdaptiveKnowledgeManager {
     }
 }
 
+impl AdaptiveKnowledgeManager {
+    /// Write every entry in `store` to `writer` as JSON Lines, one
+    /// [`KnowledgeChunk`] per line, returning the number written. The store is
+    /// read a page at a time so large bases are never held in memory at once.
+    pub async fn export<W>(&self, store: &dyn MemoryStore, writer: W) -> Result<usize>
+    where
+        W: AsyncWrite + Unpin + Send,
+    {
+        let mut writer = tokio::io::BufWriter::new(writer);
+        let mut exported = 0;
+
+        loop {
+            let page = store.list_page(exported, EXPORT_PAGE_SIZE).await?;
+            let page_len = page.len();
+            for entry in page {
+                let mut line = serde_json::to_vec(&KnowledgeChunk::from(entry))?;
+                line.push(b'\n');
+                writer.write_all(&line).await?;
+            }
+            exported += page_len;
+            if page_len < EXPORT_PAGE_SIZE {
+                break;
+            }
+        }
+
+        writer.flush().await?;
+        info!("Exported {} knowledge entries", exported);
+        Ok(exported)
+    }
+
+    /// Restore chunks written by [`Self::export`] into `store` with their
+    /// original ids and creation times, replacing entries with the same id.
+    /// Chunks whose embedding does not match the store's embedding dimension
+    /// were made by a different embedding model and are skipped.
+    pub async fn import<R>(&self, reader: R, store: &mut dyn MemoryStore) -> Result<ImportSummary>
+    where
+        R: AsyncBufRead + Unpin + Send,
+    {
+        let dimension = store.stats().await?.embedding_dimension;
+        let mut lines = reader.lines();
+        let mut summary = ImportSummary::default();
+        let mut line_number = 0;
+
+        while let Some(line) = lines.next_line().await? {
+            line_number += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let chunk: KnowledgeChunk = serde_json::from_str(&line).map_err(|e| {
+                MemoryError::StorageFailed(format!(
+                    "Invalid knowledge export at line {}: {}",
+                    line_number, e
+                ))
+            })?;
+
+            let embedding_len = chunk.embedding.as_ref().map_or(0, Vec::len);
+            if embedding_len != dimension {
+                debug!(
+                    "Skipping chunk {} with {}-dimensional embedding (expected {})",
+                    chunk.id, embedding_len, dimension
+                );
+                summary.skipped += 1;
+                continue;
+            }
+
+            store.restore(chunk.into_memory_entry()).await?;
+            summary.imported += 1;
+        }
+
+        if summary.skipped > 0 {
+            warn!(
+                "Skipped {} knowledge entries with mismatched embedding dimension",
+                summary.skipped
+            );
+        }
+        info!("Imported {} knowledge entries", summary.imported);
+        Ok(summary)
+    }
+}
+
+impl AdaptiveKnowledgeManager {
+    /// Load, chunk, embed and store every document under each of `sources`,
+    /// working on at most `concurrency` sources at once. A source that cannot
+    /// be loaded, embedded or stored is reported in
+    /// [`IngestionReport::failures`] without affecting the others. Chunks
+    /// whose embedding does not match the store's dimension are filtered. The
+    /// chunker's progress callback is told after each document across all
+    /// sources. Needs [`Self::with_llm`] and [`Self::with_store`].
+    pub async fn ingest_all<P>(&self, sources: &[P], concurrency: usize) -> Result<IngestionReport>
+    where
+        P: AsRef<Path> + Sync,
+    {
+        let (Some(llm), Some(store)) = (&self.llm, &self.store) else {
+            return Err(AgentError::Config(
+                "Knowledge ingestion needs an LLM client and a store".to_string(),
+            ));
+        };
+        ingest_sources(sources, concurrency, &self.chunker, llm.as_ref(), store).await
+    }
+}
+
+/// [`AdaptiveKnowledgeManager::ingest_all`] with the given chunker, client
+/// and store
+async fn ingest_sources<P>(
+    sources: &[P],
+    concurrency: usize,
+    chunker: &ContentChunker,
+    llm: &dyn LlmClient,
+    store: &RwLock<Box<dyn MemoryStore>>,
+) -> Result<IngestionReport>
+where
+    P: AsRef<Path> + Sync,
+{
+    let dimension = store.read().await.stats().await?.embedding_dimension;
+    let listings = join_all(sources.iter().map(|source| sized_files(source.as_ref()))).await;
+    let files = listings.iter().flatten().flatten();
+    let progress = &Mutex::new(IngestionProgress {
+        current_source: String::new(),
+        documents_processed: 0,
+        total_documents: files.clone().count(),
+        chunks_produced: 0,
+        bytes_processed: 0,
+        total_bytes: files.map(|(_, size)| size).sum(),
+    });
+    let permits = &Semaphore::new(concurrency.max(1));
+
+    let outcomes = join_all(
+        sources
+            .iter()
+            .zip(listings)
+            .map(|(source, listing)| async move {
+                let _permit = permits.acquire().await.expect("semaphore is never closed");
+                let path = source.as_ref();
+                let outcome = match listing {
+                    Ok(files) => {
+                        let source = IngestSource {
+                            path,
+                            files,
+                            progress,
+                        };
+                        ingest_source(source, chunker, llm, store, dimension).await
+                    }
+                    Err(e) => Err(e),
+                };
+                (path.display().to_string(), outcome)
+            }),
+    )
+    .await;
+
+    let mut report = IngestionReport::default();
+    for (source, outcome) in outcomes {
+        match outcome {
+            Ok(result) => report.results.push(result),
+            Err(e) => {
+                warn!("Failed to ingest {}: {}", source, e);
+                report.failures.push(IngestionFailure {
+                    source,
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+
+    info!(
+        "Ingested {} sources ({} chunks), {} failed",
+        report.results.len(),
+        report.chunks_stored(),
+        report.failures.len()
+    );
+    Ok(report)
+}
+
+/// Files under `path` with their sizes in bytes
+async fn sized_files(path: &Path) -> Result<Vec<(PathBuf, usize)>> {
+    let mut sized = Vec::new();
+    for file in list_files(path).await? {
+        let size = tokio::fs::metadata(&file).await?.len() as usize;
+        sized.push((file, size));
+    }
+    Ok(sized)
+}
+
+/// A source of [`ingest_all`] and the progress shared by all sources
+struct IngestSource<'a> {
+    path: &'a Path,
+    files: Vec<(PathBuf, usize)>,
+    progress: &'a Mutex<IngestionProgress>,
+}
+
+/// Chunk and embed every file of `source` before storing any of it, so a
+/// failed embedding leaves nothing from the source behind
+async fn ingest_source(
+    source: IngestSource<'_>,
+    chunker: &ContentChunker,
+    llm: &dyn LlmClient,
+    store: &RwLock<Box<dyn MemoryStore>>,
+    dimension: usize,
+) -> Result<IngestionResult> {
+    let mut embedded = Vec::new();
+    let mut chunks_filtered = 0;
+    let mut files = source.files.into_iter();
+    while let Some((file, size)) = files.next() {
+        let (chunks, filtered) = match embed_file(&file, chunker, llm, dimension).await {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                // The rest of the source is abandoned, which finishes it too
+                for (file, size) in std::iter::once((file, size)).chain(files) {
+                    report_progress(source.progress, chunker, &file, size, 0);
+                }
+                return Err(e);
+            }
+        };
+        report_progress(
+            source.progress,
+            chunker,
+            &file,
+            size,
+            chunks.len() + filtered,
+        );
+        chunks_filtered += filtered;
+        embedded.extend(chunks);
+    }
+
+    let chunks_stored = embedded.len();
+    for chunk in embedded {
+        let (content, embedding, metadata) = chunk.into_memory_parts();
+        store
+            .write()
+            .await
+            .store(content, embedding, metadata)
+            .await?;
+    }
+
+    Ok(IngestionResult {
+        source: source.path.display().to_string(),
+        chunks_stored,
+        chunks_filtered,
+        timestamp: Utc::now(),
+    })
+}
+
+/// Chunks of the document at `file` with their embeddings, and how many
+/// chunks were filtered for an embedding of the wrong dimension
+async fn embed_file(
+    file: &Path,
+    chunker: &ContentChunker,
+    llm: &dyn LlmClient,
+    dimension: usize,
+) -> Result<(Vec<KnowledgeChunk>, usize)> {
+    let Some(document) = load_document(file).await? else {
+        return Ok((Vec::new(), 0));
+    };
+
+    let mut embedded = Vec::new();
+    let mut filtered = 0;
+    for mut chunk in chunker.chunk_document(&document) {
+        let embedding = llm.embed(&chunk.content).await?.embedding;
+        if embedding.len() != dimension {
+            debug!(
+                "Filtering chunk of {} with {}-dimensional embedding (expected {})",
+                chunk.source,
+                embedding.len(),
+                dimension
+            );
+            filtered += 1;
+            continue;
+        }
+        chunk.embedding = Some(embedding);
+        embedded.push(chunk);
+    }
+    Ok((embedded, filtered))
+}
+
+/// Count `file` as processed and tell the chunker's progress callback
+fn report_progress(
+    progress: &Mutex<IngestionProgress>,
+    chunker: &ContentChunker,
+    file: &Path,
+    size: usize,
+    chunks_produced: usize,
+) {
+    let mut progress = progress.lock().unwrap_or_else(|e| e.into_inner());
+    progress.current_source = file.display().to_string();
+    progress.documents_processed += 1;
+    progress.chunks_produced += chunks_produced;
+    progress.bytes_processed += size;
+    if let Some(callback) = chunker.progress() {
+        callback(progress.clone());
+    }
+}
+
+/// Counts from [`AdaptiveKnowledgeManager::import`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub imported: usize,
+    /// Chunks whose embedding dimension did not match the store
+    pub skipped: usize,
+}
+
 /// Result of knowledge management operation
 #[derive(Debug, Clone)]
 pub enum ManagementResult {
@@ -279,7 +631,10 @@ impl KnowledgeStats {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::memory::MemoryEntry;
+    use crate::config::MemoryConfig;
+    use crate::error::LlmError;
+    use crate::llm::mock::MockLlm;
+    use crate::memory::{MemoryEntry, SqliteMemoryStore};
     use std::collections::HashMap;
     use uuid::Uuid;
 
@@ -297,6 +652,8 @@ mod tests {
             metadata,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            importance: 0.5,
+            last_accessed: Utc::now(),
         }
     }
 
@@ -335,4 +692,145 @@ mod tests {
         // Still true because recently created
         assert!(manager.should_keep(&low_everything));
     }
+
+    async fn memory_store(dimension: usize) -> SqliteMemoryStore {
+        let mut store = SqliteMemoryStore::new(MemoryConfig {
+            database_url: Some("sqlite::memory:".to_string()),
+            embedding_dimension: dimension,
+            ..Default::default()
+        });
+        store.initialize().await.unwrap();
+        store
+    }
+
+    #[tokio::test]
+    async fn test_export_import_round_trip() {
+        let manager = AdaptiveKnowledgeManager::new(LearningConfig::default());
+        let mut source = memory_store(4).await;
+        let docs = [
+            ("Torque limits for the hip actuator", [1.0, 0.0, 0.0, 0.0]),
+            ("Battery charger thermal derating", [0.0, 1.0, 0.0, 0.0]),
+            ("Hip actuator encoder calibration", [0.8, 0.2, 0.0, 0.0]),
+        ];
+        for (i, (content, embedding)) in docs.iter().enumerate() {
+            let metadata = HashMap::from([
+                ("source".to_string(), format!("manual-{}.pdf", i)),
+                ("source_type".to_string(), "document".to_string()),
+                ("quality_score".to_string(), "0.9".to_string()),
+            ]);
+            source
+                .store(content.to_string(), embedding.to_vec(), metadata)
+                .await
+                .unwrap();
+        }
+
+        let mut exported = Vec::new();
+        assert_eq!(manager.export(&source, &mut exported).await.unwrap(), 3);
+        let first: KnowledgeChunk =
+            serde_json::from_slice(exported.split(|b| *b == b'\n').next().unwrap()).unwrap();
+        assert_eq!(first.source_type, "document");
+        assert_eq!(first.quality_score, 0.9);
+
+        // A chunk from a different embedding model is skipped
+        let foreign = KnowledgeChunk {
+            embedding: Some(vec![0.5; 8]),
+            ..KnowledgeChunk::new("Foreign".into(), "other".into(), "web".into())
+        };
+        exported.extend(serde_json::to_vec(&foreign).unwrap());
+        exported.push(b'\n');
+
+        let mut restored = memory_store(4).await;
+        let summary = manager
+            .import(exported.as_slice(), &mut restored)
+            .await
+            .unwrap();
+        assert_eq!(
+            summary,
+            ImportSummary {
+                imported: 3,
+                skipped: 1
+            }
+        );
+
+        let query = vec![1.0, 0.1, 0.0, 0.0];
+        let expected = source.search(query.clone(), 3, 0.0).await.unwrap();
+        let actual = restored.search(query, 3, 0.0).await.unwrap();
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(&expected) {
+            assert_eq!(a.entry.id, e.entry.id);
+            assert_eq!(a.entry.created_at, e.entry.created_at);
+            assert_eq!(a.entry.content, e.entry.content);
+            assert!((a.similarity - e.similarity).abs() < 1e-6);
+            assert_eq!(a.entry.metadata, e.entry.metadata);
+        }
+    }
+
+    /// Embeds slowly and fails on text containing "corrupt"
+    fn slow_embedder() -> MockLlm {
+        MockLlm::new()
+            .with_delay(std::time::Duration::from_millis(20))
+            .with_embed(|text| {
+                if text.contains("corrupt") {
+                    return Err(LlmError::EmbeddingFailed("embedding rejected".to_string()).into());
+                }
+                Ok(vec![text.len() as f32, 1.0, 0.0, 0.0])
+            })
+    }
+
+    #[tokio::test]
+    async fn test_ingest_all_isolates_failures() {
+        let dir = tempfile::tempdir().unwrap();
+        let contents = [
+            "Torque limits for the hip actuator.",
+            "Battery charger thermal derating.",
+            "This page is corrupt.",
+            "Hip actuator encoder calibration.",
+        ];
+        let sources: Vec<_> = contents
+            .iter()
+            .enumerate()
+            .map(|(i, content)| {
+                let path = dir.path().join(format!("doc-{}.txt", i));
+                std::fs::write(&path, content).unwrap();
+                path
+            })
+            .collect();
+
+        let store: Arc<RwLock<Box<dyn MemoryStore>>> =
+            Arc::new(RwLock::new(Box::new(memory_store(4).await)));
+        let updates = Arc::new(Mutex::new(Vec::new()));
+        let recorded = updates.clone();
+        let chunker = ContentChunker::new(IngestionConfig::default()).with_progress(Arc::new(
+            move |progress| recorded.lock().unwrap().push(progress),
+        ));
+        let embedder = Arc::new(slow_embedder());
+        let manager = AdaptiveKnowledgeManager::new(LearningConfig::default())
+            .with_chunker(chunker)
+            .with_llm(embedder.clone())
+            .with_store(store.clone());
+
+        let report = manager.ingest_all(&sources, 2).await.unwrap();
+
+        assert_eq!(report.results.len(), 3);
+        assert_eq!(report.chunks_stored(), 3);
+        assert_eq!(report.failures.len(), 1);
+        assert!(report.failures[0].source.ends_with("doc-2.txt"));
+        assert!(report.failures[0].error.contains("embedding rejected"));
+
+        // Sources were worked on concurrently, but never more than two at once
+        assert_eq!(embedder.max_embeddings_in_flight(), 2);
+        assert_eq!(store.read().await.stats().await.unwrap().total_memories, 3);
+
+        // Progress covers every source, the failed one included
+        let updates = updates.lock().unwrap();
+        assert_eq!(updates.len(), contents.len());
+        for pair in updates.windows(2) {
+            assert!(pair[1].documents_processed > pair[0].documents_processed);
+            assert!(pair[1].bytes_processed > pair[0].bytes_processed);
+        }
+        let last = updates.last().unwrap();
+        assert_eq!(last.total_documents, contents.len());
+        assert_eq!(last.chunks_produced, 3);
+        assert_eq!(last.fraction(), 1.0);
+    }
 }
@@ -2,6 +2,7 @@
 //!
 //! This module provides functionality for:
 //! - Ingesting external knowledge from web, documents, code repos
+//! - Detecting document formats when loading files from disk
 //! - Chunking content for embedding
 //! - Consolidating and deduplicating knowledge
 //! - Managing knowledge lifecycle with adaptive limits
@@ -9,11 +10,13 @@
 pub mod chunker;
 pub mod consolidator;
 pub mod fetcher;
+pub mod loader;
 pub mod manager;
 pub mod types;
 
 pub use chunker::ContentChunker;
 pub use consolidator::KnowledgeConsolidator;
 pub use fetcher::{extract_text_from_html, FetchedContent, FetcherConfig, WebFetcher};
-pub use manager::{AdaptiveKnowledgeManager, KnowledgeStats, ManagementResult};
+pub use loader::{detect_format, load_path};
+pub use manager::{AdaptiveKnowledgeManager, ImportSummary, KnowledgeStats, ManagementResult};
 pub use types::*;
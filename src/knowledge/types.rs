@@ -1,8 +1,12 @@
 //! Type definitions for knowledge management
 
+use crate::ids::new_id;
+use crate::memory::ranking::importance_from_metadata;
+use crate::memory::MemoryEntry;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
 use uuid::Uuid;
 
 /// Source of external knowledge
@@ -77,7 +81,7 @@ pub struct KnowledgeChunk {
 impl KnowledgeChunk {
     pub fn new(content: String, source: String, source_type: String) -> Self {
         Self {
-            id: Uuid::new_v4(),
+            id: new_id(),
             content,
             embedding: None,
             source,
@@ -97,6 +101,68 @@ impl KnowledgeChunk {
         self.quality_score = score;
         self
     }
+
+    /// Content, embedding and metadata for storing this chunk as a memory,
+    /// with the source fields and quality score carried in the metadata
+    pub fn into_memory_parts(self) -> (String, Vec<f32>, HashMap<String, String>) {
+        let mut metadata = self.metadata;
+        metadata.insert(SOURCE_KEY.to_string(), self.source);
+        metadata.insert(SOURCE_TYPE_KEY.to_string(), self.source_type);
+        metadata.insert(
+            QUALITY_SCORE_KEY.to_string(),
+            self.quality_score.to_string(),
+        );
+        (self.content, self.embedding.unwrap_or_default(), metadata)
+    }
+
+    /// This chunk as a memory with its own id and creation time, for
+    /// restoring it exactly as it was exported
+    pub fn into_memory_entry(self) -> MemoryEntry {
+        let id = self.id;
+        let created_at = self.created_at;
+        let (content, embedding, metadata) = self.into_memory_parts();
+        MemoryEntry {
+            id,
+            content,
+            embedding,
+            importance: importance_from_metadata(&metadata),
+            metadata,
+            created_at,
+            updated_at: created_at,
+            last_accessed: created_at,
+        }
+    }
+}
+
+/// Metadata keys a chunk's source fields and quality score are stored under
+const SOURCE_KEY: &str = "source";
+const SOURCE_TYPE_KEY: &str = "source_type";
+const QUALITY_SCORE_KEY: &str = "quality_score";
+
+impl From<MemoryEntry> for KnowledgeChunk {
+    fn from(entry: MemoryEntry) -> Self {
+        let mut metadata = entry.metadata;
+        let source = metadata.remove(SOURCE_KEY).unwrap_or_default();
+        let source_type = metadata.remove(SOURCE_TYPE_KEY).unwrap_or_default();
+        let quality_score = match metadata.get(QUALITY_SCORE_KEY).map(|s| s.parse::<f32>()) {
+            Some(Ok(score)) => {
+                metadata.remove(QUALITY_SCORE_KEY);
+                score
+            }
+            _ => 0.5,
+        };
+
+        Self {
+            id: entry.id,
+            content: entry.content,
+            embedding: Some(entry.embedding),
+            source,
+            source_type,
+            metadata,
+            quality_score,
+            created_at: entry.created_at,
+        }
+    }
 }
 
 /// Configuration for knowledge ingestion
@@ -128,6 +194,78 @@ pub struct IngestionResult {
     pub timestamp: DateTime<Utc>,
 }
 
+/// A source that [`AdaptiveKnowledgeManager::ingest_all`] could not ingest
+///
+/// [`AdaptiveKnowledgeManager::ingest_all`]: super::AdaptiveKnowledgeManager::ingest_all
+#[derive(Debug, Clone)]
+pub struct IngestionFailure {
+    pub source: String,
+    pub error: String,
+}
+
+/// Outcome of a batch ingestion, in the order the sources were given
+#[derive(Debug, Clone, Default)]
+pub struct IngestionReport {
+    pub results: Vec<IngestionResult>,
+    pub failures: Vec<IngestionFailure>,
+}
+
+impl IngestionReport {
+    /// Chunks stored across all successful sources
+    pub fn chunks_stored(&self) -> usize {
+        self.results.iter().map(|result| result.chunks_stored).sum()
+    }
+}
+
+/// A document queued for chunking
+#[derive(Debug, Clone)]
+pub struct IngestionDocument {
+    pub source: String,
+    pub format: DocumentFormat,
+    pub content: String,
+}
+
+impl IngestionDocument {
+    pub fn new(
+        source: impl Into<String>,
+        format: DocumentFormat,
+        content: impl Into<String>,
+    ) -> Self {
+        Self {
+            source: source.into(),
+            format,
+            content: content.into(),
+        }
+    }
+}
+
+/// Snapshot of a batch ingestion's progress, reported after each document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IngestionProgress {
+    /// Source of the document just processed
+    pub current_source: String,
+    pub documents_processed: usize,
+    pub total_documents: usize,
+    pub chunks_produced: usize,
+    pub bytes_processed: usize,
+    pub total_bytes: usize,
+}
+
+impl IngestionProgress {
+    /// Fraction of bytes processed, between 0.0 and 1.0
+    pub fn fraction(&self) -> f32 {
+        if self.total_bytes == 0 {
+            return 1.0;
+        }
+        self.bytes_processed as f32 / self.total_bytes as f32
+    }
+}
+
+/// Callback receiving ingestion progress. It runs inline on the ingesting
+/// task, so it should only record the update (e.g. bump a progress bar or
+/// `try_send` on a channel).
+pub type IngestionProgressCallback = Arc<dyn Fn(IngestionProgress) + Send + Sync>;
+
 /// Consolidated knowledge from multiple sources
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConsolidatedKnowledge {
@@ -145,7 +283,7 @@ impl ConsolidatedKnowledge {
     pub fn new(topic: String, summary: String) -> Self {
         let now = Utc::now();
         Self {
-            id: Uuid::new_v4(),
+            id: new_id(),
             topic,
             summary,
             key_points: Vec::new(),
@@ -31,11 +31,16 @@ pub mod api;
 pub mod cache;
 pub mod config;
 pub mod error;
+pub mod evaluation;
+pub mod http_client;
+pub mod ids;
 pub mod knowledge;
 pub mod llm;
 pub mod mcp;
 pub mod memory;
+pub mod metrics;
 pub mod organization;
+pub mod prompts;
 pub mod saga;
 pub mod tools;
 pub mod ui_workflow_storage;
@@ -48,29 +53,36 @@ pub use a2a::{
     AgentId, AgentRegistration, AgentStatus, HttpA2AClient, MessageHandler, MessagePayload,
     MessagePriority, MessageType, ProtocolType, ResponseStatus,
 };
-pub use agent::{Agent, AgentBuilder};
+pub use agent::{Agent, AgentBuilder, AgentEvent, AgentMiddleware};
 pub use cache::{CacheStats, LlmCache, LlmCacheConfig};
-pub use config::{AgentConfig, LlmConfig, McpConfig, MemoryConfig};
+pub use config::{AgentConfig, ConfigError, LlmConfig, McpConfig, MemoryConfig};
 pub use error::{AgentError, Result};
+pub use evaluation::{EvalTarget, Evaluator};
+pub use http_client::HttpClientConfig;
+pub use ids::{IdGenerator, SequentialIdGenerator};
 pub use knowledge::{
     AdaptiveKnowledgeManager, ConsolidatedKnowledge, ContentChunker, DocumentFormat,
-    IngestionConfig, IngestionResult, KnowledgeChunk, KnowledgeConsolidator, KnowledgeSource,
-    KnowledgeStats, ManagementResult,
+    IngestionConfig, IngestionDocument, IngestionFailure, IngestionProgress,
+    IngestionProgressCallback, IngestionReport, IngestionResult, KnowledgeChunk,
+    KnowledgeConsolidator, KnowledgeSource, KnowledgeStats, ManagementResult,
 };
 pub use mcp::{McpClient, McpTool, ToolCall, ToolResult};
 pub use memory::{MemoryStore, VectorStore};
 pub use organization::{
     AgentStatus as OrgAgentStatus, CollaborativeWorkspace, Organization, OrganizationAgent,
-    OrganizationRole, RoleCategory, TaskPriority, TaskStatus, WorkspaceTask,
+    OrganizationRole, RoleCategory, SamplingParams, TaskEscalation, TaskPriority, TaskStatus,
+    WorkspaceTask,
 };
+pub use prompts::PromptLibrary;
 pub use saga::{
     SagaContext, SagaOrchestrator, SagaResult, SagaStep, SagaStepState, SagaWorkflowStep,
+    WorkflowSagaStep,
 };
 pub use unified_storage::{
-    CleanupStats, EvalDataset, EvalScore, InMemoryUnifiedStorage, MemoryMessage, MemoryThread,
-    MessageRole, ResourceId, ResumeCondition, RetentionPolicy, StorageManager, StorageStats,
-    SuspendReason, SuspendedWorkflow, TraceData, TraceEvent, TraceFilters, TraceStatus,
-    UnifiedStorage,
+    CleanupStats, EvalDataset, EvalItem, EvalScore, FileTraceStorage, InMemoryUnifiedStorage,
+    MemoryMessage, MemoryThread, MessageRole, ResourceId, ResumeCondition, RetentionPolicy,
+    StorageManager, StorageStats, SuspendReason, SuspendedWorkflow, TraceData, TraceEvent,
+    TraceFilters, TraceStatus, TraceStorage, UnifiedStorage, Versioned,
 };
 pub use workflow::{WorkflowContext, WorkflowEngine, WorkflowStep};
 
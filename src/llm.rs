@@ -1,17 +1,36 @@
 //! Language model integration using Ollama
 
+pub mod body_log;
+pub mod capabilities;
 pub mod connection_pool;
+pub mod context_window;
+pub mod embedding_cache;
+pub mod embedding_limit;
+pub mod generation_timeout;
 pub mod manager;
+#[cfg(test)]
+pub(crate) mod mock;
+pub mod pricing;
 pub mod provider;
 pub mod providers;
+pub mod retry_budget;
+pub mod router;
+pub mod single_flight;
+pub mod stream;
+pub mod tokenizer;
 
 use crate::cache::LlmCache;
 use crate::config::LlmConfig;
-use crate::error::{LlmError, Result};
+use crate::error::{AgentError, LlmError, Result};
+use crate::mcp::{McpTool, ToolCall};
 use async_trait::async_trait;
+use body_log::BodyLogger;
+use futures::stream::{BoxStream, StreamExt};
+use pricing::TokenUsage;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use std::time::Duration;
+use stream::StreamEvent;
 use tokio::time::timeout;
 use tracing::{debug, error, info};
 
@@ -22,6 +41,8 @@ pub enum Role {
     System,
     User,
     Assistant,
+    /// The result of a tool call, answering the assistant message that made it
+    Tool,
 }
 
 /// A message in a conversation
@@ -29,6 +50,12 @@ pub enum Role {
 pub struct Message {
     pub role: Role,
     pub content: String,
+    /// Tool calls made by an assistant message
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    /// Call a tool message is the result of
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
 }
 
 /// Response from text generation
@@ -36,8 +63,16 @@ pub struct Message {
 pub struct GenerationResponse {
     pub text: String,
     pub tokens_used: Option<u32>,
+    pub usage: Option<TokenUsage>,
     pub model: String,
     pub finish_reason: Option<String>,
+    /// Identifier of the backend configuration that served the request, when
+    /// the provider reports one. A change means the same seed may no longer
+    /// reproduce earlier output.
+    pub system_fingerprint: Option<String>,
+    /// Reasoning the model returned separately from its answer, when the
+    /// provider reports it
+    pub reasoning: Option<String>,
 }
 
 /// Embedding response
@@ -61,6 +96,45 @@ pub trait LlmClient: Send + Sync {
 
     /// Check if model is available
     async fn is_model_available(&self, model: &str) -> Result<bool>;
+
+    /// Generate text from a conversation as a stream of events.
+    ///
+    /// The default implementation generates the whole response and emits it
+    /// as a single text delta, for backends that cannot stream.
+    async fn generate_stream(
+        &self,
+        messages: &[Message],
+    ) -> Result<BoxStream<'static, Result<StreamEvent>>> {
+        let response = self.generate(messages).await?;
+        let events = StreamEvent::from_response(response).into_iter().map(Ok);
+        Ok(futures::stream::iter(events).boxed())
+    }
+
+    /// Stream a generation offering `tools` to the model, which may answer
+    /// with [`StreamEvent::ToolCall`]s instead of, or besides, text.
+    ///
+    /// The default implementation streams without offering the tools, for
+    /// backends without tool calling.
+    async fn generate_stream_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[McpTool],
+    ) -> Result<BoxStream<'static, Result<StreamEvent>>> {
+        if !tools.is_empty() {
+            debug!(
+                "Client has no tool calling, generating without {} tools",
+                tools.len()
+            );
+        }
+        self.generate_stream(messages).await
+    }
+
+    /// Load the text model ahead of the first request to avoid cold-start latency.
+    ///
+    /// The default implementation does nothing, for backends without a load step.
+    async fn preload_model(&self) -> Result<()> {
+        Ok(())
+    }
 }
 
 /// Ollama client implementation
@@ -74,9 +148,40 @@ pub struct OllamaClient {
 #[derive(Debug, Serialize)]
 struct OllamaGenerateRequest {
     model: String,
-    messages: Vec<Message>,
+    messages: Vec<OllamaMessage>,
     stream: bool,
     options: OllamaOptions,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<serde_json::Value>,
+}
+
+/// Ollama chat message, with tool calls in Ollama's function format
+#[derive(Debug, Serialize)]
+struct OllamaMessage {
+    role: Role,
+    content: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tool_calls: Vec<serde_json::Value>,
+}
+
+impl From<&Message> for OllamaMessage {
+    fn from(msg: &Message) -> Self {
+        Self {
+            role: msg.role.clone(),
+            content: msg.content.clone(),
+            tool_calls: msg
+                .tool_calls
+                .iter()
+                .map(|call| {
+                    serde_json::json!({
+                        "function": { "name": call.name, "arguments": call.arguments }
+                    })
+                })
+                .collect(),
+        }
+    }
 }
 
 /// Ollama API options
@@ -84,13 +189,42 @@ struct OllamaGenerateRequest {
 struct OllamaOptions {
     num_predict: u32,
     temperature: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+}
+
+/// Ollama tool call, which carries no id
+#[derive(Debug, Deserialize)]
+struct OllamaToolCall {
+    function: OllamaFunctionCall,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaFunctionCall {
+    name: String,
+    #[serde(default)]
+    arguments: serde_json::Value,
+}
+
+/// Message in an Ollama chat response
+#[derive(Debug, Deserialize)]
+struct OllamaResponseMessage {
+    #[serde(default)]
+    content: String,
+    /// Reasoning of thinking models, returned apart from the answer
+    #[serde(default)]
+    thinking: String,
+    #[serde(default)]
+    tool_calls: Vec<OllamaToolCall>,
 }
 
 /// Ollama API response for generation
 #[derive(Debug, Deserialize)]
 struct OllamaGenerateResponse {
     model: String,
-    message: Message,
+    message: OllamaResponseMessage,
     done: bool,
     #[serde(default)]
     done_reason: Option<String>,
@@ -106,7 +240,6 @@ struct OllamaGenerateResponse {
     #[allow(dead_code)]
     load_duration: Option<u64>,
     #[serde(default)]
-    #[allow(dead_code)]
     prompt_eval_count: Option<u32>,
     #[serde(default)]
     #[allow(dead_code)]
@@ -147,36 +280,43 @@ struct OllamaModelInfo {
 }
 
 impl OllamaClient {
-    /// Create a new Ollama client
+    /// Create a new Ollama client, panicking if its HTTP settings are invalid
     pub fn new(config: LlmConfig) -> Self {
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(config.timeout))
-            .redirect(reqwest::redirect::Policy::none())
-            .pool_max_idle_per_host(10)
-            .pool_idle_timeout(Duration::from_secs(90))
-            .tcp_keepalive(Duration::from_secs(60))
-            .connect_timeout(Duration::from_secs(10))
-            .build()
-            .expect("Failed to create HTTP client");
+        Self::try_new(config).expect("Failed to create HTTP client")
+    }
 
-        Self {
+    /// Create a new Ollama client, failing on an invalid proxy or CA
+    /// certificate
+    pub fn try_new(config: LlmConfig) -> Result<Self> {
+        let client = Self::build_client(&config)?;
+
+        Ok(Self {
             client,
             config,
             cache: None,
-        }
+        })
     }
 
-    /// Create a new Ollama client with cache
-    pub async fn new_with_cache(config: LlmConfig) -> Result<Self> {
-        let client = reqwest::Client::builder()
+    /// HTTP client for the Ollama server, with the configured proxy, trusted
+    /// CA and connection settings applied over the defaults
+    fn build_client(config: &LlmConfig) -> Result<reqwest::Client> {
+        let builder = reqwest::Client::builder()
             .timeout(Duration::from_secs(config.timeout))
             .redirect(reqwest::redirect::Policy::none())
             .pool_max_idle_per_host(10)
             .pool_idle_timeout(Duration::from_secs(90))
             .tcp_keepalive(Duration::from_secs(60))
-            .connect_timeout(Duration::from_secs(10))
+            .connect_timeout(Duration::from_secs(10));
+        config
+            .http
+            .apply(builder)?
             .build()
-            .expect("Failed to create HTTP client");
+            .map_err(|e| AgentError::Config(format!("Failed to create HTTP client: {}", e)))
+    }
+
+    /// Create a new Ollama client with cache
+    pub async fn new_with_cache(config: LlmConfig) -> Result<Self> {
+        let client = Self::build_client(&config)?;
 
         let cache = if config.cache.enabled {
             Some(Arc::new(LlmCache::new(config.cache.clone()).await?))
@@ -199,58 +339,48 @@ impl OllamaClient {
             endpoint
         )
     }
-}
 
-#[async_trait]
-impl LlmClient for OllamaClient {
-    async fn generate(&self, messages: &[Message]) -> Result<GenerationResponse> {
-        debug!("Generating text with {} messages", messages.len());
-
-        // Try cache first if available
-        if let Some(cache) = &self.cache {
-            let messages_json =
-                serde_json::to_string(&messages).unwrap_or_else(|_| format!("{:?}", messages));
-
-            let system_prompt = messages
-                .iter()
-                .find(|m| m.role == Role::System)
-                .map(|m| m.content.as_str());
-
-            let cache_key = LlmCache::compute_cache_key(
-                &messages_json,
-                &self.config.text_model,
-                self.config.temperature,
-                self.config.max_tokens,
-                system_prompt,
-            );
-
-            if let Ok(Some(cached_response)) = cache.get(&cache_key).await {
-                debug!("Using cached response");
-                return Ok(GenerationResponse {
-                    text: cached_response,
-                    tokens_used: None,
-                    model: self.config.text_model.clone(),
-                    finish_reason: Some("cached".to_string()),
-                });
-            }
-        }
+    /// Configured `keep_alive` in the form Ollama expects: plain numbers are
+    /// sent as seconds (so `-1` means forever), anything else as a duration string
+    fn keep_alive(&self) -> Option<serde_json::Value> {
+        let keep_alive = self.config.keep_alive.as_deref()?.trim();
+        Some(match keep_alive.parse::<i64>() {
+            Ok(seconds) => serde_json::Value::from(seconds),
+            Err(_) => serde_json::Value::from(keep_alive),
+        })
+    }
 
-        let request = OllamaGenerateRequest {
+    fn chat_request(&self, messages: &[Message]) -> OllamaGenerateRequest {
+        OllamaGenerateRequest {
             model: self.config.text_model.clone(),
-            messages: messages.to_vec(),
-            stream: self.config.stream,
+            messages: messages.iter().map(OllamaMessage::from).collect(),
+            stream: false,
             options: OllamaOptions {
                 num_predict: self.config.max_tokens,
                 temperature: self.config.temperature,
+                seed: self.config.seed,
+                top_p: self.config.top_p,
             },
-        };
+            keep_alive: self.keep_alive(),
+            tools: Vec::new(),
+        }
+    }
 
-        let url = self.api_url("chat");
+    /// Post a chat `request`, failing on an error status
+    async fn post_chat(
+        &self,
+        url: &str,
+        request: &OllamaGenerateRequest,
+        body_logger: Option<&BodyLogger>,
+    ) -> Result<reqwest::Response> {
         debug!("Making request to: {}", url);
+        if let Some(logger) = body_logger {
+            logger.log_request(url, &[], request);
+        }
 
         let response = timeout(
             Duration::from_secs(self.config.timeout),
-            self.client.post(&url).json(&request).send(),
+            self.client.post(url).json(request).send(),
         )
         .await
         .map_err(|_| {
@@ -279,11 +409,26 @@ impl LlmClient for OllamaClient {
             error!("Ollama API error: {}", error_text);
             return Err(LlmError::GenerationFailed(error_text).into());
         }
+        Ok(response)
+    }
 
-        let ollama_response: OllamaGenerateResponse = response
-            .json()
+    /// Send a chat `request` and parse the complete response
+    async fn send_chat(&self, request: &OllamaGenerateRequest) -> Result<OllamaGenerateResponse> {
+        let url = self.api_url("chat");
+        let body_logger =
+            BodyLogger::from_settings(self.config.log_bodies, self.config.log_body_max_len);
+        let response = self.post_chat(&url, request, body_logger.as_ref()).await?;
+
+        let status = response.status().as_u16();
+        let body = response
+            .text()
             .await
             .map_err(|e| LlmError::InvalidResponse(e.to_string()))?;
+        if let Some(logger) = &body_logger {
+            logger.log_response(&url, status, &body);
+        }
+        let ollama_response: OllamaGenerateResponse =
+            serde_json::from_str(&body).map_err(|e| LlmError::InvalidResponse(e.to_string()))?;
 
         if !ollama_response.done {
             return Err(LlmError::InvalidResponse("Incomplete response".to_string()).into());
@@ -293,25 +438,129 @@ impl LlmClient for OllamaClient {
             "Generated {} tokens",
             ollama_response.eval_count.unwrap_or(0)
         );
+        Ok(ollama_response)
+    }
+}
 
-        let response_text = ollama_response.message.content.clone();
+impl OllamaClient {
+    /// Send a chat `request` with streaming on and turn each line of the
+    /// NDJSON response into events
+    async fn stream_chat(
+        &self,
+        mut request: OllamaGenerateRequest,
+    ) -> Result<BoxStream<'static, Result<StreamEvent>>> {
+        request.stream = true;
+        let url = self.api_url("chat");
+        let body_logger =
+            BodyLogger::from_settings(self.config.log_bodies, self.config.log_body_max_len);
+        let response = self.post_chat(&url, &request, body_logger.as_ref()).await?;
+
+        let mut decoder = stream::NdjsonDecoder::default();
+        let events = response
+            .bytes_stream()
+            .map(Some)
+            .chain(futures::stream::once(async { None }))
+            .map(move |bytes| {
+                let lines = match bytes {
+                    Some(Ok(bytes)) => decoder.push(&bytes),
+                    Some(Err(e)) => {
+                        return vec![Err(LlmError::ConnectionFailed(e.to_string()).into())]
+                    }
+                    None => decoder.finish().into_iter().collect(),
+                };
+                lines
+                    .iter()
+                    .flat_map(|line| match serde_json::from_str(line) {
+                        Ok(chunk) => chunk_events(chunk),
+                        Err(e) => vec![Err(LlmError::InvalidResponse(e.to_string()).into())],
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .flat_map(futures::stream::iter);
+        Ok(events.boxed())
+    }
+}
 
-        // Cache the response if cache is available
+/// Events for one line of a streamed Ollama chat response; tool calls get a
+/// generated id, as Ollama sends none
+fn chunk_events(chunk: OllamaGenerateResponse) -> Vec<Result<StreamEvent>> {
+    let mut events = Vec::new();
+    if !chunk.message.thinking.is_empty() {
+        events.push(Ok(StreamEvent::Reasoning(chunk.message.thinking)));
+    }
+    if !chunk.message.content.is_empty() {
+        events.push(Ok(StreamEvent::TextDelta(chunk.message.content)));
+    }
+    events.extend(chunk.message.tool_calls.into_iter().map(|call| {
+        Ok(StreamEvent::ToolCall(ToolCall {
+            id: crate::ids::new_id().to_string(),
+            name: call.function.name,
+            arguments: call.function.arguments,
+        }))
+    }));
+    if chunk.done {
+        events.push(Ok(StreamEvent::Done {
+            finish_reason: chunk.done_reason,
+            usage: chunk.eval_count.map(|eval_count| {
+                TokenUsage::new(chunk.prompt_eval_count.unwrap_or(0), eval_count)
+            }),
+        }));
+    }
+    events
+}
+
+/// Tool in the function format Ollama's chat API expects
+fn ollama_tool(tool: &McpTool) -> serde_json::Value {
+    serde_json::json!({
+        "type": "function",
+        "function": {
+            "name": tool.name,
+            "description": tool.description,
+            "parameters": tool.input_schema,
+        }
+    })
+}
+
+#[async_trait]
+impl LlmClient for OllamaClient {
+    async fn generate(&self, messages: &[Message]) -> Result<GenerationResponse> {
+        debug!("Generating text with {} messages", messages.len());
+
+        // Try cache first if available
         if let Some(cache) = &self.cache {
-            let messages_json =
-                serde_json::to_string(&messages).unwrap_or_else(|_| format!("{:?}", messages));
+            let cache_key = LlmCache::compute_messages_cache_key(
+                messages,
+                &self.config.text_model,
+                self.config.temperature,
+                self.config.max_tokens,
+            );
 
-            let system_prompt = messages
-                .iter()
-                .find(|m| m.role == Role::System)
-                .map(|m| m.content.as_str());
+            if let Ok(Some(cached_response)) = cache.get(&cache_key).await {
+                debug!("Using cached response");
+                return Ok(GenerationResponse {
+                    text: cached_response,
+                    tokens_used: None,
+                    usage: None,
+                    model: self.config.text_model.clone(),
+                    finish_reason: Some("cached".to_string()),
+                    system_fingerprint: None,
+                    reasoning: None,
+                });
+            }
+        }
+
+        let request = self.chat_request(messages);
+        let ollama_response = self.send_chat(&request).await?;
 
-            let cache_key = LlmCache::compute_cache_key(
-                &messages_json,
+        let response_text = ollama_response.message.content.clone();
+
+        // Cache the response if cache is available
+        if let Some(cache) = &self.cache {
+            let cache_key = LlmCache::compute_messages_cache_key(
+                messages,
                 &self.config.text_model,
                 self.config.temperature,
                 self.config.max_tokens,
-                system_prompt,
             );
 
             if let Err(e) = cache
@@ -330,8 +579,13 @@ impl LlmClient for OllamaClient {
         Ok(GenerationResponse {
             text: response_text,
             tokens_used: ollama_response.eval_count,
+            usage: ollama_response.eval_count.map(|eval_count| {
+                TokenUsage::new(ollama_response.prompt_eval_count.unwrap_or(0), eval_count)
+            }),
             model: ollama_response.model,
             finish_reason: ollama_response.done_reason,
+            system_fingerprint: None,
+            reasoning: Some(ollama_response.message.thinking).filter(|t| !t.is_empty()),
         })
     }
 
@@ -415,6 +669,56 @@ impl LlmClient for OllamaClient {
         let models = self.list_models().await?;
         Ok(models.iter().any(|m| m == model))
     }
+
+    async fn generate_stream(
+        &self,
+        messages: &[Message],
+    ) -> Result<BoxStream<'static, Result<StreamEvent>>> {
+        debug!("Streaming with {} messages", messages.len());
+        self.stream_chat(self.chat_request(messages)).await
+    }
+
+    async fn generate_stream_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[McpTool],
+    ) -> Result<BoxStream<'static, Result<StreamEvent>>> {
+        debug!(
+            "Streaming with {} messages and {} tools",
+            messages.len(),
+            tools.len()
+        );
+        let mut request = self.chat_request(messages);
+        request.tools = tools.iter().map(ollama_tool).collect();
+        self.stream_chat(request).await
+    }
+
+    async fn preload_model(&self) -> Result<()> {
+        debug!("Preloading model {}", self.config.text_model);
+
+        // A chat request without messages loads the model without generating
+        let request = self.chat_request(&[]);
+
+        let url = self.api_url("chat");
+        let response = timeout(
+            Duration::from_secs(self.config.timeout),
+            self.client.post(&url).json(&request).send(),
+        )
+        .await
+        .map_err(|_| LlmError::Timeout)?
+        .map_err(|e| LlmError::ConnectionFailed(e.to_string()))?;
+
+        if !response.status().is_success() {
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(LlmError::ModelNotFound(error_text).into());
+        }
+
+        info!("Preloaded model {}", self.config.text_model);
+        Ok(())
+    }
 }
 
 /// Helper function to create a system message
@@ -422,6 +726,8 @@ pub fn system_message(content: impl Into<String>) -> Message {
     Message {
         role: Role::System,
         content: content.into(),
+        tool_calls: Vec::new(),
+        tool_call_id: None,
     }
 }
 
@@ -430,6 +736,8 @@ pub fn user_message(content: impl Into<String>) -> Message {
     Message {
         role: Role::User,
         content: content.into(),
+        tool_calls: Vec::new(),
+        tool_call_id: None,
     }
 }
 
@@ -438,6 +746,29 @@ pub fn assistant_message(content: impl Into<String>) -> Message {
     Message {
         role: Role::Assistant,
         content: content.into(),
+        tool_calls: Vec::new(),
+        tool_call_id: None,
+    }
+}
+
+/// Assistant message making `tool_calls`, with any text it came with
+pub fn assistant_tool_calls_message(
+    content: impl Into<String>,
+    tool_calls: Vec<ToolCall>,
+) -> Message {
+    Message {
+        tool_calls,
+        ..assistant_message(content)
+    }
+}
+
+/// Tool message carrying the result of the call `tool_call_id`
+pub fn tool_message(tool_call_id: impl Into<String>, content: impl Into<String>) -> Message {
+    Message {
+        role: Role::Tool,
+        content: content.into(),
+        tool_calls: Vec::new(),
+        tool_call_id: Some(tool_call_id.into()),
     }
 }
 
@@ -493,6 +824,190 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_try_new_rejects_unreadable_ca_certificate() {
+        let mut config = LlmConfig::default();
+        config.http.ca_cert_path = Some("/nonexistent/ca.pem".into());
+        assert!(OllamaClient::try_new(config).is_err());
+    }
+
+    #[test]
+    fn test_chat_request_includes_keep_alive() {
+        let client = OllamaClient::new(LlmConfig::default());
+        let body = serde_json::to_value(client.chat_request(&[user_message("hi")])).unwrap();
+        assert!(body.get("keep_alive").is_none());
+
+        let client = OllamaClient::new(LlmConfig {
+            keep_alive: Some("30m".to_string()),
+            ..Default::default()
+        });
+        let body = serde_json::to_value(client.chat_request(&[user_message("hi")])).unwrap();
+        assert_eq!(body["keep_alive"], "30m");
+
+        let client = OllamaClient::new(LlmConfig {
+            keep_alive: Some("-1".to_string()),
+            ..Default::default()
+        });
+        let body = serde_json::to_value(client.chat_request(&[])).unwrap();
+        assert_eq!(body["keep_alive"], -1);
+    }
+
+    #[test]
+    fn test_chat_request_offers_tools_and_parses_tool_calls() {
+        let client = OllamaClient::new(LlmConfig::default());
+        let mut request = client.chat_request(&[user_message("Weather in Paris?")]);
+        request.tools = vec![ollama_tool(&McpTool {
+            name: "get_weather".to_string(),
+            description: "Current weather in a city".to_string(),
+            input_schema: serde_json::json!({ "type": "object" }),
+        })];
+        let body = serde_json::to_value(&request).unwrap();
+        assert_eq!(body["tools"][0]["type"], "function");
+        assert_eq!(body["tools"][0]["function"]["name"], "get_weather");
+        assert_eq!(body["tools"][0]["function"]["parameters"]["type"], "object");
+
+        let response: OllamaGenerateResponse = serde_json::from_value(serde_json::json!({
+            "model": "llama3.2",
+            "message": {
+                "role": "assistant",
+                "content": "",
+                "tool_calls": [
+                    { "function": { "name": "get_weather", "arguments": { "city": "Paris" } } }
+                ]
+            },
+            "done": true
+        }))
+        .unwrap();
+        let call = &response.message.tool_calls[0].function;
+        assert_eq!(call.name, "get_weather");
+        assert_eq!(call.arguments["city"], "Paris");
+    }
+
+    /// Serves one chat request with `lines` as an NDJSON body, returning the
+    /// request body it received
+    async fn mock_ollama(
+        lines: &'static [&'static str],
+    ) -> (String, tokio::task::JoinHandle<String>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            let body_start = loop {
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                if let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break end + 4;
+                }
+            };
+            let headers = String::from_utf8_lossy(&request[..body_start]).to_lowercase();
+            let length: usize = headers
+                .lines()
+                .find_map(|line| line.strip_prefix("content-length:"))
+                .map(|length| length.trim().parse().unwrap())
+                .unwrap_or(0);
+            while request.len() < body_start + length {
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-type: application/x-ndjson\r\nconnection: close\r\n\r\n")
+                .await
+                .unwrap();
+            for line in lines {
+                socket.write_all(line.as_bytes()).await.unwrap();
+                socket.write_all(b"\n").await.unwrap();
+            }
+            String::from_utf8_lossy(&request[body_start..]).to_string()
+        });
+        (url, handle)
+    }
+
+    #[tokio::test]
+    async fn test_generate_stream_reads_ndjson_chunks() {
+        let (url, server) = mock_ollama(&[
+            r#"{"model":"llama3.2","message":{"role":"assistant","content":"","thinking":"A greeting"},"done":false}"#,
+            r#"{"model":"llama3.2","message":{"role":"assistant","content":"Hel"},"done":false}"#,
+            r#"{"model":"llama3.2","message":{"role":"assistant","content":"lo"},"done":false}"#,
+            r#"{"model":"llama3.2","message":{"role":"assistant","content":"","tool_calls":[{"function":{"name":"get_weather","arguments":{"city":"Paris"}}}]},"done":false}"#,
+            r#"{"model":"llama3.2","message":{"role":"assistant","content":""},"done":true,"done_reason":"stop","prompt_eval_count":5,"eval_count":3}"#,
+        ])
+        .await;
+        let client = OllamaClient::new(LlmConfig {
+            ollama_url: url,
+            ..Default::default()
+        });
+        let tool = McpTool {
+            name: "get_weather".to_string(),
+            description: "Current weather in a city".to_string(),
+            input_schema: serde_json::json!({ "type": "object" }),
+        };
+
+        let events: Vec<StreamEvent> = client
+            .generate_stream_with_tools(&[user_message("Weather in Paris?")], &[tool])
+            .await
+            .unwrap()
+            .map(|event| event.unwrap())
+            .collect()
+            .await;
+
+        let request: serde_json::Value = serde_json::from_str(&server.await.unwrap()).unwrap();
+        assert_eq!(request["stream"], true);
+        assert_eq!(request["tools"][0]["function"]["name"], "get_weather");
+        assert_eq!(events.len(), 5);
+        assert!(matches!(&events[0], StreamEvent::Reasoning(text) if text == "A greeting"));
+        assert!(matches!(&events[1], StreamEvent::TextDelta(text) if text == "Hel"));
+        assert!(matches!(&events[2], StreamEvent::TextDelta(text) if text == "lo"));
+        assert!(matches!(
+            &events[3],
+            StreamEvent::ToolCall(call) if call.name == "get_weather" && call.arguments["city"] == "Paris"
+        ));
+        match &events[4] {
+            StreamEvent::Done {
+                finish_reason,
+                usage,
+            } => {
+                assert_eq!(finish_reason.as_deref(), Some("stop"));
+                assert_eq!(usage.as_ref().unwrap().total(), 8);
+            }
+            other => panic!("expected Done, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_generate_reads_thinking_apart_from_content() {
+        let (url, server) = mock_ollama(&[
+            r#"{"model":"qwen3","message":{"role":"assistant","content":"4","thinking":"2 + 2"},"done":true}"#,
+        ])
+        .await;
+        let client = OllamaClient::new(LlmConfig {
+            ollama_url: url,
+            ..Default::default()
+        });
+
+        let response = client.generate(&[user_message("2+2?")]).await.unwrap();
+        server.await.unwrap();
+        assert_eq!(response.text, "4");
+        assert_eq!(response.reasoning.as_deref(), Some("2 + 2"));
+    }
+
+    #[test]
+    fn test_chat_request_includes_seed() {
+        let client = OllamaClient::new(LlmConfig::default());
+        let body = serde_json::to_value(client.chat_request(&[user_message("hi")])).unwrap();
+        assert!(body["options"].get("seed").is_none());
+
+        let client = OllamaClient::new(LlmConfig {
+            seed: Some(42),
+            ..Default::default()
+        });
+        let body = serde_json::to_value(client.chat_request(&[user_message("hi")])).unwrap();
+        assert_eq!(body["options"]["seed"], 42);
+    }
+
     #[tokio::test]
     async fn test_mock_llm_client() {
         let mut mock_client = MockMockLlmClient::new();
@@ -505,8 +1020,11 @@ mod tests {
                 Ok(GenerationResponse {
                     text: "Hello! How can I help you?".to_string(),
                     tokens_used: Some(10),
+                    usage: None,
                     model: "test-model".to_string(),
                     finish_reason: Some("stop".to_string()),
+                    system_fingerprint: None,
+                    reasoning: None,
                 })
             });
 
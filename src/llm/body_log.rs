@@ -0,0 +1,183 @@
+//! Debug logging of raw provider request and response bodies
+//!
+//! Off by default. When enabled, bodies are logged at debug level and
+//! truncated so a long conversation does not flood the logs. Credentials in
+//! auth headers and `key=` query parameters are masked.
+
+use serde::Serialize;
+use tracing::debug;
+
+/// Headers whose values are credentials
+const SECRET_HEADERS: &[&str] = &["authorization", "x-api-key", "api-key", "x-goog-api-key"];
+
+/// Query parameters whose values are credentials
+const SECRET_PARAMS: &[&str] = &["key", "api_key", "apikey"];
+
+const MASK: &str = "****";
+
+pub fn default_log_body_max_len() -> usize {
+    2048
+}
+
+/// Logs provider request and response bodies
+#[derive(Debug, Clone, Copy)]
+pub struct BodyLogger {
+    max_len: usize,
+}
+
+impl BodyLogger {
+    pub fn new(max_len: usize) -> Self {
+        Self { max_len }
+    }
+
+    /// Logger for the given settings, or `None` when body logging is off
+    pub fn from_settings(log_bodies: bool, max_len: usize) -> Option<Self> {
+        log_bodies.then(|| Self::new(max_len))
+    }
+
+    pub fn log_request<T: Serialize>(&self, url: &str, headers: &[(&str, &str)], body: &T) {
+        let body =
+            serde_json::to_string(body).unwrap_or_else(|e| format!("<unserializable: {}>", e));
+        let headers: Vec<String> = headers
+            .iter()
+            .map(|(name, value)| format!("{}: {}", name, mask_header(name, value)))
+            .collect();
+        debug!(
+            "Provider request to {} [{}]: {}",
+            mask_url(url),
+            headers.join(", "),
+            truncate_body(&body, self.max_len)
+        );
+    }
+
+    pub fn log_response(&self, url: &str, status: u16, body: &str) {
+        debug!(
+            "Provider response from {} ({}): {}",
+            mask_url(url),
+            status,
+            truncate_body(body, self.max_len)
+        );
+    }
+}
+
+/// Mask a credential header, keeping the auth scheme (e.g. `Bearer ****`)
+fn mask_header(name: &str, value: &str) -> String {
+    if !SECRET_HEADERS.contains(&name.to_lowercase().as_str()) {
+        return value.to_string();
+    }
+    match value.split_once(' ') {
+        Some((scheme, _)) => format!("{} {}", scheme, MASK),
+        None => MASK.to_string(),
+    }
+}
+
+/// Mask credential query parameters such as Gemini's `?key=`
+fn mask_url(url: &str) -> String {
+    let Some((base, query)) = url.split_once('?') else {
+        return url.to_string();
+    };
+    let params: Vec<String> = query
+        .split('&')
+        .map(|param| match param.split_once('=') {
+            Some((name, _)) if SECRET_PARAMS.contains(&name.to_lowercase().as_str()) => {
+                format!("{}={}", name, MASK)
+            }
+            _ => param.to_string(),
+        })
+        .collect();
+    format!("{}?{}", base, params.join("&"))
+}
+
+/// Cut `body` to at most `max_len` bytes on a character boundary
+fn truncate_body(body: &str, max_len: usize) -> String {
+    if body.len() <= max_len {
+        return body.to_string();
+    }
+    let mut end = max_len;
+    while !body.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}... ({} bytes truncated)", &body[..end], body.len() - end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    /// Collects the formatted message of every event
+    #[derive(Clone, Default)]
+    struct CapturedMessages(Arc<Mutex<Vec<String>>>);
+
+    struct MessageVisitor<'a>(&'a mut String);
+
+    impl tracing::field::Visit for MessageVisitor<'_> {
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            if field.name() == "message" {
+                *self.0 = format!("{:?}", value);
+            }
+        }
+    }
+
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for CapturedMessages {
+        fn on_event(
+            &self,
+            event: &tracing::Event<'_>,
+            _ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            let mut message = String::new();
+            event.record(&mut MessageVisitor(&mut message));
+            self.0.lock().unwrap().push(message);
+        }
+    }
+
+    #[test]
+    fn test_logs_truncated_bodies_with_masked_credentials() {
+        let captured = CapturedMessages::default();
+        let subscriber = tracing_subscriber::registry().with(captured.clone());
+        let logger = BodyLogger::new(64);
+        let body = serde_json::json!({ "model": "gpt-4o", "prompt": "x".repeat(500) });
+
+        tracing::subscriber::with_default(subscriber, || {
+            logger.log_request(
+                "https://api.example.com/v1/chat?key=secret-query-key&alt=json",
+                &[
+                    ("Authorization", "Bearer sk-secret-token"),
+                    ("Content-Type", "application/json"),
+                ],
+                &body,
+            );
+            logger.log_response("https://api.example.com/v1/chat", 200, &"y".repeat(500));
+        });
+
+        let messages = captured.0.lock().unwrap();
+        assert_eq!(messages.len(), 2);
+
+        let request = &messages[0];
+        assert!(request.contains("\"model\":\"gpt-4o\""));
+        assert!(request.contains("bytes truncated"));
+        assert!(request.contains("Authorization: Bearer ****"));
+        assert!(request.contains("Content-Type: application/json"));
+        assert!(request.contains("key=****&alt=json"));
+        assert!(!request.contains("sk-secret-token"));
+        assert!(!request.contains("secret-query-key"));
+        assert!(!request.contains(&"x".repeat(100)));
+
+        let response = &messages[1];
+        assert!(response.contains("(200)"));
+        assert!(response.contains("436 bytes truncated"));
+    }
+
+    #[test]
+    fn test_disabled_by_settings() {
+        assert!(BodyLogger::from_settings(false, 100).is_none());
+        assert!(BodyLogger::from_settings(true, 100).is_some());
+    }
+
+    #[test]
+    fn test_truncate_respects_char_boundaries() {
+        assert_eq!(truncate_body("héllo", 2), "h... (5 bytes truncated)");
+        assert_eq!(truncate_body("short", 10), "short");
+    }
+}
@@ -0,0 +1,189 @@
+//! Model capability discovery
+//!
+//! Catches configuration mistakes such as an embedding-only model configured
+//! as the text model before the first request fails with an opaque server
+//! error. Capabilities are inferred from the model name, with explicit entries
+//! for models the naming rules get wrong. For Ollama, the configured models can
+//! also be checked against the server's installed models.
+
+use crate::config::AgentConfig;
+use crate::error::{AgentError, Result};
+use crate::llm::{LlmClient, OllamaClient};
+use std::collections::HashMap;
+use tracing::debug;
+
+/// Something a model can be used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModelCapability {
+    /// Chat and text generation
+    Generation,
+    /// Vector embeddings
+    Embedding,
+}
+
+/// Name fragments identifying embedding-only model families
+const EMBEDDING_FAMILIES: &[&str] = &["embed", "bge-", "bge:", "minilm", "e5-", "gte-"];
+
+/// Registry answering which capabilities a model has
+#[derive(Debug, Clone, Default)]
+pub struct ModelCapabilities {
+    /// Explicit entries keyed by model name without its tag
+    models: HashMap<String, Vec<ModelCapability>>,
+}
+
+impl ModelCapabilities {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare the capabilities of a model, overriding the naming rules
+    pub fn with_model(mut self, model: &str, capabilities: Vec<ModelCapability>) -> Self {
+        self.models
+            .insert(base_name(model).to_lowercase(), capabilities);
+        self
+    }
+
+    /// Capabilities of `model`; names matching an embedding family are
+    /// embedding-only and everything else is a generation model
+    pub fn capabilities(&self, model: &str) -> Vec<ModelCapability> {
+        let name = model.to_lowercase();
+        if let Some(capabilities) = self.models.get(base_name(&name)) {
+            return capabilities.clone();
+        }
+        if EMBEDDING_FAMILIES
+            .iter()
+            .any(|family| name.contains(family))
+        {
+            vec![ModelCapability::Embedding]
+        } else {
+            vec![ModelCapability::Generation]
+        }
+    }
+
+    pub fn supports(&self, model: &str, capability: ModelCapability) -> bool {
+        self.capabilities(model).contains(&capability)
+    }
+
+    /// Check that the configured text model can generate and the embedding
+    /// model can embed
+    pub fn validate_config(&self, config: &AgentConfig) -> Result<()> {
+        let text_model = &config.llm.text_model;
+        if !self.supports(text_model, ModelCapability::Generation) {
+            return Err(AgentError::Config(format!(
+                "text_model '{}' is an embedding model and cannot generate text; \
+                 set text_model to a chat model such as 'llama3.2'",
+                text_model
+            )));
+        }
+
+        let embedding_model = &config.llm.embedding_model;
+        if !self.supports(embedding_model, ModelCapability::Embedding) {
+            return Err(AgentError::Config(format!(
+                "embedding_model '{}' does not produce embeddings; \
+                 set embedding_model to an embedding model such as 'nomic-embed-text'",
+                embedding_model
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Validate the configured models with the built-in capability rules
+pub fn validate_config(config: &AgentConfig) -> Result<()> {
+    ModelCapabilities::new().validate_config(config)
+}
+
+/// Check that the configured text and embedding models are installed on the
+/// Ollama server, via `/api/tags`
+pub async fn validate_ollama_models(config: &AgentConfig) -> Result<()> {
+    let client = OllamaClient::try_new(config.llm.clone())?;
+    let installed = client.list_models().await?;
+    debug!("Ollama reports {} installed models", installed.len());
+
+    for (setting, model) in [
+        ("text_model", &config.llm.text_model),
+        ("embedding_model", &config.llm.embedding_model),
+    ] {
+        if !installed.iter().any(|name| same_model(name, model)) {
+            return Err(AgentError::Config(format!(
+                "{} '{}' is not installed on the Ollama server at {}; run `ollama pull {}`",
+                setting, model, config.llm.ollama_url, model
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Model name without its `:tag`
+fn base_name(model: &str) -> &str {
+    model.split_once(':').map_or(model, |(name, _)| name)
+}
+
+/// Ollama lists untagged pulls as `name:latest`
+fn same_model(installed: &str, configured: &str) -> bool {
+    installed == configured
+        || (!configured.contains(':') && installed == format!("{}:latest", configured))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Json, Router};
+
+    #[test]
+    fn test_default_config_is_valid() {
+        assert!(validate_config(&AgentConfig::default()).is_ok());
+    }
+
+    #[test]
+    fn test_embedding_model_as_text_model_is_rejected() {
+        let mut config = AgentConfig::default();
+        config.llm.text_model = "nomic-embed-text".to_string();
+
+        let err = validate_config(&config).unwrap_err().to_string();
+        assert!(err.contains("text_model 'nomic-embed-text' is an embedding model"));
+
+        let mut config = AgentConfig::default();
+        config.llm.embedding_model = "llama3.2".to_string();
+        let err = validate_config(&config).unwrap_err().to_string();
+        assert!(err.contains("embedding_model 'llama3.2' does not produce embeddings"));
+
+        // An explicit entry overrides the naming rules
+        let capabilities = ModelCapabilities::new().with_model(
+            "llama3.2",
+            vec![ModelCapability::Generation, ModelCapability::Embedding],
+        );
+        assert!(capabilities.validate_config(&config).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_validate_ollama_models_checks_installed_tags() {
+        let app = Router::new().route(
+            "/api/tags",
+            get(|| async {
+                Json(serde_json::json!({
+                    "models": [
+                        { "name": "llama3.2:latest" },
+                        { "name": "nomic-embed-text:latest" }
+                    ]
+                }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let mut config = AgentConfig::default();
+        config.llm.ollama_url = format!("http://{}", addr);
+        assert!(validate_ollama_models(&config).await.is_ok());
+
+        config.llm.embedding_model = "mxbai-embed-large".to_string();
+        let err = validate_ollama_models(&config)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("embedding_model 'mxbai-embed-large' is not installed"));
+    }
+}
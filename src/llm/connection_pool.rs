@@ -2,11 +2,53 @@
 //!
 //! This module provides a semaphore-based connection pool to prevent
 //! overwhelming the Ollama server with too many concurrent requests.
+//!
+//! `acquire` is cancellation safe: a waiter dropped before it is granted a
+//! slot leaves the queue without taking one, and a granted slot is handed
+//! straight to a [`ConnectionPermit`] that returns it when dropped.
 
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::Semaphore;
 use tracing::{debug, warn};
 
+/// Snapshot of pool usage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolStats {
+    /// Permits currently held
+    pub in_use: usize,
+    /// Permits free to acquire
+    pub available: usize,
+    /// Acquisitions that found the pool saturated and had to wait, including
+    /// waiters cancelled before they got a permit
+    pub total_waits: u64,
+    /// Longest wait observed, in milliseconds
+    pub max_wait_ms: u64,
+}
+
+#[derive(Debug, Default)]
+struct WaitMetrics {
+    total_waits: AtomicU64,
+    max_wait_ms: AtomicU64,
+}
+
+/// Records a wait when dropped, whether or not the wait ended with a permit
+struct WaitTimer<'a> {
+    metrics: &'a WaitMetrics,
+    started: Instant,
+}
+
+impl Drop for WaitTimer<'_> {
+    fn drop(&mut self) {
+        let waited_ms = self.started.elapsed().as_millis() as u64;
+        self.metrics.total_waits.fetch_add(1, Ordering::Relaxed);
+        self.metrics
+            .max_wait_ms
+            .fetch_max(waited_ms, Ordering::Relaxed);
+    }
+}
+
 /// Connection pool for rate-limiting Ollama requests
 #[derive(Clone)]
 pub struct OllamaConnectionPool {
@@ -14,6 +56,8 @@ pub struct OllamaConnectionPool {
     semaphore: Arc<Semaphore>,
     /// Maximum concurrent connections
     max_connections: usize,
+    /// Wait counters shared by clones of the pool
+    metrics: Arc<WaitMetrics>,
 }
 
 impl Default for OllamaConnectionPool {
@@ -32,6 +76,7 @@ impl OllamaConnectionPool {
         Self {
             semaphore: Arc::new(Semaphore::new(max_connections)),
             max_connections,
+            metrics: Arc::new(WaitMetrics::default()),
         }
     }
 
@@ -39,12 +84,18 @@ impl OllamaConnectionPool {
     /// This will block if all connections are in use
     pub async fn acquire(&self) -> ConnectionPermit {
         let available = self.semaphore.available_permits();
-        if available == 0 {
+        let _timer = if available == 0 {
             warn!(
                 "All {} Ollama connections in use, waiting for available slot...",
                 self.max_connections
             );
-        }
+            Some(WaitTimer {
+                metrics: &self.metrics,
+                started: Instant::now(),
+            })
+        } else {
+            None
+        };
 
         let permit = self
             .semaphore
@@ -75,6 +126,17 @@ impl OllamaConnectionPool {
     pub fn max_connections(&self) -> usize {
         self.max_connections
     }
+
+    /// Current usage and wait statistics
+    pub fn stats(&self) -> PoolStats {
+        let available = self.semaphore.available_permits();
+        PoolStats {
+            in_use: self.max_connections.saturating_sub(available),
+            available,
+            total_waits: self.metrics.total_waits.load(Ordering::Relaxed),
+            max_wait_ms: self.metrics.max_wait_ms.load(Ordering::Relaxed),
+        }
+    }
 }
 
 /// A permit that represents an active connection
@@ -147,4 +209,40 @@ mod tests {
         let result = task.await.unwrap();
         assert_eq!(result, "acquired");
     }
+
+    #[tokio::test]
+    async fn test_cancelled_waiter_returns_capacity_and_records_wait() {
+        let pool = OllamaConnectionPool::new(2);
+        let first = pool.acquire().await;
+        let second = pool.acquire().await;
+        assert_eq!(pool.stats().in_use, 2);
+        assert_eq!(pool.stats().total_waits, 0);
+
+        // A waiter on the saturated pool gives up
+        let waiter = tokio::time::timeout(Duration::from_millis(30), pool.acquire()).await;
+        assert!(waiter.is_err());
+
+        // A waiter granted a permit and then aborted must not leak it
+        let pool_clone = pool.clone();
+        let task = tokio::spawn(async move {
+            let _permit = pool_clone.acquire().await;
+            sleep(Duration::from_secs(60)).await;
+        });
+        sleep(Duration::from_millis(10)).await;
+        drop(first);
+        sleep(Duration::from_millis(10)).await;
+        assert_eq!(pool.available_permits(), 0);
+        task.abort();
+        let _ = task.await;
+
+        drop(second);
+        let stats = pool.stats();
+        assert_eq!(stats.available, 2);
+        assert_eq!(stats.in_use, 0);
+        assert_eq!(stats.total_waits, 2);
+        assert!(stats.max_wait_ms >= 30);
+
+        let _third = pool.acquire().await;
+        assert_eq!(pool.stats().total_waits, 2);
+    }
 }
@@ -0,0 +1,259 @@
+//! Context window guard
+//!
+//! Estimates the prompt size of a conversation for a given model and keeps it
+//! within the model's context window, leaving room for the completion. Token
+//! counts are estimated from character counts using a ratio per model family,
+//! which is close enough to avoid overflow errors. Exact counts are available
+//! from [`tokenizer`](super::tokenizer) where precision matters.
+
+use crate::error::{LlmError, Result};
+use crate::llm::{Message, Role};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use tracing::{debug, warn};
+
+/// What to do when a conversation does not fit the context window
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContextOverflowPolicy {
+    /// Drop the oldest non-system messages until the conversation fits
+    #[default]
+    Trim,
+    /// Fail the request without sending it
+    Error,
+}
+
+/// Fixed per-message cost for role markers and separators
+const MESSAGE_OVERHEAD_TOKENS: usize = 4;
+
+/// Tokenizer families with distinct characters-per-token ratios
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenizerFamily {
+    /// OpenAI GPT and o-series models
+    Gpt,
+    /// Anthropic Claude models
+    Claude,
+    /// Google Gemini models
+    Gemini,
+    /// Llama, Mistral, Qwen and other SentencePiece-style models
+    OpenWeights,
+    /// Anything unrecognized
+    Generic,
+}
+
+impl TokenizerFamily {
+    pub fn for_model(model: &str) -> Self {
+        let model = model.to_lowercase();
+        let o_series = model
+            .strip_prefix('o')
+            .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit()));
+
+        if model.starts_with("gpt") || o_series {
+            Self::Gpt
+        } else if model.contains("claude") {
+            Self::Claude
+        } else if model.contains("gemini") {
+            Self::Gemini
+        } else if [
+            "llama", "mistral", "mixtral", "qwen", "gemma", "phi", "deepseek",
+        ]
+        .iter()
+        .any(|family| model.contains(family))
+        {
+            Self::OpenWeights
+        } else {
+            Self::Generic
+        }
+    }
+
+    pub(crate) fn chars_per_token(self) -> f64 {
+        match self {
+            Self::Gpt => 4.0,
+            Self::Claude => 3.5,
+            Self::Gemini => 4.0,
+            Self::OpenWeights => 3.6,
+            Self::Generic => 3.5,
+        }
+    }
+
+    /// Estimated token count of `text`, rounded up
+    pub fn estimate_tokens(self, text: &str) -> usize {
+        (text.chars().count() as f64 / self.chars_per_token()).ceil() as usize
+    }
+
+    /// Estimated prompt tokens for a conversation
+    pub fn estimate_messages(self, messages: &[Message]) -> usize {
+        messages
+            .iter()
+            .map(|m| self.estimate_tokens(&m.content) + MESSAGE_OVERHEAD_TOKENS)
+            .sum()
+    }
+}
+
+/// Keeps prompts within a model's context window
+#[derive(Debug, Clone)]
+pub struct ContextWindowGuard {
+    family: TokenizerFamily,
+    context_window: usize,
+    reserved_completion_tokens: usize,
+    policy: ContextOverflowPolicy,
+}
+
+impl ContextWindowGuard {
+    pub fn new(
+        model: &str,
+        context_window: u32,
+        reserved_completion_tokens: u32,
+        policy: ContextOverflowPolicy,
+    ) -> Self {
+        Self {
+            family: TokenizerFamily::for_model(model),
+            context_window: context_window as usize,
+            reserved_completion_tokens: reserved_completion_tokens as usize,
+            policy,
+        }
+    }
+
+    /// Tokens available for the prompt
+    pub fn prompt_budget(&self) -> usize {
+        self.context_window
+            .saturating_sub(self.reserved_completion_tokens)
+    }
+
+    /// Return the messages to send, trimmed if the policy allows.
+    ///
+    /// System messages and the latest message are always kept. If those alone
+    /// exceed the budget, or the policy is [`ContextOverflowPolicy::Error`], the
+    /// request fails with [`LlmError::ContextWindowExceeded`].
+    pub fn fit<'a>(&self, messages: &'a [Message]) -> Result<Cow<'a, [Message]>> {
+        let budget = self.prompt_budget();
+        let estimated = self.family.estimate_messages(messages);
+        if estimated <= budget {
+            return Ok(Cow::Borrowed(messages));
+        }
+
+        if self.policy == ContextOverflowPolicy::Error {
+            return Err(LlmError::ContextWindowExceeded {
+                estimated,
+                limit: budget,
+            }
+            .into());
+        }
+
+        let last = messages.len() - 1;
+        let mut total = estimated;
+        let mut keep = vec![true; messages.len()];
+        for (i, message) in messages.iter().enumerate() {
+            if total <= budget {
+                break;
+            }
+            if message.role == Role::System || i == last {
+                continue;
+            }
+            keep[i] = false;
+            total -= self.family.estimate_tokens(&message.content) + MESSAGE_OVERHEAD_TOKENS;
+        }
+
+        if total > budget {
+            return Err(LlmError::ContextWindowExceeded {
+                estimated: total,
+                limit: budget,
+            }
+            .into());
+        }
+
+        let trimmed: Vec<Message> = messages
+            .iter()
+            .zip(keep)
+            .filter(|(_, keep)| *keep)
+            .map(|(message, _)| message.clone())
+            .collect();
+        warn!(
+            "Trimmed {} oldest messages to fit the context window (~{} -> ~{} of {} tokens)",
+            messages.len() - trimmed.len(),
+            estimated,
+            total,
+            budget
+        );
+        debug!("Kept {} messages after trimming", trimmed.len());
+        Ok(Cow::Owned(trimmed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AgentError;
+    use crate::llm::{assistant_message, system_message, user_message};
+
+    fn long_conversation() -> Vec<Message> {
+        let filler = "word ".repeat(400); // ~2000 chars
+        let mut messages = vec![system_message("You are helpful.")];
+        for i in 0..20 {
+            messages.push(user_message(format!("question {} {}", i, filler)));
+            messages.push(assistant_message(format!("answer {} {}", i, filler)));
+        }
+        messages.push(user_message("latest question"));
+        messages
+    }
+
+    #[test]
+    fn test_trims_oldest_messages_under_limit() {
+        let guard = ContextWindowGuard::new("gpt-4o", 8_000, 1_000, ContextOverflowPolicy::Trim);
+        let messages = long_conversation();
+        let family = TokenizerFamily::for_model("gpt-4o");
+        assert!(family.estimate_messages(&messages) > guard.prompt_budget());
+
+        let fitted = guard.fit(&messages).unwrap();
+
+        assert!(family.estimate_messages(&fitted) <= guard.prompt_budget());
+        assert!(fitted.len() < messages.len());
+        assert_eq!(fitted[0].role, Role::System);
+        assert_eq!(fitted.last().unwrap().content, "latest question");
+        // The most recent history survives
+        assert!(fitted[fitted.len() - 2].content.starts_with("answer 19"));
+    }
+
+    #[test]
+    fn test_error_policy_rejects_oversized_prompt() {
+        let guard = ContextWindowGuard::new(
+            "claude-3-5-sonnet",
+            8_000,
+            1_000,
+            ContextOverflowPolicy::Error,
+        );
+        let err = guard.fit(&long_conversation()).unwrap_err();
+        assert!(matches!(
+            err,
+            AgentError::Llm(LlmError::ContextWindowExceeded { .. })
+        ));
+
+        let small = vec![user_message("hi")];
+        assert!(matches!(guard.fit(&small).unwrap(), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_tokenizer_family_detection() {
+        assert_eq!(
+            TokenizerFamily::for_model("gpt-4o-mini"),
+            TokenizerFamily::Gpt
+        );
+        assert_eq!(TokenizerFamily::for_model("o3-mini"), TokenizerFamily::Gpt);
+        assert_eq!(
+            TokenizerFamily::for_model("claude-3-haiku"),
+            TokenizerFamily::Claude
+        );
+        assert_eq!(
+            TokenizerFamily::for_model("gemini-1.5-pro"),
+            TokenizerFamily::Gemini
+        );
+        assert_eq!(
+            TokenizerFamily::for_model("llama3.2:3b"),
+            TokenizerFamily::OpenWeights
+        );
+        assert_eq!(
+            TokenizerFamily::for_model("omni-model"),
+            TokenizerFamily::Generic
+        );
+    }
+}
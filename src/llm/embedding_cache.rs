@@ -0,0 +1,255 @@
+//! Embedding cache
+//!
+//! Repeated queries and re-ingested content ask for the same embeddings over
+//! and over. An [`EmbeddingCache`] keeps the most recently used vectors,
+//! keyed by a hash of the embedding model and the text, and
+//! [`CachedEmbeddingClient`] consults it before embedding through the client
+//! it wraps. The cache is shared through an `Arc`, so the clients used for an
+//! agent's memory and for knowledge ingestion can reuse each other's vectors.
+
+use super::stream::StreamEvent;
+use super::{EmbeddingResponse, GenerationResponse, LlmClient, Message};
+use crate::error::Result;
+use crate::mcp::McpTool;
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::debug;
+
+/// Configuration for the embedding cache
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingCacheConfig {
+    /// Enable or disable caching (off by default)
+    pub enabled: bool,
+
+    /// Maximum number of cached embeddings
+    pub max_entries: usize,
+}
+
+impl Default for EmbeddingCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_entries: 1000,
+        }
+    }
+}
+
+/// Hit and miss counts of an [`EmbeddingCache`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmbeddingCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+impl EmbeddingCacheStats {
+    /// Fraction of lookups served from the cache
+    pub fn hit_rate(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 0.0,
+            lookups => self.hits as f64 / lookups as f64,
+        }
+    }
+}
+
+/// Least-recently-used cache of embeddings
+pub struct EmbeddingCache {
+    /// Entries from least to most recently used
+    entries: Mutex<IndexMap<u64, Vec<f32>>>,
+    max_entries: usize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl EmbeddingCache {
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            entries: Mutex::new(IndexMap::new()),
+            max_entries: max_entries.max(1),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Cache sized by `config`, or `None` when caching is disabled
+    pub fn from_config(config: &EmbeddingCacheConfig) -> Option<Arc<Self>> {
+        config
+            .enabled
+            .then(|| Arc::new(Self::new(config.max_entries)))
+    }
+
+    fn key(model: &str, text: &str) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        model.hash(&mut hasher);
+        text.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Cached embedding of `text` by `model`, marking it most recently used
+    pub fn get(&self, model: &str, text: &str) -> Option<Vec<f32>> {
+        let mut entries = self.entries.lock().unwrap();
+        let hit = entries
+            .shift_remove_entry(&Self::key(model, text))
+            .map(|(key, embedding)| {
+                entries.insert(key, embedding.clone());
+                embedding
+            });
+        let counter = if hit.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        hit
+    }
+
+    /// Cache the embedding of `text` by `model`, evicting the least recently
+    /// used entry when full
+    pub fn insert(&self, model: &str, text: &str, embedding: Vec<f32>) {
+        let key = Self::key(model, text);
+        let mut entries = self.entries.lock().unwrap();
+        entries.shift_remove(&key);
+        if entries.len() >= self.max_entries {
+            entries.shift_remove_index(0);
+        }
+        entries.insert(key, embedding);
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    pub fn stats(&self) -> EmbeddingCacheStats {
+        EmbeddingCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.entries.lock().unwrap().len(),
+        }
+    }
+}
+
+/// Client that serves repeated embeddings from an [`EmbeddingCache`]
+pub struct CachedEmbeddingClient {
+    inner: Arc<dyn LlmClient>,
+    cache: Arc<EmbeddingCache>,
+    model: String,
+}
+
+impl CachedEmbeddingClient {
+    /// Cache the embeddings `inner` makes with `model` in `cache`
+    pub fn new(inner: Arc<dyn LlmClient>, cache: Arc<EmbeddingCache>, model: &str) -> Self {
+        Self {
+            inner,
+            cache,
+            model: model.to_string(),
+        }
+    }
+
+    pub fn cache(&self) -> &Arc<EmbeddingCache> {
+        &self.cache
+    }
+}
+
+#[async_trait]
+impl LlmClient for CachedEmbeddingClient {
+    async fn generate(&self, messages: &[Message]) -> Result<GenerationResponse> {
+        self.inner.generate(messages).await
+    }
+
+    async fn embed(&self, text: &str) -> Result<EmbeddingResponse> {
+        if let Some(embedding) = self.cache.get(&self.model, text) {
+            debug!("Using cached embedding");
+            return Ok(EmbeddingResponse {
+                embedding,
+                model: self.model.clone(),
+            });
+        }
+        let response = self.inner.embed(text).await?;
+        self.cache
+            .insert(&self.model, text, response.embedding.clone());
+        Ok(response)
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>> {
+        self.inner.list_models().await
+    }
+
+    async fn is_model_available(&self, model: &str) -> Result<bool> {
+        self.inner.is_model_available(model).await
+    }
+
+    async fn generate_stream(
+        &self,
+        messages: &[Message],
+    ) -> Result<BoxStream<'static, Result<StreamEvent>>> {
+        self.inner.generate_stream(messages).await
+    }
+
+    async fn generate_stream_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[McpTool],
+    ) -> Result<BoxStream<'static, Result<StreamEvent>>> {
+        self.inner.generate_stream_with_tools(messages, tools).await
+    }
+
+    async fn preload_model(&self) -> Result<()> {
+        self.inner.preload_model().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::mock::MockLlm;
+
+    /// Embeds text as its length
+    fn length_embedder() -> Arc<MockLlm> {
+        Arc::new(MockLlm::new().with_embed(|text| Ok(vec![text.len() as f32])))
+    }
+
+    #[test]
+    fn test_cache_is_opt_in() {
+        assert!(EmbeddingCache::from_config(&EmbeddingCacheConfig::default()).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_identical_text_is_embedded_once() {
+        let embedder = length_embedder();
+        let cache = Arc::new(EmbeddingCache::new(10));
+        // Memory and ingestion clients sharing one cache
+        let memory = CachedEmbeddingClient::new(embedder.clone(), cache.clone(), "embedder");
+        let ingestion = CachedEmbeddingClient::new(embedder.clone(), cache.clone(), "embedder");
+
+        let first = memory.embed("rust ownership").await.unwrap();
+        let second = ingestion.embed("rust ownership").await.unwrap();
+
+        assert_eq!(first.embedding, second.embedding);
+        assert_eq!(embedder.embedded().len(), 1);
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+        assert_eq!(stats.hit_rate(), 0.5);
+    }
+
+    #[test]
+    fn test_least_recently_used_entry_is_evicted() {
+        let cache = EmbeddingCache::new(2);
+        cache.insert("m", "a", vec![1.0]);
+        cache.insert("m", "b", vec![2.0]);
+        assert!(cache.get("m", "a").is_some());
+
+        cache.insert("m", "c", vec![3.0]);
+
+        assert!(cache.get("m", "b").is_none());
+        assert!(cache.get("m", "a").is_some());
+        assert!(cache.get("m", "c").is_some());
+        // Keys include the model
+        assert!(cache.get("other", "a").is_none());
+    }
+}
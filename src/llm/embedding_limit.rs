@@ -0,0 +1,324 @@
+//! Embedding input limits
+//!
+//! Embedding models accept far less text than chat models, and servers
+//! handle longer inputs inconsistently: some fail the request, others
+//! silently embed only the beginning. [`LimitedEmbeddingClient`] counts the
+//! tokens of each input against the embedding model's limit. Inputs over it
+//! are either split into chunks that fit, embedded separately and averaged
+//! into one vector, or rejected, as [`EmbeddingOverflowPolicy`] decides. How
+//! each input was handled is counted in [`EmbeddingLimitStats`].
+
+use super::stream::StreamEvent;
+use super::tokenizer::{self, Tokenizer};
+use super::{EmbeddingResponse, GenerationResponse, LlmClient, Message};
+use crate::config::LlmConfig;
+use crate::error::{LlmError, Result};
+use crate::mcp::McpTool;
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tracing::{debug, info};
+
+/// Input limit of embedding models not in [`KNOWN_LIMITS`]
+pub const DEFAULT_MAX_INPUT_TOKENS: usize = 2048;
+
+/// Input limits of common embedding models, matched by name prefix
+const KNOWN_LIMITS: &[(&str, usize)] = &[
+    ("nomic-embed-text", 8192),
+    ("mxbai-embed-large", 512),
+    ("all-minilm", 256),
+    ("snowflake-arctic-embed2", 8192),
+    ("snowflake-arctic-embed", 512),
+    ("bge-m3", 8192),
+    ("bge-large", 512),
+    ("text-embedding-3", 8191),
+    ("text-embedding-ada-002", 8191),
+];
+
+/// What to do with an input longer than the embedding model accepts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbeddingOverflowPolicy {
+    /// Embed chunks that fit and average them, weighted by their length
+    #[default]
+    ChunkAndAverage,
+    /// Fail the request without sending it
+    Error,
+}
+
+/// Configuration for embedding input limits
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmbeddingLimitConfig {
+    /// Maximum input tokens; defaults to the embedding model's known limit
+    pub max_input_tokens: Option<usize>,
+
+    /// Handling of inputs over the limit
+    pub overflow: EmbeddingOverflowPolicy,
+}
+
+/// Input token limit of the embedding model `model`
+pub fn max_input_tokens_for_model(model: &str) -> usize {
+    let model = model.to_lowercase();
+    KNOWN_LIMITS
+        .iter()
+        .find(|(prefix, _)| model.starts_with(prefix))
+        .map_or(DEFAULT_MAX_INPUT_TOKENS, |&(_, limit)| limit)
+}
+
+/// How many inputs a [`LimitedEmbeddingClient`] embedded as they were,
+/// chunked, or rejected
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmbeddingLimitStats {
+    pub within_limit: u64,
+    pub chunked: u64,
+    pub rejected: u64,
+}
+
+/// Client that keeps the embedding inputs of another client within the
+/// embedding model's limit
+pub struct LimitedEmbeddingClient {
+    inner: Arc<dyn LlmClient>,
+    tokenizer: Arc<dyn Tokenizer>,
+    max_input_tokens: usize,
+    overflow: EmbeddingOverflowPolicy,
+    within_limit: AtomicU64,
+    chunked: AtomicU64,
+    rejected: AtomicU64,
+}
+
+impl LimitedEmbeddingClient {
+    /// Limit the embeddings `inner` makes to the embedding model and limits
+    /// in `config`
+    pub fn new(inner: Arc<dyn LlmClient>, config: &LlmConfig) -> Self {
+        let model = &config.embedding_model;
+        Self {
+            inner,
+            tokenizer: tokenizer::for_model(model),
+            max_input_tokens: config
+                .embedding_limit
+                .max_input_tokens
+                .unwrap_or_else(|| max_input_tokens_for_model(model))
+                .max(1),
+            overflow: config.embedding_limit.overflow,
+            within_limit: AtomicU64::new(0),
+            chunked: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+        }
+    }
+
+    pub fn max_input_tokens(&self) -> usize {
+        self.max_input_tokens
+    }
+
+    pub fn stats(&self) -> EmbeddingLimitStats {
+        EmbeddingLimitStats {
+            within_limit: self.within_limit.load(Ordering::Relaxed),
+            chunked: self.chunked.load(Ordering::Relaxed),
+            rejected: self.rejected.load(Ordering::Relaxed),
+        }
+    }
+
+    /// `text` split into consecutive pieces of at most `max_input_tokens`
+    fn chunks<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        let mut chunks = Vec::new();
+        let mut rest = text.trim_start();
+        while !rest.is_empty() {
+            let mut end = self.tokenizer.truncate(rest, self.max_input_tokens).len();
+            if rest[..end].trim().is_empty() {
+                // A single word over the limit; cut it at a character boundary
+                end = rest
+                    .char_indices()
+                    .nth(self.max_input_tokens)
+                    .map_or(rest.len(), |(index, _)| index);
+            }
+            chunks.push(&rest[..end]);
+            rest = rest[end..].trim_start();
+        }
+        chunks
+    }
+
+    async fn embed_chunks(&self, text: &str) -> Result<EmbeddingResponse> {
+        let chunks = self.chunks(text);
+        debug!("Embedding input in {} chunks", chunks.len());
+
+        let mut sum: Vec<f32> = Vec::new();
+        let mut total_weight = 0.0;
+        let mut model = String::new();
+        for chunk in chunks {
+            let response = self.inner.embed(chunk).await?;
+            let weight = self.tokenizer.count(chunk).max(1) as f32;
+            if sum.is_empty() {
+                sum = vec![0.0; response.embedding.len()];
+            } else if response.embedding.len() != sum.len() {
+                return Err(LlmError::EmbeddingFailed(format!(
+                    "chunk embeddings have different dimensions: {} and {}",
+                    sum.len(),
+                    response.embedding.len()
+                ))
+                .into());
+            }
+            for (total, value) in sum.iter_mut().zip(&response.embedding) {
+                *total += value * weight;
+            }
+            total_weight += weight;
+            model = response.model;
+        }
+
+        Ok(EmbeddingResponse {
+            embedding: sum.into_iter().map(|total| total / total_weight).collect(),
+            model,
+        })
+    }
+}
+
+#[async_trait]
+impl LlmClient for LimitedEmbeddingClient {
+    async fn generate(&self, messages: &[Message]) -> Result<GenerationResponse> {
+        self.inner.generate(messages).await
+    }
+
+    async fn embed(&self, text: &str) -> Result<EmbeddingResponse> {
+        let tokens = self.tokenizer.count(text);
+        if tokens <= self.max_input_tokens {
+            self.within_limit.fetch_add(1, Ordering::Relaxed);
+            return self.inner.embed(text).await;
+        }
+
+        match self.overflow {
+            EmbeddingOverflowPolicy::ChunkAndAverage => {
+                info!(
+                    "Embedding input of {} tokens exceeds the limit of {}; chunking and averaging",
+                    tokens, self.max_input_tokens
+                );
+                self.chunked.fetch_add(1, Ordering::Relaxed);
+                self.embed_chunks(text).await
+            }
+            EmbeddingOverflowPolicy::Error => {
+                info!(
+                    "Embedding input of {} tokens exceeds the limit of {}; rejecting",
+                    tokens, self.max_input_tokens
+                );
+                self.rejected.fetch_add(1, Ordering::Relaxed);
+                Err(LlmError::EmbeddingInputTooLong {
+                    tokens,
+                    limit: self.max_input_tokens,
+                }
+                .into())
+            }
+        }
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>> {
+        self.inner.list_models().await
+    }
+
+    async fn is_model_available(&self, model: &str) -> Result<bool> {
+        self.inner.is_model_available(model).await
+    }
+
+    async fn generate_stream(
+        &self,
+        messages: &[Message],
+    ) -> Result<BoxStream<'static, Result<StreamEvent>>> {
+        self.inner.generate_stream(messages).await
+    }
+
+    async fn generate_stream_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[McpTool],
+    ) -> Result<BoxStream<'static, Result<StreamEvent>>> {
+        self.inner.generate_stream_with_tools(messages, tools).await
+    }
+
+    async fn preload_model(&self) -> Result<()> {
+        self.inner.preload_model().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AgentError;
+    use crate::llm::mock::MockLlm;
+
+    /// Embeds each input as `[words, 1, 0, 0]`
+    fn word_count_embedder() -> Arc<MockLlm> {
+        Arc::new(
+            MockLlm::new()
+                .with_embed(|text| Ok(vec![text.split_whitespace().count() as f32, 1.0, 0.0, 0.0])),
+        )
+    }
+
+    fn config(overflow: EmbeddingOverflowPolicy) -> LlmConfig {
+        LlmConfig {
+            embedding_model: "nomic-embed-text".to_string(),
+            embedding_limit: EmbeddingLimitConfig {
+                max_input_tokens: Some(10),
+                overflow,
+            },
+            ..LlmConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_long_input_is_chunked_and_averaged() {
+        let embedder = word_count_embedder();
+        let client = LimitedEmbeddingClient::new(
+            embedder.clone(),
+            &config(EmbeddingOverflowPolicy::ChunkAndAverage),
+        );
+        let text = "cat ".repeat(25);
+
+        let response = client.embed(&text).await.unwrap();
+
+        assert_eq!(response.embedding.len(), 4);
+        let inputs = embedder.embedded();
+        assert_eq!(inputs.len(), 3);
+        assert!(inputs
+            .iter()
+            .all(|input| client.tokenizer.count(input) <= 10));
+        // Chunks of 10, 10 and 5 words weighted by their length
+        assert!((response.embedding[0] - 9.0).abs() < 1e-4);
+        assert!((response.embedding[1] - 1.0).abs() < 1e-4);
+
+        client.embed("short input").await.unwrap();
+        let stats = client.stats();
+        assert_eq!(
+            (stats.within_limit, stats.chunked, stats.rejected),
+            (1, 1, 0)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_long_input_is_rejected_under_error_policy() {
+        let embedder = word_count_embedder();
+        let client =
+            LimitedEmbeddingClient::new(embedder.clone(), &config(EmbeddingOverflowPolicy::Error));
+
+        let err = client.embed(&"cat ".repeat(25)).await.unwrap_err();
+
+        assert!(matches!(
+            err,
+            AgentError::Llm(LlmError::EmbeddingInputTooLong {
+                tokens: 25,
+                limit: 10
+            })
+        ));
+        assert!(embedder.embedded().is_empty());
+        assert_eq!(client.stats().rejected, 1);
+    }
+
+    #[test]
+    fn test_limits_of_known_models() {
+        assert_eq!(max_input_tokens_for_model("nomic-embed-text:latest"), 8192);
+        assert_eq!(max_input_tokens_for_model("mxbai-embed-large"), 512);
+        assert_eq!(
+            max_input_tokens_for_model("some-new-embedder"),
+            DEFAULT_MAX_INPUT_TOKENS
+        );
+    }
+}
@@ -0,0 +1,359 @@
+//! Time limits on generation calls
+//!
+//! A slow model can keep a generation running long after the connection was
+//! made. [`TimeoutLlmClient`] bounds the generations of the client it wraps:
+//! a call fails with [`AgentError::Timeout`] once it has run longer than the
+//! total limit, which for a streamed response covers the whole stream. A
+//! stream also fails when no event arrives within the idle limit. Embedding
+//! and model calls pass through unbounded.
+//!
+//! The agent's Ollama client is bounded by the limits in [`LlmConfig`];
+//! [`ProviderClient::wrap`](super::provider::ProviderClient::wrap) bounds
+//! other providers by those in their [`ProviderConfig`], and the
+//! [`ProviderManager`](super::manager::ProviderManager) applies each limit per
+//! attempt.
+
+use super::provider::ProviderConfig;
+use super::stream::StreamEvent;
+use super::{EmbeddingResponse, GenerationResponse, LlmClient, Message};
+use crate::config::LlmConfig;
+use crate::error::{AgentError, Result};
+use crate::mcp::McpTool;
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{timeout_at, Instant};
+use tracing::warn;
+
+/// Limits applied by [`TimeoutLlmClient`]; unset limits are not enforced
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GenerationTimeouts {
+    /// Longest a generation may run, including the whole of a stream
+    pub total: Option<Duration>,
+    /// Longest gap allowed between two streamed events
+    pub idle: Option<Duration>,
+}
+
+impl GenerationTimeouts {
+    /// `generation_timeout` and `stream_idle_timeout` from `config`
+    pub fn from_config(config: &LlmConfig) -> Self {
+        Self {
+            total: config.generation_timeout.map(Duration::from_secs),
+            idle: config.stream_idle_timeout.map(Duration::from_secs),
+        }
+    }
+
+    /// `generation_timeout` and `stream_idle_timeout` from a provider's
+    /// `config`
+    pub fn from_provider_config(config: &ProviderConfig) -> Self {
+        Self {
+            total: config.generation_timeout.map(Duration::from_secs),
+            idle: config.stream_idle_timeout.map(Duration::from_secs),
+        }
+    }
+
+    pub fn is_unbounded(&self) -> bool {
+        self.total.is_none() && self.idle.is_none()
+    }
+}
+
+/// Client that bounds the generations of another client
+pub struct TimeoutLlmClient {
+    inner: Arc<dyn LlmClient>,
+    timeouts: GenerationTimeouts,
+}
+
+impl TimeoutLlmClient {
+    pub fn new(inner: Arc<dyn LlmClient>, timeouts: GenerationTimeouts) -> Self {
+        Self { inner, timeouts }
+    }
+
+    /// `inner` bounded by the limits in `config`, or `inner` itself when none
+    /// are set
+    pub fn wrap(inner: Arc<dyn LlmClient>, config: &LlmConfig) -> Arc<dyn LlmClient> {
+        Self::bound(inner, GenerationTimeouts::from_config(config))
+    }
+
+    /// `inner` bounded by `timeouts`, or `inner` itself when they are
+    /// unbounded
+    pub fn bound(inner: Arc<dyn LlmClient>, timeouts: GenerationTimeouts) -> Arc<dyn LlmClient> {
+        if timeouts.is_unbounded() {
+            return inner;
+        }
+        Arc::new(Self::new(inner, timeouts))
+    }
+}
+
+/// A limit a generation can run into
+#[derive(Debug, Clone, Copy)]
+enum Limit {
+    Total(Duration),
+    Idle(Duration),
+}
+
+impl Limit {
+    fn exceeded(self) -> AgentError {
+        match self {
+            Limit::Total(limit) => {
+                warn!("Generation exceeded its {:?} limit", limit);
+                AgentError::Timeout(format!("generation exceeded {:?}", limit))
+            }
+            Limit::Idle(limit) => {
+                warn!("Stream idle for longer than {:?}", limit);
+                AgentError::Timeout(format!("no streamed output for {:?}", limit))
+            }
+        }
+    }
+}
+
+/// End `events` with a timeout error once `deadline` passes or `idle` elapses
+/// between two events
+fn bounded(
+    events: BoxStream<'static, Result<StreamEvent>>,
+    deadline: Option<(Instant, Limit)>,
+    idle: Option<Duration>,
+) -> BoxStream<'static, Result<StreamEvent>> {
+    stream::unfold(Some(events), move |events| async move {
+        let mut events = events?;
+        let idle_deadline = idle.map(|limit| (Instant::now() + limit, Limit::Idle(limit)));
+        let next_deadline = match (deadline, idle_deadline) {
+            (Some(total), Some(idle)) => Some(if idle.0 < total.0 { idle } else { total }),
+            (total, idle) => total.or(idle),
+        };
+
+        let next = match next_deadline {
+            Some((at, limit)) => match timeout_at(at, events.next()).await {
+                Ok(next) => next,
+                Err(_) => return Some((Err(limit.exceeded()), None)),
+            },
+            None => events.next().await,
+        };
+        next.map(|event| (event, Some(events)))
+    })
+    .boxed()
+}
+
+#[async_trait]
+impl LlmClient for TimeoutLlmClient {
+    async fn generate(&self, messages: &[Message]) -> Result<GenerationResponse> {
+        match self.timeouts.total {
+            Some(limit) => tokio::time::timeout(limit, self.inner.generate(messages))
+                .await
+                .map_err(|_| Limit::Total(limit).exceeded())?,
+            None => self.inner.generate(messages).await,
+        }
+    }
+
+    async fn embed(&self, text: &str) -> Result<EmbeddingResponse> {
+        self.inner.embed(text).await
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>> {
+        self.inner.list_models().await
+    }
+
+    async fn is_model_available(&self, model: &str) -> Result<bool> {
+        self.inner.is_model_available(model).await
+    }
+
+    async fn generate_stream(
+        &self,
+        messages: &[Message],
+    ) -> Result<BoxStream<'static, Result<StreamEvent>>> {
+        let deadline = self
+            .timeouts
+            .total
+            .map(|limit| (Instant::now() + limit, Limit::Total(limit)));
+        let events = match deadline {
+            Some((at, limit)) => timeout_at(at, self.inner.generate_stream(messages))
+                .await
+                .map_err(|_| limit.exceeded())??,
+            None => self.inner.generate_stream(messages).await?,
+        };
+        Ok(bounded(events, deadline, self.timeouts.idle))
+    }
+
+    async fn generate_stream_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[McpTool],
+    ) -> Result<BoxStream<'static, Result<StreamEvent>>> {
+        let deadline = self
+            .timeouts
+            .total
+            .map(|limit| (Instant::now() + limit, Limit::Total(limit)));
+        let start = self.inner.generate_stream_with_tools(messages, tools);
+        let events = match deadline {
+            Some((at, limit)) => timeout_at(at, start)
+                .await
+                .map_err(|_| limit.exceeded())??,
+            None => start.await?,
+        };
+        Ok(bounded(events, deadline, self.timeouts.idle))
+    }
+
+    async fn preload_model(&self) -> Result<()> {
+        self.inner.preload_model().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::manager::{ManagerConfig, ProviderManager};
+    use crate::llm::mock::MockLlm;
+    use crate::llm::provider::{LlmProvider, ProviderClient, ProviderType};
+    use crate::llm::user_message;
+
+    /// Emits a text delta after each of `delays_ms`, then `Done`; whole
+    /// generations take as long as the stream
+    fn paced_llm(delays_ms: Vec<u64>) -> MockLlm {
+        let total: u64 = delays_ms.iter().sum();
+        MockLlm::new()
+            .with_reply("done")
+            .with_delay(Duration::from_millis(total))
+            .with_stream(move |_| {
+                let deltas = stream::iter(delays_ms.clone()).then(|delay_ms| async move {
+                    tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+                    Ok(StreamEvent::TextDelta("token ".to_string()))
+                });
+                let done = stream::once(async {
+                    Ok(StreamEvent::Done {
+                        finish_reason: None,
+                        usage: None,
+                    })
+                });
+                deltas.chain(done).boxed()
+            })
+    }
+
+    fn client(
+        delays_ms: Vec<u64>,
+        total_ms: Option<u64>,
+        idle_ms: Option<u64>,
+    ) -> TimeoutLlmClient {
+        TimeoutLlmClient::new(
+            Arc::new(paced_llm(delays_ms)),
+            GenerationTimeouts {
+                total: total_ms.map(Duration::from_millis),
+                idle: idle_ms.map(Duration::from_millis),
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_total_timeout_bounds_generation_and_stream() {
+        tokio::time::pause();
+        let messages = [user_message("Write a long story")];
+
+        // Steady tokens that together take longer than the limit
+        let llm = client(vec![40; 10], Some(150), Some(100));
+        let err = llm.generate(&messages).await.unwrap_err();
+        assert!(matches!(err, AgentError::Timeout(_)));
+
+        let events: Vec<_> = llm
+            .generate_stream(&messages)
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert!(events.len() < 10);
+        match events.last().unwrap() {
+            Err(AgentError::Timeout(message)) => assert!(message.contains("generation exceeded")),
+            other => panic!("expected a timeout, got {:?}", other),
+        }
+
+        // Within the limit the stream is passed through unchanged
+        let llm = client(vec![10; 3], Some(1_000), Some(100));
+        let events: Vec<_> = llm
+            .generate_stream(&messages)
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert_eq!(events.len(), 4);
+        assert!(events.iter().all(Result::is_ok));
+    }
+
+    #[tokio::test]
+    async fn test_idle_gap_between_tokens_times_out() {
+        tokio::time::pause();
+        let messages = [user_message("Write a long story")];
+        let llm = client(vec![10, 10, 300, 10], Some(5_000), Some(100));
+
+        let events: Vec<_> = llm
+            .generate_stream(&messages)
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert_eq!(events.len(), 3);
+        assert!(events[..2].iter().all(Result::is_ok));
+        match &events[2] {
+            Err(AgentError::Timeout(message)) => assert!(message.contains("no streamed output")),
+            other => panic!("expected an idle timeout, got {:?}", other),
+        }
+    }
+
+    /// Provider whose generations never finish
+    struct StalledProvider;
+
+    #[async_trait]
+    impl LlmProvider for StalledProvider {
+        fn provider_type(&self) -> ProviderType {
+            ProviderType::OpenAI
+        }
+
+        fn name(&self) -> &str {
+            "stalled"
+        }
+
+        async fn generate(&self, _messages: &[Message]) -> Result<GenerationResponse> {
+            std::future::pending().await
+        }
+
+        async fn embed(&self, _text: &str) -> Result<EmbeddingResponse> {
+            std::future::pending().await
+        }
+
+        async fn list_models(&self) -> Result<Vec<String>> {
+            Ok(Vec::new())
+        }
+
+        async fn is_model_available(&self, _model: &str) -> Result<bool> {
+            Ok(true)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_provider_generations_time_out_and_fail_over() {
+        tokio::time::pause();
+        let messages = [user_message("Write a long story")];
+        let config: ProviderConfig = serde_json::from_value(serde_json::json!({
+            "provider": "openai",
+            "name": "stalled",
+            "text_model": "gpt-4o-mini",
+            "generation_timeout": 30,
+        }))
+        .unwrap();
+
+        let stalled = ProviderClient::wrap(Arc::new(StalledProvider), &config);
+        let err = stalled.generate(&messages).await.unwrap_err();
+        assert!(matches!(err, AgentError::Timeout(_)));
+
+        // The manager moves on to the fallback once the primary times out
+        let manager = ProviderManager::new(stalled)
+            .with_fallback(Arc::new(MockLlm::new().with_reply("from fallback")))
+            .with_config(ManagerConfig {
+                enable_fallback: true,
+                max_retries: 1,
+                retry_delay_ms: 0,
+            });
+        let started = Instant::now();
+        let response = manager.generate(&messages).await.unwrap();
+        assert_eq!(response.text, "from fallback");
+        let elapsed = started.elapsed();
+        assert!(elapsed >= Duration::from_secs(30) && elapsed < Duration::from_secs(31));
+    }
+}
@@ -3,9 +3,16 @@
 //! Manages multiple LLM providers with automatic fallback and load balancing
 
 use crate::config::LlmConfig;
-use crate::error::{LlmError, Result};
-use crate::llm::{EmbeddingResponse, GenerationResponse, LlmClient, Message, OllamaClient};
+use crate::error::{AgentError, LlmError, Result};
+use crate::llm::generation_timeout::TimeoutLlmClient;
+use crate::llm::stream::StreamEvent;
+use crate::llm::{
+    retry_budget, EmbeddingResponse, GenerationResponse, LlmClient, Message, OllamaClient,
+};
+use crate::mcp::McpTool;
 use async_trait::async_trait;
+use futures::stream::BoxStream;
+use std::future::Future;
 use std::sync::Arc;
 use tracing::{debug, warn};
 
@@ -24,7 +31,7 @@ pub struct ProviderManager {
 pub struct ManagerConfig {
     /// Enable automatic fallback
     pub enable_fallback: bool,
-    /// Maximum retry attempts per provider
+    /// Maximum attempts per provider, the first one included
     pub max_retries: usize,
     /// Delay between retries in milliseconds
     pub retry_delay_ms: u64,
@@ -41,9 +48,32 @@ impl Default for ManagerConfig {
 }
 
 impl ProviderManager {
-    /// Create a new provider manager with Ollama as primary
+    /// Create a provider manager over `primary`
+    pub fn new(primary: Arc<dyn LlmClient>) -> Self {
+        Self {
+            primary,
+            fallbacks: Vec::new(),
+            config: ManagerConfig::default(),
+        }
+    }
+
+    /// `inner` retrying failed calls up to `max_retries` times from `config`,
+    /// or `inner` itself when no retries are configured
+    pub fn wrap(inner: Arc<dyn LlmClient>, config: &LlmConfig) -> Arc<dyn LlmClient> {
+        if config.max_retries == 0 {
+            return inner;
+        }
+        Arc::new(Self::new(inner).with_config(ManagerConfig {
+            enable_fallback: false,
+            max_retries: config.max_retries + 1,
+            retry_delay_ms: config.retry_delay_ms,
+        }))
+    }
+
+    /// Create a new provider manager with Ollama as primary, its generations
+    /// bounded by the timeouts in `config`
     pub fn new_ollama(config: LlmConfig) -> Self {
-        let primary = Arc::new(OllamaClient::new(config));
+        let primary = TimeoutLlmClient::wrap(Arc::new(OllamaClient::new(config.clone())), &config);
 
         Self {
             primary,
@@ -54,7 +84,10 @@ impl ProviderManager {
 
     /// Create a new provider manager with Ollama and caching
     pub async fn new_ollama_with_cache(config: LlmConfig) -> Result<Self> {
-        let primary = Arc::new(OllamaClient::new_with_cache(config).await?);
+        let primary = TimeoutLlmClient::wrap(
+            Arc::new(OllamaClient::new_with_cache(config.clone()).await?),
+            &config,
+        );
 
         Ok(Self {
             primary,
@@ -75,78 +108,48 @@ impl ProviderManager {
         self
     }
 
-    /// Try to generate with retries
-    async fn try_generate_with_retries(
+    /// Run `call` against `client`, retrying failures worth repeating
+    async fn with_retries<T, F, Fut>(
         &self,
         client: &Arc<dyn LlmClient>,
-        messages: &[Message],
         provider_name: &str,
-    ) -> Result<GenerationResponse> {
+        operation: &str,
+        call: &F,
+    ) -> Result<T>
+    where
+        F: Fn(Arc<dyn LlmClient>) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
         let mut last_error = None;
 
         for attempt in 0..self.config.max_retries {
-            match client.generate(messages).await {
+            match call(client.clone()).await {
                 Ok(response) => {
                     if attempt > 0 {
                         debug!(
-                            "Successfully generated after {} retries with {}",
-                            attempt, provider_name
+                            "{} succeeded after {} retries with {}",
+                            operation, attempt, provider_name
                         );
                     }
                     return Ok(response);
                 }
                 Err(e) => {
                     warn!(
-                        "Attempt {} failed for {}: {}",
+                        "{} attempt {} failed for {}: {}",
+                        operation,
                         attempt + 1,
                         provider_name,
                         e
                     );
+                    let retry = worth_retrying(&e);
                     last_error = Some(e);
-
-                    if attempt < self.config.max_retries - 1 {
-                        tokio::time::sleep(tokio::time::Duration::from_millis(
-                            self.config.retry_delay_ms,
-                        ))
-                        .await;
+                    if !retry {
+                        break;
                     }
-                }
-            }
-        }
-
-        Err(last_error.unwrap_or_else(|| {
-            crate::error::AgentError::Llm(LlmError::Unknown("No error recorded".to_string()))
-        }))
-    }
-
-    /// Try to embed with retries
-    async fn try_embed_with_retries(
-        &self,
-        client: &Arc<dyn LlmClient>,
-        text: &str,
-        provider_name: &str,
-    ) -> Result<EmbeddingResponse> {
-        let mut last_error = None;
-
-        for attempt in 0..self.config.max_retries {
-            match client.embed(text).await {
-                Ok(response) => {
-                    if attempt > 0 {
-                        debug!(
-                            "Successfully embedded after {} retries with {}",
-                            attempt, provider_name
-                        );
+                    if attempt + 1 < self.config.max_retries && !retry_budget::allow_retry() {
+                        warn!("Retry budget exhausted, not retrying {}", provider_name);
+                        break;
                     }
-                    return Ok(response);
-                }
-                Err(e) => {
-                    warn!(
-                        "Embed attempt {} failed for {}: {}",
-                        attempt + 1,
-                        provider_name,
-                        e
-                    );
-                    last_error = Some(e);
 
                     if attempt < self.config.max_retries - 1 {
                         tokio::time::sleep(tokio::time::Duration::from_millis(
@@ -162,14 +165,17 @@ impl ProviderManager {
             crate::error::AgentError::Llm(LlmError::Unknown("No error recorded".to_string()))
         }))
     }
-}
 
-#[async_trait]
-impl LlmClient for ProviderManager {
-    async fn generate(&self, messages: &[Message]) -> Result<GenerationResponse> {
+    /// Run `call` against the primary client with retries, then against each
+    /// fallback in order if enabled
+    async fn with_failover<T, F, Fut>(&self, operation: &str, call: F) -> Result<T>
+    where
+        F: Fn(Arc<dyn LlmClient>) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
         // Try primary provider
         match self
-            .try_generate_with_retries(&self.primary, messages, "primary")
+            .with_retries(&self.primary, "primary", operation, &call)
             .await
         {
             Ok(response) => return Ok(response),
@@ -177,7 +183,10 @@ impl LlmClient for ProviderManager {
                 return Err(e);
             }
             Err(e) => {
-                warn!("Primary provider failed: {}, trying fallbacks", e);
+                warn!(
+                    "Primary provider {} failed: {}, trying fallbacks",
+                    operation, e
+                );
             }
         }
 
@@ -185,55 +194,47 @@ impl LlmClient for ProviderManager {
         for (idx, fallback) in self.fallbacks.iter().enumerate() {
             let provider_name = format!("fallback_{}", idx);
             match self
-                .try_generate_with_retries(fallback, messages, &provider_name)
+                .with_retries(fallback, &provider_name, operation, &call)
                 .await
             {
                 Ok(response) => {
-                    debug!("Successfully used fallback provider {}", idx);
+                    debug!(
+                        "Successfully used fallback provider {} for {}",
+                        idx, operation
+                    );
                     return Ok(response);
                 }
                 Err(e) => {
-                    warn!("Fallback {} failed: {}", idx, e);
+                    warn!("Fallback {} {} failed: {}", idx, operation, e);
                 }
             }
         }
 
         Err(crate::error::AgentError::Llm(LlmError::AllProvidersFailed))
     }
+}
 
-    async fn embed(&self, text: &str) -> Result<EmbeddingResponse> {
-        // Try primary provider
-        match self
-            .try_embed_with_retries(&self.primary, text, "primary")
-            .await
-        {
-            Ok(response) => return Ok(response),
-            Err(e) if !self.config.enable_fallback || self.fallbacks.is_empty() => {
-                return Err(e);
-            }
-            Err(e) => {
-                warn!("Primary provider embed failed: {}, trying fallbacks", e);
-            }
-        }
+/// Whether a failed attempt is worth repeating. Provider errors say whether
+/// they are transient; anything else may be.
+fn worth_retrying(error: &AgentError) -> bool {
+    !matches!(error, AgentError::Llm(LlmError::Provider(e)) if !e.retryable)
+}
 
-        // Try fallbacks in order
-        for (idx, fallback) in self.fallbacks.iter().enumerate() {
-            let provider_name = format!("fallback_{}", idx);
-            match self
-                .try_embed_with_retries(fallback, text, &provider_name)
-                .await
-            {
-                Ok(response) => {
-                    debug!("Successfully used fallback provider {} for embedding", idx);
-                    return Ok(response);
-                }
-                Err(e) => {
-                    warn!("Fallback {} embed failed: {}", idx, e);
-                }
-            }
-        }
+#[async_trait]
+impl LlmClient for ProviderManager {
+    async fn generate(&self, messages: &[Message]) -> Result<GenerationResponse> {
+        self.with_failover("generation", |client| async move {
+            client.generate(messages).await
+        })
+        .await
+    }
 
-        Err(crate::error::AgentError::Llm(LlmError::AllProvidersFailed))
+    async fn embed(&self, text: &str) -> Result<EmbeddingResponse> {
+        self.with_failover(
+            "embedding",
+            |client| async move { client.embed(text).await },
+        )
+        .await
     }
 
     async fn list_models(&self) -> Result<Vec<String>> {
@@ -243,12 +244,42 @@ impl LlmClient for ProviderManager {
     async fn is_model_available(&self, model: &str) -> Result<bool> {
         self.primary.is_model_available(model).await
     }
+
+    /// Retries and falls back while starting the stream; once events flow,
+    /// a failure ends the stream
+    async fn generate_stream(
+        &self,
+        messages: &[Message],
+    ) -> Result<BoxStream<'static, Result<StreamEvent>>> {
+        self.with_failover("stream", |client| async move {
+            client.generate_stream(messages).await
+        })
+        .await
+    }
+
+    async fn generate_stream_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[McpTool],
+    ) -> Result<BoxStream<'static, Result<StreamEvent>>> {
+        self.with_failover("stream", |client| async move {
+            client.generate_stream_with_tools(messages, tools).await
+        })
+        .await
+    }
+
+    async fn preload_model(&self) -> Result<()> {
+        self.primary.preload_model().await
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::config::LlmConfig;
+    use crate::llm::mock::{text_stream, MockLlm};
+    use crate::llm::retry_budget::RetryBudget;
+    use futures::StreamExt;
     use std::collections::HashMap;
 
     fn test_config() -> LlmConfig {
@@ -262,6 +293,7 @@ mod tests {
             stream: false,
             task_models: HashMap::new(),
             cache: crate::cache::LlmCacheConfig::default(),
+            ..Default::default()
         }
     }
 
@@ -296,4 +328,100 @@ mod tests {
         // This will fail if Ollama is not running, which is expected
         let _ = manager.list_models().await;
     }
+
+    /// Fails every generation with the provider error parsed from `body`
+    fn failing_llm(status: u16, body: &str) -> Arc<MockLlm> {
+        let error = crate::llm::providers::base::parse_provider_error(status, body);
+        Arc::new(MockLlm::new().with_generate(move |_| Err(LlmError::from(error.clone()).into())))
+    }
+
+    async fn attempts_for(body: &str, status: u16) -> usize {
+        let primary = failing_llm(status, body);
+        let manager = ProviderManager {
+            primary: primary.clone(),
+            fallbacks: Vec::new(),
+            config: ManagerConfig {
+                enable_fallback: false,
+                max_retries: 3,
+                retry_delay_ms: 0,
+            },
+        };
+        assert!(manager.generate(&[]).await.is_err());
+        primary.generations()
+    }
+
+    #[tokio::test]
+    async fn test_retries_only_transient_provider_errors() {
+        let quota = r#"{"error": {"message": "Quota exceeded", "type": "insufficient_quota", "code": "insufficient_quota"}}"#;
+        assert_eq!(attempts_for(quota, 429).await, 1);
+
+        let overloaded =
+            r#"{"type": "error", "error": {"type": "overloaded_error", "message": "Overloaded"}}"#;
+        assert_eq!(attempts_for(overloaded, 529).await, 3);
+    }
+
+    #[tokio::test]
+    async fn test_stream_falls_back_when_primary_cannot_start() {
+        let overloaded =
+            r#"{"type": "error", "error": {"type": "overloaded_error", "message": "Overloaded"}}"#;
+        let primary = failing_llm(529, overloaded);
+        let fallback = Arc::new(MockLlm::new().with_stream(|_| text_stream(&["Hi", "!"])));
+        let manager = ProviderManager::new(primary.clone())
+            .with_fallback(fallback.clone())
+            .with_config(ManagerConfig {
+                enable_fallback: true,
+                max_retries: 2,
+                retry_delay_ms: 0,
+            });
+
+        let events: Vec<_> = manager.generate_stream(&[]).await.unwrap().collect().await;
+
+        assert_eq!(primary.generations(), 2);
+        assert_eq!(fallback.generations(), 1);
+        assert!(matches!(&events[0], Ok(StreamEvent::TextDelta(text)) if text == "Hi"));
+        assert_eq!(events.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_budget_shared_across_calls() {
+        let overloaded =
+            r#"{"type": "error", "error": {"type": "overloaded_error", "message": "Overloaded"}}"#;
+        let primary = failing_llm(529, overloaded);
+        let manager = ProviderManager {
+            primary: primary.clone(),
+            fallbacks: Vec::new(),
+            config: ManagerConfig {
+                enable_fallback: false,
+                max_retries: 3,
+                retry_delay_ms: 0,
+            },
+        };
+        let mut counted = 0;
+        let mut attempts = || {
+            let total = primary.generations();
+            total - std::mem::replace(&mut counted, total)
+        };
+
+        let budget = RetryBudget::new(3);
+        budget
+            .clone()
+            .scope(async {
+                // Two retries on the first call, the last one on the second
+                assert!(manager.generate(&[]).await.is_err());
+                assert_eq!(attempts(), 3);
+                assert!(manager.generate(&[]).await.is_err());
+                assert_eq!(attempts(), 2);
+
+                // Exhausted: the next call fails after its first attempt
+                assert!(manager.generate(&[]).await.is_err());
+                assert_eq!(attempts(), 1);
+            })
+            .await;
+        assert!(budget.is_exhausted());
+        assert_eq!(budget.used(), 3);
+
+        // Outside the scope calls retry as configured again
+        assert!(manager.generate(&[]).await.is_err());
+        assert_eq!(attempts(), 3);
+    }
 }
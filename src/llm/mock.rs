@@ -0,0 +1,210 @@
+//! Scriptable LLM client for tests
+//!
+//! [`MockLlm`] answers generations, streams and embeddings with closures set
+//! by the test, and records what it was asked and which tools it was offered. Anything a test leaves
+//! unscripted fails with an error instead of panicking, so a test that
+//! unexpectedly reaches it fails with a readable message.
+
+use super::stream::StreamEvent;
+use super::{EmbeddingResponse, GenerationResponse, LlmClient, Message};
+use crate::error::{LlmError, Result};
+use crate::mcp::McpTool;
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+type GenerateFn = Box<dyn Fn(&[Message]) -> Result<GenerationResponse> + Send + Sync>;
+type StreamFn = Box<dyn Fn(&[Message]) -> BoxStream<'static, Result<StreamEvent>> + Send + Sync>;
+type EmbedFn = Box<dyn Fn(&str) -> Result<Vec<f32>> + Send + Sync>;
+
+/// A response with `text` from the model "mock"
+pub fn response(text: impl Into<String>) -> GenerationResponse {
+    GenerationResponse {
+        text: text.into(),
+        tokens_used: None,
+        usage: None,
+        model: "mock".to_string(),
+        finish_reason: Some("stop".to_string()),
+        system_fingerprint: None,
+        reasoning: None,
+    }
+}
+
+/// A stream of `tokens` as text deltas, then `Done`
+pub fn text_stream(tokens: &[&str]) -> BoxStream<'static, Result<StreamEvent>> {
+    let mut events: Vec<Result<StreamEvent>> = tokens
+        .iter()
+        .map(|token| Ok(StreamEvent::TextDelta(token.to_string())))
+        .collect();
+    events.push(Ok(StreamEvent::Done {
+        finish_reason: Some("stop".to_string()),
+        usage: None,
+    }));
+    stream::iter(events).boxed()
+}
+
+/// LLM client answering with scripted closures
+#[derive(Default)]
+pub struct MockLlm {
+    generate: Option<GenerateFn>,
+    stream: Option<StreamFn>,
+    embed: Option<EmbedFn>,
+    delay: Option<Duration>,
+    requests: Mutex<Vec<Vec<Message>>>,
+    offered_tools: Mutex<Vec<Vec<String>>>,
+    embedded: Mutex<Vec<String>>,
+    embeddings_in_flight: AtomicUsize,
+    max_embeddings_in_flight: AtomicUsize,
+}
+
+impl MockLlm {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer every generation with `text`
+    pub fn with_reply(self, text: impl Into<String>) -> Self {
+        let text = text.into();
+        self.with_generate(move |_| Ok(response(text.clone())))
+    }
+
+    /// Answer generations with `generate`
+    pub fn with_generate(
+        mut self,
+        generate: impl Fn(&[Message]) -> Result<GenerationResponse> + Send + Sync + 'static,
+    ) -> Self {
+        self.generate = Some(Box::new(generate));
+        self
+    }
+
+    /// Answer streamed generations with `stream`; without it, streams carry
+    /// the whole generated response as one delta
+    pub fn with_stream(
+        mut self,
+        stream: impl Fn(&[Message]) -> BoxStream<'static, Result<StreamEvent>> + Send + Sync + 'static,
+    ) -> Self {
+        self.stream = Some(Box::new(stream));
+        self
+    }
+
+    /// Answer embeddings with `embed`
+    pub fn with_embed(
+        mut self,
+        embed: impl Fn(&str) -> Result<Vec<f32>> + Send + Sync + 'static,
+    ) -> Self {
+        self.embed = Some(Box::new(embed));
+        self
+    }
+
+    /// Wait `delay` before answering each generation or embedding
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Number of generations requested, streamed or not
+    pub fn generations(&self) -> usize {
+        self.requests.lock().unwrap().len()
+    }
+
+    /// Messages of every generation requested, oldest first
+    pub fn requests(&self) -> Vec<Vec<Message>> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Messages of the latest generation requested
+    pub fn last_request(&self) -> Vec<Message> {
+        self.requests
+            .lock()
+            .unwrap()
+            .last()
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Names of the tools offered with each tool-aware generation, oldest
+    /// first
+    pub fn offered_tools(&self) -> Vec<Vec<String>> {
+        self.offered_tools.lock().unwrap().clone()
+    }
+
+    /// Texts embedded, oldest first
+    pub fn embedded(&self) -> Vec<String> {
+        self.embedded.lock().unwrap().clone()
+    }
+
+    /// Most embeddings that were in progress at once
+    pub fn max_embeddings_in_flight(&self) -> usize {
+        self.max_embeddings_in_flight.load(Ordering::SeqCst)
+    }
+
+    async fn wait(&self) {
+        if let Some(delay) = self.delay {
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+#[async_trait]
+impl LlmClient for MockLlm {
+    async fn generate(&self, messages: &[Message]) -> Result<GenerationResponse> {
+        self.requests.lock().unwrap().push(messages.to_vec());
+        self.wait().await;
+        match &self.generate {
+            Some(generate) => generate(messages),
+            None => {
+                Err(LlmError::GenerationFailed("mock has no scripted reply".to_string()).into())
+            }
+        }
+    }
+
+    async fn embed(&self, text: &str) -> Result<EmbeddingResponse> {
+        self.embedded.lock().unwrap().push(text.to_string());
+        let in_flight = self.embeddings_in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_embeddings_in_flight
+            .fetch_max(in_flight, Ordering::SeqCst);
+        self.wait().await;
+        self.embeddings_in_flight.fetch_sub(1, Ordering::SeqCst);
+
+        let embed = self.embed.as_ref().ok_or_else(|| {
+            LlmError::EmbeddingFailed("mock has no scripted embedding".to_string())
+        })?;
+        Ok(EmbeddingResponse {
+            embedding: embed(text)?,
+            model: "mock".to_string(),
+        })
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>> {
+        Ok(vec!["mock".to_string()])
+    }
+
+    async fn is_model_available(&self, _model: &str) -> Result<bool> {
+        Ok(true)
+    }
+
+    async fn generate_stream(
+        &self,
+        messages: &[Message],
+    ) -> Result<BoxStream<'static, Result<StreamEvent>>> {
+        let Some(stream) = &self.stream else {
+            let response = self.generate(messages).await?;
+            let events = StreamEvent::from_response(response).into_iter().map(Ok);
+            return Ok(stream::iter(events).boxed());
+        };
+        self.requests.lock().unwrap().push(messages.to_vec());
+        Ok(stream(messages))
+    }
+
+    async fn generate_stream_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[McpTool],
+    ) -> Result<BoxStream<'static, Result<StreamEvent>>> {
+        let names = tools.iter().map(|tool| tool.name.clone()).collect();
+        self.offered_tools.lock().unwrap().push(names);
+        self.generate_stream(messages).await
+    }
+}
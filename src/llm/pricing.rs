@@ -0,0 +1,202 @@
+//! Token pricing and cost estimation
+//!
+//! Provides a per-model pricing table used to estimate spend before running a
+//! request and to account for actual spend from reported token usage. Prices
+//! change frequently, so the built-in table can be overridden from configuration.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{LazyLock, Mutex};
+use tracing::warn;
+
+/// Models already warned about as missing from the pricing table
+static UNPRICED_MODELS: LazyLock<Mutex<HashSet<String>>> = LazyLock::new(Default::default);
+
+/// Whether `model` is seen unpriced for the first time in this process
+fn first_unpriced(model: &str) -> bool {
+    UNPRICED_MODELS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .insert(model.to_string())
+}
+
+/// Token usage reported for a single generation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    /// Tokens consumed by the prompt
+    pub input_tokens: u32,
+
+    /// Tokens produced by the model
+    pub output_tokens: u32,
+}
+
+impl TokenUsage {
+    pub fn new(input_tokens: u32, output_tokens: u32) -> Self {
+        Self {
+            input_tokens,
+            output_tokens,
+        }
+    }
+
+    /// Total tokens (input + output)
+    pub fn total(&self) -> u32 {
+        self.input_tokens + self.output_tokens
+    }
+}
+
+/// Price of a model in USD per 1K tokens
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    /// Cost per 1K input (prompt) tokens
+    pub input_per_1k: f64,
+
+    /// Cost per 1K output (completion) tokens
+    pub output_per_1k: f64,
+}
+
+impl ModelPricing {
+    pub fn new(input_per_1k: f64, output_per_1k: f64) -> Self {
+        Self {
+            input_per_1k,
+            output_per_1k,
+        }
+    }
+
+    /// Cost in USD for the given usage
+    pub fn cost(&self, usage: &TokenUsage) -> f64 {
+        (usage.input_tokens as f64 / 1000.0) * self.input_per_1k
+            + (usage.output_tokens as f64 / 1000.0) * self.output_per_1k
+    }
+}
+
+/// Per-model pricing table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PricingTable {
+    models: HashMap<String, ModelPricing>,
+}
+
+impl PricingTable {
+    /// Create an empty pricing table
+    pub fn empty() -> Self {
+        Self {
+            models: HashMap::new(),
+        }
+    }
+
+    /// Set (or replace) the price for a model
+    pub fn with_model(mut self, model: &str, pricing: ModelPricing) -> Self {
+        self.models.insert(model.to_string(), pricing);
+        self
+    }
+
+    /// Merge overrides on top of the current table
+    pub fn with_overrides(mut self, overrides: HashMap<String, ModelPricing>) -> Self {
+        self.models.extend(overrides);
+        self
+    }
+
+    /// Look up the price for a model.
+    ///
+    /// Falls back to the longest known prefix so dated variants
+    /// (e.g. `gpt-4o-2024-08-06`) resolve to their base model.
+    pub fn get(&self, model: &str) -> Option<&ModelPricing> {
+        if let Some(pricing) = self.models.get(model) {
+            return Some(pricing);
+        }
+
+        self.models
+            .iter()
+            .filter(|(name, _)| model.starts_with(name.as_str()))
+            .max_by_key(|(name, _)| name.len())
+            .map(|(_, pricing)| pricing)
+    }
+
+    /// Estimate the cost in USD of `usage` on `model`.
+    ///
+    /// Returns `None` when the model is not in the table, logging a warning
+    /// the first time each such model is seen.
+    pub fn estimate_cost(&self, usage: &TokenUsage, model: &str) -> Option<f64> {
+        match self.get(model) {
+            Some(pricing) => Some(pricing.cost(usage)),
+            None => {
+                if first_unpriced(model) {
+                    warn!(
+                        "No pricing information for model '{}', its cost is unknown",
+                        model
+                    );
+                }
+                None
+            }
+        }
+    }
+}
+
+impl Default for PricingTable {
+    fn default() -> Self {
+        Self::empty()
+            // OpenAI
+            .with_model("gpt-4o", ModelPricing::new(0.0025, 0.01))
+            .with_model("gpt-4o-mini", ModelPricing::new(0.00015, 0.0006))
+            .with_model("gpt-4-turbo", ModelPricing::new(0.01, 0.03))
+            .with_model("gpt-4", ModelPricing::new(0.03, 0.06))
+            .with_model("gpt-3.5-turbo", ModelPricing::new(0.0005, 0.0015))
+            .with_model("text-embedding-3-small", ModelPricing::new(0.00002, 0.0))
+            .with_model("text-embedding-3-large", ModelPricing::new(0.00013, 0.0))
+            .with_model("text-embedding-ada-002", ModelPricing::new(0.0001, 0.0))
+            // Anthropic
+            .with_model("claude-3-5-sonnet", ModelPricing::new(0.003, 0.015))
+            .with_model("claude-3-5-haiku", ModelPricing::new(0.0008, 0.004))
+            .with_model("claude-3-opus", ModelPricing::new(0.015, 0.075))
+            .with_model("claude-3-haiku", ModelPricing::new(0.00025, 0.00125))
+            // Google
+            .with_model("gemini-1.5-pro", ModelPricing::new(0.00125, 0.005))
+            .with_model("gemini-1.5-flash", ModelPricing::new(0.000075, 0.0003))
+            .with_model("gemini-pro", ModelPricing::new(0.0005, 0.0015))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_cost_known_model() {
+        let table = PricingTable::default();
+        let usage = TokenUsage::new(2000, 500);
+
+        // 2K input * $0.0025 + 0.5K output * $0.01
+        let cost = table.estimate_cost(&usage, "gpt-4o").unwrap();
+        assert!((cost - 0.01).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_estimate_cost_prefix_and_unknown() {
+        let table = PricingTable::default();
+        let usage = TokenUsage::new(1000, 1000);
+
+        let cost = table
+            .estimate_cost(&usage, "claude-3-5-sonnet-20241022")
+            .unwrap();
+        assert!((cost - 0.018).abs() < 1e-9);
+
+        assert!(table.estimate_cost(&usage, "llama3.2").is_none());
+    }
+
+    #[test]
+    fn test_unpriced_model_is_reported_once() {
+        assert!(first_unpriced("test-unpriced-model"));
+        assert!(!first_unpriced("test-unpriced-model"));
+        assert!(first_unpriced("test-other-unpriced-model"));
+    }
+
+    #[test]
+    fn test_overrides_replace_defaults() {
+        let overrides = HashMap::from([("gpt-4o".to_string(), ModelPricing::new(1.0, 2.0))]);
+        let table = PricingTable::default().with_overrides(overrides);
+
+        let cost = table
+            .estimate_cost(&TokenUsage::new(1000, 1000), "gpt-4o")
+            .unwrap();
+        assert!((cost - 3.0).abs() < 1e-9);
+    }
+}
@@ -3,9 +3,18 @@
 //! This module defines the common interface that all LLM providers must implement.
 
 use crate::error::Result;
-use crate::llm::{EmbeddingResponse, GenerationResponse, Message};
+use crate::http_client::HttpClientConfig;
+use crate::llm::body_log::default_log_body_max_len;
+use crate::llm::context_window::ContextOverflowPolicy;
+use crate::llm::generation_timeout::{GenerationTimeouts, TimeoutLlmClient};
+use crate::llm::stream::StreamEvent;
+use crate::llm::{EmbeddingResponse, GenerationResponse, LlmClient, Message};
+use crate::mcp::McpTool;
 use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::debug;
 
 /// Supported LLM providers
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -21,6 +30,7 @@ pub enum ProviderType {
     Replicate,
     HuggingFace,
     Cohere,
+    LlamaCpp,
 }
 
 impl std::fmt::Display for ProviderType {
@@ -36,6 +46,7 @@ impl std::fmt::Display for ProviderType {
             ProviderType::Replicate => write!(f, "replicate"),
             ProviderType::HuggingFace => write!(f, "huggingface"),
             ProviderType::Cohere => write!(f, "cohere"),
+            ProviderType::LlamaCpp => write!(f, "llamacpp"),
         }
     }
 }
@@ -77,11 +88,57 @@ pub struct ProviderConfig {
     #[serde(default = "default_timeout")]
     pub timeout: u64,
 
+    /// Longest a generation may run in seconds, including the whole of a
+    /// stream, when used through [`ProviderClient::wrap`]; unbounded when unset
+    #[serde(default)]
+    pub generation_timeout: Option<u64>,
+
+    /// Longest gap between streamed events in seconds, when used through
+    /// [`ProviderClient::wrap`]; unbounded when unset
+    #[serde(default)]
+    pub stream_idle_timeout: Option<u64>,
+
+    /// Context window of `text_model` in tokens; prompts are not checked when unset
+    #[serde(default)]
+    pub context_window: Option<u32>,
+
+    /// How to handle prompts that would not leave `max_tokens` free in the context window
+    #[serde(default)]
+    pub context_overflow: ContextOverflowPolicy,
+
+    /// Log raw request and response bodies at debug level, with API keys masked
+    #[serde(default)]
+    pub log_bodies: bool,
+
+    /// Bodies longer than this many bytes are truncated in the log
+    #[serde(default = "default_log_body_max_len")]
+    pub log_body_max_len: usize,
+
+    /// Sampling seed sent to providers that support one, for reproducible generations
+    #[serde(default)]
+    pub seed: Option<u64>,
+
+    /// Proxy, trusted CA and connection settings for the provider's HTTP client
+    #[serde(default)]
+    pub http: HttpClientConfig,
+
     /// Provider-specific options
     #[serde(default)]
     pub options: serde_json::Value,
 }
 
+impl ProviderConfig {
+    /// Check that the provider's HTTP settings can build a client and that
+    /// its provider-specific options parse
+    pub fn validate(&self) -> Result<()> {
+        self.http.validate()?;
+        if self.provider == ProviderType::Google {
+            crate::llm::providers::google::safety_settings(&self.options)?;
+        }
+        Ok(())
+    }
+}
+
 fn default_priority() -> u8 {
     10
 }
@@ -132,6 +189,88 @@ pub trait LlmProvider: Send + Sync {
     fn get_stats(&self) -> ProviderStats {
         ProviderStats::default()
     }
+
+    /// Stream a generation offering `tools` to the model; see
+    /// [`LlmClient::generate_stream_with_tools`]. The default implementation
+    /// generates without the tools and replays the response as events.
+    async fn generate_stream_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[McpTool],
+    ) -> Result<BoxStream<'static, Result<StreamEvent>>> {
+        if !tools.is_empty() {
+            debug!(
+                "{} has no tool calling, generating without tools",
+                self.name()
+            );
+        }
+        let response = self.generate(messages).await?;
+        let events = StreamEvent::from_response(response).into_iter().map(Ok);
+        Ok(stream::iter(events).boxed())
+    }
+}
+
+/// [`LlmClient`] over a provider, so agents and workflow steps can use any
+/// provider
+pub struct ProviderClient {
+    provider: Arc<dyn LlmProvider>,
+}
+
+impl ProviderClient {
+    pub fn new(provider: Arc<dyn LlmProvider>) -> Self {
+        Self { provider }
+    }
+
+    /// `provider` as a client whose generations are bounded by the
+    /// `generation_timeout` and `stream_idle_timeout` in `config`
+    pub fn wrap(provider: Arc<dyn LlmProvider>, config: &ProviderConfig) -> Arc<dyn LlmClient> {
+        TimeoutLlmClient::bound(
+            Arc::new(Self::new(provider)),
+            GenerationTimeouts::from_provider_config(config),
+        )
+    }
+
+    pub fn provider(&self) -> &Arc<dyn LlmProvider> {
+        &self.provider
+    }
+}
+
+#[async_trait]
+impl LlmClient for ProviderClient {
+    async fn generate(&self, messages: &[Message]) -> Result<GenerationResponse> {
+        self.provider.generate(messages).await
+    }
+
+    async fn embed(&self, text: &str) -> Result<EmbeddingResponse> {
+        self.provider.embed(text).await
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>> {
+        self.provider.list_models().await
+    }
+
+    async fn is_model_available(&self, model: &str) -> Result<bool> {
+        self.provider.is_model_available(model).await
+    }
+
+    async fn generate_stream(
+        &self,
+        messages: &[Message],
+    ) -> Result<BoxStream<'static, Result<StreamEvent>>> {
+        self.provider
+            .generate_stream_with_tools(messages, &[])
+            .await
+    }
+
+    async fn generate_stream_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[McpTool],
+    ) -> Result<BoxStream<'static, Result<StreamEvent>>> {
+        self.provider
+            .generate_stream_with_tools(messages, tools)
+            .await
+    }
 }
 
 /// Provider usage statistics
@@ -146,10 +285,11 @@ pub struct ProviderStats {
 }
 
 /// Fallback strategy for multi-provider setup
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum FallbackStrategy {
     /// Try providers in priority order
+    #[default]
     Priority,
     /// Distribute load across providers
     RoundRobin,
@@ -159,12 +299,6 @@ pub enum FallbackStrategy {
     TaskBased,
 }
 
-impl Default for FallbackStrategy {
-    fn default() -> Self {
-        Self::Priority
-    }
-}
-
 /// Configuration for multi-provider fallback
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FallbackConfig {
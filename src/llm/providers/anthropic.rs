@@ -4,6 +4,7 @@
 //! LlmProvider directly rather than using the OpenAI-compatible base.
 
 use crate::error::{LlmError, Result};
+use crate::llm::pricing::TokenUsage;
 use crate::llm::provider::{LlmProvider, ProviderConfig, ProviderStats, ProviderType};
 use crate::llm::providers::base::HttpProviderClient;
 use crate::llm::{EmbeddingResponse, GenerationResponse, Message, Role};
@@ -12,22 +13,46 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tracing::{debug, info};
 
-/// Anthropic message format
+/// Anthropic message format. Content is a plain string, or content blocks
+/// for tool calls and their results.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnthropicMessage {
     pub role: String,
-    pub content: String,
+    pub content: serde_json::Value,
 }
 
 impl From<&Message> for AnthropicMessage {
     fn from(msg: &Message) -> Self {
+        let content = if let Some(tool_use_id) = &msg.tool_call_id {
+            serde_json::json!([{
+                "type": "tool_result",
+                "tool_use_id": tool_use_id,
+                "content": msg.content,
+            }])
+        } else if !msg.tool_calls.is_empty() {
+            let text = (!msg.content.is_empty())
+                .then(|| serde_json::json!({ "type": "text", "text": msg.content }));
+            let tool_uses = msg.tool_calls.iter().map(|call| {
+                serde_json::json!({
+                    "type": "tool_use",
+                    "id": call.id,
+                    "name": call.name,
+                    "input": call.arguments,
+                })
+            });
+            serde_json::Value::Array(text.into_iter().chain(tool_uses).collect())
+        } else {
+            serde_json::Value::String(msg.content.clone())
+        };
+
         Self {
             role: match msg.role {
                 Role::User => "user".to_string(),
                 Role::Assistant => "assistant".to_string(),
                 Role::System => "user".to_string(), // System messages handled separately
+                Role::Tool => "user".to_string(),   // Tool results are user content blocks
             },
-            content: msg.content.clone(),
+            content,
         }
     }
 }
@@ -61,7 +86,11 @@ pub struct AnthropicResponse {
 pub struct ContentBlock {
     #[serde(rename = "type")]
     pub block_type: String,
+    #[serde(default)]
     pub text: String,
+    /// Extended thinking, in `thinking` blocks
+    #[serde(default)]
+    pub thinking: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -90,13 +119,13 @@ pub struct AnthropicProvider {
 
 impl AnthropicProvider {
     /// Create a new Anthropic provider
-    pub fn create(config: ProviderConfig) -> Arc<dyn LlmProvider> {
-        let client = HttpProviderClient::new(config.timeout);
-        Arc::new(Self {
+    pub fn create(config: ProviderConfig) -> Result<Arc<dyn LlmProvider>> {
+        let client = HttpProviderClient::from_config(&config)?;
+        Ok(Arc::new(Self {
             client,
             config,
             stats: ProviderStats::default(),
-        })
+        }))
     }
 
     /// Create from environment variable
@@ -118,10 +147,18 @@ impl AnthropicProvider {
             max_tokens: 4096,
             temperature: 0.7,
             timeout: 120,
+            generation_timeout: None,
+            stream_idle_timeout: None,
+            context_window: None,
+            context_overflow: Default::default(),
+            log_bodies: false,
+            log_body_max_len: 2048,
+            seed: None,
+            http: Default::default(),
             options: serde_json::Value::Null,
         };
 
-        Ok(Self::create(config))
+        Self::create(config).map_err(|e| e.to_string())
     }
 
     fn base_url(&self) -> String {
@@ -161,6 +198,8 @@ impl LlmProvider for AnthropicProvider {
             messages.len()
         );
 
+        let messages = self.client.fit_context(messages)?;
+
         // Extract system message if present
         let system_message = messages
             .iter()
@@ -204,9 +243,15 @@ impl LlmProvider for AnthropicProvider {
         let text = response
             .content
             .iter()
+            .filter(|block| block.block_type != "thinking")
             .map(|block| block.text.as_str())
             .collect::<Vec<_>>()
             .join("\n");
+        let thinking: Vec<&str> = response
+            .content
+            .iter()
+            .filter_map(|block| block.thinking.as_deref())
+            .collect();
 
         let total_tokens = response.usage.input_tokens + response.usage.output_tokens;
 
@@ -215,8 +260,14 @@ impl LlmProvider for AnthropicProvider {
         Ok(GenerationResponse {
             text,
             tokens_used: Some(total_tokens),
+            usage: Some(TokenUsage::new(
+                response.usage.input_tokens,
+                response.usage.output_tokens,
+            )),
             model: response.model,
             finish_reason: response.stop_reason,
+            system_fingerprint: None,
+            reasoning: (!thinking.is_empty()).then(|| thinking.join("\n")),
         })
     }
 
@@ -263,6 +314,8 @@ mod tests {
         let msg = Message {
             role: Role::User,
             content: "Hello".to_string(),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
         };
 
         let anthropic_msg = AnthropicMessage::from(&msg);
@@ -270,6 +323,29 @@ mod tests {
         assert_eq!(anthropic_msg.content, "Hello");
     }
 
+    #[test]
+    fn test_tool_call_history_conversion() {
+        let call = crate::mcp::ToolCall {
+            id: "toolu_1".to_string(),
+            name: "get_weather".to_string(),
+            arguments: serde_json::json!({ "city": "Paris" }),
+        };
+        let tool_use = AnthropicMessage::from(&crate::llm::assistant_tool_calls_message(
+            "Checking.",
+            vec![call],
+        ));
+        assert_eq!(tool_use.role, "assistant");
+        assert_eq!(tool_use.content[0]["text"], "Checking.");
+        assert_eq!(tool_use.content[1]["type"], "tool_use");
+        assert_eq!(tool_use.content[1]["input"]["city"], "Paris");
+
+        let result = AnthropicMessage::from(&crate::llm::tool_message("toolu_1", "sunny"));
+        assert_eq!(result.role, "user");
+        assert_eq!(result.content[0]["type"], "tool_result");
+        assert_eq!(result.content[0]["tool_use_id"], "toolu_1");
+        assert_eq!(result.content[0]["content"], "sunny");
+    }
+
     #[test]
     fn test_provider_creation() {
         let config = ProviderConfig {
@@ -283,10 +359,18 @@ mod tests {
             max_tokens: 4096,
             temperature: 0.7,
             timeout: 60,
+            generation_timeout: None,
+            stream_idle_timeout: None,
+            context_window: None,
+            context_overflow: Default::default(),
+            log_bodies: false,
+            log_body_max_len: 2048,
+            seed: None,
+            http: Default::default(),
             options: serde_json::Value::Null,
         };
 
-        let provider = AnthropicProvider::create(config);
+        let provider = AnthropicProvider::create(config).unwrap();
         assert_eq!(provider.name(), "test");
         assert_eq!(provider.provider_type(), ProviderType::Anthropic);
     }
@@ -304,10 +388,18 @@ mod tests {
             max_tokens: 4096,
             temperature: 0.7,
             timeout: 60,
+            generation_timeout: None,
+            stream_idle_timeout: None,
+            context_window: None,
+            context_overflow: Default::default(),
+            log_bodies: false,
+            log_body_max_len: 2048,
+            seed: None,
+            http: Default::default(),
             options: serde_json::Value::Null,
         };
 
-        let provider = AnthropicProvider::create(config);
+        let provider = AnthropicProvider::create(config).unwrap();
         let models = provider.list_models().await.unwrap();
         assert!(!models.is_empty());
         assert!(models.contains(&"claude-3-opus-20240229".to_string()));
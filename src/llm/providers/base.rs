@@ -2,10 +2,17 @@
 //!
 //! Provides common HTTP client functionality for cloud-based LLM providers
 
-use crate::error::{LlmError, Result};
+use crate::error::{LlmError, ProviderError, ProviderErrorKind, Result};
+use crate::http_client::HttpClientConfig;
+use crate::llm::body_log::BodyLogger;
+use crate::llm::context_window::ContextWindowGuard;
+use crate::llm::provider::ProviderConfig;
+use crate::llm::Message;
 use reqwest::{Client, Response};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use serde_json::Value;
+use std::borrow::Cow;
 use std::time::Duration;
 use tracing::{debug, error};
 
@@ -13,19 +20,62 @@ use tracing::{debug, error};
 pub struct HttpProviderClient {
     client: Client,
     timeout: Duration,
+    context_guard: Option<ContextWindowGuard>,
+    body_logger: Option<BodyLogger>,
 }
 
 impl HttpProviderClient {
     /// Create a new HTTP provider client
-    pub fn new(timeout_secs: u64) -> Self {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(timeout_secs))
-            .build()
-            .expect("Failed to create HTTP client");
+    pub fn new(timeout_secs: u64) -> Result<Self> {
+        Self::with_http_config(timeout_secs, &HttpClientConfig::default())
+    }
+
+    /// Create a client with proxy, trusted CA and connection settings,
+    /// failing if the proxy URL or CA file is invalid
+    pub fn with_http_config(timeout_secs: u64, http: &HttpClientConfig) -> Result<Self> {
+        let client = http.build_client(Duration::from_secs(timeout_secs))?;
 
-        Self {
+        Ok(Self {
             client,
             timeout: Duration::from_secs(timeout_secs),
+            context_guard: None,
+            body_logger: None,
+        })
+    }
+
+    /// Create a client for a provider, routing it as configured, guarding its
+    /// context window and logging bodies if configured
+    pub fn from_config(config: &ProviderConfig) -> Result<Self> {
+        let mut client = Self::with_http_config(config.timeout, &config.http)?;
+        client.body_logger = BodyLogger::from_settings(config.log_bodies, config.log_body_max_len);
+        Ok(match config.context_window {
+            Some(context_window) => client.with_context_guard(ContextWindowGuard::new(
+                &config.text_model,
+                context_window,
+                config.max_tokens,
+                config.context_overflow,
+            )),
+            None => client,
+        })
+    }
+
+    /// Check prompts against a context window before sending
+    pub fn with_context_guard(mut self, guard: ContextWindowGuard) -> Self {
+        self.context_guard = Some(guard);
+        self
+    }
+
+    /// Log request and response bodies at debug level
+    pub fn with_body_logger(mut self, logger: BodyLogger) -> Self {
+        self.body_logger = Some(logger);
+        self
+    }
+
+    /// Messages to send after applying the context window guard, if any
+    pub fn fit_context<'a>(&self, messages: &'a [Message]) -> Result<Cow<'a, [Message]>> {
+        match &self.context_guard {
+            Some(guard) => guard.fit(messages),
+            None => Ok(Cow::Borrowed(messages)),
         }
     }
 
@@ -37,6 +87,9 @@ impl HttpProviderClient {
         headers: Vec<(&str, &str)>,
     ) -> Result<R> {
         debug!("Making POST request to: {}", url);
+        if let Some(logger) = &self.body_logger {
+            logger.log_request(url, &headers, body);
+        }
 
         let mut request = self.client.post(url).json(body);
 
@@ -49,7 +102,45 @@ impl HttpProviderClient {
             .map_err(|_| LlmError::Timeout)?
             .map_err(|e| LlmError::ConnectionFailed(e.to_string()))?;
 
-        self.handle_response(response).await
+        self.handle_response(url, response).await
+    }
+
+    /// Execute a POST request and return the response for streaming its body.
+    /// Only the status is checked; the body is left unread.
+    pub async fn post_stream<T: Serialize>(
+        &self,
+        url: &str,
+        body: &T,
+        headers: Vec<(&str, &str)>,
+    ) -> Result<Response> {
+        debug!("Making streaming POST request to: {}", url);
+        if let Some(logger) = &self.body_logger {
+            logger.log_request(url, &headers, body);
+        }
+
+        let mut request = self.client.post(url).json(body);
+
+        for (key, value) in headers {
+            request = request.header(key, value);
+        }
+
+        // The timeout covers getting the response headers, not the whole stream
+        let response = tokio::time::timeout(self.timeout, request.send())
+            .await
+            .map_err(|_| LlmError::Timeout)?
+            .map_err(|e| LlmError::ConnectionFailed(e.to_string()))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| format!("HTTP {} error", status));
+            error!("API error ({}): {}", status, error_text);
+            return Err(Self::status_error(status.as_u16(), error_text).into());
+        }
+
+        Ok(response)
     }
 
     /// Execute a GET request
@@ -71,11 +162,15 @@ impl HttpProviderClient {
             .map_err(|_| LlmError::Timeout)?
             .map_err(|e| LlmError::ConnectionFailed(e.to_string()))?;
 
-        self.handle_response(response).await
+        self.handle_response(url, response).await
     }
 
     /// Handle HTTP response and deserialize
-    async fn handle_response<R: DeserializeOwned>(&self, response: Response) -> Result<R> {
+    async fn handle_response<R: DeserializeOwned>(
+        &self,
+        url: &str,
+        response: Response,
+    ) -> Result<R> {
         let status = response.status();
 
         if !status.is_success() {
@@ -86,19 +181,24 @@ impl HttpProviderClient {
 
             error!("API error ({}): {}", status, error_text);
 
-            return Err(match status.as_u16() {
-                401 => LlmError::Unauthorized,
-                429 => LlmError::RateLimited,
-                500..=599 => LlmError::ServerError(error_text),
-                _ => LlmError::GenerationFailed(error_text),
-            }
-            .into());
+            return Err(Self::status_error(status.as_u16(), error_text).into());
         }
 
-        response
-            .json()
+        let body = response
+            .text()
             .await
-            .map_err(|e| LlmError::InvalidResponse(e.to_string()).into())
+            .map_err(|e| LlmError::InvalidResponse(e.to_string()))?;
+        if let Some(logger) = &self.body_logger {
+            logger.log_response(url, status.as_u16(), &body);
+        }
+
+        serde_json::from_str(&body).map_err(|e| LlmError::InvalidResponse(e.to_string()).into())
+    }
+
+    fn status_error(status: u16, error_text: String) -> LlmError {
+        let error = parse_provider_error(status, &error_text);
+        crate::metrics::record_provider_error(error.kind);
+        error.into()
     }
 
     /// Get the underlying reqwest client
@@ -107,6 +207,87 @@ impl HttpProviderClient {
     }
 }
 
+/// Parse a provider's error response into a [`ProviderError`].
+///
+/// Understands the OpenAI (`{"error": {"type", "code", "message"}}`),
+/// Anthropic (`{"type": "error", "error": {"type", "message"}}`) and Google
+/// (`{"error": {"code", "status", "message"}}`) envelopes, and a bare
+/// `{"error": "message"}`. The kind comes from the provider's error code when
+/// it is recognized, and from the HTTP status otherwise.
+pub fn parse_provider_error(http_status: u16, body: &str) -> ProviderError {
+    let envelope: Option<Value> = serde_json::from_str(body).ok();
+    let error = envelope.as_ref().and_then(|envelope| envelope.get("error"));
+    let field = |name: &str| {
+        error
+            .and_then(|error| error.get(name))
+            .and_then(Value::as_str)
+    };
+
+    let message = field("message")
+        .or_else(|| error.and_then(Value::as_str))
+        .unwrap_or(body)
+        .trim();
+    let message = if message.is_empty() {
+        format!("HTTP {} error", http_status)
+    } else {
+        message.to_string()
+    };
+
+    // OpenAI's `code` is more specific than its `type`; Google names the
+    // error in `status`, its `code` being the HTTP status again
+    let codes: Vec<&str> = ["code", "status", "type"]
+        .into_iter()
+        .filter_map(field)
+        .collect();
+    let (kind, code) = codes
+        .iter()
+        .find_map(|code| kind_for_code(code).map(|kind| (kind, *code)))
+        .map_or_else(
+            || (kind_for_status(http_status), codes.first().copied()),
+            |(kind, code)| (kind, Some(code)),
+        );
+
+    ProviderError {
+        kind,
+        message,
+        http_status,
+        code: code.map(str::to_string),
+        retryable: kind.is_retryable(),
+    }
+}
+
+fn kind_for_code(code: &str) -> Option<ProviderErrorKind> {
+    use ProviderErrorKind::*;
+    let kind = match code {
+        "invalid_api_key" | "authentication_error" | "UNAUTHENTICATED" => Authentication,
+        "permission_error" | "PERMISSION_DENIED" => PermissionDenied,
+        "insufficient_quota" | "billing_hard_limit_reached" => InsufficientQuota,
+        "rate_limit_exceeded" | "rate_limit_error" | "RESOURCE_EXHAUSTED" => RateLimited,
+        "overloaded_error" | "server_overloaded" | "UNAVAILABLE" => Overloaded,
+        "context_length_exceeded" => ContextLengthExceeded,
+        "content_filter" | "content_policy_violation" => ContentFiltered,
+        "model_not_found" | "not_found_error" | "NOT_FOUND" => NotFound,
+        "invalid_request_error" | "request_too_large" | "INVALID_ARGUMENT" => InvalidRequest,
+        "api_error" | "server_error" | "INTERNAL" => ServerError,
+        _ => return None,
+    };
+    Some(kind)
+}
+
+fn kind_for_status(http_status: u16) -> ProviderErrorKind {
+    use ProviderErrorKind::*;
+    match http_status {
+        401 => Authentication,
+        403 => PermissionDenied,
+        404 => NotFound,
+        429 => RateLimited,
+        400 | 413 | 422 => InvalidRequest,
+        503 | 529 => Overloaded,
+        500..=599 => ServerError,
+        _ => Unknown,
+    }
+}
+
 /// Common trait for OpenAI-compatible API adapters
 pub trait OpenAICompatible {
     /// Get the base URL for the provider
@@ -129,6 +310,11 @@ pub trait OpenAICompatible {
         headers
     }
 
+    /// Provider-specific fields added to every chat completion request
+    fn request_fields(&self) -> serde_json::Map<String, serde_json::Value> {
+        serde_json::Map::new()
+    }
+
     /// Transform endpoint path (for provider-specific routing)
     fn transform_endpoint(&self, endpoint: &str) -> String {
         endpoint.to_string()
@@ -180,6 +366,82 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_from_config_guards_context_window() {
+        use crate::llm::provider::ProviderType;
+        use crate::llm::user_message;
+
+        let mut config = ProviderConfig {
+            provider: ProviderType::OpenAI,
+            name: "openai".to_string(),
+            priority: 1,
+            api_key: None,
+            base_url: None,
+            text_model: "gpt-4o-mini".to_string(),
+            embedding_model: None,
+            max_tokens: 100,
+            temperature: 0.7,
+            timeout: 30,
+            generation_timeout: None,
+            stream_idle_timeout: None,
+            context_window: None,
+            context_overflow: Default::default(),
+            log_bodies: false,
+            log_body_max_len: 2048,
+            seed: None,
+            http: Default::default(),
+            options: serde_json::Value::Null,
+        };
+        let messages: Vec<_> = (0..10)
+            .map(|i| user_message(format!("{} {}", i, "x".repeat(400))))
+            .collect();
+
+        let unguarded = HttpProviderClient::from_config(&config).unwrap();
+        assert_eq!(unguarded.fit_context(&messages).unwrap().len(), 10);
+
+        config.context_window = Some(500);
+        let guarded = HttpProviderClient::from_config(&config).unwrap();
+        let fitted = guarded.fit_context(&messages).unwrap();
+        assert!(fitted.len() < 10);
+        assert!(fitted.last().unwrap().content.starts_with("9 "));
+    }
+
+    #[test]
+    fn test_from_config_rejects_invalid_http_settings() {
+        use crate::error::AgentError;
+        use crate::llm::provider::ProviderType;
+
+        let mut config = ProviderConfig {
+            provider: ProviderType::OpenAI,
+            name: "openai".to_string(),
+            priority: 10,
+            api_key: None,
+            base_url: None,
+            text_model: "gpt-4o-mini".to_string(),
+            embedding_model: None,
+            max_tokens: 100,
+            temperature: 0.7,
+            timeout: 30,
+            generation_timeout: None,
+            stream_idle_timeout: None,
+            context_window: None,
+            context_overflow: Default::default(),
+            log_bodies: false,
+            log_body_max_len: 2048,
+            seed: None,
+            http: Default::default(),
+            options: serde_json::Value::Null,
+        };
+        assert!(config.validate().is_ok());
+
+        config.http.ca_cert_path = Some("/nonexistent/ca.pem".into());
+        assert!(config.validate().is_err());
+        assert!(matches!(
+            HttpProviderClient::from_config(&config),
+            Err(AgentError::Config(_))
+        ));
+    }
+
     #[test]
     fn test_auth_headers() {
         let provider = TestProvider {
@@ -192,4 +454,87 @@ mod tests {
         assert_eq!(headers[0].0, "Authorization");
         assert_eq!(headers[0].1, "Bearer test-key");
     }
+
+    #[test]
+    fn test_parses_openai_errors() {
+        let quota = parse_provider_error(
+            429,
+            r#"{"error": {"message": "You exceeded your current quota, please check your plan and billing details.", "type": "insufficient_quota", "param": null, "code": "insufficient_quota"}}"#,
+        );
+        assert_eq!(quota.kind, ProviderErrorKind::InsufficientQuota);
+        assert_eq!(quota.code.as_deref(), Some("insufficient_quota"));
+        assert_eq!(quota.http_status, 429);
+        assert!(quota.message.starts_with("You exceeded your current quota"));
+        // A 429 that retrying won't fix
+        assert!(!quota.retryable);
+
+        let rate_limit = parse_provider_error(
+            429,
+            r#"{"error": {"message": "Rate limit reached for gpt-4o in organization org-abc on tokens per min (TPM): Limit 30000, Used 29950, Requested 900.", "type": "tokens", "param": null, "code": "rate_limit_exceeded"}}"#,
+        );
+        assert_eq!(rate_limit.kind, ProviderErrorKind::RateLimited);
+        assert!(rate_limit.retryable);
+
+        let context = parse_provider_error(
+            400,
+            r#"{"error": {"message": "This model's maximum context length is 8192 tokens.", "type": "invalid_request_error", "param": "messages", "code": "context_length_exceeded"}}"#,
+        );
+        assert_eq!(context.kind, ProviderErrorKind::ContextLengthExceeded);
+        assert!(!context.retryable);
+
+        let key = parse_provider_error(
+            401,
+            r#"{"error": {"message": "Incorrect API key provided: sk-abc.", "type": "invalid_request_error", "param": null, "code": "invalid_api_key"}}"#,
+        );
+        assert_eq!(key.kind, ProviderErrorKind::Authentication);
+    }
+
+    #[test]
+    fn test_parses_anthropic_errors() {
+        let overloaded = parse_provider_error(
+            529,
+            r#"{"type": "error", "error": {"type": "overloaded_error", "message": "Overloaded"}}"#,
+        );
+        assert_eq!(overloaded.kind, ProviderErrorKind::Overloaded);
+        assert_eq!(overloaded.code.as_deref(), Some("overloaded_error"));
+        assert_eq!(overloaded.message, "Overloaded");
+        assert!(overloaded.retryable);
+
+        let invalid = parse_provider_error(
+            400,
+            r#"{"type": "error", "error": {"type": "invalid_request_error", "message": "max_tokens: Field required"}}"#,
+        );
+        assert_eq!(invalid.kind, ProviderErrorKind::InvalidRequest);
+        assert!(!invalid.retryable);
+    }
+
+    #[test]
+    fn test_parses_google_errors() {
+        let exhausted = parse_provider_error(
+            429,
+            r#"{"error": {"code": 429, "message": "Resource has been exhausted (e.g. check quota).", "status": "RESOURCE_EXHAUSTED"}}"#,
+        );
+        assert_eq!(exhausted.kind, ProviderErrorKind::RateLimited);
+        assert_eq!(exhausted.code.as_deref(), Some("RESOURCE_EXHAUSTED"));
+        assert!(exhausted.retryable);
+
+        let key = parse_provider_error(
+            400,
+            r#"{"error": {"code": 400, "message": "API key not valid. Please pass a valid API key.", "status": "INVALID_ARGUMENT"}}"#,
+        );
+        assert_eq!(key.kind, ProviderErrorKind::InvalidRequest);
+    }
+
+    #[test]
+    fn test_unrecognized_errors_fall_back_to_status() {
+        let bare = parse_provider_error(404, r#"{"error": "model 'llama9' not found"}"#);
+        assert_eq!(bare.kind, ProviderErrorKind::NotFound);
+        assert_eq!(bare.message, "model 'llama9' not found");
+        assert_eq!(bare.code, None);
+
+        let html = parse_provider_error(502, "<html>Bad Gateway</html>");
+        assert_eq!(html.kind, ProviderErrorKind::ServerError);
+        assert_eq!(html.message, "<html>Bad Gateway</html>");
+        assert!(html.retryable);
+    }
 }
@@ -1,11 +1,23 @@
 //! Google Gemini provider implementation
-
-use crate::error::{LlmError, Result};
+//!
+//! Besides plain generation, [`GoogleProvider::generate_with_tools`] offers
+//! [`McpTool`]s to the model as `functionDeclarations` and returns the
+//! `functionCall`s it makes as [`ToolCall`]s. Safety settings are read from the
+//! provider's `options`, e.g.
+//! `options = { safety_settings = [{ category = "HARM_CATEGORY_HARASSMENT", threshold = "BLOCK_ONLY_HIGH" }] }`.
+
+use crate::error::{AgentError, LlmError, Result};
+use crate::ids::new_id;
+use crate::llm::pricing::TokenUsage;
 use crate::llm::provider::{LlmProvider, ProviderConfig, ProviderStats, ProviderType};
 use crate::llm::providers::base::HttpProviderClient;
+use crate::llm::stream::StreamEvent;
 use crate::llm::{EmbeddingResponse, GenerationResponse, Message, Role};
+use crate::mcp::{McpTool, ToolCall};
 use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use tracing::{debug, info};
 
@@ -16,9 +28,38 @@ pub struct GeminiContent {
     pub parts: Vec<GeminiPart>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Text, a function call made by the model, or the response to one
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct GeminiPart {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub function_call: Option<GeminiFunctionCall>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub function_response: Option<GeminiFunctionResponse>,
+}
+
+impl GeminiPart {
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeminiFunctionCall {
+    pub name: String,
+    #[serde(default)]
+    pub args: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeminiFunctionResponse {
+    pub name: String,
+    pub response: serde_json::Value,
 }
 
 impl From<&Message> for GeminiContent {
@@ -28,20 +69,102 @@ impl From<&Message> for GeminiContent {
                 Role::User => "user".to_string(),
                 Role::Assistant => "model".to_string(),
                 Role::System => "user".to_string(), // System messages as user
+                Role::Tool => "user".to_string(),   // Tool results as user text
             },
-            parts: vec![GeminiPart {
-                text: msg.content.clone(),
-            }],
+            parts: vec![GeminiPart::text(msg.content.clone())],
+        }
+    }
+}
+
+/// `messages` as Gemini contents. Tool calls become `functionCall` parts and
+/// tool messages answering them `functionResponse` parts, which Gemini matches
+/// by function name rather than call id.
+pub fn gemini_contents(messages: &[Message]) -> Vec<GeminiContent> {
+    let mut call_names: HashMap<&str, &str> = HashMap::new();
+    messages
+        .iter()
+        .map(|msg| {
+            for call in &msg.tool_calls {
+                call_names.insert(&call.id, &call.name);
+            }
+            let name = msg
+                .tool_call_id
+                .as_deref()
+                .and_then(|id| call_names.get(id));
+            match (&msg.role, name) {
+                (Role::Assistant, _) if !msg.tool_calls.is_empty() => {
+                    let text = (!msg.content.is_empty()).then(|| GeminiPart::text(&msg.content));
+                    let calls = msg.tool_calls.iter().map(|call| GeminiPart {
+                        function_call: Some(GeminiFunctionCall {
+                            name: call.name.clone(),
+                            args: call.arguments.clone(),
+                        }),
+                        ..Default::default()
+                    });
+                    GeminiContent {
+                        role: "model".to_string(),
+                        parts: text.into_iter().chain(calls).collect(),
+                    }
+                }
+                (Role::Tool, Some(name)) => GeminiContent {
+                    role: "user".to_string(),
+                    parts: vec![GeminiPart {
+                        function_response: Some(GeminiFunctionResponse {
+                            name: name.to_string(),
+                            response: serde_json::json!({ "content": msg.content }),
+                        }),
+                        ..Default::default()
+                    }],
+                },
+                _ => GeminiContent::from(msg),
+            }
+        })
+        .collect()
+}
+
+/// Function declarations offered to the model
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeminiTool {
+    pub function_declarations: Vec<GeminiFunctionDeclaration>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GeminiFunctionDeclaration {
+    pub name: String,
+    pub description: String,
+    pub parameters: serde_json::Value,
+}
+
+impl From<&McpTool> for GeminiFunctionDeclaration {
+    fn from(tool: &McpTool) -> Self {
+        Self {
+            name: tool.name.clone(),
+            description: tool.description.clone(),
+            parameters: tool.input_schema.clone(),
         }
     }
 }
 
+/// Blocking threshold for one harm category
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GeminiSafetySetting {
+    /// e.g. `HARM_CATEGORY_DANGEROUS_CONTENT`
+    pub category: String,
+    /// e.g. `BLOCK_MEDIUM_AND_ABOVE`
+    pub threshold: String,
+}
+
 /// Gemini API request
 #[derive(Debug, Serialize)]
 pub struct GeminiRequest {
     pub contents: Vec<GeminiContent>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub generation_config: Option<GenerationConfig>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<GeminiTool>,
+    #[serde(rename = "safetySettings", skip_serializing_if = "Vec::is_empty")]
+    pub safety_settings: Vec<GeminiSafetySetting>,
 }
 
 #[derive(Debug, Serialize)]
@@ -54,20 +177,40 @@ pub struct GenerationConfig {
 #[derive(Debug, Deserialize)]
 pub struct GeminiResponse {
     pub candidates: Vec<Candidate>,
-    #[serde(default)]
+    #[serde(default, alias = "usageMetadata")]
     pub usage_metadata: Option<UsageMetadata>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct Candidate {
     pub content: GeminiContent,
+    #[serde(default, alias = "finishReason")]
     pub finish_reason: Option<String>,
 }
 
+impl Candidate {
+    /// Function calls in this candidate, with fresh ids since Gemini assigns none
+    pub fn tool_calls(&self) -> Vec<ToolCall> {
+        self.content
+            .parts
+            .iter()
+            .filter_map(|part| part.function_call.as_ref())
+            .map(|call| ToolCall {
+                id: new_id().to_string(),
+                name: call.name.clone(),
+                arguments: call.args.clone(),
+            })
+            .collect()
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct UsageMetadata {
+    #[serde(default, alias = "promptTokenCount")]
     pub prompt_token_count: u32,
+    #[serde(default, alias = "candidatesTokenCount")]
     pub candidates_token_count: u32,
+    #[serde(default, alias = "totalTokenCount")]
     pub total_token_count: u32,
 }
 
@@ -88,21 +231,38 @@ pub struct EmbeddingData {
     pub values: Vec<f32>,
 }
 
+/// The `safety_settings` in a provider's options, an error when malformed
+pub(crate) fn safety_settings(options: &serde_json::Value) -> Result<Vec<GeminiSafetySetting>> {
+    match options.get("safety_settings") {
+        Some(settings) => serde_json::from_value(settings.clone())
+            .map_err(|e| AgentError::Config(format!("Invalid Gemini safety_settings: {}", e))),
+        None => Ok(Vec::new()),
+    }
+}
+
 /// Google Gemini provider
 pub struct GoogleProvider {
     client: HttpProviderClient,
     config: ProviderConfig,
     stats: ProviderStats,
+    safety_settings: Vec<GeminiSafetySetting>,
 }
 
 impl GoogleProvider {
     /// Create a new Google Gemini provider
-    pub fn create(config: ProviderConfig) -> Arc<dyn LlmProvider> {
-        let client = HttpProviderClient::new(config.timeout);
-        Arc::new(Self {
+    pub fn create(config: ProviderConfig) -> Result<Arc<dyn LlmProvider>> {
+        Ok(Arc::new(Self::new(config)?))
+    }
+
+    /// Provider for `config`, reading `safety_settings` from its options
+    pub fn new(config: ProviderConfig) -> Result<Self> {
+        let client = HttpProviderClient::from_config(&config)?;
+        let safety_settings = safety_settings(&config.options)?;
+        Ok(Self {
             client,
             config,
             stats: ProviderStats::default(),
+            safety_settings,
         })
     }
 
@@ -125,10 +285,18 @@ impl GoogleProvider {
             max_tokens: 2048,
             temperature: 0.7,
             timeout: 120,
+            generation_timeout: None,
+            stream_idle_timeout: None,
+            context_window: None,
+            context_overflow: Default::default(),
+            log_bodies: false,
+            log_body_max_len: 2048,
+            seed: None,
+            http: Default::default(),
             options: serde_json::Value::Null,
         };
 
-        Ok(Self::create(config))
+        Self::create(config).map_err(|e| e.to_string())
     }
 
     fn base_url(&self) -> String {
@@ -141,40 +309,48 @@ impl GoogleProvider {
     fn api_key(&self) -> Option<&str> {
         self.config.api_key.as_deref()
     }
-}
 
-#[async_trait]
-impl LlmProvider for GoogleProvider {
-    fn provider_type(&self) -> ProviderType {
-        ProviderType::Google
-    }
-
-    fn name(&self) -> &str {
-        &self.config.name
+    /// Request for `messages`, offering `tools` when there are any
+    fn request(&self, messages: &[Message], tools: &[McpTool]) -> GeminiRequest {
+        GeminiRequest {
+            contents: gemini_contents(messages),
+            generation_config: Some(GenerationConfig {
+                temperature: self.config.temperature,
+                max_output_tokens: self.config.max_tokens,
+            }),
+            tools: if tools.is_empty() {
+                Vec::new()
+            } else {
+                vec![GeminiTool {
+                    function_declarations: tools.iter().map(Into::into).collect(),
+                }]
+            },
+            safety_settings: self.safety_settings.clone(),
+        }
     }
 
-    async fn generate(&self, messages: &[Message]) -> Result<GenerationResponse> {
+    /// Generate with `tools` offered to the model, returning the response
+    /// text and the tool calls the model made
+    pub async fn generate_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[McpTool],
+    ) -> Result<(GenerationResponse, Vec<ToolCall>)> {
         debug!(
-            "Generating with Google Gemini using {} messages",
-            messages.len()
+            "Generating with Google Gemini using {} messages and {} tools",
+            messages.len(),
+            tools.len()
         );
 
-        // Convert messages to Gemini format
-        let contents: Vec<GeminiContent> = messages.iter().map(GeminiContent::from).collect();
+        let messages = self.client.fit_context(messages)?;
 
-        if contents.is_empty() {
+        if messages.is_empty() {
             return Err(LlmError::InvalidResponse("No messages to send".to_string()).into());
         }
 
-        let request = GeminiRequest {
-            contents,
-            generation_config: Some(GenerationConfig {
-                temperature: self.config.temperature,
-                max_output_tokens: self.config.max_tokens,
-            }),
-        };
+        let request = self.request(&messages, tools);
 
-        let api_key = self.api_key().ok_or_else(|| LlmError::Unauthorized)?;
+        let api_key = self.api_key().ok_or(LlmError::Unauthorized)?;
 
         let url = format!(
             "{}/models/{}:generateContent?key={}",
@@ -196,24 +372,77 @@ impl LlmProvider for GoogleProvider {
             .content
             .parts
             .iter()
+            .filter(|p| !p.text.is_empty())
             .map(|p| p.text.as_str())
             .collect::<Vec<_>>()
             .join("\n");
+        let tool_calls = candidate.tool_calls();
 
-        let tokens_used = response.usage_metadata.map(|u| u.total_token_count);
+        let tokens_used = response
+            .usage_metadata
+            .as_ref()
+            .map(|u| u.total_token_count);
+        let usage = response
+            .usage_metadata
+            .as_ref()
+            .map(|u| TokenUsage::new(u.prompt_token_count, u.candidates_token_count));
 
         info!(
-            "Generated {} tokens with {}",
+            "Generated {} tokens and {} tool calls with {}",
             tokens_used.unwrap_or(0),
+            tool_calls.len(),
             self.config.text_model
         );
 
-        Ok(GenerationResponse {
+        let generation = GenerationResponse {
             text,
             tokens_used,
+            usage,
             model: self.config.text_model.clone(),
             finish_reason: candidate.finish_reason.clone(),
-        })
+            system_fingerprint: None,
+            reasoning: None,
+        };
+        Ok((generation, tool_calls))
+    }
+}
+
+#[async_trait]
+impl LlmProvider for GoogleProvider {
+    fn provider_type(&self) -> ProviderType {
+        ProviderType::Google
+    }
+
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    async fn generate(&self, messages: &[Message]) -> Result<GenerationResponse> {
+        let (generation, _) = self.generate_with_tools(messages, &[]).await?;
+        Ok(generation)
+    }
+
+    /// Offers `tools` in a complete request and replays the answer as events
+    async fn generate_stream_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[McpTool],
+    ) -> Result<BoxStream<'static, Result<StreamEvent>>> {
+        let (generation, tool_calls) = self.generate_with_tools(messages, tools).await?;
+        let mut events = Vec::new();
+        if !generation.text.is_empty() {
+            events.push(Ok(StreamEvent::TextDelta(generation.text)));
+        }
+        events.extend(
+            tool_calls
+                .into_iter()
+                .map(|call| Ok(StreamEvent::ToolCall(call))),
+        );
+        events.push(Ok(StreamEvent::Done {
+            finish_reason: generation.finish_reason,
+            usage: generation.usage,
+        }));
+        Ok(stream::iter(events).boxed())
     }
 
     async fn embed(&self, text: &str) -> Result<EmbeddingResponse> {
@@ -229,13 +458,11 @@ impl LlmProvider for GoogleProvider {
         let request = GeminiEmbedRequest {
             content: GeminiContent {
                 role: "user".to_string(),
-                parts: vec![GeminiPart {
-                    text: text.to_string(),
-                }],
+                parts: vec![GeminiPart::text(text)],
             },
         };
 
-        let api_key = self.api_key().ok_or_else(|| LlmError::Unauthorized)?;
+        let api_key = self.api_key().ok_or(LlmError::Unauthorized)?;
 
         let url = format!(
             "{}/models/{}:embedContent?key={}",
@@ -286,11 +513,37 @@ impl LlmProvider for GoogleProvider {
 mod tests {
     use super::*;
 
+    fn test_config() -> ProviderConfig {
+        ProviderConfig {
+            provider: ProviderType::Google,
+            name: "test".to_string(),
+            priority: 1,
+            api_key: Some("test-key".to_string()),
+            base_url: None,
+            text_model: "gemini-1.5-flash".to_string(),
+            embedding_model: None,
+            max_tokens: 256,
+            temperature: 0.0,
+            timeout: 10,
+            generation_timeout: None,
+            stream_idle_timeout: None,
+            context_window: None,
+            context_overflow: Default::default(),
+            log_bodies: false,
+            log_body_max_len: 2048,
+            seed: None,
+            http: Default::default(),
+            options: serde_json::Value::Null,
+        }
+    }
+
     #[test]
     fn test_message_conversion() {
         let msg = Message {
             role: Role::User,
             content: "Hello".to_string(),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
         };
 
         let gemini_content = GeminiContent::from(&msg);
@@ -311,10 +564,18 @@ mod tests {
             max_tokens: 2048,
             temperature: 0.7,
             timeout: 60,
+            generation_timeout: None,
+            stream_idle_timeout: None,
+            context_window: None,
+            context_overflow: Default::default(),
+            log_bodies: false,
+            log_body_max_len: 2048,
+            seed: None,
+            http: Default::default(),
             options: serde_json::Value::Null,
         };
 
-        let provider = GoogleProvider::create(config);
+        let provider = GoogleProvider::create(config).unwrap();
         assert_eq!(provider.name(), "test");
         assert_eq!(provider.provider_type(), ProviderType::Google);
     }
@@ -332,12 +593,122 @@ mod tests {
             max_tokens: 2048,
             temperature: 0.7,
             timeout: 60,
+            generation_timeout: None,
+            stream_idle_timeout: None,
+            context_window: None,
+            context_overflow: Default::default(),
+            log_bodies: false,
+            log_body_max_len: 2048,
+            seed: None,
+            http: Default::default(),
             options: serde_json::Value::Null,
         };
 
-        let provider = GoogleProvider::create(config);
+        let provider = GoogleProvider::create(config).unwrap();
         let models = provider.list_models().await.unwrap();
         assert!(!models.is_empty());
         assert!(models.contains(&"gemini-pro".to_string()));
     }
+
+    #[test]
+    fn test_tool_request_uses_gemini_shape() {
+        let mut config = test_config();
+        config.options = serde_json::json!({
+            "safety_settings": [
+                { "category": "HARM_CATEGORY_HARASSMENT", "threshold": "BLOCK_ONLY_HIGH" }
+            ]
+        });
+        let provider = GoogleProvider::new(config).unwrap();
+        let tool = McpTool {
+            name: "get_weather".to_string(),
+            description: "Current weather for a city".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": { "city": { "type": "string" } }
+            }),
+        };
+        let call = ToolCall {
+            id: "call-1".to_string(),
+            name: "get_weather".to_string(),
+            arguments: serde_json::json!({ "city": "Paris" }),
+        };
+        let messages = [
+            crate::llm::user_message("Weather in Paris?"),
+            crate::llm::assistant_tool_calls_message("", vec![call]),
+            crate::llm::tool_message("call-1", "sunny"),
+        ];
+
+        let body = serde_json::to_value(provider.request(&messages, &[tool])).unwrap();
+
+        let declaration = &body["tools"][0]["functionDeclarations"][0];
+        assert_eq!(declaration["name"], "get_weather");
+        assert_eq!(
+            declaration["parameters"]["properties"]["city"]["type"],
+            "string"
+        );
+        assert_eq!(
+            body["safetySettings"][0],
+            serde_json::json!({
+                "category": "HARM_CATEGORY_HARASSMENT",
+                "threshold": "BLOCK_ONLY_HIGH"
+            })
+        );
+        assert_eq!(body["contents"][1]["role"], "model");
+        assert_eq!(
+            body["contents"][1]["parts"][0]["functionCall"],
+            serde_json::json!({ "name": "get_weather", "args": { "city": "Paris" } })
+        );
+        let answer = &body["contents"][2]["parts"][0]["functionResponse"];
+        assert_eq!(answer["name"], "get_weather");
+        assert_eq!(answer["response"]["content"], "sunny");
+
+        // Without tools or settings neither block is sent
+        let plain_provider = GoogleProvider::new(test_config()).unwrap();
+        let plain = serde_json::to_value(plain_provider.request(&messages, &[])).unwrap();
+        assert!(plain.get("tools").is_none());
+        assert!(plain.get("safetySettings").is_none());
+    }
+
+    #[test]
+    fn test_invalid_safety_settings_are_rejected() {
+        let mut config = test_config();
+        config.options = serde_json::json!({
+            "safety_settings": [{ "category": "HARM_CATEGORY_HARASSMENT" }]
+        });
+        assert!(matches!(config.validate(), Err(AgentError::Config(_))));
+        assert!(matches!(
+            GoogleProvider::new(config),
+            Err(AgentError::Config(_))
+        ));
+    }
+
+    #[test]
+    fn test_function_call_response_parses_into_tool_call() {
+        let response: GeminiResponse = serde_json::from_value(serde_json::json!({
+            "candidates": [{
+                "content": {
+                    "role": "model",
+                    "parts": [{
+                        "functionCall": { "name": "get_weather", "args": { "city": "Paris" } }
+                    }]
+                },
+                "finishReason": "STOP"
+            }],
+            "usageMetadata": {
+                "promptTokenCount": 12,
+                "candidatesTokenCount": 5,
+                "totalTokenCount": 17
+            }
+        }))
+        .unwrap();
+
+        let candidate = &response.candidates[0];
+        let calls = candidate.tool_calls();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].name, "get_weather");
+        assert_eq!(calls[0].arguments, serde_json::json!({ "city": "Paris" }));
+        assert!(!calls[0].id.is_empty());
+        assert_eq!(candidate.finish_reason.as_deref(), Some("STOP"));
+        assert_eq!(response.usage_metadata.unwrap().total_token_count, 17);
+    }
 }
@@ -0,0 +1,238 @@
+//! llama.cpp server provider
+//!
+//! `llama-server` exposes an OpenAI-compatible `/v1/chat/completions`, so this
+//! provider reuses [`OpenAICompatibleProvider`] and only handles where the
+//! server differs from hosted APIs:
+//!
+//! - It serves whichever model it was started with. The request's `model` is
+//!   only a label, so `text_model` can be any name.
+//! - Streamed completions never carry `usage`, so streaming reports text and
+//!   tool calls only. Non-streaming responses do include usage.
+//! - Generation only stops at the model's end-of-generation token. Extra stop
+//!   sequences go in the `stop` option and are sent with every request.
+//! - Output can be constrained with a GBNF grammar, either for every request
+//!   through the `grammar` option or per request with
+//!   [`LlamaCppProvider::generate_with_grammar`].
+//! - No API key is needed unless the server was started with `--api-key`.
+
+use crate::error::Result;
+use crate::llm::provider::{LlmProvider, ProviderConfig, ProviderType};
+use crate::llm::providers::base::OpenAICompatible;
+use crate::llm::providers::openai_compatible::OpenAICompatibleProvider;
+use crate::llm::{GenerationResponse, Message};
+use serde_json::{Map, Value};
+use std::sync::Arc;
+
+/// Default address of a local `llama-server`
+pub const DEFAULT_LLAMA_CPP_URL: &str = "http://localhost:8080/v1";
+
+/// llama.cpp server adapter
+pub struct LlamaCppAdapter {
+    base_url: String,
+    api_key: Option<String>,
+    stop: Vec<String>,
+    grammar: Option<String>,
+}
+
+impl LlamaCppAdapter {
+    pub fn new(base_url: Option<String>, api_key: Option<String>) -> Self {
+        Self {
+            base_url: base_url.unwrap_or_else(|| DEFAULT_LLAMA_CPP_URL.to_string()),
+            api_key,
+            stop: Vec::new(),
+            grammar: None,
+        }
+    }
+
+    /// Stop sequences sent with every request
+    pub fn with_stop(mut self, stop: Vec<String>) -> Self {
+        self.stop = stop;
+        self
+    }
+
+    /// GBNF grammar constraining every completion
+    pub fn with_grammar(mut self, grammar: impl Into<String>) -> Self {
+        self.grammar = Some(grammar.into());
+        self
+    }
+
+    /// Adapter for `config`, reading `stop` and `grammar` from its options
+    fn from_config(config: &ProviderConfig) -> Self {
+        let mut adapter = Self::new(config.base_url.clone(), config.api_key.clone());
+        if let Some(stop) = config.options.get("stop").and_then(Value::as_array) {
+            adapter.stop = stop
+                .iter()
+                .filter_map(|s| s.as_str().map(String::from))
+                .collect();
+        }
+        adapter.grammar = config
+            .options
+            .get("grammar")
+            .and_then(Value::as_str)
+            .map(String::from);
+        adapter
+    }
+}
+
+impl OpenAICompatible for LlamaCppAdapter {
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn api_key(&self) -> Option<&str> {
+        self.api_key.as_deref()
+    }
+
+    fn request_fields(&self) -> Map<String, Value> {
+        let mut fields = Map::new();
+        if !self.stop.is_empty() {
+            fields.insert("stop".to_string(), Value::from(self.stop.clone()));
+        }
+        if let Some(grammar) = &self.grammar {
+            fields.insert("grammar".to_string(), Value::from(grammar.clone()));
+        }
+        fields
+    }
+}
+
+/// llama.cpp server provider
+pub type LlamaCppProvider = OpenAICompatibleProvider<LlamaCppAdapter>;
+
+impl LlamaCppProvider {
+    /// Provider for `config`; use this over [`Self::create`] to call
+    /// [`Self::generate_with_grammar`]
+    pub fn from_config(config: ProviderConfig) -> Result<Self> {
+        OpenAICompatibleProvider::new(LlamaCppAdapter::from_config(&config), config)
+    }
+
+    pub fn create(config: ProviderConfig) -> Result<Arc<dyn LlmProvider>> {
+        Ok(Arc::new(Self::from_config(config)?))
+    }
+
+    /// Provider for the server at `LLAMA_CPP_URL` (default
+    /// `http://localhost:8080/v1`), with the optional `LLAMA_CPP_API_KEY`
+    pub fn from_env(text_model: String) -> Result<Arc<dyn LlmProvider>> {
+        let config = ProviderConfig {
+            provider: ProviderType::LlamaCpp,
+            name: "llamacpp".to_string(),
+            priority: 10,
+            api_key: std::env::var("LLAMA_CPP_API_KEY").ok(),
+            base_url: Some(
+                std::env::var("LLAMA_CPP_URL").unwrap_or_else(|_| DEFAULT_LLAMA_CPP_URL.into()),
+            ),
+            text_model,
+            embedding_model: None,
+            max_tokens: 2048,
+            temperature: 0.7,
+            timeout: 300,
+            generation_timeout: None,
+            stream_idle_timeout: None,
+            context_window: None,
+            context_overflow: Default::default(),
+            log_bodies: false,
+            log_body_max_len: 2048,
+            seed: None,
+            http: Default::default(),
+            options: Value::Null,
+        };
+
+        Self::create(config)
+    }
+
+    /// Generate a completion constrained by the GBNF `grammar`, overriding any
+    /// grammar configured for the provider
+    pub async fn generate_with_grammar(
+        &self,
+        messages: &[Message],
+        grammar: &str,
+    ) -> Result<GenerationResponse> {
+        let mut fields = Map::new();
+        fields.insert("grammar".to_string(), Value::from(grammar));
+        self.generate_with_fields(messages, fields).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::user_message;
+    use axum::{routing::post, Json, Router};
+    use std::sync::Mutex;
+
+    #[tokio::test]
+    async fn test_completion_and_grammar_requests() {
+        let requests = Arc::new(Mutex::new(Vec::<Value>::new()));
+        let captured = requests.clone();
+        let app = Router::new().route(
+            "/v1/chat/completions",
+            post(move |Json(body): Json<Value>| {
+                let captured = captured.clone();
+                async move {
+                    captured.lock().unwrap().push(body);
+                    Json(serde_json::json!({
+                        "id": "chatcmpl-1",
+                        "model": "qwen2.5-7b-instruct-q4_k_m.gguf",
+                        "choices": [{
+                            "index": 0,
+                            "message": { "role": "assistant", "content": "yes" },
+                            "finish_reason": "stop"
+                        }],
+                        "usage": { "prompt_tokens": 12, "completion_tokens": 1, "total_tokens": 13 }
+                    }))
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let config = ProviderConfig {
+            provider: ProviderType::LlamaCpp,
+            name: "llamacpp".to_string(),
+            priority: 1,
+            api_key: None,
+            base_url: Some(format!("http://{}/v1", addr)),
+            text_model: "local".to_string(),
+            embedding_model: None,
+            max_tokens: 64,
+            temperature: 0.0,
+            timeout: 10,
+            generation_timeout: None,
+            stream_idle_timeout: None,
+            context_window: None,
+            context_overflow: Default::default(),
+            log_bodies: false,
+            log_body_max_len: 2048,
+            seed: None,
+            http: Default::default(),
+            options: serde_json::json!({ "stop": ["</s>", "\nUser:"] }),
+        };
+        let provider = LlamaCppProvider::from_config(config).unwrap();
+        let messages = [user_message("Is the arm calibrated?")];
+
+        let response = provider.generate(&messages).await.unwrap();
+        assert_eq!(response.text, "yes");
+        assert_eq!(response.finish_reason.as_deref(), Some("stop"));
+        assert_eq!(response.tokens_used, Some(13));
+
+        let grammar = r#"root ::= "yes" | "no""#;
+        provider
+            .generate_with_grammar(&messages, grammar)
+            .await
+            .unwrap();
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        let plain = &requests[0];
+        assert_eq!(plain["model"], "local");
+        assert_eq!(plain["stream"], false);
+        assert_eq!(plain["max_tokens"], 64);
+        assert_eq!(plain["messages"][0]["content"], "Is the arm calibrated?");
+        assert_eq!(plain["stop"], serde_json::json!(["</s>", "\nUser:"]));
+        assert!(plain.get("grammar").is_none());
+
+        let constrained = &requests[1];
+        assert_eq!(constrained["grammar"], grammar);
+        assert_eq!(constrained["stop"], plain["stop"]);
+    }
+}
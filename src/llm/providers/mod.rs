@@ -5,6 +5,7 @@
 pub mod anthropic;
 pub mod base;
 pub mod google;
+pub mod llama_cpp;
 pub mod openai;
 pub mod openai_compatible;
 pub mod openai_variants;
@@ -13,5 +14,6 @@ pub mod openai_variants;
 pub use anthropic::AnthropicProvider;
 pub use base::{HttpProviderClient, OpenAICompatible};
 pub use google::GoogleProvider;
+pub use llama_cpp::LlamaCppProvider;
 pub use openai::OpenAIProvider;
 pub use openai_variants::{AzureOpenAIProvider, GroqProvider, TogetherProvider};
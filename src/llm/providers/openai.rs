@@ -1,5 +1,6 @@
 //! OpenAI provider implementation
 
+use crate::error::Result;
 use crate::llm::provider::{LlmProvider, ProviderConfig, ProviderType};
 use crate::llm::providers::base::OpenAICompatible;
 use crate::llm::providers::openai_compatible::OpenAICompatibleProvider;
@@ -35,9 +36,9 @@ pub type OpenAIProvider = OpenAICompatibleProvider<OpenAIAdapter>;
 
 impl OpenAIProvider {
     /// Create a new OpenAI provider
-    pub fn create(config: ProviderConfig) -> Arc<dyn LlmProvider> {
+    pub fn create(config: ProviderConfig) -> Result<Arc<dyn LlmProvider>> {
         let adapter = OpenAIAdapter::new(config.api_key.clone(), config.base_url.clone());
-        Arc::new(OpenAICompatibleProvider::new(adapter, config))
+        Ok(Arc::new(OpenAICompatibleProvider::new(adapter, config)?))
     }
 
     /// Create from environment variable
@@ -59,10 +60,18 @@ impl OpenAIProvider {
             max_tokens: 4096,
             temperature: 0.7,
             timeout: 120,
+            generation_timeout: None,
+            stream_idle_timeout: None,
+            context_window: None,
+            context_overflow: Default::default(),
+            log_bodies: false,
+            log_body_max_len: 2048,
+            seed: None,
+            http: Default::default(),
             options: serde_json::Value::Null,
         };
 
-        Ok(Self::create(config))
+        Self::create(config).map_err(|e| e.to_string())
     }
 }
 
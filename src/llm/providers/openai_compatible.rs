@@ -5,15 +5,21 @@
 //! - Azure OpenAI
 //! - Groq
 //! - Together AI
+//! - llama.cpp server
 //! - And others
 
 use crate::error::{LlmError, Result};
+use crate::llm::pricing::TokenUsage;
 use crate::llm::provider::{LlmProvider, ProviderConfig, ProviderStats, ProviderType};
 use crate::llm::providers::base::{HttpProviderClient, OpenAICompatible};
+use crate::llm::stream::{SseDecoder, StreamEvent};
 use crate::llm::{EmbeddingResponse, GenerationResponse, Message, Role};
+use crate::mcp::{McpTool, ToolCall};
 use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
 use serde::{Deserialize, Serialize};
-use tracing::{debug, info};
+use std::collections::BTreeMap;
+use tracing::{debug, info, warn};
 
 /// OpenAI-compatible chat completion request
 #[derive(Debug, Serialize)]
@@ -26,6 +32,39 @@ pub struct ChatCompletionRequest {
     pub temperature: Option<f32>,
     #[serde(default)]
     pub stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<OpenAITool>>,
+    /// Provider-specific fields sent alongside the standard ones
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Function tool definition offered to the model
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenAITool {
+    #[serde(rename = "type")]
+    pub tool_type: String,
+    pub function: OpenAIFunction,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenAIFunction {
+    pub name: String,
+    pub description: String,
+    pub parameters: serde_json::Value,
+}
+
+impl From<&McpTool> for OpenAITool {
+    fn from(tool: &McpTool) -> Self {
+        Self {
+            tool_type: "function".to_string(),
+            function: OpenAIFunction {
+                name: tool.name.clone(),
+                description: tool.description.clone(),
+                parameters: tool.input_schema.clone(),
+            },
+        }
+    }
 }
 
 /// OpenAI message format
@@ -33,6 +72,41 @@ pub struct ChatCompletionRequest {
 pub struct OpenAIMessage {
     pub role: String,
     pub content: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<OpenAIToolCall>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+    /// Reasoning returned by reasoning models alongside the answer
+    #[serde(default, alias = "reasoning_content", skip_serializing)]
+    pub reasoning: Option<String>,
+}
+
+/// Tool call made by an assistant message, with JSON-encoded arguments
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenAIToolCall {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub call_type: String,
+    pub function: OpenAIFunctionCall,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenAIFunctionCall {
+    pub name: String,
+    pub arguments: String,
+}
+
+impl From<&ToolCall> for OpenAIToolCall {
+    fn from(call: &ToolCall) -> Self {
+        Self {
+            id: call.id.clone(),
+            call_type: "function".to_string(),
+            function: OpenAIFunctionCall {
+                name: call.name.clone(),
+                arguments: call.arguments.to_string(),
+            },
+        }
+    }
 }
 
 impl From<&Message> for OpenAIMessage {
@@ -42,8 +116,13 @@ impl From<&Message> for OpenAIMessage {
                 Role::System => "system".to_string(),
                 Role::User => "user".to_string(),
                 Role::Assistant => "assistant".to_string(),
+                Role::Tool => "tool".to_string(),
             },
             content: msg.content.clone(),
+            tool_calls: (!msg.tool_calls.is_empty())
+                .then(|| msg.tool_calls.iter().map(OpenAIToolCall::from).collect()),
+            tool_call_id: msg.tool_call_id.clone(),
+            reasoning: None,
         }
     }
 }
@@ -56,6 +135,8 @@ pub struct ChatCompletionResponse {
     pub choices: Vec<ChatChoice>,
     #[serde(default)]
     pub usage: Option<UsageInfo>,
+    #[serde(default)]
+    pub system_fingerprint: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -72,6 +153,154 @@ pub struct UsageInfo {
     pub total_tokens: u32,
 }
 
+/// One server-sent chunk of a streaming chat completion
+#[derive(Debug, Deserialize)]
+pub struct ChatCompletionChunk {
+    #[serde(default)]
+    pub choices: Vec<ChunkChoice>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChunkChoice {
+    #[serde(default)]
+    pub index: u32,
+    #[serde(default)]
+    pub delta: ChunkDelta,
+    #[serde(default)]
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ChunkDelta {
+    #[serde(default)]
+    pub content: Option<String>,
+    #[serde(default, alias = "reasoning_content")]
+    pub reasoning: Option<String>,
+    #[serde(default)]
+    pub tool_calls: Option<Vec<ToolCallDelta>>,
+}
+
+/// Fragment of a tool call. The first fragment for an `index` carries the id
+/// and name; later ones append to the arguments string.
+#[derive(Debug, Deserialize)]
+pub struct ToolCallDelta {
+    pub index: u32,
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub function: Option<FunctionDelta>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct FunctionDelta {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub arguments: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct PartialToolCall {
+    id: String,
+    name: String,
+    arguments: String,
+    emitted: bool,
+}
+
+/// Assembles streamed tool-call fragments, keyed by their index, into complete
+/// [`ToolCall`]s
+#[derive(Debug, Default)]
+pub struct ToolCallAccumulator {
+    calls: BTreeMap<u32, PartialToolCall>,
+}
+
+impl ToolCallAccumulator {
+    /// Apply a fragment, returning the call if its arguments are now a complete
+    /// JSON object
+    pub fn push(&mut self, delta: &ToolCallDelta) -> Option<ToolCall> {
+        let call = self.calls.entry(delta.index).or_default();
+        if let Some(id) = &delta.id {
+            call.id.clone_from(id);
+        }
+        if let Some(function) = &delta.function {
+            if let Some(name) = &function.name {
+                call.name.push_str(name);
+            }
+            if let Some(arguments) = &function.arguments {
+                call.arguments.push_str(arguments);
+            }
+        }
+
+        if call.emitted || call.name.is_empty() {
+            return None;
+        }
+        match serde_json::from_str::<serde_json::Value>(&call.arguments) {
+            Ok(arguments @ serde_json::Value::Object(_)) => {
+                call.emitted = true;
+                Some(ToolCall {
+                    id: call.id.clone(),
+                    name: call.name.clone(),
+                    arguments,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Calls not yet emitted once the stream has finished. Calls without
+    /// arguments get an empty object; truncated arguments are an error.
+    pub fn finish(&mut self) -> Result<Vec<ToolCall>> {
+        let mut finished = Vec::new();
+        for call in self.calls.values_mut().filter(|call| !call.emitted) {
+            let arguments = if call.arguments.trim().is_empty() {
+                serde_json::json!({})
+            } else {
+                serde_json::from_str(&call.arguments).map_err(|e| {
+                    LlmError::InvalidResponse(format!(
+                        "Incomplete arguments for tool call '{}': {}",
+                        call.name, e
+                    ))
+                })?
+            };
+            call.emitted = true;
+            finished.push(ToolCall {
+                id: call.id.clone(),
+                name: call.name.clone(),
+                arguments,
+            });
+        }
+        Ok(finished)
+    }
+
+    /// Events for one chunk of the first choice
+    pub fn events_for_chunk(&mut self, chunk: ChatCompletionChunk) -> Result<Vec<StreamEvent>> {
+        let mut events = Vec::new();
+        let Some(choice) = chunk.choices.into_iter().find(|choice| choice.index == 0) else {
+            return Ok(events);
+        };
+
+        if let Some(reasoning) = choice.delta.reasoning.filter(|r| !r.is_empty()) {
+            events.push(StreamEvent::Reasoning(reasoning));
+        }
+        if let Some(content) = choice.delta.content.filter(|c| !c.is_empty()) {
+            events.push(StreamEvent::TextDelta(content));
+        }
+        for delta in choice.delta.tool_calls.iter().flatten() {
+            if let Some(call) = self.push(delta) {
+                events.push(StreamEvent::ToolCall(call));
+            }
+        }
+        if let Some(finish_reason) = choice.finish_reason {
+            events.extend(self.finish()?.into_iter().map(StreamEvent::ToolCall));
+            events.push(StreamEvent::Done {
+                finish_reason: Some(finish_reason),
+                usage: None,
+            });
+        }
+        Ok(events)
+    }
+}
+
 /// OpenAI embedding request
 #[derive(Debug, Serialize)]
 pub struct EmbeddingRequest {
@@ -117,15 +346,15 @@ pub struct OpenAICompatibleProvider<T: OpenAICompatible + Send + Sync> {
 
 impl<T: OpenAICompatible + Send + Sync> OpenAICompatibleProvider<T> {
     /// Create a new OpenAI-compatible provider
-    pub fn new(adapter: T, config: ProviderConfig) -> Self {
-        let client = HttpProviderClient::new(config.timeout);
+    pub fn new(adapter: T, config: ProviderConfig) -> Result<Self> {
+        let client = HttpProviderClient::from_config(&config)?;
 
-        Self {
+        Ok(Self {
             adapter,
             client,
             config,
             stats: ProviderStats::default(),
-        }
+        })
     }
 
     /// Build headers for requests
@@ -137,32 +366,106 @@ impl<T: OpenAICompatible + Send + Sync> OpenAICompatibleProvider<T> {
 
         headers
     }
-}
 
-#[async_trait]
-impl<T: OpenAICompatible + Send + Sync> LlmProvider for OpenAICompatibleProvider<T> {
-    fn provider_type(&self) -> ProviderType {
-        self.config.provider
+    /// Build a non-streaming chat completion request. The configured seed is
+    /// sent as a `seed` field, which per-request `fields` may override.
+    fn chat_request(
+        &self,
+        messages: &[Message],
+        fields: serde_json::Map<String, serde_json::Value>,
+    ) -> ChatCompletionRequest {
+        let mut extra = self.adapter.request_fields();
+        if let Some(seed) = self.config.seed {
+            extra.insert("seed".to_string(), seed.into());
+        }
+        extra.extend(fields);
+        ChatCompletionRequest {
+            model: self.config.text_model.clone(),
+            messages: messages.iter().map(OpenAIMessage::from).collect(),
+            max_tokens: Some(self.config.max_tokens),
+            temperature: Some(self.config.temperature),
+            stream: false,
+            tools: None,
+            extra,
+        }
     }
 
-    fn name(&self) -> &str {
-        &self.config.name
+    /// Stream a chat completion, offering `tools` to the model. Text arrives as
+    /// it is generated; each tool call is emitted once its arguments are complete.
+    pub async fn generate_stream(
+        &self,
+        messages: &[Message],
+        tools: &[McpTool],
+    ) -> Result<BoxStream<'static, Result<StreamEvent>>> {
+        debug!(
+            "Streaming with {} using {} messages and {} tools",
+            self.name(),
+            messages.len(),
+            tools.len()
+        );
+
+        let messages = self.client.fit_context(messages)?;
+        let mut request = self.chat_request(&messages, serde_json::Map::new());
+        request.stream = true;
+        request.tools = (!tools.is_empty()).then(|| tools.iter().map(OpenAITool::from).collect());
+
+        let url = self.adapter.build_url("chat/completions");
+        let headers = self.build_headers();
+        let borrowed_headers: Vec<(&str, &str)> =
+            headers.iter().map(|(k, v)| (*k, v.as_str())).collect();
+
+        let response = self
+            .client
+            .post_stream(&url, &request, borrowed_headers)
+            .await?;
+
+        let mut decoder = SseDecoder::default();
+        let mut accumulator = ToolCallAccumulator::default();
+        let events = response
+            .bytes_stream()
+            .map(move |bytes| {
+                let bytes = match bytes {
+                    Ok(bytes) => bytes,
+                    Err(e) => return vec![Err(LlmError::ConnectionFailed(e.to_string()).into())],
+                };
+                decoder
+                    .push(&bytes)
+                    .into_iter()
+                    .filter(|payload| payload != "[DONE]")
+                    .flat_map(|payload| {
+                        match serde_json::from_str::<ChatCompletionChunk>(&payload) {
+                            Ok(chunk) => match accumulator.events_for_chunk(chunk) {
+                                Ok(events) => events.into_iter().map(Ok).collect(),
+                                Err(e) => vec![Err(e)],
+                            },
+                            Err(e) => {
+                                warn!("Skipping malformed stream chunk: {}", e);
+                                Vec::new()
+                            }
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .flat_map(stream::iter);
+
+        Ok(events.boxed())
     }
 
-    async fn generate(&self, messages: &[Message]) -> Result<GenerationResponse> {
+    /// Generate a completion with `fields` added to the request body, on top
+    /// of the adapter's own [`OpenAICompatible::request_fields`]
+    pub async fn generate_with_fields(
+        &self,
+        messages: &[Message],
+        fields: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<GenerationResponse> {
         debug!(
             "Generating with {} using {} messages",
             self.name(),
             messages.len()
         );
 
-        let request = ChatCompletionRequest {
-            model: self.config.text_model.clone(),
-            messages: messages.iter().map(OpenAIMessage::from).collect(),
-            max_tokens: Some(self.config.max_tokens),
-            temperature: Some(self.config.temperature),
-            stream: false,
-        };
+        let messages = self.client.fit_context(messages)?;
+        let request = self.chat_request(&messages, fields);
 
         let url = self.adapter.build_url("chat/completions");
         let headers = self.build_headers();
@@ -181,7 +484,11 @@ impl<T: OpenAICompatible + Send + Sync> LlmProvider for OpenAICompatibleProvider
         }
 
         let choice = &response.choices[0];
-        let tokens_used = response.usage.map(|u| u.total_tokens);
+        let tokens_used = response.usage.as_ref().map(|u| u.total_tokens);
+        let usage = response
+            .usage
+            .as_ref()
+            .map(|u| TokenUsage::new(u.prompt_tokens, u.completion_tokens));
 
         info!(
             "Generated {} tokens with {}",
@@ -192,10 +499,37 @@ impl<T: OpenAICompatible + Send + Sync> LlmProvider for OpenAICompatibleProvider
         Ok(GenerationResponse {
             text: choice.message.content.clone(),
             tokens_used,
+            usage,
             model: response.model,
             finish_reason: choice.finish_reason.clone(),
+            system_fingerprint: response.system_fingerprint,
+            reasoning: choice.message.reasoning.clone(),
         })
     }
+}
+
+#[async_trait]
+impl<T: OpenAICompatible + Send + Sync> LlmProvider for OpenAICompatibleProvider<T> {
+    fn provider_type(&self) -> ProviderType {
+        self.config.provider
+    }
+
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    async fn generate(&self, messages: &[Message]) -> Result<GenerationResponse> {
+        self.generate_with_fields(messages, serde_json::Map::new())
+            .await
+    }
+
+    async fn generate_stream_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[McpTool],
+    ) -> Result<BoxStream<'static, Result<StreamEvent>>> {
+        self.generate_stream(messages, tools).await
+    }
 
     async fn embed(&self, text: &str) -> Result<EmbeddingResponse> {
         debug!(
@@ -293,6 +627,8 @@ mod tests {
         let msg = Message {
             role: Role::User,
             content: "Hello".to_string(),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
         };
 
         let openai_msg = OpenAIMessage::from(&msg);
@@ -300,6 +636,263 @@ mod tests {
         assert_eq!(openai_msg.content, "Hello");
     }
 
+    #[test]
+    fn test_tool_call_history_conversion() {
+        let call = ToolCall {
+            id: "call-1".to_string(),
+            name: "get_weather".to_string(),
+            arguments: serde_json::json!({ "city": "Paris" }),
+        };
+        let messages = [
+            crate::llm::assistant_tool_calls_message("", vec![call]),
+            crate::llm::tool_message("call-1", "sunny"),
+        ];
+
+        let body: Vec<serde_json::Value> = messages
+            .iter()
+            .map(|msg| serde_json::to_value(OpenAIMessage::from(msg)).unwrap())
+            .collect();
+        assert_eq!(body[0]["role"], "assistant");
+        assert_eq!(body[0]["tool_calls"][0]["id"], "call-1");
+        assert_eq!(body[0]["tool_calls"][0]["type"], "function");
+        assert_eq!(
+            body[0]["tool_calls"][0]["function"]["arguments"],
+            r#"{"city":"Paris"}"#
+        );
+        assert_eq!(body[1]["role"], "tool");
+        assert_eq!(body[1]["tool_call_id"], "call-1");
+        assert!(body[1].get("tool_calls").is_none());
+    }
+
+    fn tool_call_chunk(index: u32, id: Option<&str>, name: Option<&str>, args: &str) -> String {
+        let mut function = serde_json::json!({ "arguments": args });
+        if let Some(name) = name {
+            function["name"] = serde_json::json!(name);
+        }
+        let mut call = serde_json::json!({ "index": index, "function": function });
+        if let Some(id) = id {
+            call["id"] = serde_json::json!(id);
+            call["type"] = serde_json::json!("function");
+        }
+        serde_json::json!({
+            "choices": [{ "index": 0, "delta": { "tool_calls": [call] }, "finish_reason": null }]
+        })
+        .to_string()
+    }
+
+    fn decode_events(payloads: &[String]) -> Vec<StreamEvent> {
+        let mut accumulator = ToolCallAccumulator::default();
+        payloads
+            .iter()
+            .flat_map(|payload| {
+                let chunk: ChatCompletionChunk = serde_json::from_str(payload).unwrap();
+                accumulator.events_for_chunk(chunk).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_reassembles_fragmented_tool_call() {
+        let finish = r#"{"choices":[{"index":0,"delta":{},"finish_reason":"tool_calls"}]}"#;
+        let payloads = vec![
+            tool_call_chunk(0, Some("call_1"), Some("get_weather"), ""),
+            tool_call_chunk(0, None, None, "{\"loca"),
+            tool_call_chunk(0, None, None, "tion\": \"Par"),
+            tool_call_chunk(0, None, None, "is\", \"unit\""),
+            tool_call_chunk(0, None, None, ": \"c\"}"),
+            finish.to_string(),
+        ];
+
+        let events = decode_events(&payloads);
+
+        let calls: Vec<&ToolCall> = events
+            .iter()
+            .filter_map(|event| match event {
+                StreamEvent::ToolCall(call) => Some(call),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].id, "call_1");
+        assert_eq!(calls[0].name, "get_weather");
+        assert_eq!(
+            calls[0].arguments,
+            serde_json::json!({ "location": "Paris", "unit": "c" })
+        );
+        assert!(matches!(
+            events.last(),
+            Some(StreamEvent::Done { finish_reason: Some(reason), .. }) if reason == "tool_calls"
+        ));
+    }
+
+    #[test]
+    fn test_interleaved_tool_calls_complete_independently() {
+        let payloads = vec![
+            tool_call_chunk(0, Some("call_a"), Some("search"), "{\"q\":"),
+            tool_call_chunk(1, Some("call_b"), Some("system_info"), ""),
+            tool_call_chunk(1, None, None, "{}"),
+            tool_call_chunk(0, None, None, " \"rust\"}"),
+        ];
+
+        let events = decode_events(&payloads);
+
+        let names: Vec<&str> = events
+            .iter()
+            .filter_map(|event| match event {
+                StreamEvent::ToolCall(call) => Some(call.name.as_str()),
+                _ => None,
+            })
+            .collect();
+        // Emitted in the order their arguments completed
+        assert_eq!(names, vec!["system_info", "search"]);
+    }
+
+    #[test]
+    fn test_truncated_arguments_fail_on_finish() {
+        let mut accumulator = ToolCallAccumulator::default();
+        let chunk: ChatCompletionChunk = serde_json::from_str(&tool_call_chunk(
+            0,
+            Some("call_1"),
+            Some("search"),
+            "{\"q\"",
+        ))
+        .unwrap();
+        assert!(accumulator.events_for_chunk(chunk).unwrap().is_empty());
+        assert!(accumulator.finish().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_generate_stream_over_sse() {
+        use axum::{routing::post, Router};
+
+        let sse_body: String = [
+            r#"{"choices":[{"index":0,"delta":{"content":"Checking"},"finish_reason":null}]}"#
+                .to_string(),
+            tool_call_chunk(0, Some("call_1"), Some("system_info"), "{\"verbose\""),
+            tool_call_chunk(0, None, None, ": true}"),
+            r#"{"choices":[{"index":0,"delta":{},"finish_reason":"tool_calls"}]}"#.to_string(),
+            "[DONE]".to_string(),
+        ]
+        .iter()
+        .map(|payload| format!("data: {}\n\n", payload))
+        .collect();
+
+        let app = Router::new().route(
+            "/v1/chat/completions",
+            post(move || {
+                let body = sse_body.clone();
+                async move { ([("content-type", "text/event-stream")], body) }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let base_url = format!("http://{}/v1", addr);
+        let adapter = TestAdapter {
+            base_url: base_url.clone(),
+            api_key: Some("test-key".to_string()),
+        };
+        let config = ProviderConfig {
+            provider: ProviderType::OpenAI,
+            name: "test".to_string(),
+            priority: 1,
+            api_key: Some("test-key".to_string()),
+            base_url: Some(base_url),
+            text_model: "gpt-4o".to_string(),
+            embedding_model: None,
+            max_tokens: 256,
+            temperature: 0.0,
+            timeout: 10,
+            generation_timeout: None,
+            stream_idle_timeout: None,
+            context_window: None,
+            context_overflow: Default::default(),
+            log_bodies: false,
+            log_body_max_len: 2048,
+            seed: None,
+            http: Default::default(),
+            options: serde_json::Value::Null,
+        };
+        let provider = OpenAICompatibleProvider::new(adapter, config).unwrap();
+        let tool = McpTool {
+            name: "system_info".to_string(),
+            description: "System information".to_string(),
+            input_schema: serde_json::json!({ "type": "object" }),
+        };
+
+        let events: Vec<StreamEvent> = provider
+            .generate_stream(&[crate::llm::user_message("status?")], &[tool])
+            .await
+            .unwrap()
+            .map(|event| event.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(events.len(), 3);
+        assert!(matches!(&events[0], StreamEvent::TextDelta(text) if text == "Checking"));
+        match &events[1] {
+            StreamEvent::ToolCall(call) => {
+                assert_eq!(call.name, "system_info");
+                assert_eq!(call.arguments, serde_json::json!({ "verbose": true }));
+            }
+            other => panic!("expected tool call, got {:?}", other),
+        }
+        assert!(matches!(&events[2], StreamEvent::Done { .. }));
+    }
+
+    #[test]
+    fn test_chat_request_includes_seed() {
+        let adapter = TestAdapter {
+            base_url: "https://api.example.com/v1".to_string(),
+            api_key: None,
+        };
+        let config = ProviderConfig {
+            provider: ProviderType::OpenAI,
+            name: "test".to_string(),
+            priority: 1,
+            api_key: None,
+            base_url: None,
+            text_model: "gpt-4o".to_string(),
+            embedding_model: None,
+            max_tokens: 256,
+            temperature: 0.0,
+            timeout: 10,
+            generation_timeout: None,
+            stream_idle_timeout: None,
+            context_window: None,
+            context_overflow: Default::default(),
+            log_bodies: false,
+            log_body_max_len: 2048,
+            seed: Some(42),
+            http: Default::default(),
+            options: serde_json::Value::Null,
+        };
+        let provider = OpenAICompatibleProvider::new(adapter, config).unwrap();
+        let messages = [crate::llm::user_message("hi")];
+
+        let body = serde_json::to_value(provider.chat_request(&messages, Default::default()));
+        assert_eq!(body.unwrap()["seed"], 42);
+
+        let mut fields = serde_json::Map::new();
+        fields.insert("seed".to_string(), 7.into());
+        let body = serde_json::to_value(provider.chat_request(&messages, fields)).unwrap();
+        assert_eq!(body["seed"], 7);
+    }
+
+    #[test]
+    fn test_response_surfaces_system_fingerprint() {
+        let response: ChatCompletionResponse = serde_json::from_str(
+            r#"{"id":"chatcmpl-1","model":"gpt-4o","system_fingerprint":"fp_44709d6fcb",
+                "choices":[{"index":0,"message":{"role":"assistant","content":"hi"},"finish_reason":"stop"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            response.system_fingerprint.as_deref(),
+            Some("fp_44709d6fcb")
+        );
+    }
+
     #[test]
     fn test_provider_creation() {
         let adapter = TestAdapter {
@@ -318,9 +911,17 @@ mod tests {
             max_tokens: 4096,
             temperature: 0.7,
             timeout: 60,
+            generation_timeout: None,
+            stream_idle_timeout: None,
+            context_window: None,
+            context_overflow: Default::default(),
+            log_bodies: false,
+            log_body_max_len: 2048,
+            seed: None,
+            http: Default::default(),
             options: serde_json::Value::Null,
         };
 
-        let _provider = OpenAICompatibleProvider::new(adapter, config);
+        let _provider = OpenAICompatibleProvider::new(adapter, config).unwrap();
     }
 }
@@ -1,5 +1,6 @@
 //! OpenAI-compatible provider variants (Groq, Together AI, Azure OpenAI)
 
+use crate::error::Result;
 use crate::llm::provider::{LlmProvider, ProviderConfig, ProviderType};
 use crate::llm::providers::base::OpenAICompatible;
 use crate::llm::providers::openai_compatible::OpenAICompatibleProvider;
@@ -34,9 +35,9 @@ impl OpenAICompatible for GroqAdapter {
 pub type GroqProvider = OpenAICompatibleProvider<GroqAdapter>;
 
 impl GroqProvider {
-    pub fn create(config: ProviderConfig) -> Arc<dyn LlmProvider> {
+    pub fn create(config: ProviderConfig) -> Result<Arc<dyn LlmProvider>> {
         let adapter = GroqAdapter::new(config.api_key.clone());
-        Arc::new(OpenAICompatibleProvider::new(adapter, config))
+        Ok(Arc::new(OpenAICompatibleProvider::new(adapter, config)?))
     }
 
     pub fn from_env(text_model: String) -> std::result::Result<Arc<dyn LlmProvider>, String> {
@@ -54,10 +55,18 @@ impl GroqProvider {
             max_tokens: 8192,
             temperature: 0.7,
             timeout: 60,
+            generation_timeout: None,
+            stream_idle_timeout: None,
+            context_window: None,
+            context_overflow: Default::default(),
+            log_bodies: false,
+            log_body_max_len: 2048,
+            seed: None,
+            http: Default::default(),
             options: serde_json::Value::Null,
         };
 
-        Ok(Self::create(config))
+        Self::create(config).map_err(|e| e.to_string())
     }
 }
 
@@ -90,9 +99,9 @@ impl OpenAICompatible for TogetherAdapter {
 pub type TogetherProvider = OpenAICompatibleProvider<TogetherAdapter>;
 
 impl TogetherProvider {
-    pub fn create(config: ProviderConfig) -> Arc<dyn LlmProvider> {
+    pub fn create(config: ProviderConfig) -> Result<Arc<dyn LlmProvider>> {
         let adapter = TogetherAdapter::new(config.api_key.clone());
-        Arc::new(OpenAICompatibleProvider::new(adapter, config))
+        Ok(Arc::new(OpenAICompatibleProvider::new(adapter, config)?))
     }
 
     pub fn from_env(
@@ -113,10 +122,18 @@ impl TogetherProvider {
             max_tokens: 4096,
             temperature: 0.7,
             timeout: 120,
+            generation_timeout: None,
+            stream_idle_timeout: None,
+            context_window: None,
+            context_overflow: Default::default(),
+            log_bodies: false,
+            log_body_max_len: 2048,
+            seed: None,
+            http: Default::default(),
             options: serde_json::Value::Null,
         };
 
-        Ok(Self::create(config))
+        Self::create(config).map_err(|e| e.to_string())
     }
 }
 
@@ -190,7 +207,7 @@ impl OpenAICompatible for AzureOpenAIAdapter {
 pub type AzureOpenAIProvider = OpenAICompatibleProvider<AzureOpenAIAdapter>;
 
 impl AzureOpenAIProvider {
-    pub fn create(config: ProviderConfig) -> Arc<dyn LlmProvider> {
+    pub fn create(config: ProviderConfig) -> Result<Arc<dyn LlmProvider>> {
         // Extract deployment name from options or use model name as fallback
         let deployment_name = config
             .options
@@ -217,7 +234,7 @@ impl AzureOpenAIProvider {
             api_version,
         );
 
-        Arc::new(OpenAICompatibleProvider::new(adapter, config))
+        Ok(Arc::new(OpenAICompatibleProvider::new(adapter, config)?))
     }
 
     pub fn from_env(
@@ -245,10 +262,18 @@ impl AzureOpenAIProvider {
             max_tokens: 4096,
             temperature: 0.7,
             timeout: 120,
+            generation_timeout: None,
+            stream_idle_timeout: None,
+            context_window: None,
+            context_overflow: Default::default(),
+            log_bodies: false,
+            log_body_max_len: 2048,
+            seed: None,
+            http: Default::default(),
             options: serde_json::Value::Object(options),
         };
 
-        Ok(Self::create(config))
+        Self::create(config).map_err(|e| e.to_string())
     }
 }
 
@@ -0,0 +1,74 @@
+//! Retry budget shared across the calls of one request
+//!
+//! Retrying each LLM call independently lets a request that makes many calls
+//! multiply its retries. A [`RetryBudget`] caps the retries made by all calls
+//! inside [`RetryBudget::scope`]: the provider manager takes one from the
+//! budget before every retry, and once none are left a failing call returns
+//! its error after the first attempt. Calls outside any scope retry as
+//! configured.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+tokio::task_local! {
+    static RETRY_BUDGET: RetryBudget;
+}
+
+/// Total retries allowed across the calls of one request. Clones share the
+/// same count.
+#[derive(Debug, Clone)]
+pub struct RetryBudget {
+    max_retries: usize,
+    used: Arc<AtomicUsize>,
+}
+
+impl RetryBudget {
+    pub fn new(max_retries: usize) -> Self {
+        Self {
+            max_retries,
+            used: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// The budget of the scope the current task is running in, if any
+    pub fn current() -> Option<Self> {
+        RETRY_BUDGET.try_with(Clone::clone).ok()
+    }
+
+    /// Run `future` with this as the current budget
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        RETRY_BUDGET.scope(self, future).await
+    }
+
+    /// Take one retry from the budget, returning false if none are left
+    pub fn try_consume(&self) -> bool {
+        self.used
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |used| {
+                (used < self.max_retries).then_some(used + 1)
+            })
+            .is_ok()
+    }
+
+    pub fn max_retries(&self) -> usize {
+        self.max_retries
+    }
+
+    /// Retries taken so far
+    pub fn used(&self) -> usize {
+        self.used.load(Ordering::SeqCst)
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.max_retries.saturating_sub(self.used())
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining() == 0
+    }
+}
+
+/// Whether the current budget, if any, allows another retry
+pub(crate) fn allow_retry() -> bool {
+    RetryBudget::current().is_none_or(|budget| budget.try_consume())
+}
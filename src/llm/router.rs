@@ -0,0 +1,301 @@
+//! Complexity-based model routing
+//!
+//! A [`ModelRouter`] sends each conversation to one of several model tiers,
+//! ordered from smallest to largest, so simple prompts go to a cheap fast
+//! model and complex ones to a larger model. The latest user message is
+//! scored by [`PromptComplexity`] from its length, estimated token count and
+//! the reasoning keywords it contains; the default [`ThresholdPolicy`] picks
+//! the first tier whose `max_complexity` covers the score. Any other
+//! [`RoutingPolicy`] can be plugged in with [`ModelRouter::with_policy`].
+
+use super::context_window::TokenizerFamily;
+use super::stream::StreamEvent;
+use super::tokenizer::{HeuristicTokenizer, Tokenizer};
+use crate::error::{AgentError, Result};
+use crate::llm::{EmbeddingResponse, GenerationResponse, LlmClient, Message, Role};
+use crate::mcp::McpTool;
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use std::sync::Arc;
+use tracing::debug;
+
+/// Phrases that suggest a prompt needs multi-step reasoning
+const REASONING_KEYWORDS: &[&str] = &[
+    "analyze",
+    "analyse",
+    "architecture",
+    "compare",
+    "derive",
+    "design",
+    "evaluate",
+    "explain why",
+    "implications",
+    "optimize",
+    "prove",
+    "step by step",
+    "trade-off",
+    "tradeoff",
+];
+
+/// Characters at which the length component saturates
+const LENGTH_SCALE: f32 = 2000.0;
+/// Tokens at which the token component saturates
+const TOKEN_SCALE: f32 = 500.0;
+/// Keywords at which the keyword component saturates
+const KEYWORD_SCALE: f32 = 3.0;
+
+/// Complexity measurements of a prompt
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PromptComplexity {
+    pub chars: usize,
+    /// Estimated token count
+    pub tokens: usize,
+    /// Distinct reasoning keywords found
+    pub reasoning_keywords: usize,
+    /// Weighted score from 0.0 (trivial) to 1.0 (complex)
+    pub score: f32,
+}
+
+impl PromptComplexity {
+    /// Complexity of `prompt`
+    pub fn of(prompt: &str) -> Self {
+        let chars = prompt.chars().count();
+        let tokens = HeuristicTokenizer::new(TokenizerFamily::Generic).count(prompt);
+        let lower = prompt.to_lowercase();
+        let reasoning_keywords = REASONING_KEYWORDS
+            .iter()
+            .filter(|keyword| lower.contains(*keyword))
+            .count();
+
+        let length = (chars as f32 / LENGTH_SCALE).min(1.0);
+        let token = (tokens as f32 / TOKEN_SCALE).min(1.0);
+        let keyword = (reasoning_keywords as f32 / KEYWORD_SCALE).min(1.0);
+        Self {
+            chars,
+            tokens,
+            reasoning_keywords,
+            score: 0.2 * length + 0.4 * token + 0.4 * keyword,
+        }
+    }
+
+    /// Complexity of the latest user message in `messages`
+    pub fn of_messages(messages: &[Message]) -> Self {
+        let prompt = messages
+            .iter()
+            .rev()
+            .find(|m| m.role == Role::User)
+            .map(|m| m.content.as_str())
+            .unwrap_or_default();
+        Self::of(prompt)
+    }
+}
+
+/// A model the router can send requests to
+#[derive(Clone)]
+pub struct ModelTier {
+    pub model: String,
+    pub client: Arc<dyn LlmClient>,
+    /// Highest complexity score this tier should handle
+    pub max_complexity: f32,
+}
+
+/// Chooses a tier for a prompt
+pub trait RoutingPolicy: Send + Sync {
+    /// Index into `tiers`, which is never empty
+    fn select(&self, complexity: &PromptComplexity, tiers: &[ModelTier]) -> usize;
+}
+
+/// Routes to the first tier whose `max_complexity` is at least the score,
+/// or the last tier when none is
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThresholdPolicy;
+
+impl RoutingPolicy for ThresholdPolicy {
+    fn select(&self, complexity: &PromptComplexity, tiers: &[ModelTier]) -> usize {
+        tiers
+            .iter()
+            .position(|tier| complexity.score <= tier.max_complexity)
+            .unwrap_or(tiers.len() - 1)
+    }
+}
+
+/// The tier chosen for a conversation
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteDecision {
+    pub model: String,
+    pub tier: usize,
+    pub complexity: PromptComplexity,
+}
+
+/// LLM client that picks a model tier per request by prompt complexity
+pub struct ModelRouter {
+    tiers: Vec<ModelTier>,
+    policy: Arc<dyn RoutingPolicy>,
+}
+
+impl Default for ModelRouter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ModelRouter {
+    /// Router with no tiers and the [`ThresholdPolicy`]
+    pub fn new() -> Self {
+        Self {
+            tiers: Vec::new(),
+            policy: Arc::new(ThresholdPolicy),
+        }
+    }
+
+    /// Add a tier larger than those already added
+    pub fn with_tier(
+        mut self,
+        model: impl Into<String>,
+        client: Arc<dyn LlmClient>,
+        max_complexity: f32,
+    ) -> Self {
+        self.tiers.push(ModelTier {
+            model: model.into(),
+            client,
+            max_complexity,
+        });
+        self
+    }
+
+    /// Replace the routing policy
+    pub fn with_policy(mut self, policy: impl RoutingPolicy + 'static) -> Self {
+        self.policy = Arc::new(policy);
+        self
+    }
+
+    pub fn tiers(&self) -> &[ModelTier] {
+        &self.tiers
+    }
+
+    /// Tier that would serve `messages`
+    pub fn route(&self, messages: &[Message]) -> Result<RouteDecision> {
+        self.smallest()?;
+        let complexity = PromptComplexity::of_messages(messages);
+        let tier = self
+            .policy
+            .select(&complexity, &self.tiers)
+            .min(self.tiers.len() - 1);
+        Ok(RouteDecision {
+            model: self.tiers[tier].model.clone(),
+            tier,
+            complexity,
+        })
+    }
+
+    fn smallest(&self) -> Result<&ModelTier> {
+        self.tiers
+            .first()
+            .ok_or_else(|| AgentError::Config("Model router has no tiers configured".to_string()))
+    }
+}
+
+#[async_trait]
+impl LlmClient for ModelRouter {
+    /// Generate with the routed tier. The response's `model` names the tier's
+    /// model when the client does not report one.
+    async fn generate(&self, messages: &[Message]) -> Result<GenerationResponse> {
+        let decision = self.route(messages)?;
+        debug!(
+            "Routing prompt with complexity {:.2} to {}",
+            decision.complexity.score, decision.model
+        );
+        let mut response = self.tiers[decision.tier].client.generate(messages).await?;
+        if response.model.is_empty() {
+            response.model = decision.model;
+        }
+        Ok(response)
+    }
+
+    /// Embeddings always come from the smallest tier
+    async fn embed(&self, text: &str) -> Result<EmbeddingResponse> {
+        self.smallest()?.client.embed(text).await
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>> {
+        Ok(self.tiers.iter().map(|tier| tier.model.clone()).collect())
+    }
+
+    async fn is_model_available(&self, model: &str) -> Result<bool> {
+        Ok(self.tiers.iter().any(|tier| tier.model == model))
+    }
+
+    async fn generate_stream_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[McpTool],
+    ) -> Result<BoxStream<'static, Result<StreamEvent>>> {
+        let decision = self.route(messages)?;
+        self.tiers[decision.tier]
+            .client
+            .generate_stream_with_tools(messages, tools)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::mock::{response, MockLlm};
+    use crate::llm::user_message;
+
+    /// Answers every prompt without naming a model
+    fn echo_llm() -> Arc<MockLlm> {
+        Arc::new(MockLlm::new().with_generate(|messages| {
+            let text = messages.last().map(|m| m.content.clone());
+            Ok(GenerationResponse {
+                model: String::new(),
+                ..response(text.unwrap_or_default())
+            })
+        }))
+    }
+
+    fn router() -> ModelRouter {
+        ModelRouter::new()
+            .with_tier("small-fast", echo_llm(), 0.3)
+            .with_tier("large-smart", echo_llm(), 1.0)
+    }
+
+    #[tokio::test]
+    async fn test_routes_by_prompt_complexity() {
+        let router = router();
+
+        let trivial = [user_message("What is 2 + 2?")];
+        let response = router.generate(&trivial).await.unwrap();
+        assert_eq!(response.model, "small-fast");
+
+        let complex = format!(
+            "Analyze the architecture below and explain why it fails under load. \
+             Compare it step by step with an event-driven design and evaluate the \
+             trade-off in latency and cost. {}",
+            "The gateway fans requests out to twelve services over HTTP. ".repeat(40)
+        );
+        let decision = router.route(&[user_message(complex.clone())]).unwrap();
+        assert!(decision.complexity.reasoning_keywords >= 3);
+        assert!(decision.complexity.score > 0.3);
+        let response = router.generate(&[user_message(complex)]).await.unwrap();
+        assert_eq!(response.model, "large-smart");
+    }
+
+    #[test]
+    fn test_routing_policy_is_overridable() {
+        struct AlwaysLargest;
+
+        impl RoutingPolicy for AlwaysLargest {
+            fn select(&self, _complexity: &PromptComplexity, tiers: &[ModelTier]) -> usize {
+                tiers.len() - 1
+            }
+        }
+
+        let router = router().with_policy(AlwaysLargest);
+        let decision = router.route(&[user_message("hi")]).unwrap();
+        assert_eq!(decision.model, "large-smart");
+
+        assert!(ModelRouter::new().route(&[user_message("hi")]).is_err());
+    }
+}
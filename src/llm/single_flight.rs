@@ -0,0 +1,262 @@
+//! Sharing of identical in-flight generations
+//!
+//! Agents running side by side, as in organization runs, often send the same
+//! prompt at the same moment, and the response cache can't serve them until
+//! the first call has finished. [`SingleFlightLlmClient`] keys each generation
+//! by its server and the response cache key; while a generation with the same key is in
+//! flight, later callers wait for it and receive a clone of its response
+//! rather than calling the provider themselves. The in-flight calls live in a
+//! [`SingleFlight`] registry, and [`SingleFlight::shared`] is the one all
+//! agents use.
+
+use super::stream::StreamEvent;
+use super::{EmbeddingResponse, GenerationResponse, LlmClient, Message};
+use crate::cache::LlmCache;
+use crate::config::LlmConfig;
+use crate::error::Result;
+use crate::mcp::McpTool;
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, LazyLock, Mutex};
+use tokio::sync::watch;
+use tracing::debug;
+
+/// Result of a generation as passed to the callers sharing it
+type SharedResult = Result<GenerationResponse>;
+
+static SHARED: LazyLock<Arc<SingleFlight>> = LazyLock::new(|| Arc::new(SingleFlight::new()));
+
+/// Registry of in-flight generations by key
+#[derive(Default)]
+pub struct SingleFlight {
+    in_flight: Mutex<HashMap<String, watch::Receiver<Option<SharedResult>>>>,
+}
+
+/// A caller's part in a flight
+enum Flight {
+    /// Makes the call and publishes its result
+    Leader(watch::Sender<Option<SharedResult>>),
+    /// Waits for the leader's result
+    Follower(watch::Receiver<Option<SharedResult>>),
+}
+
+/// Removes a flight from the registry when its leader finishes or is dropped
+struct Landing<'a> {
+    flights: &'a SingleFlight,
+    key: &'a str,
+}
+
+impl Drop for Landing<'_> {
+    fn drop(&mut self) {
+        self.flights.in_flight.lock().unwrap().remove(self.key);
+    }
+}
+
+impl SingleFlight {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The process-wide registry
+    pub fn shared() -> Arc<Self> {
+        SHARED.clone()
+    }
+
+    /// Number of generations in flight
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.lock().unwrap().len()
+    }
+
+    /// Result of `call`, or of the call with the same `key` already in flight.
+    /// Callers sharing a failed call get a clone of its error; if the leading
+    /// call is cancelled, they make their own calls.
+    pub async fn run<F>(&self, key: &str, call: F) -> Result<GenerationResponse>
+    where
+        F: Future<Output = Result<GenerationResponse>>,
+    {
+        let flight = {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            match in_flight.get(key) {
+                Some(receiver) => Flight::Follower(receiver.clone()),
+                None => {
+                    let (sender, receiver) = watch::channel(None);
+                    in_flight.insert(key.to_string(), receiver);
+                    Flight::Leader(sender)
+                }
+            }
+        };
+
+        match flight {
+            Flight::Leader(sender) => {
+                let _landing = Landing { flights: self, key };
+                let result = call.await;
+                sender.send_replace(Some(result.clone()));
+                result
+            }
+            Flight::Follower(mut receiver) => {
+                debug!("Sharing an identical in-flight generation");
+                let shared = receiver
+                    .wait_for(Option::is_some)
+                    .await
+                    .ok()
+                    .and_then(|result| result.clone());
+                match shared {
+                    Some(result) => result,
+                    None => call.await,
+                }
+            }
+        }
+    }
+}
+
+/// Client that shares identical concurrent generations of another client
+pub struct SingleFlightLlmClient {
+    inner: Arc<dyn LlmClient>,
+    flights: Arc<SingleFlight>,
+    server: String,
+    model: String,
+    temperature: f32,
+    max_tokens: u32,
+}
+
+impl SingleFlightLlmClient {
+    /// Share the generations `inner` makes with the settings in `config`
+    /// through `flights`
+    pub fn new(inner: Arc<dyn LlmClient>, flights: Arc<SingleFlight>, config: &LlmConfig) -> Self {
+        Self {
+            inner,
+            flights,
+            server: config.ollama_url.clone(),
+            model: config.text_model.clone(),
+            temperature: config.temperature,
+            max_tokens: config.max_tokens,
+        }
+    }
+
+    /// `inner` sharing generations through [`SingleFlight::shared`], or
+    /// `inner` itself when `single_flight` is off in `config`
+    pub fn wrap(inner: Arc<dyn LlmClient>, config: &LlmConfig) -> Arc<dyn LlmClient> {
+        if !config.single_flight {
+            return inner;
+        }
+        Arc::new(Self::new(inner, SingleFlight::shared(), config))
+    }
+}
+
+#[async_trait]
+impl LlmClient for SingleFlightLlmClient {
+    async fn generate(&self, messages: &[Message]) -> Result<GenerationResponse> {
+        let cache_key = LlmCache::compute_messages_cache_key(
+            messages,
+            &self.model,
+            self.temperature,
+            self.max_tokens,
+        );
+        let key = format!("{} {}", self.server, cache_key);
+        self.flights.run(&key, self.inner.generate(messages)).await
+    }
+
+    async fn embed(&self, text: &str) -> Result<EmbeddingResponse> {
+        self.inner.embed(text).await
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>> {
+        self.inner.list_models().await
+    }
+
+    async fn is_model_available(&self, model: &str) -> Result<bool> {
+        self.inner.is_model_available(model).await
+    }
+
+    async fn generate_stream(
+        &self,
+        messages: &[Message],
+    ) -> Result<BoxStream<'static, Result<StreamEvent>>> {
+        self.inner.generate_stream(messages).await
+    }
+
+    async fn generate_stream_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[McpTool],
+    ) -> Result<BoxStream<'static, Result<StreamEvent>>> {
+        self.inner.generate_stream_with_tools(messages, tools).await
+    }
+
+    async fn preload_model(&self) -> Result<()> {
+        self.inner.preload_model().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{AgentError, LlmError};
+    use crate::llm::mock::{response, MockLlm};
+    use crate::llm::user_message;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// Answers slowly, numbering its answers
+    fn slow_llm() -> Arc<MockLlm> {
+        let calls = AtomicUsize::new(0);
+        Arc::new(
+            MockLlm::new()
+                .with_delay(Duration::from_millis(50))
+                .with_generate(move |messages| {
+                    let call = calls.fetch_add(1, Ordering::SeqCst);
+                    Ok(response(format!(
+                        "Answer {} to {}",
+                        call, messages[0].content
+                    )))
+                }),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_identical_concurrent_requests_share_one_call() {
+        let provider = slow_llm();
+        let flights = Arc::new(SingleFlight::new());
+        let client =
+            SingleFlightLlmClient::new(provider.clone(), flights.clone(), &LlmConfig::default());
+        let messages = [user_message("Summarize the quarterly plan")];
+
+        let responses = futures::future::join_all((0..5).map(|_| client.generate(&messages))).await;
+
+        assert_eq!(provider.generations(), 1);
+        let texts: Vec<String> = responses.into_iter().map(|r| r.unwrap().text).collect();
+        assert_eq!(texts, vec!["Answer 0 to Summarize the quarterly plan"; 5]);
+        assert_eq!(flights.in_flight(), 0);
+
+        // Once the call has landed, the next request makes its own
+        client.generate(&messages).await.unwrap();
+        assert_eq!(provider.generations(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_callers_sharing_a_failed_call_get_the_same_error() {
+        let provider = Arc::new(
+            MockLlm::new()
+                .with_delay(Duration::from_millis(50))
+                .with_generate(|_| Err(LlmError::RateLimited.into())),
+        );
+        let client = SingleFlightLlmClient::new(
+            provider.clone(),
+            Arc::new(SingleFlight::new()),
+            &LlmConfig::default(),
+        );
+        let messages = [user_message("Summarize the quarterly plan")];
+
+        let responses = futures::future::join_all((0..3).map(|_| client.generate(&messages))).await;
+
+        assert_eq!(provider.generations(), 1);
+        for response in responses {
+            assert!(matches!(
+                response,
+                Err(AgentError::Llm(LlmError::RateLimited))
+            ));
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! Streaming generation events
+//!
+//! Providers that stream their output emit a sequence of [`StreamEvent`]s:
+//! text and reasoning as they arrive, tool calls once their arguments are
+//! complete, and a final `Done`. [`SseDecoder`] splits a server-sent events
+//! byte stream into the `data:` payloads providers send, and
+//! [`NdjsonDecoder`] splits a newline-delimited JSON body into its lines.
+
+use super::pricing::TokenUsage;
+use super::GenerationResponse;
+use crate::mcp::ToolCall;
+
+/// Event emitted while streaming a generation
+#[derive(Debug, Clone)]
+pub enum StreamEvent {
+    /// Text content as it is generated
+    TextDelta(String),
+    /// Reasoning the model streams separately from its answer
+    Reasoning(String),
+    /// A tool call whose arguments have been fully received
+    ToolCall(ToolCall),
+    /// The model finished generating, with the tokens it used when the
+    /// provider reports them
+    Done {
+        finish_reason: Option<String>,
+        usage: Option<TokenUsage>,
+    },
+}
+
+impl StreamEvent {
+    /// Events replaying a complete response, for clients that cannot stream
+    pub fn from_response(response: GenerationResponse) -> Vec<StreamEvent> {
+        let mut events = Vec::with_capacity(3);
+        if let Some(reasoning) = response.reasoning {
+            events.push(StreamEvent::Reasoning(reasoning));
+        }
+        events.push(StreamEvent::TextDelta(response.text));
+        events.push(StreamEvent::Done {
+            finish_reason: response.finish_reason,
+            usage: response.usage,
+        });
+        events
+    }
+}
+
+/// Incremental decoder for `text/event-stream` bodies
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    buffer: Vec<u8>,
+}
+
+impl SseDecoder {
+    /// Feed a chunk of the body and return the `data:` payloads of every line
+    /// completed by it. Chunks may split lines, or UTF-8 characters, anywhere.
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(bytes);
+
+        let mut payloads = Vec::new();
+        while let Some(newline) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=newline).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\r', '\n']);
+            if let Some(data) = line.strip_prefix("data:") {
+                payloads.push(data.trim_start().to_string());
+            }
+        }
+        payloads
+    }
+}
+
+/// Incremental decoder for newline-delimited JSON bodies
+#[derive(Debug, Default)]
+pub struct NdjsonDecoder {
+    buffer: Vec<u8>,
+}
+
+impl NdjsonDecoder {
+    /// Feed a chunk of the body and return every non-empty line completed by
+    /// it. Chunks may split lines, or UTF-8 characters, anywhere.
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(bytes);
+
+        let mut lines = Vec::new();
+        while let Some(newline) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=newline).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim();
+            if !line.is_empty() {
+                lines.push(line.to_string());
+            }
+        }
+        lines
+    }
+
+    /// The last line, if the body did not end with a newline
+    pub fn finish(&mut self) -> Option<String> {
+        let line = String::from_utf8_lossy(&std::mem::take(&mut self.buffer))
+            .trim()
+            .to_string();
+        (!line.is_empty()).then_some(line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ndjson_decoder_handles_split_lines() {
+        let mut decoder = NdjsonDecoder::default();
+        assert!(decoder.push(b"{\"a\"").is_empty());
+        assert_eq!(
+            decoder.push(b":1}\n\n{\"b\":2}\r\n{\"c\""),
+            vec!["{\"a\":1}", "{\"b\":2}"]
+        );
+        assert_eq!(decoder.push(b":3}").len(), 0);
+        assert_eq!(decoder.finish().as_deref(), Some("{\"c\":3}"));
+        assert_eq!(decoder.finish(), None);
+    }
+
+    #[test]
+    fn test_sse_decoder_handles_split_lines() {
+        let mut decoder = SseDecoder::default();
+        assert!(decoder.push(b"data: {\"a\"").is_empty());
+        assert_eq!(decoder.push(b":1}\n\n: keep-alive\r\n"), vec!["{\"a\":1}"]);
+
+        let payloads = decoder.push("data: caf\u{e9}\ndata: [DONE]\n".as_bytes());
+        assert_eq!(payloads, vec!["café", "[DONE]"]);
+    }
+}
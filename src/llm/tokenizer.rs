@@ -0,0 +1,191 @@
+//! Token counting and truncation
+//!
+//! A [`Tokenizer`] counts the tokens in a piece of text and cuts text down to
+//! a token budget. OpenAI models get exact counts from their tiktoken
+//! encoding; every other model family falls back to a word-based estimate
+//! using the family's characters-per-token ratio. [`for_model`] picks the
+//! right one for a model name.
+
+use super::context_window::TokenizerFamily;
+use std::sync::Arc;
+use tiktoken_rs::tokenizer::{get_tokenizer, Tokenizer as Encoding};
+use tiktoken_rs::CoreBPE;
+
+/// Counts and truncates text in tokens
+pub trait Tokenizer: Send + Sync {
+    /// Number of tokens in `text`
+    fn count(&self, text: &str) -> usize;
+
+    /// The longest prefix of `text` that fits in `max_tokens` tokens
+    fn truncate(&self, text: &str, max_tokens: usize) -> String;
+}
+
+/// Tokenizer for `model`: tiktoken for OpenAI models, a heuristic otherwise
+pub fn for_model(model: &str) -> Arc<dyn Tokenizer> {
+    match TokenizerFamily::for_model(model) {
+        TokenizerFamily::Gpt => Arc::new(TiktokenTokenizer::for_model(model)),
+        family => Arc::new(HeuristicTokenizer::new(family)),
+    }
+}
+
+/// Exact token counts using an OpenAI BPE encoding
+#[derive(Clone)]
+pub struct TiktokenTokenizer {
+    bpe: &'static CoreBPE,
+}
+
+impl std::fmt::Debug for TiktokenTokenizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TiktokenTokenizer").finish_non_exhaustive()
+    }
+}
+
+impl TiktokenTokenizer {
+    /// Encoding used by `model`, defaulting to `o200k_base` for models
+    /// tiktoken doesn't know yet
+    pub fn for_model(model: &str) -> Self {
+        let bpe = match get_tokenizer(model) {
+            Some(Encoding::Cl100kBase) => tiktoken_rs::cl100k_base_singleton(),
+            Some(Encoding::P50kBase) => tiktoken_rs::p50k_base_singleton(),
+            Some(Encoding::P50kEdit) => tiktoken_rs::p50k_edit_singleton(),
+            Some(Encoding::R50kBase | Encoding::Gpt2) => tiktoken_rs::r50k_base_singleton(),
+            Some(Encoding::O200kBase) | None => tiktoken_rs::o200k_base_singleton(),
+        };
+        Self { bpe }
+    }
+
+    /// The `cl100k_base` encoding of GPT-4 and GPT-3.5
+    pub fn cl100k() -> Self {
+        Self {
+            bpe: tiktoken_rs::cl100k_base_singleton(),
+        }
+    }
+
+    /// The `o200k_base` encoding of GPT-4o and the o-series
+    pub fn o200k() -> Self {
+        Self {
+            bpe: tiktoken_rs::o200k_base_singleton(),
+        }
+    }
+}
+
+impl Tokenizer for TiktokenTokenizer {
+    fn count(&self, text: &str) -> usize {
+        self.bpe.encode_ordinary(text).len()
+    }
+
+    fn truncate(&self, text: &str, max_tokens: usize) -> String {
+        let tokens = self.bpe.encode_ordinary(text);
+        if tokens.len() <= max_tokens {
+            return text.to_string();
+        }
+
+        // A cut can land inside a multi-byte character split across tokens;
+        // drop tokens until the prefix decodes cleanly
+        let mut end = max_tokens;
+        while end > 0 {
+            if let Ok(prefix) = self.bpe.decode(tokens[..end].to_vec()) {
+                return prefix;
+            }
+            end -= 1;
+        }
+        String::new()
+    }
+}
+
+/// Word-based token estimate for models without a bundled encoding.
+///
+/// Each whitespace-separated word costs its length divided by the family's
+/// characters-per-token ratio, rounded up, so short words count as one token
+/// and long ones as several.
+#[derive(Debug, Clone, Copy)]
+pub struct HeuristicTokenizer {
+    family: TokenizerFamily,
+}
+
+impl HeuristicTokenizer {
+    pub fn new(family: TokenizerFamily) -> Self {
+        Self { family }
+    }
+
+    fn word_tokens(&self, word: &str) -> usize {
+        (word.chars().count() as f64 / self.family.chars_per_token()).ceil() as usize
+    }
+}
+
+impl Tokenizer for HeuristicTokenizer {
+    fn count(&self, text: &str) -> usize {
+        text.split_whitespace()
+            .map(|word| self.word_tokens(word))
+            .sum()
+    }
+
+    fn truncate(&self, text: &str, max_tokens: usize) -> String {
+        let mut used = 0;
+        let mut end = 0;
+        for word in text.split_whitespace() {
+            used += self.word_tokens(word);
+            if used > max_tokens {
+                break;
+            }
+            // Words are subslices of `text`, so this is the byte offset past the word
+            end = word.as_ptr() as usize - text.as_ptr() as usize + word.len();
+        }
+        text[..end].to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "tiktoken is great!";
+
+    #[test]
+    fn test_tiktoken_counts_known_string() {
+        assert_eq!(TiktokenTokenizer::cl100k().count(SAMPLE), 6);
+        assert_eq!(TiktokenTokenizer::cl100k().count("hello world"), 2);
+        assert_eq!(TiktokenTokenizer::o200k().count("hello world"), 2);
+        assert_eq!(for_model("gpt-4").count(SAMPLE), 6);
+    }
+
+    #[test]
+    fn test_truncate_respects_limit() {
+        let text = "The quick brown fox jumps over the lazy dog. ".repeat(20);
+        let tokenizers: [Arc<dyn Tokenizer>; 3] = [
+            Arc::new(TiktokenTokenizer::cl100k()),
+            for_model("gpt-4o"),
+            for_model("llama3.2"),
+        ];
+
+        for tokenizer in tokenizers {
+            assert!(tokenizer.count(&text) > 50);
+            let cut = tokenizer.truncate(&text, 50);
+            assert!(tokenizer.count(&cut) <= 50);
+            assert!(tokenizer.count(&cut) >= 45);
+            assert!(text.starts_with(&cut));
+            assert_eq!(tokenizer.truncate("short", 50), "short");
+        }
+    }
+
+    #[test]
+    fn test_tiktoken_truncate_keeps_valid_utf8() {
+        let tokenizer = TiktokenTokenizer::cl100k();
+        let text = "日本語のテキストを切り詰める";
+        for max_tokens in 0..tokenizer.count(text) {
+            let cut = tokenizer.truncate(text, max_tokens);
+            assert!(text.starts_with(&cut));
+            assert!(tokenizer.count(&cut) <= max_tokens);
+        }
+    }
+
+    #[test]
+    fn test_heuristic_counts_words() {
+        let tokenizer = HeuristicTokenizer::new(TokenizerFamily::Generic);
+        assert_eq!(tokenizer.count(""), 0);
+        assert_eq!(tokenizer.count("a big cat"), 3);
+        // 14 characters at 3.5 per token
+        assert_eq!(tokenizer.count("internationals"), 4);
+        assert_eq!(tokenizer.truncate("one two three", 2), "one two");
+    }
+}
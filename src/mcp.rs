@@ -1,7 +1,13 @@
 //! Model Context Protocol (MCP) client implementation
 
+pub mod stdio;
+
+pub use stdio::StdioMcpTransport;
+
 use crate::config::{McpConfig, McpServerConfig};
 use crate::error::{McpError, Result};
+use crate::ids::new_id;
+use crate::workflow::StepSchema;
 use async_trait::async_trait;
 use jsonrpc_core::{Id, MethodCall, Params, Response, Version};
 use serde::{Deserialize, Serialize};
@@ -10,7 +16,6 @@ use std::collections::HashMap;
 use std::time::Duration;
 use tokio::time::timeout;
 use tracing::{debug, error, info, warn};
-use uuid::Uuid;
 
 /// Tool definition from MCP server
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,6 +39,33 @@ pub struct ToolResult {
     pub id: String,
     pub content: Vec<ToolContent>,
     pub is_error: bool,
+    /// Whether [`McpClient::call_tool`] cut the content short to fit
+    /// `McpConfig::max_result_bytes`, ending it with a notice
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+/// Prefix of the notice appended to results truncated by [`McpClient::call_tool`]
+pub const TRUNCATION_NOTICE: &str = "[truncated:";
+
+impl ToolResult {
+    /// Whether the content was cut short to fit `McpConfig::max_result_bytes`
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Text and resource text of the content, one item per line
+    pub fn text(&self) -> String {
+        self.content
+            .iter()
+            .filter_map(|content| match content {
+                ToolContent::Text { text } => Some(text.as_str()),
+                ToolContent::Resource { text, .. } => text.as_deref(),
+                ToolContent::Image { .. } => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 }
 
 /// Content in a tool result
@@ -48,6 +80,17 @@ pub enum ToolContent {
     Resource { uri: String, text: Option<String> },
 }
 
+impl ToolContent {
+    /// Approximate payload size in bytes
+    pub fn byte_len(&self) -> usize {
+        match self {
+            ToolContent::Text { text } => text.len(),
+            ToolContent::Image { data, mime_type } => data.len() + mime_type.len(),
+            ToolContent::Resource { uri, text } => uri.len() + text.as_ref().map_or(0, |t| t.len()),
+        }
+    }
+}
+
 /// MCP server connection types
 #[derive(Debug, Clone)]
 pub enum McpTransport {
@@ -103,7 +146,7 @@ impl HttpMcpConnection {
 #[async_trait]
 impl McpConnection for HttpMcpConnection {
     async fn call(&self, method: &str, params: Value) -> Result<Value> {
-        let id = Id::Str(Uuid::new_v4().to_string());
+        let id = Id::Str(new_id().to_string());
 
         let request = MethodCall {
             jsonrpc: Some(Version::V2),
@@ -219,9 +262,19 @@ impl McpClient {
                 .into());
             }
             "stdio" => {
-                return Err(
-                    McpError::ConnectionFailed("Stdio not implemented yet".to_string()).into(),
+                let command = server_config.command.ok_or_else(|| {
+                    McpError::ConnectionFailed("Stdio command required".to_string())
+                })?;
+
+                let timeout = Duration::from_secs(
+                    server_config.timeout.unwrap_or(self.config.default_timeout),
                 );
+
+                Box::new(StdioMcpTransport::spawn(
+                    &command,
+                    &server_config.env.unwrap_or_default(),
+                    timeout,
+                )?)
             }
             _ => {
                 return Err(McpError::ConnectionFailed(format!(
@@ -326,10 +379,28 @@ impl McpClient {
     pub async fn call_tool(&self, tool_call: ToolCall) -> Result<ToolResult> {
         debug!("Calling tool: {}", tool_call.name);
 
-        let (server_name, _tool) = self
+        let (server_name, tool) = self
             .find_tool_server(&tool_call.name)
             .ok_or_else(|| McpError::ToolNotFound(tool_call.name.clone()))?;
 
+        // Reject malformed arguments before they reach the server
+        if !tool.input_schema.is_null() {
+            let violations =
+                StepSchema::from_json_schema(&tool.input_schema).violations(&tool_call.arguments);
+            if !violations.is_empty() {
+                warn!(
+                    "Arguments for tool {} do not match its schema: {:?}",
+                    tool_call.name, violations
+                );
+                return Err(McpError::InvalidArguments {
+                    tool: tool_call.name.clone(),
+                    missing: violations.missing,
+                    mistyped: violations.mistyped,
+                }
+                .into());
+            }
+        }
+
         let connection = self.servers.get(server_name).ok_or_else(|| {
             McpError::ConnectionFailed(format!("Server {} not found", server_name))
         })?;
@@ -341,9 +412,27 @@ impl McpClient {
 
         let timeout_duration = Duration::from_secs(self.config.default_timeout);
 
-        let result = timeout(timeout_duration, connection.call("tools/call", call_params))
-            .await
-            .map_err(|_| McpError::Timeout(format!("Tool call timed out: {}", tool_call.name)))?;
+        let result =
+            match timeout(timeout_duration, connection.call("tools/call", call_params)).await {
+                Ok(result) => result,
+                Err(_) => {
+                    warn!(
+                        "Tool call {} timed out after {:?}",
+                        tool_call.name, timeout_duration
+                    );
+                    return Ok(ToolResult {
+                        id: tool_call.id,
+                        content: vec![ToolContent::Text {
+                            text: format!(
+                                "Tool call timed out after {}s: {}",
+                                self.config.default_timeout, tool_call.name
+                            ),
+                        }],
+                        is_error: true,
+                        truncated: false,
+                    });
+                }
+            };
 
         match result {
             Ok(response) => {
@@ -380,11 +469,12 @@ impl McpClient {
                     .and_then(|e| e.as_bool())
                     .unwrap_or(false);
 
-                Ok(ToolResult {
+                Ok(self.limit_result_size(ToolResult {
                     id: tool_call.id,
                     content,
                     is_error,
-                })
+                    truncated: false,
+                }))
             }
             Err(e) => {
                 error!("Tool call failed: {}", e);
@@ -394,11 +484,76 @@ impl McpClient {
                         text: format!("Tool call failed: {}", e),
                     }],
                     is_error: true,
+                    truncated: false,
                 })
             }
         }
     }
 
+    /// Enforce `max_result_bytes`, truncating or replacing the result with an error
+    fn limit_result_size(&self, mut result: ToolResult) -> ToolResult {
+        let max_bytes = self.config.max_result_bytes;
+        let total: usize = result.content.iter().map(ToolContent::byte_len).sum();
+        if total <= max_bytes {
+            return result;
+        }
+
+        warn!(
+            "Tool result {} is {} bytes, over the {} byte limit",
+            result.id, total, max_bytes
+        );
+
+        if !self.config.truncate_oversized_results {
+            return ToolResult {
+                id: result.id,
+                content: vec![ToolContent::Text {
+                    text: format!(
+                        "Tool result of {} bytes exceeds the limit of {} bytes",
+                        total, max_bytes
+                    ),
+                }],
+                is_error: true,
+                truncated: false,
+            };
+        }
+
+        let mut kept_bytes = 0;
+        let mut kept = Vec::new();
+        for item in result.content.drain(..) {
+            let remaining = max_bytes - kept_bytes;
+            let len = item.byte_len();
+            if len <= remaining {
+                kept_bytes += len;
+                kept.push(item);
+                continue;
+            }
+
+            // Only text can be cut meaningfully; other content is dropped whole
+            if let ToolContent::Text { mut text } = item {
+                let mut cut = remaining;
+                while !text.is_char_boundary(cut) {
+                    cut -= 1;
+                }
+                text.truncate(cut);
+                kept_bytes += cut;
+                kept.push(ToolContent::Text { text });
+            }
+            break;
+        }
+
+        kept.push(ToolContent::Text {
+            text: format!(
+                "{} result exceeded {} bytes, {} bytes omitted]",
+                TRUNCATION_NOTICE,
+                max_bytes,
+                total - kept_bytes
+            ),
+        });
+        result.content = kept;
+        result.truncated = true;
+        result
+    }
+
     /// Call multiple tools concurrently
     pub async fn call_tools(&self, tool_calls: Vec<ToolCall>) -> Vec<ToolResult> {
         let max_concurrent = self.config.max_concurrent_calls;
@@ -418,11 +573,12 @@ impl McpClient {
                     Err(e) => {
                         error!("Tool call error: {}", e);
                         results.push(ToolResult {
-                            id: Uuid::new_v4().to_string(),
+                            id: new_id().to_string(),
                             content: vec![ToolContent::Text {
                                 text: format!("Error: {}", e),
                             }],
                             is_error: true,
+                            truncated: false,
                         });
                     }
                 }
@@ -522,6 +678,172 @@ mod tests {
         assert_eq!(connection.auth_token, Some("token".to_string()));
     }
 
+    /// Connection serving a single tool that sleeps, then returns `payload`
+    struct MockToolConnection {
+        delay: Duration,
+        payload: String,
+    }
+
+    #[async_trait]
+    impl McpConnection for MockToolConnection {
+        async fn call(&self, _method: &str, _params: Value) -> Result<Value> {
+            tokio::time::sleep(self.delay).await;
+            Ok(json!({"content": [{"type": "text", "text": self.payload}]}))
+        }
+
+        async fn health_check(&self) -> Result<bool> {
+            Ok(true)
+        }
+
+        async fn close(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn client_with_mock(config: McpConfig, connection: MockToolConnection) -> McpClient {
+        let mut client = McpClient::new(config);
+        client
+            .servers
+            .insert("mock".to_string(), Box::new(connection));
+        client.tools_cache.insert(
+            "mock".to_string(),
+            vec![McpTool {
+                name: "mock_tool".to_string(),
+                description: "Mock tool".to_string(),
+                input_schema: json!({"type": "object"}),
+            }],
+        );
+        client
+    }
+
+    fn mock_call() -> ToolCall {
+        ToolCall {
+            id: "call-1".to_string(),
+            name: "mock_tool".to_string(),
+            arguments: json!({}),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_call_tool_timeout_returns_error_result() {
+        let config = McpConfig {
+            default_timeout: 1,
+            ..Default::default()
+        };
+        let client = client_with_mock(
+            config,
+            MockToolConnection {
+                delay: Duration::from_secs(5),
+                payload: "late".to_string(),
+            },
+        );
+
+        let result = client.call_tool(mock_call()).await.unwrap();
+        assert!(result.is_error);
+        assert_eq!(result.id, "call-1");
+        assert!(
+            matches!(&result.content[0], ToolContent::Text { text } if text.contains("timed out"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_call_tool_truncates_oversize_result() {
+        let config = McpConfig {
+            max_result_bytes: 100,
+            ..Default::default()
+        };
+        let client = client_with_mock(
+            config,
+            MockToolConnection {
+                delay: Duration::ZERO,
+                payload: "x".repeat(1000),
+            },
+        );
+
+        let result = client.call_tool(mock_call()).await.unwrap();
+        assert!(!result.is_error);
+        assert!(result.is_truncated());
+        assert!(matches!(&result.content[0], ToolContent::Text { text } if text.len() == 100));
+    }
+
+    #[test]
+    fn test_truncation_is_not_inferred_from_content() {
+        // Results serialized before the flag existed, or whose text happens to
+        // look like the notice, are not truncated
+        let result: ToolResult = serde_json::from_value(serde_json::json!({
+            "id": "call-1",
+            "content": [{ "type": "text", "text": "[truncated: by the tool itself]" }],
+            "is_error": false
+        }))
+        .unwrap();
+        assert!(!result.is_truncated());
+    }
+
+    #[tokio::test]
+    async fn test_call_tool_rejects_oversize_result() {
+        let config = McpConfig {
+            max_result_bytes: 100,
+            truncate_oversized_results: false,
+            ..Default::default()
+        };
+        let client = client_with_mock(
+            config,
+            MockToolConnection {
+                delay: Duration::ZERO,
+                payload: "x".repeat(1000),
+            },
+        );
+
+        let result = client.call_tool(mock_call()).await.unwrap();
+        assert!(result.is_error);
+        assert!(!result.is_truncated());
+        assert_eq!(result.content.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_call_tool_rejects_missing_required_argument() {
+        let mut client = client_with_mock(
+            McpConfig::default(),
+            MockToolConnection {
+                delay: Duration::ZERO,
+                payload: "dispatched".to_string(),
+            },
+        );
+        client.tools_cache.get_mut("mock").unwrap()[0].input_schema = json!({
+            "type": "object",
+            "properties": {
+                "path": {"type": "string"},
+                "limit": {"type": "integer"}
+            },
+            "required": ["path"]
+        });
+
+        let call = ToolCall {
+            arguments: json!({"limit": "ten"}),
+            ..mock_call()
+        };
+        match client.call_tool(call).await {
+            Err(crate::error::AgentError::Mcp(McpError::InvalidArguments {
+                tool,
+                missing,
+                mistyped,
+            })) => {
+                assert_eq!(tool, "mock_tool");
+                assert_eq!(missing, vec!["path".to_string()]);
+                assert_eq!(mistyped, vec!["limit: expected integer".to_string()]);
+            }
+            other => panic!("expected a validation error, got {:?}", other),
+        }
+
+        // Valid arguments are dispatched
+        let call = ToolCall {
+            arguments: json!({"path": "/tmp", "limit": 10}),
+            ..mock_call()
+        };
+        let result = client.call_tool(call).await.unwrap();
+        assert!(!result.is_error);
+    }
+
     // Mock tests would require a test MCP server, which is beyond the scope
     // of this basic implementation. In practice, you'd use wiremock or similar
     // to create mock HTTP endpoints for testing.
@@ -0,0 +1,290 @@
+//! Stdio transport for MCP servers
+//!
+//! Many MCP servers run as a subprocess and speak newline-delimited JSON-RPC
+//! over stdin/stdout. [`StdioMcpTransport`] spawns such a server and forwards
+//! calls to it one at a time. The child is killed when the transport is dropped.
+
+use super::McpConnection;
+use crate::error::{McpError, Result};
+use crate::ids::new_id;
+use async_trait::async_trait;
+use jsonrpc_core::{Id, MethodCall, Notification, Output, Params, Version};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::Mutex;
+use tokio::time::timeout;
+use tracing::{debug, info, warn};
+
+type Reader = BufReader<Box<dyn AsyncRead + Send + Unpin>>;
+type Writer = Box<dyn AsyncWrite + Send + Unpin>;
+
+/// MCP connection to a server over its stdin/stdout
+pub struct StdioMcpTransport {
+    child: Option<Child>,
+    io: Mutex<(Reader, Writer)>,
+    timeout: Duration,
+}
+
+impl StdioMcpTransport {
+    /// Spawn `command` (program followed by its arguments) as an MCP server
+    pub fn spawn(
+        command: &[String],
+        env: &HashMap<String, String>,
+        timeout: Duration,
+    ) -> Result<Self> {
+        let (program, args) = command
+            .split_first()
+            .ok_or_else(|| McpError::ConnectionFailed("Stdio command is empty".to_string()))?;
+
+        let mut child = Command::new(program)
+            .args(args)
+            .envs(env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| {
+                McpError::ConnectionFailed(format!("Failed to spawn '{}': {}", program, e))
+            })?;
+
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| McpError::ConnectionFailed("Child process has no stdin".to_string()))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| McpError::ConnectionFailed("Child process has no stdout".to_string()))?;
+
+        info!("Spawned stdio MCP server: {}", program);
+
+        let mut transport = Self::from_io(stdout, stdin, timeout);
+        transport.child = Some(child);
+        Ok(transport)
+    }
+
+    /// Speak MCP over an existing reader/writer pair, e.g. an in-process server
+    pub fn from_io(
+        reader: impl AsyncRead + Send + Unpin + 'static,
+        writer: impl AsyncWrite + Send + Unpin + 'static,
+        timeout: Duration,
+    ) -> Self {
+        let reader: Box<dyn AsyncRead + Send + Unpin> = Box::new(reader);
+        let writer: Writer = Box::new(writer);
+
+        Self {
+            child: None,
+            io: Mutex::new((BufReader::new(reader), writer)),
+            timeout,
+        }
+    }
+
+    async fn write_message(writer: &mut Writer, message: &impl serde::Serialize) -> Result<()> {
+        let mut line = serde_json::to_string(message)?;
+        line.push('\n');
+
+        writer
+            .write_all(line.as_bytes())
+            .await
+            .and(writer.flush().await)
+            .map_err(|e| McpError::ConnectionFailed(format!("Failed to write to server: {}", e)))?;
+        Ok(())
+    }
+
+    /// Read lines until the response for `id` arrives, skipping server notifications
+    async fn read_response(reader: &mut Reader, id: &Id) -> Result<Value> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let read = reader.read_line(&mut line).await.map_err(|e| {
+                McpError::ConnectionFailed(format!("Failed to read from server: {}", e))
+            })?;
+            if read == 0 {
+                return Err(
+                    McpError::ConnectionFailed("Server closed its output".to_string()).into(),
+                );
+            }
+
+            let message = line.trim();
+            if message.is_empty() {
+                continue;
+            }
+
+            let output: Output = match serde_json::from_str(message) {
+                Ok(output) => output,
+                Err(_) => {
+                    debug!("Ignoring non-response message from server: {}", message);
+                    continue;
+                }
+            };
+
+            if output.id() != id {
+                warn!("Ignoring response with unexpected id: {:?}", output.id());
+                continue;
+            }
+
+            return match output {
+                Output::Success(success) => Ok(success.result),
+                Output::Failure(failure) => Err(McpError::ProtocolError(format!(
+                    "JSON-RPC error: {:?} - {}",
+                    failure.error.code, failure.error.message
+                ))
+                .into()),
+            };
+        }
+    }
+
+    async fn exchange(&self, method: &str, params: Value) -> Result<Value> {
+        let id = Id::Str(new_id().to_string());
+        let request = MethodCall {
+            jsonrpc: Some(Version::V2),
+            method: method.to_string(),
+            params: Params::Map(params.as_object().cloned().unwrap_or_default()),
+            id: id.clone(),
+        };
+
+        // Requests are serialized so responses can be matched in order
+        let mut io = self.io.lock().await;
+        let (reader, writer) = &mut *io;
+
+        Self::write_message(writer, &request).await?;
+        let result = Self::read_response(reader, &id).await?;
+
+        // The protocol expects the client to confirm initialization
+        if method == "initialize" {
+            let initialized = Notification {
+                jsonrpc: Some(Version::V2),
+                method: "notifications/initialized".to_string(),
+                params: Params::Map(Map::new()),
+            };
+            Self::write_message(writer, &initialized).await?;
+        }
+
+        Ok(result)
+    }
+}
+
+#[async_trait]
+impl McpConnection for StdioMcpTransport {
+    async fn call(&self, method: &str, params: Value) -> Result<Value> {
+        timeout(self.timeout, self.exchange(method, params))
+            .await
+            .map_err(|_| McpError::Timeout(format!("No response to '{}'", method)))?
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        match self.call("ping", Value::Object(Map::new())).await {
+            Ok(_) => Ok(true),
+            Err(_) => Ok(false),
+        }
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        if let Some(mut child) = self.child.take() {
+            child.kill().await.map_err(|e| {
+                McpError::ConnectionFailed(format!("Failed to stop server process: {}", e))
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AgentError;
+    use serde_json::json;
+    use tokio::io::duplex;
+
+    /// Minimal MCP server exposing a single `echo` tool
+    async fn run_mock_server(reader: impl AsyncRead + Unpin, mut writer: impl AsyncWrite + Unpin) {
+        let mut lines = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let request: Value = serde_json::from_str(&line).unwrap();
+            let Some(id) = request.get("id").cloned() else {
+                // Notification, no reply
+                continue;
+            };
+
+            let result = match request["method"].as_str().unwrap() {
+                "initialize" => json!({"protocolVersion": "2024-11-05", "capabilities": {}}),
+                "tools/list" => json!({"tools": [{
+                    "name": "echo",
+                    "description": "Echo the input",
+                    "input_schema": {"type": "object"}
+                }]}),
+                "tools/call" => json!({"content": [{
+                    "type": "text",
+                    "text": request["params"]["arguments"]["message"]
+                }]}),
+                _ => json!({}),
+            };
+
+            // Interleave a server notification to check it is skipped
+            let notification = json!({"jsonrpc": "2.0", "method": "notifications/progress"});
+            let response = json!({"jsonrpc": "2.0", "id": id, "result": result});
+            let payload = format!("{}\n{}\n", notification, response);
+            writer.write_all(payload.as_bytes()).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stdio_transport_lists_and_calls_tool() {
+        let (client_side, server_side) = duplex(4096);
+        let (client_read, client_write) = tokio::io::split(client_side);
+        let (server_read, server_write) = tokio::io::split(server_side);
+        tokio::spawn(run_mock_server(server_read, server_write));
+
+        let transport =
+            StdioMcpTransport::from_io(client_read, client_write, Duration::from_secs(5));
+
+        transport.call("initialize", json!({})).await.unwrap();
+
+        let tools = transport.call("tools/list", json!({})).await.unwrap();
+        assert_eq!(tools["tools"][0]["name"], "echo");
+
+        let result = transport
+            .call(
+                "tools/call",
+                json!({"name": "echo", "arguments": {"message": "hello"}}),
+            )
+            .await
+            .unwrap();
+        assert_eq!(result["content"][0]["text"], "hello");
+    }
+
+    #[tokio::test]
+    async fn test_stdio_transport_reports_closed_server() {
+        let (client_side, server_side) = duplex(64);
+        drop(server_side);
+        let (client_read, client_write) = tokio::io::split(client_side);
+
+        let transport =
+            StdioMcpTransport::from_io(client_read, client_write, Duration::from_secs(5));
+        let err = transport.call("tools/list", json!({})).await.unwrap_err();
+        assert!(matches!(
+            err,
+            AgentError::Mcp(McpError::ConnectionFailed(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_stdio_transport_spawn_missing_command() {
+        let err = StdioMcpTransport::spawn(
+            &["definitely-not-an-mcp-server".to_string()],
+            &HashMap::new(),
+            Duration::from_secs(1),
+        )
+        .err()
+        .unwrap();
+        assert!(matches!(
+            err,
+            AgentError::Mcp(McpError::ConnectionFailed(_))
+        ));
+    }
+}
@@ -1,15 +1,31 @@
 //! Memory and vector store functionality
 
-use crate::config::MemoryConfig;
+pub mod hybrid;
+pub mod ranking;
+pub mod shared;
+pub mod vector_index;
+
+pub use hybrid::HybridSearchOptions;
+pub use ranking::MEMORY_IMPORTANCE_KEY;
+pub use shared::SharedMemory;
+pub use vector_index::VectorIndex;
+
+use crate::config::{MemoryConfig, MemoryDedupAction};
 use crate::error::{MemoryError, Result};
+use crate::ids::new_id;
+use crate::tools::memory_search::MEMORY_NAMESPACE_KEY;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use hybrid::{reciprocal_rank_fusion, KeywordIndex};
 use serde::{Deserialize, Serialize};
 use sqlx::{sqlite::SqlitePool, Row};
 use std::collections::HashMap;
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
+/// `store_settings` key recording whether stored embeddings are normalized
+const NORMALIZED_SETTING: &str = "embeddings_normalized";
+
 /// A memory entry with embedding
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoryEntry {
@@ -19,6 +35,16 @@ pub struct MemoryEntry {
     pub metadata: HashMap<String, String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// How valuable the memory is, from 0.0 to 1.0
+    #[serde(default = "default_importance")]
+    pub importance: f32,
+    /// When the memory was stored or last returned by a search
+    #[serde(default = "Utc::now")]
+    pub last_accessed: DateTime<Utc>,
+}
+
+fn default_importance() -> f32 {
+    ranking::DEFAULT_IMPORTANCE
 }
 
 /// Search result from vector store
@@ -28,13 +54,36 @@ pub struct SearchResult {
     pub similarity: f32,
 }
 
+/// What [`MemoryStore::insert`] did with an entry
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InsertOutcome {
+    /// Stored as a new memory
+    Stored(Uuid),
+    /// Folded into the existing memory it duplicates
+    Merged { into: Uuid, similarity: f32 },
+    /// Dropped as a duplicate of an existing memory
+    Skipped { duplicate_of: Uuid, similarity: f32 },
+}
+
+impl InsertOutcome {
+    /// Id of the memory now holding the entry's content
+    pub fn id(&self) -> Uuid {
+        match self {
+            Self::Stored(id) => *id,
+            Self::Merged { into, .. } => *into,
+            Self::Skipped { duplicate_of, .. } => *duplicate_of,
+        }
+    }
+}
+
 /// Memory store trait for different implementations
 #[async_trait]
 pub trait MemoryStore: Send + Sync {
     /// Initialize the memory store
     async fn initialize(&mut self) -> Result<()>;
 
-    /// Store a memory entry
+    /// Store a memory entry. Its importance is read from the
+    /// [`MEMORY_IMPORTANCE_KEY`] metadata value, if any.
     async fn store(
         &mut self,
         content: String,
@@ -42,7 +91,24 @@ pub trait MemoryStore: Send + Sync {
         metadata: HashMap<String, String>,
     ) -> Result<Uuid>;
 
-    /// Search for similar memories
+    /// Store `entry` as it is, keeping its id and timestamps, in place of any
+    /// memory with the same id. Used to restore exported memories.
+    async fn restore(&mut self, entry: MemoryEntry) -> Result<()>;
+
+    /// Store a memory unless it duplicates an existing one, as configured for
+    /// the store. Stores without duplicate detection always store.
+    async fn insert(
+        &mut self,
+        content: String,
+        embedding: Vec<f32>,
+        metadata: HashMap<String, String>,
+    ) -> Result<InsertOutcome> {
+        Ok(InsertOutcome::Stored(
+            self.store(content, embedding, metadata).await?,
+        ))
+    }
+
+    /// Search for similar memories, marking the results accessed
     async fn search(
         &self,
         query_embedding: Vec<f32>,
@@ -50,6 +116,22 @@ pub trait MemoryStore: Send + Sync {
         threshold: f32,
     ) -> Result<Vec<SearchResult>>;
 
+    /// Search combining vector similarity with keyword (BM25) relevance of the stored text.
+    ///
+    /// The two rankings are merged with weighted reciprocal rank fusion, and each
+    /// result's `similarity` holds its fused score.
+    async fn search_hybrid(
+        &self,
+        query: &str,
+        query_embedding: Vec<f32>,
+        options: &HybridSearchOptions,
+    ) -> Result<Vec<SearchResult>>;
+
+    /// Keyword-only (BM25) search over stored text, for when no query embedding is available.
+    ///
+    /// Each result's `similarity` holds its BM25 score.
+    async fn search_keywords(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>>;
+
     /// Get a specific memory by ID
     async fn get(&self, id: Uuid) -> Result<Option<MemoryEntry>>;
 
@@ -62,15 +144,34 @@ pub trait MemoryStore: Send + Sync {
         metadata: Option<HashMap<String, String>>,
     ) -> Result<()>;
 
+    /// Set the importance of a memory entry, clamped to 0.0..=1.0
+    async fn set_importance(&mut self, id: Uuid, importance: f32) -> Result<()>;
+
     /// Delete a memory entry
     async fn delete(&mut self, id: Uuid) -> Result<()>;
 
     /// Get all memories (for small datasets)
     async fn list(&self, limit: Option<usize>) -> Result<Vec<MemoryEntry>>;
 
+    /// Get memories oldest first, skipping the first `offset`, for walking
+    /// large stores a page at a time
+    async fn list_page(&self, offset: usize, limit: usize) -> Result<Vec<MemoryEntry>> {
+        let mut entries = self.list(None).await?;
+        entries.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+        Ok(entries.into_iter().skip(offset).take(limit).collect())
+    }
+
     /// Clear all memories
     async fn clear(&mut self) -> Result<()>;
 
+    /// Rebuild the store's search index from stored entries. Indexes are kept
+    /// up to date on every write, so this is only needed for maintenance, such
+    /// as after the database was changed outside the store. Stores without an
+    /// index do nothing.
+    async fn rebuild_index(&mut self) -> Result<()> {
+        Ok(())
+    }
+
     /// Get store statistics
     async fn stats(&self) -> Result<MemoryStats>;
 }
@@ -105,18 +206,55 @@ pub struct MemoryStats {
     pub total_memories: usize,
     pub embedding_dimension: usize,
     pub store_size_bytes: Option<usize>,
+    /// Entries evicted to keep the store within `MemoryConfig::max_entries`
+    /// since it was opened
+    pub evicted_entries: u64,
 }
 
 /// SQLite-based memory store implementation
 pub struct SqliteMemoryStore {
     pool: Option<SqlitePool>,
     config: MemoryConfig,
+    keyword_index: KeywordIndex,
+    /// Present when `config.vector_index` is set
+    vector_index: Option<VectorIndex>,
+    evicted_entries: u64,
 }
 
 impl SqliteMemoryStore {
     /// Create a new SQLite memory store
     pub fn new(config: MemoryConfig) -> Self {
-        Self { pool: None, config }
+        let vector_index = config.vector_index.as_ref().map(|_| {
+            if config.normalize_embeddings {
+                VectorIndex::normalized()
+            } else {
+                VectorIndex::new()
+            }
+        });
+        Self {
+            pool: None,
+            config,
+            keyword_index: KeywordIndex::new(),
+            vector_index,
+            evicted_entries: 0,
+        }
+    }
+
+    /// Tombstone `id` in the vector index, compacting it once tombstones pass
+    /// the configured ratio
+    fn remove_from_vector_index(&mut self, id: Uuid) {
+        let (Some(index), Some(config)) = (&mut self.vector_index, &self.config.vector_index)
+        else {
+            return;
+        };
+        index.remove(id);
+        if index.tombstone_ratio() > config.max_tombstone_ratio {
+            debug!(
+                "Compacting vector index ({} tombstones)",
+                index.tombstones()
+            );
+            index.compact();
+        }
     }
 
     /// Get database pool
@@ -141,6 +279,77 @@ impl SqliteMemoryStore {
         dot_product / (norm_a * norm_b)
     }
 
+    /// Dot product of two vectors, the cosine similarity of unit vectors
+    pub fn dot_product(a: &[f32], b: &[f32]) -> f32 {
+        if a.len() != b.len() {
+            return 0.0;
+        }
+        a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+    }
+
+    /// Scale `embedding` to unit length. Zero vectors are left as they are.
+    pub fn normalize_embedding(embedding: &mut [f32]) {
+        let norm = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            embedding.iter_mut().for_each(|x| *x /= norm);
+        }
+    }
+
+    /// `embedding` as it is stored or searched with, normalized if configured
+    fn prepare_embedding(&self, mut embedding: Vec<f32>) -> Vec<f32> {
+        if self.config.normalize_embeddings {
+            Self::normalize_embedding(&mut embedding);
+        }
+        embedding
+    }
+
+    /// Similarity of a prepared query to a stored embedding
+    fn similarity(&self, query: &[f32], embedding: &[f32]) -> f32 {
+        if self.config.normalize_embeddings {
+            Self::dot_product(query, embedding)
+        } else {
+            Self::cosine_similarity(query, embedding)
+        }
+    }
+
+    /// Record whether this database holds normalized embeddings, or check
+    /// that it matches the configuration if already recorded. Databases from
+    /// before the setting existed hold raw embeddings.
+    async fn check_normalization_state(&self, pool: &SqlitePool) -> Result<()> {
+        let recorded: Option<String> =
+            sqlx::query_scalar("SELECT value FROM store_settings WHERE key = ?1")
+                .bind(NORMALIZED_SETTING)
+                .fetch_optional(pool)
+                .await?;
+
+        let normalized = match recorded {
+            Some(value) => value == "true",
+            None => {
+                let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM memories")
+                    .fetch_one(pool)
+                    .await?;
+                let normalized = count == 0 && self.config.normalize_embeddings;
+                sqlx::query("INSERT INTO store_settings (key, value) VALUES (?1, ?2)")
+                    .bind(NORMALIZED_SETTING)
+                    .bind(normalized.to_string())
+                    .execute(pool)
+                    .await?;
+                normalized
+            }
+        };
+
+        if normalized != self.config.normalize_embeddings {
+            return Err(MemoryError::StorageFailed(format!(
+                "Memory store holds {} embeddings but normalize_embeddings is {}; \
+                 use a new database to change the setting",
+                if normalized { "normalized" } else { "raw" },
+                self.config.normalize_embeddings
+            ))
+            .into());
+        }
+        Ok(())
+    }
+
     /// Serialize embedding for storage
     pub fn serialize_embedding(embedding: &[f32]) -> Vec<u8> {
         embedding
@@ -165,6 +374,242 @@ impl SqliteMemoryStore {
     fn deserialize_metadata(data: &str) -> Result<HashMap<String, String>> {
         Ok(serde_json::from_str(data).unwrap_or_default())
     }
+
+    /// Build an entry from a row of the `memories` table
+    fn entry_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<MemoryEntry> {
+        let id: String = row.get("id");
+        let embedding_blob: Vec<u8> = row.get("embedding");
+        let metadata_json: String = row.get("metadata");
+        let created_at: String = row.get("created_at");
+        let updated_at: String = row.get("updated_at");
+        let last_accessed: Option<String> = row.get("last_accessed");
+        let parse_time = |time: &str| {
+            DateTime::parse_from_rfc3339(time)
+                .map(|time| time.with_timezone(&Utc))
+                .map_err(|e| MemoryError::StorageFailed(e.to_string()))
+        };
+        let updated_at = parse_time(&updated_at)?;
+
+        Ok(MemoryEntry {
+            id: Uuid::parse_str(&id).map_err(|e| MemoryError::StorageFailed(e.to_string()))?,
+            content: row.get("content"),
+            embedding: Self::deserialize_embedding(&embedding_blob),
+            metadata: Self::deserialize_metadata(&metadata_json)?,
+            created_at: parse_time(&created_at)?,
+            updated_at,
+            importance: row.get::<f64, _>("importance") as f32,
+            last_accessed: match last_accessed {
+                Some(time) => parse_time(&time)?,
+                None => updated_at,
+            },
+        })
+    }
+
+    /// Add the ranking columns to a `memories` table created before they existed
+    async fn migrate_ranking_columns(pool: &SqlitePool) -> Result<()> {
+        let columns: Vec<String> = sqlx::query("PRAGMA table_info(memories)")
+            .fetch_all(pool)
+            .await?
+            .iter()
+            .map(|row| row.get("name"))
+            .collect();
+
+        if !columns.iter().any(|column| column == "importance") {
+            sqlx::query(&format!(
+                "ALTER TABLE memories ADD COLUMN importance REAL NOT NULL DEFAULT {}",
+                ranking::DEFAULT_IMPORTANCE
+            ))
+            .execute(pool)
+            .await?;
+        }
+        if !columns.iter().any(|column| column == "last_accessed") {
+            sqlx::query("ALTER TABLE memories ADD COLUMN last_accessed TEXT")
+                .execute(pool)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Entries most similar to `query_embedding`, without marking them accessed
+    async fn find_similar(
+        &self,
+        query_embedding: Vec<f32>,
+        limit: usize,
+        threshold: f32,
+    ) -> Result<Vec<SearchResult>> {
+        let pool = self.pool()?;
+
+        if query_embedding.len() != self.config.embedding_dimension {
+            return Err(MemoryError::InvalidDimension {
+                expected: self.config.embedding_dimension,
+                actual: query_embedding.len(),
+            }
+            .into());
+        }
+        let query_embedding = self.prepare_embedding(query_embedding);
+
+        // Re-ranking may promote entries from below the top `limit` by similarity
+        let candidates = match &self.config.ranking {
+            Some(ranking) => limit.saturating_mul(ranking.candidate_factor.max(1)),
+            None => limit,
+        };
+
+        if let Some(index) = &self.vector_index {
+            let mut results = Vec::new();
+            for (id, similarity) in index.search(&query_embedding, candidates, threshold) {
+                if let Some(entry) = self.get(id).await? {
+                    results.push(SearchResult { entry, similarity });
+                }
+            }
+            self.rank(&mut results, limit);
+            debug!(
+                "Found {} similar memories above threshold {} in vector index",
+                results.len(),
+                threshold
+            );
+            return Ok(results);
+        }
+
+        // For SQLite without vector extensions, we need to do brute-force similarity search
+        let rows = sqlx::query("SELECT * FROM memories")
+            .fetch_all(pool)
+            .await?;
+
+        let mut results = Vec::new();
+
+        for row in rows {
+            let entry = Self::entry_from_row(&row)?;
+            let similarity = self.similarity(&query_embedding, &entry.embedding);
+
+            if similarity >= threshold {
+                results.push(SearchResult { entry, similarity });
+            }
+        }
+
+        // Sort by similarity (highest first) and limit results
+        results.sort_by(|a, b| {
+            b.similarity
+                .partial_cmp(&a.similarity)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        results.truncate(candidates);
+        self.rank(&mut results, limit);
+
+        debug!(
+            "Found {} similar memories above threshold {}",
+            results.len(),
+            threshold
+        );
+        Ok(results)
+    }
+
+    /// Re-rank results sorted by similarity, if configured, and keep the top `limit`
+    fn rank(&self, results: &mut Vec<SearchResult>, limit: usize) {
+        if let Some(ranking) = &self.config.ranking {
+            ranking::rerank(ranking, results, Utc::now());
+        }
+        results.truncate(limit);
+    }
+
+    /// Record that `results` were accessed now, bumping their recency
+    async fn record_access(&self, results: &mut [SearchResult]) -> Result<()> {
+        if results.is_empty() {
+            return Ok(());
+        }
+        let pool = self.pool()?;
+        let now = Utc::now();
+
+        let mut tx = pool.begin().await?;
+        for result in results.iter_mut() {
+            sqlx::query("UPDATE memories SET last_accessed = ?1 WHERE id = ?2")
+                .bind(now.to_rfc3339())
+                .bind(result.entry.id.to_string())
+                .execute(&mut *tx)
+                .await?;
+            result.entry.last_accessed = now;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Insert `entry` into the table and the indexes, then evict over capacity
+    async fn write_entry(&mut self, entry: MemoryEntry) -> Result<()> {
+        let pool = self.pool()?;
+
+        if entry.embedding.len() != self.config.embedding_dimension {
+            return Err(MemoryError::InvalidDimension {
+                expected: self.config.embedding_dimension,
+                actual: entry.embedding.len(),
+            }
+            .into());
+        }
+
+        let embedding = self.prepare_embedding(entry.embedding);
+        let embedding_blob = Self::serialize_embedding(&embedding);
+        let metadata_json = Self::serialize_metadata(&entry.metadata)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO memories
+                (id, content, embedding, metadata, created_at, updated_at, importance, last_accessed)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            "#,
+        )
+        .bind(entry.id.to_string())
+        .bind(&entry.content)
+        .bind(&embedding_blob)
+        .bind(&metadata_json)
+        .bind(entry.created_at.to_rfc3339())
+        .bind(entry.updated_at.to_rfc3339())
+        .bind(entry.importance)
+        .bind(entry.last_accessed.to_rfc3339())
+        .execute(pool)
+        .await?;
+
+        self.keyword_index.insert(entry.id, &entry.content);
+        if let Some(index) = &mut self.vector_index {
+            index.insert(entry.id, embedding);
+        }
+
+        self.evict_over_capacity().await
+    }
+
+    /// Delete the least recently accessed entries beyond `config.max_entries`
+    async fn evict_over_capacity(&mut self) -> Result<()> {
+        let Some(max_entries) = self.config.max_entries else {
+            return Ok(());
+        };
+        let pool = self.pool()?;
+
+        let row = sqlx::query("SELECT COUNT(*) as count FROM memories")
+            .fetch_one(pool)
+            .await?;
+        let total: i64 = row.get("count");
+        let excess = (total as usize).saturating_sub(max_entries);
+        if excess == 0 {
+            return Ok(());
+        }
+
+        // Entries written before recency was tracked fall back to their creation time
+        let rows = sqlx::query(
+            "SELECT id FROM memories ORDER BY COALESCE(last_accessed, created_at) ASC LIMIT ?1",
+        )
+        .bind(excess as i64)
+        .fetch_all(pool)
+        .await?;
+        for row in rows {
+            let id: String = row.get("id");
+            let id = Uuid::parse_str(&id).map_err(|e| MemoryError::StorageFailed(e.to_string()))?;
+            self.delete(id).await?;
+        }
+
+        self.evicted_entries += excess as u64;
+        debug!(
+            "Evicted {} least recently accessed memories to stay within {}",
+            excess, max_entries
+        );
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -215,19 +660,30 @@ impl MemoryStore for SqliteMemoryStore {
                 embedding BLOB NOT NULL,
                 metadata TEXT NOT NULL DEFAULT '{}',
                 created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL
+                updated_at TEXT NOT NULL,
+                importance REAL NOT NULL DEFAULT 0.5,
+                last_accessed TEXT
             )
             "#,
         )
         .execute(&pool)
         .await?;
+        Self::migrate_ranking_columns(&pool).await?;
 
         // Create index for faster searches (though not optimal for vector similarity)
         sqlx::query("CREATE INDEX IF NOT EXISTS idx_memories_created_at ON memories(created_at)")
             .execute(&pool)
             .await?;
 
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS store_settings (key TEXT PRIMARY KEY, value TEXT NOT NULL)",
+        )
+        .execute(&pool)
+        .await?;
+        self.check_normalization_state(&pool).await?;
+
         self.pool = Some(pool);
+        self.rebuild_index().await?;
         info!("SQLite memory store initialized");
 
         Ok(())
@@ -239,109 +695,166 @@ impl MemoryStore for SqliteMemoryStore {
         embedding: Vec<f32>,
         metadata: HashMap<String, String>,
     ) -> Result<Uuid> {
-        let pool = self.pool()?;
-
-        if embedding.len() != self.config.embedding_dimension {
-            return Err(MemoryError::InvalidDimension {
-                expected: self.config.embedding_dimension,
-                actual: embedding.len(),
-            }
-            .into());
-        }
-
-        let id = Uuid::new_v4();
+        let id = new_id();
         let now = Utc::now();
-        let embedding_blob = Self::serialize_embedding(&embedding);
-        let metadata_json = Self::serialize_metadata(&metadata)?;
-
-        sqlx::query(
-            r#"
-            INSERT INTO memories (id, content, embedding, metadata, created_at, updated_at)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
-            "#,
-        )
-        .bind(id.to_string())
-        .bind(&content)
-        .bind(&embedding_blob)
-        .bind(&metadata_json)
-        .bind(now.to_rfc3339())
-        .bind(now.to_rfc3339())
-        .execute(pool)
+        let importance = ranking::importance_from_metadata(&metadata);
+        self.write_entry(MemoryEntry {
+            id,
+            content,
+            embedding,
+            metadata,
+            created_at: now,
+            updated_at: now,
+            importance,
+            last_accessed: now,
+        })
         .await?;
 
         debug!("Stored memory entry with ID: {}", id);
         Ok(id)
     }
 
+    async fn restore(&mut self, entry: MemoryEntry) -> Result<()> {
+        if self.get(entry.id).await?.is_some() {
+            self.delete(entry.id).await?;
+        }
+        let id = entry.id;
+        self.write_entry(entry).await?;
+
+        debug!("Restored memory entry with ID: {}", id);
+        Ok(())
+    }
+
+    async fn insert(
+        &mut self,
+        content: String,
+        embedding: Vec<f32>,
+        metadata: HashMap<String, String>,
+    ) -> Result<InsertOutcome> {
+        let Some(dedup) = self.config.dedup.clone() else {
+            return Ok(InsertOutcome::Stored(
+                self.store(content, embedding, metadata).await?,
+            ));
+        };
+
+        // Only entries in the same namespace count as duplicates
+        let namespace = metadata.get(MEMORY_NAMESPACE_KEY);
+        let duplicate = self
+            .find_similar(embedding.clone(), usize::MAX, dedup.similarity_threshold)
+            .await?
+            .into_iter()
+            .find(|result| result.entry.metadata.get(MEMORY_NAMESPACE_KEY) == namespace);
+
+        let Some(SearchResult { entry, similarity }) = duplicate else {
+            return Ok(InsertOutcome::Stored(
+                self.store(content, embedding, metadata).await?,
+            ));
+        };
+
+        match dedup.action {
+            MemoryDedupAction::Skip => {
+                debug!(
+                    "Skipping memory duplicating {} (similarity {:.3})",
+                    entry.id, similarity
+                );
+                Ok(InsertOutcome::Skipped {
+                    duplicate_of: entry.id,
+                    similarity,
+                })
+            }
+            MemoryDedupAction::Merge => {
+                debug!(
+                    "Merging memory into {} (similarity {:.3})",
+                    entry.id, similarity
+                );
+                let mut merged = entry.metadata;
+                merged.extend(metadata);
+                self.update(entry.id, None, None, Some(merged)).await?;
+                Ok(InsertOutcome::Merged {
+                    into: entry.id,
+                    similarity,
+                })
+            }
+        }
+    }
+
     async fn search(
         &self,
         query_embedding: Vec<f32>,
         limit: usize,
         threshold: f32,
     ) -> Result<Vec<SearchResult>> {
-        let pool = self.pool()?;
-
-        if query_embedding.len() != self.config.embedding_dimension {
-            return Err(MemoryError::InvalidDimension {
-                expected: self.config.embedding_dimension,
-                actual: query_embedding.len(),
-            }
-            .into());
-        }
+        let mut results = self.find_similar(query_embedding, limit, threshold).await?;
+        self.record_access(&mut results).await?;
+        Ok(results)
+    }
 
-        // For SQLite without vector extensions, we need to do brute-force similarity search
-        let rows = sqlx::query("SELECT * FROM memories")
-            .fetch_all(pool)
+    async fn search_hybrid(
+        &self,
+        query: &str,
+        query_embedding: Vec<f32>,
+        options: &HybridSearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        let vector_results = self
+            .find_similar(query_embedding, options.candidates, f32::MIN)
             .await?;
+        let keyword_results = self.keyword_index.search(query, options.candidates);
+
+        let vector_ranking: Vec<Uuid> = vector_results.iter().map(|r| r.entry.id).collect();
+        let keyword_ranking: Vec<Uuid> = keyword_results.iter().map(|(id, _)| *id).collect();
+        let fused = reciprocal_rank_fusion(
+            &vector_ranking,
+            &keyword_ranking,
+            options.vector_weight,
+            options.rrf_k,
+        );
 
-        let mut results = Vec::new();
-
-        for row in rows {
-            let id: String = row.get("id");
-            let content: String = row.get("content");
-            let embedding_blob: Vec<u8> = row.get("embedding");
-            let metadata_json: String = row.get("metadata");
-            let created_at: String = row.get("created_at");
-            let updated_at: String = row.get("updated_at");
-
-            let embedding = Self::deserialize_embedding(&embedding_blob);
-            let similarity = Self::cosine_similarity(&query_embedding, &embedding);
-
-            if similarity >= threshold {
-                let entry = MemoryEntry {
-                    id: Uuid::parse_str(&id)
-                        .map_err(|e| MemoryError::StorageFailed(e.to_string()))?,
-                    content,
-                    embedding,
-                    metadata: Self::deserialize_metadata(&metadata_json)?,
-                    created_at: DateTime::parse_from_rfc3339(&created_at)
-                        .map_err(|e| MemoryError::StorageFailed(e.to_string()))?
-                        .with_timezone(&Utc),
-                    updated_at: DateTime::parse_from_rfc3339(&updated_at)
-                        .map_err(|e| MemoryError::StorageFailed(e.to_string()))?
-                        .with_timezone(&Utc),
-                };
+        let mut entries: HashMap<Uuid, MemoryEntry> = vector_results
+            .into_iter()
+            .map(|r| (r.entry.id, r.entry))
+            .collect();
 
-                results.push(SearchResult { entry, similarity });
-            }
+        let mut results = Vec::new();
+        for (id, score) in fused.into_iter().take(options.limit) {
+            let entry = match entries.remove(&id) {
+                Some(entry) => entry,
+                // Keyword-only hit outside the vector candidates
+                None => match self.get(id).await? {
+                    Some(entry) => entry,
+                    None => continue,
+                },
+            };
+            results.push(SearchResult {
+                entry,
+                similarity: score,
+            });
         }
 
-        // Sort by similarity (highest first) and limit results
-        results.sort_by(|a, b| {
-            b.similarity
-                .partial_cmp(&a.similarity)
-                .unwrap_or(std::cmp::Ordering::Equal)
-        });
-        results.truncate(limit);
-
+        self.record_access(&mut results).await?;
         debug!(
-            "Found {} similar memories above threshold {}",
+            "Hybrid search returned {} memories ({} keyword matches)",
             results.len(),
-            threshold
+            keyword_ranking.len()
         );
         Ok(results)
     }
 
+    async fn search_keywords(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
+        let mut results = Vec::new();
+        for (id, score) in self.keyword_index.search(query, limit) {
+            if let Some(entry) = self.get(id).await? {
+                results.push(SearchResult {
+                    entry,
+                    similarity: score,
+                });
+            }
+        }
+
+        self.record_access(&mut results).await?;
+        debug!("Keyword search returned {} memories", results.len());
+        Ok(results)
+    }
+
     async fn get(&self, id: Uuid) -> Result<Option<MemoryEntry>> {
         let pool = self.pool()?;
 
@@ -350,31 +863,7 @@ impl MemoryStore for SqliteMemoryStore {
             .fetch_optional(pool)
             .await?;
 
-        if let Some(row) = row {
-            let content: String = row.get("content");
-            let embedding_blob: Vec<u8> = row.get("embedding");
-            let metadata_json: String = row.get("metadata");
-            let created_at: String = row.get("created_at");
-            let updated_at: String = row.get("updated_at");
-
-            let embedding = Self::deserialize_embedding(&embedding_blob);
-            let metadata = Self::deserialize_metadata(&metadata_json)?;
-
-            Ok(Some(MemoryEntry {
-                id,
-                content,
-                embedding,
-                metadata,
-                created_at: DateTime::parse_from_rfc3339(&created_at)
-                    .map_err(|e| MemoryError::StorageFailed(e.to_string()))?
-                    .with_timezone(&Utc),
-                updated_at: DateTime::parse_from_rfc3339(&updated_at)
-                    .map_err(|e| MemoryError::StorageFailed(e.to_string()))?
-                    .with_timezone(&Utc),
-            }))
-        } else {
-            Ok(None)
-        }
+        row.as_ref().map(Self::entry_from_row).transpose()
     }
 
     async fn update(
@@ -396,16 +885,17 @@ impl MemoryStore for SqliteMemoryStore {
                 .into());
             }
         }
+        let embedding = embedding.map(|embedding| self.prepare_embedding(embedding));
 
         let mut query_parts = Vec::new();
         let mut values: Vec<String> = Vec::new();
 
-        if let Some(content) = content {
+        if let Some(content) = content.clone() {
             query_parts.push("content = ?");
             values.push(content);
         }
 
-        if let Some(embedding) = embedding {
+        if let Some(embedding) = embedding.clone() {
             query_parts.push("embedding = ?");
             let embedding_blob = Self::serialize_embedding(&embedding);
             use base64::Engine;
@@ -441,12 +931,35 @@ impl MemoryStore for SqliteMemoryStore {
         if result.rows_affected() == 0 {
             warn!("No memory found with ID: {}", id);
         } else {
+            if let Some(content) = content {
+                self.keyword_index.insert(id, &content);
+            }
+            if let (Some(index), Some(embedding)) = (&mut self.vector_index, embedding) {
+                index.insert(id, embedding);
+            }
             debug!("Updated memory entry with ID: {}", id);
         }
 
         Ok(())
     }
 
+    async fn set_importance(&mut self, id: Uuid, importance: f32) -> Result<()> {
+        let pool = self.pool()?;
+
+        let result = sqlx::query("UPDATE memories SET importance = ?1 WHERE id = ?2")
+            .bind(importance.clamp(0.0, 1.0))
+            .bind(id.to_string())
+            .execute(pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            warn!("No memory found with ID: {}", id);
+        } else {
+            debug!("Set importance of memory {} to {}", id, importance);
+        }
+        Ok(())
+    }
+
     async fn delete(&mut self, id: Uuid) -> Result<()> {
         let pool = self.pool()?;
 
@@ -455,6 +968,9 @@ impl MemoryStore for SqliteMemoryStore {
             .execute(pool)
             .await?;
 
+        self.keyword_index.remove(id);
+        self.remove_from_vector_index(id);
+
         if result.rows_affected() == 0 {
             warn!("No memory found with ID: {}", id);
         } else {
@@ -477,47 +993,73 @@ impl MemoryStore for SqliteMemoryStore {
         };
 
         let rows = sqlx::query(&query).fetch_all(pool).await?;
-
-        let mut entries = Vec::new();
-
-        for row in rows {
-            let id: String = row.get("id");
-            let content: String = row.get("content");
-            let embedding_blob: Vec<u8> = row.get("embedding");
-            let metadata_json: String = row.get("metadata");
-            let created_at: String = row.get("created_at");
-            let updated_at: String = row.get("updated_at");
-
-            let embedding = Self::deserialize_embedding(&embedding_blob);
-            let metadata = Self::deserialize_metadata(&metadata_json)?;
-
-            entries.push(MemoryEntry {
-                id: Uuid::parse_str(&id).map_err(|e| MemoryError::StorageFailed(e.to_string()))?,
-                content,
-                embedding,
-                metadata,
-                created_at: DateTime::parse_from_rfc3339(&created_at)
-                    .map_err(|e| MemoryError::StorageFailed(e.to_string()))?
-                    .with_timezone(&Utc),
-                updated_at: DateTime::parse_from_rfc3339(&updated_at)
-                    .map_err(|e| MemoryError::StorageFailed(e.to_string()))?
-                    .with_timezone(&Utc),
-            });
-        }
+        let entries = rows
+            .iter()
+            .map(Self::entry_from_row)
+            .collect::<Result<Vec<_>>>()?;
 
         debug!("Listed {} memory entries", entries.len());
         Ok(entries)
     }
 
+    async fn list_page(&self, offset: usize, limit: usize) -> Result<Vec<MemoryEntry>> {
+        let pool = self.pool()?;
+
+        let rows = sqlx::query(
+            "SELECT * FROM memories ORDER BY created_at ASC, id ASC LIMIT ?1 OFFSET ?2",
+        )
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(pool)
+        .await?;
+
+        rows.iter().map(Self::entry_from_row).collect()
+    }
+
     async fn clear(&mut self) -> Result<()> {
         let pool = self.pool()?;
 
         let result = sqlx::query("DELETE FROM memories").execute(pool).await?;
+        self.keyword_index.clear();
+        if let Some(index) = &mut self.vector_index {
+            index.clear();
+        }
 
         info!("Cleared {} memory entries", result.rows_affected());
         Ok(())
     }
 
+    async fn rebuild_index(&mut self) -> Result<()> {
+        let pool = self.pool()?;
+
+        // Both indexes are kept up to date on writes after this
+        let rows = sqlx::query("SELECT id, content, embedding FROM memories")
+            .fetch_all(pool)
+            .await?;
+        self.keyword_index.clear();
+        if let Some(index) = &mut self.vector_index {
+            index.clear();
+        }
+        for row in rows {
+            let id: String = row.get("id");
+            let Ok(id) = Uuid::parse_str(&id) else {
+                continue;
+            };
+            let content: String = row.get("content");
+            self.keyword_index.insert(id, &content);
+            if let Some(index) = &mut self.vector_index {
+                let embedding_blob: Vec<u8> = row.get("embedding");
+                index.insert(id, Self::deserialize_embedding(&embedding_blob));
+            }
+        }
+
+        debug!(
+            "Rebuilt search indexes over {} memories",
+            self.keyword_index.len()
+        );
+        Ok(())
+    }
+
     async fn stats(&self) -> Result<MemoryStats> {
         let pool = self.pool()?;
 
@@ -531,6 +1073,7 @@ impl MemoryStore for SqliteMemoryStore {
             total_memories: total_memories as usize,
             embedding_dimension: self.config.embedding_dimension,
             store_size_bytes: None, // Could be calculated by examining the database file
+            evicted_entries: self.evicted_entries,
         })
     }
 }
@@ -649,6 +1192,324 @@ mod tests {
         assert!(results[0].similarity > 0.8);
     }
 
+    #[tokio::test]
+    async fn test_hybrid_search_finds_exact_id() {
+        let mut store = create_test_store().await;
+
+        let embedding = |values: &[f32]| {
+            let mut embedding = values.to_vec();
+            embedding.resize(384, 0.0);
+            embedding
+        };
+
+        let ticket_id = store
+            .store(
+                "Ticket INC-4821 was escalated to the networking team".to_string(),
+                embedding(&[0.2, 1.0]),
+                HashMap::new(),
+            )
+            .await
+            .unwrap();
+        store
+            .store(
+                "Postmortem of last week's network outage".to_string(),
+                embedding(&[1.0, 0.0]),
+                HashMap::new(),
+            )
+            .await
+            .unwrap();
+
+        // A paraphrased query whose embedding sits closer to the postmortem
+        let query = "any follow up on INC-4821?";
+        let query_embedding = embedding(&[1.0, 0.1]);
+
+        let vector_results = store.search(query_embedding.clone(), 1, 0.0).await.unwrap();
+        assert_ne!(vector_results[0].entry.id, ticket_id);
+
+        let options = HybridSearchOptions::default().with_limit(1);
+        let hybrid_results = store
+            .search_hybrid(query, query_embedding, &options)
+            .await
+            .unwrap();
+        assert_eq!(hybrid_results[0].entry.id, ticket_id);
+    }
+
+    #[tokio::test]
+    async fn test_vector_index_excludes_deleted_entry_without_rebuild() {
+        let config = MemoryConfig {
+            database_url: Some("sqlite::memory:".to_string()),
+            embedding_dimension: 3,
+            vector_index: Some(crate::config::VectorIndexConfig {
+                max_tombstone_ratio: 0.9,
+            }),
+            ..Default::default()
+        };
+        let mut store = SqliteMemoryStore::new(config);
+        store.initialize().await.unwrap();
+
+        let mut ids = Vec::new();
+        for (content, embedding) in [
+            ("Arm calibration steps", vec![1.0, 0.0, 0.0]),
+            ("Gripper calibration notes", vec![0.9, 0.3, 0.0]),
+            ("Conveyor maintenance log", vec![0.0, 0.0, 1.0]),
+        ] {
+            let id = store
+                .store(content.to_string(), embedding, HashMap::new())
+                .await
+                .unwrap();
+            ids.push(id);
+        }
+
+        let results = store.search(vec![1.0, 0.0, 0.0], 2, 0.0).await.unwrap();
+        assert_eq!(results[0].entry.id, ids[0]);
+
+        store.delete(ids[0]).await.unwrap();
+        let results = store.search(vec![1.0, 0.0, 0.0], 2, 0.0).await.unwrap();
+        assert_eq!(results[0].entry.id, ids[1]);
+        assert!(results.iter().all(|r| r.entry.id != ids[0]));
+
+        // The delete was a tombstone in the live index, not a rebuild
+        let index = store.vector_index.as_ref().unwrap();
+        assert_eq!((index.len(), index.tombstones()), (2, 1));
+
+        store.rebuild_index().await.unwrap();
+        let index = store.vector_index.as_ref().unwrap();
+        assert_eq!((index.len(), index.tombstones()), (2, 0));
+    }
+
+    #[tokio::test]
+    async fn test_normalized_embeddings_rank_by_direction() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            database_url: Some(format!("sqlite://{}", dir.path().join("m.db").display())),
+            embedding_dimension: 2,
+            normalize_embeddings: true,
+            ..Default::default()
+        };
+        let mut store = SqliteMemoryStore::new(config.clone());
+        store.initialize().await.unwrap();
+
+        // By raw dot product the long vector would win any query
+        let long_id = store
+            .store("Long".to_string(), vec![30.0, 40.0], HashMap::new())
+            .await
+            .unwrap();
+        let aligned_id = store
+            .store("Aligned".to_string(), vec![0.5, 0.0], HashMap::new())
+            .await
+            .unwrap();
+
+        let stored = store.get(long_id).await.unwrap().unwrap();
+        assert!((stored.embedding[0] - 0.6).abs() < 1e-6);
+        assert!((stored.embedding[1] - 0.8).abs() < 1e-6);
+
+        // The query is normalized too, so similarities stay within [-1, 1]
+        let results = store.search(vec![5.0, 0.0], 2, 0.0).await.unwrap();
+        assert_eq!(results[0].entry.id, aligned_id);
+        assert!((results[0].similarity - 1.0).abs() < 1e-6);
+        assert_eq!(results[1].entry.id, long_id);
+        assert!((results[1].similarity - 0.6).abs() < 1e-6);
+
+        // The database won't reopen with raw embeddings
+        drop(store);
+        let mut raw = SqliteMemoryStore::new(MemoryConfig {
+            normalize_embeddings: false,
+            ..config
+        });
+        let err = raw.initialize().await.unwrap_err();
+        assert!(err.to_string().contains("normalized"));
+    }
+
+    #[tokio::test]
+    async fn test_ranking_prefers_recent_important_memories() {
+        let config = MemoryConfig {
+            database_url: Some("sqlite::memory:".to_string()),
+            embedding_dimension: 2,
+            ranking: Some(crate::config::MemoryRankingConfig::default()),
+            ..Default::default()
+        };
+        let mut store = SqliteMemoryStore::new(config);
+        store.initialize().await.unwrap();
+
+        let importance = |value: &str| HashMap::from([("importance".to_string(), value.into())]);
+        let stale_id = store
+            .store("Trivia".to_string(), vec![1.0, 0.0], importance("0.2"))
+            .await
+            .unwrap();
+        let key_id = store
+            .store("Key fact".to_string(), vec![1.0, 0.0], importance("0.9"))
+            .await
+            .unwrap();
+
+        let long_ago = Utc::now() - chrono::Duration::days(60);
+        sqlx::query("UPDATE memories SET last_accessed = ?1 WHERE id = ?2")
+            .bind(long_ago.to_rfc3339())
+            .bind(stale_id.to_string())
+            .execute(store.pool().unwrap())
+            .await
+            .unwrap();
+
+        let results = store.search(vec![1.0, 0.0], 2, 0.5).await.unwrap();
+        assert_eq!(results[0].similarity, results[1].similarity);
+        assert_eq!(results[0].entry.id, key_id);
+        assert_eq!(results[1].entry.id, stale_id);
+
+        // Being returned by the search counts as an access
+        let stale = store.get(stale_id).await.unwrap().unwrap();
+        assert!(stale.last_accessed > long_ago);
+        assert_eq!(stale.importance, 0.2);
+    }
+
+    #[tokio::test]
+    async fn test_max_entries_evicts_least_recently_accessed() {
+        let config = MemoryConfig {
+            database_url: Some("sqlite::memory:".to_string()),
+            embedding_dimension: 2,
+            max_entries: Some(3),
+            ..Default::default()
+        };
+        let mut store = SqliteMemoryStore::new(config);
+        store.initialize().await.unwrap();
+
+        let mut ids = HashMap::new();
+        for (content, embedding) in [("a", [1.0, 0.0]), ("b", [0.0, 1.0]), ("c", [1.0, 0.0])] {
+            let id = store
+                .store(content.to_string(), embedding.to_vec(), HashMap::new())
+                .await
+                .unwrap();
+            ids.insert(content, id);
+        }
+
+        // Searching touches "b", leaving "a" and then "c" least recently accessed
+        let results = store.search(vec![0.0, 1.0], 1, 0.5).await.unwrap();
+        assert_eq!(results[0].entry.id, ids["b"]);
+
+        for content in ["d", "e"] {
+            let id = store
+                .store(content.to_string(), vec![1.0, 1.0], HashMap::new())
+                .await
+                .unwrap();
+            ids.insert(content, id);
+        }
+
+        let stats = store.stats().await.unwrap();
+        assert_eq!(stats.total_memories, 3);
+        assert_eq!(stats.evicted_entries, 2);
+        assert!(store.get(ids["a"]).await.unwrap().is_none());
+        assert!(store.get(ids["c"]).await.unwrap().is_none());
+        for content in ["b", "d", "e"] {
+            assert!(store.get(ids[content]).await.unwrap().is_some());
+        }
+    }
+
+    async fn dedup_store(threshold: f32, action: MemoryDedupAction) -> SqliteMemoryStore {
+        let config = MemoryConfig {
+            database_url: Some("sqlite::memory:".to_string()),
+            embedding_dimension: 3,
+            dedup: Some(crate::config::MemoryDedupConfig {
+                similarity_threshold: threshold,
+                action,
+            }),
+            ..Default::default()
+        };
+        let mut store = SqliteMemoryStore::new(config);
+        store.initialize().await.unwrap();
+        store
+    }
+
+    #[tokio::test]
+    async fn test_insert_skips_near_duplicates_above_threshold() {
+        let original = vec![1.0, 0.0, 0.0];
+        // Cosine similarity to the original is about 0.995
+        let near_duplicate = vec![1.0, 0.1, 0.0];
+
+        let mut store = dedup_store(0.99, MemoryDedupAction::Skip).await;
+        let first = store
+            .insert(
+                "User likes tea".to_string(),
+                original.clone(),
+                HashMap::new(),
+            )
+            .await
+            .unwrap();
+        let outcome = store
+            .insert(
+                "User likes tea a lot".to_string(),
+                near_duplicate.clone(),
+                HashMap::new(),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(
+            outcome,
+            InsertOutcome::Skipped { duplicate_of, similarity }
+                if duplicate_of == first.id() && similarity > 0.99
+        ));
+        assert_eq!(store.stats().await.unwrap().total_memories, 1);
+
+        // The same pair is distinct enough under a stricter threshold
+        let mut store = dedup_store(0.999, MemoryDedupAction::Skip).await;
+        store
+            .insert("User likes tea".to_string(), original, HashMap::new())
+            .await
+            .unwrap();
+        let outcome = store
+            .insert(
+                "User likes tea a lot".to_string(),
+                near_duplicate,
+                HashMap::new(),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(outcome, InsertOutcome::Stored(_)));
+        assert_eq!(store.stats().await.unwrap().total_memories, 2);
+    }
+
+    #[tokio::test]
+    async fn test_insert_merges_duplicates_within_namespace() {
+        let mut store = dedup_store(0.9, MemoryDedupAction::Merge).await;
+        let namespaced = |namespace: &str, key: &str| {
+            HashMap::from([
+                (MEMORY_NAMESPACE_KEY.to_string(), namespace.to_string()),
+                (key.to_string(), "true".to_string()),
+            ])
+        };
+
+        let first = store
+            .insert(
+                "Deploys happen on Fridays".to_string(),
+                vec![0.0, 1.0, 0.0],
+                namespaced("ops", "seen"),
+            )
+            .await
+            .unwrap();
+        let merged = store
+            .insert(
+                "Deploys are on Fridays".to_string(),
+                vec![0.0, 1.0, 0.05],
+                namespaced("ops", "confirmed"),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(merged, InsertOutcome::Merged { into, .. } if into == first.id()));
+
+        let entry = store.get(first.id()).await.unwrap().unwrap();
+        assert_eq!(entry.content, "Deploys happen on Fridays");
+        assert_eq!(entry.metadata["seen"], "true");
+        assert_eq!(entry.metadata["confirmed"], "true");
+
+        // Another namespace's memory is never a duplicate
+        let other = store
+            .insert(
+                "Deploys are on Fridays".to_string(),
+                vec![0.0, 1.0, 0.0],
+                namespaced("sales", "seen"),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(other, InsertOutcome::Stored(id) if id != first.id()));
+    }
+
     #[test]
     fn test_cosine_similarity() {
         let a = vec![1.0, 0.0, 0.0];
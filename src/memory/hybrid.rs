@@ -0,0 +1,217 @@
+//! Keyword indexing and rank fusion for hybrid memory search
+//!
+//! Vector similarity is good at paraphrases but weak on exact tokens such as
+//! ticket IDs or proper nouns. [`KeywordIndex`] scores stored text with BM25,
+//! and [`reciprocal_rank_fusion`] merges its ranking with the vector ranking.
+
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// Options for [`MemoryStore::search_hybrid`](super::MemoryStore::search_hybrid)
+#[derive(Debug, Clone)]
+pub struct HybridSearchOptions {
+    /// Maximum number of results to return
+    pub limit: usize,
+
+    /// Weight of the vector ranking in `[0, 1]`; the keyword ranking gets `1 - vector_weight`
+    pub vector_weight: f32,
+
+    /// RRF constant; larger values flatten the difference between top ranks
+    pub rrf_k: f32,
+
+    /// Number of candidates taken from each ranking before fusion
+    pub candidates: usize,
+}
+
+impl Default for HybridSearchOptions {
+    fn default() -> Self {
+        Self {
+            limit: 5,
+            vector_weight: 0.5,
+            rrf_k: 60.0,
+            candidates: 50,
+        }
+    }
+}
+
+impl HybridSearchOptions {
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub fn with_vector_weight(mut self, vector_weight: f32) -> Self {
+        self.vector_weight = vector_weight.clamp(0.0, 1.0);
+        self
+    }
+}
+
+/// Split text into lowercase terms, keeping `-` and `_` inside identifiers
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+        .map(|token| token.trim_matches(|c| c == '-' || c == '_'))
+        .filter(|token| !token.is_empty())
+        .map(|token| token.to_lowercase())
+        .collect()
+}
+
+/// Incrementally maintained BM25 index over memory contents
+#[derive(Debug, Default)]
+pub struct KeywordIndex {
+    /// term -> (document -> term frequency)
+    postings: HashMap<String, HashMap<Uuid, u32>>,
+    doc_lengths: HashMap<Uuid, usize>,
+    total_length: usize,
+}
+
+impl KeywordIndex {
+    const K1: f32 = 1.2;
+    const B: f32 = 0.75;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Index a document, replacing any previous content for the same id
+    pub fn insert(&mut self, id: Uuid, content: &str) {
+        self.remove(id);
+
+        let terms = tokenize(content);
+        self.total_length += terms.len();
+        self.doc_lengths.insert(id, terms.len());
+        for term in terms {
+            *self
+                .postings
+                .entry(term)
+                .or_default()
+                .entry(id)
+                .or_insert(0) += 1;
+        }
+    }
+
+    /// Remove a document from the index
+    pub fn remove(&mut self, id: Uuid) {
+        let Some(length) = self.doc_lengths.remove(&id) else {
+            return;
+        };
+        self.total_length -= length;
+        self.postings.retain(|_, docs| {
+            docs.remove(&id);
+            !docs.is_empty()
+        });
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn len(&self) -> usize {
+        self.doc_lengths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.doc_lengths.is_empty()
+    }
+
+    /// Score documents against a query, best first. Documents with no matching term are omitted.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<(Uuid, f32)> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+
+        let doc_count = self.doc_lengths.len() as f32;
+        let avg_length = (self.total_length as f32 / doc_count).max(1.0);
+        let mut scores: HashMap<Uuid, f32> = HashMap::new();
+
+        let terms: HashSet<String> = tokenize(query).into_iter().collect();
+        for term in terms {
+            let Some(docs) = self.postings.get(&term) else {
+                continue;
+            };
+
+            let df = docs.len() as f32;
+            let idf = ((doc_count - df + 0.5) / (df + 0.5) + 1.0).ln();
+
+            for (id, &tf) in docs {
+                let tf = tf as f32;
+                let length = self.doc_lengths[id] as f32;
+                let norm = Self::K1 * (1.0 - Self::B + Self::B * length / avg_length);
+                *scores.entry(*id).or_insert(0.0) += idf * tf * (Self::K1 + 1.0) / (tf + norm);
+            }
+        }
+
+        let mut ranked: Vec<(Uuid, f32)> = scores.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        ranked.truncate(limit);
+        ranked
+    }
+}
+
+/// Fuse two rankings (best first) with weighted reciprocal rank fusion.
+///
+/// Each document scores `weight / (k + rank)` per list it appears in, with
+/// 1-based ranks. Returns `(id, fused score)` sorted best first.
+pub fn reciprocal_rank_fusion(
+    vector_ranking: &[Uuid],
+    keyword_ranking: &[Uuid],
+    vector_weight: f32,
+    k: f32,
+) -> Vec<(Uuid, f32)> {
+    let mut scores: HashMap<Uuid, f32> = HashMap::new();
+
+    for (ranking, weight) in [
+        (vector_ranking, vector_weight),
+        (keyword_ranking, 1.0 - vector_weight),
+    ] {
+        for (rank, id) in ranking.iter().enumerate() {
+            *scores.entry(*id).or_insert(0.0) += weight / (k + rank as f32 + 1.0);
+        }
+    }
+
+    let mut fused: Vec<(Uuid, f32)> = scores.into_iter().collect();
+    fused.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    fused
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize_keeps_identifiers() {
+        assert_eq!(
+            tokenize("Ticket INC-4821 (see build_id)."),
+            vec!["ticket", "inc-4821", "see", "build_id"]
+        );
+    }
+
+    #[test]
+    fn test_keyword_index_incremental_updates() {
+        let mut index = KeywordIndex::new();
+        let a = Uuid::new_v4();
+        let b = Uuid::new_v4();
+
+        index.insert(a, "rust ownership and borrowing");
+        index.insert(b, "cooking pasta");
+        assert_eq!(index.search("borrowing", 10)[0].0, a);
+
+        index.insert(a, "gardening tips");
+        assert!(index.search("borrowing", 10).is_empty());
+
+        index.remove(b);
+        assert!(index.search("pasta", 10).is_empty());
+        assert_eq!(index.len(), 1);
+    }
+
+    #[test]
+    fn test_rrf_respects_weight() {
+        let a = Uuid::new_v4();
+        let b = Uuid::new_v4();
+
+        let fused = reciprocal_rank_fusion(&[a, b], &[b, a], 0.9, 60.0);
+        assert_eq!(fused[0].0, a);
+
+        let fused = reciprocal_rank_fusion(&[a, b], &[b, a], 0.1, 60.0);
+        assert_eq!(fused[0].0, b);
+    }
+}
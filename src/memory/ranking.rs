@@ -0,0 +1,86 @@
+//! Re-ranking of similarity search results by importance and recency
+//!
+//! Raw similarity treats every memory alike, so old trivia can outrank a key
+//! fact used yesterday. With [`MemoryRankingConfig`] set, results are ordered
+//! by a weighted sum of their similarity, their importance and how recently
+//! they were last accessed.
+
+use super::SearchResult;
+use crate::config::MemoryRankingConfig;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+/// Metadata key read as an entry's importance when it is stored
+pub const MEMORY_IMPORTANCE_KEY: &str = "importance";
+
+/// Importance of entries stored without one
+pub const DEFAULT_IMPORTANCE: f32 = 0.5;
+
+/// Importance recorded in `metadata`, clamped to 0.0..=1.0
+pub fn importance_from_metadata(metadata: &HashMap<String, String>) -> f32 {
+    metadata
+        .get(MEMORY_IMPORTANCE_KEY)
+        .and_then(|value| value.parse::<f32>().ok())
+        .filter(|importance| importance.is_finite())
+        .map_or(DEFAULT_IMPORTANCE, |importance| importance.clamp(0.0, 1.0))
+}
+
+/// Recency of an access at `last_accessed`: 1.0 now, halving every
+/// `half_life_hours` after
+pub fn recency(last_accessed: DateTime<Utc>, now: DateTime<Utc>, half_life_hours: f64) -> f32 {
+    if half_life_hours <= 0.0 {
+        return 0.0;
+    }
+    let age_hours = (now - last_accessed).num_seconds().max(0) as f64 / 3600.0;
+    0.5f64.powf(age_hours / half_life_hours) as f32
+}
+
+/// Combined score of a search result
+pub fn score(config: &MemoryRankingConfig, result: &SearchResult, now: DateTime<Utc>) -> f32 {
+    config.similarity_weight * result.similarity
+        + config.importance_weight * result.entry.importance
+        + config.recency_weight
+            * recency(
+                result.entry.last_accessed,
+                now,
+                config.recency_half_life_hours,
+            )
+}
+
+/// Sort `results` by combined score, highest first
+pub fn rerank(config: &MemoryRankingConfig, results: &mut [SearchResult], now: DateTime<Utc>) {
+    results.sort_by(|a, b| {
+        score(config, b, now)
+            .partial_cmp(&score(config, a, now))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_recency_halves_each_half_life() {
+        let now = Utc::now();
+        assert!((recency(now, now, 24.0) - 1.0).abs() < 1e-6);
+        assert!((recency(now - Duration::hours(24), now, 24.0) - 0.5).abs() < 1e-6);
+        assert!((recency(now - Duration::hours(48), now, 24.0) - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_importance_from_metadata() {
+        let metadata = |value: &str| HashMap::from([("importance".to_string(), value.into())]);
+        assert_eq!(
+            importance_from_metadata(&HashMap::new()),
+            DEFAULT_IMPORTANCE
+        );
+        assert_eq!(importance_from_metadata(&metadata("0.9")), 0.9);
+        assert_eq!(importance_from_metadata(&metadata("7")), 1.0);
+        assert_eq!(
+            importance_from_metadata(&metadata("high")),
+            DEFAULT_IMPORTANCE
+        );
+    }
+}
@@ -0,0 +1,123 @@
+//! Memory shared by the agents of a workspace
+//!
+//! Each agent keeps its own memory store. A [`SharedMemory`] is a namespace in
+//! a store several agents hold at once, so that what one teammate learns can
+//! be found by the others. Searches through it only see entries of its own
+//! namespace, letting one store serve many workspaces.
+
+use super::{MemoryStore, SearchResult};
+use crate::error::Result;
+use crate::tools::memory_search::MEMORY_NAMESPACE_KEY;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// Metadata key recording which agent stored a shared memory
+pub const MEMORY_AUTHOR_KEY: &str = "author";
+
+/// Namespace of a workspace's shared memories
+pub fn workspace_namespace(workspace_id: &str) -> String {
+    format!("workspace:{}", workspace_id)
+}
+
+/// A namespace in a memory store shared between agents
+#[derive(Clone)]
+pub struct SharedMemory {
+    store: Arc<RwLock<Box<dyn MemoryStore>>>,
+    namespace: String,
+}
+
+impl std::fmt::Debug for SharedMemory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedMemory")
+            .field("namespace", &self.namespace)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SharedMemory {
+    pub fn new(store: Arc<RwLock<Box<dyn MemoryStore>>>, namespace: impl Into<String>) -> Self {
+        Self {
+            store,
+            namespace: namespace.into(),
+        }
+    }
+
+    /// The shared memory of a workspace in `store`
+    pub fn for_workspace(store: Arc<RwLock<Box<dyn MemoryStore>>>, workspace_id: &str) -> Self {
+        Self::new(store, workspace_namespace(workspace_id))
+    }
+
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    /// Store a memory in this namespace on behalf of `author`
+    pub async fn store(
+        &self,
+        author: &str,
+        content: String,
+        embedding: Vec<f32>,
+        mut metadata: HashMap<String, String>,
+    ) -> Result<Uuid> {
+        metadata.insert(MEMORY_NAMESPACE_KEY.to_string(), self.namespace.clone());
+        metadata
+            .entry(MEMORY_AUTHOR_KEY.to_string())
+            .or_insert_with(|| author.to_string());
+        self.store
+            .write()
+            .await
+            .store(content, embedding, metadata)
+            .await
+    }
+
+    /// Similarity search over this namespace
+    pub async fn search(
+        &self,
+        query_embedding: Vec<f32>,
+        limit: usize,
+        threshold: f32,
+    ) -> Result<Vec<SearchResult>> {
+        // Other namespaces are filtered out afterwards, so fetch everything
+        // above the threshold
+        let results = self
+            .store
+            .read()
+            .await
+            .search(query_embedding, usize::MAX, threshold)
+            .await?;
+        Ok(self.in_namespace(results, limit))
+    }
+
+    /// Keyword search over this namespace
+    pub async fn search_keywords(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
+        let results = self
+            .store
+            .read()
+            .await
+            .search_keywords(query, usize::MAX)
+            .await?;
+        Ok(self.in_namespace(results, limit))
+    }
+
+    fn in_namespace(&self, mut results: Vec<SearchResult>, limit: usize) -> Vec<SearchResult> {
+        results.retain(|result| {
+            result.entry.metadata.get(MEMORY_NAMESPACE_KEY) == Some(&self.namespace)
+        });
+        results.truncate(limit);
+        results
+    }
+}
+
+/// The `limit` most similar of two sets of results
+pub fn merge_results(
+    mut results: Vec<SearchResult>,
+    shared: Vec<SearchResult>,
+    limit: usize,
+) -> Vec<SearchResult> {
+    results.extend(shared);
+    results.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+    results.truncate(limit);
+    results
+}
@@ -0,0 +1,150 @@
+//! In-memory vector index for memory search
+//!
+//! Without a vector extension, SQLite search has to load and score every
+//! stored embedding. [`VectorIndex`] keeps the embeddings in memory and is
+//! updated in place on every write, so it never needs a full rebuild to stay
+//! current. Deletes only tombstone an entry's slot; tombstoned slots are
+//! skipped by searches and reclaimed by [`VectorIndex::compact`].
+
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Flat cosine-similarity index with tombstoned deletes
+#[derive(Debug, Default)]
+pub struct VectorIndex {
+    /// Slots in insertion order; `None` marks a tombstone
+    slots: Vec<Option<(Uuid, Vec<f32>)>>,
+    /// id -> live slot
+    positions: HashMap<Uuid, usize>,
+    /// Embeddings and queries are unit length, so similarity is the dot product
+    unit_vectors: bool,
+}
+
+impl VectorIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Index of unit-length embeddings, scored by dot product
+    pub fn normalized() -> Self {
+        Self {
+            unit_vectors: true,
+            ..Self::default()
+        }
+    }
+
+    /// Index an embedding, tombstoning any previous one for the same id
+    pub fn insert(&mut self, id: Uuid, embedding: Vec<f32>) {
+        self.remove(id);
+        self.positions.insert(id, self.slots.len());
+        self.slots.push(Some((id, embedding)));
+    }
+
+    /// Tombstone the embedding for `id`
+    pub fn remove(&mut self, id: Uuid) {
+        if let Some(position) = self.positions.remove(&id) {
+            self.slots[position] = None;
+        }
+    }
+
+    /// Drop tombstoned slots
+    pub fn compact(&mut self) {
+        self.slots.retain(Option::is_some);
+        self.positions = self
+            .slots
+            .iter()
+            .enumerate()
+            .filter_map(|(position, slot)| slot.as_ref().map(|(id, _)| (*id, position)))
+            .collect();
+    }
+
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.positions.clear();
+    }
+
+    /// Number of live embeddings
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Number of tombstoned slots awaiting compaction
+    pub fn tombstones(&self) -> usize {
+        self.slots.len() - self.positions.len()
+    }
+
+    /// Fraction of slots that are tombstones
+    pub fn tombstone_ratio(&self) -> f32 {
+        if self.slots.is_empty() {
+            0.0
+        } else {
+            self.tombstones() as f32 / self.slots.len() as f32
+        }
+    }
+
+    /// Live ids whose cosine similarity to `query` is at least `threshold`,
+    /// most similar first
+    pub fn search(&self, query: &[f32], limit: usize, threshold: f32) -> Vec<(Uuid, f32)> {
+        let mut results: Vec<(Uuid, f32)> = self
+            .slots
+            .iter()
+            .flatten()
+            .map(|(id, embedding)| {
+                let similarity = if self.unit_vectors {
+                    dot_product(query, embedding)
+                } else {
+                    cosine_similarity(query, embedding)
+                };
+                (*id, similarity)
+            })
+            .filter(|(_, similarity)| *similarity >= threshold)
+            .collect();
+
+        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        results.truncate(limit);
+        results
+    }
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    super::SqliteMemoryStore::cosine_similarity(a, b)
+}
+
+fn dot_product(a: &[f32], b: &[f32]) -> f32 {
+    super::SqliteMemoryStore::dot_product(a, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deletes_are_tombstoned_until_compaction() {
+        let mut index = VectorIndex::new();
+        let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        index.insert(a, vec![1.0, 0.0]);
+        index.insert(b, vec![0.0, 1.0]);
+        index.insert(c, vec![0.7, 0.7]);
+
+        assert_eq!(index.search(&[1.0, 0.0], 1, 0.0)[0].0, a);
+
+        index.remove(a);
+        let results = index.search(&[1.0, 0.0], 3, 0.0);
+        assert_eq!(results[0].0, c);
+        assert!(results.iter().all(|(id, _)| *id != a));
+        assert_eq!((index.len(), index.tombstones()), (2, 1));
+
+        // Re-inserting tombstones the replaced embedding
+        index.insert(b, vec![1.0, 0.0]);
+        assert_eq!(index.search(&[1.0, 0.0], 1, 0.0)[0].0, b);
+        assert_eq!((index.len(), index.tombstones()), (2, 2));
+
+        index.compact();
+        assert_eq!((index.len(), index.tombstones()), (2, 0));
+        assert_eq!(index.search(&[1.0, 0.0], 1, 0.0)[0].0, b);
+    }
+}
@@ -0,0 +1,213 @@
+//! Prometheus metrics for agent activity
+//!
+//! Counters and histograms are kept in a process-wide [`Registry`] and updated
+//! by the agent, the LLM providers, the response cache and the workflow
+//! engine as they work. [`render`] returns them in the Prometheus text
+//! exposition format, which the API server serves at `GET /metrics`.
+
+use crate::error::ProviderErrorKind;
+use crate::llm::pricing::TokenUsage;
+use crate::workflow::SuspendReason;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, Opts, Registry, TextEncoder,
+};
+use std::sync::LazyLock;
+use std::time::Duration;
+
+/// Content type of [`render`]'s output
+pub const CONTENT_TYPE: &str = prometheus::TEXT_FORMAT;
+
+struct Metrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    request_duration: HistogramVec,
+    tokens: IntCounterVec,
+    tool_calls: IntCounterVec,
+    cache_hits: IntCounter,
+    cache_misses: IntCounter,
+    workflow_suspensions: IntCounterVec,
+    provider_errors: IntCounterVec,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new();
+        let counter_vec = |name: &str, help: &str, labels: &[&str]| {
+            let counter = IntCounterVec::new(Opts::new(name, help), labels)
+                .expect("valid counter definition");
+            registry
+                .register(Box::new(counter.clone()))
+                .expect("metric registered once");
+            counter
+        };
+        let counter = |name: &str, help: &str| {
+            let counter = IntCounter::new(name, help).expect("valid counter definition");
+            registry
+                .register(Box::new(counter.clone()))
+                .expect("metric registered once");
+            counter
+        };
+
+        let requests = counter_vec(
+            "agent_requests_total",
+            "Requests processed by agents",
+            &["outcome"],
+        );
+        let request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "agent_request_duration_seconds",
+                "Time taken to process a request",
+            )
+            .buckets(vec![0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0]),
+            &["outcome"],
+        )
+        .expect("valid histogram definition");
+        registry
+            .register(Box::new(request_duration.clone()))
+            .expect("metric registered once");
+        let tokens = counter_vec(
+            "agent_tokens_total",
+            "Tokens reported by LLM providers",
+            &["model", "kind"],
+        );
+        let tool_calls = counter_vec(
+            "agent_tool_calls_total",
+            "Tool calls made by agents",
+            &["tool", "outcome"],
+        );
+        let cache_hits = counter(
+            "agent_cache_hits_total",
+            "LLM responses served from the cache",
+        );
+        let cache_misses = counter(
+            "agent_cache_misses_total",
+            "LLM cache lookups that found no usable entry",
+        );
+        let workflow_suspensions = counter_vec(
+            "agent_workflow_suspensions_total",
+            "Workflows suspended",
+            &["reason"],
+        );
+        let provider_errors = counter_vec(
+            "agent_provider_errors_total",
+            "Error responses from LLM providers",
+            &["kind"],
+        );
+
+        Self {
+            registry,
+            requests,
+            request_duration,
+            tokens,
+            tool_calls,
+            cache_hits,
+            cache_misses,
+            workflow_suspensions,
+            provider_errors,
+        }
+    }
+}
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+fn outcome(success: bool) -> &'static str {
+    if success {
+        "success"
+    } else {
+        "error"
+    }
+}
+
+/// Record a processed request and how long it took
+pub fn record_request(success: bool, duration: Duration) {
+    let outcome = outcome(success);
+    METRICS.requests.with_label_values(&[outcome]).inc();
+    METRICS
+        .request_duration
+        .with_label_values(&[outcome])
+        .observe(duration.as_secs_f64());
+}
+
+/// Record the tokens a generation by `model` used
+pub fn record_tokens(model: &str, usage: &TokenUsage) {
+    METRICS
+        .tokens
+        .with_label_values(&[model, "prompt"])
+        .inc_by(usage.input_tokens.into());
+    METRICS
+        .tokens
+        .with_label_values(&[model, "completion"])
+        .inc_by(usage.output_tokens.into());
+}
+
+/// Tool label of calls to tools the agent does not know
+pub const OTHER_TOOL: &str = "other";
+
+/// Record a call to `tool`. Calls to tools outside the agent's known tools
+/// are counted under [`OTHER_TOOL`], since models can name any tool.
+pub fn record_tool_call(tool: &str, known: bool, success: bool) {
+    let tool = if known { tool } else { OTHER_TOOL };
+    METRICS
+        .tool_calls
+        .with_label_values(&[tool, outcome(success)])
+        .inc();
+}
+
+/// Record an LLM cache lookup
+pub fn record_cache_lookup(hit: bool) {
+    if hit {
+        METRICS.cache_hits.inc();
+    } else {
+        METRICS.cache_misses.inc();
+    }
+}
+
+/// Record a workflow suspension
+pub fn record_workflow_suspension(reason: &SuspendReason) {
+    METRICS
+        .workflow_suspensions
+        .with_label_values(&[reason.kind()])
+        .inc();
+}
+
+/// Record an error response from an LLM provider
+pub fn record_provider_error(kind: ProviderErrorKind) {
+    METRICS
+        .provider_errors
+        .with_label_values(&[&format!("{:?}", kind)])
+        .inc();
+}
+
+/// All metrics in the Prometheus text exposition format
+pub fn render() -> String {
+    let mut buffer = Vec::new();
+    if let Err(e) = TextEncoder::new().encode(&METRICS.registry.gather(), &mut buffer) {
+        tracing::error!("Failed to encode metrics: {}", e);
+    }
+    String::from_utf8(buffer).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_includes_recorded_metrics() {
+        record_tokens("metrics-test-model", &TokenUsage::new(12, 5));
+        record_workflow_suspension(&SuspendReason::RateLimit);
+        record_provider_error(ProviderErrorKind::RateLimited);
+        record_tool_call("metrics_test_tool", true, true);
+        record_tool_call("metrics_test_hallucinated_tool", false, false);
+
+        let text = render();
+        assert!(text.contains(r#"agent_tokens_total{kind="prompt",model="metrics-test-model"} 12"#));
+        assert!(
+            text.contains(r#"agent_tokens_total{kind="completion",model="metrics-test-model"} 5"#)
+        );
+        assert!(text.contains(r#"agent_workflow_suspensions_total{reason="rate_limit"}"#));
+        assert!(text.contains(r#"agent_provider_errors_total{kind="RateLimited"}"#));
+        assert!(text.contains(r#"agent_tool_calls_total{outcome="success",tool="metrics_test_tool"}"#));
+        assert!(text.contains(r#"agent_tool_calls_total{outcome="error",tool="other"}"#));
+        assert!(!text.contains("metrics_test_hallucinated_tool"));
+    }
+}
@@ -5,14 +5,31 @@
 //! in robotics and advanced technology sectors.
 
 pub mod a2a_local;
+pub mod artifacts;
 pub mod coordinator;
+pub mod experience;
 pub mod knowledge_helpers;
 pub mod prompts;
 
+use crate::config::LlmConfig;
 use crate::error::Result;
+use crate::ids::new_id;
+use crate::tools::tool_name_matches;
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use uuid::Uuid;
+
+/// Tools every role may use
+const COMMON_TOOLS: &[&str] = &["datetime_info", "datetime", "memory_search"];
+
+/// Filesystem and host tools for hands-on technical roles
+const WORKSTATION_TOOLS: &[&str] = &[
+    "system_info",
+    "file_*",
+    "read_file",
+    "write_file",
+    "list_directory",
+];
 
 /// Organizational role for complex robotics organizations
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -297,6 +314,140 @@ impl OrganizationRole {
         }
     }
 
+    /// The role this one reports to and escalates stuck work to. Every chain
+    /// of managers ends at the CEO, who reports to no one.
+    pub fn reports_to(&self) -> Option<OrganizationRole> {
+        let manager = match self {
+            Self::ChiefExecutiveOfficer => return None,
+
+            Self::ChiefTechnologyOfficer
+            | Self::ChiefOperatingOfficer
+            | Self::ChiefFinancialOfficer
+            | Self::ChiefProductOfficer
+            | Self::ChiefMarketingOfficer
+            | Self::VPSales
+            | Self::CounselEmploymentCompensation
+            | Self::CounselCommercialTrade => Self::ChiefExecutiveOfficer,
+
+            Self::VPEngineering | Self::VPResearchDevelopment | Self::HeadOfPhysicalRobotSafety => {
+                Self::ChiefTechnologyOfficer
+            }
+
+            Self::VPOperations
+            | Self::DirectorOfOperations
+            | Self::DirectorOfPeople
+            | Self::StrategicPlanningManager => Self::ChiefOperatingOfficer,
+
+            Self::PayrollAccountant => Self::ChiefFinancialOfficer,
+
+            Self::PrincipalProductManager | Self::DesignDirector => Self::ChiefProductOfficer,
+
+            // Research
+            Self::ResearchEngineerScaling
+            | Self::ResearchEngineerAutonomy
+            | Self::ResearchEngineerWorldModels
+            | Self::ResearchEngineerRL
+            | Self::ResearchEngineerDataInfrastructure
+            | Self::ResearchEngineerRobotCharacter
+            | Self::ResearchEngineerRobotics
+            | Self::RoboticsScientist
+            | Self::AIResident
+            | Self::MechanicalEngineerAllLevels
+            | Self::RDEngineerHumanoidCore
+            | Self::SoftgoodsEngineerPrototyping
+            | Self::TestEngineerRD => Self::VPResearchDevelopment,
+
+            // Engineering
+            Self::SoftwareEngineerSimulation
+            | Self::SoftwareEngineerTeleoperation
+            | Self::SoftwareEngineerPlatforms
+            | Self::SoftwareEngineerOperatingSystem
+            | Self::SoftwareEngineerDevOps
+            | Self::SoftwareEngineerEmbeddedSystems
+            | Self::SoftwareEngineerSystems
+            | Self::SoftwareEngineerFrontend
+            | Self::SoftwareEngineerCloudInfrastructure
+            | Self::SoftwareEngineerERPSystems
+            | Self::ProductSecurityEngineerOperatingSystem
+            | Self::ProductSecurityEngineerCloudInfrastructure
+            | Self::ProductSecurityEngineerCryptography
+            | Self::NetworkSecurityEngineer
+            | Self::PrincipalEnterpriseITEngineer
+            | Self::TechnicalLeadElectricalEngineering
+            | Self::SeniorRoboticsEngineerControls
+            | Self::SeniorRoboticsEngineerSoftware
+            | Self::SeniorAudioSystemsEngineer
+            | Self::TechnicalProgramManager
+            | Self::EngineeringProgramManager => Self::VPEngineering,
+            Self::EnterpriseEngineer => Self::PrincipalEnterpriseITEngineer,
+            Self::ElectricalEngineerEntryLevel
+            | Self::ElectricalEngineerBatteryCharger
+            | Self::HardcoreElectricalEngineer
+            | Self::EMIEMCEngineer
+            | Self::WiringHarnessingEngineer
+            | Self::ElectricalEngineeringIntern => Self::TechnicalLeadElectricalEngineering,
+            Self::MechanicalEngineeringIntern => Self::MechanicalEngineerAllLevels,
+            Self::RoboticsEngineerControlsTesting => Self::SeniorRoboticsEngineerControls,
+
+            // Manufacturing, supply chain and quality
+            Self::SeniorManagerProduction
+            | Self::GlobalSupplyManagerStructures
+            | Self::GlobalSupplyManagerMotorsMagnets
+            | Self::NPIProjectManager
+            | Self::QualityEngineerManufacturing
+            | Self::DataAnalyst => Self::VPOperations,
+            Self::ManufacturingEngineer
+            | Self::AutomationEngineerManufacturing
+            | Self::TestEngineerManufacturing
+            | Self::ProductionLead => Self::SeniorManagerProduction,
+            Self::BuildQualityEngineerElectrical
+            | Self::BuildQualityEngineerMechanical
+            | Self::AssemblyTechnician
+            | Self::CNCOperator
+            | Self::CNCProgrammer => Self::ProductionLead,
+            Self::SupplierDevelopmentEngineerStructures => Self::GlobalSupplyManagerStructures,
+            Self::SupplierDevelopmentEngineerMotorsMagnets
+            | Self::SupplierDevelopmentEngineerEEE => Self::GlobalSupplyManagerMotorsMagnets,
+            Self::NPIPlanner => Self::NPIProjectManager,
+            Self::QualityInspectionSpecialist => Self::QualityEngineerManufacturing,
+
+            // Operations and service
+            Self::SrServiceTrainingEngineer
+            | Self::FacilitiesManager
+            | Self::EnvironmentalHealthSafetyManager
+            | Self::RiskManagementSpecialist => Self::DirectorOfOperations,
+            Self::SrRobotServiceTechnician | Self::RobotOperator => Self::SrServiceTrainingEngineer,
+
+            // Product and design
+            Self::SeniorProductManager => Self::PrincipalProductManager,
+            Self::ProductManager => Self::SeniorProductManager,
+            Self::PrincipalProductDesigner | Self::UXResearcher | Self::IndustrialDesigner => {
+                Self::DesignDirector
+            }
+
+            // People
+            Self::TalentAcquisitionManager
+            | Self::LearningDevelopmentManager
+            | Self::OrganizationalDevelopmentSpecialist => Self::DirectorOfPeople,
+            Self::SeniorRecruiter => Self::TalentAcquisitionManager,
+
+            // Marketing and sales
+            Self::ProductMarketingManager
+            | Self::TechnicalMarketingManager
+            | Self::CommunicationsManager => Self::ChiefMarketingOfficer,
+            Self::DeveloperAdvocate => Self::TechnicalMarketingManager,
+            Self::ContentStrategist => Self::CommunicationsManager,
+            Self::EnterpriseAccountExecutive
+            | Self::SalesEngineer
+            | Self::CustomerSuccessManager
+            | Self::TechnicalAccountManager
+            | Self::SolutionsArchitect
+            | Self::BusinessDevelopmentManager
+            | Self::PartnershipManager => Self::VPSales,
+        };
+        Some(manager)
+    }
+
     /// Get role capabilities and expertise
     pub fn capabilities(&self) -> Vec<String> {
         match self {
@@ -335,6 +486,82 @@ impl OrganizationRole {
         }
     }
 
+    /// Tool name patterns this role may use, in the format of
+    /// [`tool_name_matches`]. Agents spawned for the role only see these tools.
+    pub fn allowed_tools(&self) -> Vec<String> {
+        let role_tools: &[&str] = match self.category() {
+            RoleCategory::ResearchAI
+            | RoleCategory::SoftwareEngineering
+            | RoleCategory::Infrastructure
+            | RoleCategory::Security => &["execute_*", "code_*", "git_*", "search_*", "web_*"],
+            RoleCategory::HardwareEngineering | RoleCategory::RoboticsEngineering => {
+                &["cad_*", "simulation_*", "search_*", "web_*"]
+            }
+            RoleCategory::Manufacturing
+            | RoleCategory::SupplyChainQuality
+            | RoleCategory::ServiceSupport => {
+                &["manufacturing_*", "inventory_*", "quality_*", "search_*"]
+            }
+            RoleCategory::Specializations => &["search_*", "web_*"],
+            RoleCategory::LegalFinance
+            | RoleCategory::ExecutiveLeadership
+            | RoleCategory::StrategicBusiness
+            | RoleCategory::PeopleCulture
+            | RoleCategory::MarketingCommunications
+            | RoleCategory::CustomerSuccessSales
+            | RoleCategory::OperationsFacilities
+            | RoleCategory::DesignUX => &["search_*", "web_*", "location_info"],
+        };
+
+        let needs_workstation = matches!(
+            self.category(),
+            RoleCategory::ResearchAI
+                | RoleCategory::SoftwareEngineering
+                | RoleCategory::Infrastructure
+                | RoleCategory::Security
+                | RoleCategory::HardwareEngineering
+                | RoleCategory::RoboticsEngineering
+                | RoleCategory::Manufacturing
+                | RoleCategory::SupplyChainQuality
+                | RoleCategory::ServiceSupport
+                | RoleCategory::Specializations
+        );
+
+        COMMON_TOOLS
+            .iter()
+            .chain(if needs_workstation {
+                WORKSTATION_TOOLS
+            } else {
+                &[]
+            })
+            .chain(role_tools)
+            .map(|tool| tool.to_string())
+            .collect()
+    }
+
+    /// Sampling params agents spawned for this role generate with: research
+    /// and creative roles sample more freely, roles whose work must be exact
+    /// stay close to the most likely output
+    pub fn sampling_params(&self) -> SamplingParams {
+        match self.category() {
+            RoleCategory::ResearchAI
+            | RoleCategory::MarketingCommunications
+            | RoleCategory::DesignUX => SamplingParams::new(0.9).with_top_p(0.95),
+            RoleCategory::Security
+            | RoleCategory::Manufacturing
+            | RoleCategory::SupplyChainQuality
+            | RoleCategory::LegalFinance => SamplingParams::new(0.2).with_top_p(0.8),
+            _ => SamplingParams::new(0.5).with_top_p(0.9),
+        }
+    }
+
+    /// Whether this role may use the named tool
+    pub fn allows_tool(&self, tool_name: &str) -> bool {
+        self.allowed_tools()
+            .iter()
+            .any(|pattern| tool_name_matches(pattern, tool_name))
+    }
+
     /// Get typical collaborators for this role
     pub fn typical_collaborators(&self) -> Vec<OrganizationRole> {
         match self {
@@ -372,6 +599,37 @@ impl OrganizationRole {
     }
 }
 
+/// Sampling settings for an organization agent's generations
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SamplingParams {
+    pub temperature: f32,
+    /// Nucleus sampling cutoff; the provider default applies when unset
+    #[serde(default)]
+    pub top_p: Option<f32>,
+}
+
+impl SamplingParams {
+    pub fn new(temperature: f32) -> Self {
+        Self {
+            temperature,
+            top_p: None,
+        }
+    }
+
+    pub fn with_top_p(mut self, top_p: f32) -> Self {
+        self.top_p = Some(top_p);
+        self
+    }
+
+    /// Set these params on `config`, keeping its `top_p` when none is given
+    pub fn apply(&self, config: &mut LlmConfig) {
+        config.temperature = self.temperature;
+        if self.top_p.is_some() {
+            config.top_p = self.top_p;
+        }
+    }
+}
+
 /// Role category for organizational grouping
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum RoleCategory {
@@ -405,6 +663,9 @@ pub struct OrganizationAgent {
     pub current_tasks: Vec<String>,
     pub status: AgentStatus,
     pub capabilities: Vec<String>,
+    /// Sampling params to use instead of the role's defaults
+    #[serde(default)]
+    pub sampling: Option<SamplingParams>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -417,16 +678,35 @@ pub enum AgentStatus {
 impl OrganizationAgent {
     pub fn new(name: String, role: OrganizationRole) -> Self {
         Self {
-            id: Uuid::new_v4().to_string(),
+            id: new_id().to_string(),
             name,
             capabilities: role.capabilities(),
             role,
             workspace_memberships: Vec::new(),
             current_tasks: Vec::new(),
             status: AgentStatus::Available,
+            sampling: None,
         }
     }
 
+    /// Sample with `sampling` instead of the role's defaults
+    pub fn with_sampling(mut self, sampling: SamplingParams) -> Self {
+        self.sampling = Some(sampling);
+        self
+    }
+
+    /// Sampling params this agent generates with
+    pub fn sampling_params(&self) -> SamplingParams {
+        self.sampling.unwrap_or_else(|| self.role.sampling_params())
+    }
+
+    /// Use `id` instead of a random one, for agents that must keep the same
+    /// id across runs
+    pub fn with_id(mut self, id: impl Into<String>) -> Self {
+        self.id = id.into();
+        self
+    }
+
     pub fn join_workspace(&mut self, workspace_id: String) {
         if !self.workspace_memberships.contains(&workspace_id) {
             self.workspace_memberships.push(workspace_id);
@@ -461,7 +741,7 @@ pub struct CollaborativeWorkspace {
 impl CollaborativeWorkspace {
     pub fn new(name: String, description: String) -> Self {
         Self {
-            id: Uuid::new_v4().to_string(),
+            id: new_id().to_string(),
             name,
             description,
             member_agents: Vec::new(),
@@ -498,6 +778,19 @@ pub struct WorkspaceTask {
     pub priority: TaskPriority,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub completed_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Reassignments made by escalating this task, oldest first
+    #[serde(default)]
+    pub escalations: Vec<TaskEscalation>,
+    /// Times the task has been executed
+    #[serde(default)]
+    pub attempts: u32,
+    /// Times a failed task may be executed again
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+}
+
+fn default_max_retries() -> u32 {
+    2
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -507,6 +800,17 @@ pub enum TaskStatus {
     Blocked,
     UnderReview,
     Completed,
+    Failed,
+}
+
+/// Record of a task being reassigned up the reporting line
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TaskEscalation {
+    pub from_agents: Vec<String>,
+    pub to_agent: String,
+    /// Status that triggered the escalation
+    pub status: TaskStatus,
+    pub escalated_at: chrono::DateTime<chrono::Utc>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
@@ -520,7 +824,7 @@ pub enum TaskPriority {
 impl WorkspaceTask {
     pub fn new(title: String, description: String, assigned_to: Vec<String>) -> Self {
         Self {
-            id: Uuid::new_v4().to_string(),
+            id: new_id().to_string(),
             title,
             description,
             assigned_to,
@@ -529,6 +833,9 @@ impl WorkspaceTask {
             priority: TaskPriority::Medium,
             created_at: chrono::Utc::now(),
             completed_at: None,
+            escalations: Vec::new(),
+            attempts: 0,
+            max_retries: default_max_retries(),
         }
     }
 
@@ -542,6 +849,11 @@ impl WorkspaceTask {
         self
     }
 
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
     pub fn start(&mut self) {
         self.status = TaskStatus::InProgress;
     }
@@ -550,22 +862,39 @@ impl WorkspaceTask {
         self.status = TaskStatus::Completed;
         self.completed_at = Some(chrono::Utc::now());
     }
+
+    pub fn fail(&mut self) {
+        self.status = TaskStatus::Failed;
+    }
+
+    /// Whether the task is stuck and should go up the reporting line
+    pub fn needs_escalation(&self) -> bool {
+        matches!(self.status, TaskStatus::Failed | TaskStatus::Blocked)
+    }
+
+    /// Whether the task failed and has retries left
+    pub fn can_retry(&self) -> bool {
+        self.status == TaskStatus::Failed && self.attempts <= self.max_retries
+    }
 }
 
-/// Organization that manages agents and workspaces
+/// Organization that manages agents and workspaces.
+///
+/// Agents and workspaces iterate in the order they were added, so reports and
+/// spawn order are the same on every run.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Organization {
     pub name: String,
-    pub agents: HashMap<String, OrganizationAgent>,
-    pub workspaces: HashMap<String, CollaborativeWorkspace>,
+    pub agents: IndexMap<String, OrganizationAgent>,
+    pub workspaces: IndexMap<String, CollaborativeWorkspace>,
 }
 
 impl Organization {
     pub fn new(name: String) -> Self {
         Self {
             name,
-            agents: HashMap::new(),
-            workspaces: HashMap::new(),
+            agents: IndexMap::new(),
+            workspaces: IndexMap::new(),
         }
     }
 
@@ -639,6 +968,90 @@ mod tests {
         assert_eq!(agent.status, AgentStatus::Available);
     }
 
+    #[test]
+    fn test_agents_iterate_in_insertion_order() {
+        let names = ["Zoe", "Adam", "Mia", "Bo", "Kai", "Ana"];
+        for _ in 0..3 {
+            let mut org = Organization::new("RoboTech Industries".to_string());
+            for (i, name) in names.iter().enumerate() {
+                org.add_agent(
+                    OrganizationAgent::new(name.to_string(), OrganizationRole::DataAnalyst)
+                        .with_id(format!("agent-{}", names.len() - i)),
+                );
+            }
+
+            let order: Vec<&str> = org.agents.values().map(|a| a.name.as_str()).collect();
+            assert_eq!(order, names);
+            let ids: Vec<&str> = org.agents.keys().map(String::as_str).collect();
+            assert_eq!(
+                ids,
+                ["agent-6", "agent-5", "agent-4", "agent-3", "agent-2", "agent-1"]
+            );
+
+            // Order survives a serialization round trip
+            let restored: Organization =
+                serde_json::from_str(&serde_json::to_string(&org).unwrap()).unwrap();
+            let restored_order: Vec<&str> =
+                restored.agents.values().map(|a| a.name.as_str()).collect();
+            assert_eq!(restored_order, names);
+        }
+    }
+
+    #[test]
+    fn test_roles_report_through_managers_to_the_ceo() {
+        let chain = |role: OrganizationRole| {
+            std::iter::successors(Some(role), OrganizationRole::reports_to).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            chain(OrganizationRole::CNCOperator),
+            [
+                OrganizationRole::CNCOperator,
+                OrganizationRole::ProductionLead,
+                OrganizationRole::SeniorManagerProduction,
+                OrganizationRole::VPOperations,
+                OrganizationRole::ChiefOperatingOfficer,
+                OrganizationRole::ChiefExecutiveOfficer,
+            ]
+        );
+        assert_eq!(OrganizationRole::ChiefExecutiveOfficer.reports_to(), None);
+
+        // Every role reaches the CEO without a cycle
+        for role in [
+            OrganizationRole::ResearchEngineerRL,
+            OrganizationRole::ElectricalEngineeringIntern,
+            OrganizationRole::MechanicalEngineeringIntern,
+            OrganizationRole::RobotOperator,
+            OrganizationRole::DeveloperAdvocate,
+            OrganizationRole::SeniorRecruiter,
+            OrganizationRole::ProductManager,
+            OrganizationRole::UXResearcher,
+            OrganizationRole::PayrollAccountant,
+            OrganizationRole::SupplierDevelopmentEngineerEEE,
+        ] {
+            let chain = chain(role);
+            assert!(chain.len() <= 8, "{:?}", chain);
+            assert_eq!(chain.last(), Some(&OrganizationRole::ChiefExecutiveOfficer));
+        }
+    }
+
+    #[test]
+    fn test_roles_get_different_tools() {
+        let support = OrganizationRole::CustomerSuccessManager;
+        let manufacturing = OrganizationRole::ManufacturingEngineer;
+
+        assert_ne!(support.allowed_tools(), manufacturing.allowed_tools());
+
+        assert!(support.allows_tool("memory_search"));
+        assert!(support.allows_tool("web_search"));
+        assert!(!support.allows_tool("write_file"));
+        assert!(!support.allows_tool("manufacturing_schedule"));
+
+        assert!(manufacturing.allows_tool("memory_search"));
+        assert!(manufacturing.allows_tool("write_file"));
+        assert!(manufacturing.allows_tool("manufacturing_schedule"));
+        assert!(!manufacturing.allows_tool("location_info"));
+    }
+
     #[test]
     fn test_workspace_task_management() {
         let mut task = WorkspaceTask::new(
@@ -5,13 +5,13 @@
 
 use crate::a2a::*;
 use crate::error::{AgentError, Result};
+use crate::ids::new_id;
 use async_trait::async_trait;
 use flume::{bounded, Receiver, Sender};
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 use tokio::sync::{broadcast, RwLock};
-use uuid::Uuid;
 
 /// Local A2A client for in-process communication using flume channels
 pub struct LocalA2AClient {
@@ -153,7 +153,7 @@ impl A2AClient for LocalA2AClient {
 
     async fn request(&self, to: AgentId, payload: MessagePayload) -> Result<A2AResponse> {
         let message = A2AMessage {
-            id: Uuid::new_v4().to_string(),
+            id: new_id().to_string(),
             from: self.config.agent_id.clone(),
             to,
             message_type: MessageType::Request,
@@ -171,7 +171,7 @@ impl A2AClient for LocalA2AClient {
 
     async fn notify(&self, to: AgentId, payload: MessagePayload) -> Result<()> {
         let message = A2AMessage {
-            id: Uuid::new_v4().to_string(),
+            id: new_id().to_string(),
             from: self.config.agent_id.clone(),
             to,
             message_type: MessageType::Notification,
@@ -197,7 +197,7 @@ impl A2AClient for LocalA2AClient {
 
         for agent_id in to_agents {
             let message = A2AMessage {
-                id: Uuid::new_v4().to_string(),
+                id: new_id().to_string(),
                 from: self.config.agent_id.clone(),
                 to: agent_id,
                 message_type: MessageType::Event,
@@ -0,0 +1,165 @@
+//! Streaming artifact writer
+//!
+//! Agents produce work products (design docs, code, configs) over the course
+//! of a run. [`ArtifactWriter`] receives them over a bounded channel and writes
+//! each one to disk as it arrives, so producers never hold the whole set in
+//! memory and slow down when the disk falls behind. A failed write is recorded
+//! and the writer moves on to the next artifact.
+
+use crate::error::{AgentError, Result};
+use std::path::{Component, Path, PathBuf};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+/// A work product to write under the writer's root directory
+#[derive(Debug, Clone)]
+pub struct ArtifactFile {
+    /// Directory relative to the root, created on demand
+    pub subdir: PathBuf,
+    pub file_name: String,
+    pub content: String,
+}
+
+impl ArtifactFile {
+    pub fn new(
+        subdir: impl Into<PathBuf>,
+        file_name: impl Into<String>,
+        content: impl Into<String>,
+    ) -> Self {
+        Self {
+            subdir: subdir.into(),
+            file_name: file_name.into(),
+            content: content.into(),
+        }
+    }
+
+    /// Path relative to the root, rejecting anything that would escape it
+    fn relative_path(&self) -> Result<PathBuf> {
+        let path = self.subdir.join(&self.file_name);
+        let escapes = self.file_name.is_empty()
+            || path
+                .components()
+                .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir));
+        if escapes {
+            return Err(AgentError::Config(format!(
+                "Artifact path '{}' must be relative to the output directory",
+                path.display()
+            )));
+        }
+        Ok(path)
+    }
+}
+
+/// Outcome of a writer's run
+#[derive(Debug, Clone, Default)]
+pub struct ArtifactWriteReport {
+    /// Paths of the files written
+    pub written: Vec<PathBuf>,
+    /// Artifacts that could not be written, with the error
+    pub failed: Vec<(String, String)>,
+}
+
+/// Writes artifacts to disk as they arrive on a bounded channel
+pub struct ArtifactWriter {
+    sender: mpsc::Sender<ArtifactFile>,
+    handle: JoinHandle<ArtifactWriteReport>,
+}
+
+impl ArtifactWriter {
+    /// Start a writer rooted at `root`. At most `buffer` artifacts wait in the
+    /// channel; senders block beyond that until the writer catches up.
+    pub fn spawn(root: impl Into<PathBuf>, buffer: usize) -> Self {
+        let root = root.into();
+        let (sender, receiver) = mpsc::channel(buffer.max(1));
+        let handle = tokio::spawn(Self::run(root, receiver));
+        Self { sender, handle }
+    }
+
+    /// Sender for producers running on other tasks
+    pub fn sender(&self) -> mpsc::Sender<ArtifactFile> {
+        self.sender.clone()
+    }
+
+    /// Queue an artifact, waiting for room in the buffer
+    pub async fn write(&self, artifact: ArtifactFile) -> Result<()> {
+        self.sender
+            .send(artifact)
+            .await
+            .map_err(|_| AgentError::from(anyhow::anyhow!("Artifact writer has stopped")))
+    }
+
+    /// Wait for every queued artifact to be written. Clones from [`Self::sender`]
+    /// must be dropped first or this waits for them too.
+    pub async fn finish(self) -> Result<ArtifactWriteReport> {
+        drop(self.sender);
+        self.handle
+            .await
+            .map_err(|e| AgentError::from(anyhow::anyhow!("Artifact writer panicked: {}", e)))
+    }
+
+    async fn run(root: PathBuf, mut receiver: mpsc::Receiver<ArtifactFile>) -> ArtifactWriteReport {
+        let mut report = ArtifactWriteReport::default();
+        while let Some(artifact) = receiver.recv().await {
+            match Self::write_one(&root, &artifact).await {
+                Ok(path) => {
+                    debug!("Wrote artifact {}", path.display());
+                    report.written.push(path);
+                }
+                Err(e) => {
+                    warn!("Failed to write artifact '{}': {}", artifact.file_name, e);
+                    report.failed.push((artifact.file_name, e.to_string()));
+                }
+            }
+        }
+        report
+    }
+
+    async fn write_one(root: &Path, artifact: &ArtifactFile) -> Result<PathBuf> {
+        let path = root.join(artifact.relative_path()?);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&path, &artifact.content).await?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_streams_artifacts_into_subdirs() {
+        let dir = tempdir().unwrap();
+        let writer = ArtifactWriter::spawn(dir.path(), 1);
+
+        let producer = {
+            let sender = writer.sender();
+            tokio::spawn(async move {
+                let artifacts = [
+                    ArtifactFile::new("design_docs", "arm.md", "# Arm design"),
+                    ArtifactFile::new("code", "control.rs", "fn main() {}"),
+                    ArtifactFile::new("../outside", "escape.txt", "nope"),
+                    ArtifactFile::new("configs/robot", "limits.toml", "max_speed = 2"),
+                ];
+                for artifact in artifacts {
+                    sender.send(artifact).await.unwrap();
+                }
+            })
+        };
+        producer.await.unwrap();
+        let report = writer.finish().await.unwrap();
+
+        assert_eq!(report.written.len(), 3);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, "escape.txt");
+
+        let read = |path: &str| std::fs::read_to_string(dir.path().join(path)).unwrap();
+        assert_eq!(read("design_docs/arm.md"), "# Arm design");
+        assert_eq!(read("code/control.rs"), "fn main() {}");
+        assert_eq!(read("configs/robot/limits.toml"), "max_speed = 2");
+        assert!(!dir.path().join("../outside").exists());
+    }
+}
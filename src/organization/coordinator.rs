@@ -4,17 +4,26 @@
 //! Now enhanced with A2A protocol messaging and knowledge management integration.
 
 use super::a2a_local::LocalA2AClient;
+use super::experience::ExperienceCapture;
 use super::knowledge_helpers::{
     build_knowledge_enhanced_prompt, create_knowledge_entry, find_similar_tasks,
 };
-use super::{AgentStatus, Organization, OrganizationRole, WorkspaceTask};
+use super::{
+    AgentStatus, Organization, OrganizationRole, TaskEscalation, TaskPriority, TaskStatus,
+    WorkspaceTask,
+};
 use crate::a2a::{A2AClient, A2AConfig, AgentCapabilities, AgentId, MessagePayload};
 use crate::error::Result;
+use crate::ids::new_id;
 use crate::knowledge::AdaptiveKnowledgeManager;
+use crate::memory::{MemoryStore, SharedMemory};
+use crate::prompts::{self, PromptLibrary};
 use crate::{Agent, AgentConfig};
+use futures::stream::{self, StreamExt};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
 
@@ -53,12 +62,159 @@ pub enum AgentMessage {
 }
 
 /// Task execution result
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TaskResult {
     pub success: bool,
     pub output: String,
     pub metrics: HashMap<String, f64>,
     pub errors: Vec<String>,
+    /// Names of the work products the task produced, see [`output_artifacts`]
+    #[serde(default)]
+    pub artifacts: Vec<String>,
+}
+
+/// Names of the work products in an agent's `output` for the task `task_id`:
+/// one per fenced code block. A block is named by the file name following
+/// its language, as in ```` ```rust src/main.rs ````, and otherwise after the
+/// task, numbered in order, with an extension for its language.
+pub fn output_artifacts(task_id: &str, output: &str) -> Vec<String> {
+    let mut artifacts = Vec::new();
+    let mut in_block = false;
+    for line in output.lines() {
+        let Some(info) = line.trim_start().strip_prefix("```") else {
+            continue;
+        };
+        in_block = !in_block;
+        if !in_block {
+            continue;
+        }
+        let mut words = info.split_whitespace();
+        let language = words.next().unwrap_or_default().to_lowercase();
+        let name = match words.next() {
+            Some(file_name) => file_name.to_string(),
+            None => format!(
+                "{}-{}.{}",
+                task_id,
+                artifacts.len() + 1,
+                language_extension(&language)
+            ),
+        };
+        artifacts.push(name);
+    }
+    artifacts
+}
+
+/// File extension for code in `language`
+fn language_extension(language: &str) -> &str {
+    match language {
+        "" | "text" | "plaintext" => "txt",
+        "rust" => "rs",
+        "python" => "py",
+        "javascript" => "js",
+        "typescript" => "ts",
+        "markdown" => "md",
+        "shell" | "bash" | "sh" => "sh",
+        "yml" => "yaml",
+        other => other,
+    }
+}
+
+/// Structured record of a single task execution within a workspace
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskOutcome {
+    pub task_id: String,
+    pub agent_id: String,
+    pub workspace_id: String,
+    pub priority: TaskPriority,
+    pub status: TaskStatus,
+    pub duration_ms: u64,
+    pub result: TaskResult,
+}
+
+impl TaskOutcome {
+    /// Emit this outcome as a structured `tracing` event
+    pub fn log(&self) {
+        info!(
+            task_id = %self.task_id,
+            agent_id = %self.agent_id,
+            workspace_id = %self.workspace_id,
+            priority = ?self.priority,
+            status = ?self.status,
+            duration_ms = self.duration_ms,
+            success = self.result.success,
+            "Task execution finished"
+        );
+    }
+}
+
+/// Rollup of a workspace project's task outcomes
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceReport {
+    /// Tasks run, which excludes the skipped ones
+    pub total: usize,
+    pub completed: usize,
+    pub failed: usize,
+    /// Tasks not run because the workspace had no agent for them
+    #[serde(default)]
+    pub skipped: usize,
+    pub by_priority: BTreeMap<TaskPriority, PriorityCounts>,
+    /// Sum of the task durations, which exceeds the wall-clock time of a
+    /// project run in parallel
+    pub total_duration_ms: u64,
+    /// Artifacts of all tasks, in task order
+    pub artifacts: Vec<String>,
+    /// The outcomes rolled up, which
+    /// [`AgentCoordinator::retry_failed_tasks`] retries the failures of
+    #[serde(default)]
+    pub outcomes: Vec<TaskOutcome>,
+}
+
+/// Task counts for one priority in a [`WorkspaceReport`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PriorityCounts {
+    pub total: usize,
+    pub completed: usize,
+    pub failed: usize,
+}
+
+impl WorkspaceReport {
+    pub fn from_outcomes(outcomes: &[TaskOutcome]) -> Self {
+        let mut report = Self::default();
+        for outcome in outcomes {
+            let counts = report
+                .by_priority
+                .entry(outcome.priority.clone())
+                .or_default();
+            counts.total += 1;
+            report.total += 1;
+            if outcome.result.success {
+                counts.completed += 1;
+                report.completed += 1;
+            } else {
+                counts.failed += 1;
+                report.failed += 1;
+            }
+            report.total_duration_ms += outcome.duration_ms;
+            report
+                .artifacts
+                .extend(outcome.result.artifacts.iter().cloned());
+        }
+        report.outcomes = outcomes.to_vec();
+        report
+    }
+}
+
+/// How [`AgentCoordinator::coordinate_workspace_project_with_mode`] schedules
+/// a project's tasks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExecutionMode {
+    /// One task at a time, in the order given
+    Sequential,
+    /// Up to `max_concurrency` tasks at a time, started in the order given
+    Parallel { max_concurrency: usize },
+    /// One task at a time, highest priority first
+    #[default]
+    PriorityOrdered,
 }
 
 /// Coordinator that manages agent interactions and task orchestration
@@ -68,6 +224,10 @@ pub struct AgentCoordinator {
     a2a_client: Arc<LocalA2AClient>,
     agent_id_map: Arc<RwLock<HashMap<String, AgentId>>>, // org agent id -> A2A agent id
     knowledge_manager: Option<Arc<AdaptiveKnowledgeManager>>,
+    experience_capture: Option<Arc<ExperienceCapture>>,
+    shared_memory: Option<Arc<RwLock<Box<dyn MemoryStore>>>>,
+    preload_models: bool,
+    prompts: Arc<PromptLibrary>,
 }
 
 impl AgentCoordinator {
@@ -86,6 +246,10 @@ impl AgentCoordinator {
             a2a_client: Arc::new(a2a_client),
             agent_id_map: Arc::new(RwLock::new(HashMap::new())),
             knowledge_manager: None,
+            experience_capture: None,
+            shared_memory: None,
+            preload_models: false,
+            prompts: Arc::new(PromptLibrary::builtin()),
         }
     }
 
@@ -95,9 +259,81 @@ impl AgentCoordinator {
         self
     }
 
-    /// Initialize an agent in the organization
-    pub async fn spawn_agent(&self, agent_id: String, config: AgentConfig) -> Result<()> {
-        let agent = Agent::new(config).await?;
+    /// Summarize each completed workspace task into the responsible agent's memory
+    pub fn with_experience_capture(mut self, experience_capture: ExperienceCapture) -> Self {
+        self.experience_capture = Some(Arc::new(experience_capture));
+        self
+    }
+
+    /// Give each workspace a shared memory namespace in `store`. Agents
+    /// search it alongside their own memory while working on the
+    /// workspace's tasks, and store knowledge there if their config sets
+    /// `memory.shared_writes`.
+    pub fn with_shared_memory(mut self, store: Box<dyn MemoryStore>) -> Self {
+        self.shared_memory = Some(Arc::new(RwLock::new(store)));
+        self
+    }
+
+    /// The shared memory of a workspace, if shared memory is enabled
+    pub fn workspace_memory(&self, workspace_id: &str) -> Option<SharedMemory> {
+        self.shared_memory
+            .as_ref()
+            .map(|store| SharedMemory::for_workspace(store.clone(), workspace_id))
+    }
+
+    /// Connect an agent to a workspace's shared memory, if enabled
+    async fn join_workspace_memory(&self, agent_id: &str, workspace_id: &str) {
+        let Some(shared) = self.workspace_memory(workspace_id) else {
+            return;
+        };
+        let agent_arc = self.active_agents.read().await.get(agent_id).cloned();
+        if let Some(agent_arc) = agent_arc {
+            agent_arc.write().await.set_shared_memory(Some(shared));
+        }
+    }
+
+    /// Warm each agent's model when it is spawned, before it receives real tasks
+    pub fn with_model_preload(mut self) -> Self {
+        self.preload_models = true;
+        self
+    }
+
+    /// Build task prompts from `prompts` instead of the built-in templates
+    pub fn with_prompt_library(mut self, prompts: Arc<PromptLibrary>) -> Self {
+        self.prompts = prompts;
+        self
+    }
+
+    /// Initialize an agent in the organization.
+    ///
+    /// Agents in the organization generate with their role's sampling params,
+    /// or the agent's own [`OrganizationAgent::with_sampling`] override,
+    /// in place of those in `config`.
+    ///
+    /// [`OrganizationAgent::with_sampling`]: super::OrganizationAgent::with_sampling
+    pub async fn spawn_agent(&self, agent_id: String, mut config: AgentConfig) -> Result<()> {
+        let org_agent = self
+            .organization
+            .read()
+            .await
+            .agents
+            .get(&agent_id)
+            .cloned();
+        if let Some(org_agent) = &org_agent {
+            org_agent.sampling_params().apply(&mut config.llm);
+        }
+        let mut agent = Agent::new(config).await?;
+
+        // Each agent only sees the tools appropriate to its role
+        if let Some(org_agent) = &org_agent {
+            agent.restrict_tools(org_agent.role.allowed_tools());
+        }
+
+        if self.preload_models {
+            if let Err(e) = agent.preload_model().await {
+                warn!("Failed to preload model for agent {}: {}", agent_id, e);
+            }
+        }
         let mut agents = self.active_agents.write().await;
         agents.insert(agent_id.clone(), Arc::new(RwLock::new(agent)));
 
@@ -148,7 +384,11 @@ impl AgentCoordinator {
         }
 
         if let Some(workspace) = org.workspaces.get_mut(workspace_id) {
-            workspace.add_task(task.clone());
+            // A retried task replaces its earlier record
+            match workspace.tasks.iter_mut().find(|t| t.id == task.id) {
+                Some(existing) => *existing = task.clone(),
+                None => workspace.add_task(task.clone()),
+            }
         }
 
         // Queue message for agent
@@ -224,13 +464,13 @@ impl AgentCoordinator {
                 };
 
                 // Build enhanced prompt with past experiences
-                build_knowledge_enhanced_prompt(role, task, &past_experiences)
+                build_knowledge_enhanced_prompt(&self.prompts, role, task, &past_experiences)?
             } else {
                 // Fallback to simple prompt
-                format!(
-                    "Task: {}\n\nDescription: {}\n\nPlease provide a solution.",
-                    task.title, task.description
-                )
+                self.prompts.render(
+                    prompts::PLAIN_TASK,
+                    &[("title", &task.title), ("description", &task.description)],
+                )?
             };
 
             info!("Executing task with {} characters of context", prompt.len());
@@ -258,6 +498,7 @@ impl AgentCoordinator {
 
             Ok(TaskResult {
                 success: true,
+                artifacts: output_artifacts(&task.id, &result),
                 output: result,
                 metrics: HashMap::new(),
                 errors: Vec::new(),
@@ -319,7 +560,7 @@ impl AgentCoordinator {
                     context,
                     from_agent,
                 } => MessagePayload::Query {
-                    query_id: uuid::Uuid::new_v4().to_string(),
+                    query_id: new_id().to_string(),
                     query_type: "question".to_string(),
                     parameters: HashMap::from([
                         ("question".to_string(), question),
@@ -439,42 +680,412 @@ impl AgentCoordinator {
         workspace_id: &str,
         project_tasks: Vec<WorkspaceTask>,
     ) -> Result<Vec<TaskResult>> {
-        info!("Coordinating workspace project: {}", workspace_id);
+        let outcomes = self
+            .coordinate_workspace_project_with_outcomes(workspace_id, project_tasks)
+            .await?;
 
-        let mut results = Vec::new();
+        Ok(outcomes.into_iter().map(|outcome| outcome.result).collect())
+    }
 
-        // Sort tasks by priority
-        let mut sorted_tasks = project_tasks;
-        sorted_tasks.sort_by(|a, b| b.priority.cmp(&a.priority));
+    /// Coordinate a workspace project, returning a structured outcome per task
+    /// in the order the tasks were given
+    pub async fn coordinate_workspace_project_with_outcomes(
+        &self,
+        workspace_id: &str,
+        project_tasks: Vec<WorkspaceTask>,
+    ) -> Result<Vec<TaskOutcome>> {
+        self.coordinate_workspace_project_with_mode(
+            workspace_id,
+            project_tasks,
+            ExecutionMode::PriorityOrdered,
+        )
+        .await
+    }
 
-        for task in sorted_tasks {
-            // Find appropriate agent for task
-            let agent_id = {
-                let org = self.organization.read().await;
-                let workspace = org.workspaces.get(workspace_id);
+    /// Coordinate a workspace project, scheduling its tasks as set by `mode`.
+    ///
+    /// Each task runs on its first assignee that belongs to the workspace, or
+    /// on the workspace's first member, and tasks with no agent are skipped.
+    /// Outcomes are returned in the order the tasks were given, however they
+    /// were executed. The first failed task aborts the project.
+    pub async fn coordinate_workspace_project_with_mode(
+        &self,
+        workspace_id: &str,
+        project_tasks: Vec<WorkspaceTask>,
+        mode: ExecutionMode,
+    ) -> Result<Vec<TaskOutcome>> {
+        let (outcomes, _) = self
+            .run_project(workspace_id, project_tasks, mode, false)
+            .await?;
+        Ok(outcomes)
+    }
 
-                workspace.and_then(|ws| {
-                    if !ws.member_agents.is_empty() {
-                        Some(ws.member_agents[0].clone())
-                    } else {
-                        None
+    /// Coordinate a workspace project like
+    /// [`Self::coordinate_workspace_project_with_mode`], but run every task
+    /// even if some fail, recording failures as unsuccessful outcomes, and
+    /// roll the outcomes up into a [`WorkspaceReport`], which also counts the
+    /// tasks skipped
+    pub async fn coordinate_workspace_project_with_report(
+        &self,
+        workspace_id: &str,
+        project_tasks: Vec<WorkspaceTask>,
+        mode: ExecutionMode,
+    ) -> Result<(Vec<TaskOutcome>, WorkspaceReport)> {
+        let (outcomes, skipped) = self
+            .run_project(workspace_id, project_tasks, mode, true)
+            .await?;
+        let mut report = WorkspaceReport::from_outcomes(&outcomes);
+        report.skipped = skipped;
+        info!(
+            workspace_id = %workspace_id,
+            total = report.total,
+            completed = report.completed,
+            failed = report.failed,
+            skipped = report.skipped,
+            "Workspace project finished"
+        );
+        Ok((outcomes, report))
+    }
+
+    /// Run again the tasks that failed in `previous`, a report of a project
+    /// in `workspace_id`, keeping its successful outcomes, and report on the
+    /// combined outcomes. Tasks that have used up their `max_retries` keep
+    /// their failed outcome.
+    pub async fn retry_failed_tasks(
+        &self,
+        workspace_id: &str,
+        previous: &WorkspaceReport,
+    ) -> Result<WorkspaceReport> {
+        let retries: Vec<WorkspaceTask> = {
+            let org = self.organization.read().await;
+            let workspace = org.workspaces.get(workspace_id);
+            previous
+                .outcomes
+                .iter()
+                .filter(|outcome| !outcome.result.success)
+                .filter_map(|outcome| {
+                    let task = workspace?.tasks.iter().find(|t| t.id == outcome.task_id);
+                    match task {
+                        Some(task) if task.can_retry() => {
+                            let mut task = task.clone();
+                            task.status = TaskStatus::Pending;
+                            Some(task)
+                        }
+                        _ => {
+                            warn!(task_id = %outcome.task_id, "Task cannot be retried");
+                            None
+                        }
                     }
                 })
-            };
+                .collect()
+        };
+        info!(
+            workspace_id = %workspace_id,
+            tasks = retries.len(),
+            "Retrying failed workspace tasks"
+        );
 
-            if let Some(agent_id) = agent_id {
-                self.assign_task(&agent_id, workspace_id, task.clone())
-                    .await?;
-                // Execute task directly
-                let result = self.execute_task(&agent_id, &task).await?;
-                self.handle_task_completion(&agent_id, &task.id, result.clone())
-                    .await?;
+        let (retried, _) = self
+            .run_project(workspace_id, retries, ExecutionMode::default(), true)
+            .await?;
+        let mut retried: HashMap<String, TaskOutcome> = retried
+            .into_iter()
+            .map(|outcome| (outcome.task_id.clone(), outcome))
+            .collect();
+        let outcomes: Vec<TaskOutcome> = previous
+            .outcomes
+            .iter()
+            .map(|outcome| {
+                retried
+                    .remove(&outcome.task_id)
+                    .unwrap_or_else(|| outcome.clone())
+            })
+            .collect();
+        let mut report = WorkspaceReport::from_outcomes(&outcomes);
+        report.skipped = previous.skipped;
+        Ok(report)
+    }
+
+    /// Run `project_tasks`, returning the outcomes of those run, in the order
+    /// given, and the number skipped
+    async fn run_project(
+        &self,
+        workspace_id: &str,
+        project_tasks: Vec<WorkspaceTask>,
+        mode: ExecutionMode,
+        keep_going: bool,
+    ) -> Result<(Vec<TaskOutcome>, usize)> {
+        info!(workspace_id = %workspace_id, ?mode, "Coordinating workspace project");
+
+        let mut order: Vec<usize> = (0..project_tasks.len()).collect();
+        if mode == ExecutionMode::PriorityOrdered {
+            // Stable, so tasks of equal priority keep their given order
+            order.sort_by(|a, b| project_tasks[*b].priority.cmp(&project_tasks[*a].priority));
+        }
+        let max_concurrency = match mode {
+            ExecutionMode::Parallel { max_concurrency } => max_concurrency.max(1),
+            ExecutionMode::Sequential | ExecutionMode::PriorityOrdered => 1,
+        };
+
+        let mut outcomes: Vec<Option<TaskOutcome>> = vec![None; project_tasks.len()];
+        let mut runs = stream::iter(order.into_iter().map(|index| {
+            let task = project_tasks[index].clone();
+            async move {
+                let outcome = self.run_project_task(workspace_id, task, keep_going).await;
+                (index, outcome)
+            }
+        }))
+        .buffer_unordered(max_concurrency);
+
+        while let Some((index, outcome)) = runs.next().await {
+            outcomes[index] = outcome?;
+        }
+
+        let skipped = outcomes.iter().filter(|outcome| outcome.is_none()).count();
+        Ok((outcomes.into_iter().flatten().collect(), skipped))
+    }
+
+    /// Execute a project task on the workspace agent responsible for it. With
+    /// `keep_going`, a failed execution becomes an unsuccessful outcome
+    /// rather than an error.
+    async fn run_project_task(
+        &self,
+        workspace_id: &str,
+        task: WorkspaceTask,
+        keep_going: bool,
+    ) -> Result<Option<TaskOutcome>> {
+        let agent_id = {
+            let org = self.organization.read().await;
+            org.workspaces.get(workspace_id).and_then(|ws| {
+                task.assigned_to
+                    .iter()
+                    .find(|agent_id| ws.member_agents.contains(agent_id))
+                    .or_else(|| ws.member_agents.first())
+                    .cloned()
+            })
+        };
+
+        let Some(agent_id) = agent_id else {
+            debug!(task_id = %task.id, "No workspace agent for task; skipping");
+            return Ok(None);
+        };
+
+        let started = Instant::now();
+        match self
+            .execute_workspace_task(&agent_id, workspace_id, task.clone())
+            .await
+        {
+            Ok(outcome) => Ok(Some(outcome)),
+            Err(e) if keep_going => Ok(Some(TaskOutcome {
+                task_id: task.id,
+                agent_id,
+                workspace_id: workspace_id.to_string(),
+                priority: task.priority,
+                status: TaskStatus::Failed,
+                duration_ms: started.elapsed().as_millis() as u64,
+                result: TaskResult {
+                    success: false,
+                    output: String::new(),
+                    metrics: HashMap::new(),
+                    errors: vec![e.to_string()],
+                    artifacts: Vec::new(),
+                },
+            })),
+            Err(e) => Err(e),
+        }
+    }
 
-                results.push(result);
+    /// Assign, execute and complete a single workspace task, logging a structured outcome
+    async fn execute_workspace_task(
+        &self,
+        agent_id: &str,
+        workspace_id: &str,
+        mut task: WorkspaceTask,
+    ) -> Result<TaskOutcome> {
+        let started = Instant::now();
+        task.attempts += 1;
+
+        self.assign_task(agent_id, workspace_id, task.clone())
+            .await?;
+        self.join_workspace_memory(agent_id, workspace_id).await;
+
+        // Execute task directly
+        let result = match self.execute_task(agent_id, &task).await {
+            Ok(result) => result,
+            Err(e) => {
+                warn!(
+                    task_id = %task.id,
+                    agent_id = %agent_id,
+                    workspace_id = %workspace_id,
+                    priority = ?task.priority,
+                    duration_ms = started.elapsed().as_millis() as u64,
+                    error = %e,
+                    "Task execution failed"
+                );
+                self.mark_task_failed(workspace_id, &task.id).await;
+                return Err(e);
             }
+        };
+
+        self.handle_task_completion(agent_id, &task.id, result.clone())
+            .await?;
+        self.capture_experience(agent_id, workspace_id, &task, &result)
+            .await;
+
+        let status = {
+            let org = self.organization.read().await;
+            org.workspaces
+                .get(workspace_id)
+                .and_then(|ws| ws.tasks.iter().find(|t| t.id == task.id))
+                .map(|t| t.status.clone())
+                .unwrap_or(TaskStatus::Completed)
+        };
+
+        let outcome = TaskOutcome {
+            task_id: task.id,
+            agent_id: agent_id.to_string(),
+            workspace_id: workspace_id.to_string(),
+            priority: task.priority,
+            status,
+            duration_ms: started.elapsed().as_millis() as u64,
+            result,
+        };
+        outcome.log();
+
+        Ok(outcome)
+    }
+
+    /// Store a summary of a completed task in the agent's memory namespace
+    async fn capture_experience(
+        &self,
+        agent_id: &str,
+        workspace_id: &str,
+        task: &WorkspaceTask,
+        result: &TaskResult,
+    ) {
+        let Some(experience_capture) = &self.experience_capture else {
+            return;
+        };
+
+        let role = {
+            let org = self.organization.read().await;
+            org.agents.get(agent_id).map(|a| a.role.clone())
+        };
+        let agent_arc = self.active_agents.read().await.get(agent_id).cloned();
+        let (Some(role), Some(agent_arc)) = (role, agent_arc) else {
+            warn!("Agent {} not found for experience capture", agent_id);
+            return;
+        };
+
+        let entry = experience_capture
+            .capture(&role, workspace_id, task, result)
+            .await;
+
+        // Non-fatal: a failed capture doesn't undo the completed task
+        let mut agent = agent_arc.write().await;
+        match agent.store_knowledge(entry).await {
+            Ok(memory_id) => debug!("Stored experience for task {} ({})", task.id, memory_id),
+            Err(e) => warn!("Failed to store experience for task {}: {}", task.id, e),
         }
+    }
 
-        Ok(results)
+    /// Record a failed execution so the task can be escalated
+    async fn mark_task_failed(&self, workspace_id: &str, task_id: &str) {
+        let mut org = self.organization.write().await;
+        if let Some(task) = org
+            .workspaces
+            .get_mut(workspace_id)
+            .and_then(|ws| ws.tasks.iter_mut().find(|t| t.id == task_id))
+        {
+            task.fail();
+        }
+    }
+
+    /// Reassign a failed or blocked task to an agent in the category its
+    /// current assignee reports to, recording the reassignment on the task
+    pub async fn escalate_task(&self, workspace_id: &str, task_id: &str) -> Result<TaskEscalation> {
+        let (task, escalation) = {
+            let mut org = self.organization.write().await;
+
+            let task = org
+                .workspaces
+                .get(workspace_id)
+                .and_then(|ws| ws.tasks.iter().find(|t| t.id == task_id))
+                .ok_or_else(|| {
+                    anyhow::anyhow!("Task {} not found in workspace {}", task_id, workspace_id)
+                })?;
+            if !task.needs_escalation() {
+                return Err(anyhow::anyhow!(
+                    "Task {} is {:?}; only failed or blocked tasks can be escalated",
+                    task_id,
+                    task.status
+                )
+                .into());
+            }
+            let from_agents = task.assigned_to.clone();
+            let status = task.status.clone();
+
+            let mut manager = from_agents
+                .iter()
+                .filter_map(|id| org.agents.get(id))
+                .find_map(|agent| agent.role.reports_to())
+                .ok_or_else(|| anyhow::anyhow!("Task {} has no escalation path", task_id))?;
+            // Skip managers with no available agent, up to the top of the chain
+            let available = org.get_available_agents(None);
+            let to_agent = loop {
+                let candidate = available
+                    .iter()
+                    .find(|agent| agent.role == manager && !from_agents.contains(&agent.id));
+                if let Some(agent) = candidate {
+                    break agent.id.clone();
+                }
+                manager = manager.reports_to().ok_or_else(|| {
+                    anyhow::anyhow!("No available manager to escalate task {} to", task_id)
+                })?;
+            };
+
+            for agent_id in &from_agents {
+                if let Some(agent) = org.agents.get_mut(agent_id) {
+                    agent.complete_task(task_id);
+                }
+            }
+            if let Some(agent) = org.agents.get_mut(&to_agent) {
+                agent.assign_task(task_id.to_string());
+            }
+            org.assign_agent_to_workspace(&to_agent, workspace_id)?;
+
+            let escalation = TaskEscalation {
+                from_agents,
+                to_agent: to_agent.clone(),
+                status,
+                escalated_at: chrono::Utc::now(),
+            };
+            let task = org
+                .workspaces
+                .get_mut(workspace_id)
+                .and_then(|ws| ws.tasks.iter_mut().find(|t| t.id == task_id))
+                .expect("task looked up above");
+            task.assigned_to = vec![to_agent];
+            task.status = TaskStatus::Pending;
+            task.escalations.push(escalation.clone());
+
+            (task.clone(), escalation)
+        };
+
+        let message = AgentMessage::TaskAssignment {
+            task_id: task.id.clone(),
+            task,
+            from_agent: "coordinator".to_string(),
+        };
+        let _ = self.send_message(&escalation.to_agent, message).await;
+
+        info!(
+            task_id = %task_id,
+            from = ?escalation.from_agents,
+            to = %escalation.to_agent,
+            "Task escalated"
+        );
+        Ok(escalation)
     }
 
     /// Route task to best available agent based on role and capabilities
@@ -494,6 +1105,7 @@ impl AgentCoordinator {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::organization::{CollaborativeWorkspace, SamplingParams};
     use crate::OrganizationAgent;
 
     #[tokio::test]
@@ -525,4 +1137,813 @@ mod tests {
         // Agent not yet registered until spawn_agent is called
         assert!(id_map.is_empty());
     }
+
+    #[tokio::test]
+    async fn test_spawned_agents_get_role_tools() {
+        let mut org = Organization::new("Test Org".to_string());
+        let support =
+            OrganizationAgent::new("Sam".to_string(), OrganizationRole::CustomerSuccessManager);
+        let engineer =
+            OrganizationAgent::new("Eve".to_string(), OrganizationRole::ManufacturingEngineer);
+        let support_id = org.add_agent(support);
+        let engineer_id = org.add_agent(engineer);
+
+        let coordinator = AgentCoordinator::new(org);
+        for agent_id in [&support_id, &engineer_id] {
+            let mut config = AgentConfig::default();
+            config.memory.database_url = Some("sqlite::memory:".to_string());
+            coordinator
+                .spawn_agent(agent_id.clone(), config)
+                .await
+                .unwrap();
+        }
+
+        let agents = coordinator.active_agents.read().await;
+        let support_tools = agents[&support_id].read().await.get_available_tools().await;
+        let engineer_tools = agents[&engineer_id]
+            .read()
+            .await
+            .get_available_tools()
+            .await;
+
+        assert_ne!(support_tools, engineer_tools);
+        assert!(!support_tools.contains(&"system_info".to_string()));
+        assert!(engineer_tools.contains(&"system_info".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_workspace_agents_share_memory() {
+        let mut org = Organization::new("Test Org".to_string());
+        let writer_id = org.add_agent(OrganizationAgent::new(
+            "Ada".to_string(),
+            OrganizationRole::ResearchEngineerRL,
+        ));
+        let reader_id = org.add_agent(OrganizationAgent::new(
+            "Ben".to_string(),
+            OrganizationRole::ResearchEngineerRL,
+        ));
+        let outsider_id = org.add_agent(OrganizationAgent::new(
+            "Cy".to_string(),
+            OrganizationRole::ResearchEngineerRL,
+        ));
+        let mut project =
+            CollaborativeWorkspace::new("Project".to_string(), "Shared project".to_string());
+        project.add_member(writer_id.clone());
+        project.add_member(reader_id.clone());
+        let project_id = org.create_workspace(project);
+        let mut other =
+            CollaborativeWorkspace::new("Other".to_string(), "Another project".to_string());
+        other.add_member(outsider_id.clone());
+        let other_id = org.create_workspace(other);
+
+        let mut store = crate::memory::SqliteMemoryStore::new(crate::config::MemoryConfig {
+            database_url: Some("sqlite::memory:".to_string()),
+            embedding_dimension: 3,
+            ..Default::default()
+        });
+        store.initialize().await.unwrap();
+        let coordinator = AgentCoordinator::new(org).with_shared_memory(Box::new(store));
+
+        for (agent_id, shared_writes) in [
+            (&writer_id, true),
+            (&reader_id, false),
+            (&outsider_id, true),
+        ] {
+            let mut config = AgentConfig::default();
+            config.agent.name = agent_id.clone();
+            config.memory.database_url = Some("sqlite::memory:".to_string());
+            config.memory.embedding_dimension = 3;
+            config.memory.shared_writes = shared_writes;
+            coordinator
+                .spawn_agent(agent_id.clone(), config)
+                .await
+                .unwrap();
+        }
+        coordinator
+            .join_workspace_memory(&writer_id, &project_id)
+            .await;
+        coordinator
+            .join_workspace_memory(&reader_id, &project_id)
+            .await;
+        coordinator
+            .join_workspace_memory(&outsider_id, &other_id)
+            .await;
+
+        let agents = coordinator.active_agents.read().await;
+        let now = chrono::Utc::now();
+        agents[&writer_id]
+            .write()
+            .await
+            .store_knowledge(crate::memory::MemoryEntry {
+                id: uuid::Uuid::new_v4(),
+                content: "The staging cluster needs a VPN connection".to_string(),
+                embedding: vec![1.0, 0.0, 0.0],
+                metadata: HashMap::new(),
+                created_at: now,
+                updated_at: now,
+                importance: 0.5,
+                last_accessed: now,
+            })
+            .await
+            .unwrap();
+
+        // The writer's teammate finds it; its own store stays empty
+        let reader = agents[&reader_id].read().await;
+        let found = reader
+            .query_memory(vec![1.0, 0.0, 0.0], 5, 0.5)
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(
+            found[0].entry.content,
+            "The staging cluster needs a VPN connection"
+        );
+        assert_eq!(
+            found[0]
+                .entry
+                .metadata
+                .get(crate::memory::shared::MEMORY_AUTHOR_KEY),
+            Some(&writer_id)
+        );
+        let writer = agents[&writer_id].read().await;
+        assert!(writer.list_memories(None).await.unwrap().is_empty());
+
+        // Agents of another workspace don't see it
+        let outsider = agents[&outsider_id].read().await;
+        let found = outsider
+            .query_memory(vec![1.0, 0.0, 0.0], 5, 0.5)
+            .await
+            .unwrap();
+        assert!(found.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_spawned_agents_get_role_sampling() {
+        let mut org = Organization::new("Test Org".to_string());
+        let researcher =
+            OrganizationAgent::new("Rae".to_string(), OrganizationRole::ResearchEngineerRL);
+        let quality = OrganizationAgent::new(
+            "Quinn".to_string(),
+            OrganizationRole::QualityEngineerManufacturing,
+        );
+        let tuned = OrganizationAgent::new(
+            "Tess".to_string(),
+            OrganizationRole::QualityEngineerManufacturing,
+        )
+        .with_sampling(SamplingParams::new(0.65));
+        let researcher_id = org.add_agent(researcher);
+        let quality_id = org.add_agent(quality);
+        let tuned_id = org.add_agent(tuned);
+
+        let coordinator = AgentCoordinator::new(org);
+        for agent_id in [&researcher_id, &quality_id, &tuned_id] {
+            let mut config = AgentConfig::default();
+            config.memory.database_url = Some("sqlite::memory:".to_string());
+            coordinator
+                .spawn_agent(agent_id.clone(), config)
+                .await
+                .unwrap();
+        }
+
+        let agents = coordinator.active_agents.read().await;
+        let llm_config = |id: &String| {
+            let agent = agents[id].try_read().unwrap();
+            agent.config().llm.clone()
+        };
+        let researcher = llm_config(&researcher_id);
+        let quality = llm_config(&quality_id);
+        let tuned = llm_config(&tuned_id);
+
+        assert_eq!(researcher.temperature, 0.9);
+        assert_eq!(researcher.top_p, Some(0.95));
+        assert_eq!(quality.temperature, 0.2);
+        assert_eq!(quality.top_p, Some(0.8));
+        assert!(researcher.temperature > quality.temperature);
+        // The override wins over the role's temperature; with no top_p of its
+        // own, the config's is kept
+        assert_eq!(tuned.temperature, 0.65);
+        assert_eq!(tuned.top_p, None);
+    }
+
+    /// Records the field names and values of every event it sees
+    #[derive(Clone, Default)]
+    struct CapturedFields(Arc<std::sync::Mutex<Vec<HashMap<String, String>>>>);
+
+    struct FieldVisitor<'a>(&'a mut HashMap<String, String>);
+
+    impl tracing::field::Visit for FieldVisitor<'_> {
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            self.0
+                .insert(field.name().to_string(), format!("{:?}", value));
+        }
+    }
+
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for CapturedFields {
+        fn on_event(
+            &self,
+            event: &tracing::Event<'_>,
+            _ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            let mut fields = HashMap::new();
+            event.record(&mut FieldVisitor(&mut fields));
+            self.0.lock().unwrap().push(fields);
+        }
+    }
+
+    #[test]
+    fn test_task_outcome_emits_structured_fields() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let captured = CapturedFields::default();
+        let subscriber = tracing_subscriber::registry().with(captured.clone());
+
+        let outcome = TaskOutcome {
+            task_id: "task-1".to_string(),
+            agent_id: "agent-1".to_string(),
+            workspace_id: "ws-1".to_string(),
+            priority: TaskPriority::High,
+            status: TaskStatus::Completed,
+            duration_ms: 42,
+            result: TaskResult {
+                success: true,
+                output: "done".to_string(),
+                metrics: HashMap::new(),
+                errors: Vec::new(),
+                artifacts: Vec::new(),
+            },
+        };
+
+        tracing::subscriber::with_default(subscriber, || outcome.log());
+
+        let events = captured.0.lock().unwrap();
+        let fields = events
+            .iter()
+            .find(|f| f.contains_key("task_id"))
+            .expect("task outcome event");
+
+        assert_eq!(fields["task_id"], "task-1");
+        assert_eq!(fields["agent_id"], "agent-1");
+        assert_eq!(fields["workspace_id"], "ws-1");
+        assert_eq!(fields["priority"], "High");
+        assert_eq!(fields["status"], "Completed");
+        assert_eq!(fields["duration_ms"], "42");
+    }
+
+    /// Mock Ollama chat endpoint that takes `delay` per request, recording the
+    /// prompts it receives in arrival order and the most requests in flight
+    #[derive(Clone, Default)]
+    struct SlowOllama {
+        prompts: Arc<std::sync::Mutex<Vec<String>>>,
+        in_flight: Arc<std::sync::atomic::AtomicUsize>,
+        max_in_flight: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl SlowOllama {
+        /// Start the server and a coordinator whose workspace holds one agent per
+        /// name, all backed by it
+        async fn coordinator(
+            &self,
+            delay: std::time::Duration,
+            names: &[&str],
+        ) -> (AgentCoordinator, String, Vec<String>) {
+            use axum::{routing::post, Json, Router};
+            use std::sync::atomic::Ordering;
+
+            let mock = self.clone();
+            let app = Router::new().route(
+                "/api/chat",
+                post(move |Json(body): Json<serde_json::Value>| {
+                    let mock = mock.clone();
+                    async move {
+                        let prompt = body["messages"]
+                            .as_array()
+                            .and_then(|messages| messages.last())
+                            .and_then(|message| message["content"].as_str())
+                            .unwrap_or_default()
+                            .to_string();
+                        mock.prompts.lock().unwrap().push(prompt);
+                        let running = mock.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                        mock.max_in_flight.fetch_max(running, Ordering::SeqCst);
+                        tokio::time::sleep(delay).await;
+                        mock.in_flight.fetch_sub(1, Ordering::SeqCst);
+                        Json(serde_json::json!({
+                            "model": "mock",
+                            "message": { "role": "assistant", "content": "Done." },
+                            "done": true
+                        }))
+                    }
+                }),
+            );
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+            let mut org = Organization::new("Test Org".to_string());
+            let workspace_id = org.create_workspace(CollaborativeWorkspace::new(
+                "Platform".to_string(),
+                "Platform work".to_string(),
+            ));
+            let agent_ids: Vec<String> = names
+                .iter()
+                .map(|name| {
+                    let agent_id = org.add_agent(OrganizationAgent::new(
+                        name.to_string(),
+                        OrganizationRole::SoftwareEngineerPlatforms,
+                    ));
+                    org.assign_agent_to_workspace(&agent_id, &workspace_id)
+                        .unwrap();
+                    agent_id
+                })
+                .collect();
+
+            let coordinator = AgentCoordinator::new(org);
+            for agent_id in &agent_ids {
+                let mut config = AgentConfig::default();
+                config.llm.ollama_url = format!("http://{}", addr);
+                config.memory.database_url = Some("sqlite::memory:".to_string());
+                config.agent.use_memory = false;
+                config.agent.use_tools = false;
+                coordinator
+                    .spawn_agent(agent_id.clone(), config)
+                    .await
+                    .unwrap();
+            }
+            (coordinator, workspace_id, agent_ids)
+        }
+
+        /// Index of the task whose title appears in each prompt, in arrival order
+        fn execution_order(&self, tasks: &[WorkspaceTask]) -> Vec<usize> {
+            self.prompts
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|prompt| {
+                    tasks
+                        .iter()
+                        .position(|task| prompt.contains(&task.title))
+                        .unwrap()
+                })
+                .collect()
+        }
+    }
+
+    fn project_tasks(assignees: &[&String], priorities: &[TaskPriority]) -> Vec<WorkspaceTask> {
+        assignees
+            .iter()
+            .zip(priorities)
+            .enumerate()
+            .map(|(i, (agent_id, priority))| {
+                WorkspaceTask::new(
+                    format!("Project task {}", i),
+                    format!("Step {} of the rollout", i),
+                    vec![agent_id.to_string()],
+                )
+                .with_priority(priority.clone())
+            })
+            .collect()
+    }
+
+    fn task_ids(outcomes: &[TaskOutcome]) -> Vec<String> {
+        outcomes.iter().map(|o| o.task_id.clone()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_sequential_mode_runs_tasks_in_given_order() {
+        let mock = SlowOllama::default();
+        let (coordinator, workspace_id, agents) = mock
+            .coordinator(std::time::Duration::from_millis(10), &["Ann"])
+            .await;
+        let tasks = project_tasks(
+            &[&agents[0], &agents[0], &agents[0]],
+            &[
+                TaskPriority::Low,
+                TaskPriority::Critical,
+                TaskPriority::Medium,
+            ],
+        );
+        let expected: Vec<String> = tasks.iter().map(|t| t.id.clone()).collect();
+
+        let outcomes = coordinator
+            .coordinate_workspace_project_with_mode(
+                &workspace_id,
+                tasks.clone(),
+                ExecutionMode::Sequential,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(task_ids(&outcomes), expected);
+        assert_eq!(mock.execution_order(&tasks), vec![0, 1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_priority_mode_returns_results_in_given_order() {
+        let mock = SlowOllama::default();
+        let (coordinator, workspace_id, agents) = mock
+            .coordinator(std::time::Duration::from_millis(10), &["Ann"])
+            .await;
+        let tasks = project_tasks(
+            &[&agents[0], &agents[0], &agents[0]],
+            &[
+                TaskPriority::Low,
+                TaskPriority::Critical,
+                TaskPriority::Medium,
+            ],
+        );
+        let expected: Vec<String> = tasks.iter().map(|t| t.id.clone()).collect();
+
+        let outcomes = coordinator
+            .coordinate_workspace_project_with_mode(
+                &workspace_id,
+                tasks.clone(),
+                ExecutionMode::PriorityOrdered,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(task_ids(&outcomes), expected);
+        assert_eq!(mock.execution_order(&tasks), vec![1, 2, 0]);
+        assert_eq!(
+            mock.max_in_flight.load(std::sync::atomic::Ordering::SeqCst),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parallel_mode_runs_tasks_concurrently_up_to_limit() {
+        let mock = SlowOllama::default();
+        let (coordinator, workspace_id, agents) = mock
+            .coordinator(std::time::Duration::from_millis(50), &["Ann", "Bo", "Cy"])
+            .await;
+        let tasks = project_tasks(
+            &[&agents[0], &agents[1], &agents[2]],
+            &[
+                TaskPriority::Low,
+                TaskPriority::Critical,
+                TaskPriority::Medium,
+            ],
+        );
+        let expected: Vec<String> = tasks.iter().map(|t| t.id.clone()).collect();
+
+        let outcomes = coordinator
+            .coordinate_workspace_project_with_mode(
+                &workspace_id,
+                tasks,
+                ExecutionMode::Parallel { max_concurrency: 2 },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(task_ids(&outcomes), expected);
+        let ran_on: Vec<&String> = outcomes.iter().map(|o| &o.agent_id).collect();
+        assert_eq!(ran_on, agents.iter().collect::<Vec<_>>());
+        // Requests overlapped, but never more than two at once
+        assert_eq!(
+            mock.max_in_flight.load(std::sync::atomic::Ordering::SeqCst),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_completed_task_is_captured_as_experience() {
+        let mock = SlowOllama::default();
+        let (coordinator, workspace_id, agents) = mock
+            .coordinator(std::time::Duration::from_millis(10), &["Ann"])
+            .await;
+        let summarizer = crate::llm::mock::MockLlm::new()
+            .with_reply("Rolled out in two steps; the canary caught a bad config.");
+        let coordinator =
+            coordinator.with_experience_capture(ExperienceCapture::new(Arc::new(summarizer)));
+        let tasks = project_tasks(&[&agents[0]], &[TaskPriority::High]);
+        let task_id = tasks[0].id.clone();
+
+        coordinator
+            .coordinate_workspace_project(&workspace_id, tasks)
+            .await
+            .unwrap();
+
+        let agent = coordinator.active_agents.read().await[&agents[0]].clone();
+        let memories = agent.read().await.list_memories(None).await.unwrap();
+        let experience = memories
+            .iter()
+            .find(|entry| {
+                entry.metadata.get("kind").map(String::as_str)
+                    == Some(super::super::experience::EXPERIENCE_KIND)
+            })
+            .expect("no experience stored");
+        assert_eq!(experience.metadata["task_id"], task_id);
+        assert_eq!(experience.metadata["workspace_id"], workspace_id);
+        assert!(experience
+            .content
+            .contains("the canary caught a bad config"));
+    }
+
+    #[tokio::test]
+    async fn test_report_counts_outcomes_by_priority() {
+        let mock = SlowOllama::default();
+        let (coordinator, workspace_id, agents) = mock
+            .coordinator(std::time::Duration::from_millis(10), &["Ann"])
+            .await;
+        // A workspace member with no running agent fails every task
+        let ghost = {
+            let mut org = coordinator.organization.write().await;
+            let ghost = org.add_agent(OrganizationAgent::new(
+                "Ghost".to_string(),
+                OrganizationRole::SoftwareEngineerPlatforms,
+            ));
+            org.assign_agent_to_workspace(&ghost, &workspace_id)
+                .unwrap();
+            ghost
+        };
+        let tasks = project_tasks(
+            &[&agents[0], &agents[0], &ghost, &agents[0]],
+            &[
+                TaskPriority::High,
+                TaskPriority::Low,
+                TaskPriority::High,
+                TaskPriority::Critical,
+            ],
+        );
+
+        let (outcomes, report) = coordinator
+            .coordinate_workspace_project_with_report(
+                &workspace_id,
+                tasks,
+                ExecutionMode::PriorityOrdered,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(outcomes.len(), 4);
+        assert_eq!(outcomes[2].status, TaskStatus::Failed);
+        assert_eq!((report.total, report.completed, report.failed), (4, 3, 1));
+        let counts = |priority| report.by_priority[&priority];
+        assert_eq!(
+            counts(TaskPriority::High),
+            PriorityCounts {
+                total: 2,
+                completed: 1,
+                failed: 1
+            }
+        );
+        assert_eq!(counts(TaskPriority::Critical).completed, 1);
+        assert_eq!(counts(TaskPriority::Low).completed, 1);
+        assert!(!report.by_priority.contains_key(&TaskPriority::Medium));
+        assert_eq!(
+            report.total_duration_ms,
+            outcomes.iter().map(|o| o.duration_ms).sum::<u64>()
+        );
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["by_priority"]["High"]["failed"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_report_counts_skipped_tasks() {
+        let mock = SlowOllama::default();
+        // A workspace with no members has no agent for any task
+        let (coordinator, workspace_id, _) = mock
+            .coordinator(std::time::Duration::from_millis(10), &[])
+            .await;
+        let tasks = vec![
+            WorkspaceTask::new("Plan".to_string(), "Plan it".to_string(), vec![]),
+            WorkspaceTask::new("Ship".to_string(), "Ship it".to_string(), vec![]),
+        ];
+
+        let (outcomes, report) = coordinator
+            .coordinate_workspace_project_with_report(
+                &workspace_id,
+                tasks,
+                ExecutionMode::Sequential,
+            )
+            .await
+            .unwrap();
+
+        assert!(outcomes.is_empty());
+        assert_eq!((report.total, report.skipped), (0, 2));
+        let retried = coordinator
+            .retry_failed_tasks(&workspace_id, &report)
+            .await
+            .unwrap();
+        assert_eq!(retried.skipped, 2);
+    }
+
+    #[test]
+    fn test_output_artifacts_names_code_blocks() {
+        let output = "Here is the plan.\n\
+            ```rust src/motor.rs\nfn main() {}\n```\n\
+            And the config:\n\
+            ```yml\nspeed: 3\n```\n\
+            ```\nnotes\n```\n";
+
+        assert_eq!(
+            output_artifacts("task-7", output),
+            vec!["src/motor.rs", "task-7-2.yaml", "task-7-3.txt"]
+        );
+        assert!(output_artifacts("task-7", "Done.").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_retry_runs_only_failed_tasks() {
+        let mock = SlowOllama::default();
+        let (coordinator, workspace_id, agents) = mock
+            .coordinator(std::time::Duration::from_millis(10), &["Ann"])
+            .await;
+        // Tasks for a member with no running agent fail
+        let ghost = {
+            let mut org = coordinator.organization.write().await;
+            let ghost = org.add_agent(OrganizationAgent::new(
+                "Ghost".to_string(),
+                OrganizationRole::SoftwareEngineerPlatforms,
+            ));
+            org.assign_agent_to_workspace(&ghost, &workspace_id)
+                .unwrap();
+            ghost
+        };
+        let tasks = project_tasks(
+            &[&agents[0], &ghost],
+            &[TaskPriority::High, TaskPriority::Medium],
+        );
+
+        let (_, report) = coordinator
+            .coordinate_workspace_project_with_report(
+                &workspace_id,
+                tasks.clone(),
+                ExecutionMode::Sequential,
+            )
+            .await
+            .unwrap();
+        assert_eq!((report.completed, report.failed), (1, 1));
+
+        // With the ghost gone, the failed task falls back to Ann
+        coordinator.organization.write().await.workspaces[&workspace_id]
+            .member_agents
+            .retain(|agent_id| agent_id != &ghost);
+        let retried = coordinator
+            .retry_failed_tasks(&workspace_id, &report)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            (retried.total, retried.completed, retried.failed),
+            (2, 2, 0)
+        );
+        assert_eq!(retried.outcomes[0], report.outcomes[0]);
+        assert_eq!(retried.outcomes[1].agent_id, agents[0]);
+        assert_eq!(mock.execution_order(&tasks), vec![0, 1]);
+        let org = coordinator.get_organization().await;
+        let attempts: Vec<u32> = org.workspaces[&workspace_id]
+            .tasks
+            .iter()
+            .map(|task| task.attempts)
+            .collect();
+        assert_eq!(attempts, vec![1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_retries_stop_at_max_retries() {
+        let mock = SlowOllama::default();
+        let (coordinator, workspace_id, _) = mock
+            .coordinator(std::time::Duration::from_millis(10), &[])
+            .await;
+        let ghost = {
+            let mut org = coordinator.organization.write().await;
+            let ghost = org.add_agent(OrganizationAgent::new(
+                "Ghost".to_string(),
+                OrganizationRole::SoftwareEngineerPlatforms,
+            ));
+            org.assign_agent_to_workspace(&ghost, &workspace_id)
+                .unwrap();
+            ghost
+        };
+        let task = WorkspaceTask::new(
+            "Flaky task".to_string(),
+            "Never succeeds".to_string(),
+            vec![ghost],
+        )
+        .with_max_retries(1);
+
+        let (_, mut report) = coordinator
+            .coordinate_workspace_project_with_report(
+                &workspace_id,
+                vec![task],
+                ExecutionMode::Sequential,
+            )
+            .await
+            .unwrap();
+        for _ in 0..3 {
+            report = coordinator
+                .retry_failed_tasks(&workspace_id, &report)
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(report.failed, 1);
+        let org = coordinator.get_organization().await;
+        assert_eq!(org.workspaces[&workspace_id].tasks[0].attempts, 2);
+    }
+
+    #[tokio::test]
+    async fn test_failed_task_escalates_to_manager() {
+        let mut org = Organization::new("Test Org".to_string());
+        let engineer = org.add_agent(OrganizationAgent::new(
+            "Eve".to_string(),
+            OrganizationRole::SoftwareEngineerSimulation,
+        ));
+        let peer = org.add_agent(OrganizationAgent::new(
+            "Pat".to_string(),
+            OrganizationRole::SoftwareEngineerPlatforms,
+        ));
+        let vp = org.add_agent(OrganizationAgent::new(
+            "Val".to_string(),
+            OrganizationRole::VPEngineering,
+        ));
+        let workspace_id = org.create_workspace(CollaborativeWorkspace::new(
+            "Sim".to_string(),
+            "Simulation work".to_string(),
+        ));
+        let coordinator = AgentCoordinator::new(org);
+
+        let task = WorkspaceTask::new(
+            "Fix solver".to_string(),
+            "Physics solver diverges".to_string(),
+            vec![engineer.clone()],
+        );
+        let task_id = task.id.clone();
+        coordinator
+            .assign_task(&engineer, &workspace_id, task)
+            .await
+            .unwrap();
+
+        // Only stuck tasks are escalated
+        assert!(coordinator
+            .escalate_task(&workspace_id, &task_id)
+            .await
+            .is_err());
+
+        coordinator.mark_task_failed(&workspace_id, &task_id).await;
+        let escalation = coordinator
+            .escalate_task(&workspace_id, &task_id)
+            .await
+            .unwrap();
+        assert_eq!(escalation.to_agent, vp);
+        assert_eq!(escalation.from_agents, vec![engineer.clone()]);
+        assert_eq!(escalation.status, TaskStatus::Failed);
+
+        let org = coordinator.get_organization().await;
+        let task = &org.workspaces[&workspace_id].tasks[0];
+        assert_eq!(task.assigned_to, vec![vp.clone()]);
+        assert_eq!(task.status, TaskStatus::Pending);
+        assert_eq!(task.escalations, vec![escalation]);
+        assert_eq!(
+            OrganizationRole::SoftwareEngineerSimulation.reports_to(),
+            Some(org.agents[&vp].role.clone())
+        );
+        assert!(org.agents[&vp].current_tasks.contains(&task_id));
+        assert!(org.agents[&engineer].current_tasks.is_empty());
+        assert!(org.agents[&peer].current_tasks.is_empty());
+        assert!(org.workspaces[&workspace_id].member_agents.contains(&vp));
+    }
+
+    #[tokio::test]
+    async fn test_escalation_skips_managers_with_no_agent() {
+        let mut org = Organization::new("Test Org".to_string());
+        let operator = org.add_agent(OrganizationAgent::new(
+            "Oli".to_string(),
+            OrganizationRole::CNCOperator,
+        ));
+        let coo = org.add_agent(OrganizationAgent::new(
+            "Cora".to_string(),
+            OrganizationRole::ChiefOperatingOfficer,
+        ));
+        let senior_manager = org.add_agent(OrganizationAgent::new(
+            "Sam".to_string(),
+            OrganizationRole::SeniorManagerProduction,
+        ));
+        let workspace_id = org.create_workspace(CollaborativeWorkspace::new(
+            "Line 2".to_string(),
+            "Machining cell".to_string(),
+        ));
+        let coordinator = AgentCoordinator::new(org);
+
+        let task = WorkspaceTask::new(
+            "Recalibrate spindle".to_string(),
+            "Spindle runout out of tolerance".to_string(),
+            vec![operator.clone()],
+        );
+        let task_id = task.id.clone();
+        coordinator
+            .assign_task(&operator, &workspace_id, task)
+            .await
+            .unwrap();
+        coordinator.mark_task_failed(&workspace_id, &task_id).await;
+
+        // No production lead, so the next manager up takes it, not the COO
+        let escalation = coordinator
+            .escalate_task(&workspace_id, &task_id)
+            .await
+            .unwrap();
+        assert_eq!(escalation.to_agent, senior_manager);
+        assert_ne!(escalation.to_agent, coo);
+    }
 }
@@ -0,0 +1,192 @@
+//! Experience Capture
+//!
+//! Summarizes completed workspace tasks with an LLM so the responsible agent can
+//! store the outcome in its memory and retrieve it when similar work comes up.
+
+use super::knowledge_helpers::create_knowledge_entry;
+use super::{OrganizationRole, WorkspaceTask};
+use crate::llm::{system_message, user_message, LlmClient};
+use crate::memory::MemoryEntry;
+use crate::organization::coordinator::TaskResult;
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+/// Metadata value marking a memory as a captured task experience
+pub const EXPERIENCE_KIND: &str = "experience";
+
+const SUMMARY_SYSTEM_PROMPT: &str = "You record lessons learned for an engineering team. \
+Summarize the task and its outcome in 2-4 sentences: what was asked, what was done, \
+whether it worked and anything worth remembering next time.";
+
+/// Turns a completed task and its result into a memory entry
+pub struct ExperienceCapture {
+    llm: Arc<dyn LlmClient>,
+    max_output_chars: usize,
+}
+
+impl ExperienceCapture {
+    pub fn new(llm: Arc<dyn LlmClient>) -> Self {
+        Self {
+            llm,
+            max_output_chars: 2000,
+        }
+    }
+
+    /// Maximum characters of task output included in the summarization prompt
+    pub fn with_max_output_chars(mut self, max_output_chars: usize) -> Self {
+        self.max_output_chars = max_output_chars;
+        self
+    }
+
+    /// Summarize a task and its result, returning `None` if the LLM call fails
+    pub async fn summarize(&self, task: &WorkspaceTask, result: &TaskResult) -> Option<String> {
+        let output: String = result.output.chars().take(self.max_output_chars).collect();
+        let prompt = format!(
+            "Task: {}\nDescription: {}\nOutcome: {}\nErrors: {}\n\nResult:\n{}",
+            task.title,
+            task.description,
+            if result.success { "Success" } else { "Failed" },
+            if result.errors.is_empty() {
+                "none".to_string()
+            } else {
+                result.errors.join("; ")
+            },
+            output
+        );
+
+        let messages = [system_message(SUMMARY_SYSTEM_PROMPT), user_message(prompt)];
+        match self.llm.generate(&messages).await {
+            Ok(response) if !response.text.trim().is_empty() => {
+                Some(response.text.trim().to_string())
+            }
+            Ok(_) => {
+                warn!("Empty experience summary for task {}", task.id);
+                None
+            }
+            Err(e) => {
+                warn!("Failed to summarize task {}: {}", task.id, e);
+                None
+            }
+        }
+    }
+
+    /// Build the memory entry recording this task's outcome.
+    ///
+    /// The entry carries `task_id`, `workspace_id` and `timestamp` metadata on
+    /// top of the usual knowledge fields. If summarization fails the plain task
+    /// record is kept, so an unavailable model never loses the experience. The
+    /// embedding is left empty for the storing agent to fill in.
+    pub async fn capture(
+        &self,
+        role: &OrganizationRole,
+        workspace_id: &str,
+        task: &WorkspaceTask,
+        result: &TaskResult,
+    ) -> MemoryEntry {
+        let mut entry = create_knowledge_entry(role, task, result);
+
+        if let Some(summary) = self.summarize(task, result).await {
+            entry.content = format!("Task: {}\nExperience: {}", task.title, summary);
+        }
+
+        entry
+            .metadata
+            .insert("workspace_id".to_string(), workspace_id.to_string());
+        entry
+            .metadata
+            .insert("kind".to_string(), EXPERIENCE_KIND.to_string());
+
+        debug!(
+            "Captured experience for task {} in workspace {}",
+            task.id, workspace_id
+        );
+        entry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MemoryConfig;
+    use crate::llm::mock::MockLlm;
+    use crate::mcp::{ToolCall, ToolContent};
+    use crate::memory::{MemoryStore, SqliteMemoryStore};
+    use crate::tools::memory_search::MemorySearchTool;
+    use std::collections::HashMap;
+    use tokio::sync::RwLock;
+
+    /// Summarizes with a fixed sentence and embeds on a "billing" keyword axis
+    fn experience_llm() -> Arc<MockLlm> {
+        Arc::new(
+            MockLlm::new()
+                .with_reply(
+                    "Moved billing invoices to the new ledger; batch writes avoided timeouts.",
+                )
+                .with_embed(|text| {
+                    let billing = text.to_lowercase().contains("billing");
+                    Ok(vec![if billing { 1.0 } else { 0.0 }, 0.1, 0.0])
+                }),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_captured_experience_is_searchable() {
+        let config = MemoryConfig {
+            database_url: Some("sqlite::memory:".to_string()),
+            embedding_dimension: 3,
+            ..Default::default()
+        };
+        let mut store: Box<dyn MemoryStore> = Box::new(SqliteMemoryStore::new(config));
+        store.initialize().await.unwrap();
+        let memory = Arc::new(RwLock::new(store));
+        let llm: Arc<dyn LlmClient> = experience_llm();
+
+        let task = WorkspaceTask::new(
+            "Migrate billing".to_string(),
+            "Move invoices to the new ledger".to_string(),
+            vec![],
+        );
+        let result = TaskResult {
+            success: true,
+            output: "Done".to_string(),
+            metrics: HashMap::new(),
+            errors: vec![],
+            artifacts: vec![],
+        };
+
+        let entry = ExperienceCapture::new(llm.clone())
+            .capture(
+                &OrganizationRole::SoftwareEngineerSimulation,
+                "ws-1",
+                &task,
+                &result,
+            )
+            .await;
+        assert_eq!(entry.metadata["task_id"], task.id);
+        assert_eq!(entry.metadata["workspace_id"], "ws-1");
+        assert_eq!(entry.metadata["kind"], EXPERIENCE_KIND);
+        assert!(entry.metadata.contains_key("timestamp"));
+
+        let embedding = llm.embed(&entry.content).await.unwrap().embedding;
+        memory
+            .write()
+            .await
+            .store(entry.content, embedding, entry.metadata)
+            .await
+            .unwrap();
+
+        let search = MemorySearchTool::new(memory, llm)
+            .execute(&ToolCall {
+                id: "call-1".to_string(),
+                name: MemorySearchTool::NAME.to_string(),
+                arguments: serde_json::json!({"query": "billing ledger"}),
+            })
+            .await;
+
+        assert!(!search.is_error);
+        match &search.content[0] {
+            ToolContent::Text { text } => assert!(text.contains("batch writes avoided timeouts")),
+            _ => panic!("expected text content"),
+        }
+    }
+}
@@ -3,11 +3,14 @@
 //! Utilities for querying and storing organizational knowledge
 
 use super::{OrganizationRole, WorkspaceTask};
+use crate::error::Result;
+use crate::ids::new_id;
+use crate::memory::ranking::DEFAULT_IMPORTANCE;
 use crate::memory::MemoryEntry;
 use crate::organization::coordinator::TaskResult;
+use crate::prompts::{self, PromptLibrary};
 use chrono::Utc;
 use std::collections::HashMap;
-use uuid::Uuid;
 
 /// Format past experiences for inclusion in agent prompt
 pub fn format_past_experiences(memories: &[MemoryEntry]) -> String {
@@ -76,7 +79,7 @@ pub fn create_knowledge_entry(
     );
 
     MemoryEntry {
-        id: Uuid::new_v4(),
+        id: new_id(),
         content,
         embedding: vec![], // Empty for now - would be populated by embedding service
         metadata: HashMap::from([
@@ -91,6 +94,8 @@ pub fn create_knowledge_entry(
         ]),
         created_at: Utc::now(),
         updated_at: Utc::now(),
+        importance: DEFAULT_IMPORTANCE,
+        last_accessed: Utc::now(),
     }
 }
 
@@ -147,26 +152,26 @@ fn summarize_result(result: &TaskResult) -> String {
     summary
 }
 
-/// Build an enhanced prompt that includes past knowledge
+/// Build an enhanced prompt that includes past knowledge, from the templates
+/// in `library`
 pub fn build_knowledge_enhanced_prompt(
+    library: &PromptLibrary,
     role: &OrganizationRole,
     task: &WorkspaceTask,
     past_experiences: &[MemoryEntry],
-) -> String {
-    let role_context = role.system_prompt();
+) -> Result<String> {
+    let role_context = role.system_prompt_from(library)?;
     let experiences = format_past_experiences(past_experiences);
 
-    format!(
-        "{}\n\n\
-        {}\n\n\
-        ### Current Task:\n\
-        **Title:** {}\n\
-        **Description:** {}\n\
-        **Priority:** {:?}\n\n\
-        Please leverage the past experiences above to inform your approach. \
-        Consider what worked well and what challenges were encountered.\n\n\
-        Provide a comprehensive solution.",
-        role_context, experiences, task.title, task.description, task.priority
+    library.render(
+        prompts::WORKSPACE_TASK,
+        &[
+            ("role_context", &role_context),
+            ("experiences", &experiences),
+            ("title", &task.title),
+            ("description", &task.description),
+            ("priority", &format!("{:?}", task.priority)),
+        ],
     )
 }
 
@@ -246,6 +251,7 @@ mod tests {
             output: "Task completed successfully".to_string(),
             metrics: HashMap::new(),
             errors: vec![],
+            artifacts: vec![],
         };
 
         let entry = create_knowledge_entry(&role, &task, &result);
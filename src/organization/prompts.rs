@@ -1,36 +1,39 @@
 //! Role-Specific System Prompts with Organizational Learning
 //!
 //! This module provides tailored system prompts for each organizational role
-//! with integration to The Agency's learning and memory systems.
+//! with integration to The Agency's learning and memory systems. The prompt
+//! layout and learning guidance come from a [`PromptLibrary`], where a single
+//! role's description can also be replaced with a `role.<Role>` template.
 
 use super::OrganizationRole;
-
-/// Learning organization context that should be injected into all prompts
-const LEARNING_CONTEXT: &str = r#"
-ORGANIZATIONAL LEARNING:
-You are part of a learning organization. Before performing tasks:
-1. Query organizational memory for relevant past experiences, best practices, and lessons learned
-2. Apply learned patterns and successful approaches from similar past work
-3. After completing tasks, document key learnings, decisions, and outcomes for future reference
-4. Share insights with collaborators to build collective organizational knowledge
-
-Use the available memory and knowledge management tools to:
-- Retrieve relevant organizational knowledge before starting work
-- Store successful approaches and best practices after completion
-- Reference past failures to avoid repeating mistakes
-- Contribute to the organization's evolving knowledge base
-"#;
+use crate::error::Result;
+use crate::prompts::{self, PromptLibrary};
 
 impl OrganizationRole {
     /// Get comprehensive system prompt for this role
     pub fn system_prompt(&self) -> String {
-        let role_specific = self.role_specific_prompt();
+        self.system_prompt_from(&PromptLibrary::builtin())
+            .expect("built-in role prompt templates render")
+    }
+
+    /// Get the system prompt for this role from the templates in `library`
+    pub fn system_prompt_from(&self, library: &PromptLibrary) -> Result<String> {
+        let role_specific = match library.get(&format!("{}{:?}", prompts::ROLE_PREFIX, self)) {
+            Some(description) => description.to_string(),
+            None => self.role_specific_prompt(),
+        };
         let capabilities = self.capability_description();
+        let learning_context = library.render(prompts::LEARNING_CONTEXT, &[])?;
         let learning_behaviors = self.learning_behaviors();
 
-        format!(
-            "{}\n\n{}\n\n{}\n\n{}",
-            role_specific, capabilities, LEARNING_CONTEXT, learning_behaviors
+        library.render(
+            prompts::ROLE_SYSTEM,
+            &[
+                ("role_description", &role_specific),
+                ("capabilities", &capabilities),
+                ("learning_context", &learning_context),
+                ("learning_behaviors", &learning_behaviors),
+            ],
         )
     }
 
@@ -487,6 +490,29 @@ mod tests {
         assert!(prompt.contains("organizational memory"));
     }
 
+    #[test]
+    fn test_role_description_override() {
+        let mut library = PromptLibrary::builtin();
+        library.set(
+            "role.ChiefExecutiveOfficer",
+            "You are the CEO of a two-person robotics startup.",
+        );
+
+        let ceo = OrganizationRole::ChiefExecutiveOfficer
+            .system_prompt_from(&library)
+            .unwrap();
+        assert!(ceo.starts_with("You are the CEO of a two-person robotics startup."));
+        assert!(ceo.contains("ORGANIZATIONAL LEARNING"));
+
+        let cto = OrganizationRole::ChiefTechnologyOfficer
+            .system_prompt_from(&library)
+            .unwrap();
+        assert_eq!(
+            cto,
+            OrganizationRole::ChiefTechnologyOfficer.system_prompt()
+        );
+    }
+
     #[test]
     fn test_all_roles_have_prompts() {
         // Ensure all roles generate valid prompts
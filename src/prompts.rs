@@ -0,0 +1,285 @@
+//! Reusable prompt templates
+//!
+//! [`PromptLibrary`] holds named prompt templates used by organization roles
+//! and workflow steps. It starts from a built-in set compiled into the crate,
+//! and any template can be replaced at runtime, either one at a time with
+//! [`PromptLibrary::set`] or from a directory of `.prompt` files, each named
+//! after the template it replaces (`workflow.tool_summary.prompt`).
+//!
+//! Templates interpolate variables written as `{{name}}`, the same syntax as
+//! [`SystemPromptStep`](crate::workflow::SystemPromptStep) templates.
+//! Rendering fails if a variable has no value, so a typo in an override shows
+//! up as an error instead of a prompt with a hole in it.
+
+use crate::error::{AgentError, Result};
+use std::collections::HashMap;
+use std::path::Path;
+use tracing::debug;
+
+/// Layout of every role's system prompt
+pub const ROLE_SYSTEM: &str = "organization.role_system";
+
+/// Organizational learning guidance included in every role's system prompt
+pub const LEARNING_CONTEXT: &str = "organization.learning_context";
+
+/// Prompt for a workspace task, with the role's context and past experiences
+pub const WORKSPACE_TASK: &str = "organization.workspace_task";
+
+/// Prompt for a workspace task when the agent has no organization role
+pub const PLAIN_TASK: &str = "organization.plain_task";
+
+/// System prompt for summarizing oversized tool results
+pub const TOOL_SUMMARY: &str = "workflow.tool_summary";
+
+/// System prompt for the guardrail policy classifier
+pub const GUARDRAIL_CLASSIFIER: &str = "workflow.guardrail_classifier";
+
+/// System prompt for rewriting a response that violates policy
+pub const GUARDRAIL_REWRITE: &str = "workflow.guardrail_rewrite";
+
+/// System prompt for compressing a response to fit `{{max_chars}}` characters
+pub const RESPONSE_COMPRESS: &str = "agent.response_compress";
+
+/// Prefix of templates that replace a single role's description, such as
+/// `role.ChiefExecutiveOfficer`
+pub const ROLE_PREFIX: &str = "role.";
+
+const BUILTIN_TEMPLATES: &[(&str, &str)] = &[
+    (
+        ROLE_SYSTEM,
+        "{{role_description}}\n\n{{capabilities}}\n\n{{learning_context}}\n\n{{learning_behaviors}}",
+    ),
+    (
+        LEARNING_CONTEXT,
+        r#"
+ORGANIZATIONAL LEARNING:
+You are part of a learning organization. Before performing tasks:
+1. Query organizational memory for relevant past experiences, best practices, and lessons learned
+2. Apply learned patterns and successful approaches from similar past work
+3. After completing tasks, document key learnings, decisions, and outcomes for future reference
+4. Share insights with collaborators to build collective organizational knowledge
+
+Use the available memory and knowledge management tools to:
+- Retrieve relevant organizational knowledge before starting work
+- Store successful approaches and best practices after completion
+- Reference past failures to avoid repeating mistakes
+- Contribute to the organization's evolving knowledge base
+"#,
+    ),
+    (
+        WORKSPACE_TASK,
+        "{{role_context}}\n\n\
+        {{experiences}}\n\n\
+        ### Current Task:\n\
+        **Title:** {{title}}\n\
+        **Description:** {{description}}\n\
+        **Priority:** {{priority}}\n\n\
+        Please leverage the past experiences above to inform your approach. \
+        Consider what worked well and what challenges were encountered.\n\n\
+        Provide a comprehensive solution.",
+    ),
+    (
+        PLAIN_TASK,
+        "Task: {{title}}\n\nDescription: {{description}}\n\nPlease provide a solution.",
+    ),
+    (
+        TOOL_SUMMARY,
+        "You condense tool output for an assistant that must answer the user's question. \
+        Keep every fact, number, identifier and error that could matter; drop boilerplate, \
+        markup and repetition. Reply with the summary only.",
+    ),
+    (
+        GUARDRAIL_CLASSIFIER,
+        "You review assistant responses for policy violations: harmful instructions, \
+        harassment, personal data, or anything unsafe to show a user. Reply with SAFE, \
+        or with UNSAFE: followed by a short reason.",
+    ),
+    (
+        GUARDRAIL_REWRITE,
+        "Rewrite the assistant response so it no longer violates policy, keeping as much \
+        of its useful content as possible. Reply with the rewritten response only.",
+    ),
+    (
+        RESPONSE_COMPRESS,
+        "Shorten the assistant response to at most {{max_chars}} characters. Keep its answer \
+        and key facts; drop examples, asides and repetition. Reply with the shortened \
+        response only.",
+    ),
+];
+
+/// Named prompt templates, built in or overridden at runtime
+#[derive(Debug, Clone)]
+pub struct PromptLibrary {
+    templates: HashMap<String, String>,
+}
+
+impl Default for PromptLibrary {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl PromptLibrary {
+    /// Library holding the built-in templates
+    pub fn builtin() -> Self {
+        Self {
+            templates: BUILTIN_TEMPLATES
+                .iter()
+                .map(|(name, template)| (name.to_string(), template.to_string()))
+                .collect(),
+        }
+    }
+
+    /// Built-in templates overridden by the `.prompt` files in `dir`
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<Self> {
+        let mut library = Self::builtin();
+        library.load_dir(dir)?;
+        Ok(library)
+    }
+
+    /// Add or replace templates from the `.prompt` files in `dir`, returning
+    /// how many were loaded
+    pub fn load_dir(&mut self, dir: impl AsRef<Path>) -> Result<usize> {
+        let dir = dir.as_ref();
+        let entries = std::fs::read_dir(dir).map_err(|e| {
+            AgentError::Config(format!(
+                "Failed to read prompt directory {}: {}",
+                dir.display(),
+                e
+            ))
+        })?;
+
+        let mut loaded = 0;
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("prompt") {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let template = std::fs::read_to_string(&path)?;
+            debug!("Loaded prompt template {} from {}", name, path.display());
+            self.templates.insert(name.to_string(), template);
+            loaded += 1;
+        }
+        Ok(loaded)
+    }
+
+    /// Add or replace a template
+    pub fn set(&mut self, name: impl Into<String>, template: impl Into<String>) {
+        self.templates.insert(name.into(), template.into());
+    }
+
+    /// Raw template text
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.templates.get(name).map(String::as_str)
+    }
+
+    /// Names of all templates, sorted
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.templates.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Render a template, replacing each `{{name}}` with its value in `vars`
+    pub fn render(&self, name: &str, vars: &[(&str, &str)]) -> Result<String> {
+        let template = self
+            .get(name)
+            .ok_or_else(|| AgentError::Config(format!("Unknown prompt template '{}'", name)))?;
+
+        let mut rendered = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find("{{") {
+            let Some(len) = rest[start + 2..].find("}}") else {
+                break;
+            };
+            let variable = rest[start + 2..start + 2 + len].trim();
+            let value = vars
+                .iter()
+                .find(|(key, _)| *key == variable)
+                .map(|(_, value)| *value)
+                .ok_or_else(|| {
+                    AgentError::Config(format!(
+                        "Prompt template '{}' needs variable '{}'",
+                        name, variable
+                    ))
+                })?;
+            rendered.push_str(&rest[..start]);
+            rendered.push_str(value);
+            rest = &rest[start + 2 + len + 2..];
+        }
+        rendered.push_str(rest);
+        Ok(rendered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_builtin_template() {
+        let library = PromptLibrary::builtin();
+        let prompt = library
+            .render(
+                PLAIN_TASK,
+                &[
+                    ("title", "Calibrate arm"),
+                    ("description", "Joint 3 drifts"),
+                ],
+            )
+            .unwrap();
+        assert_eq!(
+            prompt,
+            "Task: Calibrate arm\n\nDescription: Joint 3 drifts\n\nPlease provide a solution."
+        );
+
+        // Every variable needs a value
+        let err = library
+            .render(PLAIN_TASK, &[("title", "Calibrate arm")])
+            .unwrap_err();
+        assert!(err.to_string().contains("description"));
+        assert!(library.render("no.such.template", &[]).is_err());
+    }
+
+    #[test]
+    fn test_directory_overrides_builtin_template() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(format!("{}.prompt", PLAIN_TASK)),
+            "Do {{ title }}: {{description}}",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("team.greeting.prompt"), "Hi {{name}}").unwrap();
+        std::fs::write(dir.path().join("README.md"), "not a template").unwrap();
+
+        let library = PromptLibrary::from_dir(dir.path()).unwrap();
+        assert_eq!(
+            library
+                .render(
+                    PLAIN_TASK,
+                    &[("title", "Calibrate arm"), ("description", "now")]
+                )
+                .unwrap(),
+            "Do Calibrate arm: now"
+        );
+        assert_eq!(
+            library.render("team.greeting", &[("name", "Ann")]).unwrap(),
+            "Hi Ann"
+        );
+        assert!(library.get("README").is_none());
+        assert_eq!(
+            library.get(TOOL_SUMMARY),
+            PromptLibrary::builtin().get(TOOL_SUMMARY)
+        );
+
+        let mut library = library;
+        library.set(TOOL_SUMMARY, "Summarize tersely.");
+        assert_eq!(
+            library.render(TOOL_SUMMARY, &[]).unwrap(),
+            "Summarize tersely."
+        );
+    }
+}
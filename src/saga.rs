@@ -1,29 +1,44 @@
 //! Saga pattern implementation for distributed transactions and workflow compensation
 //!
 //! A saga is a sequence of local transactions where each transaction has a compensating transaction.
-//! If any transaction fails, the saga executes compensation transactions in reverse order.
+//! If any transaction fails, the saga executes compensation transactions in reverse order,
+//! unless steps are assigned to compensation groups that the orchestrator rolls back in a
+//! declared sequence.
 //!
 //! This implementation provides:
 //! - Forward execution of saga steps
 //! - Automatic compensation on failure
 //! - State persistence for long-running sagas
 //! - Saga orchestration and coordination
+//! - Sub-workflows as saga steps via [`WorkflowSagaStep`]
 
 use crate::error::{AgentError, Result};
-use crate::workflow::{WorkflowContext, WorkflowDecision, WorkflowStep};
+use crate::ids::new_id;
+use crate::workflow::{
+    WorkflowContext, WorkflowDecision, WorkflowEngine, WorkflowResult, WorkflowStep,
+};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
 /// Type alias for saga action functions
-type SagaActionFn = Box<dyn Fn(&mut WorkflowContext) -> Result<serde_json::Value> + Send + Sync>;
+type SagaActionFn = Box<
+    dyn for<'a> Fn(&'a mut WorkflowContext) -> BoxFuture<'a, Result<serde_json::Value>>
+        + Send
+        + Sync,
+>;
 
 /// Type alias for saga compensation functions
-type SagaCompensationFn =
-    Box<dyn Fn(&mut WorkflowContext, &serde_json::Value) -> Result<()> + Send + Sync>;
+type SagaCompensationFn = Box<
+    dyn for<'a> Fn(&'a mut WorkflowContext, &'a serde_json::Value) -> BoxFuture<'a, Result<()>>
+        + Send
+        + Sync,
+>;
 
 /// Saga execution result
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -89,6 +104,10 @@ pub struct SagaStep {
 
     /// Maximum retry attempts
     pub max_retries: usize,
+
+    /// Group this step is compensated with, see
+    /// [`SagaOrchestrator::with_compensation_order`]
+    pub compensation_group: Option<String>,
 }
 
 impl SagaStep {
@@ -96,6 +115,32 @@ impl SagaStep {
     where
         F: Fn(&mut WorkflowContext) -> Result<serde_json::Value> + Send + Sync + 'static,
         C: Fn(&mut WorkflowContext, &serde_json::Value) -> Result<()> + Send + Sync + 'static,
+    {
+        Self::new_async(
+            id,
+            name,
+            move |ctx| {
+                let result = action(ctx);
+                Box::pin(async move { result })
+            },
+            move |ctx, step_result| {
+                let result = compensation(ctx, step_result);
+                Box::pin(async move { result })
+            },
+        )
+    }
+
+    /// Create a step whose action and compensation are asynchronous
+    pub fn new_async<F, C>(id: &str, name: &str, action: F, compensation: C) -> Self
+    where
+        F: for<'a> Fn(&'a mut WorkflowContext) -> BoxFuture<'a, Result<serde_json::Value>>
+            + Send
+            + Sync
+            + 'static,
+        C: for<'a> Fn(&'a mut WorkflowContext, &'a serde_json::Value) -> BoxFuture<'a, Result<()>>
+            + Send
+            + Sync
+            + 'static,
     {
         Self {
             id: id.to_string(),
@@ -104,6 +149,7 @@ impl SagaStep {
             compensation: Box::new(compensation),
             retryable: true,
             max_retries: 3,
+            compensation_group: None,
         }
     }
 
@@ -117,6 +163,12 @@ impl SagaStep {
         self.max_retries = 0;
         self
     }
+
+    /// Compensate this step together with the other steps of `group`
+    pub fn in_compensation_group(mut self, group: &str) -> Self {
+        self.compensation_group = Some(group.to_string());
+        self
+    }
 }
 
 /// Saga execution context
@@ -150,7 +202,7 @@ pub struct SagaContext {
 impl SagaContext {
     pub fn new(name: String, workflow_context: WorkflowContext) -> Self {
         Self {
-            id: Uuid::new_v4(),
+            id: new_id(),
             name,
             started_at: Utc::now(),
             ended_at: None,
@@ -211,11 +263,15 @@ impl SagaContext {
 /// Saga orchestrator - manages saga execution
 pub struct SagaOrchestrator {
     steps: Vec<SagaStep>,
+    compensation_order: Vec<String>,
 }
 
 impl SagaOrchestrator {
     pub fn new() -> Self {
-        Self { steps: Vec::new() }
+        Self {
+            steps: Vec::new(),
+            compensation_order: Vec::new(),
+        }
     }
 
     pub fn add_step(mut self, step: SagaStep) -> Self {
@@ -223,6 +279,32 @@ impl SagaOrchestrator {
         self
     }
 
+    /// Compensate the steps of each group in `groups` before those of the next,
+    /// e.g. release locks before refunding. Steps within a group, and steps in
+    /// no listed group, which go last, are still compensated in reverse order.
+    pub fn with_compensation_order<S: Into<String>>(
+        mut self,
+        groups: impl IntoIterator<Item = S>,
+    ) -> Self {
+        self.compensation_order = groups.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Indices of the steps before `failed_at_index` in the order they are
+    /// compensated
+    fn compensation_sequence(&self, failed_at_index: usize) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..failed_at_index).rev().collect();
+        // Stable, so reverse order holds within each group
+        indices.sort_by_key(|&index| {
+            self.steps[index]
+                .compensation_group
+                .as_ref()
+                .and_then(|group| self.compensation_order.iter().position(|g| g == group))
+                .unwrap_or(self.compensation_order.len())
+        });
+        indices
+    }
+
     /// Execute the saga
     pub async fn execute(&self, mut context: SagaContext) -> Result<SagaResult> {
         info!(
@@ -289,7 +371,7 @@ impl SagaOrchestrator {
                 );
             }
 
-            match (step.action)(&mut context.workflow_context) {
+            match (step.action)(&mut context.workflow_context).await {
                 Ok(result) => {
                     return Ok(result);
                 }
@@ -314,7 +396,7 @@ impl SagaOrchestrator {
         Err(last_error.unwrap_or_else(|| AgentError::Workflow("Step execution failed".to_string())))
     }
 
-    /// Compensate completed steps in reverse order
+    /// Compensate completed steps in reverse order, or group by group
     async fn compensate(
         &self,
         mut context: SagaContext,
@@ -325,8 +407,7 @@ impl SagaOrchestrator {
         let mut compensated_steps = Vec::new();
         let mut compensation_error = None;
 
-        // Compensate in reverse order
-        for index in (0..failed_at_index).rev() {
+        for index in self.compensation_sequence(failed_at_index) {
             let step = &self.steps[index];
 
             if !context.is_step_completed(&step.id) {
@@ -342,7 +423,7 @@ impl SagaOrchestrator {
                 .cloned()
                 .unwrap_or(serde_json::json!({}));
 
-            match (step.compensation)(&mut context.workflow_context, &step_result) {
+            match (step.compensation)(&mut context.workflow_context, &step_result).await {
                 Ok(_) => {
                     context.mark_step_compensated(&step.id);
                     compensated_steps.push(step.name.clone());
@@ -389,6 +470,93 @@ impl Default for SagaOrchestrator {
     }
 }
 
+/// Saga step that runs a workflow forward and, on rollback, a compensating workflow.
+///
+/// The forward workflow must complete; a suspended or otherwise unfinished
+/// result fails the step so the saga compensates. On success the saga adopts
+/// the workflow's final context and records a summary of the result. Convert
+/// into a [`SagaStep`] with `.into()` to add it to an orchestrator.
+pub struct WorkflowSagaStep {
+    id: String,
+    name: String,
+    forward: Arc<WorkflowEngine>,
+    compensation: Option<Arc<WorkflowEngine>>,
+}
+
+impl WorkflowSagaStep {
+    pub fn new(id: &str, name: &str, forward: WorkflowEngine) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            forward: Arc::new(forward),
+            compensation: None,
+        }
+    }
+
+    /// Workflow run against the saga context when this step is rolled back
+    pub fn with_compensation(mut self, workflow: WorkflowEngine) -> Self {
+        self.compensation = Some(Arc::new(workflow));
+        self
+    }
+
+    /// Run `engine` on a copy of `context`, adopting the final context only if
+    /// the workflow completed
+    async fn run(
+        engine: &WorkflowEngine,
+        name: &str,
+        context: &mut WorkflowContext,
+    ) -> Result<WorkflowResult> {
+        let result = engine.execute(context.clone()).await?;
+        if !result.completed {
+            return Err(AgentError::Workflow(format!(
+                "Workflow '{}' did not complete: {}",
+                name, result.response
+            )));
+        }
+        *context = result.context.clone();
+        Ok(result)
+    }
+}
+
+impl From<WorkflowSagaStep> for SagaStep {
+    /// Workflows are not retried: a suspension waits on something external and
+    /// re-running would only create more snapshots
+    fn from(step: WorkflowSagaStep) -> Self {
+        let forward = step.forward;
+        let compensation = step.compensation;
+        let forward_name = step.name.clone();
+        let compensation_name = format!("{} (compensation)", step.name);
+
+        SagaStep::new_async(
+            &step.id,
+            &step.name,
+            move |ctx| {
+                let forward = forward.clone();
+                let name = forward_name.clone();
+                Box::pin(async move {
+                    let result = WorkflowSagaStep::run(&forward, &name, ctx).await?;
+                    Ok(serde_json::json!({
+                        "response": result.response,
+                        "completed": result.completed,
+                        "steps_executed": result.steps_executed,
+                    }))
+                })
+            },
+            move |ctx, _step_result| {
+                let compensation = compensation.clone();
+                let name = compensation_name.clone();
+                Box::pin(async move {
+                    if let Some(workflow) = compensation {
+                        WorkflowSagaStep::run(&workflow, &name, ctx).await?;
+                    }
+                    Ok(())
+                })
+            },
+        )
+        .non_retryable()
+    }
+}
+
 /// Workflow step that executes a saga
 pub struct SagaWorkflowStep {
     orchestrator: SagaOrchestrator,
@@ -458,7 +626,8 @@ impl WorkflowStep for SagaWorkflowStep {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::workflow::WorkflowContext;
+    use crate::workflow::{HumanApprovalStep, WorkflowContext};
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     #[tokio::test]
     async fn test_saga_success() {
@@ -512,4 +681,141 @@ mod tests {
 
         assert!(matches!(result, SagaResult::Compensated { .. }));
     }
+
+    #[tokio::test]
+    async fn test_compensation_runs_in_declared_group_order() {
+        let compensated = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let step = |id: &'static str| {
+            let compensated = compensated.clone();
+            SagaStep::new(
+                id,
+                id,
+                |_ctx| Ok(serde_json::json!({})),
+                move |_ctx, _result| {
+                    compensated.lock().unwrap().push(id);
+                    Ok(())
+                },
+            )
+        };
+
+        let orchestrator = SagaOrchestrator::new()
+            .add_step(step("charge_card").in_compensation_group("payments"))
+            .add_step(step("lock_seat").in_compensation_group("locks"))
+            .add_step(step("send_email"))
+            .add_step(step("lock_room").in_compensation_group("locks"))
+            .add_step(
+                SagaStep::new(
+                    "confirm",
+                    "confirm",
+                    |_ctx| Err(AgentError::Workflow("Intentional failure".to_string())),
+                    |_ctx, _result| Ok(()),
+                )
+                .non_retryable(),
+            )
+            .with_compensation_order(["locks", "payments"]);
+
+        let saga_ctx = SagaContext::new("booking".to_string(), WorkflowContext::new(10));
+        let result = orchestrator.execute(saga_ctx).await.unwrap();
+
+        let expected = vec!["lock_room", "lock_seat", "charge_card", "send_email"];
+        assert_eq!(*compensated.lock().unwrap(), expected);
+        match result {
+            SagaResult::Compensated {
+                compensated_steps, ..
+            } => assert_eq!(compensated_steps, expected),
+            other => panic!("expected compensation, got {:?}", other),
+        }
+    }
+
+    /// Completes the workflow, counting runs and tagging the context
+    struct CompleteStep {
+        label: &'static str,
+        runs: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl WorkflowStep for CompleteStep {
+        async fn execute(&self, context: &mut WorkflowContext) -> Result<WorkflowDecision> {
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            context
+                .metadata
+                .insert(self.label.to_string(), "done".to_string());
+            Ok(WorkflowDecision::Complete(format!("{} done", self.label)))
+        }
+
+        fn name(&self) -> &str {
+            self.label
+        }
+    }
+
+    fn completing_workflow(label: &'static str, runs: &Arc<AtomicUsize>) -> WorkflowEngine {
+        WorkflowEngine::new().add_step(Box::new(CompleteStep {
+            label,
+            runs: runs.clone(),
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_workflow_step_maps_result_into_saga_context() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let reserve: SagaStep =
+            WorkflowSagaStep::new("reserve", "Reserve", completing_workflow("reserve", &runs))
+                .into();
+        let check = SagaStep::new(
+            "check",
+            "Check",
+            |ctx| match ctx.metadata.get("reserve") {
+                Some(_) => Ok(serde_json::json!({})),
+                None => Err(AgentError::Workflow("reservation missing".to_string())),
+            },
+            |_ctx, _result| Ok(()),
+        )
+        .non_retryable();
+
+        let orchestrator = SagaOrchestrator::new().add_step(reserve).add_step(check);
+        let saga_ctx = SagaContext::new("test-saga".to_string(), WorkflowContext::new(10));
+        let result = orchestrator.execute(saga_ctx).await.unwrap();
+
+        assert!(matches!(result, SagaResult::Completed(_)));
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_suspended_workflow_triggers_compensation() {
+        let reserved = Arc::new(AtomicUsize::new(0));
+        let released = Arc::new(AtomicUsize::new(0));
+
+        let reserve = WorkflowSagaStep::new(
+            "reserve",
+            "Reserve inventory",
+            completing_workflow("reserve", &reserved),
+        )
+        .with_compensation(completing_workflow("release", &released));
+        let approve = WorkflowSagaStep::new(
+            "approve",
+            "Manager approval",
+            WorkflowEngine::new().add_step(Box::new(HumanApprovalStep::new(
+                "Approve the order?".to_string(),
+            ))),
+        );
+
+        let orchestrator = SagaOrchestrator::new()
+            .add_step(reserve.into())
+            .add_step(approve.into());
+        let saga_ctx = SagaContext::new("order-saga".to_string(), WorkflowContext::new(10));
+        let result = orchestrator.execute(saga_ctx).await.unwrap();
+
+        match result {
+            SagaResult::Compensated {
+                error,
+                compensated_steps,
+            } => {
+                assert_eq!(error, "Manager approval");
+                assert_eq!(compensated_steps, vec!["Reserve inventory".to_string()]);
+            }
+            other => panic!("expected compensation, got {:?}", other),
+        }
+        assert_eq!(reserved.load(Ordering::SeqCst), 1);
+        assert_eq!(released.load(Ordering::SeqCst), 1);
+    }
 }
@@ -1,41 +1,46 @@
 //! Tool management and execution
 
+pub mod datetime;
+pub mod memory_search;
+pub mod system_info;
+
+pub use datetime::DateTimeTool;
+pub use memory_search::MemorySearchTool;
+pub use system_info::SystemInfoField;
+
+use crate::ids::new_id;
 use crate::mcp::{ToolCall, ToolContent, ToolResult};
 use chrono::{Local, Utc};
 use std::collections::HashMap;
 use tokio::process::Command;
-use uuid::Uuid;
+
+/// Whether a tool name matches an allowlist pattern. Patterns are exact names,
+/// or prefixes ending in `*` (e.g. `file_*`); a lone `*` matches every tool.
+pub fn tool_name_matches(pattern: &str, name: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => pattern == name,
+    }
+}
 
 /// Built-in tool for system information
 pub fn create_system_info_tool() -> ToolCall {
     ToolCall {
-        id: Uuid::new_v4().to_string(),
+        id: new_id().to_string(),
         name: "system_info".to_string(),
         arguments: serde_json::json!({}),
     }
 }
 
-/// Execute a system info tool call
+/// Execute a system info tool call reporting every field, as JSON
 pub async fn execute_system_info() -> ToolResult {
-    let info = serde_json::json!({
-        "os": std::env::consts::OS,
-        "arch": std::env::consts::ARCH,
-        "family": std::env::consts::FAMILY,
-    });
-
-    ToolResult {
-        id: Uuid::new_v4().to_string(),
-        content: vec![ToolContent::Text {
-            text: format!("System Info: {}", info),
-        }],
-        is_error: false,
-    }
+    system_info::execute(&serde_json::json!({})).await
 }
 
 /// Built-in tool for date and time information
 pub fn create_datetime_tool() -> ToolCall {
     ToolCall {
-        id: Uuid::new_v4().to_string(),
+        id: new_id().to_string(),
         name: "datetime_info".to_string(),
         arguments: serde_json::json!({}),
     }
@@ -59,7 +64,7 @@ pub async fn execute_datetime_info() -> ToolResult {
     });
 
     ToolResult {
-        id: Uuid::new_v4().to_string(),
+        id: new_id().to_string(),
         content: vec![ToolContent::Text {
             text: format!(
                 "Date/Time Info: {}",
@@ -67,13 +72,14 @@ pub async fn execute_datetime_info() -> ToolResult {
             ),
         }],
         is_error: false,
+        truncated: false,
     }
 }
 
 /// Built-in tool for location information
 pub fn create_location_tool() -> ToolCall {
     ToolCall {
-        id: Uuid::new_v4().to_string(),
+        id: new_id().to_string(),
         name: "location_info".to_string(),
         arguments: serde_json::json!({}),
     }
@@ -107,7 +113,7 @@ pub async fn execute_location_info() -> ToolResult {
         serde_json::Value::String(now_local.format("%Y-%m-%d %H:%M:%S %Z (%z)").to_string());
 
     ToolResult {
-        id: Uuid::new_v4().to_string(),
+        id: new_id().to_string(),
         content: vec![ToolContent::Text {
             text: format!(
                 "Location Info: {}",
@@ -116,6 +122,7 @@ pub async fn execute_location_info() -> ToolResult {
             ),
         }],
         is_error: false,
+        truncated: false,
     }
 }
 
@@ -287,9 +294,12 @@ async fn get_network_location() -> Result<serde_json::Value, Box<dyn std::error:
     Ok(network_info)
 }
 
-/// Type alias for tool executor functions
-type ToolExecutorFn =
-    Box<dyn Fn() -> Box<dyn std::future::Future<Output = ToolResult> + Send + Unpin> + Send + Sync>;
+/// Type alias for tool executor functions, called with the tool call's arguments
+type ToolExecutorFn = Box<
+    dyn Fn(serde_json::Value) -> Box<dyn std::future::Future<Output = ToolResult> + Send + Unpin>
+        + Send
+        + Sync,
+>;
 
 /// Built-in tool registry
 pub struct BuiltinTools {
@@ -303,43 +313,39 @@ impl BuiltinTools {
         // Add system info tool
         tools.insert(
             "system_info".to_string(),
-            Box::new(|| {
-                Box::new(Box::pin(execute_system_info()))
+            Box::new(|arguments: serde_json::Value| {
+                Box::new(Box::pin(
+                    async move { system_info::execute(&arguments).await },
+                ))
                     as Box<dyn std::future::Future<Output = ToolResult> + Send + Unpin>
-            })
-                as Box<
-                    dyn Fn() -> Box<dyn std::future::Future<Output = ToolResult> + Send + Unpin>
-                        + Send
-                        + Sync,
-                >,
+            }) as ToolExecutorFn,
         );
 
         // Add datetime info tool
         tools.insert(
             "datetime_info".to_string(),
-            Box::new(|| {
+            Box::new(|_| {
                 Box::new(Box::pin(execute_datetime_info()))
                     as Box<dyn std::future::Future<Output = ToolResult> + Send + Unpin>
-            })
-                as Box<
-                    dyn Fn() -> Box<dyn std::future::Future<Output = ToolResult> + Send + Unpin>
-                        + Send
-                        + Sync,
-                >,
+            }) as ToolExecutorFn,
+        );
+
+        // Add date/time arithmetic tool
+        tools.insert(
+            DateTimeTool::NAME.to_string(),
+            Box::new(|arguments: serde_json::Value| {
+                Box::new(Box::pin(async move { DateTimeTool::execute(&arguments) }))
+                    as Box<dyn std::future::Future<Output = ToolResult> + Send + Unpin>
+            }) as ToolExecutorFn,
         );
 
         // Add location info tool
         tools.insert(
             "location_info".to_string(),
-            Box::new(|| {
+            Box::new(|_| {
                 Box::new(Box::pin(execute_location_info()))
                     as Box<dyn std::future::Future<Output = ToolResult> + Send + Unpin>
-            })
-                as Box<
-                    dyn Fn() -> Box<dyn std::future::Future<Output = ToolResult> + Send + Unpin>
-                        + Send
-                        + Sync,
-                >,
+            }) as ToolExecutorFn,
         );
 
         Self { tools }
@@ -350,8 +356,18 @@ impl BuiltinTools {
     }
 
     pub async fn execute(&self, tool_name: &str) -> Option<ToolResult> {
+        self.execute_with_arguments(tool_name, serde_json::json!({}))
+            .await
+    }
+
+    /// Run a built-in tool with the arguments of its tool call
+    pub async fn execute_with_arguments(
+        &self,
+        tool_name: &str,
+        arguments: serde_json::Value,
+    ) -> Option<ToolResult> {
         if let Some(executor) = self.tools.get(tool_name) {
-            Some(executor().await)
+            Some(executor(arguments).await)
         } else {
             None
         }
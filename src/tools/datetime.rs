@@ -0,0 +1,510 @@
+//! Date and time arithmetic tool
+//!
+//! Lets the LLM hand temporal reasoning to code: the current time in a
+//! timezone, adding an ISO-8601 duration to an instant, the duration between
+//! two instants and conversion between IANA timezones. Instants are read and
+//! written as RFC 3339 strings. Bad input produces an error result whose text
+//! is a JSON object with a `code` and a `message`.
+
+use crate::ids::new_id;
+use crate::mcp::{McpTool, ToolContent, ToolResult};
+use chrono::{
+    DateTime, Days, FixedOffset, LocalResult, Months, NaiveDateTime, SecondsFormat, TimeDelta,
+    TimeZone, Utc,
+};
+use chrono_tz::Tz;
+use serde_json::{json, Value};
+
+/// A problem with the arguments of a call
+#[derive(Debug)]
+struct DateTimeError {
+    code: &'static str,
+    message: String,
+}
+
+impl DateTimeError {
+    fn new(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+type ToolOutput = std::result::Result<Value, DateTimeError>;
+
+/// Tool for date and time operations
+pub struct DateTimeTool;
+
+impl DateTimeTool {
+    /// Tool name as exposed to the LLM
+    pub const NAME: &'static str = "datetime";
+
+    /// Supported values of the `operation` argument
+    pub const OPERATIONS: [&'static str; 4] = ["now", "add_duration", "diff", "convert_timezone"];
+
+    /// Tool definition advertised to the LLM
+    pub fn definition() -> McpTool {
+        McpTool {
+            name: Self::NAME.to_string(),
+            description: "Date and time operations: the current time, adding a duration, the \
+                          difference between two times and timezone conversion"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "operation": {
+                        "type": "string",
+                        "enum": Self::OPERATIONS,
+                    },
+                    "datetime": {
+                        "type": "string",
+                        "description": "RFC 3339 time for add_duration and convert_timezone; \
+                                        without an offset it is read in `from_timezone` or `timezone`"
+                    },
+                    "duration": {
+                        "type": "string",
+                        "description": "ISO-8601 duration for add_duration, e.g. P1DT2H or -PT30M"
+                    },
+                    "start": {
+                        "type": "string",
+                        "description": "RFC 3339 start time for diff"
+                    },
+                    "end": {
+                        "type": "string",
+                        "description": "RFC 3339 end time for diff"
+                    },
+                    "timezone": {
+                        "type": "string",
+                        "description": "IANA timezone, e.g. Europe/Paris: the zone for now, the \
+                                        target of convert_timezone and the calendar add_duration uses"
+                    },
+                    "from_timezone": {
+                        "type": "string",
+                        "description": "IANA timezone of a `datetime` given without an offset"
+                    }
+                },
+                "required": ["operation"]
+            }),
+        }
+    }
+
+    /// Execute a `datetime` tool call with the given arguments
+    pub fn execute(arguments: &Value) -> ToolResult {
+        let (text, is_error) = match Self::run(arguments) {
+            Ok(output) => (
+                serde_json::to_string_pretty(&output).unwrap_or_else(|_| output.to_string()),
+                false,
+            ),
+            Err(e) => (
+                json!({ "error": { "code": e.code, "message": e.message } }).to_string(),
+                true,
+            ),
+        };
+
+        ToolResult {
+            id: new_id().to_string(),
+            content: vec![ToolContent::Text { text }],
+            is_error,
+            truncated: false,
+        }
+    }
+
+    fn run(arguments: &Value) -> ToolOutput {
+        match required(arguments, "operation")? {
+            "now" => now(arguments),
+            "add_duration" => add_duration(arguments),
+            "diff" => diff(arguments),
+            "convert_timezone" => convert_timezone(arguments),
+            other => Err(DateTimeError::new(
+                "invalid_operation",
+                format!(
+                    "Unknown operation '{}'; expected one of: {}",
+                    other,
+                    Self::OPERATIONS.join(", ")
+                ),
+            )),
+        }
+    }
+}
+
+fn now(arguments: &Value) -> ToolOutput {
+    let now = Utc::now();
+    match optional_timezone(arguments, "timezone")? {
+        Some(tz) => Ok(describe(&now.with_timezone(&tz), Some(tz))),
+        None => Ok(describe(&now, None)),
+    }
+}
+
+fn add_duration(arguments: &Value) -> ToolOutput {
+    let from = optional_timezone(arguments, "from_timezone")?;
+    let tz = optional_timezone(arguments, "timezone")?;
+    let datetime = parse_datetime(required(arguments, "datetime")?, from.or(tz))?;
+    let duration = IsoDuration::parse(required(arguments, "duration")?)?;
+
+    let out_of_range = || DateTimeError::new("out_of_range", "Result is out of range");
+    match tz {
+        // Calendar units follow the zone's rules, e.g. P1D across a DST change
+        Some(tz) => {
+            let result = duration
+                .add_to(datetime.with_timezone(&tz))
+                .ok_or_else(out_of_range)?;
+            Ok(describe(&result, Some(tz)))
+        }
+        None => {
+            let result = duration.add_to(datetime).ok_or_else(out_of_range)?;
+            Ok(describe(&result, None))
+        }
+    }
+}
+
+fn diff(arguments: &Value) -> ToolOutput {
+    let from = optional_timezone(arguments, "from_timezone")?;
+    let start = parse_datetime(required(arguments, "start")?, from)?;
+    let end = parse_datetime(required(arguments, "end")?, from)?;
+    let delta = end.signed_duration_since(start);
+
+    Ok(json!({
+        "duration": format_duration(delta),
+        "seconds": delta.num_seconds(),
+    }))
+}
+
+fn convert_timezone(arguments: &Value) -> ToolOutput {
+    let tz =
+        optional_timezone(arguments, "timezone")?.ok_or_else(|| missing_argument("timezone"))?;
+    let from = optional_timezone(arguments, "from_timezone")?;
+    let datetime = parse_datetime(required(arguments, "datetime")?, from)?;
+    Ok(describe(&datetime.with_timezone(&tz), Some(tz)))
+}
+
+/// An instant as RFC 3339 with its offset and, if known, its timezone name
+fn describe<Z: TimeZone>(datetime: &DateTime<Z>, tz: Option<Tz>) -> Value {
+    let fixed = datetime.fixed_offset();
+    let mut output = json!({
+        "datetime": fixed.to_rfc3339_opts(SecondsFormat::AutoSi, false),
+        "utc_offset": fixed.format("%:z").to_string(),
+        "unix_timestamp": fixed.timestamp(),
+    });
+    if let Some(tz) = tz {
+        output["timezone"] = Value::String(tz.name().to_string());
+    }
+    output
+}
+
+fn missing_argument(name: &str) -> DateTimeError {
+    DateTimeError::new(
+        "missing_argument",
+        format!("Missing required argument '{}'", name),
+    )
+}
+
+fn required<'a>(arguments: &'a Value, name: &str) -> std::result::Result<&'a str, DateTimeError> {
+    arguments
+        .get(name)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| missing_argument(name))
+}
+
+fn optional_timezone(
+    arguments: &Value,
+    name: &str,
+) -> std::result::Result<Option<Tz>, DateTimeError> {
+    let Some(value) = arguments.get(name).and_then(Value::as_str) else {
+        return Ok(None);
+    };
+    value.trim().parse::<Tz>().map(Some).map_err(|_| {
+        DateTimeError::new(
+            "invalid_timezone",
+            format!(
+                "Unknown timezone '{}'; expected an IANA name such as Europe/Paris",
+                value
+            ),
+        )
+    })
+}
+
+/// An RFC 3339 time, or a time without an offset read in `tz` (UTC if unset)
+fn parse_datetime(
+    value: &str,
+    tz: Option<Tz>,
+) -> std::result::Result<DateTime<FixedOffset>, DateTimeError> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Ok(datetime);
+    }
+
+    let invalid = || {
+        DateTimeError::new(
+            "invalid_datetime",
+            format!("Cannot read '{}' as an RFC 3339 date and time", value),
+        )
+    };
+    let naive = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f"))
+        .map_err(|_| invalid())?;
+    let tz = tz.unwrap_or(Tz::UTC);
+    match tz.from_local_datetime(&naive) {
+        LocalResult::Single(datetime) => Ok(datetime.fixed_offset()),
+        LocalResult::Ambiguous(..) => Err(DateTimeError::new(
+            "invalid_datetime",
+            format!("'{}' is ambiguous in {}; give an offset", value, tz.name()),
+        )),
+        LocalResult::None => Err(DateTimeError::new(
+            "invalid_datetime",
+            format!("'{}' does not exist in {}", value, tz.name()),
+        )),
+    }
+}
+
+/// An ISO-8601 duration such as `P1Y2M3DT4H5M6S`, optionally negated with a
+/// leading `-`. Years and months are calendar months and weeks and days are
+/// calendar days; the time part is exact.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct IsoDuration {
+    negative: bool,
+    months: u32,
+    days: u64,
+    seconds: i64,
+}
+
+impl IsoDuration {
+    fn parse(value: &str) -> std::result::Result<Self, DateTimeError> {
+        let invalid = || {
+            DateTimeError::new(
+                "invalid_duration",
+                format!(
+                    "Cannot read '{}' as an ISO-8601 duration such as P1DT2H30M",
+                    value
+                ),
+            )
+        };
+
+        let (negative, rest) = match value.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, value.strip_prefix('+').unwrap_or(value)),
+        };
+        let rest = rest
+            .strip_prefix('P')
+            .or_else(|| rest.strip_prefix('p'))
+            .ok_or_else(invalid)?;
+        let (date_part, time_part) = match rest.split_once(['T', 't']) {
+            Some((_, "")) => return Err(invalid()),
+            Some((date, time)) => (date, Some(time)),
+            None => (rest, None),
+        };
+
+        let mut duration = Self {
+            negative,
+            ..Self::default()
+        };
+        let mut any = false;
+        for (amount, unit) in components(date_part).ok_or_else(invalid)? {
+            any = true;
+            let (months, days) = match unit.to_ascii_uppercase() {
+                'Y' => (amount.checked_mul(12), Some(0)),
+                'M' => (Some(amount), Some(0)),
+                'W' => (Some(0), amount.checked_mul(7)),
+                'D' => (Some(0), Some(amount)),
+                _ => return Err(invalid()),
+            };
+            duration.months = months
+                .and_then(|months| u32::try_from(months).ok())
+                .and_then(|months| duration.months.checked_add(months))
+                .ok_or_else(invalid)?;
+            duration.days = days
+                .and_then(|days| duration.days.checked_add(days))
+                .ok_or_else(invalid)?;
+        }
+        for (amount, unit) in components(time_part.unwrap_or("")).ok_or_else(invalid)? {
+            any = true;
+            let seconds = i64::try_from(amount).map_err(|_| invalid())?;
+            let factor = match unit.to_ascii_uppercase() {
+                'H' => 3600,
+                'M' => 60,
+                'S' => 1,
+                _ => return Err(invalid()),
+            };
+            duration.seconds = seconds
+                .checked_mul(factor)
+                .and_then(|seconds| duration.seconds.checked_add(seconds))
+                .ok_or_else(invalid)?;
+        }
+
+        if any {
+            Ok(duration)
+        } else {
+            Err(invalid())
+        }
+    }
+
+    /// `datetime` moved by this duration, or `None` out of range. Calendar
+    /// units are applied before the time part.
+    fn add_to<Z: TimeZone>(&self, datetime: DateTime<Z>) -> Option<DateTime<Z>> {
+        let time = TimeDelta::try_seconds(self.seconds)?;
+        if self.negative {
+            datetime
+                .checked_sub_months(Months::new(self.months))?
+                .checked_sub_days(Days::new(self.days))?
+                .checked_sub_signed(time)
+        } else {
+            datetime
+                .checked_add_months(Months::new(self.months))?
+                .checked_add_days(Days::new(self.days))?
+                .checked_add_signed(time)
+        }
+    }
+}
+
+/// `(amount, unit)` pairs of a duration part such as `1Y2M`, or `None` if
+/// it is malformed
+fn components(part: &str) -> Option<Vec<(u64, char)>> {
+    let mut components = Vec::new();
+    let mut digits = String::new();
+    for c in part.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+        } else {
+            components.push((digits.parse().ok()?, c));
+            digits.clear();
+        }
+    }
+    digits.is_empty().then_some(components)
+}
+
+/// `delta` as an ISO-8601 duration in days, hours, minutes and seconds
+fn format_duration(delta: TimeDelta) -> String {
+    let mut seconds = delta.num_seconds();
+    let sign = if seconds < 0 { "-" } else { "" };
+    seconds = seconds.abs();
+
+    let days = seconds / 86_400;
+    let hours = seconds % 86_400 / 3600;
+    let minutes = seconds % 3600 / 60;
+    let seconds = seconds % 60;
+
+    let mut text = format!("{}P", sign);
+    if days > 0 {
+        text.push_str(&format!("{}D", days));
+    }
+    if hours > 0 || minutes > 0 || seconds > 0 || days == 0 {
+        text.push('T');
+        if hours > 0 {
+            text.push_str(&format!("{}H", hours));
+        }
+        if minutes > 0 {
+            text.push_str(&format!("{}M", minutes));
+        }
+        if seconds > 0 || (days == 0 && hours == 0 && minutes == 0) {
+            text.push_str(&format!("{}S", seconds));
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(arguments: Value) -> Value {
+        let result = DateTimeTool::execute(&arguments);
+        let ToolContent::Text { text } = &result.content[0] else {
+            panic!("expected text");
+        };
+        let value: Value = serde_json::from_str(text).unwrap();
+        assert_eq!(result.is_error, value.get("error").is_some(), "{}", text);
+        value
+    }
+
+    #[test]
+    fn test_convert_timezone() {
+        // New York had moved to daylight time earlier that morning
+        let converted = output(json!({
+            "operation": "convert_timezone",
+            "datetime": "2024-03-10T12:00:00Z",
+            "timezone": "America/New_York",
+        }));
+        assert_eq!(converted["datetime"], "2024-03-10T08:00:00-04:00");
+        assert_eq!(converted["timezone"], "America/New_York");
+
+        // A time without an offset is read in from_timezone
+        let converted = output(json!({
+            "operation": "convert_timezone",
+            "datetime": "2024-07-01T09:30:00",
+            "from_timezone": "Europe/Paris",
+            "timezone": "Asia/Tokyo",
+        }));
+        assert_eq!(converted["datetime"], "2024-07-01T16:30:00+09:00");
+
+        let error = output(json!({
+            "operation": "convert_timezone",
+            "datetime": "2024-03-10T12:00:00Z",
+            "timezone": "Mars/Olympus_Mons",
+        }));
+        assert_eq!(error["error"]["code"], "invalid_timezone");
+    }
+
+    #[test]
+    fn test_add_duration() {
+        // Months clamp to the end of the shorter month
+        let added = output(json!({
+            "operation": "add_duration",
+            "datetime": "2024-01-31T10:00:00+00:00",
+            "duration": "P1MT2H30M",
+        }));
+        assert_eq!(added["datetime"], "2024-02-29T12:30:00+00:00");
+
+        // A calendar day across the DST change keeps the wall-clock time
+        let added = output(json!({
+            "operation": "add_duration",
+            "datetime": "2024-03-09T12:00:00-05:00",
+            "duration": "P1D",
+            "timezone": "America/New_York",
+        }));
+        assert_eq!(added["datetime"], "2024-03-10T12:00:00-04:00");
+
+        let subtracted = output(json!({
+            "operation": "add_duration",
+            "datetime": "2024-01-01T00:00:00Z",
+            "duration": "-PT90M",
+        }));
+        assert_eq!(subtracted["datetime"], "2023-12-31T22:30:00+00:00");
+
+        let overflowing = [
+            "P3000000000000000000Y",
+            "P4294967295M1M",
+            "P3000000000000000000W",
+        ];
+        for duration in ["1 day", "P", "PT", "P1H", "PT1D", "P1.5D"]
+            .into_iter()
+            .chain(overflowing)
+        {
+            let error = output(json!({
+                "operation": "add_duration",
+                "datetime": "2024-01-01T00:00:00Z",
+                "duration": duration,
+            }));
+            assert_eq!(error["error"]["code"], "invalid_duration", "{}", duration);
+        }
+    }
+
+    #[test]
+    fn test_diff_and_errors() {
+        let diff = output(json!({
+            "operation": "diff",
+            "start": "2024-01-01T00:00:00Z",
+            "end": "2024-01-02T03:04:05+01:00",
+        }));
+        assert_eq!(diff["duration"], "P1DT2H4M5S");
+        assert_eq!(diff["seconds"], 93_845);
+
+        let error = output(json!({ "operation": "diff", "start": "2024-01-01T00:00:00Z" }));
+        assert_eq!(error["error"]["code"], "missing_argument");
+        let error = output(json!({ "operation": "tomorrow" }));
+        assert_eq!(error["error"]["code"], "invalid_operation");
+
+        let now = output(json!({ "operation": "now", "timezone": "UTC" }));
+        assert!(DateTime::parse_from_rfc3339(now["datetime"].as_str().unwrap()).is_ok());
+    }
+}
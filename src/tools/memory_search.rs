@@ -0,0 +1,287 @@
+//! Memory search tool
+//!
+//! Exposes the agent's vector memory as a tool so the LLM can look things up
+//! on demand instead of relying only on the heuristic retrieval step.
+
+use crate::llm::LlmClient;
+use crate::mcp::{McpTool, ToolCall, ToolContent, ToolResult};
+use crate::memory::shared::merge_results;
+use crate::memory::{MemoryStore, SearchResult, SharedMemory};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{debug, warn};
+
+/// Metadata key used to scope memories to an agent
+pub const MEMORY_NAMESPACE_KEY: &str = "namespace";
+
+/// Tool that runs a similarity search over an agent's memory store
+pub struct MemorySearchTool {
+    memory: Arc<RwLock<Box<dyn MemoryStore>>>,
+    llm: Arc<dyn LlmClient>,
+    namespace: Option<String>,
+    shared: Option<SharedMemory>,
+    default_top_k: usize,
+    threshold: f32,
+}
+
+impl MemorySearchTool {
+    /// Tool name as exposed to the LLM
+    pub const NAME: &'static str = "memory_search";
+
+    pub fn new(memory: Arc<RwLock<Box<dyn MemoryStore>>>, llm: Arc<dyn LlmClient>) -> Self {
+        Self {
+            memory,
+            llm,
+            namespace: None,
+            shared: None,
+            default_top_k: 5,
+            threshold: 0.0,
+        }
+    }
+
+    /// Only return memories tagged with this namespace
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
+    /// Also search `shared`, or stop searching shared memory with `None`
+    pub fn set_shared_memory(&mut self, shared: Option<SharedMemory>) {
+        self.shared = shared;
+    }
+
+    /// Number of results returned when `top_k` is not supplied
+    pub fn with_default_top_k(mut self, top_k: usize) -> Self {
+        self.default_top_k = top_k;
+        self
+    }
+
+    /// Minimum similarity for a memory to be returned
+    pub fn with_threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Tool definition advertised to the LLM
+    pub fn definition() -> McpTool {
+        McpTool {
+            name: Self::NAME.to_string(),
+            description: "Search the agent's long-term memory for entries relevant to a query"
+                .to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "What to look for"
+                    },
+                    "top_k": {
+                        "type": "integer",
+                        "description": "Maximum number of results",
+                        "minimum": 1
+                    }
+                },
+                "required": ["query"]
+            }),
+        }
+    }
+
+    /// Execute a `memory_search` tool call
+    pub async fn execute(&self, call: &ToolCall) -> ToolResult {
+        let query = match call.arguments.get("query").and_then(|q| q.as_str()) {
+            Some(query) if !query.trim().is_empty() => query,
+            _ => return Self::error_result(&call.id, "Missing required argument 'query'"),
+        };
+
+        let top_k = call
+            .arguments
+            .get("top_k")
+            .and_then(|k| k.as_u64())
+            .map(|k| k as usize)
+            .unwrap_or(self.default_top_k)
+            .max(1);
+
+        match self.search(query, top_k).await {
+            Ok(results) => ToolResult {
+                id: call.id.clone(),
+                content: vec![ToolContent::Text {
+                    text: Self::format_results(query, &results),
+                }],
+                is_error: false,
+                truncated: false,
+            },
+            Err(e) => Self::error_result(&call.id, &format!("Memory search failed: {}", e)),
+        }
+    }
+
+    async fn search(&self, query: &str, top_k: usize) -> crate::error::Result<Vec<SearchResult>> {
+        // When scoped to a namespace we filter after the search, so fetch everything
+        // above the threshold and truncate afterwards.
+        let limit = if self.namespace.is_some() {
+            usize::MAX
+        } else {
+            top_k
+        };
+
+        let memory = self.memory.read().await;
+        let embedding = match self.llm.embed(query).await {
+            Ok(response) => Some(response.embedding),
+            Err(e) => {
+                warn!("Embedding failed, using keyword memory search: {}", e);
+                None
+            }
+        };
+        let mut results = match &embedding {
+            Some(embedding) => {
+                memory
+                    .search(embedding.clone(), limit, self.threshold)
+                    .await?
+            }
+            None => memory.search_keywords(query, limit).await?,
+        };
+
+        if let Some(namespace) = &self.namespace {
+            results.retain(|r| r.entry.metadata.get(MEMORY_NAMESPACE_KEY) == Some(namespace));
+        }
+        if let Some(shared) = &self.shared {
+            let found = match embedding {
+                Some(embedding) => shared.search(embedding, top_k, self.threshold).await?,
+                None => shared.search_keywords(query, top_k).await?,
+            };
+            results = merge_results(results, found, top_k);
+        }
+        results.truncate(top_k);
+
+        debug!(
+            "memory_search returned {} results for '{}'",
+            results.len(),
+            query
+        );
+        Ok(results)
+    }
+
+    fn format_results(query: &str, results: &[SearchResult]) -> String {
+        if results.is_empty() {
+            return format!("No memories found for '{}'", query);
+        }
+
+        let mut text = format!("Memories matching '{}':\n", query);
+        for (idx, result) in results.iter().enumerate() {
+            text.push_str(&format!(
+                "{}. (similarity {:.2}) {}\n",
+                idx + 1,
+                result.similarity,
+                result.entry.content
+            ));
+        }
+        text
+    }
+
+    fn error_result(id: &str, message: &str) -> ToolResult {
+        ToolResult {
+            id: id.to_string(),
+            content: vec![ToolContent::Text {
+                text: message.to_string(),
+            }],
+            is_error: true,
+            truncated: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MemoryConfig;
+    use crate::llm::mock::MockLlm;
+    use crate::memory::SqliteMemoryStore;
+    use std::collections::HashMap;
+
+    /// Embeds text onto two axes: "rust" and "cooking"
+    fn keyword_embedding(text: &str) -> Vec<f32> {
+        let lower = text.to_lowercase();
+        vec![
+            if lower.contains("rust") { 1.0 } else { 0.0 },
+            if lower.contains("cook") { 1.0 } else { 0.0 },
+            0.1,
+        ]
+    }
+
+    fn keyword_embedder() -> Arc<MockLlm> {
+        Arc::new(MockLlm::new().with_embed(|text| Ok(keyword_embedding(text))))
+    }
+
+    async fn create_store() -> Arc<RwLock<Box<dyn MemoryStore>>> {
+        let config = MemoryConfig {
+            database_url: Some("sqlite::memory:".to_string()),
+            embedding_dimension: 3,
+            ..Default::default()
+        };
+        let mut store: Box<dyn MemoryStore> = Box::new(SqliteMemoryStore::new(config));
+        store.initialize().await.unwrap();
+
+        for content in ["Rust ownership rules", "Cooking pasta al dente"] {
+            store
+                .store(
+                    content.to_string(),
+                    keyword_embedding(content),
+                    HashMap::from([(MEMORY_NAMESPACE_KEY.to_string(), "agent-a".to_string())]),
+                )
+                .await
+                .unwrap();
+        }
+
+        Arc::new(RwLock::new(store))
+    }
+
+    fn call(arguments: serde_json::Value) -> ToolCall {
+        ToolCall {
+            id: "call-1".to_string(),
+            name: MemorySearchTool::NAME.to_string(),
+            arguments,
+        }
+    }
+
+    fn text(result: &ToolResult) -> &str {
+        match &result.content[0] {
+            ToolContent::Text { text } => text,
+            _ => panic!("expected text content"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_memory_search_returns_relevant_entry() {
+        let tool = MemorySearchTool::new(create_store().await, keyword_embedder());
+
+        let result = tool
+            .execute(&call(
+                serde_json::json!({"query": "rust borrow checker", "top_k": 1}),
+            ))
+            .await;
+
+        assert!(!result.is_error);
+        assert_eq!(result.id, "call-1");
+        assert!(text(&result).contains("Rust ownership rules"));
+        assert!(!text(&result).contains("Cooking"));
+    }
+
+    #[tokio::test]
+    async fn test_memory_search_respects_namespace() {
+        let tool = MemorySearchTool::new(create_store().await, keyword_embedder())
+            .with_namespace("agent-b");
+
+        let result = tool
+            .execute(&call(serde_json::json!({"query": "rust"})))
+            .await;
+
+        assert!(!result.is_error);
+        assert!(text(&result).starts_with("No memories found"));
+    }
+
+    #[tokio::test]
+    async fn test_memory_search_requires_query() {
+        let tool = MemorySearchTool::new(create_store().await, keyword_embedder());
+        let result = tool.execute(&call(serde_json::json!({}))).await;
+        assert!(result.is_error);
+    }
+}
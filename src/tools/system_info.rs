@@ -0,0 +1,369 @@
+//! System information tool
+//!
+//! Reports facts about the host as a JSON object keyed by field name, so an
+//! agent can read specific values instead of parsing prose. Callers choose the
+//! fields with a `fields` argument and get all of them when it is omitted. A
+//! field the platform can't provide is reported as
+//! `{"unavailable": "<reason>"}` rather than failing the whole call.
+
+use crate::ids::new_id;
+use crate::mcp::{McpTool, ToolContent, ToolResult};
+use serde_json::{json, Map, Value};
+#[cfg(unix)]
+use tokio::process::Command;
+
+/// Tool name as exposed to the LLM
+pub const SYSTEM_INFO_TOOL: &str = "system_info";
+
+/// A group of facts the `system_info` tool can report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SystemInfoField {
+    Os,
+    Cpu,
+    Memory,
+    Disk,
+    Uptime,
+}
+
+impl SystemInfoField {
+    pub const ALL: [Self; 5] = [Self::Os, Self::Cpu, Self::Memory, Self::Disk, Self::Uptime];
+
+    /// Key of this field in arguments and results
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Os => "os",
+            Self::Cpu => "cpu",
+            Self::Memory => "memory",
+            Self::Disk => "disk",
+            Self::Uptime => "uptime",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|field| field.name().eq_ignore_ascii_case(name.trim()))
+    }
+
+    async fn collect(self) -> Value {
+        let value = match self {
+            Self::Os => Ok(os_info().await),
+            Self::Cpu => Ok(cpu_info().await),
+            Self::Memory => memory_info().await,
+            Self::Disk => disk_info().await,
+            Self::Uptime => uptime_info().await,
+        };
+        value.unwrap_or_else(|reason| json!({ "unavailable": reason }))
+    }
+}
+
+/// Tool definition advertised to the LLM
+pub fn definition() -> McpTool {
+    let names: Vec<&str> = SystemInfoField::ALL.iter().map(|f| f.name()).collect();
+    McpTool {
+        name: SYSTEM_INFO_TOOL.to_string(),
+        description: "Report facts about the host system as JSON".to_string(),
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "fields": {
+                    "type": "array",
+                    "items": { "type": "string", "enum": names },
+                    "description": "Fields to report; all of them when omitted"
+                }
+            }
+        }),
+    }
+}
+
+/// Fields requested by a call's `arguments`: every field when `fields` is
+/// absent, or an error naming the first field that isn't supported
+pub fn requested_fields(arguments: &Value) -> Result<Vec<SystemInfoField>, String> {
+    let Some(fields) = arguments.get("fields").filter(|fields| !fields.is_null()) else {
+        return Ok(SystemInfoField::ALL.to_vec());
+    };
+    let Some(fields) = fields.as_array() else {
+        return Err("'fields' must be an array of field names".to_string());
+    };
+
+    let mut requested = Vec::new();
+    for field in fields {
+        let field = field
+            .as_str()
+            .and_then(SystemInfoField::from_name)
+            .ok_or_else(|| {
+                let supported: Vec<&str> = SystemInfoField::ALL.iter().map(|f| f.name()).collect();
+                format!(
+                    "Unsupported field {}; expected one of: {}",
+                    field,
+                    supported.join(", ")
+                )
+            })?;
+        if !requested.contains(&field) {
+            requested.push(field);
+        }
+    }
+    Ok(requested)
+}
+
+/// The requested fields, keyed by name
+pub async fn collect(fields: &[SystemInfoField]) -> Value {
+    let mut info = Map::new();
+    for field in fields {
+        info.insert(field.name().to_string(), field.collect().await);
+    }
+    Value::Object(info)
+}
+
+/// Execute a `system_info` tool call with the given arguments
+pub async fn execute(arguments: &Value) -> ToolResult {
+    let (text, is_error) = match requested_fields(arguments) {
+        Ok(fields) => {
+            let info = collect(&fields).await;
+            (
+                serde_json::to_string_pretty(&info).unwrap_or_else(|_| info.to_string()),
+                false,
+            )
+        }
+        Err(message) => (message, true),
+    };
+
+    ToolResult {
+        id: new_id().to_string(),
+        content: vec![ToolContent::Text { text }],
+        is_error,
+        truncated: false,
+    }
+}
+
+async fn os_info() -> Value {
+    let mut info = json!({
+        "name": std::env::consts::OS,
+        "family": std::env::consts::FAMILY,
+        "arch": std::env::consts::ARCH,
+    });
+    if let Some(version) = os_version().await {
+        info["version"] = Value::String(version);
+    }
+    info
+}
+
+async fn os_version() -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        let release = tokio::fs::read_to_string("/etc/os-release").await.ok()?;
+        release.lines().find_map(|line| {
+            line.strip_prefix("PRETTY_NAME=")
+                .map(|name| name.trim_matches('"').to_string())
+        })
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        command_output("sw_vers", &["-productVersion"]).await
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    {
+        None
+    }
+}
+
+async fn cpu_info() -> Value {
+    let mut info = json!({ "arch": std::env::consts::ARCH });
+    if let Ok(cores) = std::thread::available_parallelism() {
+        info["logical_cores"] = cores.get().into();
+    }
+    if let Some(model) = cpu_model().await {
+        info["model"] = Value::String(model);
+    }
+    info
+}
+
+async fn cpu_model() -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        let cpuinfo = tokio::fs::read_to_string("/proc/cpuinfo").await.ok()?;
+        cpuinfo.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            (key.trim() == "model name").then(|| value.trim().to_string())
+        })
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        command_output("sysctl", &["-n", "machdep.cpu.brand_string"]).await
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    {
+        None
+    }
+}
+
+async fn memory_info() -> Result<Value, String> {
+    #[cfg(target_os = "linux")]
+    {
+        let meminfo = tokio::fs::read_to_string("/proc/meminfo")
+            .await
+            .map_err(|e| format!("cannot read /proc/meminfo: {}", e))?;
+        // Values are reported in kB
+        let bytes = |key: &str| {
+            meminfo.lines().find_map(|line| {
+                let value = line.strip_prefix(key)?.strip_prefix(':')?;
+                let kb: u64 = value.trim().trim_end_matches("kB").trim().parse().ok()?;
+                Some(kb * 1024)
+            })
+        };
+        let total = bytes("MemTotal").ok_or("MemTotal missing from /proc/meminfo")?;
+        let available = bytes("MemAvailable").or_else(|| bytes("MemFree"));
+        Ok(json!({
+            "total_bytes": total,
+            "available_bytes": available,
+            "used_bytes": available.map(|available| total.saturating_sub(available)),
+        }))
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let total: u64 = command_output("sysctl", &["-n", "hw.memsize"])
+            .await
+            .and_then(|total| total.parse().ok())
+            .ok_or("sysctl hw.memsize failed")?;
+        Ok(json!({ "total_bytes": total }))
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    {
+        Err(unsupported())
+    }
+}
+
+async fn disk_info() -> Result<Value, String> {
+    #[cfg(unix)]
+    {
+        // POSIX output: filesystem, 1024-blocks, used, available, capacity, mount
+        let output = command_output("df", &["-kP", "/"])
+            .await
+            .ok_or("df failed")?;
+        let columns: Vec<&str> = output
+            .lines()
+            .nth(1)
+            .ok_or("unexpected df output")?
+            .split_whitespace()
+            .collect();
+        let kb = |index: usize| {
+            columns
+                .get(index)
+                .and_then(|value| value.parse::<u64>().ok())
+                .map(|kb| kb * 1024)
+        };
+        Ok(json!({
+            "mount": columns.last().copied().unwrap_or("/"),
+            "total_bytes": kb(1).ok_or("unexpected df output")?,
+            "used_bytes": kb(2),
+            "available_bytes": kb(3),
+        }))
+    }
+
+    #[cfg(not(unix))]
+    {
+        Err(unsupported())
+    }
+}
+
+async fn uptime_info() -> Result<Value, String> {
+    #[cfg(target_os = "linux")]
+    {
+        let uptime = tokio::fs::read_to_string("/proc/uptime")
+            .await
+            .map_err(|e| format!("cannot read /proc/uptime: {}", e))?;
+        let seconds: f64 = uptime
+            .split_whitespace()
+            .next()
+            .and_then(|seconds| seconds.parse().ok())
+            .ok_or("unexpected /proc/uptime contents")?;
+        Ok(json!({ "seconds": seconds as u64 }))
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        // Prints e.g. `{ sec = 1700000000, usec = 0 } Tue Nov 14 ...`
+        let boot_time: u64 = command_output("sysctl", &["-n", "kern.boottime"])
+            .await
+            .and_then(|output| {
+                let seconds = output.split("sec = ").nth(1)?.split(',').next()?;
+                seconds.trim().parse().ok()
+            })
+            .ok_or("sysctl kern.boottime failed")?;
+        let now = chrono::Utc::now().timestamp().max(0) as u64;
+        Ok(json!({ "seconds": now.saturating_sub(boot_time) }))
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    {
+        Err(unsupported())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn unsupported() -> String {
+    format!("not supported on {}", std::env::consts::OS)
+}
+
+/// Trimmed stdout of a successful command
+#[cfg(unix)]
+async fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().await.ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result_json(result: &ToolResult) -> Value {
+        match &result.content[0] {
+            ToolContent::Text { text } => serde_json::from_str(text).unwrap(),
+            other => panic!("unexpected content {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reports_only_requested_fields() {
+        let result = execute(&json!({ "fields": ["os", "memory"] })).await;
+        assert!(!result.is_error);
+
+        let info = result_json(&result);
+        let mut keys: Vec<&str> = info
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        keys.sort_unstable();
+        assert_eq!(keys, vec!["memory", "os"]);
+        assert_eq!(info["os"]["name"], std::env::consts::OS);
+    }
+
+    #[tokio::test]
+    async fn test_all_fields_by_default() {
+        let info = result_json(&execute(&json!({})).await);
+        for field in SystemInfoField::ALL {
+            assert!(info.get(field.name()).is_some(), "missing {}", field.name());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_field_is_an_error() {
+        let result = execute(&json!({ "fields": ["os", "gpu"] })).await;
+        assert!(result.is_error);
+        let ToolContent::Text { text } = &result.content[0] else {
+            panic!("expected text");
+        };
+        assert!(text.contains("\"gpu\""));
+    }
+}
@@ -7,7 +7,12 @@
 //! - Evaluation dataset and scoring management
 //! - Cross-component data consistency and isolation
 
+pub mod file_trace;
+
+pub use file_trace::FileTraceStorage;
+
 use crate::error::{AgentError, Result};
+use crate::ids::new_id;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -15,7 +20,6 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 use tokio::sync::RwLock;
-use uuid::Uuid;
 
 /// Resource identifier for scoping data
 #[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
@@ -37,6 +41,45 @@ impl ResourceId {
     }
 }
 
+/// A stored record together with its version.
+///
+/// Every write to a record bumps its version, starting from 1; a record that
+/// doesn't exist is at version 0. The `update_*` methods of
+/// [`UnifiedStorage`] only write when the record is still at the version the
+/// caller last read, so concurrent writers can't silently overwrite each other.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Versioned<T> {
+    pub value: T,
+    pub version: u64,
+}
+
+/// Store `value` under `key`, bumping its version. With `expected_version`
+/// set, fails with [`AgentError::Conflict`] unless the stored version matches.
+fn put_versioned<T>(
+    records: &mut HashMap<String, Versioned<T>>,
+    resource: &str,
+    key: &str,
+    value: T,
+    expected_version: Option<u64>,
+) -> Result<u64> {
+    let current = records.get(key).map_or(0, |record| record.version);
+    if let Some(expected) = expected_version.filter(|expected| *expected != current) {
+        return Err(AgentError::Conflict {
+            resource: format!("{} {}", resource, key),
+            expected,
+            actual: current,
+        });
+    }
+
+    let version = current + 1;
+    records.insert(key.to_string(), Versioned { value, version });
+    Ok(version)
+}
+
+fn versioning_unsupported() -> AgentError {
+    AgentError::Config("This storage does not support versioned updates".to_string())
+}
+
 /// Suspended workflow state for serialization and resumption
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SuspendedWorkflow {
@@ -153,6 +196,29 @@ pub struct EvalDataset {
     pub created_at: SystemTime,
     pub version: String,
     pub metadata: HashMap<String, String>,
+    #[serde(default)]
+    pub items: Vec<EvalItem>,
+}
+
+/// A single input with its reference answer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalItem {
+    pub item_id: String,
+    pub input: String,
+    pub expected: String,
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+}
+
+impl EvalItem {
+    pub fn new(input: &str, expected: &str) -> Self {
+        Self {
+            item_id: new_id().to_string(),
+            input: input.to_string(),
+            expected: expected.to_string(),
+            metadata: HashMap::new(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -213,6 +279,50 @@ pub struct TraceFilters {
     pub max_duration_ms: Option<u64>,
 }
 
+impl TraceFilters {
+    /// Whether `trace` passes every filter that is set. Statuses match by
+    /// kind, so an `Error` filter matches errors with any message.
+    pub fn matches(&self, trace: &TraceData) -> bool {
+        self.component
+            .as_ref()
+            .is_none_or(|component| &trace.component == component)
+            && self
+                .operation_name
+                .as_ref()
+                .is_none_or(|operation_name| &trace.operation_name == operation_name)
+            && self.status.as_ref().is_none_or(|status| {
+                std::mem::discriminant(status) == std::mem::discriminant(&trace.status)
+            })
+            && self
+                .start_time_after
+                .is_none_or(|after| trace.start_time >= after)
+            && self
+                .start_time_before
+                .is_none_or(|before| trace.start_time < before)
+            && self
+                .min_duration_ms
+                .is_none_or(|min| trace.duration_ms.is_some_and(|duration| duration >= min))
+            && self
+                .max_duration_ms
+                .is_none_or(|max| trace.duration_ms.is_some_and(|duration| duration <= max))
+    }
+}
+
+/// Storage for trace data on its own, for backends that keep traces apart
+/// from the rest of [`UnifiedStorage`]
+#[async_trait]
+pub trait TraceStorage: Send + Sync {
+    async fn store_trace(&self, trace: &TraceData) -> Result<()>;
+    async fn get_trace(&self, trace_id: &str) -> Result<Option<TraceData>>;
+    async fn query_traces(
+        &self,
+        resource_id: &ResourceId,
+        filters: TraceFilters,
+    ) -> Result<Vec<TraceData>>;
+    async fn delete_traces_before(&self, timestamp: SystemTime) -> Result<usize>;
+    async fn count_traces(&self) -> Result<usize>;
+}
+
 /// Unified storage interface
 #[async_trait]
 pub trait UnifiedStorage: Send + Sync {
@@ -258,16 +368,65 @@ pub trait UnifiedStorage: Send + Sync {
     // Storage Management
     async fn get_storage_stats(&self) -> Result<StorageStats>;
     async fn cleanup_old_data(&self, retention_policy: &RetentionPolicy) -> Result<CleanupStats>;
+
+    // Optimistic Concurrency
+    //
+    // Each update writes only if the record is still at `expected_version`,
+    // as returned by the matching versioned get (0 to create a record that
+    // must not exist yet), and returns the new version. A stale version fails
+    // with `AgentError::Conflict`; re-read and retry. Storages without
+    // versioning fail these calls.
+    async fn get_suspended_workflow_versioned(
+        &self,
+        _workflow_id: &str,
+    ) -> Result<Option<Versioned<SuspendedWorkflow>>> {
+        Err(versioning_unsupported())
+    }
+    async fn update_suspended_workflow(
+        &self,
+        _workflow: &SuspendedWorkflow,
+        _expected_version: u64,
+    ) -> Result<u64> {
+        Err(versioning_unsupported())
+    }
+    async fn get_memory_thread_versioned(
+        &self,
+        _thread_id: &str,
+    ) -> Result<Option<Versioned<MemoryThread>>> {
+        Err(versioning_unsupported())
+    }
+    async fn update_memory_thread(
+        &self,
+        _thread: &MemoryThread,
+        _expected_version: u64,
+    ) -> Result<u64> {
+        Err(versioning_unsupported())
+    }
+    async fn get_eval_dataset_versioned(
+        &self,
+        _dataset_id: &str,
+    ) -> Result<Option<Versioned<EvalDataset>>> {
+        Err(versioning_unsupported())
+    }
+    async fn update_eval_dataset(
+        &self,
+        _dataset: &EvalDataset,
+        _expected_version: u64,
+    ) -> Result<u64> {
+        Err(versioning_unsupported())
+    }
 }
 
 /// In-memory test implementation
 pub struct InMemoryUnifiedStorage {
-    workflows: Arc<RwLock<HashMap<String, SuspendedWorkflow>>>,
-    threads: Arc<RwLock<HashMap<String, MemoryThread>>>,
+    workflows: Arc<RwLock<HashMap<String, Versioned<SuspendedWorkflow>>>>,
+    threads: Arc<RwLock<HashMap<String, Versioned<MemoryThread>>>>,
     messages: Arc<RwLock<HashMap<String, Vec<MemoryMessage>>>>,
     traces: Arc<RwLock<HashMap<String, TraceData>>>,
-    datasets: Arc<RwLock<HashMap<String, EvalDataset>>>,
+    datasets: Arc<RwLock<HashMap<String, Versioned<EvalDataset>>>>,
     scores: Arc<RwLock<HashMap<String, Vec<EvalScore>>>>,
+    /// Where traces go instead of `traces`, when set
+    trace_storage: Option<Arc<dyn TraceStorage>>,
 }
 
 impl Default for InMemoryUnifiedStorage {
@@ -285,21 +444,35 @@ impl InMemoryUnifiedStorage {
             traces: Arc::new(RwLock::new(HashMap::new())),
             datasets: Arc::new(RwLock::new(HashMap::new())),
             scores: Arc::new(RwLock::new(HashMap::new())),
+            trace_storage: None,
         }
     }
+
+    /// Keep traces in `trace_storage` instead of in memory, so they survive
+    /// restarts and don't grow the process without bound
+    pub fn with_trace_storage(mut self, trace_storage: Arc<dyn TraceStorage>) -> Self {
+        self.trace_storage = Some(trace_storage);
+        self
+    }
 }
 
 #[async_trait]
 impl UnifiedStorage for InMemoryUnifiedStorage {
     async fn store_suspended_workflow(&self, workflow: &SuspendedWorkflow) -> Result<()> {
         let mut workflows = self.workflows.write().await;
-        workflows.insert(workflow.workflow_id.clone(), workflow.clone());
+        put_versioned(
+            &mut workflows,
+            "suspended workflow",
+            &workflow.workflow_id,
+            workflow.clone(),
+            None,
+        )?;
         Ok(())
     }
 
     async fn get_suspended_workflow(&self, workflow_id: &str) -> Result<Option<SuspendedWorkflow>> {
         let workflows = self.workflows.read().await;
-        Ok(workflows.get(workflow_id).cloned())
+        Ok(workflows.get(workflow_id).map(|w| w.value.clone()))
     }
 
     async fn list_suspended_workflows(
@@ -309,8 +482,8 @@ impl UnifiedStorage for InMemoryUnifiedStorage {
         let workflows = self.workflows.read().await;
         Ok(workflows
             .values()
-            .filter(|w| &w.resource_id == resource_id)
-            .cloned()
+            .filter(|w| &w.value.resource_id == resource_id)
+            .map(|w| w.value.clone())
             .collect())
     }
 
@@ -318,6 +491,7 @@ impl UnifiedStorage for InMemoryUnifiedStorage {
         let mut workflows = self.workflows.write().await;
         workflows
             .remove(workflow_id)
+            .map(|w| w.value)
             .ok_or_else(|| AgentError::Config(format!("Workflow {} not found", workflow_id)))
     }
 
@@ -329,21 +503,27 @@ impl UnifiedStorage for InMemoryUnifiedStorage {
 
     async fn create_memory_thread(&self, thread: &MemoryThread) -> Result<()> {
         let mut threads = self.threads.write().await;
-        threads.insert(thread.thread_id.clone(), thread.clone());
+        put_versioned(
+            &mut threads,
+            "memory thread",
+            &thread.thread_id,
+            thread.clone(),
+            None,
+        )?;
         Ok(())
     }
 
     async fn get_memory_thread(&self, thread_id: &str) -> Result<Option<MemoryThread>> {
         let threads = self.threads.read().await;
-        Ok(threads.get(thread_id).cloned())
+        Ok(threads.get(thread_id).map(|t| t.value.clone()))
     }
 
     async fn list_memory_threads(&self, resource_id: &ResourceId) -> Result<Vec<MemoryThread>> {
         let threads = self.threads.read().await;
         Ok(threads
             .values()
-            .filter(|t| &t.resource_id == resource_id)
-            .cloned()
+            .filter(|t| &t.value.resource_id == resource_id)
+            .map(|t| t.value.clone())
             .collect())
     }
 
@@ -382,12 +562,18 @@ impl UnifiedStorage for InMemoryUnifiedStorage {
     }
 
     async fn store_trace(&self, trace: &TraceData) -> Result<()> {
+        if let Some(trace_storage) = &self.trace_storage {
+            return trace_storage.store_trace(trace).await;
+        }
         let mut traces = self.traces.write().await;
         traces.insert(trace.trace_id.clone(), trace.clone());
         Ok(())
     }
 
     async fn get_trace(&self, trace_id: &str) -> Result<Option<TraceData>> {
+        if let Some(trace_storage) = &self.trace_storage {
+            return trace_storage.get_trace(trace_id).await;
+        }
         let traces = self.traces.read().await;
         Ok(traces.get(trace_id).cloned())
     }
@@ -397,26 +583,21 @@ impl UnifiedStorage for InMemoryUnifiedStorage {
         resource_id: &ResourceId,
         filters: TraceFilters,
     ) -> Result<Vec<TraceData>> {
+        if let Some(trace_storage) = &self.trace_storage {
+            return trace_storage.query_traces(resource_id, filters).await;
+        }
         let traces = self.traces.read().await;
-        let mut result: Vec<TraceData> = traces
+        Ok(traces
             .values()
-            .filter(|t| &t.resource_id == resource_id)
+            .filter(|t| &t.resource_id == resource_id && filters.matches(t))
             .cloned()
-            .collect();
-
-        // Apply filters
-        if let Some(component) = &filters.component {
-            result.retain(|t| &t.component == component);
-        }
-
-        if let Some(operation_name) = &filters.operation_name {
-            result.retain(|t| &t.operation_name == operation_name);
-        }
-
-        Ok(result)
+            .collect())
     }
 
     async fn delete_traces_before(&self, timestamp: SystemTime) -> Result<usize> {
+        if let Some(trace_storage) = &self.trace_storage {
+            return trace_storage.delete_traces_before(timestamp).await;
+        }
         let mut traces = self.traces.write().await;
         let initial_count = traces.len();
         traces.retain(|_, trace| trace.start_time >= timestamp);
@@ -425,21 +606,27 @@ impl UnifiedStorage for InMemoryUnifiedStorage {
 
     async fn create_eval_dataset(&self, dataset: &EvalDataset) -> Result<()> {
         let mut datasets = self.datasets.write().await;
-        datasets.insert(dataset.dataset_id.clone(), dataset.clone());
+        put_versioned(
+            &mut datasets,
+            "eval dataset",
+            &dataset.dataset_id,
+            dataset.clone(),
+            None,
+        )?;
         Ok(())
     }
 
     async fn get_eval_dataset(&self, dataset_id: &str) -> Result<Option<EvalDataset>> {
         let datasets = self.datasets.read().await;
-        Ok(datasets.get(dataset_id).cloned())
+        Ok(datasets.get(dataset_id).map(|d| d.value.clone()))
     }
 
     async fn list_eval_datasets(&self, resource_id: &ResourceId) -> Result<Vec<EvalDataset>> {
         let datasets = self.datasets.read().await;
         Ok(datasets
             .values()
-            .filter(|d| &d.resource_id == resource_id)
-            .cloned()
+            .filter(|d| &d.value.resource_id == resource_id)
+            .map(|d| d.value.clone())
             .collect())
     }
 
@@ -459,7 +646,10 @@ impl UnifiedStorage for InMemoryUnifiedStorage {
         let workflows = self.workflows.read().await;
         let threads = self.threads.read().await;
         let messages = self.messages.read().await;
-        let traces = self.traces.read().await;
+        let trace_count = match &self.trace_storage {
+            Some(trace_storage) => trace_storage.count_traces().await?,
+            None => self.traces.read().await.len(),
+        };
         let datasets = self.datasets.read().await;
         let scores = self.scores.read().await;
 
@@ -470,7 +660,7 @@ impl UnifiedStorage for InMemoryUnifiedStorage {
             suspended_workflows: workflows.len(),
             memory_threads: threads.len(),
             memory_messages: total_messages,
-            traces: traces.len(),
+            traces: trace_count,
             eval_datasets: datasets.len(),
             eval_runs: scores.len(),
             eval_scores: total_scores,
@@ -500,6 +690,72 @@ impl UnifiedStorage for InMemoryUnifiedStorage {
             bytes_freed: 1024 * 100, // Mock 100KB freed
         })
     }
+
+    async fn get_suspended_workflow_versioned(
+        &self,
+        workflow_id: &str,
+    ) -> Result<Option<Versioned<SuspendedWorkflow>>> {
+        Ok(self.workflows.read().await.get(workflow_id).cloned())
+    }
+
+    async fn update_suspended_workflow(
+        &self,
+        workflow: &SuspendedWorkflow,
+        expected_version: u64,
+    ) -> Result<u64> {
+        let mut workflows = self.workflows.write().await;
+        put_versioned(
+            &mut workflows,
+            "suspended workflow",
+            &workflow.workflow_id,
+            workflow.clone(),
+            Some(expected_version),
+        )
+    }
+
+    async fn get_memory_thread_versioned(
+        &self,
+        thread_id: &str,
+    ) -> Result<Option<Versioned<MemoryThread>>> {
+        Ok(self.threads.read().await.get(thread_id).cloned())
+    }
+
+    async fn update_memory_thread(
+        &self,
+        thread: &MemoryThread,
+        expected_version: u64,
+    ) -> Result<u64> {
+        let mut threads = self.threads.write().await;
+        put_versioned(
+            &mut threads,
+            "memory thread",
+            &thread.thread_id,
+            thread.clone(),
+            Some(expected_version),
+        )
+    }
+
+    async fn get_eval_dataset_versioned(
+        &self,
+        dataset_id: &str,
+    ) -> Result<Option<Versioned<EvalDataset>>> {
+        Ok(self.datasets.read().await.get(dataset_id).cloned())
+    }
+
+    async fn update_eval_dataset(
+        &self,
+        dataset: &EvalDataset,
+        expected_version: u64,
+    ) -> Result<u64> {
+        let mut datasets = self.datasets.write().await;
+        put_versioned(
+            &mut datasets,
+            "eval dataset",
+            &dataset.dataset_id,
+            dataset.clone(),
+            Some(expected_version),
+        )
+    }
 }
 
 /// Storage manager for coordination
@@ -553,7 +809,7 @@ impl StorageManager {
         resource_id: ResourceId,
         title: &str,
     ) -> Result<String> {
-        let thread_id = Uuid::new_v4().to_string();
+        let thread_id = new_id().to_string();
         let thread = MemoryThread {
             thread_id: thread_id.clone(),
             resource_id,
@@ -575,7 +831,7 @@ impl StorageManager {
         role: MessageRole,
         content: &str,
     ) -> Result<String> {
-        let message_id = Uuid::new_v4().to_string();
+        let message_id = new_id().to_string();
         let message = MemoryMessage {
             message_id: message_id.clone(),
             thread_id: thread_id.to_string(),
@@ -602,8 +858,8 @@ impl StorageManager {
         status: TraceStatus,
         attributes: HashMap<String, String>,
     ) -> Result<String> {
-        let trace_id = Uuid::new_v4().to_string();
-        let span_id = Uuid::new_v4().to_string();
+        let trace_id = new_id().to_string();
+        let span_id = new_id().to_string();
         let duration_ms = end_time
             .duration_since(start_time)
             .ok()
@@ -635,7 +891,7 @@ impl StorageManager {
         description: &str,
         version: &str,
     ) -> Result<String> {
-        let dataset_id = Uuid::new_v4().to_string();
+        let dataset_id = new_id().to_string();
         let dataset = EvalDataset {
             dataset_id: dataset_id.clone(),
             name: name.to_string(),
@@ -644,6 +900,7 @@ impl StorageManager {
             created_at: SystemTime::now(),
             version: version.to_string(),
             metadata: HashMap::new(),
+            items: Vec::new(),
         };
 
         self.storage.create_eval_dataset(&dataset).await?;
@@ -661,7 +918,7 @@ impl StorageManager {
         reason: &str,
         scorer_name: &str,
     ) -> Result<String> {
-        let score_id = Uuid::new_v4().to_string();
+        let score_id = new_id().to_string();
         let eval_score = EvalScore {
             score_id: score_id.clone(),
             run_id: run_id.to_string(),
@@ -698,3 +955,120 @@ impl Default for RetentionPolicy {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn thread(title: &str) -> MemoryThread {
+        MemoryThread {
+            thread_id: "thread-1".to_string(),
+            resource_id: ResourceId::new("tenant", "agent"),
+            title: title.to_string(),
+            created_at: SystemTime::now(),
+            updated_at: SystemTime::now(),
+            metadata: HashMap::new(),
+            message_count: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stale_version_update_is_rejected() {
+        let storage = InMemoryUnifiedStorage::new();
+        assert_eq!(
+            storage
+                .update_memory_thread(&thread("Draft"), 0)
+                .await
+                .unwrap(),
+            1
+        );
+
+        // Both writers read the thread at the same version
+        let first = storage
+            .get_memory_thread_versioned("thread-1")
+            .await
+            .unwrap()
+            .unwrap();
+        let second = storage
+            .get_memory_thread_versioned("thread-1")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(first.version, 1);
+
+        let version = storage
+            .update_memory_thread(&thread("First writer"), first.version)
+            .await
+            .unwrap();
+        assert_eq!(version, 2);
+
+        let err = storage
+            .update_memory_thread(&thread("Second writer"), second.version)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            AgentError::Conflict {
+                expected: 1,
+                actual: 2,
+                ..
+            }
+        ));
+        let stored = storage
+            .get_memory_thread("thread-1")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.title, "First writer");
+
+        // Retrying from a fresh read succeeds
+        let current = storage
+            .get_memory_thread_versioned("thread-1")
+            .await
+            .unwrap()
+            .unwrap();
+        storage
+            .update_memory_thread(&thread("Second writer"), current.version)
+            .await
+            .unwrap();
+        let current = storage
+            .get_memory_thread_versioned("thread-1")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(current.version, 3);
+        assert_eq!(current.value.title, "Second writer");
+    }
+
+    #[tokio::test]
+    async fn test_unconditional_writes_bump_the_version() {
+        let storage = InMemoryUnifiedStorage::new();
+        storage.create_memory_thread(&thread("One")).await.unwrap();
+        storage.create_memory_thread(&thread("Two")).await.unwrap();
+
+        let err = storage
+            .update_memory_thread(&thread("Three"), 1)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AgentError::Conflict { actual: 2, .. }));
+        // Creating a record that already exists conflicts too
+        assert!(storage
+            .update_memory_thread(&thread("Four"), 0)
+            .await
+            .is_err());
+
+        storage.delete_memory_thread("thread-1").await.unwrap();
+        assert!(storage
+            .get_memory_thread_versioned("thread-1")
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            storage
+                .update_memory_thread(&thread("Five"), 0)
+                .await
+                .unwrap(),
+            1
+        );
+    }
+}
@@ -0,0 +1,414 @@
+//! Trace storage in rotating JSON-lines files
+//!
+//! [`FileTraceStorage`] appends each stored trace as one JSON line to the
+//! newest file in its directory, moving on to a new file once the current one
+//! would grow past the size limit. Queries scan the files oldest first; a trace
+//! stored more than once is represented by its latest record. Files are read
+//! a line at a time and filtered as they are read.
+
+use super::{ResourceId, TraceData, TraceFilters, TraceStorage};
+use crate::error::Result;
+use async_trait::async_trait;
+use indexmap::IndexMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+/// Size past which a trace file is rotated, unless configured otherwise
+pub const DEFAULT_MAX_FILE_BYTES: u64 = 10 * 1024 * 1024;
+
+const FILE_PREFIX: &str = "traces-";
+const FILE_EXTENSION: &str = "jsonl";
+
+/// The file new traces are appended to
+#[derive(Debug, Clone, Copy)]
+struct ActiveFile {
+    seq: u64,
+    size: u64,
+}
+
+/// Traces appended as JSON lines to size-rotated files in a directory
+#[derive(Debug)]
+pub struct FileTraceStorage {
+    dir: PathBuf,
+    max_file_bytes: u64,
+    /// Found on first use. The lock also keeps reads and rewrites from seeing
+    /// a half-written line.
+    active: Mutex<Option<ActiveFile>>,
+}
+
+impl FileTraceStorage {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            max_file_bytes: DEFAULT_MAX_FILE_BYTES,
+            active: Mutex::new(None),
+        }
+    }
+
+    /// Rotate files once they would grow past `max_file_bytes`. A single
+    /// trace larger than the limit still gets a file of its own.
+    pub fn with_max_file_bytes(mut self, max_file_bytes: u64) -> Self {
+        self.max_file_bytes = max_file_bytes.max(1);
+        self
+    }
+
+    /// Trace files in the directory, oldest first
+    pub async fn files(&self) -> Result<Vec<PathBuf>> {
+        Ok(self
+            .sequences()
+            .await?
+            .into_iter()
+            .map(|seq| self.file_path(seq))
+            .collect())
+    }
+
+    fn file_path(&self, seq: u64) -> PathBuf {
+        self.dir
+            .join(format!("{}{:06}.{}", FILE_PREFIX, seq, FILE_EXTENSION))
+    }
+
+    async fn sequences(&self) -> Result<Vec<u64>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut sequences = Vec::new();
+        let mut dir = fs::read_dir(&self.dir).await?;
+        while let Some(entry) = dir.next_entry().await? {
+            if let Some(seq) = file_seq(&entry.path()) {
+                sequences.push(seq);
+            }
+        }
+        sequences.sort_unstable();
+        Ok(sequences)
+    }
+
+    /// Continue the newest existing file, or start the first
+    async fn find_active(&self) -> Result<ActiveFile> {
+        let seq = self.sequences().await?.last().copied().unwrap_or(1);
+        let size = match fs::metadata(self.file_path(seq)).await {
+            Ok(metadata) => metadata.len(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => 0,
+            Err(e) => return Err(e.into()),
+        };
+        Ok(ActiveFile { seq, size })
+    }
+
+    /// The latest record of every trace, in the order traces were first
+    /// stored, kept only where `keep` accepts it. Files are read a line at a
+    /// time, so only the kept traces and the ids of the rest are held in memory.
+    async fn load(
+        &self,
+        keep: impl Fn(&TraceData) -> bool,
+    ) -> Result<IndexMap<String, Option<TraceData>>> {
+        let mut traces = IndexMap::new();
+        for path in self.files().await? {
+            let Some(mut lines) = open_lines(&path).await? else {
+                continue;
+            };
+            while let Some(line) = lines.next_line().await? {
+                if let Some(trace) = parse_trace(&path, &line) {
+                    // A later record replaces the earlier one in its place
+                    let trace_id = trace.trace_id.clone();
+                    traces.insert(trace_id, keep(&trace).then_some(trace));
+                }
+            }
+        }
+        Ok(traces)
+    }
+}
+
+/// Sequence number of a trace file, `None` for any other file
+fn file_seq(path: &Path) -> Option<u64> {
+    if path.extension().is_none_or(|ext| ext != FILE_EXTENSION) {
+        return None;
+    }
+    path.file_stem()?
+        .to_str()?
+        .strip_prefix(FILE_PREFIX)?
+        .parse()
+        .ok()
+}
+
+/// Lines of `path`, `None` if it no longer exists
+async fn open_lines(path: &Path) -> Result<Option<Lines<BufReader<File>>>> {
+    match File::open(path).await {
+        Ok(file) => Ok(Some(BufReader::new(file).lines())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// The trace recorded on `line` of `path`, `None` for blank or unreadable lines
+fn parse_trace(path: &Path, line: &str) -> Option<TraceData> {
+    if line.trim().is_empty() {
+        return None;
+    }
+    match serde_json::from_str(line) {
+        Ok(trace) => Some(trace),
+        Err(e) => {
+            warn!("Skipping unreadable trace in {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Traces recorded in `path`, in the order they were written
+async fn read_traces(path: &Path) -> Result<Vec<TraceData>> {
+    let mut traces = Vec::new();
+    if let Some(mut lines) = open_lines(path).await? {
+        while let Some(line) = lines.next_line().await? {
+            traces.extend(parse_trace(path, &line));
+        }
+    }
+    Ok(traces)
+}
+
+#[async_trait]
+impl TraceStorage for FileTraceStorage {
+    async fn store_trace(&self, trace: &TraceData) -> Result<()> {
+        let mut line = serde_json::to_vec(trace)?;
+        line.push(b'\n');
+        let len = line.len() as u64;
+
+        let mut active = self.active.lock().await;
+        let mut file = match *active {
+            Some(file) => file,
+            None => self.find_active().await?,
+        };
+        if file.size > 0 && file.size + len > self.max_file_bytes {
+            file = ActiveFile {
+                seq: file.seq + 1,
+                size: 0,
+            };
+            debug!(
+                "Rotating trace log to {}",
+                self.file_path(file.seq).display()
+            );
+        }
+
+        fs::create_dir_all(&self.dir).await?;
+        let mut handle = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.file_path(file.seq))
+            .await?;
+        handle.write_all(&line).await?;
+        handle.flush().await?;
+
+        file.size += len;
+        *active = Some(file);
+        Ok(())
+    }
+
+    async fn get_trace(&self, trace_id: &str) -> Result<Option<TraceData>> {
+        let _active = self.active.lock().await;
+        Ok(self
+            .load(|trace| trace.trace_id == trace_id)
+            .await?
+            .swap_remove(trace_id)
+            .flatten())
+    }
+
+    async fn query_traces(
+        &self,
+        resource_id: &ResourceId,
+        filters: TraceFilters,
+    ) -> Result<Vec<TraceData>> {
+        let _active = self.active.lock().await;
+        Ok(self
+            .load(|trace| &trace.resource_id == resource_id && filters.matches(trace))
+            .await?
+            .into_values()
+            .flatten()
+            .collect())
+    }
+
+    async fn delete_traces_before(&self, timestamp: SystemTime) -> Result<usize> {
+        let mut active = self.active.lock().await;
+        let deleted = self
+            .load(|trace| trace.start_time < timestamp)
+            .await?
+            .values()
+            .flatten()
+            .count();
+        if deleted == 0 {
+            return Ok(0);
+        }
+
+        // Rewrite each file without the deleted records, replacing it whole
+        // so a crash leaves either the old or the new contents
+        for seq in self.sequences().await? {
+            let path = self.file_path(seq);
+            let kept: Vec<TraceData> = read_traces(&path)
+                .await?
+                .into_iter()
+                .filter(|trace| trace.start_time >= timestamp)
+                .collect();
+
+            let mut contents = Vec::new();
+            for trace in &kept {
+                contents.extend(serde_json::to_vec(trace)?);
+                contents.push(b'\n');
+            }
+            if contents.is_empty() {
+                fs::remove_file(&path).await?;
+            } else {
+                let temp_path = path.with_extension(format!("{}.tmp", FILE_EXTENSION));
+                fs::write(&temp_path, &contents).await?;
+                fs::rename(&temp_path, &path).await?;
+            }
+
+            if let Some(file) = active.as_mut().filter(|file| file.seq == seq) {
+                file.size = contents.len() as u64;
+            }
+        }
+        Ok(deleted)
+    }
+
+    async fn count_traces(&self) -> Result<usize> {
+        let _active = self.active.lock().await;
+        Ok(self.load(|_| false).await?.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unified_storage::{TraceEvent, TraceStatus};
+    use std::collections::HashMap;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    fn trace(resource_id: &ResourceId, n: usize, status: TraceStatus) -> TraceData {
+        let start_time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000 + n as u64);
+        TraceData {
+            trace_id: format!("trace-{}", n),
+            span_id: format!("span-{}", n),
+            parent_span_id: None,
+            resource_id: resource_id.clone(),
+            operation_name: "llm.generate".to_string(),
+            start_time,
+            end_time: Some(start_time + Duration::from_millis(250)),
+            duration_ms: Some(250),
+            status,
+            attributes: HashMap::from([("model".to_string(), "llama3.2".to_string())]),
+            events: vec![TraceEvent {
+                name: "first_token".to_string(),
+                timestamp: start_time,
+                attributes: HashMap::new(),
+            }],
+            component: "agent".to_string(),
+        }
+    }
+
+    fn status(n: usize) -> TraceStatus {
+        match n % 3 {
+            0 => TraceStatus::Ok,
+            1 => TraceStatus::Error {
+                message: format!("failure {}", n),
+            },
+            _ => TraceStatus::Timeout,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_file_trace_storage_rotates_and_queries_by_status() {
+        let temp_dir = tempdir().unwrap();
+        let resource_id = ResourceId::new("tenant", "agent");
+        let storage = FileTraceStorage::new(temp_dir.path()).with_max_file_bytes(4 * 1024);
+        for n in 0..90 {
+            storage
+                .store_trace(&trace(&resource_id, n, status(n)))
+                .await
+                .unwrap();
+        }
+
+        let files = storage.files().await.unwrap();
+        assert!(files.len() > 1, "expected rotation, got {:?}", files);
+        for file in &files {
+            // Only the record that crossed the limit may push a file past it
+            assert!(fs::metadata(file).await.unwrap().len() < 2 * 4 * 1024);
+        }
+
+        // Reopening finds everything written before
+        let storage = FileTraceStorage::new(temp_dir.path()).with_max_file_bytes(4 * 1024);
+        assert_eq!(storage.count_traces().await.unwrap(), 90);
+
+        let errors = storage
+            .query_traces(
+                &resource_id,
+                TraceFilters {
+                    status: Some(TraceStatus::Error {
+                        message: String::new(),
+                    }),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(errors.len(), 30);
+        assert!(errors
+            .iter()
+            .all(|trace| matches!(trace.status, TraceStatus::Error { .. })));
+
+        let timeouts = storage
+            .query_traces(
+                &resource_id,
+                TraceFilters {
+                    status: Some(TraceStatus::Timeout),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(timeouts.len(), 30);
+
+        let other_resource = ResourceId::new("tenant", "other");
+        let none = storage
+            .query_traces(&other_resource, TraceFilters::default())
+            .await
+            .unwrap();
+        assert!(none.is_empty());
+
+        let fetched = storage.get_trace("trace-42").await.unwrap().unwrap();
+        assert_eq!(fetched.events.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_file_trace_storage_deletes_old_traces() {
+        let temp_dir = tempdir().unwrap();
+        let resource_id = ResourceId::new("tenant", "agent");
+        let storage = FileTraceStorage::new(temp_dir.path()).with_max_file_bytes(2 * 1024);
+        for n in 0..20 {
+            storage
+                .store_trace(&trace(&resource_id, n, TraceStatus::Ok))
+                .await
+                .unwrap();
+        }
+        // A later record of the same trace replaces the earlier one
+        storage
+            .store_trace(&trace(&resource_id, 3, TraceStatus::Cancelled))
+            .await
+            .unwrap();
+        assert!(matches!(
+            storage.get_trace("trace-3").await.unwrap().unwrap().status,
+            TraceStatus::Cancelled
+        ));
+
+        let cutoff = SystemTime::UNIX_EPOCH + Duration::from_secs(1_010);
+        assert_eq!(storage.delete_traces_before(cutoff).await.unwrap(), 10);
+        assert_eq!(storage.count_traces().await.unwrap(), 10);
+        assert!(storage.get_trace("trace-3").await.unwrap().is_none());
+
+        storage
+            .store_trace(&trace(&resource_id, 20, TraceStatus::Ok))
+            .await
+            .unwrap();
+        assert_eq!(storage.count_traces().await.unwrap(), 11);
+    }
+}
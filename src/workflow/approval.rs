@@ -0,0 +1,345 @@
+//! Tool call approval
+//!
+//! [`ToolApprovals`] maps tool names to an [`ApprovalPolicy`]. A
+//! [`ToolExecutionStep`](super::ToolExecutionStep) built with approvals holds
+//! back calls whose policy requires review and suspends the workflow with
+//! [`SuspendReason::WaitingForInput`](super::SuspendReason::WaitingForInput),
+//! the proposed calls rendered as JSON in the prompt and stored under
+//! [`PENDING_TOOL_APPROVALS_KEY`]. A reviewer records a decision for each
+//! call with [`WorkflowContext::set_tool_approval`](super::WorkflowContext::set_tool_approval)
+//! and resumes the workflow; approved calls are dispatched and rejected ones
+//! get an error result instead.
+//!
+//! A [`WorkflowEngine`](super::WorkflowEngine) built with
+//! [`with_tool_approvals`](super::WorkflowEngine::with_tool_approvals) applies
+//! the same review to every batch of tool calls a step asks it to execute, so
+//! calls proposed by any step, and those the agent runs, are covered. Each
+//! decision is removed once the call it covers is dispatched or rejected.
+
+use super::WorkflowContext;
+use crate::mcp::{ToolCall, ToolContent, ToolResult};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use tracing::info;
+
+/// Data key holding the tool calls waiting for a reviewer's decision
+pub const PENDING_TOOL_APPROVALS_KEY: &str = "pending_tool_approvals";
+
+/// Metadata value recording an approved tool call
+pub const TOOL_CALL_APPROVED: &str = "approved";
+
+/// Metadata value recording a rejected tool call
+pub const TOOL_CALL_REJECTED: &str = "rejected";
+
+/// Data key holding the batch of tool calls a suspended engine dispatches
+/// once it resumes
+pub(crate) const HELD_TOOL_CALLS_KEY: &str = "held_tool_calls";
+
+/// Metadata key holding the reviewer's decision on the call `tool_call_id`
+pub fn tool_approval_key(tool_call_id: &str) -> String {
+    format!("tool_approval:{}", tool_call_id)
+}
+
+/// When calls to a tool need a human to approve them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum ApprovalPolicy {
+    /// Every call is reviewed
+    Always,
+    /// Calls run without review
+    #[default]
+    Never,
+    /// Calls whose estimated cost exceeds the threshold are reviewed. Calls
+    /// the cost estimator cannot price are reviewed too.
+    AboveCost(f64),
+}
+
+/// Estimates what a tool call will cost, or `None` if it cannot tell
+pub type CostEstimator = Arc<dyn Fn(&ToolCall) -> Option<f64> + Send + Sync>;
+
+/// Per-tool approval policies, with [`ApprovalPolicy::Never`] for unlisted tools
+#[derive(Clone, Default)]
+pub struct ToolApprovals {
+    policies: HashMap<String, ApprovalPolicy>,
+    cost_estimator: Option<CostEstimator>,
+}
+
+impl ToolApprovals {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply `policy` to calls of `tool`
+    pub fn with_policy(mut self, tool: impl Into<String>, policy: ApprovalPolicy) -> Self {
+        self.policies.insert(tool.into(), policy);
+        self
+    }
+
+    /// Price calls for [`ApprovalPolicy::AboveCost`]
+    pub fn with_cost_estimator<F>(mut self, estimator: F) -> Self
+    where
+        F: Fn(&ToolCall) -> Option<f64> + Send + Sync + 'static,
+    {
+        self.cost_estimator = Some(Arc::new(estimator));
+        self
+    }
+
+    /// Policy applied to calls of `tool`
+    pub fn policy(&self, tool: &str) -> ApprovalPolicy {
+        self.policies.get(tool).copied().unwrap_or_default()
+    }
+
+    /// Whether `call` has to be approved before it runs
+    pub fn requires_approval(&self, call: &ToolCall) -> bool {
+        match self.policy(&call.name) {
+            ApprovalPolicy::Always => true,
+            ApprovalPolicy::Never => false,
+            ApprovalPolicy::AboveCost(threshold) => self
+                .cost_estimator
+                .as_ref()
+                .and_then(|estimate| estimate(call))
+                .is_none_or(|cost| cost > threshold),
+        }
+    }
+}
+
+impl fmt::Debug for ToolApprovals {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ToolApprovals")
+            .field("policies", &self.policies)
+            .field("cost_estimator", &self.cost_estimator.is_some())
+            .finish()
+    }
+}
+
+/// Tool calls sorted by the reviewer's decisions on them
+#[derive(Debug, Default)]
+pub(crate) struct Review {
+    /// Calls that need no review or were approved
+    pub approved: Vec<ToolCall>,
+    pub rejected: Vec<ToolCall>,
+    /// Calls that need review and have no decision yet
+    pub awaiting: Vec<ToolCall>,
+}
+
+impl Review {
+    pub fn new(approvals: &ToolApprovals, context: &WorkflowContext, calls: Vec<ToolCall>) -> Self {
+        let mut review = Self::default();
+        for call in calls {
+            if !approvals.requires_approval(&call) {
+                review.approved.push(call);
+                continue;
+            }
+            let decision = context.metadata.get(&tool_approval_key(&call.id));
+            match decision.map(String::as_str) {
+                Some(TOOL_CALL_APPROVED) => review.approved.push(call),
+                Some(TOOL_CALL_REJECTED) => review.rejected.push(call),
+                _ => review.awaiting.push(call),
+            }
+        }
+        review
+    }
+
+    /// Answer the rejected calls with an error result, removing the decisions
+    /// on them and on the approved calls about to be dispatched
+    pub fn apply(self, context: &mut WorkflowContext) -> Vec<ToolCall> {
+        for call in &self.rejected {
+            info!("Tool call '{}' rejected by reviewer", call.name);
+            context.add_tool_result(call.id.clone(), rejected_result(call));
+        }
+        for call in self.rejected.iter().chain(&self.approved) {
+            context.metadata.remove(&tool_approval_key(&call.id));
+        }
+        self.approved
+    }
+}
+
+/// Prompt asking a reviewer to decide on `calls`
+pub(crate) fn approval_prompt(calls: &[ToolCall]) -> crate::error::Result<String> {
+    let proposed = serde_json::to_string(calls)?;
+    Ok(format!("Approve tool calls: {}", proposed))
+}
+
+/// Error result standing in for a call the reviewer rejected
+pub(crate) fn rejected_result(call: &ToolCall) -> ToolResult {
+    ToolResult {
+        id: call.id.clone(),
+        content: vec![ToolContent::Text {
+            text: format!("Tool call '{}' was rejected by the reviewer", call.name),
+        }],
+        is_error: true,
+        truncated: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Result;
+    use crate::ids::new_id;
+    use crate::workflow::{
+        FileSnapshotStorage, SuspendReason, ToolExecutionStep, WorkflowContext, WorkflowDecision,
+        WorkflowEngine, WorkflowStep,
+    };
+    use async_trait::async_trait;
+
+    fn call(id: &str, name: &str, arguments: serde_json::Value) -> ToolCall {
+        ToolCall {
+            id: id.to_string(),
+            name: name.to_string(),
+            arguments,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_approval_required_tool_suspends_until_approved() {
+        let step = ToolExecutionStep::with_approvals(
+            ToolApprovals::new().with_policy("transfer_funds", ApprovalPolicy::Always),
+        );
+        let mut context = WorkflowContext::new(10);
+        context
+            .queue_tool_call(call(
+                "call-1",
+                "transfer_funds",
+                serde_json::json!({ "amount": 250 }),
+            ))
+            .unwrap();
+
+        // The call is held back and proposed to the reviewer
+        match step.execute(&mut context).await.unwrap() {
+            WorkflowDecision::Suspend(SuspendReason::WaitingForInput(prompt)) => {
+                assert!(prompt.contains("transfer_funds"));
+                assert!(prompt.contains("call-1"));
+            }
+            other => panic!("expected suspension, got {:?}", other),
+        }
+        let pending: Vec<ToolCall> = context.get(PENDING_TOOL_APPROVALS_KEY).unwrap().unwrap();
+        assert_eq!(pending[0].id, "call-1");
+
+        // Resuming without a decision suspends again
+        assert!(matches!(
+            step.execute(&mut context).await.unwrap(),
+            WorkflowDecision::Suspend(SuspendReason::WaitingForInput(_))
+        ));
+
+        context.set_tool_approval("call-1", true);
+        match step.execute(&mut context).await.unwrap() {
+            WorkflowDecision::ExecuteTools(calls) => assert_eq!(calls[0].id, "call-1"),
+            other => panic!("expected tool dispatch, got {:?}", other),
+        }
+        assert!(!context.data.contains_key(PENDING_TOOL_APPROVALS_KEY));
+    }
+
+    #[tokio::test]
+    async fn test_rejected_and_cheap_calls() {
+        let approvals = ToolApprovals::new()
+            .with_policy("transfer_funds", ApprovalPolicy::AboveCost(100.0))
+            .with_cost_estimator(|call| call.arguments["amount"].as_f64());
+        assert!(!approvals.requires_approval(&call(
+            "a",
+            "transfer_funds",
+            serde_json::json!({ "amount": 20 })
+        )));
+        assert!(approvals.requires_approval(&call(
+            "b",
+            "transfer_funds",
+            serde_json::json!({ "amount": 500 })
+        )));
+        // Calls that cannot be priced are reviewed
+        assert!(approvals.requires_approval(&call("c", "transfer_funds", serde_json::json!({}))));
+        assert!(!approvals.requires_approval(&call("d", "weather_lookup", serde_json::json!({}))));
+
+        let step = ToolExecutionStep::with_approvals(approvals);
+        let mut context = WorkflowContext::new(10);
+        context
+            .queue_tool_call(call(
+                "call-1",
+                "transfer_funds",
+                serde_json::json!({ "amount": 500 }),
+            ))
+            .unwrap();
+        assert!(matches!(
+            step.execute(&mut context).await.unwrap(),
+            WorkflowDecision::Suspend(_)
+        ));
+
+        // A rejected call is answered with an error instead of being dispatched
+        context.set_tool_approval("call-1", false);
+        assert!(matches!(
+            step.execute(&mut context).await.unwrap(),
+            WorkflowDecision::Continue
+        ));
+        assert!(context.tool_results["call-1"].is_error);
+    }
+
+    /// Proposes a transfer under a fresh call id each time it runs
+    struct ProposeTransfer;
+
+    #[async_trait]
+    impl WorkflowStep for ProposeTransfer {
+        async fn execute(&self, context: &mut WorkflowContext) -> Result<WorkflowDecision> {
+            if !context.tool_results.is_empty() {
+                return Ok(WorkflowDecision::Complete("Transferred".to_string()));
+            }
+            Ok(WorkflowDecision::ExecuteTools(vec![call(
+                &new_id().to_string(),
+                "transfer_funds",
+                serde_json::json!({ "amount": 250 }),
+            )]))
+        }
+
+        fn name(&self) -> &str {
+            "propose_transfer"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_engine_holds_tool_calls_until_approved() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let engine = WorkflowEngine::new()
+            .with_snapshot_storage(Box::new(FileSnapshotStorage::new(temp_dir.path())))
+            .with_tool_approvals(
+                ToolApprovals::new().with_policy("transfer_funds", ApprovalPolicy::Always),
+            )
+            .add_step(Box::new(ProposeTransfer));
+
+        let result = engine.execute(WorkflowContext::new(10)).await.unwrap();
+        assert!(matches!(
+            result.suspended,
+            Some(SuspendReason::WaitingForInput(_))
+        ));
+        assert!(result.pending_tool_calls.is_none());
+        let proposed: Vec<ToolCall> = result
+            .context
+            .get(PENDING_TOOL_APPROVALS_KEY)
+            .unwrap()
+            .unwrap();
+
+        // Resume with the reviewer's approval recorded
+        let snapshot = engine
+            .list_snapshots(None)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|snapshot| matches!(snapshot.suspend_reason, SuspendReason::WaitingForInput(_)))
+            .unwrap();
+        let mut context = snapshot.context;
+        context.set_tool_approval(&proposed[0].id, true);
+        let snapshot = engine
+            .create_snapshot(&context, snapshot.current_step, SuspendReason::Manual)
+            .await
+            .unwrap();
+        engine.store_snapshot(&snapshot).await.unwrap();
+
+        // The held call is dispatched as proposed, and its decision used up
+        let result = engine.resume_from_snapshot(snapshot.id).await.unwrap();
+        let calls = result.pending_tool_calls.unwrap();
+        assert_eq!(calls[0].id, proposed[0].id);
+        assert!(!result
+            .context
+            .metadata
+            .contains_key(&tool_approval_key(&proposed[0].id)));
+        assert!(!result.context.data.contains_key(PENDING_TOOL_APPROVALS_KEY));
+    }
+}
@@ -0,0 +1,213 @@
+//! Serialization formats for workflow snapshots
+//!
+//! JSON is readable but bulky for large contexts, so snapshot storages can be
+//! given a binary [`SnapshotCodec`] instead. Binary encodings start with a
+//! header byte naming the codec; JSON is written as plain JSON, whose first
+//! byte can never be a header. Decoding picks the codec from the data itself,
+//! so snapshots written before a storage's codec changed still load.
+
+use crate::error::{AgentError, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+const MESSAGE_PACK_HEADER: u8 = 0x01;
+const BINCODE_HEADER: u8 = 0x02;
+
+/// How a snapshot storage encodes snapshots
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SnapshotCodec {
+    /// Plain JSON
+    #[default]
+    Json,
+    /// MessagePack with named fields
+    MessagePack,
+    /// Bincode
+    Bincode,
+}
+
+impl SnapshotCodec {
+    pub const ALL: [SnapshotCodec; 3] = [Self::Json, Self::MessagePack, Self::Bincode];
+
+    /// File extension used by file-based storage
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::MessagePack => "msgpack",
+            Self::Bincode => "bin",
+        }
+    }
+
+    pub fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>> {
+        let encoded = match self {
+            Self::Json => serde_json::to_vec_pretty(value).map_err(|e| e.to_string()),
+            Self::MessagePack => rmp_serde::to_vec_named(value)
+                .map(|bytes| with_header(MESSAGE_PACK_HEADER, bytes))
+                .map_err(|e| e.to_string()),
+            Self::Bincode => serde_json::to_value(value)
+                .map_err(|e| e.to_string())
+                .and_then(|value| {
+                    bincode::serialize(&TreeValue::from(value)).map_err(|e| e.to_string())
+                })
+                .map(|bytes| with_header(BINCODE_HEADER, bytes)),
+        };
+        encoded.map_err(|e| {
+            AgentError::Workflow(format!("Failed to encode snapshot as {:?}: {}", self, e))
+        })
+    }
+
+    /// The codec `bytes` were encoded with
+    pub fn detect(bytes: &[u8]) -> Result<Self> {
+        match bytes.first() {
+            Some(&MESSAGE_PACK_HEADER) => Ok(Self::MessagePack),
+            Some(&BINCODE_HEADER) => Ok(Self::Bincode),
+            Some(byte) if *byte == b'{' || byte.is_ascii_whitespace() => Ok(Self::Json),
+            Some(byte) => Err(AgentError::Workflow(format!(
+                "Unknown snapshot encoding header 0x{:02x}",
+                byte
+            ))),
+            None => Err(AgentError::Workflow("Snapshot data is empty".to_string())),
+        }
+    }
+
+    /// Decode `bytes` with whichever codec encoded them
+    pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+        let codec = Self::detect(bytes)?;
+        let decoded = match codec {
+            Self::Json => serde_json::from_slice(bytes).map_err(|e| e.to_string()),
+            Self::MessagePack => rmp_serde::from_slice(&bytes[1..]).map_err(|e| e.to_string()),
+            Self::Bincode => bincode::deserialize::<TreeValue>(&bytes[1..])
+                .map_err(|e| e.to_string())
+                .and_then(|tree| serde_json::from_value(tree.into()).map_err(|e| e.to_string())),
+        };
+        decoded.map_err(|e| {
+            AgentError::Workflow(format!("Failed to decode {:?} snapshot: {}", codec, e))
+        })
+    }
+}
+
+fn with_header(header: u8, mut bytes: Vec<u8>) -> Vec<u8> {
+    bytes.insert(0, header);
+    bytes
+}
+
+/// Mirror of `serde_json::Value` that bincode can decode. Snapshots hold
+/// free-form JSON values, which bincode cannot decode directly because its
+/// format does not describe its own types, so the bincode codec encodes the
+/// snapshot as a tree of these instead.
+#[derive(Serialize, Deserialize)]
+enum TreeValue {
+    Null,
+    Bool(bool),
+    Unsigned(u64),
+    Signed(i64),
+    Float(f64),
+    String(String),
+    Array(Vec<TreeValue>),
+    Object(Vec<(String, TreeValue)>),
+}
+
+impl From<serde_json::Value> for TreeValue {
+    fn from(value: serde_json::Value) -> Self {
+        use serde_json::Value;
+        match value {
+            Value::Null => Self::Null,
+            Value::Bool(b) => Self::Bool(b),
+            Value::Number(n) => match (n.as_u64(), n.as_i64()) {
+                (Some(u), _) => Self::Unsigned(u),
+                (None, Some(i)) => Self::Signed(i),
+                _ => Self::Float(n.as_f64().unwrap_or_default()),
+            },
+            Value::String(s) => Self::String(s),
+            Value::Array(items) => Self::Array(items.into_iter().map(Self::from).collect()),
+            Value::Object(map) => Self::Object(
+                map.into_iter()
+                    .map(|(key, value)| (key, Self::from(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl From<TreeValue> for serde_json::Value {
+    fn from(tree: TreeValue) -> Self {
+        match tree {
+            TreeValue::Null => Self::Null,
+            TreeValue::Bool(b) => Self::Bool(b),
+            TreeValue::Unsigned(u) => Self::from(u),
+            TreeValue::Signed(i) => Self::from(i),
+            TreeValue::Float(f) => Self::from(f),
+            TreeValue::String(s) => Self::String(s),
+            TreeValue::Array(items) => Self::Array(items.into_iter().map(Self::from).collect()),
+            TreeValue::Object(entries) => Self::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, Self::from(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::user_message;
+    use crate::workflow::{SuspendReason, WorkflowContext, WorkflowSnapshot};
+    use chrono::Utc;
+    use std::collections::HashMap;
+    use uuid::Uuid;
+
+    fn sample_snapshot() -> WorkflowSnapshot {
+        let mut context = WorkflowContext::new(10);
+        context.add_message(user_message("Summarize the quarterly report"));
+        context
+            .metadata
+            .insert("stage".to_string(), "review".to_string());
+        context
+            .set(
+                "scores",
+                serde_json::json!({ "precision": 0.25, "offset": -3, "tags": ["a", null, true] }),
+            )
+            .unwrap();
+
+        WorkflowSnapshot {
+            id: Uuid::new_v4(),
+            created_at: Utc::now(),
+            context,
+            current_step: 2,
+            suspend_reason: SuspendReason::WaitingForInput("approve?".to_string()),
+            metadata: HashMap::from([("owner".to_string(), "ops".to_string())]),
+            step_state: HashMap::from([("retries".to_string(), serde_json::json!(u64::MAX))]),
+        }
+    }
+
+    #[test]
+    fn test_round_trips_through_every_codec() {
+        let snapshot = sample_snapshot();
+        let expected = serde_json::to_value(&snapshot).unwrap();
+
+        for codec in SnapshotCodec::ALL {
+            let bytes = codec.encode(&snapshot).unwrap();
+            assert_eq!(SnapshotCodec::detect(&bytes).unwrap(), codec);
+
+            let decoded: WorkflowSnapshot = SnapshotCodec::decode(&bytes).unwrap();
+            assert_eq!(
+                serde_json::to_value(&decoded).unwrap(),
+                expected,
+                "{:?}",
+                codec
+            );
+        }
+
+        let json = SnapshotCodec::Json.encode(&snapshot).unwrap();
+        let msgpack = SnapshotCodec::MessagePack.encode(&snapshot).unwrap();
+        assert!(msgpack.len() < json.len());
+    }
+
+    #[test]
+    fn test_rejects_unknown_header() {
+        let err = SnapshotCodec::decode::<WorkflowSnapshot>(&[0x7f, 1, 2]).unwrap_err();
+        assert!(err.to_string().contains("0x7f"));
+    }
+}
@@ -0,0 +1,91 @@
+//! External control of a running workflow
+//!
+//! A [`ControlHandle`] lets code outside the workflow pause, resume or abort
+//! it. [`WorkflowEngine::execute_with_control`](super::WorkflowEngine::execute_with_control)
+//! and [`WorkflowEngine::resume_from_snapshot_with_control`](super::WorkflowEngine::resume_from_snapshot_with_control)
+//! check the handle between steps: a paused workflow is suspended with
+//! [`SuspendReason::Manual`](super::SuspendReason::Manual) and waits for a
+//! resume or abort, and an aborted workflow stops with the partial result.
+//! The pause snapshot is deleted once the workflow resumes in place.
+
+use tokio::sync::watch;
+
+/// Requested state of a controlled workflow
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ControlState {
+    #[default]
+    Running,
+    Paused,
+    Aborted,
+}
+
+/// Cloneable handle for pausing, resuming and aborting a workflow
+#[derive(Debug, Clone)]
+pub struct ControlHandle {
+    state: watch::Sender<ControlState>,
+}
+
+impl Default for ControlHandle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ControlHandle {
+    pub fn new() -> Self {
+        let (state, _) = watch::channel(ControlState::Running);
+        Self { state }
+    }
+
+    /// Pause before the next step. Has no effect once aborted.
+    pub fn pause(&self) {
+        self.transition(ControlState::Paused);
+    }
+
+    /// Continue a paused workflow. Has no effect once aborted.
+    pub fn resume(&self) {
+        self.transition(ControlState::Running);
+    }
+
+    /// Stop before the next step, even while paused
+    pub fn abort(&self) {
+        self.state.send_replace(ControlState::Aborted);
+    }
+
+    pub fn state(&self) -> ControlState {
+        *self.state.borrow()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state() == ControlState::Paused
+    }
+
+    pub fn is_aborted(&self) -> bool {
+        self.state() == ControlState::Aborted
+    }
+
+    /// Wait while paused, returning the state that ended the pause
+    pub(crate) async fn wait_while_paused(&self) -> ControlState {
+        let mut receiver = self.state.subscribe();
+        let state = match receiver
+            .wait_for(|state| *state != ControlState::Paused)
+            .await
+        {
+            Ok(state) => *state,
+            // The sender lives in `self`, so the channel cannot close here
+            Err(_) => self.state(),
+        };
+        state
+    }
+
+    fn transition(&self, next: ControlState) {
+        self.state.send_if_modified(|state| {
+            if *state == ControlState::Aborted || *state == next {
+                false
+            } else {
+                *state = next;
+                true
+            }
+        });
+    }
+}
@@ -0,0 +1,308 @@
+//! Delegating workflow steps to remote agents
+//!
+//! [`DelegateToAgentStep`] sends the latest user message to another agent over
+//! A2A and adds the reply to the conversation as an assistant message. Request
+//! and response transports return the reply in the [`A2AResponse`]; message
+//! transports deliver it later as a `Response` message whose correlation id is
+//! the request's id, so the step subscribes before sending and waits for
+//! either. A peer that cannot be reached or does not answer in time either
+//! suspends the workflow or ends it, depending on [`DelegationFailure`].
+
+use super::{SuspendReason, WorkflowContext, WorkflowDecision, WorkflowStep};
+use crate::a2a::{
+    A2AClient, A2AMessage, A2AResponse, AgentId, MessagePayload, MessagePriority, MessageType,
+    ResponseStatus,
+};
+use crate::error::Result;
+use crate::ids::new_id;
+use crate::llm::assistant_message;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
+
+/// What a [`DelegateToAgentStep`] does when the remote agent fails to answer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DelegationFailure {
+    /// Suspend with [`SuspendReason::WaitingForResource`] so the workflow can
+    /// be resumed once the agent is back
+    #[default]
+    Suspend,
+    /// Complete the workflow with an error message
+    Complete,
+}
+
+/// Step that asks a remote agent to answer the latest user message
+pub struct DelegateToAgentStep {
+    pub agent_id: AgentId,
+    pub a2a_client: Arc<dyn A2AClient>,
+    /// Sender recorded on outgoing messages
+    pub from: AgentId,
+    /// How long to wait for the reply
+    pub timeout: Duration,
+    pub on_failure: DelegationFailure,
+}
+
+impl DelegateToAgentStep {
+    pub fn new(agent_id: AgentId, a2a_client: Arc<dyn A2AClient>) -> Self {
+        Self {
+            agent_id,
+            a2a_client,
+            from: AgentId::new("workflow", "delegate_to_agent"),
+            timeout: Duration::from_secs(30),
+            on_failure: DelegationFailure::default(),
+        }
+    }
+
+    pub fn with_sender(mut self, from: AgentId) -> Self {
+        self.from = from;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn on_failure(mut self, on_failure: DelegationFailure) -> Self {
+        self.on_failure = on_failure;
+        self
+    }
+
+    /// Send `message` and wait up to `timeout` for its reply, returning the
+    /// reply text
+    async fn delegate(
+        &self,
+        message: A2AMessage,
+        timeout: Duration,
+    ) -> std::result::Result<String, String> {
+        let mut replies = self
+            .a2a_client
+            .subscribe(vec![MessageType::Response])
+            .await
+            .map_err(|e| e.to_string())?;
+        let request_id = message.id.clone();
+
+        let exchange = async {
+            let response = self
+                .a2a_client
+                .send_message(message)
+                .await
+                .map_err(|e| e.to_string())?;
+            if let Some(payload) = Self::accepted_payload(&request_id, response)? {
+                return Ok(payload);
+            }
+            Self::await_reply(&mut replies, &request_id).await
+        };
+
+        match tokio::time::timeout(timeout, exchange).await {
+            Ok(result) => result.map(|payload| payload_text(&payload)),
+            Err(_) => Err(format!("no reply within {:?}", timeout)),
+        }
+    }
+
+    /// The reply carried by the send response itself, if any
+    fn accepted_payload(
+        request_id: &str,
+        response: A2AResponse,
+    ) -> std::result::Result<Option<MessagePayload>, String> {
+        if response.message_id != request_id {
+            return Err(format!(
+                "response is for message {}, expected {}",
+                response.message_id, request_id
+            ));
+        }
+        match response.status {
+            ResponseStatus::Success | ResponseStatus::Processing => Ok(response.payload),
+            status => Err(response
+                .error
+                .unwrap_or_else(|| format!("request {:?}", status).to_lowercase())),
+        }
+    }
+
+    async fn await_reply(
+        replies: &mut broadcast::Receiver<A2AMessage>,
+        request_id: &str,
+    ) -> std::result::Result<MessagePayload, String> {
+        loop {
+            match replies.recv().await {
+                Ok(reply)
+                    if reply.message_type == MessageType::Response
+                        && reply.correlation_id.as_deref() == Some(request_id) =>
+                {
+                    return Ok(reply.payload);
+                }
+                Ok(_) => continue,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("Missed {} A2A messages while awaiting a reply", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => {
+                    return Err("message stream closed before a reply arrived".to_string());
+                }
+            }
+        }
+    }
+}
+
+/// Conversation text for a reply payload
+fn payload_text(payload: &MessagePayload) -> String {
+    match payload {
+        MessagePayload::Text { content } => content.clone(),
+        MessagePayload::Json { data } => data.to_string(),
+        other => serde_json::to_string(other).unwrap_or_default(),
+    }
+}
+
+#[async_trait]
+impl WorkflowStep for DelegateToAgentStep {
+    async fn execute(&self, context: &mut WorkflowContext) -> Result<WorkflowDecision> {
+        let Some(question) = context.last_user_message().map(|m| m.content.clone()) else {
+            debug!("No user message to delegate");
+            return Ok(WorkflowDecision::Continue);
+        };
+        debug!("Delegating to agent {}", self.agent_id);
+
+        let timeout = context.timeout_within_deadline(self.timeout);
+        let now = SystemTime::now();
+        let message = A2AMessage {
+            id: new_id().to_string(),
+            from: self.from.clone(),
+            to: self.agent_id.clone(),
+            message_type: MessageType::Request,
+            payload: MessagePayload::Text { content: question },
+            priority: MessagePriority::Normal,
+            timestamp: now,
+            expires_at: Some(now + timeout),
+            correlation_id: None,
+            reply_to: None,
+            metadata: HashMap::new(),
+        };
+
+        match self.delegate(message, timeout).await {
+            Ok(reply) => {
+                context.add_message(assistant_message(&reply));
+                context
+                    .metadata
+                    .insert("delegated_to".to_string(), self.agent_id.to_string());
+                Ok(WorkflowDecision::Continue)
+            }
+            Err(e) => {
+                warn!("Delegation to agent {} failed: {}", self.agent_id, e);
+                let reason = format!("Agent {} unavailable: {}", self.agent_id, e);
+                Ok(match self.on_failure {
+                    DelegationFailure::Suspend => {
+                        WorkflowDecision::Suspend(SuspendReason::WaitingForResource(reason))
+                    }
+                    DelegationFailure::Complete => WorkflowDecision::Complete(reason),
+                })
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        "delegate_to_agent"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::a2a::{A2AConfig, AgentCapabilities};
+    use crate::llm::{user_message, Role};
+    use crate::organization::a2a_local::LocalA2AClient;
+
+    fn capabilities() -> AgentCapabilities {
+        AgentCapabilities {
+            services: vec!["qa".to_string()],
+            protocols: vec!["local".to_string()],
+            message_types: vec!["request".to_string()],
+            metadata: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delegates_to_echoing_peer() {
+        let client = Arc::new(LocalA2AClient::new(A2AConfig::default()).unwrap());
+        let workflow_id = AgentId::new("test", "workflow");
+        let peer_id = AgentId::new("test", "researcher");
+        let _workflow_inbox = client
+            .register_agent_with_channel(workflow_id.clone(), capabilities(), 8)
+            .await
+            .unwrap();
+        let inbox = client
+            .register_agent_with_channel(peer_id.clone(), capabilities(), 8)
+            .await
+            .unwrap();
+
+        // The peer answers each request with an echo correlated to it
+        let peer_client = client.clone();
+        tokio::spawn(async move {
+            while let Ok(request) = inbox.recv_async().await {
+                let MessagePayload::Text { content } = &request.payload else {
+                    continue;
+                };
+                let reply = A2AMessage {
+                    id: new_id().to_string(),
+                    from: request.to.clone(),
+                    to: request.from.clone(),
+                    message_type: MessageType::Response,
+                    payload: MessagePayload::Text {
+                        content: format!("echo: {}", content),
+                    },
+                    priority: MessagePriority::Normal,
+                    timestamp: SystemTime::now(),
+                    expires_at: None,
+                    correlation_id: Some(request.id.clone()),
+                    reply_to: None,
+                    metadata: HashMap::new(),
+                };
+                peer_client.send_message(reply).await.unwrap();
+            }
+        });
+
+        let step = DelegateToAgentStep::new(peer_id.clone(), client.clone())
+            .with_sender(workflow_id)
+            .with_timeout(Duration::from_secs(5));
+        let mut context = WorkflowContext::new(5);
+        context.add_message(user_message("What is the torque limit?"));
+
+        let decision = step.execute(&mut context).await.unwrap();
+        assert!(matches!(decision, WorkflowDecision::Continue));
+        let reply = context.messages.last().unwrap();
+        assert_eq!(reply.role, Role::Assistant);
+        assert_eq!(reply.content, "echo: What is the torque limit?");
+        assert_eq!(context.metadata["delegated_to"], peer_id.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_silent_or_unreachable_peer_follows_failure_policy() {
+        let client = Arc::new(LocalA2AClient::new(A2AConfig::default()).unwrap());
+        let silent_id = AgentId::new("test", "silent");
+        let _inbox = client
+            .register_agent_with_channel(silent_id.clone(), capabilities(), 8)
+            .await
+            .unwrap();
+
+        let mut context = WorkflowContext::new(5);
+        context.add_message(user_message("Anyone there?"));
+
+        let step = DelegateToAgentStep::new(silent_id, client.clone())
+            .with_timeout(Duration::from_millis(50));
+        let decision = step.execute(&mut context).await.unwrap();
+        assert!(matches!(
+            decision,
+            WorkflowDecision::Suspend(SuspendReason::WaitingForResource(ref reason))
+                if reason.contains("no reply within")
+        ));
+
+        let step = DelegateToAgentStep::new(AgentId::new("test", "missing"), client)
+            .on_failure(DelegationFailure::Complete);
+        match step.execute(&mut context).await.unwrap() {
+            WorkflowDecision::Complete(message) => assert!(message.contains("not found")),
+            other => panic!("expected completion, got {:?}", other),
+        }
+        assert_eq!(context.messages.len(), 1);
+    }
+}
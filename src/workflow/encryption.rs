@@ -0,0 +1,487 @@
+//! Encryption at rest for workflow snapshots
+//!
+//! Snapshots carry the full conversation context, so [`EncryptedSnapshotStorage`]
+//! seals them with AES-256-GCM before handing them to any other
+//! [`SnapshotStorage`]. The inner storage only sees an envelope holding the
+//! snapshot id, creation time, the id of the key that sealed it, and the
+//! ciphertext. Tagging each envelope with its key id lets keys be rotated
+//! without losing access to older snapshots.
+//!
+//! Snapshots without a key id were stored unencrypted and are rejected, so
+//! plaintext written to the inner storage cannot be passed off as a sealed
+//! snapshot. To migrate snapshots written before encryption was enabled,
+//! allow them with [`EncryptedSnapshotStorage::with_unencrypted_reads`] and
+//! run [`EncryptedSnapshotStorage::reencrypt_all`].
+
+use super::{
+    summary_page, SnapshotStorage, SnapshotSummary, SuspendReason, WorkflowContext,
+    WorkflowSnapshot,
+};
+use crate::config::SnapshotEncryptionConfig;
+use crate::error::{AgentError, Result};
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Environment variable holding the active key when it is not in the config
+pub const SNAPSHOT_KEY_ENV: &str = "SNAPSHOT_ENCRYPTION_KEY";
+
+/// Envelope metadata key naming the key that sealed the snapshot
+const KEY_ID_METADATA: &str = "encryption_key_id";
+
+/// Envelope step state key holding the base64 nonce and ciphertext
+const CIPHERTEXT_STATE: &str = "ciphertext";
+
+const NONCE_LEN: usize = 12;
+
+/// Set of snapshot encryption keys, one of which seals new snapshots
+#[derive(Clone)]
+pub struct SnapshotKeyring {
+    active_key_id: String,
+    keys: HashMap<String, Aes256Gcm>,
+}
+
+impl std::fmt::Debug for SnapshotKeyring {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print key material
+        f.debug_struct("SnapshotKeyring")
+            .field("active_key_id", &self.active_key_id)
+            .field("key_ids", &self.keys.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl SnapshotKeyring {
+    /// Create a keyring with a single raw 256-bit key
+    pub fn new(key_id: impl Into<String>, key: &[u8]) -> Result<Self> {
+        let key_id = key_id.into();
+        let cipher = Self::cipher(&key_id, key)?;
+
+        Ok(Self {
+            keys: HashMap::from([(key_id.clone(), cipher)]),
+            active_key_id: key_id,
+        })
+    }
+
+    /// Build a keyring from config, falling back to [`SNAPSHOT_KEY_ENV`] for the active key
+    pub fn from_config(config: &SnapshotEncryptionConfig) -> Result<Self> {
+        let mut keys = HashMap::new();
+        for (key_id, encoded) in &config.keys {
+            keys.insert(key_id.clone(), Self::decode(key_id, encoded)?);
+        }
+
+        if !keys.contains_key(&config.active_key_id) {
+            let encoded = std::env::var(SNAPSHOT_KEY_ENV).map_err(|_| {
+                AgentError::Config(format!(
+                    "Snapshot key '{}' is not configured and {} is not set",
+                    config.active_key_id, SNAPSHOT_KEY_ENV
+                ))
+            })?;
+            keys.insert(
+                config.active_key_id.clone(),
+                Self::decode(&config.active_key_id, &encoded)?,
+            );
+        }
+
+        Ok(Self {
+            active_key_id: config.active_key_id.clone(),
+            keys,
+        })
+    }
+
+    /// Add a key and make it the one used for new snapshots. Existing keys stay
+    /// available for decryption.
+    pub fn rotate(mut self, key_id: impl Into<String>, key: &[u8]) -> Result<Self> {
+        let key_id = key_id.into();
+        let cipher = Self::cipher(&key_id, key)?;
+        self.keys.insert(key_id.clone(), cipher);
+        self.active_key_id = key_id;
+        Ok(self)
+    }
+
+    pub fn active_key_id(&self) -> &str {
+        &self.active_key_id
+    }
+
+    fn decode(key_id: &str, encoded: &str) -> Result<Aes256Gcm> {
+        let key = BASE64.decode(encoded.trim()).map_err(|e| {
+            AgentError::Config(format!("Snapshot key '{}' is not valid base64: {}", key_id, e))
+        })?;
+        Self::cipher(key_id, &key)
+    }
+
+    fn cipher(key_id: &str, key: &[u8]) -> Result<Aes256Gcm> {
+        Aes256Gcm::new_from_slice(key).map_err(|_| {
+            AgentError::Config(format!(
+                "Snapshot key '{}' must be 32 bytes, got {}",
+                key_id,
+                key.len()
+            ))
+        })
+    }
+
+    /// Encrypt with the active key. The snapshot id is bound as associated data
+    /// so ciphertext cannot be swapped between snapshots.
+    fn seal(&self, id: Uuid, plaintext: &[u8]) -> Result<String> {
+        let cipher = &self.keys[&self.active_key_id];
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: plaintext,
+                    aad: id.as_bytes(),
+                },
+            )
+            .map_err(|_| AgentError::Workflow("Failed to encrypt snapshot".to_string()))?;
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        Ok(BASE64.encode(sealed))
+    }
+
+    fn open(&self, key_id: &str, id: Uuid, sealed: &str) -> Result<Vec<u8>> {
+        let cipher = self.keys.get(key_id).ok_or_else(|| {
+            AgentError::Workflow(format!(
+                "Snapshot {} was encrypted with unknown key '{}'",
+                id, key_id
+            ))
+        })?;
+
+        let sealed = BASE64
+            .decode(sealed)
+            .map_err(|e| AgentError::Workflow(format!("Corrupt encrypted snapshot {}: {}", id, e)))?;
+        if sealed.len() < NONCE_LEN {
+            return Err(AgentError::Workflow(format!(
+                "Corrupt encrypted snapshot {}: ciphertext too short",
+                id
+            )));
+        }
+
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let nonce: [u8; NONCE_LEN] = nonce.try_into().expect("split at nonce length");
+        cipher
+            .decrypt(
+                &Nonce::from(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: id.as_bytes(),
+                },
+            )
+            .map_err(|_| {
+                AgentError::Workflow(format!(
+                    "Failed to decrypt snapshot {} with key '{}'",
+                    id, key_id
+                ))
+            })
+    }
+}
+
+/// [`SnapshotStorage`] decorator that encrypts snapshots before storing them
+pub struct EncryptedSnapshotStorage {
+    inner: Arc<dyn SnapshotStorage>,
+    keyring: SnapshotKeyring,
+    allow_unencrypted: bool,
+}
+
+impl EncryptedSnapshotStorage {
+    pub fn new(inner: Arc<dyn SnapshotStorage>, keyring: SnapshotKeyring) -> Self {
+        Self {
+            inner,
+            keyring,
+            allow_unencrypted: false,
+        }
+    }
+
+    /// Encrypt `inner` with the keys in `config`
+    pub fn from_config(
+        inner: Arc<dyn SnapshotStorage>,
+        config: &SnapshotEncryptionConfig,
+    ) -> Result<Self> {
+        let storage = Self::new(inner, SnapshotKeyring::from_config(config)?);
+        Ok(storage.with_unencrypted_reads(config.allow_unencrypted))
+    }
+
+    /// Read snapshots stored before encryption was enabled instead of
+    /// rejecting them, so [`Self::reencrypt_all`] can migrate them
+    pub fn with_unencrypted_reads(mut self, allow: bool) -> Self {
+        self.allow_unencrypted = allow;
+        self
+    }
+
+    fn encrypt(&self, snapshot: &WorkflowSnapshot) -> Result<WorkflowSnapshot> {
+        let plaintext = serde_json::to_vec(snapshot)
+            .map_err(|e| AgentError::Workflow(format!("Failed to serialize snapshot: {}", e)))?;
+        let sealed = self.keyring.seal(snapshot.id, &plaintext)?;
+
+        // Creation time stays visible so the inner storage can order and expire snapshots
+        Ok(WorkflowSnapshot {
+            id: snapshot.id,
+            created_at: snapshot.created_at,
+            context: WorkflowContext::new(0),
+            current_step: 0,
+            suspend_reason: SuspendReason::Manual,
+            metadata: HashMap::from([(
+                KEY_ID_METADATA.to_string(),
+                self.keyring.active_key_id.clone(),
+            )]),
+            step_state: HashMap::from([(CIPHERTEXT_STATE.to_string(), sealed.into())]),
+        })
+    }
+
+    fn decrypt(&self, envelope: WorkflowSnapshot) -> Result<WorkflowSnapshot> {
+        let Some(key_id) = envelope.metadata.get(KEY_ID_METADATA) else {
+            if !self.allow_unencrypted {
+                return Err(AgentError::Workflow(format!(
+                    "Snapshot {} is not encrypted",
+                    envelope.id
+                )));
+            }
+            // Written before encryption was enabled
+            debug!("Reading unencrypted snapshot {}", envelope.id);
+            return Ok(envelope);
+        };
+        let sealed = envelope
+            .step_state
+            .get(CIPHERTEXT_STATE)
+            .and_then(|value| value.as_str())
+            .ok_or_else(|| {
+                AgentError::Workflow(format!(
+                    "Encrypted snapshot {} has no ciphertext",
+                    envelope.id
+                ))
+            })?;
+
+        let plaintext = self.keyring.open(key_id, envelope.id, sealed)?;
+        serde_json::from_slice(&plaintext)
+            .map_err(|e| AgentError::Workflow(format!("Failed to deserialize snapshot: {}", e)))
+    }
+
+    /// Re-encrypt every stored snapshot that is not sealed with the active key.
+    ///
+    /// Run after [`SnapshotKeyring::rotate`] so the retired key can eventually be
+    /// dropped, or with unencrypted reads allowed to encrypt snapshots stored
+    /// before encryption was enabled. Returns the number of snapshots rewritten.
+    pub async fn reencrypt_all(&self) -> Result<usize> {
+        let mut rewritten = 0;
+        for envelope in self.inner.list_snapshots(None).await? {
+            if envelope.metadata.get(KEY_ID_METADATA) == Some(&self.keyring.active_key_id) {
+                continue;
+            }
+            let snapshot = self.decrypt(envelope)?;
+            self.inner.store_snapshot(&self.encrypt(&snapshot)?).await?;
+            rewritten += 1;
+        }
+
+        if rewritten > 0 {
+            info!(
+                "Re-encrypted {} snapshots with key '{}'",
+                rewritten, self.keyring.active_key_id
+            );
+        }
+        Ok(rewritten)
+    }
+}
+
+#[async_trait]
+impl SnapshotStorage for EncryptedSnapshotStorage {
+    async fn store_snapshot(&self, snapshot: &WorkflowSnapshot) -> Result<()> {
+        self.inner.store_snapshot(&self.encrypt(snapshot)?).await
+    }
+
+    async fn get_snapshot(&self, id: Uuid) -> Result<Option<WorkflowSnapshot>> {
+        self.inner
+            .get_snapshot(id)
+            .await?
+            .map(|envelope| self.decrypt(envelope))
+            .transpose()
+    }
+
+    async fn list_snapshots(
+        &self,
+        filter: Option<HashMap<String, String>>,
+    ) -> Result<Vec<WorkflowSnapshot>> {
+        // Metadata is encrypted, so filtering has to happen after decryption
+        let mut snapshots = Vec::new();
+        for envelope in self.inner.list_snapshots(None).await? {
+            let id = envelope.id;
+            let snapshot = match self.decrypt(envelope) {
+                Ok(snapshot) => snapshot,
+                Err(e) => {
+                    warn!("Skipping snapshot {}: {}", id, e);
+                    continue;
+                }
+            };
+
+            let matches = filter.as_ref().is_none_or(|filter_map| {
+                filter_map
+                    .iter()
+                    .all(|(key, value)| snapshot.metadata.get(key) == Some(value))
+            });
+            if matches {
+                snapshots.push(snapshot);
+            }
+        }
+        Ok(snapshots)
+    }
+
+    async fn list_snapshots_page(
+        &self,
+        offset: usize,
+        limit: usize,
+        filter: Option<HashMap<String, String>>,
+    ) -> Result<(Vec<SnapshotSummary>, usize)> {
+        if filter.is_some() {
+            // Metadata is encrypted, so filtering has to decrypt every snapshot
+            let snapshots = self.list_snapshots(filter).await?;
+            let summaries = snapshots.iter().map(SnapshotSummary::from).collect();
+            return Ok(summary_page(summaries, offset, limit));
+        }
+
+        // Envelopes keep their creation time in the clear, so the inner
+        // storage pages them and only the page is decrypted
+        let (envelopes, total) = self.inner.list_snapshots_page(offset, limit, None).await?;
+        let mut summaries = Vec::new();
+        for envelope in envelopes {
+            match self.get_snapshot(envelope.id).await {
+                Ok(Some(snapshot)) => summaries.push(SnapshotSummary::from(&snapshot)),
+                Ok(None) => {}
+                Err(e) => warn!("Skipping snapshot {}: {}", envelope.id, e),
+            }
+        }
+        Ok((summaries, total))
+    }
+
+    async fn delete_snapshot(&self, id: Uuid) -> Result<bool> {
+        self.inner.delete_snapshot(id).await
+    }
+
+    async fn cleanup_old_snapshots(&self, older_than: chrono::Duration) -> Result<usize> {
+        self.inner.cleanup_old_snapshots(older_than).await
+    }
+
+    async fn delete_older_than_keeping(&self, max_keep: usize) -> Result<usize> {
+        self.inner.delete_older_than_keeping(max_keep).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::user_message;
+    use crate::workflow::FileSnapshotStorage;
+    use chrono::Utc;
+    use tempfile::TempDir;
+
+    fn sensitive_snapshot() -> WorkflowSnapshot {
+        let mut context = WorkflowContext::new(10);
+        context.add_message(user_message("my card number is 4111-1111-1111-1111"));
+
+        WorkflowSnapshot {
+            id: Uuid::new_v4(),
+            created_at: Utc::now(),
+            context,
+            current_step: 3,
+            suspend_reason: SuspendReason::WaitingForInput("confirm payment".to_string()),
+            metadata: HashMap::from([("user".to_string(), "alice".to_string())]),
+            step_state: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_storage_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let inner = Arc::new(FileSnapshotStorage::new(temp_dir.path()));
+        let keyring = SnapshotKeyring::new("k1", &[7u8; 32]).unwrap();
+        let storage = EncryptedSnapshotStorage::new(inner.clone(), keyring);
+
+        let snapshot = sensitive_snapshot();
+        storage.store_snapshot(&snapshot).await.unwrap();
+
+        let raw = std::fs::read_to_string(temp_dir.path().join(format!("{}.json", snapshot.id)))
+            .unwrap();
+        assert!(!raw.contains("4111"));
+        assert!(!raw.contains("confirm payment"));
+        assert!(!raw.contains("alice"));
+        assert!(serde_json::from_str::<WorkflowSnapshot>(&raw)
+            .unwrap()
+            .context
+            .messages
+            .is_empty());
+
+        let restored = storage.get_snapshot(snapshot.id).await.unwrap().unwrap();
+        assert_eq!(restored.current_step, 3);
+        assert_eq!(restored.context.messages.len(), 1);
+        assert!(restored.context.messages[0].content.contains("4111"));
+
+        let filtered = storage
+            .list_snapshots(Some(HashMap::from([(
+                "user".to_string(),
+                "alice".to_string(),
+            )])))
+            .await
+            .unwrap();
+        assert_eq!(filtered.len(), 1);
+
+        // Pages carry the decrypted summaries
+        let (page, total) = storage.list_snapshots_page(0, 10, None).await.unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(page[0].metadata["user"], "alice");
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_storage_key_rotation() {
+        let temp_dir = TempDir::new().unwrap();
+        let inner: Arc<dyn SnapshotStorage> = Arc::new(FileSnapshotStorage::new(temp_dir.path()));
+
+        let old_keyring = SnapshotKeyring::new("old", &[1u8; 32]).unwrap();
+        let snapshot = sensitive_snapshot();
+        EncryptedSnapshotStorage::new(inner.clone(), old_keyring.clone())
+            .store_snapshot(&snapshot)
+            .await
+            .unwrap();
+
+        // A keyring without the old key can't read it
+        let unrelated = EncryptedSnapshotStorage::new(
+            inner.clone(),
+            SnapshotKeyring::new("new", &[2u8; 32]).unwrap(),
+        );
+        assert!(unrelated.get_snapshot(snapshot.id).await.is_err());
+
+        let rotated = EncryptedSnapshotStorage::new(
+            inner.clone(),
+            old_keyring.rotate("new", &[2u8; 32]).unwrap(),
+        );
+        assert!(rotated.get_snapshot(snapshot.id).await.unwrap().is_some());
+
+        assert_eq!(rotated.reencrypt_all().await.unwrap(), 1);
+        assert_eq!(rotated.reencrypt_all().await.unwrap(), 0);
+        assert!(unrelated.get_snapshot(snapshot.id).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_unencrypted_snapshots_need_migration_opt_in() {
+        let temp_dir = TempDir::new().unwrap();
+        let inner: Arc<dyn SnapshotStorage> = Arc::new(FileSnapshotStorage::new(temp_dir.path()));
+        let snapshot = sensitive_snapshot();
+        inner.store_snapshot(&snapshot).await.unwrap();
+
+        let keyring = SnapshotKeyring::new("k1", &[7u8; 32]).unwrap();
+        let storage = EncryptedSnapshotStorage::new(inner.clone(), keyring.clone());
+        assert!(storage.get_snapshot(snapshot.id).await.is_err());
+        assert!(storage.list_snapshots(None).await.unwrap().is_empty());
+
+        let migrating =
+            EncryptedSnapshotStorage::new(inner.clone(), keyring).with_unencrypted_reads(true);
+        assert_eq!(migrating.reencrypt_all().await.unwrap(), 1);
+
+        // Once sealed it reads back without the opt-in
+        let restored = storage.get_snapshot(snapshot.id).await.unwrap().unwrap();
+        assert_eq!(restored.current_step, 3);
+    }
+}
@@ -0,0 +1,364 @@
+//! Output guardrails
+//!
+//! [`GuardrailStep`] is a final safety gate for a drafted response. The draft
+//! is checked against a regex blocklist, a maximum length and optionally an
+//! LLM policy classifier. A clean draft lets the workflow continue. On a
+//! violation the step rewrites the draft, replaces it with a canned message,
+//! or suspends for human review, as set by its [`GuardrailAction`].
+//! Violations are recorded in the context metadata either way.
+//!
+//! As a workflow step it checks the assistant message a previous step drafted
+//! in the same run. Responses the agent generates after its workflow are
+//! checked with [`Agent::set_guardrail`](crate::agent::Agent::set_guardrail).
+
+use super::{SuspendReason, WorkflowContext, WorkflowDecision, WorkflowStep};
+use crate::error::{AgentError, Result};
+use crate::llm::{system_message, user_message, LlmClient, Role};
+use crate::prompts::{self, PromptLibrary};
+use async_trait::async_trait;
+use regex::Regex;
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+/// Metadata key listing the violations found in the draft, one per line
+pub const GUARDRAIL_VIOLATIONS_KEY: &str = "guardrail_violations";
+
+/// Metadata key recording what the guardrail did: `rewritten`, `blocked` or
+/// `review`
+pub const GUARDRAIL_ACTION_KEY: &str = "guardrail_action";
+
+/// Metadata key a reviewer sets to `approved` or `rejected` before resuming a
+/// workflow suspended for review
+pub const GUARDRAIL_REVIEW_KEY: &str = "guardrail_review";
+
+/// What a [`GuardrailStep`] does with a draft that fails a check
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GuardrailAction {
+    /// Redact blocked patterns, truncate to the maximum length and have the
+    /// classifier model rewrite policy violations
+    Rewrite,
+    /// Replace the draft with the blocked message
+    #[default]
+    Block,
+    /// Suspend until a reviewer sets [`GUARDRAIL_REVIEW_KEY`], which is
+    /// removed once acted on
+    Review,
+}
+
+/// Step that checks the drafted response before it is returned
+pub struct GuardrailStep {
+    blocklist: Vec<Regex>,
+    max_length: Option<usize>,
+    classifier: Option<Arc<dyn LlmClient>>,
+    action: GuardrailAction,
+    blocked_message: String,
+    prompts: Arc<PromptLibrary>,
+}
+
+impl GuardrailStep {
+    pub fn new(action: GuardrailAction) -> Self {
+        Self {
+            blocklist: Vec::new(),
+            max_length: None,
+            classifier: None,
+            action,
+            blocked_message: "I can't share that response.".to_string(),
+            prompts: Arc::new(PromptLibrary::builtin()),
+        }
+    }
+
+    /// Reject drafts matching `pattern`
+    pub fn with_blocked_pattern(mut self, pattern: &str) -> Result<Self> {
+        let regex = Regex::new(pattern).map_err(|e| {
+            AgentError::Config(format!("Invalid guardrail pattern '{}': {}", pattern, e))
+        })?;
+        self.blocklist.push(regex);
+        Ok(self)
+    }
+
+    /// Reject drafts longer than `max_length` characters
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.max_length = Some(max_length);
+        self
+    }
+
+    /// Ask `llm` whether each draft violates policy. Drafts are treated as
+    /// violations when the classifier cannot be reached.
+    pub fn with_classifier(mut self, llm: Arc<dyn LlmClient>) -> Self {
+        self.classifier = Some(llm);
+        self
+    }
+
+    /// Take the classifier and rewrite prompts from `prompts`
+    pub fn with_prompt_library(mut self, prompts: Arc<PromptLibrary>) -> Self {
+        self.prompts = prompts;
+        self
+    }
+
+    /// Message that replaces blocked drafts
+    pub fn with_blocked_message(mut self, message: impl Into<String>) -> Self {
+        self.blocked_message = message.into();
+        self
+    }
+
+    pub fn blocked_message(&self) -> &str {
+        &self.blocked_message
+    }
+
+    /// Check `draft`: `Continue` when it may be returned as it is, `Complete`
+    /// with the response to return instead, or `Suspend` for review
+    pub async fn review(
+        &self,
+        context: &mut WorkflowContext,
+        draft: &str,
+    ) -> Result<WorkflowDecision> {
+        let (violations, flagged) = self.check(draft).await?;
+        if violations.is_empty() {
+            context.metadata.remove(GUARDRAIL_VIOLATIONS_KEY);
+            return Ok(WorkflowDecision::Continue);
+        }
+        warn!("Guardrail found {} violations", violations.len());
+        context
+            .metadata
+            .insert(GUARDRAIL_VIOLATIONS_KEY.to_string(), violations.join("\n"));
+
+        match self.action {
+            GuardrailAction::Block => {
+                Ok(self.finish(context, "blocked", self.blocked_message.clone()))
+            }
+            GuardrailAction::Rewrite => Ok(match self.rewrite(draft, flagged).await {
+                Some(rewritten) => self.finish(context, "rewritten", rewritten),
+                None => self.finish(context, "blocked", self.blocked_message.clone()),
+            }),
+            GuardrailAction::Review => match context.metadata.remove(GUARDRAIL_REVIEW_KEY) {
+                Some(decision) if decision == "approved" => Ok(WorkflowDecision::Continue),
+                Some(decision) if decision == "rejected" => {
+                    Ok(self.finish(context, "blocked", self.blocked_message.clone()))
+                }
+                _ => {
+                    context
+                        .metadata
+                        .insert(GUARDRAIL_ACTION_KEY.to_string(), "review".to_string());
+                    Ok(WorkflowDecision::Suspend(SuspendReason::WaitingForInput(
+                        format!("Review response: {}", violations.join("; ")),
+                    )))
+                }
+            },
+        }
+    }
+
+    /// Violations of `draft`, with whether the classifier flagged it
+    async fn check(&self, draft: &str) -> Result<(Vec<String>, bool)> {
+        let mut violations: Vec<String> = self
+            .blocklist
+            .iter()
+            .filter(|regex| regex.is_match(draft))
+            .map(|regex| format!("matched blocked pattern '{}'", regex.as_str()))
+            .collect();
+
+        let length = draft.chars().count();
+        if let Some(max_length) = self.max_length.filter(|max| length > *max) {
+            violations.push(format!("length {} exceeds maximum {}", length, max_length));
+        }
+
+        let mut flagged = false;
+        if let Some(classifier) = &self.classifier {
+            let system_prompt = self.prompts.render(prompts::GUARDRAIL_CLASSIFIER, &[])?;
+            let messages = [system_message(system_prompt), user_message(draft)];
+            match classifier.generate(&messages).await {
+                Ok(response) => {
+                    let verdict = response.text.trim();
+                    if !verdict.to_uppercase().starts_with("SAFE") {
+                        let reason = verdict
+                            .split_once(':')
+                            .map_or(verdict, |(_, reason)| reason)
+                            .trim();
+                        violations.push(format!("policy violation: {}", reason));
+                        flagged = true;
+                    }
+                }
+                Err(e) => {
+                    warn!("Guardrail classifier failed: {}", e);
+                    violations.push(format!("policy check unavailable: {}", e));
+                    flagged = true;
+                }
+            }
+        }
+
+        Ok((violations, flagged))
+    }
+
+    /// `draft` with the violations removed, or `None` if it cannot be rewritten
+    async fn rewrite(&self, draft: &str, flagged: bool) -> Option<String> {
+        let mut text = self
+            .blocklist
+            .iter()
+            .fold(draft.to_string(), |text, regex| {
+                regex.replace_all(&text, "[redacted]").into_owned()
+            });
+
+        if flagged {
+            let classifier = self.classifier.as_ref()?;
+            let system_prompt = match self.prompts.render(prompts::GUARDRAIL_REWRITE, &[]) {
+                Ok(system_prompt) => system_prompt,
+                Err(e) => {
+                    warn!("Failed to render guardrail rewrite prompt: {}", e);
+                    return None;
+                }
+            };
+            let messages = [system_message(system_prompt), user_message(text)];
+            match classifier.generate(&messages).await {
+                Ok(response) if !response.text.trim().is_empty() => {
+                    text = response.text.trim().to_string();
+                }
+                Ok(_) => return None,
+                Err(e) => {
+                    warn!("Guardrail rewrite failed: {}", e);
+                    return None;
+                }
+            }
+        }
+
+        if let Some(max_length) = self.max_length {
+            text = text.chars().take(max_length).collect();
+        }
+        Some(text)
+    }
+
+    fn finish(
+        &self,
+        context: &mut WorkflowContext,
+        action: &str,
+        response: String,
+    ) -> WorkflowDecision {
+        info!("Guardrail {} the drafted response", action);
+        context
+            .metadata
+            .insert(GUARDRAIL_ACTION_KEY.to_string(), action.to_string());
+        replace_draft(context, &response);
+        WorkflowDecision::Complete(response)
+    }
+}
+
+/// Replace the content of the drafted response, if the context ends with one
+fn replace_draft(context: &mut WorkflowContext, content: &str) {
+    if let Some(message) = context
+        .messages
+        .last_mut()
+        .filter(|message| message.role == Role::Assistant)
+    {
+        message.content = content.to_string();
+    }
+}
+
+#[async_trait]
+impl WorkflowStep for GuardrailStep {
+    async fn execute(&self, context: &mut WorkflowContext) -> Result<WorkflowDecision> {
+        // Only a draft written since the last user message is this run's;
+        // earlier assistant messages were already returned
+        let draft = context
+            .messages
+            .last()
+            .filter(|message| message.role == Role::Assistant)
+            .map(|message| message.content.clone());
+        let Some(draft) = draft else {
+            debug!("No drafted response to check");
+            return Ok(WorkflowDecision::Continue);
+        };
+        self.review(context, &draft).await
+    }
+
+    fn name(&self) -> &str {
+        "guardrail"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::assistant_message;
+
+    fn drafted(response: &str) -> WorkflowContext {
+        let mut context = WorkflowContext::new(5);
+        context.add_message(user_message("How do I reset the controller?"));
+        context.add_message(assistant_message(response));
+        context
+    }
+
+    #[tokio::test]
+    async fn test_blocklist_hit_is_blocked() {
+        let step = GuardrailStep::new(GuardrailAction::Block)
+            .with_blocked_pattern(r"(?i)password:\s*\S+")
+            .unwrap()
+            .with_blocked_message("Response withheld.");
+        let mut context = drafted("Log in with password: hunter2 and hold reset.");
+
+        match step.execute(&mut context).await.unwrap() {
+            WorkflowDecision::Complete(response) => assert_eq!(response, "Response withheld."),
+            other => panic!("expected completion, got {:?}", other),
+        }
+        assert_eq!(
+            context.last_assistant_message().unwrap().content,
+            "Response withheld."
+        );
+        assert!(context.metadata[GUARDRAIL_VIOLATIONS_KEY].contains("blocked pattern"));
+        assert_eq!(context.metadata[GUARDRAIL_ACTION_KEY], "blocked");
+
+        // Rewriting redacts the match instead
+        let step = GuardrailStep::new(GuardrailAction::Rewrite)
+            .with_blocked_pattern(r"(?i)password:\s*\S+")
+            .unwrap();
+        let mut context = drafted("Log in with password: hunter2 and hold reset.");
+        match step.execute(&mut context).await.unwrap() {
+            WorkflowDecision::Complete(response) => {
+                assert_eq!(response, "Log in with [redacted] and hold reset.")
+            }
+            other => panic!("expected completion, got {:?}", other),
+        }
+
+        // Review suspends until a reviewer decides
+        let step = GuardrailStep::new(GuardrailAction::Review).with_max_length(10);
+        let mut context = drafted("Hold the reset button for ten seconds.");
+        assert!(matches!(
+            step.execute(&mut context).await.unwrap(),
+            WorkflowDecision::Suspend(SuspendReason::WaitingForInput(_))
+        ));
+        context
+            .metadata
+            .insert(GUARDRAIL_REVIEW_KEY.to_string(), "approved".to_string());
+        assert!(matches!(
+            step.execute(&mut context).await.unwrap(),
+            WorkflowDecision::Continue
+        ));
+        assert!(!context.metadata.contains_key(GUARDRAIL_REVIEW_KEY));
+        assert_eq!(
+            context.last_assistant_message().unwrap().content,
+            "Hold the reset button for ten seconds."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_clean_response_passes_through() {
+        let step = GuardrailStep::new(GuardrailAction::Block)
+            .with_blocked_pattern(r"(?i)password:\s*\S+")
+            .unwrap()
+            .with_max_length(200);
+        let mut context = drafted("Hold the reset button for ten seconds.");
+
+        assert!(matches!(
+            step.execute(&mut context).await.unwrap(),
+            WorkflowDecision::Continue
+        ));
+        assert!(!context.metadata.contains_key(GUARDRAIL_VIOLATIONS_KEY));
+        assert!(!context.metadata.contains_key(GUARDRAIL_ACTION_KEY));
+
+        // A response from an earlier turn is not checked again
+        let mut context = drafted("Log in with password: hunter2");
+        context.add_message(user_message("Thanks, what next?"));
+        assert!(matches!(
+            step.execute(&mut context).await.unwrap(),
+            WorkflowDecision::Continue
+        ));
+        assert!(!context.metadata.contains_key(GUARDRAIL_VIOLATIONS_KEY));
+    }
+}
@@ -0,0 +1,331 @@
+//! Retrieval-augmented generation from the knowledge base
+//!
+//! [`KnowledgeRetrievalStep`] embeds the latest user message, searches the
+//! memory store for ingested knowledge chunks and prepends the best matches
+//! to the conversation as a system message. Each chunk is numbered and cited
+//! with its source and metadata so the model can refer back to it. Chunks cut
+//! from the same document overlap, so text a chunk shares with one already
+//! selected is dropped, and so is a chunk with nothing left to add.
+
+use super::{SystemPromptMode, WorkflowContext, WorkflowDecision, WorkflowStep};
+use crate::error::Result;
+use crate::knowledge::KnowledgeChunk;
+use crate::llm::LlmClient;
+use crate::memory::MemoryStore;
+use async_trait::async_trait;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::debug;
+
+/// Data key of the chunks injected for the latest user message
+pub const KNOWLEDGE_CHUNKS_KEY: &str = "knowledge_chunks";
+
+/// Metadata key of the user message knowledge was last retrieved for
+const KNOWLEDGE_QUERY_KEY: &str = "knowledge_query";
+
+/// Shortest shared run of characters treated as chunk overlap rather than
+/// coincidence
+const MIN_OVERLAP_CHARS: usize = 20;
+
+/// How many times `top_k` results to search for, so that enough remain after
+/// plain memories and duplicate chunks are dropped
+const SEARCH_OVERFETCH: usize = 3;
+
+/// Step that injects relevant knowledge base chunks into the conversation
+pub struct KnowledgeRetrievalStep {
+    llm: Arc<dyn LlmClient>,
+    store: Arc<RwLock<Box<dyn MemoryStore>>>,
+    top_k: usize,
+    min_score: f32,
+}
+
+impl KnowledgeRetrievalStep {
+    pub fn new(llm: Arc<dyn LlmClient>, store: Arc<RwLock<Box<dyn MemoryStore>>>) -> Self {
+        Self {
+            llm,
+            store,
+            top_k: 5,
+            min_score: 0.5,
+        }
+    }
+
+    /// Inject at most `top_k` chunks
+    pub fn with_top_k(mut self, top_k: usize) -> Self {
+        self.top_k = top_k;
+        self
+    }
+
+    /// Skip chunks less similar to the query than `min_score`
+    pub fn with_min_score(mut self, min_score: f32) -> Self {
+        self.min_score = min_score;
+        self
+    }
+}
+
+#[async_trait]
+impl WorkflowStep for KnowledgeRetrievalStep {
+    async fn execute(&self, context: &mut WorkflowContext) -> Result<WorkflowDecision> {
+        let Some(query) = context.last_user_message().map(|msg| msg.content.clone()) else {
+            return Ok(WorkflowDecision::Continue);
+        };
+        // Looping back to this step must not inject the same context twice
+        if context.metadata.get(KNOWLEDGE_QUERY_KEY) == Some(&query) {
+            return Ok(WorkflowDecision::Continue);
+        }
+
+        let embedding = self.llm.embed(&query).await?.embedding;
+        let results = self
+            .store
+            .read()
+            .await
+            .search(
+                embedding,
+                self.top_k.saturating_mul(SEARCH_OVERFETCH),
+                self.min_score,
+            )
+            .await?;
+
+        // Memories that did not come from ingestion carry no source
+        let mut chunks = deduplicate(
+            results
+                .into_iter()
+                .map(|result| KnowledgeChunk::from(result.entry))
+                .filter(|chunk| !chunk.source.is_empty())
+                .map(|chunk| KnowledgeChunk {
+                    embedding: None,
+                    ..chunk
+                })
+                .collect(),
+        );
+        chunks.truncate(self.top_k);
+        context
+            .metadata
+            .insert(KNOWLEDGE_QUERY_KEY.to_string(), query);
+
+        debug!("Injecting {} knowledge chunks", chunks.len());
+        if !chunks.is_empty() {
+            context.set_system_prompt(&render_context(&chunks), SystemPromptMode::Prepend);
+        }
+        context.set(KNOWLEDGE_CHUNKS_KEY, &chunks)?;
+
+        Ok(WorkflowDecision::Continue)
+    }
+
+    fn name(&self) -> &str {
+        "knowledge_retrieval"
+    }
+}
+
+/// `chunks`, best first, with the text each shares with a better chunk from
+/// the same source removed and chunks left with nothing new dropped
+fn deduplicate(chunks: Vec<KnowledgeChunk>) -> Vec<KnowledgeChunk> {
+    let mut kept: Vec<KnowledgeChunk> = Vec::with_capacity(chunks.len());
+    for mut chunk in chunks {
+        let mut content = chunk.content.trim();
+        for other in kept.iter().filter(|other| other.source == chunk.source) {
+            if other.content.contains(content) {
+                content = "";
+                break;
+            }
+            content = &content[shared_edge(&other.content, content)..];
+            content = &content[..content.len() - shared_edge(content, &other.content)];
+        }
+
+        let content = content.trim();
+        if !content.is_empty() {
+            chunk.content = content.to_string();
+            kept.push(chunk);
+        }
+    }
+    kept
+}
+
+/// Length in bytes of the longest start of `tail` that `head` ends with, or 0
+/// if it is shorter than [`MIN_OVERLAP_CHARS`]
+fn shared_edge(head: &str, tail: &str) -> usize {
+    tail.char_indices()
+        .map(|(i, c)| i + c.len_utf8())
+        .rev()
+        .find(|&len| head.ends_with(&tail[..len]))
+        .filter(|&len| tail[..len].chars().count() >= MIN_OVERLAP_CHARS)
+        .unwrap_or(0)
+}
+
+/// System message presenting `chunks` with numbered citations
+fn render_context(chunks: &[KnowledgeChunk]) -> String {
+    let mut text = String::from(
+        "Use the following knowledge base excerpts to answer. Cite them by number, e.g. [1].\n",
+    );
+    for (i, chunk) in chunks.iter().enumerate() {
+        let mut details: Vec<String> = chunk
+            .metadata
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect();
+        details.sort();
+        if !chunk.source_type.is_empty() {
+            details.insert(0, chunk.source_type.clone());
+        }
+
+        text.push_str(&format!("\n[{}] {}", i + 1, chunk.source));
+        if !details.is_empty() {
+            text.push_str(&format!(" ({})", details.join(", ")));
+        }
+        text.push_str(&format!("\n{}\n", chunk.content));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MemoryConfig;
+    use crate::llm::mock::MockLlm;
+    use crate::llm::{user_message, Role};
+    use crate::memory::SqliteMemoryStore;
+
+    /// Embeds text onto two axes: "encoder" and "battery"
+    fn keyword_embedding(text: &str) -> Vec<f32> {
+        let lower = text.to_lowercase();
+        vec![
+            if lower.contains("encoder") { 1.0 } else { 0.0 },
+            if lower.contains("battery") { 1.0 } else { 0.0 },
+            0.1,
+        ]
+    }
+
+    fn keyword_embedder() -> Arc<MockLlm> {
+        Arc::new(MockLlm::new().with_embed(|text| Ok(keyword_embedding(text))))
+    }
+
+    fn chunk(content: &str, source: &str) -> KnowledgeChunk {
+        KnowledgeChunk::new(
+            content.to_string(),
+            source.to_string(),
+            "markdown".to_string(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_injects_relevant_chunks_with_citations() {
+        let config = MemoryConfig {
+            database_url: Some("sqlite::memory:".to_string()),
+            embedding_dimension: 3,
+            ..Default::default()
+        };
+        let mut store: Box<dyn MemoryStore> = Box::new(SqliteMemoryStore::new(config));
+        store.initialize().await.unwrap();
+
+        let overlap = "then zero the encoder offset in the joint panel.";
+        let documents = [
+            chunk(
+                &format!(
+                    "Power off the arm before calibrating the encoder, {}",
+                    overlap
+                ),
+                "arm_manual.md",
+            )
+            .with_metadata("section".to_string(), "Calibration".to_string()),
+            chunk(
+                &format!("{} Encoder drift above 2 degrees needs service.", overlap),
+                "arm_manual.md",
+            )
+            .with_metadata("section".to_string(), "Calibration".to_string()),
+            chunk("Charge the battery for four hours.", "charging.md"),
+        ];
+        for document in documents {
+            let embedding = keyword_embedding(&document.content);
+            let (content, _, metadata) = document.into_memory_parts();
+            store.store(content, embedding, metadata).await.unwrap();
+        }
+        // A plain memory is not knowledge and is never cited
+        store
+            .store(
+                "User asked about the encoder yesterday".to_string(),
+                keyword_embedding("encoder"),
+                Default::default(),
+            )
+            .await
+            .unwrap();
+
+        let step = KnowledgeRetrievalStep::new(keyword_embedder(), Arc::new(RwLock::new(store)))
+            .with_top_k(5)
+            .with_min_score(0.5);
+        let mut context = WorkflowContext::new(10);
+        context.add_message(user_message("How do I calibrate the encoder?"));
+
+        step.execute(&mut context).await.unwrap();
+
+        assert_eq!(context.messages.len(), 2);
+        let injected = &context.messages[0];
+        assert_eq!(injected.role, Role::System);
+        assert!(injected
+            .content
+            .contains("[1] arm_manual.md (markdown, section=Calibration)"));
+        assert!(injected.content.contains("[2] arm_manual.md"));
+        assert!(injected.content.contains("Power off the arm"));
+        assert!(injected.content.contains("needs service"));
+        assert_eq!(injected.content.matches(overlap).count(), 1);
+        assert!(!injected.content.contains("battery"));
+        assert!(!injected.content.contains("yesterday"));
+
+        let chunks: Vec<KnowledgeChunk> = context.get(KNOWLEDGE_CHUNKS_KEY).unwrap().unwrap();
+        assert_eq!(chunks.len(), 2);
+
+        // Running again for the same message leaves the conversation alone
+        step.execute(&mut context).await.unwrap();
+        assert_eq!(context.messages.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_top_k_counts_only_injected_chunks() {
+        let config = MemoryConfig {
+            database_url: Some("sqlite::memory:".to_string()),
+            embedding_dimension: 3,
+            ..Default::default()
+        };
+        let mut store: Box<dyn MemoryStore> = Box::new(SqliteMemoryStore::new(config));
+        store.initialize().await.unwrap();
+
+        // Plain memories match the query better than the only knowledge chunk
+        for memory in ["Encoder came up in chat", "Encoder came up again"] {
+            store
+                .store(
+                    memory.to_string(),
+                    keyword_embedding("encoder"),
+                    Default::default(),
+                )
+                .await
+                .unwrap();
+        }
+        let (content, _, metadata) =
+            chunk("Encoder and battery share the joint bus.", "bus.md").into_memory_parts();
+        store
+            .store(content.clone(), keyword_embedding(&content), metadata)
+            .await
+            .unwrap();
+
+        let step = KnowledgeRetrievalStep::new(keyword_embedder(), Arc::new(RwLock::new(store)))
+            .with_top_k(1)
+            .with_min_score(0.5);
+        let mut context = WorkflowContext::new(10);
+        context.add_message(user_message("How do I calibrate the encoder?"));
+
+        step.execute(&mut context).await.unwrap();
+
+        let chunks: Vec<KnowledgeChunk> = context.get(KNOWLEDGE_CHUNKS_KEY).unwrap().unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].source, "bus.md");
+    }
+
+    #[test]
+    fn test_deduplicate_drops_contained_chunks() {
+        let chunks = deduplicate(vec![
+            chunk("Encoders are calibrated from the joint panel.", "a.md"),
+            chunk("calibrated from the joint panel", "a.md"),
+            chunk("calibrated from the joint panel", "b.md"),
+        ]);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[1].source, "b.md");
+    }
+}
@@ -0,0 +1,316 @@
+//! Reason-act loop within a single step
+//!
+//! [`ReActStep`] lets the model drive tool use: each iteration streams a
+//! generation, runs the tool calls it proposes and adds their results to the
+//! conversation as tool messages answering the assistant's tool calls, until
+//! the model answers without calling a tool. The executor's tools are offered
+//! with every generation and the calls the model makes arrive as
+//! [`StreamEvent::ToolCall`]s. Results are also recorded in the context's
+//! tool results.
+
+use super::{WorkflowContext, WorkflowDecision, WorkflowStep};
+use crate::error::{AgentError, Result};
+use crate::llm::stream::StreamEvent;
+use crate::llm::{assistant_message, LlmClient};
+use crate::mcp::{McpClient, McpTool, ToolCall, ToolContent, ToolResult};
+use async_trait::async_trait;
+use futures::StreamExt;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+
+/// Metadata key recording how many model turns a [`ReActStep`] took
+pub const REACT_ITERATIONS_KEY: &str = "react_iterations";
+
+/// Offers tools to a [`ReActStep`]'s model and runs the calls it proposes
+#[async_trait]
+pub trait ToolExecutor: Send + Sync {
+    /// Tools the model may call
+    async fn tools(&self) -> Vec<McpTool>;
+
+    async fn call_tool(&self, tool_call: ToolCall) -> Result<ToolResult>;
+}
+
+#[async_trait]
+impl ToolExecutor for McpClient {
+    async fn tools(&self) -> Vec<McpTool> {
+        self.list_tools()
+            .into_iter()
+            .map(|(_, tool)| tool.clone())
+            .collect()
+    }
+
+    async fn call_tool(&self, tool_call: ToolCall) -> Result<ToolResult> {
+        McpClient::call_tool(self, tool_call).await
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for RwLock<McpClient> {
+    async fn tools(&self) -> Vec<McpTool> {
+        ToolExecutor::tools(&*self.read().await).await
+    }
+
+    async fn call_tool(&self, tool_call: ToolCall) -> Result<ToolResult> {
+        self.read().await.call_tool(tool_call).await
+    }
+}
+
+/// Step that alternates model turns and tool calls until a final answer
+pub struct ReActStep {
+    llm: Arc<dyn LlmClient>,
+    tools: Arc<dyn ToolExecutor>,
+    /// Model turns allowed before the step fails with
+    /// [`AgentError::LoopLimitExceeded`]
+    pub max_iterations: usize,
+}
+
+impl ReActStep {
+    pub fn new(llm: Arc<dyn LlmClient>, tools: Arc<dyn ToolExecutor>) -> Self {
+        Self {
+            llm,
+            tools,
+            max_iterations: 5,
+        }
+    }
+
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations.max(1);
+        self
+    }
+
+    /// Text and tool calls of one model turn, offered `tools`
+    async fn think(
+        &self,
+        context: &WorkflowContext,
+        tools: &[McpTool],
+    ) -> Result<(String, Vec<ToolCall>)> {
+        let mut events = self
+            .llm
+            .generate_stream_with_tools(&context.messages, tools)
+            .await?;
+        let mut text = String::new();
+        let mut tool_calls = Vec::new();
+        while let Some(event) = events.next().await {
+            match event? {
+                StreamEvent::TextDelta(delta) => text.push_str(&delta),
+                StreamEvent::ToolCall(call) => tool_calls.push(call),
+                StreamEvent::Reasoning(_) => {}
+                StreamEvent::Done { .. } => break,
+            }
+        }
+        Ok((text, tool_calls))
+    }
+
+    /// Run `call`, turning a failed call into an error result the model can
+    /// see and react to
+    async fn act(&self, call: &ToolCall) -> ToolResult {
+        match self.tools.call_tool(call.clone()).await {
+            Ok(result) => result,
+            Err(e) => {
+                warn!("ReAct tool call '{}' failed: {}", call.name, e);
+                ToolResult {
+                    id: call.id.clone(),
+                    content: vec![ToolContent::Text {
+                        text: format!("Error: {}", e),
+                    }],
+                    is_error: true,
+                    truncated: false,
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl WorkflowStep for ReActStep {
+    async fn execute(&self, context: &mut WorkflowContext) -> Result<WorkflowDecision> {
+        let tools = self.tools.tools().await;
+        for iteration in 1..=self.max_iterations {
+            context
+                .metadata
+                .insert(REACT_ITERATIONS_KEY.to_string(), iteration.to_string());
+
+            let (text, tool_calls) = self.think(context, &tools).await?;
+            if tool_calls.is_empty() {
+                info!("ReAct loop answered after {} iterations", iteration);
+                context.add_message(assistant_message(text.clone()));
+                return Ok(WorkflowDecision::Complete(text));
+            }
+
+            debug!(
+                "ReAct iteration {} requested {} tool calls",
+                iteration,
+                tool_calls.len()
+            );
+            if let Some(response) = context.tool_budget_exceeded(tool_calls.len()) {
+                warn!("ReAct loop stopped at the workflow's tool-call budget");
+                context.add_message(assistant_message(response.clone()));
+                return Ok(WorkflowDecision::Complete(response));
+            }
+            context.add_tool_calls(text, tool_calls.clone());
+            for call in tool_calls {
+                let result = self.act(&call).await;
+                context.add_tool_result(call.id, result);
+            }
+        }
+
+        warn!(
+            "ReAct loop gave no final answer within {} iterations",
+            self.max_iterations
+        );
+        Err(AgentError::LoopLimitExceeded {
+            step: self.name().to_string(),
+            iterations: self.max_iterations,
+        })
+    }
+
+    fn name(&self) -> &str {
+        "react"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::mock::MockLlm;
+    use crate::llm::{user_message, Role};
+    use crate::workflow::WorkflowEngine;
+    use std::sync::Mutex;
+
+    /// Model that asks for the weather until it sees a tool result, then
+    /// answers from it. With `stubborn` set it never answers.
+    fn weather_model(stubborn: bool) -> Arc<MockLlm> {
+        Arc::new(MockLlm::new().with_stream(move |messages| {
+            let observed = messages.iter().rev().find(|m| m.role == Role::Tool);
+            let events = match observed {
+                Some(observation) if !stubborn => vec![
+                    StreamEvent::TextDelta("Paris: ".to_string()),
+                    StreamEvent::TextDelta(observation.content.lines().last().unwrap().into()),
+                ],
+                _ => vec![
+                    StreamEvent::TextDelta("Let me check the weather.".to_string()),
+                    StreamEvent::ToolCall(ToolCall {
+                        id: format!("call-{}", messages.len()),
+                        name: "get_weather".to_string(),
+                        arguments: serde_json::json!({ "city": "Paris" }),
+                    }),
+                ],
+            };
+            let done = StreamEvent::Done {
+                finish_reason: None,
+                usage: None,
+            };
+            futures::stream::iter(events.into_iter().chain([done]).map(Ok)).boxed()
+        }))
+    }
+
+    #[derive(Default)]
+    struct RecordingTools {
+        calls: Mutex<Vec<ToolCall>>,
+    }
+
+    #[async_trait]
+    impl ToolExecutor for RecordingTools {
+        async fn tools(&self) -> Vec<McpTool> {
+            vec![McpTool {
+                name: "get_weather".to_string(),
+                description: "Current weather in a city".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": { "city": { "type": "string" } },
+                }),
+            }]
+        }
+
+        async fn call_tool(&self, tool_call: ToolCall) -> Result<ToolResult> {
+            let id = tool_call.id.clone();
+            self.calls.lock().unwrap().push(tool_call);
+            Ok(ToolResult {
+                id,
+                content: vec![ToolContent::Text {
+                    text: "sunny, 22°C".to_string(),
+                }],
+                is_error: false,
+                truncated: false,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_react_calls_tool_then_answers() {
+        let tools = Arc::new(RecordingTools::default());
+        let model = weather_model(false);
+        let step = ReActStep::new(model.clone(), tools.clone());
+        let mut context = WorkflowContext::new(10);
+        context.add_message(user_message("What's the weather in Paris?"));
+
+        let decision = step.execute(&mut context).await.unwrap();
+
+        assert!(matches!(
+            decision,
+            WorkflowDecision::Complete(ref answer) if answer == "Paris: sunny, 22°C"
+        ));
+        let calls = tools.calls.lock().unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].name, "get_weather");
+        assert_eq!(calls[0].arguments["city"], "Paris");
+        assert!(context.tool_results.contains_key(&calls[0].id));
+        assert_eq!(context.metadata[REACT_ITERATIONS_KEY], "2");
+        // The executor's tools are offered on every model turn
+        assert_eq!(model.offered_tools(), vec![vec!["get_weather"]; 2]);
+
+        // The call and its result are in the history as the provider expects
+        let roles: Vec<&Role> = context.messages.iter().map(|m| &m.role).collect();
+        assert_eq!(
+            roles,
+            [&Role::User, &Role::Assistant, &Role::Tool, &Role::Assistant]
+        );
+        assert_eq!(context.messages[1].tool_calls[0].id, calls[0].id);
+        assert_eq!(
+            context.messages[2].tool_call_id.as_ref(),
+            Some(&calls[0].id)
+        );
+        assert_eq!(
+            context.last_assistant_message().unwrap().content,
+            "Paris: sunny, 22°C"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_react_stops_at_iteration_cap() {
+        let tools = Arc::new(RecordingTools::default());
+        let step = ReActStep::new(weather_model(true), tools.clone()).with_max_iterations(3);
+        let mut context = WorkflowContext::new(10);
+        context.add_message(user_message("What's the weather in Paris?"));
+
+        let err = step.execute(&mut context).await.unwrap_err();
+
+        assert!(matches!(
+            err,
+            AgentError::LoopLimitExceeded { iterations: 3, .. }
+        ));
+        assert_eq!(tools.calls.lock().unwrap().len(), 3);
+        assert_eq!(context.tool_results.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_workflow_stops_at_tool_call_budget() {
+        let tools = Arc::new(RecordingTools::default());
+        let step = ReActStep::new(weather_model(true), tools.clone()).with_max_iterations(10);
+        let engine = WorkflowEngine::new().add_step(Box::new(step));
+        let mut context = WorkflowContext::new(10).with_max_tool_calls(2);
+        context.add_message(user_message("What's the weather in Paris?"));
+
+        let result = engine.execute(context).await.unwrap();
+
+        assert!(result.completed);
+        assert!(result.response.contains("2 of its 2 allowed tool calls"));
+        assert_eq!(tools.calls.lock().unwrap().len(), 2);
+        assert_eq!(result.context.tool_calls_made, 2);
+        assert_eq!(
+            result.context.last_assistant_message().unwrap().content,
+            result.response
+        );
+    }
+}
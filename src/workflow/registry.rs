@@ -0,0 +1,287 @@
+//! Declarative workflow definitions
+//!
+//! A [`WorkflowDefinition`] lists steps by type name with optional per-step
+//! config, so workflows can be written in JSON or YAML and loaded at runtime,
+//! as the visual workflow editor does with its nodes. A [`StepFactory`] maps
+//! each type name to a constructor; [`StepFactory::default`] knows the
+//! built-in steps and more can be registered. Building fails on any step type
+//! the factory does not know, naming all of them at once.
+//!
+//! ```json
+//! { "name": "answer", "steps": [
+//!     { "type": "memory_retrieval" },
+//!     { "type": "context_compaction", "config": { "max_messages": 10 } },
+//!     { "type": "response_generation" }
+//! ] }
+//! ```
+
+use super::{
+    ContextCompactionStep, HumanApprovalStep, IntentClassificationStep, MemoryRetrievalStep,
+    ResponseGenerationStep, SystemPromptMode, SystemPromptStep, ToolAnalysisStep,
+    ToolExecutionStep, ToolSelectionStep, WorkflowEngine, WorkflowStep,
+};
+use crate::error::{AgentError, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Builds a step from its definition's `config`
+pub type StepConstructor =
+    Arc<dyn Fn(&serde_json::Value) -> Result<Box<dyn WorkflowStep>> + Send + Sync>;
+
+/// A workflow as a list of step definitions
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkflowDefinition {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub steps: Vec<StepDefinition>,
+}
+
+/// One step: a type name known to the [`StepFactory`] and its config
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepDefinition {
+    #[serde(rename = "type")]
+    pub step_type: String,
+    #[serde(default)]
+    pub config: serde_json::Value,
+}
+
+impl WorkflowDefinition {
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json)
+            .map_err(|e| AgentError::Workflow(format!("Invalid workflow definition: {}", e)))
+    }
+
+    pub fn from_yaml(yaml: &str) -> Result<Self> {
+        serde_yml::from_str(yaml)
+            .map_err(|e| AgentError::Workflow(format!("Invalid workflow definition: {}", e)))
+    }
+}
+
+/// Registry of step constructors by type name
+#[derive(Clone)]
+pub struct StepFactory {
+    constructors: HashMap<String, StepConstructor>,
+}
+
+impl StepFactory {
+    /// Factory that knows no step types
+    pub fn empty() -> Self {
+        Self {
+            constructors: HashMap::new(),
+        }
+    }
+
+    /// Build steps of `step_type` with `constructor`, replacing any
+    /// constructor already registered for it
+    pub fn register<F>(&mut self, step_type: &str, constructor: F)
+    where
+        F: Fn(&serde_json::Value) -> Result<Box<dyn WorkflowStep>> + Send + Sync + 'static,
+    {
+        self.constructors
+            .insert(step_type.to_string(), Arc::new(constructor));
+    }
+
+    pub fn with_step<F>(mut self, step_type: &str, constructor: F) -> Self
+    where
+        F: Fn(&serde_json::Value) -> Result<Box<dyn WorkflowStep>> + Send + Sync + 'static,
+    {
+        self.register(step_type, constructor);
+        self
+    }
+
+    /// Registered type names, sorted
+    pub fn step_types(&self) -> Vec<&str> {
+        let mut types: Vec<&str> = self.constructors.keys().map(String::as_str).collect();
+        types.sort();
+        types
+    }
+
+    pub fn create(&self, step: &StepDefinition) -> Result<Box<dyn WorkflowStep>> {
+        let constructor = self.constructors.get(&step.step_type).ok_or_else(|| {
+            AgentError::Workflow(format!("Unknown step type '{}'", step.step_type))
+        })?;
+        constructor(&step.config)
+    }
+
+    /// Engine running the steps of `definition` in order
+    pub fn build(&self, definition: &WorkflowDefinition) -> Result<WorkflowEngine> {
+        let mut unknown: Vec<&str> = definition
+            .steps
+            .iter()
+            .map(|step| step.step_type.as_str())
+            .filter(|step_type| !self.constructors.contains_key(*step_type))
+            .collect();
+        if !unknown.is_empty() {
+            unknown.sort_unstable();
+            unknown.dedup();
+            return Err(AgentError::Workflow(format!(
+                "Unknown step types in workflow {}: {} (known: {})",
+                definition.name.as_deref().unwrap_or("definition"),
+                unknown.join(", "),
+                self.step_types().join(", ")
+            )));
+        }
+
+        definition
+            .steps
+            .iter()
+            .try_fold(WorkflowEngine::new(), |engine, step| {
+                Ok(engine.add_step(self.create(step)?))
+            })
+    }
+}
+
+impl Default for StepFactory {
+    /// Factory for the built-in steps that can be configured from JSON
+    fn default() -> Self {
+        Self::empty()
+            .with_step("memory_retrieval", |_| Ok(Box::new(MemoryRetrievalStep)))
+            .with_step("intent_classification", |_| {
+                Ok(Box::new(IntentClassificationStep))
+            })
+            .with_step("tool_analysis", |_| Ok(Box::new(ToolAnalysisStep)))
+            .with_step("tool_selection", |_| Ok(Box::new(ToolSelectionStep)))
+            .with_step("tool_execution", |_| Ok(Box::new(ToolExecutionStep::new())))
+            .with_step("response_generation", |_| {
+                Ok(Box::new(ResponseGenerationStep))
+            })
+            .with_step("context_compaction", |config| {
+                let config: CompactionConfig = step_config("context_compaction", config)?;
+                Ok(Box::new(
+                    ContextCompactionStep::new(config.max_messages)
+                        .with_max_memories(config.max_memories),
+                ))
+            })
+            .with_step("system_prompt", |config| {
+                let config: SystemPromptConfig = step_config("system_prompt", config)?;
+                Ok(Box::new(SystemPromptStep::new(config.prompt, config.mode)))
+            })
+            .with_step("human_approval", |config| {
+                let config: ApprovalConfig = step_config("human_approval", config)?;
+                Ok(Box::new(HumanApprovalStep::new(config.message)))
+            })
+    }
+}
+
+#[derive(Deserialize)]
+struct CompactionConfig {
+    #[serde(default = "default_max_messages")]
+    max_messages: usize,
+    #[serde(default = "default_max_memories")]
+    max_memories: usize,
+}
+
+fn default_max_messages() -> usize {
+    20
+}
+
+fn default_max_memories() -> usize {
+    5
+}
+
+#[derive(Deserialize)]
+struct SystemPromptConfig {
+    prompt: String,
+    #[serde(default = "default_prompt_mode")]
+    mode: SystemPromptMode,
+}
+
+fn default_prompt_mode() -> SystemPromptMode {
+    SystemPromptMode::Set
+}
+
+#[derive(Deserialize)]
+struct ApprovalConfig {
+    message: String,
+}
+
+/// `config` as `T`, treating a missing config as an empty object
+fn step_config<T: DeserializeOwned>(step_type: &str, config: &serde_json::Value) -> Result<T> {
+    let config = if config.is_null() {
+        serde_json::json!({})
+    } else {
+        config.clone()
+    };
+    serde_json::from_value(config).map_err(|e| {
+        AgentError::Workflow(format!("Invalid config for step '{}': {}", step_type, e))
+    })
+}
+
+impl WorkflowEngine {
+    /// Engine for a JSON [`WorkflowDefinition`] of built-in steps
+    pub fn from_definition(json: &str) -> Result<Self> {
+        StepFactory::default().build(&WorkflowDefinition::from_json(json)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::user_message;
+    use crate::mcp::{ToolContent, ToolResult};
+    use crate::workflow::WorkflowContext;
+
+    #[tokio::test]
+    async fn test_loads_and_runs_json_workflow() {
+        let engine = WorkflowEngine::from_definition(
+            r#"{
+                "name": "answer",
+                "steps": [
+                    { "type": "memory_retrieval" },
+                    { "type": "tool_analysis" },
+                    { "type": "response_generation" }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            engine.step_names(),
+            vec!["memory_retrieval", "tool_analysis", "response_generation"]
+        );
+
+        let mut context = WorkflowContext::new(10);
+        context.add_message(user_message("Show me the system info"));
+        context.available_tools = vec!["system_info".to_string()];
+        let result = engine.execute(context).await.unwrap();
+        let calls = result.pending_tool_calls.unwrap();
+        assert_eq!(calls[0].name, "system_info");
+
+        let mut context = result.context;
+        context.add_tool_result(
+            calls[0].id.clone(),
+            ToolResult {
+                id: calls[0].id.clone(),
+                content: vec![ToolContent::Text {
+                    text: "8 cores".to_string(),
+                }],
+                is_error: false,
+                truncated: false,
+            },
+        );
+        let result = engine.execute(context).await.unwrap();
+        assert!(result.completed);
+        assert!(result.response.contains("8 cores"));
+    }
+
+    #[test]
+    fn test_unknown_step_types_are_rejected() {
+        let definition = WorkflowDefinition::from_yaml(
+            "name: broken\nsteps:\n  - type: teleport\n  - type: memory_retrieval\n  - type: summon\n  - type: teleport\n",
+        )
+        .unwrap();
+
+        let err = StepFactory::default().build(&definition).err().unwrap();
+        let message = err.to_string();
+        // Each unknown type is named once
+        assert!(message.contains("types in workflow broken: summon, teleport (known"));
+        assert!(message.contains("memory_retrieval"));
+
+        // Registering a constructor makes the type known
+        let factory = StepFactory::default()
+            .with_step("teleport", |_| Ok(Box::new(ResponseGenerationStep)))
+            .with_step("summon", |_| Ok(Box::new(ResponseGenerationStep)));
+        assert_eq!(factory.build(&definition).unwrap().steps().len(), 4);
+    }
+}
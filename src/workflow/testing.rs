@@ -0,0 +1,332 @@
+//! Deterministic workflow runs for tests
+//!
+//! [`WorkflowTestHarness`] runs a [`WorkflowEngine`] without LLM, tool or
+//! memory backends. Responses are scripted per step name: a step can be forced
+//! to make a given decision without running, tool calls a step requests are
+//! answered with canned results, and memory queries with canned hits. The
+//! harness resumes the workflow after each request the way the agent does, so
+//! a test can assert on the combined decision log and the final context.
+//!
+//! Branch and loop conditions built with [`scripted_condition`] read their
+//! value from the context, and the harness sets it from
+//! [`WorkflowTestHarness::with_condition`] before the run starts.
+
+use super::{
+    ConditionFn, DecisionLogEntry, WorkflowContext, WorkflowDecision, WorkflowEngine,
+    WorkflowResult, WorkflowStep,
+};
+use crate::error::{AgentError, Result};
+use crate::mcp::ToolResult;
+use crate::memory::SearchResult;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tracing::debug;
+
+/// Prefix of the metadata keys holding scripted condition values
+pub const CONDITION_KEY_PREFIX: &str = "harness.condition.";
+
+/// Times the harness resumes a workflow for tools or memories before giving up
+pub const DEFAULT_MAX_ROUNDS: usize = 16;
+
+/// A condition whose value is scripted with
+/// [`WorkflowTestHarness::with_condition`]. It is false unless scripted true.
+pub fn scripted_condition(name: &str) -> ConditionFn {
+    let key = format!("{}{}", CONDITION_KEY_PREFIX, name);
+    Arc::new(move |context, _| context.metadata.get(&key).is_some_and(|v| v == "true"))
+}
+
+/// Runs a step as usual unless a decision has been forced for its name
+struct ScriptedStep {
+    step: Box<dyn WorkflowStep>,
+    decisions: Arc<Mutex<HashMap<String, WorkflowDecision>>>,
+}
+
+#[async_trait]
+impl WorkflowStep for ScriptedStep {
+    async fn execute(&self, context: &mut WorkflowContext) -> Result<WorkflowDecision> {
+        let forced = self
+            .decisions
+            .lock()
+            .expect("scripted decisions lock poisoned")
+            .get(self.step.name())
+            .cloned();
+        match forced {
+            Some(decision) => {
+                debug!("Forcing decision for step '{}'", self.step.name());
+                Ok(decision)
+            }
+            None => self.step.execute(context).await,
+        }
+    }
+
+    fn name(&self) -> &str {
+        self.step.name()
+    }
+}
+
+/// Runs a workflow against scripted responses instead of real backends
+pub struct WorkflowTestHarness {
+    engine: WorkflowEngine,
+    decisions: Arc<Mutex<HashMap<String, WorkflowDecision>>>,
+    tool_results: HashMap<(String, String), ToolResult>,
+    memory_hits: HashMap<String, Vec<SearchResult>>,
+    conditions: HashMap<String, bool>,
+    max_rounds: usize,
+}
+
+impl WorkflowTestHarness {
+    /// Wrap the engine's top-level steps so their decisions can be scripted
+    pub fn new(mut engine: WorkflowEngine) -> Self {
+        let decisions = Arc::new(Mutex::new(HashMap::new()));
+        engine.steps = std::mem::take(&mut engine.steps)
+            .into_iter()
+            .map(|step| {
+                Box::new(ScriptedStep {
+                    step,
+                    decisions: decisions.clone(),
+                }) as Box<dyn WorkflowStep>
+            })
+            .collect();
+
+        Self {
+            engine,
+            decisions,
+            tool_results: HashMap::new(),
+            memory_hits: HashMap::new(),
+            conditions: HashMap::new(),
+            max_rounds: DEFAULT_MAX_ROUNDS,
+        }
+    }
+
+    /// Make `step` return `decision` without running
+    pub fn with_decision(self, step: &str, decision: WorkflowDecision) -> Self {
+        self.decisions
+            .lock()
+            .expect("scripted decisions lock poisoned")
+            .insert(step.to_string(), decision);
+        self
+    }
+
+    /// Answer calls to `tool` requested by `step` with `result`
+    pub fn with_tool_result(mut self, step: &str, tool: &str, result: ToolResult) -> Self {
+        self.tool_results
+            .insert((step.to_string(), tool.to_string()), result);
+        self
+    }
+
+    /// Answer memory queries requested by `step` with `hits`. Queries from
+    /// steps without scripted hits find nothing.
+    pub fn with_memory_hits(mut self, step: &str, hits: Vec<SearchResult>) -> Self {
+        self.memory_hits.insert(step.to_string(), hits);
+        self
+    }
+
+    /// Set the value of the [`scripted_condition`] called `name`
+    pub fn with_condition(mut self, name: &str, value: bool) -> Self {
+        self.conditions.insert(name.to_string(), value);
+        self
+    }
+
+    /// Fail a run that is still requesting tools or memories after this many
+    /// resumptions
+    pub fn with_max_rounds(mut self, max_rounds: usize) -> Self {
+        self.max_rounds = max_rounds;
+        self
+    }
+
+    /// Run the workflow to completion or suspension, answering its tool and
+    /// memory requests from the script. The result's decision log covers
+    /// every round.
+    pub async fn run(&self, mut context: WorkflowContext) -> Result<WorkflowResult> {
+        for (name, value) in &self.conditions {
+            context.metadata.insert(
+                format!("{}{}", CONDITION_KEY_PREFIX, name),
+                value.to_string(),
+            );
+        }
+
+        let mut decision_log = Vec::new();
+        let mut result = self.engine.execute(context).await?;
+        for _ in 0..self.max_rounds {
+            decision_log.append(&mut result.decision_log);
+            if !self.answer_pending(&mut result, &decision_log)? {
+                result.decision_log = decision_log;
+                return Ok(result);
+            }
+            result = self.engine.execute(result.context).await?;
+        }
+
+        Err(AgentError::Workflow(format!(
+            "Workflow still had pending actions after {} rounds",
+            self.max_rounds
+        )))
+    }
+
+    /// Fill in the result's pending tool calls or memory query, returning
+    /// whether there was anything to answer
+    fn answer_pending(
+        &self,
+        result: &mut WorkflowResult,
+        decision_log: &[DecisionLogEntry],
+    ) -> Result<bool> {
+        let step = decision_log
+            .last()
+            .map(|entry| entry.step_name.clone())
+            .unwrap_or_default();
+
+        if let Some(tool_calls) = result.pending_tool_calls.take() {
+            for call in tool_calls {
+                let mut tool_result = self
+                    .tool_results
+                    .get(&(step.clone(), call.name.clone()))
+                    .cloned()
+                    .ok_or_else(|| {
+                        AgentError::Workflow(format!(
+                            "No scripted result for tool '{}' called by step '{}'",
+                            call.name, step
+                        ))
+                    })?;
+                tool_result.id = call.id.clone();
+                result.context.add_tool_result(call.id, tool_result);
+            }
+            return Ok(true);
+        }
+
+        if let Some(query) = result.pending_memory_query.take() {
+            debug!("Answering memory query '{}' for step '{}'", query, step);
+            result.context.memories = self.memory_hits.get(&step).cloned().unwrap_or_default();
+            result.context.mark_memories_retrieved();
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::{ToolCall, ToolContent};
+    use crate::workflow::WorkflowBuilder;
+
+    /// Looks something up, then answers with what the lookup returned
+    struct LookupStep;
+
+    #[async_trait]
+    impl WorkflowStep for LookupStep {
+        async fn execute(&self, context: &mut WorkflowContext) -> Result<WorkflowDecision> {
+            match context.tool_results.get("lookup-1") {
+                Some(result) => match &result.content[0] {
+                    ToolContent::Text { text } => Ok(WorkflowDecision::Complete(text.clone())),
+                    other => panic!("unexpected content {:?}", other),
+                },
+                None => Ok(WorkflowDecision::ExecuteTools(vec![ToolCall {
+                    id: "lookup-1".to_string(),
+                    name: "lookup".to_string(),
+                    arguments: serde_json::json!({"query": "weather"}),
+                }])),
+            }
+        }
+
+        fn name(&self) -> &str {
+            "lookup"
+        }
+    }
+
+    struct RespondStep(&'static str);
+
+    #[async_trait]
+    impl WorkflowStep for RespondStep {
+        async fn execute(&self, _context: &mut WorkflowContext) -> Result<WorkflowDecision> {
+            Ok(WorkflowDecision::Complete(self.0.to_string()))
+        }
+
+        fn name(&self) -> &str {
+            self.0
+        }
+    }
+
+    fn harness() -> WorkflowTestHarness {
+        let engine = WorkflowBuilder::new("branching")
+            .branch(scripted_condition("use_tools"), Box::new(LookupStep), None)
+            .then(Box::new(RespondStep("fallback")))
+            .build();
+        WorkflowTestHarness::new(engine).with_tool_result(
+            "branch_execution",
+            "lookup",
+            ToolResult {
+                id: String::new(),
+                content: vec![ToolContent::Text {
+                    text: "sunny".to_string(),
+                }],
+                is_error: false,
+                truncated: false,
+            },
+        )
+    }
+
+    fn log(result: &WorkflowResult) -> Vec<(&str, &str)> {
+        result
+            .decision_log
+            .iter()
+            .map(|entry| (entry.step_name.as_str(), entry.decision.as_str()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_scripted_condition_drives_both_branches() {
+        let result = harness()
+            .with_condition("use_tools", true)
+            .run(WorkflowContext::new(10))
+            .await
+            .unwrap();
+        assert!(result.completed);
+        assert_eq!(result.response, "sunny");
+        assert_eq!(
+            log(&result),
+            vec![
+                ("branch_execution", "execute 1 tool calls"),
+                ("branch_execution", "complete (5 chars)"),
+            ]
+        );
+        assert_eq!(result.context.tool_results["lookup-1"].id, "lookup-1");
+
+        let result = harness()
+            .with_condition("use_tools", false)
+            .run(WorkflowContext::new(10))
+            .await
+            .unwrap();
+        assert_eq!(result.response, "fallback");
+        assert_eq!(
+            log(&result),
+            vec![
+                ("branch_execution", "continue"),
+                ("fallback", "complete (8 chars)"),
+            ]
+        );
+        assert!(result.context.tool_results.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_forced_decision_and_missing_tool_result() {
+        let result = harness()
+            .with_condition("use_tools", false)
+            .with_decision("fallback", WorkflowDecision::Complete("forced".to_string()))
+            .run(WorkflowContext::new(10))
+            .await
+            .unwrap();
+        assert_eq!(result.response, "forced");
+
+        let engine = WorkflowBuilder::new("unscripted")
+            .then(Box::new(LookupStep))
+            .build();
+        let err = WorkflowTestHarness::new(engine)
+            .run(WorkflowContext::new(10))
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("No scripted result for tool 'lookup'"));
+    }
+}
@@ -0,0 +1,225 @@
+//! Summarizing long tool results
+//!
+//! A tool such as an HTTP fetch can return far more text than the rest of the
+//! conversation, crowding it out of the model's context. [`ToolResultSummarizer`]
+//! replaces results longer than a per-tool threshold with an LLM summary before
+//! they are added to the [`WorkflowContext`], and keeps the raw result under
+//! [`RAW_TOOL_RESULTS_KEY`] in the context's data so it can still be audited.
+
+use super::WorkflowContext;
+use crate::config::ToolSummaryConfig;
+use crate::error::Result;
+use crate::llm::{system_message, user_message, LlmClient};
+use crate::mcp::{ToolCall, ToolContent, ToolResult};
+use crate::prompts::{self, PromptLibrary};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+/// Data key of the raw results of summarized tool calls, keyed by call id
+pub const RAW_TOOL_RESULTS_KEY: &str = "raw_tool_results";
+
+/// Summarizes oversized tool results before they enter the workflow context
+pub struct ToolResultSummarizer {
+    llm: Arc<dyn LlmClient>,
+    config: ToolSummaryConfig,
+    prompts: Arc<PromptLibrary>,
+}
+
+impl ToolResultSummarizer {
+    pub fn new(llm: Arc<dyn LlmClient>, config: ToolSummaryConfig) -> Self {
+        Self {
+            llm,
+            config,
+            prompts: Arc::new(PromptLibrary::builtin()),
+        }
+    }
+
+    /// Take the summarization prompt from `prompts`
+    pub fn with_prompt_library(mut self, prompts: Arc<PromptLibrary>) -> Self {
+        self.prompts = prompts;
+        self
+    }
+
+    /// Add the result of `tool_call` to `context`, summarized if its text is
+    /// over the tool's threshold. Error results are added as they are, and so
+    /// is the raw result if summarization fails.
+    pub async fn add_result(
+        &self,
+        context: &mut WorkflowContext,
+        tool_call: &ToolCall,
+        result: ToolResult,
+    ) -> Result<()> {
+        let Some(threshold) = self.config.threshold_for(&tool_call.name) else {
+            context.add_tool_result(tool_call.id.clone(), result);
+            return Ok(());
+        };
+        let text = result_text(&result);
+        let length = text.chars().count();
+        if result.is_error || length <= threshold {
+            context.add_tool_result(tool_call.id.clone(), result);
+            return Ok(());
+        }
+
+        let Some(summary) = self.summarize(tool_call, &text).await else {
+            context.add_tool_result(tool_call.id.clone(), result);
+            return Ok(());
+        };
+        debug!(
+            "Summarized {} result from {} to {} characters",
+            tool_call.name,
+            length,
+            summary.chars().count()
+        );
+
+        let mut raw: HashMap<String, ToolResult> =
+            context.get(RAW_TOOL_RESULTS_KEY)?.unwrap_or_default();
+        let summarized = ToolResult {
+            id: result.id.clone(),
+            content: vec![ToolContent::Text { text: summary }],
+            is_error: false,
+            truncated: false,
+        };
+        raw.insert(tool_call.id.clone(), result);
+        context.set(RAW_TOOL_RESULTS_KEY, raw)?;
+        context.add_tool_result(tool_call.id.clone(), summarized);
+        Ok(())
+    }
+
+    async fn summarize(&self, tool_call: &ToolCall, text: &str) -> Option<String> {
+        let prompt = format!(
+            "Tool: {}\nArguments: {}\n\nOutput:\n{}",
+            tool_call.name, tool_call.arguments, text
+        );
+        let system_prompt = match self.prompts.render(prompts::TOOL_SUMMARY, &[]) {
+            Ok(system_prompt) => system_prompt,
+            Err(e) => {
+                warn!(
+                    "Failed to render tool summary prompt; keeping result whole: {}",
+                    e
+                );
+                return None;
+            }
+        };
+        let messages = [system_message(system_prompt), user_message(prompt)];
+        match self.llm.generate(&messages).await {
+            Ok(response) if !response.text.trim().is_empty() => {
+                Some(response.text.trim().to_string())
+            }
+            Ok(_) => {
+                warn!(
+                    "Empty summary for {} result; keeping it whole",
+                    tool_call.name
+                );
+                None
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to summarize {} result; keeping it whole: {}",
+                    tool_call.name, e
+                );
+                None
+            }
+        }
+    }
+}
+
+/// Text content of a result, one part per line
+fn result_text(result: &ToolResult) -> String {
+    result
+        .content
+        .iter()
+        .filter_map(|content| match content {
+            ToolContent::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ToolSummaryOverride;
+    use crate::llm::mock::MockLlm;
+
+    /// Answers every request with a fixed summary
+    fn summary_llm() -> Arc<MockLlm> {
+        Arc::new(MockLlm::new().with_reply("Status 200; 3 open orders, oldest from March."))
+    }
+
+    fn prompts(llm: &MockLlm) -> Vec<String> {
+        llm.requests()
+            .iter()
+            .map(|messages| messages.last().unwrap().content.clone())
+            .collect()
+    }
+
+    fn call(name: &str) -> ToolCall {
+        ToolCall {
+            id: format!("{}-1", name),
+            name: name.to_string(),
+            arguments: serde_json::json!({}),
+        }
+    }
+
+    fn text_result(id: &str, text: String) -> ToolResult {
+        ToolResult {
+            id: id.to_string(),
+            content: vec![ToolContent::Text { text }],
+            is_error: false,
+            truncated: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_oversized_result_is_summarized_and_raw_kept() {
+        let llm = summary_llm();
+        let config = ToolSummaryConfig {
+            threshold_chars: 100,
+            tools: HashMap::from([(
+                "read_file".to_string(),
+                ToolSummaryOverride {
+                    enabled: false,
+                    threshold_chars: None,
+                },
+            )]),
+        };
+        let summarizer = ToolResultSummarizer::new(llm.clone(), config);
+        let mut context = WorkflowContext::new(5);
+        let body = "<tr><td>order</td></tr>".repeat(50);
+
+        let http = call("http_get");
+        summarizer
+            .add_result(&mut context, &http, text_result(&http.id, body.clone()))
+            .await
+            .unwrap();
+
+        let added = &context.tool_results[&http.id];
+        assert_eq!(
+            result_text(added),
+            "Status 200; 3 open orders, oldest from March."
+        );
+        let raw: HashMap<String, ToolResult> = context.get(RAW_TOOL_RESULTS_KEY).unwrap().unwrap();
+        assert_eq!(result_text(&raw[&http.id]), body);
+        assert!(prompts(&llm)[0].contains("Tool: http_get"));
+
+        // Short results and disabled tools go in unchanged
+        let short = call("datetime_info");
+        summarizer
+            .add_result(&mut context, &short, text_result(&short.id, "noon".into()))
+            .await
+            .unwrap();
+        let file = call("read_file");
+        summarizer
+            .add_result(&mut context, &file, text_result(&file.id, body.clone()))
+            .await
+            .unwrap();
+
+        assert_eq!(result_text(&context.tool_results[&short.id]), "noon");
+        assert_eq!(result_text(&context.tool_results[&file.id]), body);
+        assert_eq!(prompts(&llm).len(), 1);
+        let raw: HashMap<String, ToolResult> = context.get(RAW_TOOL_RESULTS_KEY).unwrap().unwrap();
+        assert_eq!(raw.len(), 1);
+    }
+}
@@ -0,0 +1,74 @@
+//! Per-step schema enforcement
+//!
+//! [`SchemaValidatedStep`] wraps another step and checks the context data
+//! against an input schema before the step runs and an output schema after it
+//! continues or completes, failing with
+//! [`AgentError::SchemaValidation`](crate::error::AgentError::SchemaValidation)
+//! on a mismatch. Workflow-wide schemas are set on the engine instead; see
+//! [`WorkflowEngine::with_input_schema`](super::WorkflowEngine::with_input_schema).
+
+use super::{StepSchema, WorkflowContext, WorkflowDecision, WorkflowStep};
+use crate::error::Result;
+use async_trait::async_trait;
+
+/// Step that validates the context data around another step
+pub struct SchemaValidatedStep {
+    step: Box<dyn WorkflowStep>,
+    input_schema: Option<StepSchema>,
+    output_schema: Option<StepSchema>,
+}
+
+impl SchemaValidatedStep {
+    pub fn new(step: Box<dyn WorkflowStep>) -> Self {
+        Self {
+            step,
+            input_schema: None,
+            output_schema: None,
+        }
+    }
+
+    /// Require the context data to match `schema` before the step runs
+    pub fn with_input_schema(mut self, schema: StepSchema) -> Self {
+        self.input_schema = Some(schema);
+        self
+    }
+
+    /// Require the context data to match `schema` once the step continues or
+    /// completes
+    pub fn with_output_schema(mut self, schema: StepSchema) -> Self {
+        self.output_schema = Some(schema);
+        self
+    }
+}
+
+#[async_trait]
+impl WorkflowStep for SchemaValidatedStep {
+    async fn execute(&self, context: &mut WorkflowContext) -> Result<WorkflowDecision> {
+        if let Some(schema) = &self.input_schema {
+            schema.check(
+                &format!("input of step '{}'", self.step.name()),
+                &context.data_value(),
+            )?;
+        }
+
+        let decision = self.step.execute(context).await?;
+
+        let finished = matches!(
+            decision,
+            WorkflowDecision::Continue
+                | WorkflowDecision::Complete(_)
+                | WorkflowDecision::GenerateWithLlm
+        );
+        if let (Some(schema), true) = (&self.output_schema, finished) {
+            schema.check(
+                &format!("output of step '{}'", self.step.name()),
+                &context.data_value(),
+            )?;
+        }
+        Ok(decision)
+    }
+
+    fn name(&self) -> &str {
+        self.step.name()
+    }
+}
@@ -0,0 +1,122 @@
+//! Webhook notifications for suspended workflows
+//!
+//! A workflow waiting for human input just sits in storage until someone
+//! resumes it. [`SuspensionWebhook`] lets the engine POST a
+//! [`SuspensionNotice`] to an external system (chat bot, ticketing, pager) so a
+//! person is alerted. Delivery is best effort: failed attempts are retried and
+//! logged, but never fail the suspension itself.
+
+use super::SuspendReason;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Endpoint notified when a workflow suspends waiting for input
+#[derive(Debug, Clone)]
+pub struct SuspensionWebhook {
+    /// URL receiving a JSON POST for each suspension
+    pub url: String,
+
+    /// Attempts after the first one before giving up
+    pub max_retries: u32,
+
+    /// Delay before the first retry, doubled after each failed attempt
+    pub retry_delay: Duration,
+
+    /// Timeout for each POST
+    pub timeout: Duration,
+}
+
+impl SuspensionWebhook {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            max_retries: 3,
+            retry_delay: Duration::from_millis(500),
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    pub fn with_retries(mut self, max_retries: u32, retry_delay: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// Deliver a notice, retrying with exponential backoff. Returns whether it was accepted.
+    pub async fn notify(&self, notice: &SuspensionNotice) -> bool {
+        let client = match reqwest::Client::builder().timeout(self.timeout).build() {
+            Ok(client) => client,
+            Err(e) => {
+                warn!("Failed to create webhook client: {}", e);
+                return false;
+            }
+        };
+
+        let mut delay = self.retry_delay;
+        for attempt in 0..=self.max_retries {
+            if attempt > 0 {
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+
+            match client.post(&self.url).json(notice).send().await {
+                Ok(response) if response.status().is_success() => {
+                    info!(
+                        "Notified {} of suspended workflow {}",
+                        self.url, notice.snapshot_id
+                    );
+                    return true;
+                }
+                Ok(response) => warn!(
+                    "Suspension webhook returned {} (attempt {}/{})",
+                    response.status(),
+                    attempt + 1,
+                    self.max_retries + 1
+                ),
+                Err(e) => warn!(
+                    "Suspension webhook failed: {} (attempt {}/{})",
+                    e,
+                    attempt + 1,
+                    self.max_retries + 1
+                ),
+            }
+        }
+
+        warn!(
+            "Giving up notifying {} of suspended workflow {}",
+            self.url, notice.snapshot_id
+        );
+        false
+    }
+}
+
+/// Payload POSTed to the suspension webhook
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuspensionNotice {
+    pub snapshot_id: Uuid,
+    pub suspended_at: DateTime<Utc>,
+    pub reason: SuspendReason,
+
+    /// What the workflow is asking the human for
+    pub prompt: String,
+}
+
+impl SuspensionNotice {
+    /// Build a notice for suspensions that need a human; other reasons return `None`
+    pub fn for_suspension(snapshot_id: Uuid, reason: &SuspendReason) -> Option<Self> {
+        let SuspendReason::WaitingForInput(prompt) = reason else {
+            debug!("No suspension webhook for reason {:?}", reason);
+            return None;
+        };
+
+        Some(Self {
+            snapshot_id,
+            suspended_at: Utc::now(),
+            reason: reason.clone(),
+            prompt: prompt.clone(),
+        })
+    }
+}
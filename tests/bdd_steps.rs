@@ -211,8 +211,8 @@ async fn set_invalid_ollama_url(world: &mut AgentWorld) {
             Ok(_) => {
                 world.error = Some("Configuration should have failed validation".to_string());
             }
-            Err(e) => {
-                world.error = Some(e.to_string());
+            Err(errors) => {
+                world.error = Some(AgentError::from(errors).to_string());
             }
         }
     }
@@ -56,13 +56,14 @@ async fn test_builtin_tools_includes_new_tools() {
     let tools = BuiltinTools::new();
     let tool_list = tools.list_tools();
 
-    // Should contain all three built-in tools
+    // Should contain all four built-in tools
     assert!(tool_list.contains(&"system_info".to_string()));
     assert!(tool_list.contains(&"datetime_info".to_string()));
+    assert!(tool_list.contains(&"datetime".to_string()));
     assert!(tool_list.contains(&"location_info".to_string()));
 
-    // Should be exactly 3 tools
-    assert_eq!(tool_list.len(), 3);
+    // Should be exactly 4 tools
+    assert_eq!(tool_list.len(), 4);
 }
 
 #[tokio::test]
@@ -18,6 +18,7 @@ fn test_llm_config() -> LlmConfig {
         stream: false,
         task_models: HashMap::new(),
         cache: the_agency::cache::LlmCacheConfig::default(),
+        ..Default::default()
     }
 }
 
@@ -33,6 +34,8 @@ fn test_message_serialization() {
     let message = Message {
         role: Role::User,
         content: "Hello, world!".to_string(),
+        tool_calls: Vec::new(),
+        tool_call_id: None,
     };
 
     let json = serde_json::to_string(&message).unwrap();
@@ -107,10 +110,14 @@ async fn test_ollama_client_generation_mock() {
         Message {
             role: Role::System,
             content: "You are a helpful assistant.".to_string(),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
         },
         Message {
             role: Role::User,
             content: "Hello!".to_string(),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
         },
     ];
 
@@ -128,8 +135,11 @@ fn test_generation_response_structure() {
     let response = GenerationResponse {
         text: "Test response".to_string(),
         tokens_used: Some(42),
+        usage: None,
         model: "llama3.2".to_string(),
         finish_reason: Some("stop".to_string()),
+        system_fingerprint: None,
+        reasoning: None,
     };
 
     assert_eq!(response.text, "Test response");
@@ -192,6 +192,7 @@ async fn setup_evaluation_data(world: &mut StorageWorld) {
             created_at: SystemTime::now(),
             version: "1.0".to_string(),
             metadata: HashMap::new(),
+            items: Vec::new(),
         };
 
         storage.create_eval_dataset(&dataset).await.unwrap();
@@ -64,6 +64,12 @@ async fn test_memory_store() {
         similarity_threshold: 0.7,
         persistent: true,
         store_type: "sqlite".to_string(),
+        dedup: None,
+        vector_index: None,
+        normalize_embeddings: false,
+        ranking: None,
+        shared_writes: false,
+        max_entries: None,
     };
 
     let mut store = memory::SqliteMemoryStore::new(config);
@@ -222,6 +228,7 @@ async fn test_workflow_context() {
             text: "Result".to_string(),
         }],
         is_error: false,
+        truncated: false,
     };
 
     context.add_tool_result("call-1".to_string(), tool_result);
@@ -358,6 +365,12 @@ async fn test_concurrent_operations() {
         similarity_threshold: 0.7,
         persistent: true,
         store_type: "sqlite".to_string(),
+        dedup: None,
+        vector_index: None,
+        normalize_embeddings: false,
+        ranking: None,
+        shared_writes: false,
+        max_entries: None,
     };
 
     let mut store = memory::SqliteMemoryStore::new(config);